@@ -0,0 +1,541 @@
+//! Visitor accounts, separate from the admin login. Collectors register
+//! with a username and password and star minerals to find them again on
+//! `/account` or through `GET /api/me/favorites`. Accounts and their
+//! favorites live in `data/accounts.json` with argon2 password hashes;
+//! sessions are kept in memory like admin sessions, so a restart signs
+//! visitors out.
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
+};
+
+use anyhow::{anyhow, Context, Result};
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::HeaderMap,
+    response::{IntoResponse, Redirect, Response},
+    Form, Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{
+    append_set_cookie,
+    auth::{hash_password, verify_password},
+    catalog_for_language, cookie_value, crypto,
+    csrf::{self, CsrfForm},
+    get_mineral,
+    i18n::{ui_text, Language},
+    lang_path, require_visible, resolve_language,
+    web::{AccountTemplate, TemplateResponse},
+    AppError, AppState,
+};
+
+pub const SESSION_COOKIE: &str = "visitor_session";
+const SESSION_MAX_AGE_SECS: u64 = 30 * 24 * 60 * 60;
+pub const MIN_PASSWORD_LEN: usize = 10;
+const MAX_PASSWORD_LEN: usize = 256;
+/// Starring beyond this many favorites is refused.
+const MAX_FAVORITES: usize = 500;
+
+#[derive(Debug, Clone)]
+pub struct AccountStore {
+    path: PathBuf,
+    accounts: Arc<RwLock<Vec<Account>>>,
+    /// Session token to its account and CSRF token.
+    sessions: Arc<Mutex<HashMap<String, VisitorSession>>>,
+    /// Held across the file write so two saves finishing together cannot
+    /// leave the older snapshot on disk.
+    writer: Arc<tokio::sync::Mutex<()>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Account {
+    id: String,
+    username: String,
+    password_hash: String,
+    created_utc: String,
+    /// Mineral slugs, most recently starred first.
+    #[serde(default)]
+    favorites: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VisitorSession {
+    pub account_id: String,
+    pub username: String,
+    pub csrf_token: String,
+}
+
+/// Star toggle on the mineral page of a signed-in visitor.
+#[derive(Debug, Clone)]
+pub struct FavoriteButton {
+    pub starred: bool,
+    pub csrf_token: String,
+}
+
+impl AccountStore {
+    pub async fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let accounts = match fs::read_to_string(&path).await {
+            Ok(raw) => serde_json::from_str(&raw)
+                .with_context(|| format!("failed to parse {}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(anyhow!("failed to read {}: {err}", path.display())),
+        };
+
+        Ok(Self {
+            path,
+            accounts: Arc::new(RwLock::new(accounts)),
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            writer: Arc::new(tokio::sync::Mutex::new(())),
+        })
+    }
+
+    /// Adds an account for an already normalized `username`; `None` when
+    /// the name is taken.
+    pub async fn register(&self, username: &str, password_hash: String) -> Result<Option<String>> {
+        let _writer = self.writer.lock().await;
+        let id = crypto::random_hex(8)?;
+        let snapshot = {
+            let mut accounts = self
+                .accounts
+                .write()
+                .map_err(|_| anyhow!("account store lock poisoned"))?;
+            if accounts.iter().any(|account| account.username == username) {
+                return Ok(None);
+            }
+            accounts.push(Account {
+                id: id.clone(),
+                username: username.to_string(),
+                password_hash,
+                created_utc: Utc::now().to_rfc3339(),
+                favorites: Vec::new(),
+            });
+            accounts.clone()
+        };
+        self.persist(&snapshot).await?;
+        Ok(Some(id))
+    }
+
+    /// Account id and password hash for `username`.
+    pub fn credential(&self, username: &str) -> Option<(String, String)> {
+        self.accounts
+            .read()
+            .ok()?
+            .iter()
+            .find(|account| account.username == username)
+            .map(|account| (account.id.clone(), account.password_hash.clone()))
+    }
+
+    pub fn favorites(&self, account_id: &str) -> Vec<String> {
+        self.accounts
+            .read()
+            .ok()
+            .and_then(|accounts| {
+                accounts
+                    .iter()
+                    .find(|account| account.id == account_id)
+                    .map(|account| account.favorites.clone())
+            })
+            .unwrap_or_default()
+    }
+
+    pub async fn set_favorite(&self, account_id: &str, slug: &str, starred: bool) -> Result<()> {
+        let _writer = self.writer.lock().await;
+        let snapshot = {
+            let mut accounts = self
+                .accounts
+                .write()
+                .map_err(|_| anyhow!("account store lock poisoned"))?;
+            let account = accounts
+                .iter_mut()
+                .find(|account| account.id == account_id)
+                .ok_or_else(|| anyhow!("account '{account_id}' not found"))?;
+            let present = account.favorites.iter().any(|favorite| favorite == slug);
+            match (starred, present) {
+                (true, false) => {
+                    if account.favorites.len() >= MAX_FAVORITES {
+                        return Err(anyhow!("favorites are limited to {MAX_FAVORITES}"));
+                    }
+                    account.favorites.insert(0, slug.to_string());
+                }
+                (false, true) => account.favorites.retain(|favorite| favorite != slug),
+                _ => return Ok(()),
+            }
+            accounts.clone()
+        };
+        self.persist(&snapshot).await
+    }
+
+    /// Opens a session and returns its cookie token.
+    pub fn start_session(&self, account_id: &str, username: &str) -> Result<String> {
+        let token = crypto::random_hex(24)?;
+        let session = VisitorSession {
+            account_id: account_id.to_string(),
+            username: username.to_string(),
+            csrf_token: crypto::random_hex(24)?,
+        };
+        self.sessions
+            .lock()
+            .map_err(|_| anyhow!("visitor session store lock poisoned"))?
+            .insert(token.clone(), session);
+        Ok(token)
+    }
+
+    pub fn session(&self, token: &str) -> Option<VisitorSession> {
+        self.sessions.lock().ok()?.get(token).cloned()
+    }
+
+    pub fn end_session(&self, token: &str) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.remove(token);
+        }
+    }
+
+    async fn persist(&self, accounts: &[Account]) -> Result<()> {
+        let json =
+            serde_json::to_string_pretty(accounts).context("failed to serialize accounts")?;
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, json)
+            .await
+            .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &self.path)
+            .await
+            .with_context(|| format!("failed to replace {}", self.path.display()))
+    }
+}
+
+/// Lowercases and checks a username: 3-32 letters, digits, `.`, `_`, `-`.
+pub fn normalize_username(raw: &str) -> Option<String> {
+    let username = raw.trim().to_lowercase();
+    let valid = (3..=32).contains(&username.len())
+        && username
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-'));
+    valid.then_some(username)
+}
+
+/// The signed-in visitor, if the request carries a live session cookie.
+pub fn visitor_session(state: &AppState, headers: &HeaderMap) -> Option<VisitorSession> {
+    state
+        .accounts
+        .session(&cookie_value(headers, SESSION_COOKIE)?)
+}
+
+pub fn favorite_button(
+    state: &AppState,
+    headers: &HeaderMap,
+    slug: &str,
+) -> Option<FavoriteButton> {
+    let session = visitor_session(state, headers)?;
+    Some(FavoriteButton {
+        starred: state
+            .accounts
+            .favorites(&session.account_id)
+            .iter()
+            .any(|favorite| favorite == slug),
+        csrf_token: session.csrf_token,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountForm {
+    #[serde(default)]
+    username: String,
+    #[serde(default)]
+    password: String,
+    #[serde(default)]
+    csrf_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FavoriteForm {
+    #[serde(default)]
+    csrf_token: String,
+    /// `add` or `remove`.
+    action: String,
+    /// `account` to return to `/account` instead of the mineral page.
+    #[serde(default)]
+    back: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FavoritesResponse {
+    username: String,
+    lang: &'static str,
+    favorites: Vec<FavoriteMineral>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FavoriteMineral {
+    slug: String,
+    name: String,
+    family: String,
+    page_url: String,
+    thumbnail_url: Option<String>,
+}
+
+pub async fn account_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let language = resolve_language(&state, &headers);
+    match visitor_session(&state, &headers) {
+        Some(session) => Ok(favorites_page(&state, language, session)
+            .await
+            .into_response()),
+        None => login_page(language, None),
+    }
+}
+
+pub async fn register(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(form): Form<AccountForm>,
+) -> Result<Response, AppError> {
+    let language = resolve_language(&state, &headers);
+    csrf::verify_account_login(&headers, &form.csrf_token)?;
+    let txt = ui_text(language);
+    let username = normalize_username(&form.username);
+    let password_len = form.password.chars().count();
+    let Some(username) =
+        username.filter(|_| (MIN_PASSWORD_LEN..=MAX_PASSWORD_LEN).contains(&password_len))
+    else {
+        return login_page(language, Some(txt.account_register_hint));
+    };
+
+    let password = form.password;
+    let password_hash = tokio::task::spawn_blocking(move || hash_password(&password))
+        .await
+        .context("password hashing task failed")??;
+    let Some(account_id) = state.accounts.register(&username, password_hash).await? else {
+        return login_page(language, Some(txt.account_username_taken));
+    };
+    signed_in(&state, &headers, &account_id, &username)
+}
+
+pub async fn login(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(form): Form<AccountForm>,
+) -> Result<Response, AppError> {
+    let language = resolve_language(&state, &headers);
+    csrf::verify_account_login(&headers, &form.csrf_token)?;
+    let invalid = || login_page(language, Some(ui_text(language).account_invalid_login));
+    let Some(username) = normalize_username(&form.username) else {
+        return invalid();
+    };
+    let Some((account_id, password_hash)) = state.accounts.credential(&username) else {
+        return invalid();
+    };
+
+    let password = form.password;
+    let verified = tokio::task::spawn_blocking(move || verify_password(&password, &password_hash))
+        .await
+        .context("password verification task failed")?;
+    if !verified {
+        return invalid();
+    }
+    signed_in(&state, &headers, &account_id, &username)
+}
+
+pub async fn logout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    form: Option<Form<CsrfForm>>,
+) -> Result<Response, AppError> {
+    if let Some(session) = visitor_session(&state, &headers) {
+        csrf::verify_visitor(&session.csrf_token, &headers, &csrf::form_token(form))?;
+        if let Some(token) = cookie_value(&headers, SESSION_COOKIE) {
+            state.accounts.end_session(&token);
+        }
+    }
+
+    let mut response = Redirect::to(&lang_path::prefixed(&headers, "/account")).into_response();
+    append_set_cookie(&mut response, &state.session_cookie(SESSION_COOKIE, "", 0))?;
+    Ok(response)
+}
+
+pub async fn toggle_favorite(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+    Form(form): Form<FavoriteForm>,
+) -> Result<Redirect, AppError> {
+    let session = require_visitor(&state, &headers)?;
+    csrf::verify_visitor(&session.csrf_token, &headers, &form.csrf_token)?;
+    let starred = match form.action.as_str() {
+        "add" => true,
+        "remove" => false,
+        other => {
+            return Err(AppError::BadRequest(format!(
+                "unknown favorite action '{other}'; use add or remove"
+            )))
+        }
+    };
+    if starred {
+        // Only published minerals can be starred; removal also cleans up
+        // slugs of minerals deleted since.
+        let mineral = get_mineral(&state, state.default_language, &slug).await?;
+        require_visible(&state, &headers, &slug, &mineral)?;
+    }
+    state
+        .accounts
+        .set_favorite(&session.account_id, &slug, starred)
+        .await
+        .map_err(|err| AppError::BadRequest(err.to_string()))?;
+
+    let target = if form.back == "account" {
+        "/account".to_string()
+    } else {
+        format!("/minerals/{slug}")
+    };
+    Ok(Redirect::to(&lang_path::prefixed(&headers, &target)))
+}
+
+/// `GET /api/me/favorites`: the signed-in visitor's favorites in the
+/// request's language, newest first.
+pub async fn api_favorites(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<FavoritesResponse>, AppError> {
+    let session = require_visitor(&state, &headers)?;
+    let language = resolve_language(&state, &headers);
+    let site = |path: &str| state.pdf_generator.public_url(path);
+    let catalog = catalog_for_language(&state, language).await?.public_view();
+    let favorites = state
+        .accounts
+        .favorites(&session.account_id)
+        .iter()
+        .filter_map(|slug| catalog.resolve(slug).cloned())
+        .map(|mineral| FavoriteMineral {
+            page_url: state.pdf_generator.mineral_url(&mineral.slug, language),
+            thumbnail_url: mineral.thumbnail_src().map(site),
+            slug: mineral.slug,
+            name: mineral.common_name,
+            family: mineral.mineral_family,
+        })
+        .collect();
+    Ok(Json(FavoritesResponse {
+        username: session.username,
+        lang: language.code(),
+        favorites,
+    }))
+}
+
+fn require_visitor(state: &AppState, headers: &HeaderMap) -> Result<VisitorSession, AppError> {
+    visitor_session(state, headers).ok_or_else(|| {
+        AppError::Unauthorized("Visitor session required. Log in at /account.".to_string())
+    })
+}
+
+fn signed_in(
+    state: &AppState,
+    headers: &HeaderMap,
+    account_id: &str,
+    username: &str,
+) -> Result<Response, AppError> {
+    if let Some(previous) = cookie_value(headers, SESSION_COOKIE) {
+        state.accounts.end_session(&previous);
+    }
+    let token = state.accounts.start_session(account_id, username)?;
+    let mut response = Redirect::to(&lang_path::prefixed(headers, "/account")).into_response();
+    append_set_cookie(
+        &mut response,
+        &state.session_cookie(SESSION_COOKIE, &token, SESSION_MAX_AGE_SECS),
+    )?;
+    Ok(response)
+}
+
+async fn favorites_page(
+    state: &AppState,
+    language: Language,
+    session: VisitorSession,
+) -> TemplateResponse<AccountTemplate> {
+    let catalog = catalog_for_language(state, language)
+        .await
+        .ok()
+        .map(|catalog| catalog.public_view());
+    let favorites = state
+        .accounts
+        .favorites(&session.account_id)
+        .iter()
+        .filter_map(|slug| catalog.as_ref()?.resolve(slug).cloned())
+        .collect();
+    TemplateResponse(AccountTemplate {
+        lang_code: language.code().to_string(),
+        lang_dir: language.dir().to_string(),
+        txt: ui_text(language),
+        username: Some(session.username),
+        favorites,
+        csrf_token: session.csrf_token,
+        error_message: None,
+    })
+}
+
+/// Login and registration forms, with a fresh double-submit CSRF cookie.
+fn login_page(language: Language, error_message: Option<&str>) -> Result<Response, AppError> {
+    let csrf_token = crypto::random_hex(24)?;
+    let mut response = TemplateResponse(AccountTemplate {
+        lang_code: language.code().to_string(),
+        lang_dir: language.dir().to_string(),
+        txt: ui_text(language),
+        username: None,
+        favorites: Vec::new(),
+        csrf_token: csrf_token.clone(),
+        error_message: error_message.map(str::to_string),
+    })
+    .into_response();
+    csrf::attach_account_login_cookie(&mut response, &csrf_token)?;
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_username, AccountStore};
+    use crate::test_support::TempDir;
+
+    #[tokio::test]
+    async fn registers_once_and_keeps_favorites_on_disk() {
+        assert_eq!(
+            normalize_username(" Quartz_Fan "),
+            Some("quartz_fan".to_string())
+        );
+        assert_eq!(normalize_username("ab"), None);
+        assert_eq!(normalize_username("rock hound"), None);
+
+        let dir = TempDir::new("accounts");
+        let path = dir.join("accounts.json");
+        let store = AccountStore::load(&path).await.unwrap();
+        let id = store
+            .register("quartz_fan", "hash".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(store
+            .register("quartz_fan", "other".to_string())
+            .await
+            .unwrap()
+            .is_none());
+
+        store.set_favorite(&id, "quartz", true).await.unwrap();
+        store.set_favorite(&id, "beryl", true).await.unwrap();
+        store.set_favorite(&id, "quartz", true).await.unwrap();
+        store.set_favorite(&id, "opal", false).await.unwrap();
+
+        let reloaded = AccountStore::load(&path).await.unwrap();
+        assert_eq!(reloaded.favorites(&id), ["beryl", "quartz"]);
+        reloaded.set_favorite(&id, "beryl", false).await.unwrap();
+        assert_eq!(reloaded.favorites(&id), ["quartz"]);
+        assert_eq!(reloaded.credential("quartz_fan").unwrap().0, id);
+
+        let token = reloaded.start_session(&id, "quartz_fan").unwrap();
+        assert_eq!(reloaded.session(&token).unwrap().account_id, id);
+        reloaded.end_session(&token);
+        assert!(reloaded.session(&token).is_none());
+    }
+}
@@ -3,8 +3,16 @@ use std::cmp::Ordering;
 use chrono::Utc;
 
 use crate::{
+    custody::CustodyEvent,
     i18n::Language,
+    indices::{self, DerivedIndex},
     models::{Mineral, ReportRequest},
+    safety::{self, Hazard},
+    similarity::RelatedMineral,
+    specimens::Specimen,
+    spectra::Spectrum,
+    strunz,
+    units::UnitSystem,
 };
 
 #[derive(Debug, Clone)]
@@ -19,14 +27,92 @@ pub struct MineralReport {
     pub audience: String,
     pub purpose: String,
     pub site_context: String,
+    /// Name of the report preset used; blank for none.
+    pub preset: String,
     pub generated_utc: String,
     pub dominant_element: String,
     pub dominant_element_pct: f32,
     pub hardness_band: String,
     pub density_band: String,
+    /// Nickel–Strunz class, e.g. `9.FA Tectosilicates`.
+    pub strunz_class: String,
     pub summary: String,
     pub recommendations: Vec<String>,
     pub element_breakdown: Vec<ElementShare>,
+    /// Grade, silica, volatile, and specific-gravity indices that apply.
+    pub derived_indices: Vec<DerivedIndex>,
+    /// Handling hazards, most severe first.
+    pub hazards: Vec<Hazard>,
+    /// Closest catalog matches, best first.
+    pub related: Vec<RelatedMineral>,
+    pub specimens: Vec<Specimen>,
+    /// Chain-of-custody events, oldest first.
+    pub custody: Vec<CustodyEvent>,
+    /// Raman and XRD spectra, oldest upload first.
+    pub spectra: Vec<Spectrum>,
+    /// What wrote `summary` and `recommendations`.
+    pub narrative: Narrative,
+    /// Cutoffs the hardness and density bands were assigned with.
+    pub classification: Classification,
+    /// Units the density and specimens are shown in.
+    pub units: UnitSystem,
+}
+
+/// Band cutoffs, ascending; each one is where the next band starts.
+/// Deployments set their own in the `[classification]` config section.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Classification {
+    /// Mohs hardness between soft, medium, hard, and very hard.
+    pub hardness: [f32; 3],
+    /// Density in g/cm3 between light, moderate, and dense.
+    pub density: [f32; 2],
+}
+
+impl Default for Classification {
+    fn default() -> Self {
+        Self {
+            hardness: [3.0, 6.0, 7.5],
+            density: [2.6, 3.2],
+        }
+    }
+}
+
+/// Author of a report's summary and recommendations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Narrative {
+    /// The fixed sentences of this chain.
+    #[default]
+    Rules,
+    /// The configured LLM provider, from the chain's metrics.
+    Llm { model: String },
+}
+
+impl Narrative {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Narrative::Rules => "rules",
+            Narrative::Llm { .. } => "llm",
+        }
+    }
+}
+
+/// Several minerals analysed under one request, with findings that only
+/// make sense side by side.
+#[derive(Debug, Clone)]
+pub struct ComparisonReport {
+    /// One report per mineral, in request order.
+    pub minerals: Vec<MineralReport>,
+    pub audience: String,
+    pub purpose: String,
+    pub site_context: String,
+    pub generated_utc: String,
+    pub summary: String,
+    pub recommendations: Vec<String>,
+    /// Every element found in any of the minerals, highest share first.
+    pub elements: Vec<String>,
+    /// Cutoffs shared by the per-mineral reports.
+    pub classification: Classification,
+    pub units: UnitSystem,
 }
 
 #[derive(Debug, Clone)]
@@ -35,7 +121,9 @@ struct DerivedMetrics {
     dominant_element_pct: f32,
     hardness_band: HardnessBand,
     density_band: DensityBand,
+    strunz_class: String,
     element_breakdown: Vec<ElementShare>,
+    derived_indices: Vec<DerivedIndex>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -53,12 +141,24 @@ enum DensityBand {
     Dense,
 }
 
+/// Files kept in a mineral folder beside its record, carried into the
+/// report unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct MineralAttachments {
+    pub specimens: Vec<Specimen>,
+    pub custody: Vec<CustodyEvent>,
+    pub spectra: Vec<Spectrum>,
+}
+
 pub fn run_agentic_chain(
     mineral: &Mineral,
     request: &ReportRequest,
+    related: Vec<RelatedMineral>,
+    attachments: MineralAttachments,
+    classification: Classification,
     language: Language,
 ) -> MineralReport {
-    let metrics = derive_metrics(mineral, language);
+    let metrics = derive_metrics(mineral, &attachments.specimens, &classification, language);
     let summary = compose_summary(language, mineral, request, &metrics);
     let recommendations = propose_recommendations(language, mineral, request, &metrics);
 
@@ -67,18 +167,373 @@ pub fn run_agentic_chain(
         audience: request.audience.clone(),
         purpose: request.purpose.clone(),
         site_context: request.site_context.clone(),
+        preset: request.preset.clone(),
         generated_utc: Utc::now().to_rfc3339(),
         dominant_element: metrics.dominant_element,
         dominant_element_pct: metrics.dominant_element_pct,
         hardness_band: localized_hardness_band(language, metrics.hardness_band).to_string(),
         density_band: localized_density_band(language, metrics.density_band).to_string(),
+        strunz_class: metrics.strunz_class,
         summary,
         recommendations,
         element_breakdown: metrics.element_breakdown,
+        derived_indices: metrics.derived_indices,
+        hazards: safety::hazards(mineral),
+        related,
+        specimens: attachments.specimens,
+        custody: attachments.custody,
+        spectra: attachments.spectra,
+        narrative: Narrative::Rules,
+        classification,
+        units: UnitSystem::from_code(&request.units).unwrap_or_default(),
+    }
+}
+
+/// Compares the per-mineral `reports` (at least two) for `request`.
+pub fn run_comparison_chain(
+    reports: Vec<MineralReport>,
+    request: &ReportRequest,
+    language: Language,
+) -> ComparisonReport {
+    let elements = compared_elements(&reports);
+    let classification = reports
+        .first()
+        .map(|report| report.classification)
+        .unwrap_or_default();
+    let summary = compose_comparison_summary(language, &reports, request);
+    let recommendations = propose_comparison_recommendations(language, &reports, &elements);
+
+    ComparisonReport {
+        minerals: reports,
+        audience: request.audience.clone(),
+        purpose: request.purpose.clone(),
+        site_context: request.site_context.clone(),
+        generated_utc: Utc::now().to_rfc3339(),
+        summary,
+        recommendations,
+        elements,
+        classification,
+        units: UnitSystem::from_code(&request.units).unwrap_or_default(),
+    }
+}
+
+/// Weight percent of `element` in `report`, `0` when absent.
+pub fn element_share(report: &MineralReport, element: &str) -> f32 {
+    report
+        .element_breakdown
+        .iter()
+        .find(|share| share.name == element)
+        .map(|share| share.percent)
+        .unwrap_or(0.0)
+}
+
+fn compared_elements(reports: &[MineralReport]) -> Vec<String> {
+    let mut elements: Vec<(String, f32)> = Vec::new();
+    for share in reports.iter().flat_map(|report| &report.element_breakdown) {
+        match elements.iter_mut().find(|(name, _)| *name == share.name) {
+            Some((_, highest)) => *highest = highest.max(share.percent),
+            None => elements.push((share.name.clone(), share.percent)),
+        }
     }
+    elements.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+    elements.into_iter().map(|(name, _)| name).collect()
+}
+
+/// Reports with the lowest and highest `value`.
+fn extremes(
+    reports: &[MineralReport],
+    value: impl Fn(&MineralReport) -> f32,
+) -> (&MineralReport, &MineralReport) {
+    let by_value = |a: &&MineralReport, b: &&MineralReport| {
+        value(a).partial_cmp(&value(b)).unwrap_or(Ordering::Equal)
+    };
+    let lowest = reports.iter().min_by(by_value).expect("compared reports");
+    let highest = reports.iter().max_by(by_value).expect("compared reports");
+    (lowest, highest)
 }
 
-fn derive_metrics(mineral: &Mineral, language: Language) -> DerivedMetrics {
+fn compose_comparison_summary(
+    language: Language,
+    reports: &[MineralReport],
+    request: &ReportRequest,
+) -> String {
+    let names = reports
+        .iter()
+        .map(|report| report.mineral.common_name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let (_, hardest) = extremes(reports, |report| report.mineral.hardness_mohs);
+    let (_, densest) = extremes(reports, |report| report.mineral.density_g_cm3);
+    let hardest_name = &hardest.mineral.common_name;
+    let hardness = hardest.mineral.hardness_mohs;
+    let densest_name = &densest.mineral.common_name;
+    let density = densest.mineral.density_g_cm3;
+
+    match language {
+        Language::En => format!(
+            "Comparing {names} for {audience} in the {site} context: {hardest_name} is the hardest ({hardness:.1} Mohs) and {densest_name} the densest ({density:.2} g/cm3), informing {purpose} decisions.",
+            audience = request.audience,
+            site = request.site_context,
+            purpose = request.purpose,
+        ),
+        Language::Es => format!(
+            "Comparando {names} para {audience} en el contexto {site}: {hardest_name} es el mas duro ({hardness:.1} Mohs) y {densest_name} el mas denso ({density:.2} g/cm3), lo que orienta decisiones de {purpose}.",
+            audience = request.audience,
+            site = request.site_context,
+            purpose = request.purpose,
+        ),
+        Language::Cs => format!(
+            "Srovnani {names} pro {audience} v kontextu {site}: {hardest_name} je nejtvrdsi ({hardness:.1} Mohs) a {densest_name} nejhustsi ({density:.2} g/cm3), coz podporuje rozhodovani pro {purpose}.",
+            audience = request.audience,
+            site = request.site_context,
+            purpose = request.purpose,
+        ),
+        Language::Zh => format!(
+            "面向{audience}并结合{site}场景比较{names}：{hardest_name}硬度最高（{hardness:.1} Mohs），{densest_name}密度最大（{density:.2} g/cm3），可支持{purpose}相关决策。",
+            audience = request.audience,
+            site = request.site_context,
+            purpose = request.purpose,
+        ),
+        Language::Ar => format!(
+            "مقارنة {names} بالنسبة الى {audience} في سياق {site}: {hardest_name} هو الاصلب ({hardness:.1} Mohs) و{densest_name} هو الاكثف ({density:.2} g/cm3)، ما يدعم قرارات {purpose}.",
+            audience = request.audience,
+            site = request.site_context,
+            purpose = request.purpose,
+        ),
+        Language::Fr => format!(
+            "Comparaison de {names} pour {audience} dans le contexte {site} : {hardest_name} est le plus dur ({hardness:.1} Mohs) et {densest_name} le plus dense ({density:.2} g/cm3), ce qui oriente les decisions de {purpose}.",
+            audience = request.audience,
+            site = request.site_context,
+            purpose = request.purpose,
+        ),
+        Language::De => format!(
+            "Vergleich von {names} fur {audience} im Kontext {site}: {hardest_name} ist am hartesten ({hardness:.1} Mohs) und {densest_name} am dichtesten ({density:.2} g/cm3), als Grundlage fur Entscheidungen zu {purpose}.",
+            audience = request.audience,
+            site = request.site_context,
+            purpose = request.purpose,
+        ),
+        Language::Pt => format!(
+            "Comparando {names} para {audience} no contexto {site}: {hardest_name} e o mais duro ({hardness:.1} Mohs) e {densest_name} o mais denso ({density:.2} g/cm3), orientando decisoes de {purpose}.",
+            audience = request.audience,
+            site = request.site_context,
+            purpose = request.purpose,
+        ),
+        Language::Hi => format!(
+            "{audience} ke liye {site} sandarbh me {names} ki tulna: {hardest_name} sabse kathor hai ({hardness:.1} Mohs) aur {densest_name} sabse ghan ({density:.2} g/cm3), jo {purpose} nirnayon ko samarthan deta hai.",
+            audience = request.audience,
+            site = request.site_context,
+            purpose = request.purpose,
+        ),
+        Language::Ja => format!(
+            "{audience} 向けに {site} の文脈で {names} を比較すると、{hardest_name} が最も硬く（{hardness:.1} Mohs）、{densest_name} が最も高密度です（{density:.2} g/cm3）。{purpose} の判断を支援します。",
+            audience = request.audience,
+            site = request.site_context,
+            purpose = request.purpose,
+        ),
+        Language::Ru => format!(
+            "Сравнение {names} для {audience} в контексте {site}: {hardest_name} самый твердый ({hardness:.1} Mohs), а {densest_name} самый плотный ({density:.2} g/cm3), что помогает в решениях по {purpose}.",
+            audience = request.audience,
+            site = request.site_context,
+            purpose = request.purpose,
+        ),
+        Language::Tr => format!(
+            "{audience} icin {site} baglaminda {names} karsilastirmasi: {hardest_name} en sert ({hardness:.1} Mohs), {densest_name} ise en yogun ({density:.2} g/cm3); bu da {purpose} kararlarina yon verir.",
+            audience = request.audience,
+            site = request.site_context,
+            purpose = request.purpose,
+        ),
+        Language::Ko => format!(
+            "{audience}을 위한 {site} 맥락에서 {names} 비교: {hardest_name}이 가장 단단하고({hardness:.1} Mohs) {densest_name}이 가장 밀도가 높아({density:.2} g/cm3) {purpose} 의사결정을 지원합니다.",
+            audience = request.audience,
+            site = request.site_context,
+            purpose = request.purpose,
+        ),
+        Language::Sw => format!(
+            "Ulinganisho wa {names} kwa {audience} katika muktadha wa {site}: {hardest_name} ni mgumu zaidi ({hardness:.1} Mohs) na {densest_name} ni mzito zaidi ({density:.2} g/cm3), hivyo kusaidia maamuzi ya {purpose}.",
+            audience = request.audience,
+            site = request.site_context,
+            purpose = request.purpose,
+        ),
+    }
+}
+
+fn propose_comparison_recommendations(
+    language: Language,
+    reports: &[MineralReport],
+    elements: &[String],
+) -> Vec<String> {
+    let mut recs = Vec::new();
+
+    let (lightest, densest) = extremes(reports, |report| report.mineral.density_g_cm3);
+    let densest_name = &densest.mineral.common_name;
+    let lightest_name = &lightest.mineral.common_name;
+    let gap = densest.mineral.density_g_cm3 - lightest.mineral.density_g_cm3;
+    recs.push(match language {
+        Language::En => format!(
+            "Route {densest_name} to density separation first; it is {gap:.2} g/cm3 denser than {lightest_name}."
+        ),
+        Language::Es => format!(
+            "Envie primero {densest_name} a separacion por densidad; es {gap:.2} g/cm3 mas denso que {lightest_name}."
+        ),
+        Language::Cs => format!(
+            "Nejprve smerujte {densest_name} do hustotni separace; je o {gap:.2} g/cm3 hustsi nez {lightest_name}."
+        ),
+        Language::Zh => format!(
+            "优先将 {densest_name} 送入密度分选；其密度比 {lightest_name} 高 {gap:.2} g/cm3。"
+        ),
+        Language::Ar => format!(
+            "وجّه {densest_name} اولا الى الفصل بالكثافة؛ فهو اكثف من {lightest_name} بمقدار {gap:.2} g/cm3."
+        ),
+        Language::Fr => format!(
+            "Orientez d'abord {densest_name} vers la separation par densite ; il est plus dense que {lightest_name} de {gap:.2} g/cm3."
+        ),
+        Language::De => format!(
+            "Leiten Sie {densest_name} zuerst in die Dichtetrennung; es ist {gap:.2} g/cm3 dichter als {lightest_name}."
+        ),
+        Language::Pt => format!(
+            "Encaminhe primeiro {densest_name} para separacao por densidade; e {gap:.2} g/cm3 mais denso que {lightest_name}."
+        ),
+        Language::Hi => format!(
+            "{densest_name} ko pehle density separation me bhejein; yeh {lightest_name} se {gap:.2} g/cm3 adhik ghan hai."
+        ),
+        Language::Ja => format!(
+            "{densest_name} を優先して比重選別に回してください。{lightest_name} より {gap:.2} g/cm3 高密度です。"
+        ),
+        Language::Ru => format!(
+            "Сначала направьте {densest_name} на гравитационное разделение; он на {gap:.2} g/cm3 плотнее, чем {lightest_name}."
+        ),
+        Language::Tr => format!(
+            "{densest_name} once yogunluk ayirmasina gonderin; {lightest_name} mineralinden {gap:.2} g/cm3 daha yogundur."
+        ),
+        Language::Ko => format!(
+            "{densest_name}을 먼저 비중 선별로 보내십시오. {lightest_name}보다 {gap:.2} g/cm3 더 밀도가 높습니다."
+        ),
+        Language::Sw => format!(
+            "Peleka {densest_name} kwanza kwenye utenganishaji kwa msongamano; ni mzito kwa {gap:.2} g/cm3 kuliko {lightest_name}."
+        ),
+    });
+
+    let (softest, hardest) = extremes(reports, |report| report.mineral.hardness_mohs);
+    let hardest_name = &hardest.mineral.common_name;
+    let hardest_mohs = hardest.mineral.hardness_mohs;
+    let softest_name = &softest.mineral.common_name;
+    let softest_mohs = softest.mineral.hardness_mohs;
+    recs.push(match language {
+        Language::En => format!(
+            "Size crushing and tooling for {hardest_name} ({hardest_mohs:.1} Mohs); {softest_name} ({softest_mohs:.1} Mohs) needs the least comminution energy."
+        ),
+        Language::Es => format!(
+            "Dimensione la trituracion y las herramientas para {hardest_name} ({hardest_mohs:.1} Mohs); {softest_name} ({softest_mohs:.1} Mohs) requiere la menor energia de conminucion."
+        ),
+        Language::Cs => format!(
+            "Dimenzujte drceni a nastroje pro {hardest_name} ({hardest_mohs:.1} Mohs); {softest_name} ({softest_mohs:.1} Mohs) vyzaduje nejmene energie na rozmelneni."
+        ),
+        Language::Zh => format!(
+            "按 {hardest_name}（{hardest_mohs:.1} Mohs）配置破碎设备与工具；{softest_name}（{softest_mohs:.1} Mohs）所需粉碎能耗最低。"
+        ),
+        Language::Ar => format!(
+            "صمم التكسير والادوات وفق {hardest_name} ({hardest_mohs:.1} Mohs)؛ اما {softest_name} ({softest_mohs:.1} Mohs) فيحتاج اقل طاقة تكسير وطحن."
+        ),
+        Language::Fr => format!(
+            "Dimensionnez le concassage et l'outillage pour {hardest_name} ({hardest_mohs:.1} Mohs) ; {softest_name} ({softest_mohs:.1} Mohs) demande le moins d'energie de comminution."
+        ),
+        Language::De => format!(
+            "Legen Sie Brecher und Werkzeuge fur {hardest_name} ({hardest_mohs:.1} Mohs) aus; {softest_name} ({softest_mohs:.1} Mohs) benotigt die geringste Zerkleinerungsenergie."
+        ),
+        Language::Pt => format!(
+            "Dimensione a britagem e as ferramentas para {hardest_name} ({hardest_mohs:.1} Mohs); {softest_name} ({softest_mohs:.1} Mohs) exige a menor energia de cominuicao."
+        ),
+        Language::Hi => format!(
+            "Crushing aur tooling ko {hardest_name} ({hardest_mohs:.1} Mohs) ke hisaab se tay karein; {softest_name} ({softest_mohs:.1} Mohs) ko sabse kam comminution energy chahiye."
+        ),
+        Language::Ja => format!(
+            "破砕設備と工具は {hardest_name}（{hardest_mohs:.1} Mohs）を基準に選定してください。{softest_name}（{softest_mohs:.1} Mohs）は粉砕エネルギーが最も少なくて済みます。"
+        ),
+        Language::Ru => format!(
+            "Подбирайте дробление и инструмент под {hardest_name} ({hardest_mohs:.1} Mohs); {softest_name} ({softest_mohs:.1} Mohs) требует наименьших затрат энергии на измельчение."
+        ),
+        Language::Tr => format!(
+            "Kirma ve takimlari {hardest_name} ({hardest_mohs:.1} Mohs) icin boyutlandirin; {softest_name} ({softest_mohs:.1} Mohs) en az ogutme enerjisi gerektirir."
+        ),
+        Language::Ko => format!(
+            "파쇄 설비와 공구는 {hardest_name}({hardest_mohs:.1} Mohs)에 맞춰 선정하십시오. {softest_name}({softest_mohs:.1} Mohs)은 분쇄 에너지가 가장 적게 듭니다."
+        ),
+        Language::Sw => format!(
+            "Panga usagaji na vifaa kulingana na {hardest_name} ({hardest_mohs:.1} Mohs); {softest_name} ({softest_mohs:.1} Mohs) inahitaji nishati ndogo zaidi ya kusaga."
+        ),
+    });
+
+    // The element whose share varies most tells the minerals apart best;
+    // nothing to suggest when they all carry the same amounts.
+    let widest = elements
+        .iter()
+        .map(|element| {
+            let (low, high) = extremes(reports, |report| element_share(report, element));
+            let spread = element_share(high, element) - element_share(low, element);
+            (element, low, high, spread)
+        })
+        .filter(|(_, _, _, spread)| *spread >= 0.1)
+        .max_by(|a, b| a.3.partial_cmp(&b.3).unwrap_or(Ordering::Equal));
+    if let Some((element, low, high, _)) = widest {
+        let low_name = &low.mineral.common_name;
+        let low_pct = element_share(low, element);
+        let high_name = &high.mineral.common_name;
+        let high_pct = element_share(high, element);
+        recs.push(match language {
+            Language::En => format!(
+                "Use {element} assays to tell these minerals apart: it ranges from {low_pct:.1} wt% in {low_name} to {high_pct:.1} wt% in {high_name}."
+            ),
+            Language::Es => format!(
+                "Use ensayos de {element} para distinguir estos minerales: varia de {low_pct:.1} % en peso en {low_name} a {high_pct:.1} % en peso en {high_name}."
+            ),
+            Language::Cs => format!(
+                "K rozliseni techto mineralu pouzijte analyzy {element}: pohybuje se od {low_pct:.1} hm. % v {low_name} po {high_pct:.1} hm. % v {high_name}."
+            ),
+            Language::Zh => format!(
+                "使用 {element} 化验区分这些矿物：其含量从 {low_name} 的 {low_pct:.1} wt% 到 {high_name} 的 {high_pct:.1} wt%。"
+            ),
+            Language::Ar => format!(
+                "استخدم تحاليل {element} للتمييز بين هذه المعادن: تتراوح نسبته من {low_pct:.1} wt% في {low_name} الى {high_pct:.1} wt% في {high_name}."
+            ),
+            Language::Fr => format!(
+                "Utilisez les analyses de {element} pour distinguer ces mineraux : sa teneur va de {low_pct:.1} wt% dans {low_name} a {high_pct:.1} wt% dans {high_name}."
+            ),
+            Language::De => format!(
+                "Nutzen Sie {element}-Analysen, um diese Minerale zu unterscheiden: der Gehalt reicht von {low_pct:.1} wt% in {low_name} bis {high_pct:.1} wt% in {high_name}."
+            ),
+            Language::Pt => format!(
+                "Use ensaios de {element} para distinguir estes minerais: varia de {low_pct:.1} wt% em {low_name} a {high_pct:.1} wt% em {high_name}."
+            ),
+            Language::Hi => format!(
+                "In khanijon ko alag pehchanne ke liye {element} assays ka upyog karein: yeh {low_name} me {low_pct:.1} wt% se {high_name} me {high_pct:.1} wt% tak hai."
+            ),
+            Language::Ja => format!(
+                "これらの鉱物の判別には {element} の分析を用いてください。含有量は {low_name} の {low_pct:.1} wt% から {high_name} の {high_pct:.1} wt% まで幅があります。"
+            ),
+            Language::Ru => format!(
+                "Используйте анализы на {element}, чтобы различать эти минералы: его содержание меняется от {low_pct:.1} wt% в {low_name} до {high_pct:.1} wt% в {high_name}."
+            ),
+            Language::Tr => format!(
+                "Bu mineralleri ayirt etmek icin {element} analizlerini kullanin: orani {low_name} icinde {low_pct:.1} wt% ile {high_name} icinde {high_pct:.1} wt% arasinda degisir."
+            ),
+            Language::Ko => format!(
+                "이 광물들을 구별하려면 {element} 분석을 사용하십시오. 함량은 {low_name}의 {low_pct:.1} wt%에서 {high_name}의 {high_pct:.1} wt%까지 다양합니다."
+            ),
+            Language::Sw => format!(
+                "Tumia uchambuzi wa {element} kutofautisha madini haya: kiwango chake ni kuanzia {low_pct:.1} wt% katika {low_name} hadi {high_pct:.1} wt% katika {high_name}."
+            ),
+        });
+    }
+
+    recs
+}
+
+fn derive_metrics(
+    mineral: &Mineral,
+    specimens: &[Specimen],
+    classification: &Classification,
+    language: Language,
+) -> DerivedMetrics {
     let mut element_breakdown: Vec<ElementShare> = mineral
         .major_elements_pct
         .iter()
@@ -95,25 +550,33 @@ fn derive_metrics(mineral: &Mineral, language: Language) -> DerivedMetrics {
         percent: 0.0,
     });
 
+    let [soft_below, medium_below, hard_below] = classification.hardness;
     let hardness_band = match mineral.hardness_mohs {
-        h if h < 3.0 => HardnessBand::Soft,
-        h if h < 6.0 => HardnessBand::Medium,
-        h if h < 7.5 => HardnessBand::Hard,
+        h if h < soft_below => HardnessBand::Soft,
+        h if h < medium_below => HardnessBand::Medium,
+        h if h < hard_below => HardnessBand::Hard,
         _ => HardnessBand::VeryHard,
     };
 
+    let [light_below, moderate_below] = classification.density;
     let density_band = match mineral.density_g_cm3 {
-        d if d < 2.6 => DensityBand::Light,
-        d if d < 3.2 => DensityBand::Moderate,
+        d if d < light_below => DensityBand::Light,
+        d if d < moderate_below => DensityBand::Moderate,
         _ => DensityBand::Dense,
     };
 
+    let strunz_class = strunz::classify(&mineral.formula, &mineral.mineral_family)
+        .map(|class| class.label())
+        .unwrap_or_else(|| localized_unknown(language).to_string());
+
     DerivedMetrics {
         dominant_element: dominant.name,
         dominant_element_pct: dominant.percent,
         hardness_band,
         density_band,
+        strunz_class,
         element_breakdown,
+        derived_indices: indices::derive(mineral, specimens),
     }
 }
 
@@ -237,6 +700,50 @@ fn compose_summary(
             pct = metrics.dominant_element_pct,
             purpose = request.purpose,
         ),
+        Language::Ru => format!(
+            "Для {audience} в контексте {site} минерал {mineral} классифицируется как {hardness}, плотность — {density}. В химическом составе преобладает {element} ({pct:.1} wt%), что помогает в решениях по {purpose}.",
+            audience = request.audience,
+            site = request.site_context,
+            mineral = mineral.common_name,
+            hardness = hardness,
+            density = density,
+            element = metrics.dominant_element,
+            pct = metrics.dominant_element_pct,
+            purpose = request.purpose,
+        ),
+        Language::Tr => format!(
+            "{audience} icin ve {site} baglaminda {mineral}, {hardness} olarak siniflandirilir ve yogunluk davranisi {density} seklindedir. Kimyasal bilesimde {element} ({pct:.1} wt%) baskindir ve {purpose} kararlarini destekler.",
+            audience = request.audience,
+            site = request.site_context,
+            mineral = mineral.common_name,
+            hardness = hardness,
+            density = density,
+            element = metrics.dominant_element,
+            pct = metrics.dominant_element_pct,
+            purpose = request.purpose,
+        ),
+        Language::Ko => format!(
+            "{audience}을 위한 {site} 맥락에서 {mineral}은 {hardness}으로 분류되며 밀도 특성은 {density}입니다. 화학 조성은 {element}({pct:.1} wt%)이 우세하여 {purpose} 의사결정을 지원합니다.",
+            audience = request.audience,
+            site = request.site_context,
+            mineral = mineral.common_name,
+            hardness = hardness,
+            density = density,
+            element = metrics.dominant_element,
+            pct = metrics.dominant_element_pct,
+            purpose = request.purpose,
+        ),
+        Language::Sw => format!(
+            "Kwa {audience} katika muktadha wa {site}, {mineral} imeainishwa kama {hardness} na tabia ya msongamano {density}. Kemia yake inaongozwa na {element} ({pct:.1} wt%), hivyo kusaidia maamuzi ya {purpose}.",
+            audience = request.audience,
+            site = request.site_context,
+            mineral = mineral.common_name,
+            hardness = hardness,
+            density = density,
+            element = metrics.dominant_element,
+            pct = metrics.dominant_element_pct,
+            purpose = request.purpose,
+        ),
     }
 }
 
@@ -289,6 +796,22 @@ fn propose_recommendations(
             "{} では {} の濃集が最も強いサンプルを優先してください。",
             mineral.common_name, metrics.dominant_element
         )),
+        Language::Ru => recs.push(format!(
+            "Отдавайте приоритет пробам {}, где обогащение {} наиболее выражено.",
+            mineral.common_name, metrics.dominant_element
+        )),
+        Language::Tr => recs.push(format!(
+            "{} orneklerinde {} zenginlesmesinin en guclu oldugu yerlere oncelik verin.",
+            mineral.common_name, metrics.dominant_element
+        )),
+        Language::Ko => recs.push(format!(
+            "{} 시료 중 {} 부화가 가장 강한 시료를 우선하십시오.",
+            mineral.common_name, metrics.dominant_element
+        )),
+        Language::Sw => recs.push(format!(
+            "Zipe kipaumbele sampuli za {} ambapo urutubishaji wa {} ni mkubwa zaidi.",
+            mineral.common_name, metrics.dominant_element
+        )),
     }
 
     if matches!(
@@ -324,6 +847,18 @@ fn propose_recommendations(
             Language::Ja => {
                 "耐摩耗工具を使用し、粉砕エネルギー見積もりを上方修正してください。".to_string()
             }
+            Language::Ru => {
+                "Используйте износостойкий инструмент и скорректируйте оценки энергии измельчения в большую сторону.".to_string()
+            }
+            Language::Tr => {
+                "Asinmaya dayanikli takimlar kullanin ve ogutme enerjisi tahminlerini yukari yonde guncelleyin.".to_string()
+            }
+            Language::Ko => {
+                "내마모성 공구를 사용하고 분쇄 에너지 추정치를 상향 조정하십시오.".to_string()
+            }
+            Language::Sw => {
+                "Tumia vifaa vinavyostahimili mmomonyoko na ongeza makadirio ya nishati ya kusaga.".to_string()
+            }
         });
     } else {
         recs.push(match language {
@@ -355,6 +890,18 @@ fn propose_recommendations(
             Language::Ja => {
                 "軟質な鉱物は品位管理を偏らせる可能性があるため、破砕性と風化速度を早期に検証してください。".to_string()
             }
+            Language::Ru => {
+                "Заранее проверьте скорость разрушения и выветривания: более мягкий материал может искажать контроль содержания.".to_string()
+            }
+            Language::Tr => {
+                "Kirilma ve ayrisma hizlarini erkenden dogrulayin; daha yumusak malzeme tenor kontrolunu saptirabilir.".to_string()
+            }
+            Language::Ko => {
+                "무른 물질은 품위 관리를 왜곡할 수 있으므로 파쇄성과 풍화 속도를 조기에 검증하십시오.".to_string()
+            }
+            Language::Sw => {
+                "Thibitisha mapema viwango vya kuvunjika na hali ya hewa, kwa kuwa nyenzo laini zaidi inaweza kupotosha udhibiti wa daraja.".to_string()
+            }
         });
     }
 
@@ -388,6 +935,18 @@ fn propose_recommendations(
             Language::Ja => {
                 "初期フローシートにおける回収率向上の可能性を確認するため、比重選別試験を実施してください。".to_string()
             }
+            Language::Ru => {
+                "Проведите испытания гравитационного разделения, чтобы подтвердить потенциал роста извлечения в ранних технологических схемах.".to_string()
+            }
+            Language::Tr => {
+                "Erken akis semalarinda geri kazanim artisi potansiyelini dogrulamak icin yogunluk ayirma testleri yapin.".to_string()
+            }
+            Language::Ko => {
+                "초기 공정 흐름도에서 회수율 향상 가능성을 확인하기 위해 비중 선별 시험을 수행하십시오.".to_string()
+            }
+            Language::Sw => {
+                "Fanya majaribio ya utenganishaji kwa msongamano ili kuthibitisha uwezekano wa kuongeza urejeshaji katika mtiririko wa awali wa uchakataji.".to_string()
+            }
         });
     } else {
         recs.push(match language {
@@ -419,6 +978,18 @@ fn propose_recommendations(
             Language::Ja => {
                 "比重分離への過度な依存を避けるため、XRD と地球化学データを組み合わせて評価してください。".to_string()
             }
+            Language::Ru => {
+                "Сочетайте XRD с геохимией, чтобы не полагаться чрезмерно на разделение по плотности.".to_string()
+            }
+            Language::Tr => {
+                "Yogunluga dayali ayirmaya asiri bagimliligi onlemek icin XRD'yi jeokimya ile birlestirin.".to_string()
+            }
+            Language::Ko => {
+                "비중 기반 선별에 과도하게 의존하지 않도록 XRD와 지구화학 데이터를 함께 활용하십시오.".to_string()
+            }
+            Language::Sw => {
+                "Changanya XRD na jiokemia ili kuepuka kutegemea kupita kiasi utenganishaji unaotegemea msongamano.".to_string()
+            }
         });
     }
 
@@ -463,6 +1034,22 @@ fn propose_recommendations(
             "再現可能な意思決定記録のため、このレポートを '{}' の目的に紐づけて保存してください。",
             request.purpose
         )),
+        Language::Ru => recs.push(format!(
+            "Сохраните этот отчет в архиве с целями '{}' для воспроизводимых записей о решениях.",
+            request.purpose
+        )),
+        Language::Tr => recs.push(format!(
+            "Tekrarlanabilir karar kayitlari icin bu raporu '{}' hedefleri altinda arsivleyin.",
+            request.purpose
+        )),
+        Language::Ko => recs.push(format!(
+            "재현 가능한 의사결정 기록을 위해 이 보고서를 '{}' 목적과 연결해 보관하십시오.",
+            request.purpose
+        )),
+        Language::Sw => recs.push(format!(
+            "Hifadhi ripoti hii chini ya malengo ya '{}' kwa kumbukumbu za maamuzi zinazoweza kurudiwa.",
+            request.purpose
+        )),
     }
 
     recs
@@ -480,9 +1067,34 @@ fn localized_unknown(language: Language) -> &'static str {
         Language::Pt => "Desconhecido",
         Language::Hi => "Agyat",
         Language::Ja => "不明",
+        Language::Ru => "Неизвестно",
+        Language::Tr => "Bilinmiyor",
+        Language::Ko => "알 수 없음",
+        Language::Sw => "Haijulikani",
     }
 }
 
+/// Hardness band names in `language`, softest first.
+pub fn hardness_band_names(language: Language) -> [&'static str; 4] {
+    [
+        HardnessBand::Soft,
+        HardnessBand::Medium,
+        HardnessBand::Hard,
+        HardnessBand::VeryHard,
+    ]
+    .map(|band| localized_hardness_band(language, band))
+}
+
+/// Density band names in `language`, lightest first.
+pub fn density_band_names(language: Language) -> [&'static str; 3] {
+    [
+        DensityBand::Light,
+        DensityBand::Moderate,
+        DensityBand::Dense,
+    ]
+    .map(|band| localized_density_band(language, band))
+}
+
 fn localized_hardness_band(language: Language, band: HardnessBand) -> &'static str {
     match language {
         Language::En => match band {
@@ -545,6 +1157,30 @@ fn localized_hardness_band(language: Language, band: HardnessBand) -> &'static s
             HardnessBand::Hard => "硬い",
             HardnessBand::VeryHard => "非常に硬い",
         },
+        Language::Ru => match band {
+            HardnessBand::Soft => "мягкий",
+            HardnessBand::Medium => "средний",
+            HardnessBand::Hard => "твердый",
+            HardnessBand::VeryHard => "очень твердый",
+        },
+        Language::Tr => match band {
+            HardnessBand::Soft => "yumusak",
+            HardnessBand::Medium => "orta",
+            HardnessBand::Hard => "sert",
+            HardnessBand::VeryHard => "cok sert",
+        },
+        Language::Ko => match band {
+            HardnessBand::Soft => "무름",
+            HardnessBand::Medium => "중간",
+            HardnessBand::Hard => "단단함",
+            HardnessBand::VeryHard => "매우 단단함",
+        },
+        Language::Sw => match band {
+            HardnessBand::Soft => "laini",
+            HardnessBand::Medium => "wastani",
+            HardnessBand::Hard => "mgumu",
+            HardnessBand::VeryHard => "mgumu sana",
+        },
     }
 }
 
@@ -600,6 +1236,26 @@ fn localized_density_band(language: Language, band: DensityBand) -> &'static str
             DensityBand::Moderate => "中程度",
             DensityBand::Dense => "高密度",
         },
+        Language::Ru => match band {
+            DensityBand::Light => "легкий",
+            DensityBand::Moderate => "умеренный",
+            DensityBand::Dense => "плотный",
+        },
+        Language::Tr => match band {
+            DensityBand::Light => "hafif",
+            DensityBand::Moderate => "orta",
+            DensityBand::Dense => "yogun",
+        },
+        Language::Ko => match band {
+            DensityBand::Light => "가벼움",
+            DensityBand::Moderate => "보통",
+            DensityBand::Dense => "고밀도",
+        },
+        Language::Sw => match band {
+            DensityBand::Light => "mwepesi",
+            DensityBand::Moderate => "wastani",
+            DensityBand::Dense => "mzito",
+        },
     }
 }
 
@@ -607,19 +1263,20 @@ fn localized_density_band(language: Language, band: DensityBand) -> &'static str
 mod tests {
     use std::collections::BTreeMap;
 
-    use super::run_agentic_chain;
+    use super::{
+        run_agentic_chain, run_comparison_chain, Classification, MineralAttachments, MineralReport,
+    };
     use crate::{
         i18n::Language,
-        models::{Mineral, ReportRequest},
+        models::{sample_mineral, Mineral, ReportRequest},
     };
 
-    #[test]
-    fn chain_sorts_elements_and_sets_dominant() {
+    fn test_mineral() -> Mineral {
         let mut elements = BTreeMap::new();
         elements.insert("Si".to_string(), 46.7);
         elements.insert("O".to_string(), 53.3);
 
-        let mineral = Mineral {
+        Mineral {
             slug: "mineral.silicate.0xaaaaaa".to_string(),
             folder_name: "mineral.silicate.0xaaaaaa".to_string(),
             common_name: "Test Mineral".to_string(),
@@ -634,13 +1291,120 @@ mod tests {
             luster: "vitreous".to_string(),
             major_elements_pct: elements,
             notes: "n/a".to_string(),
-            image_path: None,
+            ..sample_mineral()
+        }
+    }
+
+    fn chain(mineral: &Mineral, classification: Classification) -> MineralReport {
+        run_agentic_chain(
+            mineral,
+            &ReportRequest::default(),
+            Vec::new(),
+            MineralAttachments::default(),
+            classification,
+            Language::En,
+        )
+    }
+
+    #[test]
+    fn chain_writes_the_summary_in_the_added_languages() {
+        let mineral = test_mineral();
+        let summary = |language| {
+            run_agentic_chain(
+                &mineral,
+                &ReportRequest::default(),
+                Vec::new(),
+                MineralAttachments::default(),
+                Classification::default(),
+                language,
+            )
+            .summary
         };
+        let english = summary(Language::En);
+        for (language, script) in [(Language::Ru, 'а'..='я'), (Language::Ko, '가'..='힣')] {
+            let text = summary(language);
+            assert!(text.chars().any(|c| script.contains(&c)), "{text}");
+        }
+        for language in [Language::Tr, Language::Sw] {
+            assert_ne!(summary(language), english);
+            assert_eq!(Language::from_code(language.code()), Some(language));
+            assert_eq!(language.dir(), "ltr");
+        }
+    }
+
+    #[test]
+    fn chain_sorts_elements_and_sets_dominant() {
+        let mineral = test_mineral();
 
-        let report = run_agentic_chain(&mineral, &ReportRequest::default(), Language::En);
+        let report = chain(&mineral, Classification::default());
 
         assert_eq!(report.dominant_element, "O");
         assert_eq!(report.element_breakdown[0].name, "O");
         assert_eq!(report.hardness_band, "hard");
     }
+
+    #[test]
+    fn chain_sets_the_strunz_class() {
+        let report = chain(&test_mineral(), Classification::default());
+        assert_eq!(report.strunz_class, "4.DA Oxides, M:O = 1:2 (quartz group)");
+    }
+
+    #[test]
+    fn chain_bands_with_custom_classification_thresholds() {
+        let strict = Classification {
+            hardness: [2.5, 5.5, 7.0],
+            density: [2.0, 2.6],
+        };
+        let report = chain(&test_mineral(), strict);
+        assert_eq!(report.hardness_band, "very hard");
+        assert_eq!(report.density_band, "dense");
+        assert_eq!(report.classification, strict);
+    }
+
+    fn mineral(name: &str, hardness: f32, density: f32, elements: &[(&str, f32)]) -> Mineral {
+        Mineral {
+            slug: format!("mineral.test.{name}"),
+            folder_name: format!("mineral.test.{name}"),
+            common_name: name.to_string(),
+            mineral_family: "oxide".to_string(),
+            hardness_mohs: hardness,
+            density_g_cm3: density,
+            major_elements_pct: elements
+                .iter()
+                .map(|(element, pct)| (element.to_string(), *pct))
+                .collect(),
+            ..sample_mineral()
+        }
+    }
+
+    #[test]
+    fn comparison_ranks_minerals_and_finds_the_distinguishing_element() {
+        let request = ReportRequest::default();
+        let reports = [
+            mineral("Quartz", 7.0, 2.65, &[("O", 53.3), ("Si", 46.7)]),
+            mineral("Hematite", 5.5, 5.26, &[("Fe", 69.9), ("O", 30.1)]),
+        ]
+        .iter()
+        .map(|mineral| {
+            run_agentic_chain(
+                mineral,
+                &request,
+                Vec::new(),
+                MineralAttachments::default(),
+                Classification::default(),
+                Language::En,
+            )
+        })
+        .collect();
+
+        let comparison = run_comparison_chain(reports, &request, Language::En);
+        assert_eq!(comparison.elements, ["Fe", "O", "Si"]);
+        assert!(comparison
+            .summary
+            .contains("Quartz is the hardest (7.0 Mohs) and Hematite the densest (5.26 g/cm3)"));
+        assert_eq!(comparison.recommendations.len(), 3);
+        assert!(comparison.recommendations[0].contains("Hematite to density separation first"));
+        assert!(comparison.recommendations[2]
+            .contains("Fe assays to tell these minerals apart: it ranges from 0.0 wt% in Quartz"));
+    }
 }
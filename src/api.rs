@@ -0,0 +1,355 @@
+//! Token-authenticated JSON CRUD over the mineral catalog. Requests and
+//! responses use the on-disk `MineralDiskRecord` shape (plus `slug`) so
+//! external inventory tools can sync with the folders directly.
+
+use axum::{
+    extract::{Path as AxumPath, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{
+    api_tokens::ApiTokenStore,
+    background, catalog_for_language, check_slugs_available,
+    client_ip::ClientIp,
+    create_mineral_folder, folder_file_name,
+    i18n::Language,
+    images,
+    migrations::SCHEMA_VERSION,
+    mineral_folder_for_slug,
+    models::{
+        normalize_extra_attributes, normalize_tags, select_metadata_path, ImageEntry,
+        MineralDiskRecord,
+    },
+    permalinks, read_english_record, read_record_file, refresh_mineral, required_string,
+    sanitize_image, schedule, store_image, trash, update_mineral_folder, AppError, AppState,
+    MineralImage, NewMineralDraft,
+};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ApiLangParams {
+    lang: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiMineral {
+    slug: String,
+    #[serde(flatten)]
+    record: MineralDiskRecord,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiMineralWrite {
+    #[serde(flatten)]
+    record: MineralDiskRecord,
+    /// PNG, JPEG, WebP, GIF, HEIC or TIFF; the format is read from the data
+    /// itself.
+    #[serde(default)]
+    image_base64: Option<String>,
+}
+
+pub async fn list_minerals(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<ApiLangParams>,
+) -> Result<Json<Vec<ApiMineral>>, AppError> {
+    require_api_token(&state, &headers)?;
+    let language = requested_language(&params)?;
+
+    let minerals_root = state.data_root.join("minerals");
+    let mut minerals = Vec::new();
+    for mineral in &catalog_for_language(&state, Language::En).await?.ordered {
+        let folder_path = minerals_root.join(&mineral.folder_name);
+        let Some(metadata_path) = select_metadata_path(&folder_path, language.code()) else {
+            continue;
+        };
+        minerals.push(ApiMineral {
+            slug: mineral.slug.clone(),
+            record: read_record_file(&metadata_path).await?,
+        });
+    }
+
+    Ok(Json(minerals))
+}
+
+pub async fn get_mineral(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+    Query(params): Query<ApiLangParams>,
+) -> Result<Json<ApiMineral>, AppError> {
+    require_api_token(&state, &headers)?;
+    let language = requested_language(&params)?;
+
+    let folder_path = mineral_folder_for_slug(&state, Language::En, &slug)
+        .await
+        .map_err(AppError::NotFound)?;
+    let metadata_path = select_metadata_path(&folder_path, language.code()).ok_or_else(|| {
+        AppError::NotFound(format!("no metadata file found for mineral '{slug}'"))
+    })?;
+    let record = read_record_file(&metadata_path).await?;
+
+    Ok(Json(ApiMineral {
+        slug: permalinks::slug(&folder_file_name(&folder_path), &record),
+        record,
+    }))
+}
+
+pub async fn create_mineral(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<ApiMineralWrite>,
+) -> Result<(StatusCode, Json<ApiMineral>), AppError> {
+    require_api_token(&state, &headers)?;
+    let image = decode_image(&state, &request).await?;
+    let record = validate_record(request.record)?;
+    check_slugs_available(&state, &record, None).await?;
+
+    let draft = NewMineralDraft {
+        record,
+        images: image.into_iter().collect(),
+        captions: Vec::new(),
+        edits: Vec::new(),
+        primary_image: 0,
+    };
+    let clean_background = draft.record.clean_background;
+    let (folder_name, _) = create_mineral_folder(&state, draft).await?;
+    refresh_mineral(&state, &folder_name).await?;
+    if clean_background {
+        background::spawn_refresh(state.clone(), folder_name.clone());
+    }
+
+    let folder_path = state.data_root.join("minerals").join(&folder_name);
+    let record = read_english_record(&folder_path).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiMineral {
+            slug: permalinks::slug(&folder_name, &record),
+            record,
+        }),
+    ))
+}
+
+pub async fn update_mineral(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+    Json(request): Json<ApiMineralWrite>,
+) -> Result<Json<ApiMineral>, AppError> {
+    require_api_token(&state, &headers)?;
+    let image = decode_image(&state, &request).await?;
+    let requested_images = request.record.images.clone();
+    let mut updated = validate_record(request.record)?;
+
+    let folder_path = mineral_folder_for_slug(&state, Language::En, &slug)
+        .await
+        .map_err(AppError::NotFound)?;
+    let previous = read_english_record(&folder_path).await?;
+    let folder_name = folder_file_name(&folder_path);
+    permalinks::carry_over(&previous, &mut updated);
+    check_slugs_available(&state, &updated, Some(&folder_name)).await?;
+
+    // Files are managed by the server; clients may only retitle entries or
+    // move the primary flag among the files already in the gallery.
+    let mut gallery = previous
+        .images
+        .iter()
+        .map(|current| {
+            requested_images
+                .iter()
+                .find(|requested| requested.file == current.file)
+                .map(|requested| ImageEntry {
+                    file: current.file.clone(),
+                    ..requested.clone()
+                })
+                .unwrap_or_else(|| current.clone())
+        })
+        .collect::<Vec<_>>();
+
+    let new_photo = image.is_some();
+    if let Some(image) = image {
+        // A new upload replaces the primary photo and keeps its caption.
+        let position = gallery.iter().position(|entry| entry.primary);
+        let replaced = position.map(|index| gallery.remove(index));
+        let file = store_image(&folder_path, &gallery, image).await?;
+        if let Some(old) = replaced.as_ref().filter(|old| old.file != file) {
+            let _ = fs::remove_file(folder_path.join(&old.file)).await;
+            images::remove_variants(&folder_path, &old.file);
+        }
+        gallery.insert(
+            position.unwrap_or(0),
+            ImageEntry {
+                file,
+                caption: replaced.map(|old| old.caption).unwrap_or_default(),
+                primary: true,
+            },
+        );
+    }
+    updated.images = gallery;
+    let updated = updated.normalize_images();
+
+    update_mineral_folder(&state, &folder_path, &previous, &updated).await?;
+    refresh_mineral(&state, &folder_name).await?;
+    // A new upload may reuse the primary photo's file name.
+    if background::needs_refresh(&previous, &updated) || (new_photo && updated.clean_background) {
+        background::spawn_refresh(state.clone(), folder_name.clone());
+    }
+
+    let record = read_english_record(&folder_path).await?;
+    Ok(Json(ApiMineral {
+        slug: permalinks::slug(&folder_name, &record),
+        record,
+    }))
+}
+
+pub async fn delete_mineral(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ClientIp(client): ClientIp,
+    AxumPath(slug): AxumPath<String>,
+) -> Result<StatusCode, AppError> {
+    require_api_token(&state, &headers)?;
+
+    let folder_path = mineral_folder_for_slug(&state, Language::En, &slug)
+        .await
+        .map_err(AppError::NotFound)?;
+    trash::move_to_trash(&state.data_root, &folder_path, "api", client).await?;
+    refresh_mineral(&state, &folder_file_name(&folder_path)).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn require_api_token(state: &AppState, headers: &HeaderMap) -> Result<(), AppError> {
+    check_api_token(&state.api_tokens, headers)
+}
+
+/// The check behind [`require_api_token`], against `tokens`.
+fn check_api_token(tokens: &ApiTokenStore, headers: &HeaderMap) -> Result<(), AppError> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| {
+            AppError::Unauthorized(
+                "API token required. Send 'Authorization: Bearer <token>'.".to_string(),
+            )
+        })?;
+
+    tokens
+        .verify(token)
+        .map(|_| ())
+        .ok_or_else(|| AppError::Unauthorized("Invalid API token.".to_string()))
+}
+
+fn requested_language(params: &ApiLangParams) -> Result<Language, AppError> {
+    match params.lang.as_deref() {
+        Some(code) => Language::from_code(code)
+            .ok_or_else(|| AppError::BadRequest(format!("unsupported language code '{code}'"))),
+        None => Ok(Language::En),
+    }
+}
+
+pub fn validate_record(record: MineralDiskRecord) -> Result<MineralDiskRecord, AppError> {
+    for (value, key) in [
+        (record.hardness_mohs, "hardness_mohs"),
+        (record.density_g_cm3, "density_g_cm3"),
+    ] {
+        if !value.is_finite() || value < 0.0 {
+            return Err(AppError::BadRequest(format!(
+                "'{key}' must be a non-negative number"
+            )));
+        }
+    }
+
+    Ok(MineralDiskRecord {
+        common_name: required_string(&record.common_name, "common_name")?,
+        description: required_string(&record.description, "description")?,
+        mineral_family: required_string(&record.mineral_family, "mineral_family")?,
+        formula: required_string(&record.formula, "formula")?,
+        crystal_system: required_string(&record.crystal_system, "crystal_system")?,
+        color: required_string(&record.color, "color")?,
+        streak: required_string(&record.streak, "streak")?,
+        luster: required_string(&record.luster, "luster")?,
+        notes: required_string(&record.notes, "notes")?,
+        images: Vec::new(),
+        tags: normalize_tags(record.tags.iter().map(String::as_str)),
+        extra_attributes: normalize_extra_attributes(record.extra_attributes)
+            .map_err(AppError::BadRequest)?,
+        permalink: permalinks::parse_permalink(record.permalink.as_deref().unwrap_or_default())
+            .map_err(AppError::BadRequest)?,
+        aliases: permalinks::parse_aliases(&record.aliases.join("\n"))
+            .map_err(AppError::BadRequest)?,
+        location: record.location.validate().map_err(AppError::BadRequest)?,
+        listing: record.listing.validate().map_err(AppError::BadRequest)?,
+        publish_at: schedule::parse_publish_at(record.publish_at.as_deref().unwrap_or_default())
+            .map_err(AppError::BadRequest)?,
+        schema_version: SCHEMA_VERSION,
+        translated_from: None,
+        ..record
+    })
+}
+
+async fn decode_image(
+    state: &AppState,
+    request: &ApiMineralWrite,
+) -> Result<Option<MineralImage>, AppError> {
+    let Some(encoded) = request.image_base64.as_deref() else {
+        return Ok(None);
+    };
+
+    let bytes = BASE64.decode(encoded.trim()).map_err(|err| {
+        AppError::BadRequest(format!("'image_base64' is not valid base64: {err}"))
+    })?;
+    if bytes.is_empty() {
+        return Err(AppError::BadRequest("'image_base64' is empty".to_string()));
+    }
+    if bytes.len() > state.image_upload_max_bytes() {
+        return Err(state.image_too_large());
+    }
+
+    sanitize_image(state, bytes).await.map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header, HeaderMap, HeaderValue};
+
+    use super::check_api_token;
+    use crate::{api_tokens::ApiTokenStore, test_support::TempDir, AppError};
+
+    #[tokio::test]
+    async fn requires_a_live_bearer_token() {
+        let dir = TempDir::new("api-auth");
+        let path = dir.join("api-auth.json");
+        let tokens = ApiTokenStore::load(&path).await.unwrap();
+        let token = tokens.create("ci", "CI", "secret").await.unwrap();
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::AUTHORIZATION, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+        let unauthorized =
+            |result: Result<(), AppError>| matches!(result, Err(AppError::Unauthorized(_)));
+
+        assert!(unauthorized(check_api_token(&tokens, &HeaderMap::new())));
+        assert!(unauthorized(check_api_token(
+            &tokens,
+            &headers(&format!("Basic {token}"))
+        )));
+        assert!(unauthorized(check_api_token(
+            &tokens,
+            &headers("Bearer mnl_guess")
+        )));
+        assert!(check_api_token(&tokens, &headers(&format!("Bearer {token}"))).is_ok());
+
+        tokens.revoke("ci").await.unwrap();
+        assert!(unauthorized(check_api_token(
+            &tokens,
+            &headers(&format!("Bearer {token}"))
+        )));
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::auth::sha256_hex;
+
+/// Prefix that makes catalog API tokens easy to spot in configs and logs.
+pub const API_TOKEN_PREFIX: &str = "mnl_";
+
+/// API tokens for the catalog REST endpoints. Only a SHA-256 of each token
+/// is kept (in memory and in the JSON file); the plaintext is shown once at
+/// creation time.
+#[derive(Debug, Clone)]
+pub struct ApiTokenStore {
+    path: PathBuf,
+    tokens: Arc<RwLock<Vec<ApiTokenRecord>>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTokenRecord {
+    pub id: String,
+    pub label: String,
+    pub created_utc: String,
+    token_sha256: String,
+}
+
+impl ApiTokenStore {
+    pub async fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let tokens = match fs::read_to_string(&path).await {
+            Ok(raw) => serde_json::from_str(&raw)
+                .with_context(|| format!("failed to parse {}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(anyhow!("failed to read {}: {err}", path.display())),
+        };
+
+        Ok(Self {
+            path,
+            tokens: Arc::new(RwLock::new(tokens)),
+        })
+    }
+
+    pub fn list(&self) -> Vec<ApiTokenRecord> {
+        self.tokens
+            .read()
+            .map(|tokens| tokens.clone())
+            .unwrap_or_default()
+    }
+
+    /// Registers `secret` (already random) under `label` and returns the
+    /// full token to hand to the operator.
+    pub async fn create(&self, id: &str, label: &str, secret: &str) -> Result<String> {
+        let token = format!("{API_TOKEN_PREFIX}{secret}");
+        let snapshot = {
+            let mut tokens = self
+                .tokens
+                .write()
+                .map_err(|_| anyhow!("api token store lock poisoned"))?;
+            tokens.push(ApiTokenRecord {
+                id: id.to_string(),
+                label: label.to_string(),
+                created_utc: Utc::now().to_rfc3339(),
+                token_sha256: token_digest(&token),
+            });
+            tokens.clone()
+        };
+        self.persist(&snapshot).await?;
+        Ok(token)
+    }
+
+    pub async fn revoke(&self, id: &str) -> Result<bool> {
+        let snapshot = {
+            let mut tokens = self
+                .tokens
+                .write()
+                .map_err(|_| anyhow!("api token store lock poisoned"))?;
+            let before = tokens.len();
+            tokens.retain(|record| record.id != id);
+            if tokens.len() == before {
+                return Ok(false);
+            }
+            tokens.clone()
+        };
+        self.persist(&snapshot).await?;
+        Ok(true)
+    }
+
+    /// Returns the matching token record, if any.
+    pub fn verify(&self, token: &str) -> Option<ApiTokenRecord> {
+        let digest = token_digest(token.trim());
+        self.tokens
+            .read()
+            .ok()?
+            .iter()
+            .find(|record| record.token_sha256 == digest)
+            .cloned()
+    }
+
+    async fn persist(&self, tokens: &[ApiTokenRecord]) -> Result<()> {
+        let json =
+            serde_json::to_string_pretty(tokens).context("failed to serialize api tokens")?;
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, json)
+            .await
+            .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &self.path)
+            .await
+            .with_context(|| format!("failed to replace {}", self.path.display()))
+    }
+}
+
+fn token_digest(token: &str) -> String {
+    sha256_hex(token.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApiTokenStore;
+    use crate::test_support::TempDir;
+
+    #[tokio::test]
+    async fn creates_verifies_revokes_and_reloads_tokens() {
+        let dir = TempDir::new("api-tokens");
+        let path = dir.join("api-tokens.json");
+        let store = ApiTokenStore::load(&path).await.unwrap();
+        assert!(store.list().is_empty());
+
+        let first = store.create("a1", "Importer", "s3cret").await.unwrap();
+        let second = store.create("b2", "Dashboard", "other").await.unwrap();
+        assert_eq!(first, "mnl_s3cret");
+        assert_eq!(
+            store.verify(&format!(" {first} ")).unwrap().label,
+            "Importer"
+        );
+        assert!(store.verify("mnl_wrong").is_none());
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("s3cret"));
+
+        assert!(store.revoke("a1").await.unwrap());
+        assert!(!store.revoke("a1").await.unwrap());
+        assert!(store.verify(&first).is_none());
+
+        let reloaded = ApiTokenStore::load(&path).await.unwrap();
+        assert!(reloaded.verify(&first).is_none());
+        assert_eq!(reloaded.verify(&second).unwrap().id, "b2");
+        assert_eq!(reloaded.list().len(), 1);
+    }
+}
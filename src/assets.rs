@@ -0,0 +1,108 @@
+//! Cache headers for `/static`. At startup every file under `static_root`
+//! is hashed, and templates link to `/static/app.css?v=<hash>` through
+//! [`url`]. A request carrying the current hash may be cached for a year,
+//! since new content means a new URL; any other request to `/static` is
+//! revalidated each time. Files edited while the server runs keep their old
+//! hash until it restarts.
+
+use std::{collections::HashMap, path::Path, sync::OnceLock};
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::auth::sha256_hex;
+
+/// Hex digits of the content hash kept in the URL.
+const HASH_CHARS: usize = 12;
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+const REVALIDATE: &str = "no-cache";
+
+/// File name under `static_root` (with `/` separators) to its hash.
+static VERSIONS: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Hashes the files under `static_root`. Later calls keep the first result.
+pub fn init(static_root: &Path) {
+    let mut versions = HashMap::new();
+    collect(static_root, "", &mut versions);
+    let _ = VERSIONS.set(versions);
+}
+
+/// `/static/<file>`, with `?v=<hash>` once [`init`] has seen the file.
+pub fn url(file: &str) -> String {
+    match VERSIONS.get().and_then(|versions| versions.get(file)) {
+        Some(version) => format!("/static/{file}?v={version}"),
+        None => format!("/static/{file}"),
+    }
+}
+
+/// Wraps the `/static` file service (paths arrive with that prefix already
+/// stripped).
+pub async fn cache_headers(request: Request, next: Next) -> Response {
+    let current = VERSIONS
+        .get()
+        .and_then(|versions| versions.get(request.uri().path().trim_start_matches('/')))
+        .is_some_and(|version| requested_version(request.uri().query()) == Some(version));
+
+    let mut response = next.run(request).await;
+    if response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED {
+        response.headers_mut().insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(if current { IMMUTABLE } else { REVALIDATE }),
+        );
+    }
+    response
+}
+
+fn requested_version(query: Option<&str>) -> Option<&str> {
+    query?.split('&').find_map(|pair| pair.strip_prefix("v="))
+}
+
+fn collect(dir: &Path, prefix: &str, versions: &mut HashMap<String, String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = entry.path();
+        if path.is_dir() {
+            collect(&path, &format!("{prefix}{name}/"), versions);
+        } else if let Ok(contents) = std::fs::read(&path) {
+            let mut version = sha256_hex(&contents);
+            version.truncate(HASH_CHARS);
+            versions.insert(format!("{prefix}{name}"), version);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{collect, requested_version};
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn hashes_files_by_content_and_reads_the_version_query() {
+        let dir = TempDir::new("assets");
+        std::fs::create_dir_all(dir.join("fonts")).unwrap();
+        std::fs::write(dir.join("app.css"), "body {}").unwrap();
+        std::fs::write(dir.join("fonts/copy.css"), "body {}").unwrap();
+        std::fs::write(dir.join("theme.js"), "// theme").unwrap();
+
+        let mut versions = HashMap::new();
+        collect(&dir, "", &mut versions);
+
+        assert_eq!(versions.len(), 3);
+        assert_eq!(versions["app.css"].len(), 12);
+        assert_eq!(versions["app.css"], versions["fonts/copy.css"]);
+        assert_ne!(versions["app.css"], versions["theme.js"]);
+
+        assert_eq!(requested_version(Some("x=1&v=abc")), Some("abc"));
+        assert_eq!(requested_version(Some("version=abc")), None);
+        assert_eq!(requested_version(None), None);
+    }
+}
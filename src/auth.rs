@@ -0,0 +1,111 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tracing::info;
+
+pub const MIN_ADMIN_PASSWORD_LEN: usize = 12;
+
+pub fn hash_password(password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|err| anyhow!("failed to hash admin password: {err}"))
+}
+
+/// Argon2 verification compares the derived hash in constant time, so a
+/// mismatch leaks nothing about how much of the password was right.
+pub fn verify_password(password: &str, credential: &str) -> bool {
+    PasswordHash::new(credential)
+        .map(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+/// Resolves the admin credential at startup. A credential file written by a
+/// previous rotation wins; otherwise `ADMIN_PASSWORD` bootstraps it, either
+/// as plaintext (hashed here, in memory only) or as an argon2 PHC string.
+pub async fn load_admin_credential(path: &Path, env_password: Option<String>) -> Result<String> {
+    if fs::try_exists(path).await.unwrap_or(false) {
+        let stored = fs::read_to_string(path)
+            .await
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let stored = stored.trim().to_string();
+        PasswordHash::new(&stored)
+            .map_err(|err| anyhow!("invalid credential in {}: {err}", path.display()))?;
+        info!("using rotated admin credential from {}", path.display());
+        return Ok(stored);
+    }
+
+    let password = env_password.ok_or_else(|| {
+        anyhow!("ADMIN_PASSWORD is required. Set it in .env.local (or env) before starting.")
+    })?;
+    if password.trim().is_empty() {
+        return Err(anyhow!("ADMIN_PASSWORD cannot be empty"));
+    }
+
+    let trimmed = password.trim();
+    if trimmed.starts_with("$argon2") {
+        PasswordHash::new(trimmed).map_err(|err| {
+            anyhow!("ADMIN_PASSWORD looks like an argon2 hash but is invalid: {err}")
+        })?;
+        return Ok(trimmed.to_string());
+    }
+    hash_password(&password)
+}
+
+pub async fn store_admin_credential(path: &Path, credential: &str) -> Result<()> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, format!("{credential}\n"))
+        .await
+        .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o600))
+            .await
+            .with_context(|| format!("failed to restrict {}", tmp_path.display()))?;
+    }
+    fs::rename(&tmp_path, path)
+        .await
+        .with_context(|| format!("failed to replace {}", path.display()))
+}
+
+pub fn sha256_hex(input: &[u8]) -> String {
+    Sha256::digest(input)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash_password, verify_password};
+
+    #[test]
+    fn verifies_only_the_hashed_password() {
+        let hash = hash_password("correct horse battery").unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(verify_password("correct horse battery", &hash));
+        assert!(!verify_password("correct horse battery!", &hash));
+        assert!(!verify_password("correct horse battery", "not-a-hash"));
+    }
+}
@@ -0,0 +1,163 @@
+//! Clean-background copies of a mineral's primary photo, for minerals whose
+//! record sets `clean_background`. The copy is written as `image_clean.webp`
+//! next to the photo and shown on catalog cards and certificates. The
+//! background is removed by a local command when `BACKGROUND_REMOVAL_BIN` is
+//! set (for example a wrapper around an ONNX segmentation model such as
+//! rembg), otherwise through the LLM provider's image-editing API.
+
+use std::{path::Path, process::Stdio, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use tokio::{fs, io::AsyncWriteExt, process::Command};
+use tracing::{info, warn};
+
+use crate::{
+    content_type_from_ext, images, llm::LlmImage, models::MineralDiskRecord, read_english_record,
+    refresh_mineral, AppState,
+};
+
+pub const CLEAN_FILE: &str = "image_clean.webp";
+
+/// A stuck model must not keep a worker busy forever.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Whether saving `updated` over `previous` calls for a new
+/// `image_clean.webp`, or for removing it.
+pub fn needs_refresh(previous: &MineralDiskRecord, updated: &MineralDiskRecord) -> bool {
+    let primary = |record: &MineralDiskRecord| {
+        record
+            .images
+            .iter()
+            .find(|image| image.primary)
+            .map(|image| image.file.clone())
+    };
+    previous.clean_background != updated.clean_background
+        || (updated.clean_background && primary(previous) != primary(updated))
+}
+
+/// Brings `image_clean.webp` in line with the mineral's record in the
+/// background, then refreshes the cached catalogs. Failures are logged;
+/// the catalog keeps showing the regular photo.
+pub fn spawn_refresh(state: AppState, folder_name: String) {
+    tokio::spawn(async move {
+        let folder_path = state.data_root.join("minerals").join(&folder_name);
+        match write_clean_image(&state, &folder_path).await {
+            Ok(()) => {
+                if let Err(err) = refresh_mineral(&state, &folder_name).await {
+                    warn!("catalog refresh after background removal failed: {err:#}");
+                }
+            }
+            Err(err) => warn!("background removal skipped for {folder_name}: {err:#}"),
+        }
+    });
+}
+
+/// Writes `image_clean.webp` from the primary photo, or removes it when the
+/// record no longer asks for one.
+async fn write_clean_image(state: &AppState, folder_path: &Path) -> Result<()> {
+    let clean_path = folder_path.join(CLEAN_FILE);
+    let record = read_english_record(folder_path)
+        .await
+        .map_err(|err| anyhow!("{err}"))?;
+    let primary = record.images.iter().find(|image| image.primary);
+    let Some(primary) = primary.filter(|_| record.clean_background) else {
+        match fs::remove_file(&clean_path).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                return Err(err)
+                    .with_context(|| format!("failed to remove {}", clean_path.display()))
+            }
+            _ => return Ok(()),
+        }
+    };
+
+    let photo_path = folder_path.join(&primary.file);
+    let photo = fs::read(&photo_path)
+        .await
+        .with_context(|| format!("failed to read {}", photo_path.display()))?;
+    let ext = primary.file.rsplit_once('.').map_or("", |(_, ext)| ext);
+    let cutout = match &state.background_removal_bin {
+        Some(command) => remove_with_command(command, &photo).await?,
+        None => {
+            let provider = state
+                .llm
+                .as_deref()
+                .ok_or_else(|| anyhow!("no BACKGROUND_REMOVAL_BIN and no LLM provider"))?;
+            provider
+                .remove_background(&LlmImage {
+                    mime: content_type_from_ext(ext),
+                    bytes: &photo,
+                })
+                .await?
+        }
+    };
+    let clean = tokio::task::spawn_blocking(move || images::encode_clean(&cutout))
+        .await
+        .context("background removal task failed")??;
+    fs::write(&clean_path, clean)
+        .await
+        .with_context(|| format!("failed to write {}", clean_path.display()))?;
+    info!("wrote {}", clean_path.display());
+    Ok(())
+}
+
+/// Runs `command` (a program and its arguments, split on whitespace) with
+/// the photo on stdin and reads the cut-out image from stdout.
+async fn remove_with_command(command: &str, photo: &[u8]) -> Result<Vec<u8>> {
+    let mut words = command.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| anyhow!("BACKGROUND_REMOVAL_BIN is empty"))?;
+    let mut child = Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("failed to start {program}"))?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = photo.to_vec();
+    // Written alongside the read, as in `images::convert_to_jpeg`.
+    let writer = tokio::spawn(async move {
+        let _ = stdin.write_all(&input).await;
+    });
+    let output = tokio::time::timeout(COMMAND_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| anyhow!("{program} timed out after {}s", COMMAND_TIMEOUT.as_secs()))?
+        .with_context(|| format!("{program} failed"))?;
+    let _ = writer.await;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{program} exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, ImageFormat, RgbaImage};
+
+    use super::remove_with_command;
+    use crate::images::encode_clean;
+
+    #[tokio::test]
+    async fn pipes_photos_through_the_local_command() {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgba8(RgbaImage::new(30, 10))
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+
+        let cutout = remove_with_command("cat", &png).await.unwrap();
+        let clean = image::load_from_memory(&encode_clean(&cutout).unwrap()).unwrap();
+        assert_eq!((clean.width(), clean.height()), (30, 10));
+        assert!(clean.color().has_alpha());
+
+        assert!(remove_with_command("false", &png).await.is_err());
+    }
+}
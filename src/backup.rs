@@ -0,0 +1,466 @@
+//! Snapshots of the data directory. `minerals backup <path>` and
+//! `POST /admin/backup` write a zstd-compressed tar of every file under
+//! `DATA_ROOT` (below `data/` in the archive) followed by `MANIFEST.json`,
+//! which lists each file's size and SHA-256. `minerals restore <path>`
+//! unpacks a snapshot into a staging folder next to `DATA_ROOT`, checks it
+//! against the manifest, and only then swaps it in; the replaced directory
+//! is kept beside it.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use axum::{body::Body, extract::State, http::HeaderMap, response::Response, Form};
+use chrono::{DateTime, Utc};
+use futures_util::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio_util::io::{ReaderStream, SyncIoBridge};
+use tracing::{info, warn};
+
+use crate::{
+    auth::sha256_hex,
+    csrf::{self, CsrfForm},
+    export::attachment,
+    AppError, AppState,
+};
+
+pub const MANIFEST_FILE: &str = "MANIFEST.json";
+const ARCHIVE_DATA_DIR: &str = "data";
+const MANIFEST_FORMAT: u32 = 1;
+/// Rebuilt on demand, so not worth carrying around.
+const SKIPPED_DIRS: &[&str] = &["cache"];
+const ZSTD_LEVEL: i32 = 3;
+/// Compressed bytes buffered between the archive writer and the response.
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: u32,
+    pub created_utc: String,
+    pub files: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Relative to `DATA_ROOT`, with `/` separators.
+    pub path: String,
+    pub size: u64,
+    /// Lowercase hex.
+    pub sha256: String,
+}
+
+impl Manifest {
+    pub fn total_bytes(&self) -> u64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+}
+
+/// What `restore` did.
+#[derive(Debug)]
+pub struct Restored {
+    pub manifest: Manifest,
+    /// Where the replaced data directory was moved; `None` when there was
+    /// none.
+    pub previous: Option<PathBuf>,
+}
+
+/// `POST /admin/backup`: the snapshot as a download. The archive is written
+/// on the blocking pool into a pipe and streamed as it is compressed, so a
+/// large data directory is never held in memory. A failed snapshot aborts
+/// the response instead of ending it like a complete archive.
+pub async fn download_backup(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(form): Form<CsrfForm>,
+) -> Result<Response, AppError> {
+    csrf::verify(&state, &headers, &form.csrf_token)?;
+    Ok(attachment(
+        snapshot_body(state.data_root.to_path_buf()),
+        "application/zstd",
+        &snapshot_file_name(Utc::now()),
+    ))
+}
+
+/// A snapshot of `data_root` as a response body, written on the blocking
+/// pool while it is sent.
+fn snapshot_body(data_root: PathBuf) -> Body {
+    let (reader, writer) = tokio::io::duplex(PIPE_CAPACITY);
+    let writer = SyncIoBridge::new(writer);
+    let snapshot = tokio::task::spawn_blocking(move || write_snapshot(&data_root, writer));
+    let outcome = stream::once(async move {
+        let manifest = snapshot.await.map_err(io::Error::other)?.map_err(|err| {
+            warn!("admin backup failed: {err:#}");
+            io::Error::other(err)
+        })?;
+        info!(
+            "admin backup: {} files, {} bytes",
+            manifest.files.len(),
+            manifest.total_bytes()
+        );
+        io::Result::Ok(None)
+    });
+    let body = ReaderStream::new(reader)
+        .map(|chunk| chunk.map(Some))
+        .chain(outcome)
+        .filter_map(|chunk| async move { chunk.transpose() });
+    Body::from_stream(body)
+}
+
+/// Writes a snapshot of `data_root` to `target`, or into it when `target`
+/// is a directory. Returns the file written and its manifest.
+pub fn create(data_root: &Path, target: &Path) -> Result<(PathBuf, Manifest)> {
+    let path = if target.is_dir() {
+        target.join(snapshot_file_name(Utc::now()))
+    } else {
+        target.to_path_buf()
+    };
+    if path.starts_with(data_root) {
+        bail!(
+            "{} is inside the data directory it would back up",
+            path.display()
+        );
+    }
+    let tmp_path = path.with_extension("zst.tmp");
+    let file = File::create(&tmp_path)
+        .with_context(|| format!("failed to create {}", tmp_path.display()))?;
+    let result = write_snapshot(data_root, file);
+    let manifest = match result {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(err);
+        }
+    };
+    fs::rename(&tmp_path, &path)
+        .with_context(|| format!("failed to replace {}", path.display()))?;
+    Ok((path, manifest))
+}
+
+/// Streams every file under `data_root` into a tar.zst on `out`, then the
+/// manifest of what was written.
+pub fn write_snapshot(data_root: &Path, out: impl Write) -> Result<Manifest> {
+    let mut files = Vec::new();
+    collect_files(data_root, data_root, &mut files)?;
+
+    let mut builder = tar::Builder::new(
+        zstd::Encoder::new(out, ZSTD_LEVEL).context("failed to start compression")?,
+    );
+    let mut entries = Vec::with_capacity(files.len());
+    for (relative, path) in files {
+        // Read once, so the digest describes exactly the bytes archived.
+        let body = fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        append(
+            &mut builder,
+            &format!("{ARCHIVE_DATA_DIR}/{relative}"),
+            &body,
+        )?;
+        entries.push(ManifestEntry {
+            path: relative,
+            size: body.len() as u64,
+            sha256: sha256_hex(&body),
+        });
+    }
+
+    let manifest = Manifest {
+        format: MANIFEST_FORMAT,
+        created_utc: Utc::now().to_rfc3339(),
+        files: entries,
+    };
+    let json = serde_json::to_vec_pretty(&manifest).context("failed to serialize manifest")?;
+    append(&mut builder, MANIFEST_FILE, &json)?;
+    builder
+        .into_inner()
+        .context("failed to finish the archive")?
+        .finish()
+        .context("failed to finish compression")?;
+    Ok(manifest)
+}
+
+/// Replaces `data_root` with the snapshot at `archive`. Nothing is touched
+/// unless every file matches the manifest. Stop the server first.
+pub fn restore(data_root: &Path, archive: &Path) -> Result<Restored> {
+    let file =
+        File::open(archive).with_context(|| format!("failed to open {}", archive.display()))?;
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    let staging = sibling(data_root, &format!("restore-{stamp}"))?;
+    if staging.exists() {
+        bail!("{} already exists", staging.display());
+    }
+
+    let manifest = match extract_verified(file, &staging) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(err.context(format!("{} was not restored", archive.display())));
+        }
+    };
+
+    let previous = if data_root.exists() {
+        let previous = sibling(data_root, &format!("before-restore-{stamp}"))?;
+        fs::rename(data_root, &previous).with_context(|| {
+            format!(
+                "failed to move {} aside; the verified snapshot is in {}",
+                data_root.display(),
+                staging.display()
+            )
+        })?;
+        Some(previous)
+    } else {
+        None
+    };
+    fs::rename(&staging, data_root).with_context(|| {
+        format!(
+            "failed to move {} into place at {}",
+            staging.display(),
+            data_root.display()
+        )
+    })?;
+    Ok(Restored { manifest, previous })
+}
+
+/// Unpacks `archive` into `dest` (which must not exist yet) and checks the
+/// files against the manifest: same set, same sizes, same digests.
+fn extract_verified(archive: impl Read, dest: &Path) -> Result<Manifest> {
+    fs::create_dir_all(dest).with_context(|| format!("failed to create {}", dest.display()))?;
+    let mut archive = tar::Archive::new(zstd::Decoder::new(archive).context("not a zstd stream")?);
+    let mut manifest = None;
+    let mut extracted = BTreeMap::new();
+    for entry in archive.entries().context("failed to read the archive")? {
+        let mut entry = entry.context("failed to read the archive")?;
+        let name = entry
+            .path()
+            .context("archive entry has an unreadable path")?
+            .to_string_lossy()
+            .into_owned();
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let mut body = Vec::new();
+        entry
+            .read_to_end(&mut body)
+            .with_context(|| format!("failed to read {name} from the archive"))?;
+
+        if name == MANIFEST_FILE {
+            manifest = Some(
+                serde_json::from_slice::<Manifest>(&body)
+                    .with_context(|| format!("{MANIFEST_FILE} is not a backup manifest"))?,
+            );
+            continue;
+        }
+        let relative = name
+            .strip_prefix(&format!("{ARCHIVE_DATA_DIR}/"))
+            .filter(|relative| is_safe_relative(relative))
+            .ok_or_else(|| anyhow!("unexpected archive entry '{name}'"))?;
+        let path = dest.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        fs::write(&path, &body).with_context(|| format!("failed to write {}", path.display()))?;
+        if extracted
+            .insert(relative.to_string(), (body.len() as u64, sha256_hex(&body)))
+            .is_some()
+        {
+            bail!("'{relative}' appears twice in the archive");
+        }
+    }
+
+    let manifest = manifest.ok_or_else(|| anyhow!("the archive has no {MANIFEST_FILE}"))?;
+    if manifest.format != MANIFEST_FORMAT {
+        bail!("unsupported manifest format {}", manifest.format);
+    }
+    let mut problems = Vec::new();
+    for file in &manifest.files {
+        match extracted.remove(&file.path) {
+            None => problems.push(format!("{}: missing", file.path)),
+            Some((size, _)) if size != file.size => problems.push(format!(
+                "{}: {size} bytes, manifest says {}",
+                file.path, file.size
+            )),
+            Some((_, sha256)) if sha256 != file.sha256 => {
+                problems.push(format!("{}: checksum mismatch", file.path))
+            }
+            Some(_) => {}
+        }
+    }
+    problems.extend(
+        extracted
+            .into_keys()
+            .map(|path| format!("{path}: not in the manifest")),
+    );
+    if !problems.is_empty() {
+        bail!("integrity check failed:\n  - {}", problems.join("\n  - "));
+    }
+    Ok(manifest)
+}
+
+fn append<W: Write>(builder: &mut tar::Builder<W>, name: &str, body: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(body.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp().unsigned_abs());
+    builder
+        .append_data(&mut header, name, body)
+        .with_context(|| format!("failed to add {name} to the archive"))
+}
+
+/// Regular files below `dir`, sorted, as `(path relative to root, path)`.
+/// Symlinks are not followed.
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) -> Result<()> {
+    let mut entries = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .collect::<io::Result<Vec<_>>>()
+            .with_context(|| format!("failed to read {}", dir.display()))?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound && dir == root => return Ok(()),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}", dir.display())),
+    };
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let file_type = entry
+            .file_type()
+            .with_context(|| format!("failed to inspect {}", path.display()))?;
+        let relative = path
+            .strip_prefix(root)
+            .expect("walked from root")
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if file_type.is_dir() {
+            if dir == root && SKIPPED_DIRS.contains(&relative.as_str()) {
+                continue;
+            }
+            collect_files(root, &path, files)?;
+        } else if file_type.is_file() {
+            files.push((relative, path));
+        }
+    }
+    Ok(())
+}
+
+/// Only plain names: no `..`, no absolute paths, nothing that could land
+/// outside the staging folder.
+fn is_safe_relative(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// `<data_root>.<suffix>` in the same parent folder, so the final renames
+/// stay on one file system.
+fn sibling(data_root: &Path, suffix: &str) -> Result<PathBuf> {
+    let name = data_root
+        .file_name()
+        .ok_or_else(|| anyhow!("{} has no folder name", data_root.display()))?;
+    Ok(data_root.with_file_name(format!("{}.{suffix}", name.to_string_lossy())))
+}
+
+fn snapshot_file_name(created: DateTime<Utc>) -> String {
+    format!(
+        "minerals-backup-{}.tar.zst",
+        created.format("%Y%m%dT%H%M%SZ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{append, extract_verified, restore, snapshot_body, write_snapshot, MANIFEST_FILE};
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn restores_a_verified_snapshot_and_refuses_a_tampered_one() {
+        let root = TempDir::new("backup");
+        let data = root.join("data");
+        fs::create_dir_all(data.join("minerals/mineral.oxide.0x1")).unwrap();
+        fs::create_dir_all(data.join("cache/translations")).unwrap();
+        fs::write(data.join("minerals/mineral.oxide.0x1/metadata.json"), "{}").unwrap();
+        fs::write(data.join("accounts.json"), "[]").unwrap();
+        fs::write(data.join("cache/translations/x.json"), "skip").unwrap();
+
+        let archive = root.join("snapshot.tar.zst");
+        let manifest = write_snapshot(&data, fs::File::create(&archive).unwrap()).unwrap();
+        let paths = manifest
+            .files
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            ["accounts.json", "minerals/mineral.oxide.0x1/metadata.json"]
+        );
+
+        fs::write(data.join("accounts.json"), "[\"changed\"]").unwrap();
+        let restored = restore(&data, &archive).unwrap();
+        assert_eq!(
+            fs::read_to_string(data.join("accounts.json")).unwrap(),
+            "[]"
+        );
+        let previous = restored.previous.unwrap();
+        assert!(previous.join("cache/translations/x.json").exists());
+
+        // Same manifest, one file altered after the fact.
+        let tampered = root.join("tampered.tar.zst");
+        let mut builder =
+            tar::Builder::new(zstd::Encoder::new(fs::File::create(&tampered).unwrap(), 3).unwrap());
+        append(&mut builder, "data/accounts.json", b"[\"evil\"]").unwrap();
+        append(
+            &mut builder,
+            "data/minerals/mineral.oxide.0x1/metadata.json",
+            b"{}",
+        )
+        .unwrap();
+        append(
+            &mut builder,
+            MANIFEST_FILE,
+            &serde_json::to_vec(&manifest).unwrap(),
+        )
+        .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+        let message = format!("{:#}", restore(&data, &tampered).unwrap_err());
+        assert!(message.contains("accounts.json"), "{message}");
+        assert_eq!(
+            fs::read_to_string(data.join("accounts.json")).unwrap(),
+            "[]"
+        );
+    }
+
+    #[tokio::test]
+    async fn streams_a_snapshot_larger_than_the_pipe() {
+        let root = TempDir::new("backup-stream");
+        let data = root.join("data");
+        fs::create_dir_all(data.join("minerals/mineral.oxide.0x1")).unwrap();
+        // Noise, so the compressed archive still outgrows the pipe.
+        let mut seed = 0x2545_f491_u32;
+        let photo = (0..512 * 1024)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                seed as u8
+            })
+            .collect::<Vec<_>>();
+        fs::write(data.join("minerals/mineral.oxide.0x1/1.png"), &photo).unwrap();
+
+        let archive = axum::body::to_bytes(snapshot_body(data.clone()), usize::MAX)
+            .await
+            .unwrap();
+        let manifest = extract_verified(&archive[..], &root.join("unpacked")).unwrap();
+        let unpacked = fs::read(root.join("unpacked/minerals/mineral.oxide.0x1/1.png"));
+        // A data root that cannot be read fails the body, not just ends it.
+        let unreadable = data.join("minerals/mineral.oxide.0x1/1.png");
+        let failed = axum::body::to_bytes(snapshot_body(unreadable), usize::MAX).await;
+
+        assert!(archive.len() > 64 * 1024);
+        assert_eq!(manifest.files.len(), 1);
+        assert_eq!(unpacked.unwrap(), photo);
+        assert!(failed.is_err());
+    }
+}
@@ -0,0 +1,289 @@
+//! Report dossiers for a selection of minerals, e.g. everything in one
+//! shipment. `POST /api/reports/batch` renders one report per slug with a
+//! shared request and returns every `report.pdf` and `report.sig` in a
+//! single ZIP, plus a `manifest.json` listing each mineral's run or error.
+//! Reports are generated one after another, like the job queue, so a batch
+//! never runs several PDF tools at once. With `"queue": true` the reports
+//! are handed to the job queue instead and the response lists one job per
+//! mineral to poll.
+
+use std::{
+    io::{Cursor, Write},
+    ops::RangeInclusive,
+};
+
+use anyhow::{anyhow, Context, Result};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tracing::{info, warn};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::{
+    agent::run_agentic_chain, api::require_api_token, crypto, export::attachment,
+    get_mineral_with_related, jobs::PdfJob, load_attachments, models::ReportRequest, narrative,
+    presets, report_runs::REPORTS_DIR, resolve_pdf_backend, resolve_report_language,
+    resolve_report_units, signing::SIGNATURE_FILE, AppError, AppState,
+};
+
+/// Keeps one request from occupying the PDF tool for too long.
+pub const BATCH_MAX_MINERALS: usize = 25;
+
+const ZIP_CONTENT_TYPE: &str = "application/zip";
+
+#[derive(Debug, Deserialize)]
+pub struct BatchReportRequest {
+    slugs: Vec<String>,
+    #[serde(flatten)]
+    report: ReportRequest,
+    #[serde(default)]
+    backend: Option<String>,
+    /// Submit to the job queue and answer right away instead of waiting
+    /// for the ZIP.
+    #[serde(default)]
+    queue: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchJobsResponse {
+    jobs: Vec<PdfJob>,
+}
+
+/// One line of `manifest.json`.
+#[derive(Debug, Serialize)]
+struct BatchEntry {
+    slug: String,
+    name: String,
+    run_id: Option<String>,
+    /// Paths inside the archive.
+    files: Vec<String>,
+    error: Option<String>,
+}
+
+pub async fn batch_reports(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<BatchReportRequest>,
+) -> Result<Response, AppError> {
+    require_api_token(&state, &headers)?;
+    let slugs =
+        requested_slugs(&request.slugs, 1..=BATCH_MAX_MINERALS).map_err(AppError::BadRequest)?;
+    let language = resolve_report_language(&state, &headers, &request.report)?;
+    let units = resolve_report_units(&headers, &request.report.units)?;
+    let backend = resolve_pdf_backend(&state, request.backend.as_deref())?;
+    let layout = state
+        .report_templates
+        .load(&request.report.template, backend)
+        .await
+        .map_err(AppError::BadRequest)?;
+    presets::check(&state, &request.report).await?;
+    // Resolve every slug first so a typo fails before any PDF is rendered.
+    let mut minerals = Vec::new();
+    for slug in &slugs {
+        minerals.push(get_mineral_with_related(&state, language, slug).await?);
+    }
+
+    let mut reports = Vec::new();
+    for (mineral, related) in minerals {
+        let attachments = load_attachments(&state, &mineral).await?;
+        let mut report = run_agentic_chain(
+            &mineral,
+            &request.report,
+            related,
+            attachments,
+            state.classification,
+            language,
+        );
+        report.units = units;
+        narrative::apply(&state, &request.report, &mut report, language).await;
+        reports.push(report);
+    }
+
+    if request.queue {
+        let mut jobs = Vec::new();
+        for report in reports {
+            let id = crypto::random_hex(12)?;
+            jobs.push(state.pdf_jobs.submit(
+                id,
+                report,
+                layout.clone(),
+                language,
+                backend,
+                None,
+            )?);
+        }
+        info!("queued batch of {} reports", jobs.len());
+        return Ok((StatusCode::ACCEPTED, Json(BatchJobsResponse { jobs })).into_response());
+    }
+
+    let mut entries = Vec::new();
+    let mut files = Vec::new();
+    for report in reports {
+        let mineral = &report.mineral;
+        let mut entry = BatchEntry {
+            slug: mineral.slug.clone(),
+            name: mineral.common_name.clone(),
+            run_id: None,
+            files: Vec::new(),
+            error: None,
+        };
+        match state
+            .pdf_generator
+            .generate_pdf(&report, &layout, language, backend)
+            .await
+        {
+            Ok(artifacts) => {
+                let run_dir = state
+                    .data_root
+                    .join("minerals")
+                    .join(&mineral.folder_name)
+                    .join(REPORTS_DIR)
+                    .join(&artifacts.run_id);
+                for name in ["report.pdf", SIGNATURE_FILE] {
+                    let path = run_dir.join(name);
+                    let contents = fs::read(&path)
+                        .await
+                        .with_context(|| format!("failed to read {}", path.display()))?;
+                    let archive_path = format!("{}/{name}", mineral.slug);
+                    entry.files.push(archive_path.clone());
+                    files.push((archive_path, contents));
+                }
+                entry.run_id = Some(artifacts.run_id);
+            }
+            Err(err) => {
+                warn!("batch report failed for {}: {err:#}", mineral.slug);
+                entry.error = Some(err.to_string());
+            }
+        }
+        entries.push(entry);
+    }
+
+    if files.is_empty() {
+        let first_error = entries
+            .iter()
+            .find_map(|entry| entry.error.clone())
+            .unwrap_or_default();
+        return Err(AppError::Internal(anyhow!(
+            "no report in the batch could be generated: {first_error}"
+        )));
+    }
+    info!(
+        "generated batch of {} reports ({} failed)",
+        entries.len(),
+        entries.iter().filter(|entry| entry.error.is_some()).count()
+    );
+    let archive = write_archive(&entries, &files)?;
+    Ok(attachment(
+        archive,
+        ZIP_CONTENT_TYPE,
+        &format!("reports-{}.zip", Utc::now().format("%Y-%m-%dT%H-%M-%S")),
+    ))
+}
+
+/// Trimmed, de-duplicated slugs in request order, as many as `allowed`.
+pub fn requested_slugs(
+    slugs: &[String],
+    allowed: RangeInclusive<usize>,
+) -> Result<Vec<String>, String> {
+    let mut unique = Vec::new();
+    for slug in slugs.iter().map(|slug| slug.trim()) {
+        if !slug.is_empty() && !unique.iter().any(|seen: &String| seen == slug) {
+            unique.push(slug.to_string());
+        }
+    }
+    if !allowed.contains(&unique.len()) {
+        return Err(format!(
+            "'slugs' lists {} distinct minerals; expected {} to {}",
+            unique.len(),
+            allowed.start(),
+            allowed.end()
+        ));
+    }
+    Ok(unique)
+}
+
+fn write_archive(entries: &[BatchEntry], files: &[(String, Vec<u8>)]) -> Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+    let manifest = serde_json::to_vec_pretty(entries).context("failed to serialize manifest")?;
+    for (path, contents) in std::iter::once(("manifest.json", manifest.as_slice())).chain(
+        files
+            .iter()
+            .map(|(path, contents)| (path.as_str(), contents.as_slice())),
+    ) {
+        zip.start_file(path, options)
+            .with_context(|| format!("failed to add {path} to the archive"))?;
+        zip.write_all(contents)
+            .with_context(|| format!("failed to write {path} to the archive"))?;
+    }
+    Ok(zip
+        .finish()
+        .context("failed to finish the archive")?
+        .into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use super::{requested_slugs, write_archive, BatchEntry, BATCH_MAX_MINERALS};
+
+    #[test]
+    fn limits_slugs_and_archives_reports_with_a_manifest() {
+        let slugs = [
+            " mineral.silicates.0x1 ",
+            "mineral.oxides.0x2",
+            "mineral.silicates.0x1",
+            "",
+        ]
+        .map(str::to_string);
+        assert_eq!(
+            requested_slugs(&slugs, 1..=BATCH_MAX_MINERALS).unwrap(),
+            ["mineral.silicates.0x1", "mineral.oxides.0x2"]
+        );
+        assert!(requested_slugs(&[" ".to_string()], 1..=BATCH_MAX_MINERALS).is_err());
+        let too_many = (0..=BATCH_MAX_MINERALS)
+            .map(|n| format!("mineral.x.0x{n}"))
+            .collect::<Vec<_>>();
+        assert!(requested_slugs(&too_many, 1..=BATCH_MAX_MINERALS).is_err());
+
+        let entries = vec![
+            BatchEntry {
+                slug: "mineral.silicates.0x1".to_string(),
+                name: "Quartz".to_string(),
+                run_id: Some("2024-06-01T12-00-00".to_string()),
+                files: vec!["mineral.silicates.0x1/report.pdf".to_string()],
+                error: None,
+            },
+            BatchEntry {
+                slug: "mineral.oxides.0x2".to_string(),
+                name: "Hematite".to_string(),
+                run_id: None,
+                files: Vec::new(),
+                error: Some("latexmk failed".to_string()),
+            },
+        ];
+        let files = vec![(
+            "mineral.silicates.0x1/report.pdf".to_string(),
+            b"%PDF-1.7".to_vec(),
+        )];
+        let archive = write_archive(&entries, &files).unwrap();
+
+        let mut zip = zip::ZipArchive::new(Cursor::new(archive)).unwrap();
+        let mut pdf = String::new();
+        zip.by_name("mineral.silicates.0x1/report.pdf")
+            .unwrap()
+            .read_to_string(&mut pdf)
+            .unwrap();
+        assert_eq!(pdf, "%PDF-1.7");
+        let manifest: serde_json::Value =
+            serde_json::from_reader(zip.by_name("manifest.json").unwrap()).unwrap();
+        assert_eq!(manifest[1]["error"], "latexmk failed");
+    }
+}
@@ -0,0 +1,919 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::Mineral,
+    permalinks,
+    search::{SearchIndex, SuggestIndex, Suggestion},
+};
+
+pub const DEFAULT_PAGE_SIZE: usize = 24;
+pub const MAX_PAGE_SIZE: usize = 200;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortKey {
+    #[default]
+    Name,
+    Hardness,
+    Density,
+    Family,
+}
+
+impl SortKey {
+    pub fn all() -> &'static [SortKey] {
+        &[
+            SortKey::Name,
+            SortKey::Hardness,
+            SortKey::Density,
+            SortKey::Family,
+        ]
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Hardness => "hardness",
+            SortKey::Density => "density",
+            SortKey::Family => "family",
+        }
+    }
+
+    pub fn from_param(value: &str) -> Option<Self> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|key| key.as_str() == value.trim().to_ascii_lowercase())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CatalogPage {
+    pub minerals: Vec<Mineral>,
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+    pub total_pages: usize,
+}
+
+/// Narrows a catalog listing. Unset fields match every mineral.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CatalogQuery {
+    /// Case-insensitive substring, so `silicate` matches `Silicates`.
+    pub family: Option<String>,
+    pub hardness_min: Option<f32>,
+    pub hardness_max: Option<f32>,
+    /// Case-insensitive, whole value.
+    pub crystal_system: Option<String>,
+    pub tag: Option<String>,
+}
+
+impl CatalogQuery {
+    pub fn matches(&self, mineral: &Mineral) -> bool {
+        let family = mineral.mineral_family.to_lowercase();
+        self.family
+            .as_deref()
+            .is_none_or(|wanted| family.contains(&wanted.to_lowercase()))
+            && self
+                .hardness_min
+                .is_none_or(|min| mineral.hardness_mohs >= min)
+            && self
+                .hardness_max
+                .is_none_or(|max| mineral.hardness_mohs <= max)
+            && self
+                .crystal_system
+                .as_deref()
+                .is_none_or(|wanted| mineral.crystal_system.trim().eq_ignore_ascii_case(wanted))
+            && self
+                .tag
+                .as_deref()
+                .is_none_or(|tag| mineral.tags.iter().any(|own| own == tag))
+    }
+}
+
+/// The filter form's inputs as submitted (`?family=silicate&hardness_min=6`),
+/// kept as text so the form can show them back unchanged.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CatalogFilters {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub family: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub hardness_min: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub hardness_max: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub crystal_system: String,
+}
+
+impl CatalogFilters {
+    pub fn to_query(&self) -> Result<CatalogQuery, String> {
+        let text = |value: &str| {
+            let value = value.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+        let hardness = |value: &str, key: &str| match value.trim() {
+            "" => Ok(None),
+            raw => raw
+                .parse::<f32>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .map(Some)
+                .ok_or_else(|| format!("'{key}' must be a number")),
+        };
+
+        Ok(CatalogQuery {
+            family: text(&self.family),
+            hardness_min: hardness(&self.hardness_min, "hardness_min")?,
+            hardness_max: hardness(&self.hardness_max, "hardness_max")?,
+            crystal_system: text(&self.crystal_system),
+            tag: None,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.to_query()
+            .is_ok_and(|query| query == CatalogQuery::default())
+    }
+
+    /// `&family=...` pairs for pager links; empty when no filter is set.
+    pub fn query_string(&self) -> String {
+        match serde_urlencoded::to_string(self) {
+            Ok(pairs) if !pairs.is_empty() => format!("&{pairs}"),
+            _ => String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MineralCatalog {
+    pub by_slug: HashMap<String, Mineral>,
+    /// Former slugs, and the folder names of minerals with a permalink,
+    /// mapped to the current slug.
+    pub aliases: HashMap<String, String>,
+    /// `/m/<name>` slugs, see [`permalinks::name_slug`], mapped to the
+    /// current slug.
+    pub by_name_slug: HashMap<String, String>,
+    /// Folder names mapped to the slug of the mineral loaded from them.
+    by_folder_name: HashMap<String, String>,
+    pub ordered: Vec<Mineral>,
+    /// Every tag in use, alphabetical.
+    pub tags: Vec<TagCount>,
+    /// Distinct families and crystal systems, for the filter form.
+    pub families: Vec<String>,
+    pub crystal_systems: Vec<String>,
+    search_index: SearchIndex,
+    suggest_index: SuggestIndex,
+    /// Per lowercased family and crystal system: the spelling listed and
+    /// how many minerals use it.
+    family_counts: BTreeMap<String, (String, usize)>,
+    crystal_system_counts: BTreeMap<String, (String, usize)>,
+    /// Minerals per `/m/<name>` base slug, by folder name, with their slug
+    /// and the numbered name they were given.
+    namesakes: HashMap<String, BTreeMap<String, (String, String)>>,
+    /// Folders of the minerals that were private, unlisted, or scheduled
+    /// when added.
+    unlisted: HashSet<String>,
+    /// What visitors without an admin session see, when some minerals are
+    /// private or unlisted; see [`MineralCatalog::public_view`].
+    public: Option<Arc<MineralCatalog>>,
+}
+
+/// `/m/<name>` slugs given up and handed out by one change.
+#[derive(Debug, Default)]
+struct NameChanges {
+    removed: Vec<String>,
+    added: Vec<(String, String)>,
+}
+
+impl MineralCatalog {
+    pub fn new(minerals: Vec<Mineral>) -> Self {
+        let mut catalog = Self {
+            ordered: minerals,
+            ..Self::default()
+        };
+        let ordered = std::mem::take(&mut catalog.ordered);
+        for mineral in &ordered {
+            catalog.insert_lookups(mineral);
+            catalog.insert_listing(mineral);
+            if !mineral.is_listed() {
+                catalog.unlisted.insert(mineral.folder_name.clone());
+            }
+        }
+        let mut by_folder = ordered.iter().collect::<Vec<_>>();
+        by_folder.sort_by(|a, b| a.folder_name.cmp(&b.folder_name));
+        for mineral in by_folder {
+            catalog.join_namesakes(mineral, &mut NameChanges::default());
+        }
+        catalog.ordered = ordered;
+        if !catalog.unlisted.is_empty() {
+            catalog.public = Some(Arc::new(catalog.public_subset()));
+        }
+        catalog
+    }
+
+    /// Adds the mineral in `mineral.folder_name`, or replaces the one
+    /// loaded from that folder before, keeping the catalog sorted by name.
+    /// Only that mineral's entries in the lookups, search indexes, tags,
+    /// and filter options are touched, plus the `/m/<name>` numbering of
+    /// its namesakes; the other minerals are neither cloned nor reindexed.
+    pub fn upsert(&mut self, mineral: Mineral) {
+        let mut names = NameChanges::default();
+        let old = self.take(&mineral.folder_name, &mut names);
+        self.insert_lookups(&mineral);
+        self.insert_listing(&mineral);
+        self.join_namesakes(&mineral, &mut names);
+        if !mineral.is_listed() {
+            self.unlisted.insert(mineral.folder_name.clone());
+        }
+        let position = self
+            .ordered
+            .partition_point(|existing| existing.common_name <= mineral.common_name);
+        self.ordered.insert(position, mineral);
+        self.sync_public(old.as_ref(), Some(&self.ordered[position].clone()), names);
+    }
+
+    /// Drops the mineral loaded from `folder_name`, if any.
+    pub fn remove(&mut self, folder_name: &str) -> Option<Mineral> {
+        let mut names = NameChanges::default();
+        let removed = self.take(folder_name, &mut names)?;
+        self.sync_public(Some(&removed), None, names);
+        Some(removed)
+    }
+
+    /// Removes the mineral loaded from `folder_name` from every index of
+    /// this catalog, renumbering its namesakes.
+    fn take(&mut self, folder_name: &str, names: &mut NameChanges) -> Option<Mineral> {
+        let position = self
+            .ordered
+            .iter()
+            .position(|mineral| mineral.folder_name == folder_name)?;
+        let removed = self.ordered.remove(position);
+        self.remove_lookups(&removed);
+        self.remove_listing(&removed);
+        self.leave_namesakes(&removed, names);
+        self.unlisted.remove(folder_name);
+        Some(removed)
+    }
+
+    fn insert_lookups(&mut self, mineral: &Mineral) {
+        for alias in std::iter::once(&mineral.folder_name).chain(&mineral.aliases) {
+            if *alias != mineral.slug {
+                self.aliases
+                    .entry(alias.clone())
+                    .or_insert_with(|| mineral.slug.clone());
+            }
+        }
+        self.by_folder_name
+            .insert(mineral.folder_name.clone(), mineral.slug.clone());
+        self.by_slug.insert(mineral.slug.clone(), mineral.clone());
+    }
+
+    fn remove_lookups(&mut self, mineral: &Mineral) {
+        for alias in std::iter::once(&mineral.folder_name).chain(&mineral.aliases) {
+            if self.aliases.get(alias) == Some(&mineral.slug) {
+                self.aliases.remove(alias);
+            }
+        }
+        if self.by_folder_name.get(&mineral.folder_name) == Some(&mineral.slug) {
+            self.by_folder_name.remove(&mineral.folder_name);
+        }
+        self.by_slug.remove(&mineral.slug);
+    }
+
+    /// Tags, filter options, and the search indexes.
+    fn insert_listing(&mut self, mineral: &Mineral) {
+        self.search_index.insert(mineral);
+        self.suggest_index.insert(mineral);
+        for tag in &mineral.tags {
+            match self
+                .tags
+                .binary_search_by(|count| count.tag.as_str().cmp(tag))
+            {
+                Ok(index) => self.tags[index].count += 1,
+                Err(index) => self.tags.insert(
+                    index,
+                    TagCount {
+                        tag: tag.clone(),
+                        count: 1,
+                    },
+                ),
+            }
+        }
+        self.families = count_distinct(&mut self.family_counts, &mineral.mineral_family, true);
+        self.crystal_systems = count_distinct(
+            &mut self.crystal_system_counts,
+            &mineral.crystal_system,
+            true,
+        );
+    }
+
+    fn remove_listing(&mut self, mineral: &Mineral) {
+        self.search_index.remove(mineral);
+        self.suggest_index.remove(mineral);
+        for tag in &mineral.tags {
+            if let Ok(index) = self
+                .tags
+                .binary_search_by(|count| count.tag.as_str().cmp(tag))
+            {
+                self.tags[index].count -= 1;
+                if self.tags[index].count == 0 {
+                    self.tags.remove(index);
+                }
+            }
+        }
+        self.families = count_distinct(&mut self.family_counts, &mineral.mineral_family, false);
+        self.crystal_systems = count_distinct(
+            &mut self.crystal_system_counts,
+            &mineral.crystal_system,
+            false,
+        );
+    }
+
+    /// Gives `mineral` a `/m/<name>` slug. Minerals whose names give the
+    /// same slug are numbered in folder-name order (`rose-quartz`,
+    /// `rose-quartz-2`), so a namesake whose folder sorts first renumbers
+    /// the others.
+    fn join_namesakes(&mut self, mineral: &Mineral, names: &mut NameChanges) {
+        let Some(base) = permalinks::name_slug(&mineral.common_name) else {
+            return;
+        };
+        self.namesakes.entry(base.clone()).or_default().insert(
+            mineral.folder_name.clone(),
+            (mineral.slug.clone(), String::new()),
+        );
+        self.renumber(&base, names);
+    }
+
+    fn leave_namesakes(&mut self, mineral: &Mineral, names: &mut NameChanges) {
+        let Some(base) = permalinks::name_slug(&mineral.common_name) else {
+            return;
+        };
+        let Some(group) = self.namesakes.get_mut(&base) else {
+            return;
+        };
+        if let Some((_, name)) = group.remove(&mineral.folder_name) {
+            self.by_name_slug.remove(&name);
+            names.removed.push(name);
+        }
+        if group.is_empty() {
+            self.namesakes.remove(&base);
+        } else {
+            self.renumber(&base, names);
+        }
+    }
+
+    /// Numbers the minerals sharing `base` again, skipping names other
+    /// minerals already hold.
+    fn renumber(&mut self, base: &str, names: &mut NameChanges) {
+        let Some(mut group) = self.namesakes.remove(base) else {
+            return;
+        };
+        for (slug, name) in group.values() {
+            if self.by_name_slug.get(name) == Some(slug) {
+                self.by_name_slug.remove(name);
+                names.removed.push(name.clone());
+            }
+        }
+        for (slug, name) in group.values_mut() {
+            *name = std::iter::once(base.to_string())
+                .chain((2..).map(|n| format!("{base}-{n}")))
+                .find(|name| !self.by_name_slug.contains_key(name))
+                .unwrap_or_else(|| base.to_string());
+            self.by_name_slug.insert(name.clone(), slug.clone());
+            names.added.push((name.clone(), slug.clone()));
+        }
+        self.namesakes.insert(base.to_string(), group);
+    }
+
+    /// Applies one change to the public view: `old` is the mineral as it
+    /// was, `new` as it is now. The view is only built in full when the
+    /// first private, unlisted, or scheduled mineral appears.
+    fn sync_public(&mut self, old: Option<&Mineral>, new: Option<&Mineral>, names: NameChanges) {
+        if self.unlisted.is_empty() {
+            self.public = None;
+            return;
+        }
+        let Some(mut public) = self.public.take() else {
+            self.public = Some(Arc::new(self.public_subset()));
+            return;
+        };
+        let view = Arc::make_mut(&mut public);
+        if let Some(old) = old {
+            if view.by_slug.contains_key(&old.slug) {
+                view.remove_lookups(old);
+            }
+            if let Some(position) = view
+                .ordered
+                .iter()
+                .position(|mineral| mineral.folder_name == old.folder_name)
+            {
+                view.ordered.remove(position);
+                view.remove_listing(old);
+            }
+        }
+        if let Some(new) = new.filter(|new| new.is_reachable()) {
+            view.insert_lookups(new);
+            if new.is_listed() {
+                let position = view
+                    .ordered
+                    .partition_point(|existing| existing.common_name <= new.common_name);
+                view.ordered.insert(position, new.clone());
+                view.insert_listing(new);
+            }
+        }
+        for name in names.removed {
+            view.by_name_slug.remove(&name);
+        }
+        for (name, slug) in names.added {
+            if view.by_slug.contains_key(&slug) {
+                view.by_name_slug.insert(name, slug);
+            }
+        }
+        self.public = Some(public);
+    }
+
+    /// Lists public minerals only and resolves unlisted ones too; minerals
+    /// scheduled for later count as private until they are updated after
+    /// their `publish_at`, which [`crate::schedule`] triggers. Lookups
+    /// are filtered from the full catalog rather than rebuilt, so a
+    /// mineral's `/m/<name>` slug is the same for admins and visitors.
+    fn public_subset(&self) -> MineralCatalog {
+        let reachable = |slug: &String| {
+            self.by_slug
+                .get(slug)
+                .is_some_and(|mineral| mineral.is_reachable())
+        };
+        let mut view = MineralCatalog {
+            by_slug: self
+                .by_slug
+                .iter()
+                .filter(|(slug, _)| reachable(slug))
+                .map(|(slug, mineral)| (slug.clone(), mineral.clone()))
+                .collect(),
+            aliases: self
+                .aliases
+                .iter()
+                .filter(|(_, slug)| reachable(slug))
+                .map(|(alias, slug)| (alias.clone(), slug.clone()))
+                .collect(),
+            by_name_slug: self
+                .by_name_slug
+                .iter()
+                .filter(|(_, slug)| reachable(slug))
+                .map(|(name, slug)| (name.clone(), slug.clone()))
+                .collect(),
+            by_folder_name: self
+                .by_folder_name
+                .iter()
+                .filter(|(_, slug)| reachable(slug))
+                .map(|(folder, slug)| (folder.clone(), slug.clone()))
+                .collect(),
+            ..MineralCatalog::default()
+        };
+        let listed = self
+            .ordered
+            .iter()
+            .filter(|mineral| mineral.is_listed())
+            .cloned()
+            .collect::<Vec<_>>();
+        for mineral in &listed {
+            view.insert_listing(mineral);
+        }
+        view.ordered = listed;
+        view
+    }
+
+    /// The catalog as visitors without an admin session see it: private
+    /// minerals are gone, unlisted ones only resolve by slug.
+    pub fn public_view(self: &Arc<Self>) -> Arc<MineralCatalog> {
+        match &self.public {
+            Some(public) => Arc::clone(public),
+            None => Arc::clone(self),
+        }
+    }
+
+    /// The public minerals, for lists shown alongside any one mineral such
+    /// as its related minerals.
+    pub fn listed(&self) -> &[Mineral] {
+        match &self.public {
+            Some(public) => &public.ordered,
+            None => &self.ordered,
+        }
+    }
+
+    /// The mineral published under `slug`, or under a slug it had before.
+    pub fn resolve(&self, slug: &str) -> Option<&Mineral> {
+        self.by_slug.get(slug).or_else(|| {
+            self.aliases
+                .get(slug)
+                .and_then(|current| self.by_slug.get(current))
+        })
+    }
+
+    /// The mineral loaded from `folder_name`.
+    pub fn by_folder(&self, folder_name: &str) -> Option<&Mineral> {
+        self.by_folder_name
+            .get(folder_name)
+            .and_then(|slug| self.by_slug.get(slug))
+    }
+
+    /// The `/m/<name>` slug of the mineral published under `slug`.
+    pub fn name_slug_of(&self, slug: &str) -> Option<&str> {
+        self.by_name_slug
+            .iter()
+            .find(|(_, current)| *current == slug)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Filters and sorts the catalog and slices out one 1-based page.
+    /// Out-of-range pages are clamped to the last page so stale links still
+    /// render.
+    pub fn page(
+        &self,
+        query: &CatalogQuery,
+        sort: SortKey,
+        page: usize,
+        page_size: usize,
+    ) -> CatalogPage {
+        let matching = self
+            .ordered
+            .iter()
+            .filter(|mineral| query.matches(mineral))
+            .collect();
+        paginate(matching, sort, page, page_size)
+    }
+
+    /// Every match of `query`, sorted; the GraphQL API slices these by
+    /// offset rather than page number.
+    pub fn matching(&self, query: &CatalogQuery, sort: SortKey) -> Vec<&Mineral> {
+        let mut matching = self
+            .ordered
+            .iter()
+            .filter(|mineral| query.matches(mineral))
+            .collect::<Vec<_>>();
+        matching.sort_by(|a, b| compare_minerals(a, b, sort));
+        matching
+    }
+
+    /// Full-text search over name, family, formula, color, tags, and notes,
+    /// tolerating up to `max_distance` typos per term.
+    pub fn search(&self, query: &str, max_distance: usize) -> Vec<Mineral> {
+        self.search_index
+            .search(query, max_distance)
+            .into_iter()
+            .filter_map(|slug| self.by_slug.get(&slug).cloned())
+            .collect()
+    }
+
+    /// Mineral and family names for a search box's typeahead;
+    /// [`Suggestion::slug`] is a key of `by_slug`.
+    pub fn suggest(&self, query: &str, limit: usize, max_distance: usize) -> Vec<Suggestion> {
+        self.suggest_index.suggest(query, limit, max_distance)
+    }
+}
+
+fn paginate(
+    mut sorted: Vec<&Mineral>,
+    sort: SortKey,
+    page: usize,
+    page_size: usize,
+) -> CatalogPage {
+    let page_size = page_size.clamp(1, MAX_PAGE_SIZE);
+    let total = sorted.len();
+    let total_pages = total.div_ceil(page_size).max(1);
+    let page = page.clamp(1, total_pages);
+    sorted.sort_by(|a, b| compare_minerals(a, b, sort));
+
+    CatalogPage {
+        minerals: sorted
+            .into_iter()
+            .skip((page - 1) * page_size)
+            .take(page_size)
+            .cloned()
+            .collect(),
+        total,
+        page,
+        page_size,
+        total_pages,
+    }
+}
+
+/// Counts `value` in or out of `counts` (case-insensitive, trimmed) and
+/// returns the distinct values, sorted; the first spelling seen wins.
+fn count_distinct(
+    counts: &mut BTreeMap<String, (String, usize)>,
+    value: &str,
+    added: bool,
+) -> Vec<String> {
+    let value = value.trim();
+    if !value.is_empty() {
+        let key = value.to_lowercase();
+        if added {
+            counts
+                .entry(key)
+                .or_insert_with(|| (value.to_string(), 0))
+                .1 += 1;
+        } else if let Some((_, count)) = counts.get_mut(&key) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&key);
+            }
+        }
+    }
+    counts
+        .values()
+        .map(|(spelling, _)| spelling.clone())
+        .collect()
+}
+
+fn compare_minerals(a: &Mineral, b: &Mineral, sort: SortKey) -> Ordering {
+    let primary = match sort {
+        SortKey::Name => Ordering::Equal,
+        SortKey::Hardness => a
+            .hardness_mohs
+            .partial_cmp(&b.hardness_mohs)
+            .unwrap_or(Ordering::Equal),
+        SortKey::Density => a
+            .density_g_cm3
+            .partial_cmp(&b.density_g_cm3)
+            .unwrap_or(Ordering::Equal),
+        SortKey::Family => a
+            .mineral_family
+            .to_lowercase()
+            .cmp(&b.mineral_family.to_lowercase()),
+    };
+
+    primary.then_with(|| a.common_name.cmp(&b.common_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{CatalogFilters, CatalogQuery, MineralCatalog, SortKey, TagCount};
+    use crate::{
+        models::{sample_mineral, Mineral},
+        visibility::Visibility,
+    };
+
+    fn mineral(name: &str, family: &str, hardness: f32, tags: &[&str]) -> Mineral {
+        Mineral {
+            slug: format!("mineral.{family}.0x{}", name.to_lowercase()),
+            folder_name: format!("mineral.{family}.0x{}", name.to_lowercase()),
+            common_name: name.to_string(),
+            mineral_family: family.to_string(),
+            hardness_mohs: hardness,
+            density_g_cm3: 3.0,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..sample_mineral()
+        }
+    }
+
+    #[test]
+    fn sorts_then_paginates_and_clamps_out_of_range_pages() {
+        let catalog = MineralCatalog::new(vec![
+            mineral("Calcite", "carbonates", 3.0, &["brazil"]),
+            mineral("Quartz", "silicates", 7.0, &["brazil", "client-a"]),
+            mineral("Talc", "silicates", 1.0, &[]),
+        ]);
+
+        let first = catalog.page(&CatalogQuery::default(), SortKey::Hardness, 1, 2);
+        let names: Vec<_> = first
+            .minerals
+            .iter()
+            .map(|m| m.common_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Talc", "Calcite"]);
+        assert_eq!((first.total, first.total_pages), (3, 2));
+
+        let last = catalog.page(&CatalogQuery::default(), SortKey::Hardness, 99, 2);
+        assert_eq!(last.page, 2);
+        assert_eq!(last.minerals[0].common_name, "Quartz");
+        assert_eq!(SortKey::from_param("Density"), Some(SortKey::Density));
+
+        let tagged = catalog.page(
+            &CatalogQuery {
+                tag: Some("brazil".to_string()),
+                ..CatalogQuery::default()
+            },
+            SortKey::Hardness,
+            1,
+            24,
+        );
+        assert_eq!(tagged.total, 2);
+        assert_eq!(tagged.minerals[0].common_name, "Calcite");
+        assert_eq!(
+            catalog.tags,
+            vec![
+                TagCount {
+                    tag: "brazil".to_string(),
+                    count: 2
+                },
+                TagCount {
+                    tag: "client-a".to_string(),
+                    count: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn filters_parse_and_narrow_the_listing() {
+        let catalog = MineralCatalog::new(vec![
+            mineral("Calcite", "Carbonates", 3.0, &[]),
+            mineral("Quartz", "Silicates", 7.0, &[]),
+            mineral("Talc", "silicates", 1.0, &[]),
+        ]);
+        assert_eq!(catalog.families, vec!["Carbonates", "Silicates"]);
+
+        let filters = CatalogFilters {
+            family: "silicate".to_string(),
+            hardness_min: " 6 ".to_string(),
+            ..CatalogFilters::default()
+        };
+        let page = catalog.page(&filters.to_query().unwrap(), SortKey::Name, 1, 24);
+        assert_eq!(page.total, 1);
+        assert_eq!(page.minerals[0].common_name, "Quartz");
+        assert_eq!(filters.query_string(), "&family=silicate&hardness_min=+6+");
+        assert!(!filters.is_empty());
+        assert!(CatalogFilters::default().is_empty());
+
+        let invalid = CatalogFilters {
+            hardness_max: "hard".to_string(),
+            ..CatalogFilters::default()
+        };
+        assert!(invalid.to_query().is_err());
+    }
+
+    #[test]
+    fn upsert_and_remove_update_one_mineral_in_place() {
+        let mut catalog = MineralCatalog::new(vec![
+            mineral("Calcite", "carbonates", 3.0, &["brazil"]),
+            mineral("Talc", "silicates", 1.0, &[]),
+        ]);
+
+        catalog.upsert(mineral("Quartz", "silicates", 7.0, &["brazil"]));
+        let mut edited = mineral("Talc", "silicates", 1.0, &["soft"]);
+        edited.common_name = "Soapstone".to_string();
+        catalog.upsert(edited);
+
+        let names = |catalog: &MineralCatalog| {
+            catalog
+                .ordered
+                .iter()
+                .map(|mineral| mineral.common_name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&catalog), ["Calcite", "Quartz", "Soapstone"]);
+        assert_eq!(
+            catalog.search("soap", 1)[0].folder_name,
+            "mineral.silicates.0xtalc"
+        );
+        assert_eq!(catalog.tags[0].count, 2);
+
+        let removed = catalog.remove("mineral.carbonates.0xcalcite").unwrap();
+        assert_eq!(removed.common_name, "Calcite");
+        assert!(catalog.remove("mineral.carbonates.0xcalcite").is_none());
+        assert_eq!(names(&catalog), ["Quartz", "Soapstone"]);
+        assert_eq!(catalog.families, ["silicates"]);
+        assert!(catalog.resolve("mineral.carbonates.0xcalcite").is_none());
+        assert!(catalog.by_folder("mineral.carbonates.0xcalcite").is_none());
+        assert_eq!(
+            catalog
+                .by_folder("mineral.silicates.0xtalc")
+                .unwrap()
+                .common_name,
+            "Soapstone"
+        );
+    }
+
+    #[test]
+    fn upsert_leaves_other_minerals_untouched() {
+        const CALCITE: &str = "mineral.carbonates.0xcalcite";
+        const TALC: &str = "mineral.silicates.0xtalc";
+        let mut catalog = MineralCatalog::new(vec![
+            mineral("Calcite", "carbonates", 3.0, &["brazil"]),
+            mineral("Talc", "silicates", 1.0, &[]),
+        ]);
+        let hidden = Mineral {
+            visibility: Visibility::Unlisted,
+            ..mineral("Beryl", "silicates", 8.0, &[])
+        };
+        catalog.upsert(hidden);
+        let calcite = |catalog: &MineralCatalog| {
+            let public = catalog.public.as_deref().unwrap();
+            [
+                catalog.by_slug[CALCITE].common_name.as_ptr(),
+                catalog.ordered[1].common_name.as_ptr(),
+                public.by_slug[CALCITE].common_name.as_ptr(),
+                public.ordered[0].common_name.as_ptr(),
+            ]
+        };
+        let before = calcite(&catalog);
+
+        catalog.upsert(mineral("Talc", "silicates", 1.5, &["soft"]));
+        catalog.upsert(mineral("Quartz", "silicates", 7.0, &[]));
+        catalog.remove("mineral.silicates.0xquartz");
+
+        assert_eq!(calcite(&catalog), before);
+        assert_eq!(catalog.by_slug[TALC].hardness_mohs, 1.5);
+        let catalog = Arc::new(catalog);
+        assert_eq!(catalog.public_view().by_slug[TALC].hardness_mohs, 1.5);
+        assert!(catalog.search("quartz", 0).is_empty());
+    }
+
+    #[test]
+    fn public_view_lists_public_minerals_and_resolves_unlisted_ones() {
+        let hidden = |name: &str, visibility: Visibility| Mineral {
+            visibility,
+            ..mineral(name, "silicates", 7.0, &["client-a"])
+        };
+        let mut catalog = Arc::new(MineralCatalog::new(vec![
+            mineral("Calcite", "carbonates", 3.0, &["brazil"]),
+            hidden("Quartz", Visibility::Private),
+            hidden("Talc", Visibility::Unlisted),
+        ]));
+        assert_eq!(catalog.ordered.len(), 3);
+
+        let public = catalog.public_view();
+        assert!(Arc::ptr_eq(&public, &catalog.public_view()));
+        assert_eq!(public.ordered.len(), 1);
+        assert_eq!(public.listed().len(), 1);
+        assert_eq!(catalog.listed()[0].common_name, "Calcite");
+        assert_eq!(public.families, ["carbonates"]);
+        assert_eq!(public.tags.len(), 1);
+        assert!(public.search("talc", 0).is_empty());
+        assert!(public.suggest("quar", 5, 0).is_empty());
+        assert!(public.resolve("mineral.silicates.0xquartz").is_none());
+        assert!(public.resolve("mineral.silicates.0xtalc").is_some());
+        assert_eq!(
+            public.by_name_slug.get("talc").map(String::as_str),
+            Some("mineral.silicates.0xtalc")
+        );
+        assert!(!public.by_name_slug.contains_key("quartz"));
+
+        Arc::make_mut(&mut catalog).upsert(mineral("Quartz", "silicates", 7.0, &[]));
+        Arc::make_mut(&mut catalog).upsert(mineral("Talc", "silicates", 1.0, &[]));
+        assert!(Arc::ptr_eq(&catalog.public_view(), &catalog));
+        assert!(catalog.public.is_none());
+
+        let scheduled = |name: &str, publish_at: &str| Mineral {
+            publish_at: Some(publish_at.to_string()),
+            ..mineral(name, "silicates", 7.0, &[])
+        };
+        Arc::make_mut(&mut catalog).upsert(scheduled("Quartz", "2999-01-01T00:00:00Z"));
+        Arc::make_mut(&mut catalog).upsert(scheduled("Talc", "2020-01-01T00:00:00Z"));
+        let public = catalog.public_view();
+        let listed = public
+            .listed()
+            .iter()
+            .map(|mineral| mineral.common_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(listed, ["Calcite", "Talc"]);
+        assert!(public.resolve("mineral.silicates.0xquartz").is_none());
+        assert!(catalog.resolve("mineral.silicates.0xquartz").is_some());
+    }
+
+    #[test]
+    fn namesakes_get_numbered_name_slugs_in_folder_order() {
+        let rose = |folder: &str| Mineral {
+            slug: folder.to_string(),
+            folder_name: folder.to_string(),
+            ..mineral("Rose Quartz", "silicates", 7.0, &[])
+        };
+        let mut catalog = MineralCatalog::new(vec![rose("mineral.silicates.0xb")]);
+        let named = |catalog: &MineralCatalog, name: &str| catalog.by_name_slug.get(name).cloned();
+        assert_eq!(
+            named(&catalog, "rose-quartz").as_deref(),
+            Some("mineral.silicates.0xb")
+        );
+
+        catalog.upsert(rose("mineral.silicates.0xa"));
+        assert_eq!(
+            named(&catalog, "rose-quartz").as_deref(),
+            Some("mineral.silicates.0xa")
+        );
+        assert_eq!(
+            named(&catalog, "rose-quartz-2").as_deref(),
+            Some("mineral.silicates.0xb")
+        );
+
+        catalog.remove("mineral.silicates.0xa");
+        assert_eq!(
+            named(&catalog, "rose-quartz").as_deref(),
+            Some("mineral.silicates.0xb")
+        );
+        assert!(named(&catalog, "rose-quartz-2").is_none());
+    }
+}
@@ -0,0 +1,190 @@
+//! Public catalog for apps and partner sites. `GET /api/catalog` pages
+//! through published minerals in one language, in a fixed JSON shape that
+//! is a subset of `Mineral` and does not follow the on-disk record, so the
+//! folder layout can change without breaking consumers. It has its own
+//! path because `GET /api/minerals` is the token API's full-record list
+//! (`api::list_minerals`), which existing clients rely on.
+
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    catalog::{CatalogQuery, SortKey, MAX_PAGE_SIZE},
+    geo::Location,
+    i18n::Language,
+    lang_path::path_language,
+    models::Mineral,
+    resolve_language, visible_catalog, AppError, AppState,
+};
+
+const DEFAULT_PER_PAGE: usize = 50;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CatalogApiParams {
+    lang: Option<String>,
+    page: Option<usize>,
+    per_page: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CatalogApiPage {
+    lang: &'static str,
+    page: usize,
+    per_page: usize,
+    total: usize,
+    total_pages: usize,
+    minerals: Vec<CatalogApiMineral>,
+}
+
+/// Text fields are in the page's language; URLs are absolute, on
+/// `PUBLIC_URL`.
+#[derive(Debug, Serialize)]
+pub struct CatalogApiMineral {
+    slug: String,
+    name: String,
+    family: String,
+    formula: String,
+    hardness_mohs: f32,
+    density_g_cm3: f32,
+    crystal_system: String,
+    color: String,
+    streak: String,
+    luster: String,
+    major_elements_pct: BTreeMap<String, f32>,
+    tags: Vec<String>,
+    location: Location,
+    page_url: String,
+    image_url: Option<String>,
+    thumbnail_url: Option<String>,
+}
+
+pub async fn list_minerals(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<CatalogApiParams>,
+) -> Result<Response, AppError> {
+    let language = negotiate_language(&state, &headers, params.lang.as_deref())?;
+    let (page_number, per_page) = page_window(params.page, params.per_page)?;
+
+    let catalog = visible_catalog(&state, &headers, language).await?;
+    let page = catalog.page(
+        &CatalogQuery::default(),
+        SortKey::default(),
+        page_number,
+        per_page,
+    );
+    // `page` clamps to the last page; past it a client gets an empty list.
+    let minerals = if page.page == page_number {
+        page.minerals
+            .iter()
+            .map(|mineral| api_mineral(&state, mineral, language))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut response = Json(CatalogApiPage {
+        lang: language.code(),
+        page: page_number,
+        per_page,
+        total: page.total,
+        total_pages: page.total_pages,
+        minerals,
+    })
+    .into_response();
+    let response_headers = response.headers_mut();
+    response_headers.insert(
+        header::CONTENT_LANGUAGE,
+        HeaderValue::from_static(language.code()),
+    );
+    response_headers.insert(
+        header::VARY,
+        HeaderValue::from_static("accept-language, cookie"),
+    );
+    Ok(response)
+}
+
+/// `?page=` and `?per_page=` with their defaults, checked against the
+/// catalog's page size limit.
+fn page_window(page: Option<usize>, per_page: Option<usize>) -> Result<(usize, usize), AppError> {
+    let page = page.unwrap_or(1);
+    let per_page = per_page.unwrap_or(DEFAULT_PER_PAGE);
+    if page == 0 {
+        return Err(AppError::BadRequest("page starts at 1".to_string()));
+    }
+    if !(1..=MAX_PAGE_SIZE).contains(&per_page) {
+        return Err(AppError::BadRequest(format!(
+            "per_page must be between 1 and {MAX_PAGE_SIZE}"
+        )));
+    }
+    Ok((page, per_page))
+}
+
+/// `?lang=` when given (unknown codes are an error), then a `/<lang>/`
+/// path prefix, then `Accept-Language`, then the `lang` cookie.
+fn negotiate_language(
+    state: &AppState,
+    headers: &HeaderMap,
+    requested: Option<&str>,
+) -> Result<Language, AppError> {
+    if let Some(code) = requested {
+        return Language::from_code(code)
+            .ok_or_else(|| AppError::BadRequest(format!("unsupported language code '{code}'")));
+    }
+    let accepted = headers
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(Language::from_accept_language);
+    Ok(path_language(headers)
+        .or(accepted)
+        .unwrap_or_else(|| resolve_language(state, headers)))
+}
+
+fn api_mineral(state: &AppState, mineral: &Mineral, language: Language) -> CatalogApiMineral {
+    let site = |path: &str| state.pdf_generator.public_url(path);
+    CatalogApiMineral {
+        page_url: state.pdf_generator.mineral_url(&mineral.slug, language),
+        image_url: mineral.image_path.as_deref().map(site),
+        thumbnail_url: mineral.thumbnail_src().map(site),
+        slug: mineral.slug.clone(),
+        name: mineral.common_name.clone(),
+        family: mineral.mineral_family.clone(),
+        formula: mineral.formula.clone(),
+        hardness_mohs: mineral.hardness_mohs,
+        density_g_cm3: mineral.density_g_cm3,
+        crystal_system: mineral.crystal_system.clone(),
+        color: mineral.color.clone(),
+        streak: mineral.streak.clone(),
+        luster: mineral.luster.clone(),
+        major_elements_pct: mineral.major_elements_pct.clone(),
+        tags: mineral.tags.clone(),
+        location: mineral.location.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{page_window, DEFAULT_PER_PAGE};
+    use crate::catalog::MAX_PAGE_SIZE;
+
+    #[test]
+    fn defaults_and_bounds_the_page_window() {
+        assert_eq!(page_window(None, None).unwrap(), (1, DEFAULT_PER_PAGE));
+        assert_eq!(page_window(Some(2), Some(50)).unwrap(), (2, 50));
+        assert_eq!(
+            page_window(Some(1), Some(MAX_PAGE_SIZE)).unwrap(),
+            (1, MAX_PAGE_SIZE)
+        );
+        assert!(page_window(Some(0), None).is_err());
+        assert!(page_window(None, Some(0)).is_err());
+        assert!(page_window(None, Some(MAX_PAGE_SIZE + 1)).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! Certificates of authenticity. `POST /api/minerals/:slug/certificate`
+//! renders a one-page certificate for one specimen of the mineral into
+//! `certificates/<run>/` next to its `reports/`, signed like a report. The
+//! QR code on the certificate points at
+//! `GET /api/certificates/:slug/verify?run=<run>`.
+
+use anyhow::Context;
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::HeaderMap,
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::require_api_token, get_mineral, lang_path, pdf::Certificate, resolve_language,
+    resolve_pdf_backend, resolve_report_units, specimens, AppError, AppState,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct CertificateRequest {
+    specimen_id: String,
+    /// Name printed under the signature line.
+    #[serde(default)]
+    issued_by: String,
+    #[serde(default)]
+    backend: Option<String>,
+    /// `metric` or `imperial`; blank for the `units` cookie.
+    #[serde(default)]
+    units: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CertificateResponse {
+    run_id: String,
+    specimen_id: String,
+    pdf_path: String,
+    html_path: String,
+    signature_path: String,
+    /// What the certificate's QR code encodes.
+    verify_url: String,
+}
+
+pub async fn issue_certificate(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+    Json(request): Json<CertificateRequest>,
+) -> Result<Json<CertificateResponse>, AppError> {
+    require_api_token(&state, &headers)?;
+    let language = resolve_language(&state, &headers);
+    let units = resolve_report_units(&headers, &request.units)?;
+    let backend = resolve_pdf_backend(&state, request.backend.as_deref())?;
+    let mineral = get_mineral(&state, language, &slug).await?;
+    let specimen = specimens::find(&state, &mineral, request.specimen_id.trim()).await?;
+
+    let certificate = Certificate {
+        mineral,
+        specimen,
+        issued_by: request.issued_by.trim().to_string(),
+        issued_utc: Utc::now().to_rfc3339(),
+        units,
+    };
+    let artifacts = state
+        .pdf_generator
+        .generate_certificate(&certificate, language, backend)
+        .await
+        .with_context(|| {
+            format!(
+                "failed to generate certificate for specimen '{}' of '{slug}'",
+                certificate.specimen.id
+            )
+        })?;
+
+    Ok(Json(CertificateResponse {
+        verify_url: state
+            .pdf_generator
+            .certificate_verify_url(&slug, &artifacts.run_id),
+        run_id: artifacts.run_id,
+        specimen_id: certificate.specimen.id,
+        pdf_path: lang_path::prefixed(&headers, &artifacts.pdf_path),
+        html_path: lang_path::prefixed(&headers, &artifacts.html_path),
+        signature_path: lang_path::prefixed(&headers, &artifacts.signature_path),
+    }))
+}
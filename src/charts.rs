@@ -0,0 +1,282 @@
+//! Server-side charts for reports. The element composition is drawn as a
+//! horizontal bar chart on a fixed 0–100 wt% scale, as plain SVG: embedded
+//! in `report.html` and included by the Typst source as `CHART_FILE`.
+//! LaTeX cannot include SVG without an external converter, so `report.tex`
+//! draws the same bars with rules in `BAR_COLOR`. Spectra are line plots,
+//! as SVG the same way and as TikZ paths for `report.tex`.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+
+use crate::{agent::ElementShare, locale_format::LocaleFormat};
+
+/// Chart next to the Typst source that includes it.
+pub const CHART_FILE: &str = "report_chart.svg";
+
+/// Bar fill, hex without `#`.
+pub const BAR_COLOR: &str = "2F6F8F";
+
+const WIDTH: u32 = 640;
+const LABEL_WIDTH: u32 = 72;
+const VALUE_WIDTH: u32 = 64;
+const ROW_HEIGHT: u32 = 24;
+const AXIS_HEIGHT: u32 = 24;
+const TICKS: [u32; 5] = [0, 25, 50, 75, 100];
+
+const SPECTRUM_HEIGHT: u32 = 240;
+const SPECTRUM_LEFT: u32 = 28;
+const SPECTRUM_RIGHT: u32 = 16;
+const SPECTRUM_TOP: u32 = 8;
+const SPECTRUM_BOTTOM: u32 = 44;
+/// Plot size in `report.tex`, in centimetres.
+const TIKZ_WIDTH: f32 = 14.0;
+const TIKZ_HEIGHT: f32 = 4.5;
+
+/// Spectrum plot `index` (from 0) next to the Typst source.
+pub fn spectrum_file(index: usize) -> String {
+    format!("spectrum_{}.svg", index + 1)
+}
+
+pub fn svg_data_uri(svg: &str) -> String {
+    format!("data:image/svg+xml;base64,{}", BASE64.encode(svg))
+}
+
+/// Bar chart of `elements` in their order, or `None` when there are none.
+/// Values are written the way `locale` writes numbers.
+pub fn element_bars_svg(elements: &[ElementShare], locale: LocaleFormat) -> Option<String> {
+    if elements.is_empty() {
+        return None;
+    }
+    let plot_width = (WIDTH - LABEL_WIDTH - VALUE_WIDTH) as f32;
+    let plot_bottom = ROW_HEIGHT * elements.len() as u32 + 4;
+    let height = plot_bottom + AXIS_HEIGHT;
+    let x_at = |percent: f32| LABEL_WIDTH as f32 + plot_width * percent.clamp(0.0, 100.0) / 100.0;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{WIDTH}\" height=\"{height}\" \
+         viewBox=\"0 0 {WIDTH} {height}\" font-family=\"sans-serif\" font-size=\"12\">\
+         <rect width=\"{WIDTH}\" height=\"{height}\" fill=\"#ffffff\"/>"
+    );
+    for tick in TICKS {
+        let x = x_at(tick as f32);
+        svg.push_str(&format!(
+            "<line x1=\"{x:.1}\" y1=\"0\" x2=\"{x:.1}\" y2=\"{plot_bottom}\" stroke=\"#d0d7de\"/>\
+             <text x=\"{x:.1}\" y=\"{}\" text-anchor=\"middle\" fill=\"#57606a\">{tick}%</text>",
+            plot_bottom + 16
+        ));
+    }
+    for (index, element) in elements.iter().enumerate() {
+        let top = ROW_HEIGHT * index as u32 + 4;
+        let baseline = top + 15;
+        let end = x_at(element.percent);
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{baseline}\" text-anchor=\"end\" fill=\"#24292f\">{}</text>\
+             <rect x=\"{LABEL_WIDTH}\" y=\"{}\" width=\"{:.1}\" height=\"14\" fill=\"#{BAR_COLOR}\"/>\
+             <text x=\"{:.1}\" y=\"{baseline}\" fill=\"#24292f\">{}</text>",
+            LABEL_WIDTH - 8,
+            xml_escape(&element.name),
+            top + 3,
+            end - LABEL_WIDTH as f32,
+            end + 6.0,
+            locale.decimal(element.percent, 2)
+        ));
+    }
+    svg.push_str("</svg>");
+    Some(svg)
+}
+
+/// Line plot of `points` (ascending `x`) with labelled `x` ticks; the
+/// intensity axis is unitless, so it only carries `y_label`. `None` when
+/// the points span no `x` range.
+pub fn spectrum_svg(
+    points: &[[f32; 2]],
+    x_label: &str,
+    y_label: &str,
+    locale: LocaleFormat,
+) -> Option<String> {
+    let plot = SpectrumPlot::new(points, locale)?;
+    let plot_width = (WIDTH - SPECTRUM_LEFT - SPECTRUM_RIGHT) as f32;
+    let plot_height = (SPECTRUM_HEIGHT - SPECTRUM_TOP - SPECTRUM_BOTTOM) as f32;
+    let plot_bottom = SPECTRUM_TOP as f32 + plot_height;
+    let x_at = |fraction: f32| SPECTRUM_LEFT as f32 + plot_width * fraction;
+    let y_at = |fraction: f32| plot_bottom - plot_height * fraction;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{WIDTH}\" height=\"{SPECTRUM_HEIGHT}\" \
+         viewBox=\"0 0 {WIDTH} {SPECTRUM_HEIGHT}\" font-family=\"sans-serif\" font-size=\"12\">\
+         <rect width=\"{WIDTH}\" height=\"{SPECTRUM_HEIGHT}\" fill=\"#ffffff\"/>\
+         <line x1=\"{SPECTRUM_LEFT}\" y1=\"{plot_bottom:.1}\" x2=\"{}\" y2=\"{plot_bottom:.1}\" stroke=\"#57606a\"/>",
+        WIDTH - SPECTRUM_RIGHT
+    );
+    for (fraction, label) in &plot.ticks {
+        let x = x_at(*fraction);
+        svg.push_str(&format!(
+            "<line x1=\"{x:.1}\" y1=\"{plot_bottom:.1}\" x2=\"{x:.1}\" y2=\"{:.1}\" stroke=\"#57606a\"/>\
+             <text x=\"{x:.1}\" y=\"{:.1}\" text-anchor=\"middle\" fill=\"#57606a\">{label}</text>",
+            plot_bottom + 4.0,
+            plot_bottom + 17.0
+        ));
+    }
+    let path = plot
+        .points
+        .iter()
+        .map(|(x, y)| format!("{:.1},{:.1}", x_at(*x), y_at(*y)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    svg.push_str(&format!(
+        "<polyline points=\"{path}\" fill=\"none\" stroke=\"#{BAR_COLOR}\" stroke-width=\"1.2\"/>\
+         <text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\" fill=\"#24292f\">{}</text>\
+         <text transform=\"translate(14 {:.1}) rotate(-90)\" text-anchor=\"middle\" fill=\"#24292f\">{}</text>\
+         </svg>",
+        x_at(0.5),
+        SPECTRUM_HEIGHT - 8,
+        xml_escape(x_label),
+        y_at(0.5),
+        xml_escape(y_label)
+    ));
+    Some(svg)
+}
+
+/// The same plot as [`spectrum_svg`] as a TikZ picture, for LaTeX.
+/// `x_label` and `y_label` must already be escaped for LaTeX.
+pub fn spectrum_tikz(
+    points: &[[f32; 2]],
+    x_label: &str,
+    y_label: &str,
+    locale: LocaleFormat,
+) -> Option<String> {
+    let plot = SpectrumPlot::new(points, locale)?;
+    let mut tikz = format!(
+        "\\begin{{tikzpicture}}\n\\definecolor{{spectrum}}{{HTML}}{{{BAR_COLOR}}}\n\
+         \\draw[gray] (0,0) -- ({TIKZ_WIDTH:.2},0);\n"
+    );
+    for (fraction, label) in &plot.ticks {
+        tikz.push_str(&format!(
+            "\\draw[gray] ({x:.3},0) -- ({x:.3},-0.1) node[below, font=\\scriptsize] {{{label}}};\n",
+            x = TIKZ_WIDTH * fraction
+        ));
+    }
+    let path = plot
+        .points
+        .iter()
+        .map(|(x, y)| format!("({:.3},{:.3})", TIKZ_WIDTH * x, TIKZ_HEIGHT * y))
+        .collect::<Vec<_>>()
+        .join(" -- ");
+    tikz.push_str(&format!(
+        "\\draw[spectrum, line width=0.6pt] {path};\n\
+         \\node[below, font=\\small] at ({:.2},-0.55) {{{x_label}}};\n\
+         \\node[rotate=90, font=\\small] at (-0.35,{:.2}) {{{y_label}}};\n\
+         \\end{{tikzpicture}}",
+        TIKZ_WIDTH / 2.0,
+        TIKZ_HEIGHT / 2.0
+    ));
+    Some(tikz)
+}
+
+/// Points and `x` ticks as fractions of the plot area, `y` up from the
+/// baseline (zero, or the lowest intensity if that is negative).
+struct SpectrumPlot {
+    points: Vec<(f32, f32)>,
+    ticks: Vec<(f32, String)>,
+}
+
+impl SpectrumPlot {
+    fn new(points: &[[f32; 2]], locale: LocaleFormat) -> Option<Self> {
+        let x_min = points.iter().map(|point| point[0]).reduce(f32::min)?;
+        let x_max = points.iter().map(|point| point[0]).reduce(f32::max)?;
+        if x_max <= x_min {
+            return None;
+        }
+        let y_min = points.iter().map(|point| point[1]).fold(0.0, f32::min);
+        let y_max = points.iter().map(|point| point[1]).fold(y_min, f32::max);
+        let y_span = if y_max > y_min { y_max - y_min } else { 1.0 };
+
+        let step = tick_step((x_max - x_min) / 5.0);
+        let places = (-step.log10().floor()).max(0.0) as usize;
+        let ticks = (((x_min / step).ceil() as i64)..=((x_max / step).floor() as i64))
+            .map(|index| index as f32 * step)
+            .map(|value| {
+                (
+                    (value - x_min) / (x_max - x_min),
+                    locale.decimal(value, places),
+                )
+            })
+            .collect();
+
+        Some(Self {
+            points: points
+                .iter()
+                .map(|point| {
+                    (
+                        (point[0] - x_min) / (x_max - x_min),
+                        (point[1] - y_min) / y_span,
+                    )
+                })
+                .collect(),
+            ticks,
+        })
+    }
+}
+
+/// 1, 2, or 5 times a power of ten, at least `raw`.
+fn tick_step(raw: f32) -> f32 {
+    let magnitude = 10f32.powf(raw.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|factor| factor * magnitude)
+        .find(|step| *step >= raw)
+        .unwrap_or(10.0 * magnitude)
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{element_bars_svg, spectrum_svg, spectrum_tikz};
+    use crate::{agent::ElementShare, i18n::Language, locale_format::LocaleFormat};
+
+    #[test]
+    fn draws_one_bar_per_element_on_a_percent_scale() {
+        let elements = [
+            ElementShare {
+                name: "O".to_string(),
+                percent: 50.0,
+            },
+            ElementShare {
+                name: "<Si>".to_string(),
+                percent: 46.7,
+            },
+        ];
+        let svg = element_bars_svg(&elements, LocaleFormat::new(Language::En)).unwrap();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert_eq!(svg.matches("height=\"14\"").count(), 2);
+        // Half of the 504px plot width.
+        assert!(svg.contains("<rect x=\"72\" y=\"7\" width=\"252.0\""));
+        assert!(svg.contains(">&lt;Si&gt;</text>"));
+        assert!(svg.contains(">46.70</text>"));
+        let german = element_bars_svg(&elements, LocaleFormat::new(Language::De)).unwrap();
+        assert!(german.contains(">46,70</text>"));
+        assert!(element_bars_svg(&[], LocaleFormat::new(Language::En)).is_none());
+    }
+
+    #[test]
+    fn plots_a_spectrum_with_rounded_ticks() {
+        let points = [[100.0, 5.0], [464.0, 100.0], [1200.0, 10.0]];
+        let locale = LocaleFormat::new(Language::En);
+        let svg = spectrum_svg(&points, "Raman shift (cm⁻¹)", "Intensity", locale).unwrap();
+        assert_eq!(svg.matches("<polyline").count(), 1);
+        for tick in [">500</text>", ">1,000</text>"] {
+            assert!(svg.contains(tick), "{tick}");
+        }
+        assert!(!svg.contains(">100</text>"));
+        let tikz = spectrum_tikz(&points, "2$\\theta$", "Intensity", locale).unwrap();
+        assert!(tikz.starts_with("\\begin{tikzpicture}"));
+        assert!(tikz.contains("(0.000,0.225) -- "));
+        assert!(spectrum_svg(&[[1.0, 2.0]], "x", "y", locale).is_none());
+    }
+}
@@ -0,0 +1,214 @@
+//! Theoretical composition from a chemical formula: parses `Fe2O3`,
+//! `Ca5(PO4)3F`, or `CaSO4·2H2O` into atom counts and turns them into
+//! weight percentages with standard atomic weights.
+
+use std::collections::BTreeMap;
+
+use crate::elements;
+
+/// Entered and formula-derived values further apart than this (in wt%)
+/// are flagged on the admin form.
+pub const DIVERGENCE_TOLERANCE_PCT: f32 = 2.0;
+
+/// An element whose entered percentage is more than
+/// `DIVERGENCE_TOLERANCE_PCT` away from what the formula implies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementDivergence {
+    pub element: String,
+    pub expected_pct: f32,
+    /// Zero when the element was not entered at all.
+    pub entered_pct: f32,
+}
+
+/// Theoretical wt% per element, rounded to two decimals.
+pub fn weight_percentages(formula: &str) -> Result<BTreeMap<String, f32>, String> {
+    let counts = parse_formula(formula)?;
+    let masses = counts
+        .into_iter()
+        .map(|(element, count)| {
+            let weight = atomic_weight(&element)
+                .ok_or_else(|| format!("unknown element '{element}' in formula"))?;
+            Ok((element, count * weight))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let total = masses.iter().map(|(_, mass)| mass).sum::<f64>();
+    if total <= 0.0 {
+        return Err("formula has no atoms".to_string());
+    }
+
+    Ok(masses
+        .into_iter()
+        .map(|(element, mass)| {
+            let pct = (mass / total * 100.0 * 100.0).round() / 100.0;
+            (element, pct as f32)
+        })
+        .collect())
+}
+
+/// Elements that are entered or implied and disagree by more than the
+/// tolerance, largest gap first.
+pub fn divergences(
+    expected: &BTreeMap<String, f32>,
+    entered: &BTreeMap<String, f32>,
+) -> Vec<ElementDivergence> {
+    let mut found = expected
+        .keys()
+        .chain(
+            entered
+                .keys()
+                .filter(|element| !expected.contains_key(*element)),
+        )
+        .map(|element| ElementDivergence {
+            element: element.clone(),
+            expected_pct: expected.get(element).copied().unwrap_or(0.0),
+            entered_pct: entered.get(element).copied().unwrap_or(0.0),
+        })
+        .filter(|item| (item.expected_pct - item.entered_pct).abs() > DIVERGENCE_TOLERANCE_PCT)
+        .collect::<Vec<_>>();
+    found.sort_by(|a, b| {
+        let gap = |item: &ElementDivergence| (item.expected_pct - item.entered_pct).abs();
+        gap(b).total_cmp(&gap(a))
+    });
+    found
+}
+
+fn atomic_weight(symbol: &str) -> Option<f64> {
+    elements::by_symbol(symbol).map(|element| element.atomic_mass)
+}
+
+/// Atom counts per element. Handles nested `()` / `[]` groups, Unicode
+/// subscripts, and hydrate parts after `·` (or `.`) with a leading
+/// coefficient. Solid-solution sites like `(Mg,Fe)` are rejected because
+/// their proportions are unknown.
+fn parse_formula(formula: &str) -> Result<BTreeMap<String, f64>, String> {
+    let normalized = formula
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '₀'..='₉' => char::from(b'0' + (c as u32 - '₀' as u32) as u8),
+            '[' => '(',
+            ']' => ')',
+            '.' | '•' | '∙' | '⋅' | '*' => '·',
+            other => other,
+        })
+        .collect::<String>();
+    if normalized.is_empty() {
+        return Err("formula is empty".to_string());
+    }
+    if normalized.contains(',') {
+        return Err(
+            "formula lists alternative elements for one site (e.g. '(Mg,Fe)'); enter an end-member formula"
+                .to_string(),
+        );
+    }
+
+    let mut totals = BTreeMap::new();
+    for part in normalized.split('·') {
+        let digits = part
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(part.len());
+        let coefficient = match &part[..digits] {
+            "" => 1.0,
+            raw => raw
+                .parse::<f64>()
+                .map_err(|_| format!("invalid coefficient '{raw}'"))?,
+        };
+        let chars = part[digits..].chars().collect::<Vec<_>>();
+        let mut position = 0;
+        let counts = parse_group(&chars, &mut position)?;
+        if position != chars.len() {
+            return Err(format!("unmatched ')' in '{part}'"));
+        }
+        for (element, count) in counts {
+            *totals.entry(element).or_insert(0.0) += count * coefficient;
+        }
+    }
+
+    if totals.is_empty() {
+        return Err("formula has no element symbols".to_string());
+    }
+    Ok(totals)
+}
+
+fn parse_group(chars: &[char], position: &mut usize) -> Result<BTreeMap<String, f64>, String> {
+    let mut counts = BTreeMap::<String, f64>::new();
+    while let Some(&c) = chars.get(*position) {
+        match c {
+            '(' => {
+                *position += 1;
+                let inner = parse_group(chars, position)?;
+                if chars.get(*position) != Some(&')') {
+                    return Err("unclosed '(' in formula".to_string());
+                }
+                *position += 1;
+                let multiplier = parse_count(chars, position)?;
+                for (element, count) in inner {
+                    *counts.entry(element).or_insert(0.0) += count * multiplier;
+                }
+            }
+            ')' => break,
+            c if c.is_ascii_uppercase() => {
+                let mut symbol = c.to_string();
+                *position += 1;
+                if let Some(&next) = chars.get(*position).filter(|c| c.is_ascii_lowercase()) {
+                    symbol.push(next);
+                    *position += 1;
+                }
+                if atomic_weight(&symbol).is_none() {
+                    return Err(format!("unknown element '{symbol}' in formula"));
+                }
+                let count = parse_count(chars, position)?;
+                *counts.entry(symbol).or_insert(0.0) += count;
+            }
+            other => return Err(format!("unexpected '{other}' in formula")),
+        }
+    }
+    Ok(counts)
+}
+
+/// The count after a symbol or group; 1 when absent.
+fn parse_count(chars: &[char], position: &mut usize) -> Result<f64, String> {
+    let start = *position;
+    while chars.get(*position).is_some_and(char::is_ascii_digit) {
+        *position += 1;
+    }
+    if start == *position {
+        return Ok(1.0);
+    }
+    let raw = chars[start..*position].iter().collect::<String>();
+    raw.parse::<f64>()
+        .map_err(|_| format!("invalid count '{raw}' in formula"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{divergences, weight_percentages};
+
+    #[test]
+    fn computes_weight_percentages_and_flags_divergences() {
+        let hematite = weight_percentages("Fe2O3").unwrap();
+        assert_eq!(hematite["Fe"], 69.94);
+        assert_eq!(hematite["O"], 30.06);
+
+        let gypsum = weight_percentages("CaSO₄·2H₂O").unwrap();
+        assert_eq!(gypsum["H"], 2.34);
+        assert_eq!(gypsum["O"], 55.76);
+        let apatite = weight_percentages("Ca5(PO4)3F").unwrap();
+        assert_eq!(apatite["P"], 18.43);
+
+        assert!(weight_percentages("(Mg,Fe)2SiO4").is_err());
+        assert!(weight_percentages("Xx2O").is_err());
+        assert!(weight_percentages("Ca(CO3").is_err());
+
+        let entered = BTreeMap::from([("Fe".to_string(), 60.0), ("Ti".to_string(), 5.0)]);
+        let flagged = divergences(&hematite, &entered);
+        let elements = flagged
+            .iter()
+            .map(|item| item.element.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(elements, vec!["O", "Fe", "Ti"]);
+        assert_eq!(flagged[0].entered_pct, 0.0);
+    }
+}
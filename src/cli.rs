@@ -0,0 +1,416 @@
+//! Command line. Without a subcommand `minerals` runs the server; the other
+//! subcommands do one job against the same data directory and
+//! configuration, through the same code the routes use, and exit. Results
+//! go to stdout, logs to stderr.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    api::validate_record,
+    backup, build_state, catalog_for_language,
+    config::Config,
+    email::parse_addresses,
+    generate_report, history,
+    i18n::Language,
+    images, migrations,
+    models::{is_valid_mineral_folder_name, read_disk_record, ReportRequest},
+    read_record_file, reload_catalog,
+    translations::find_missing_translations,
+    units::UnitSystem,
+    write_metadata_file, PdfReportRequest,
+};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "minerals",
+    version,
+    about = "Mineral catalog and report server"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the web server (the default).
+    Serve,
+    /// Render one mineral's report and print where it was written, as JSON.
+    GenerateReport(GenerateReportArgs),
+    /// Check every mineral folder's metadata and images; exits non-zero
+    /// when anything is wrong.
+    ValidateData,
+    /// Generate missing image variants and reload every language's catalog.
+    Reindex,
+    /// Upgrade metadata files written in an older layout to the current
+    /// schema version.
+    Migrate {
+        /// Only list the files that would change.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Translate every missing or fallback language file with the LLM
+    /// provider, waiting until the batch is done.
+    TranslateMissing,
+    /// Write a snapshot of the data directory to a file, or into a folder.
+    Backup { target: PathBuf },
+    /// Replace the data directory with a snapshot. Stop the server first.
+    Restore { archive: PathBuf },
+}
+
+#[derive(Debug, Args)]
+pub struct GenerateReportArgs {
+    /// Mineral folder name, e.g. `mineral.silicates.0x5b6b8000`.
+    pub slug: String,
+    /// Report language; `DEFAULT_LANGUAGE` when omitted.
+    #[arg(long, value_parser = parse_language)]
+    pub lang: Option<Language>,
+    /// `typst` or `latex`; `PDF_BACKEND` when omitted.
+    #[arg(long)]
+    pub backend: Option<String>,
+    /// Folder under `REPORT_TEMPLATES_DIR`.
+    #[arg(long)]
+    pub template: Option<String>,
+    #[arg(long)]
+    pub audience: Option<String>,
+    #[arg(long)]
+    pub purpose: Option<String>,
+    #[arg(long)]
+    pub site_context: Option<String>,
+    /// `metric` or `imperial`; metric when omitted.
+    #[arg(long, value_parser = parse_units)]
+    pub units: Option<UnitSystem>,
+    /// Have the LLM provider write the summary and recommendations.
+    #[arg(long)]
+    pub llm_narrative: bool,
+    /// Also mail the finished report to these comma-separated addresses.
+    #[arg(long)]
+    pub email_to: Option<String>,
+    /// Inline the stylesheet and images into `report.html`.
+    #[arg(long)]
+    pub standalone: bool,
+}
+
+pub async fn run(command: Command, config: Config) -> Result<()> {
+    match command {
+        Command::Serve => unreachable!("serve is handled by main"),
+        Command::GenerateReport(args) => generate(args, &config).await,
+        Command::ValidateData => {
+            let problems = validate_data(&config.data_root.join("minerals"))?;
+            if problems.is_empty() {
+                println!("ok");
+                return Ok(());
+            }
+            for problem in &problems {
+                println!("{problem}");
+            }
+            bail!("{} problems found", problems.len())
+        }
+        Command::Reindex => reindex(&config).await,
+        Command::Migrate { dry_run } => migrate(&config.data_root.join("minerals"), dry_run).await,
+        Command::TranslateMissing => translate_missing(&config).await,
+        Command::Backup { target } => {
+            let (path, manifest) = backup::create(&config.data_root, &target)?;
+            info!(
+                "wrote {} ({} files, {} bytes)",
+                path.display(),
+                manifest.files.len(),
+                manifest.total_bytes()
+            );
+            Ok(())
+        }
+        Command::Restore { archive } => {
+            let restored = backup::restore(&config.data_root, &archive)?;
+            info!(
+                "restored {} files from {} (snapshot of {})",
+                restored.manifest.files.len(),
+                archive.display(),
+                restored.manifest.created_utc
+            );
+            if let Some(previous) = restored.previous {
+                info!("the replaced data directory is in {}", previous.display());
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn generate(args: GenerateReportArgs, config: &Config) -> Result<()> {
+    if let Some(to) = &args.email_to {
+        parse_addresses(to)?;
+    }
+    let defaults = ReportRequest::default();
+    let request = PdfReportRequest {
+        report: ReportRequest {
+            audience: args.audience.unwrap_or(defaults.audience),
+            purpose: args.purpose.unwrap_or(defaults.purpose),
+            site_context: args.site_context.unwrap_or(defaults.site_context),
+            template: args.template.unwrap_or(defaults.template),
+            llm_narrative: args.llm_narrative,
+            ..defaults
+        },
+        backend: args.backend,
+        email_to: args.email_to.clone(),
+        standalone: args.standalone,
+    };
+    let state = build_state(config).await?;
+    let language = args.lang.unwrap_or(config.default_language);
+    let units = args.units.unwrap_or_default();
+    let response =
+        generate_report(&state, language, units, &args.slug, &request, args.email_to).await?;
+    println!("{}", serde_json::to_string_pretty(&response)?);
+    Ok(())
+}
+
+async fn reindex(config: &Config) -> Result<()> {
+    let state = build_state(config).await?;
+    let minerals_root = state.data_root.join("minerals");
+    let updated =
+        tokio::task::spawn_blocking(move || images::backfill_variants(&minerals_root)).await?;
+    println!("image variants generated for {updated} minerals");
+    reload_catalog(&state)?;
+    for &language in Language::all() {
+        let catalog = catalog_for_language(&state, language).await?;
+        println!("{}: {} minerals", language.code(), catalog.ordered.len());
+    }
+    Ok(())
+}
+
+async fn translate_missing(config: &Config) -> Result<()> {
+    let state = build_state(config).await?;
+    if state.llm.is_none() {
+        bail!("no LLM provider is configured; set LLM_PROVIDER and its API key first");
+    }
+    let pending = find_missing_translations(&state.data_root.join("minerals")).await?;
+    if pending.is_empty() {
+        println!("every mineral already has all translations");
+        return Ok(());
+    }
+    let progress = state
+        .translation_batch
+        .run_to_end(&state, pending, state.translation_interval)
+        .await?;
+    println!(
+        "{} translated, {} failed, of {}",
+        progress.translated, progress.failed, progress.total
+    );
+    for error in &progress.errors {
+        println!("{error}");
+    }
+    if progress.failed > 0 {
+        bail!("{} translations failed", progress.failed);
+    }
+    Ok(())
+}
+
+/// Everything wrong under `minerals_root`, one line per problem: folder
+/// names the catalog would skip, metadata that is missing, unreadable, in
+/// an unknown language, or that the API would refuse to write, and images
+/// the metadata lists but the folder lacks.
+fn validate_data(minerals_root: &Path) -> Result<Vec<String>> {
+    let mut problems = Vec::new();
+    for folder in sorted_entries(minerals_root)?
+        .iter()
+        .filter(|path| path.is_dir())
+    {
+        let folder_name = file_name(folder);
+        if !is_valid_mineral_folder_name(&folder_name) {
+            problems.push(format!("{folder_name}: not a mineral folder name"));
+            continue;
+        }
+        if !folder.join("mineral.en.json").exists() && !folder.join("mineral.json").exists() {
+            problems.push(format!("{folder_name}: no mineral.en.json"));
+        }
+
+        for file in sorted_entries(folder)?.iter().map(|path| file_name(path)) {
+            // `mineral.json` is the legacy, language-less English record.
+            if file != "mineral.json" {
+                let Some(code) = file
+                    .strip_prefix("mineral.")
+                    .and_then(|rest| rest.strip_suffix(".json"))
+                else {
+                    continue;
+                };
+                if Language::from_code(code).is_none() {
+                    problems.push(format!("{folder_name}/{file}: unknown language '{code}'"));
+                    continue;
+                }
+            }
+            let record = match read_disk_record(&folder.join(&file)) {
+                Ok(record) => record,
+                Err(err) => {
+                    // The context repeats the path; the cause is what matters.
+                    problems.push(format!("{folder_name}/{file}: {}", err.root_cause()));
+                    continue;
+                }
+            };
+            for image in &record.images {
+                if !folder.join(&image.file).is_file() {
+                    problems.push(format!(
+                        "{folder_name}/{file}: image '{}' is missing",
+                        image.file
+                    ));
+                }
+            }
+            if let Err(err) = validate_record(record) {
+                problems.push(format!("{folder_name}/{file}: {err}"));
+            }
+        }
+    }
+    Ok(problems)
+}
+
+/// Upgrades every metadata file still in an older layout (see
+/// [`crate::migrations`]), saving each folder to its history first. With
+/// `dry_run` it only lists them.
+async fn migrate(minerals_root: &Path, dry_run: bool) -> Result<()> {
+    let mut upgraded = 0;
+    for folder in sorted_entries(minerals_root)? {
+        if !folder.is_dir() || !is_valid_mineral_folder_name(&file_name(&folder)) {
+            continue;
+        }
+        let mut outdated = Vec::new();
+        for path in sorted_entries(&folder)? {
+            let name = file_name(&path);
+            if !(name.starts_with("mineral.") && name.ends_with(".json")) {
+                continue;
+            }
+            let raw = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let mut value = serde_json::from_str::<Value>(&raw)
+                .with_context(|| format!("failed to parse {}", path.display()))?;
+            if migrations::upgrade(&mut value)
+                .with_context(|| format!("cannot migrate {}", path.display()))?
+            {
+                println!("{}/{name}", file_name(&folder));
+                outdated.push(path);
+            }
+        }
+        if outdated.is_empty() || dry_run {
+            upgraded += outdated.len();
+            continue;
+        }
+        history::record(&folder, "edit").await?;
+        for path in &outdated {
+            let record = read_record_file(path).await?;
+            write_metadata_file(path, &record).await?;
+        }
+        upgraded += outdated.len();
+    }
+    let verb = if dry_run { "to upgrade" } else { "upgraded" };
+    println!(
+        "{upgraded} files {verb} to schema version {}",
+        migrations::SCHEMA_VERSION
+    );
+    Ok(())
+}
+
+/// The entries of `dir`, sorted by path.
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut entries = fs::read_dir(dir)
+        .with_context(|| format!("failed to read {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(|| format!("failed to read {}", dir.display()))?;
+    entries.sort();
+    Ok(entries)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn parse_units(value: &str) -> Result<UnitSystem, String> {
+    UnitSystem::from_code(value).ok_or_else(|| "expected metric or imperial".to_string())
+}
+
+fn parse_language(value: &str) -> Result<Language, String> {
+    Language::from_code(value).ok_or_else(|| {
+        let codes = Language::all()
+            .iter()
+            .map(|language| language.code())
+            .collect::<Vec<_>>();
+        format!("expected one of {}", codes.join(", "))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use clap::{CommandFactory, Parser};
+
+    use super::{validate_data, Cli, Command};
+    use crate::{i18n::Language, test_support::TempDir};
+
+    #[test]
+    fn parses_subcommands_and_reports_data_problems() {
+        Cli::command().debug_assert();
+        let cli = Cli::try_parse_from([
+            "minerals",
+            "generate-report",
+            "mineral.silicates.0x5b6b8000",
+            "--lang",
+            "de",
+            "--llm-narrative",
+        ])
+        .unwrap();
+        let Some(Command::GenerateReport(args)) = cli.command else {
+            panic!("expected generate-report");
+        };
+        assert_eq!(args.lang, Some(Language::De));
+        assert!(args.llm_narrative);
+        assert!(Cli::try_parse_from(["minerals", "generate-report", "x", "--lang", "xx"]).is_err());
+        assert!(Cli::try_parse_from(["minerals"]).unwrap().command.is_none());
+
+        let root = TempDir::new("cli");
+        let good = root.join("mineral.oxide.0x12345");
+        let bad = root.join("mineral.oxide.0x54321");
+        fs::create_dir_all(&good).unwrap();
+        fs::create_dir_all(&bad).unwrap();
+        fs::create_dir_all(root.join("scratch")).unwrap();
+        let record = serde_json::json!({
+            "common_name": "Hematite",
+            "description": "Iron oxide.",
+            "mineral_family": "oxide",
+            "formula": "Fe2O3",
+            "hardness_mohs": 6.0,
+            "density_g_cm3": 5.3,
+            "crystal_system": "trigonal",
+            "color": "grey",
+            "streak": "red",
+            "luster": "metallic",
+            "major_elements_pct": {},
+            "notes": "Common.",
+            "images": [{"file": "image.jpg", "primary": true}],
+        });
+        fs::write(good.join("mineral.en.json"), record.to_string()).unwrap();
+        fs::write(good.join("image.jpg"), b"jpeg").unwrap();
+        fs::write(bad.join("mineral.en.json"), record.to_string()).unwrap();
+        fs::write(bad.join("mineral.xx.json"), record.to_string()).unwrap();
+        fs::write(bad.join("mineral.de.json"), "{").unwrap();
+
+        let problems = validate_data(&root).unwrap();
+        assert_eq!(problems.len(), 4, "{problems:#?}");
+        assert!(problems[0].starts_with("mineral.oxide.0x54321/mineral.de.json: EOF"));
+        assert_eq!(
+            problems[1],
+            "mineral.oxide.0x54321/mineral.en.json: image 'image.jpg' is missing"
+        );
+        assert_eq!(
+            problems[2],
+            "mineral.oxide.0x54321/mineral.xx.json: unknown language 'xx'"
+        );
+        assert_eq!(problems[3], "scratch: not a mineral folder name");
+    }
+}
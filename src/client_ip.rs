@@ -0,0 +1,260 @@
+//! The visitor's address behind a reverse proxy. The connecting address is
+//! the proxy's, so the one it reports in `X-Forwarded-For` or `Forwarded`
+//! is used instead, but only when the connection comes from a configured
+//! trusted proxy; anyone else could send those headers to pick an address.
+
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use axum::{
+    extract::{ConnectInfo, FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+
+/// An address block such as `10.0.0.0/8` or `::1/128`; a bare address
+/// covers only itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, canonical(ip)) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                prefix_matches(&network.octets(), &ip.octets(), self.prefix)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                prefix_matches(&network.octets(), &ip.octets(), self.prefix)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let raw = raw.trim();
+        let (address, prefix) = match raw.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (raw, None),
+        };
+        let network = address
+            .parse::<IpAddr>()
+            .map(canonical)
+            .map_err(|_| format!("'{raw}' is not an IP address or CIDR block"))?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max)
+                .ok_or_else(|| format!("'{raw}' has a prefix length above {max}"))?,
+            None => max,
+        };
+        Ok(Self { network, prefix })
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix)
+    }
+}
+
+fn prefix_matches(network: &[u8], ip: &[u8], prefix: u8) -> bool {
+    let full = usize::from(prefix / 8);
+    let rest = prefix % 8;
+    if network[..full] != ip[..full] {
+        return false;
+    }
+    rest == 0 || {
+        let mask = 0xff_u8 << (8 - rest);
+        network[full] & mask == ip[full] & mask
+    }
+}
+
+/// IPv4 peers on a dual-stack socket arrive as `::ffff:a.b.c.d`.
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        IpAddr::V4(_) => ip,
+    }
+}
+
+/// The client's address as resolved by [`resolve_client_ip`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClientIp(pub IpAddr);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let resolved = parts.extensions.get::<ClientIp>().copied();
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(peer)| ClientIp(peer.ip()));
+        Ok(resolved
+            .or(peer)
+            .unwrap_or(ClientIp(IpAddr::from([0, 0, 0, 0]))))
+    }
+}
+
+/// Router middleware; runs before logging and the rate limiters so they
+/// all see the same address.
+pub async fn resolve_client_ip(
+    State(trusted): State<Arc<Vec<Cidr>>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let client = client_ip(peer.ip(), request.headers(), &trusted);
+    request.extensions_mut().insert(ClientIp(client));
+    next.run(request).await
+}
+
+/// Walks the proxy chain from the nearest hop outwards and stops at the
+/// first address that is not a trusted proxy. `Forwarded` wins over
+/// `X-Forwarded-For` when both are present.
+fn client_ip(peer: IpAddr, headers: &HeaderMap, trusted: &[Cidr]) -> IpAddr {
+    let is_trusted = |ip: IpAddr| trusted.iter().any(|cidr| cidr.contains(ip));
+    if !is_trusted(peer) {
+        return peer;
+    }
+    let hops = forwarded_hops(headers);
+    let mut client = peer;
+    for hop in hops.iter().rev() {
+        match hop {
+            Some(ip) => {
+                client = canonical(*ip);
+                if !is_trusted(client) {
+                    break;
+                }
+            }
+            // An obfuscated or unparseable hop ends the chain we can vouch for.
+            None => break,
+        }
+    }
+    client
+}
+
+/// Addresses listed by the proxies, client first; `None` for entries that
+/// are not plain IP addresses.
+fn forwarded_hops(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    let forwarded = header_values(headers, header::FORWARDED.as_str());
+    if !forwarded.is_empty() {
+        return forwarded
+            .iter()
+            .flat_map(|value| value.split(','))
+            .filter_map(|element| {
+                element.split(';').find_map(|pair| {
+                    let (key, value) = pair.trim().split_once('=')?;
+                    key.eq_ignore_ascii_case("for")
+                        .then(|| parse_node(value.trim().trim_matches('"')))
+                })
+            })
+            .collect();
+    }
+    header_values(headers, "x-forwarded-for")
+        .iter()
+        .flat_map(|value| value.split(','))
+        .map(|value| parse_node(value.trim()))
+        .collect()
+}
+
+fn header_values(headers: &HeaderMap, name: &str) -> Vec<String> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .map(str::to_string)
+        .collect()
+}
+
+/// `203.0.113.7`, `203.0.113.7:4711`, `[2001:db8::1]`, or
+/// `[2001:db8::1]:4711`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    if let Ok(ip) = node.parse::<IpAddr>() {
+        return Some(ip);
+    }
+    if let Ok(address) = node.parse::<SocketAddr>() {
+        return Some(address.ip());
+    }
+    node.strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .and_then(|ip| ip.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use axum::http::{HeaderMap, HeaderValue};
+
+    use super::{client_ip, Cidr};
+
+    fn ip(raw: &str) -> IpAddr {
+        raw.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_and_matches_cidr_blocks() {
+        let private: Cidr = "10.0.0.0/8".parse().unwrap();
+        assert!(private.contains(ip("10.200.1.2")));
+        assert!(private.contains(ip("::ffff:10.0.0.1")));
+        assert!(!private.contains(ip("11.0.0.1")));
+        let odd: Cidr = "192.168.4.0/22".parse().unwrap();
+        assert!(odd.contains(ip("192.168.7.255")));
+        assert!(!odd.contains(ip("192.168.8.0")));
+        let loopback: Cidr = "::1".parse().unwrap();
+        assert!(loopback.contains(ip("::1")));
+        assert_eq!(loopback.to_string(), "::1/128");
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!("localhost".parse::<Cidr>().is_err());
+    }
+
+    #[test]
+    fn trusts_forwarded_headers_only_from_trusted_proxies() {
+        let trusted = vec!["127.0.0.1".parse().unwrap(), "10.0.0.0/8".parse().unwrap()];
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("198.51.100.9, 203.0.113.7, 10.0.0.5"),
+        );
+        assert_eq!(
+            client_ip(ip("127.0.0.1"), &headers, &trusted),
+            ip("203.0.113.7")
+        );
+        assert_eq!(
+            client_ip(ip("198.51.100.1"), &headers, &trusted),
+            ip("198.51.100.1")
+        );
+        assert_eq!(client_ip(ip("127.0.0.1"), &headers, &[]), ip("127.0.0.1"));
+
+        headers.insert(
+            "forwarded",
+            HeaderValue::from_static("for=192.0.2.60;proto=https, for=\"[2001:db8::17]:4711\""),
+        );
+        assert_eq!(
+            client_ip(ip("10.1.1.1"), &headers, &trusted),
+            ip("2001:db8::17")
+        );
+        headers.insert("forwarded", HeaderValue::from_static("for=_hidden"));
+        assert_eq!(
+            client_ip(ip("10.1.1.1"), &headers, &trusted),
+            ip("10.1.1.1")
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! Side-by-side comparison of 2–5 minerals. `POST /api/reports/compare`
+//! (API token required) runs the analysis chain for each mineral under one
+//! shared request, adds comparative findings, and renders a single report
+//! from the built-in `comparison.*` templates into
+//! `data/comparisons/<run>/`.
+
+use anyhow::Context;
+use axum::{extract::State, http::HeaderMap, Json};
+use serde::Deserialize;
+
+use crate::{
+    agent::{run_agentic_chain, run_comparison_chain, MineralAttachments, Narrative},
+    api::require_api_token,
+    batch::requested_slugs,
+    get_mineral_with_related, lang_path,
+    models::ReportRequest,
+    require_visible, resolve_pdf_backend, resolve_report_language, resolve_report_units, AppError,
+    AppState, PdfApiResponse,
+};
+
+pub const COMPARE_MIN_MINERALS: usize = 2;
+pub const COMPARE_MAX_MINERALS: usize = 5;
+
+#[derive(Debug, Deserialize)]
+pub struct CompareRequest {
+    slugs: Vec<String>,
+    #[serde(flatten)]
+    report: ReportRequest,
+    #[serde(default)]
+    backend: Option<String>,
+}
+
+pub async fn compare_minerals(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CompareRequest>,
+) -> Result<Json<PdfApiResponse>, AppError> {
+    require_api_token(&state, &headers)?;
+    let slugs = requested_slugs(&request.slugs, COMPARE_MIN_MINERALS..=COMPARE_MAX_MINERALS)
+        .map_err(AppError::BadRequest)?;
+    if !request.report.template.trim().is_empty() {
+        return Err(AppError::BadRequest(
+            "custom report templates do not apply to comparison reports".to_string(),
+        ));
+    }
+    if request.report.llm_narrative {
+        return Err(AppError::BadRequest(
+            "'llm_narrative' does not apply to comparison reports".to_string(),
+        ));
+    }
+    let language = resolve_report_language(&state, &headers, &request.report)?;
+    let units = resolve_report_units(&headers, &request.report.units)?;
+    let backend = resolve_pdf_backend(&state, request.backend.as_deref())?;
+
+    let mut reports = Vec::new();
+    for slug in &slugs {
+        let (mineral, _) = get_mineral_with_related(&state, language, slug).await?;
+        require_visible(&state, &headers, slug, &mineral)?;
+        reports.push(run_agentic_chain(
+            &mineral,
+            &request.report,
+            Vec::new(),
+            MineralAttachments::default(),
+            state.classification,
+            language,
+        ));
+    }
+    let mut report = run_comparison_chain(reports, &request.report, language);
+    report.units = units;
+    let artifacts = state
+        .pdf_generator
+        .generate_comparison(&report, language, backend)
+        .await
+        .with_context(|| format!("failed to generate comparison of {}", slugs.join(", ")))?;
+
+    Ok(Json(PdfApiResponse {
+        run_id: artifacts.run_id,
+        pdf_path: lang_path::prefixed(&headers, &artifacts.pdf_path),
+        html_path: lang_path::prefixed(&headers, &artifacts.html_path),
+        signature_path: lang_path::prefixed(&headers, &artifacts.signature_path),
+        summary: report.summary,
+        narrative: Narrative::Rules.as_str(),
+        preset: String::new(),
+        emailed_to: None,
+    }))
+}
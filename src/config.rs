@@ -0,0 +1,1177 @@
+//! Startup settings. Values come from `minerals.toml` (or the file named by
+//! `MINERALS_CONFIG`), with environment variables taking precedence. Every
+//! setting is checked before the server starts and all problems are
+//! reported together, instead of silently falling back to defaults.
+
+use std::{
+    env,
+    fmt::Display,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use lettre::message::Mailbox;
+use serde::Deserialize;
+use tracing::info;
+
+use crate::{
+    agent::Classification,
+    client_ip::Cidr,
+    email::{parse_addresses, SmtpSecurity},
+    i18n::Language,
+    llm::LlmProviderKind,
+    llm::RetryPolicy,
+    llm_usage::TokenPrices,
+    mindat::MINDAT_BASE_URL,
+    pdf::PdfBackendKind,
+    rate_limit::RateLimit,
+};
+
+pub const DEFAULT_CONFIG_FILE: &str = "minerals.toml";
+
+pub struct Config {
+    pub port: u16,
+    /// Address readers reach the site at, without a trailing slash; used
+    /// for the mineral links printed on reports.
+    pub public_url: String,
+    /// `None` serves plain HTTP, as behind a reverse proxy.
+    pub tls: Option<TlsConfig>,
+    /// Proxies whose `X-Forwarded-For` / `Forwarded` headers name the
+    /// client; empty uses the connecting address as is.
+    pub trusted_proxies: Vec<Cidr>,
+    pub data_root: PathBuf,
+    /// Stylesheets, images, and report assets served under `/static`.
+    pub static_root: PathBuf,
+    /// UI string files (`<code>.json`); built-in copies fill in for any
+    /// that are missing.
+    pub locales_dir: PathBuf,
+    pub default_language: Language,
+    /// Environment only; secrets do not belong in the config file.
+    pub admin_password: Option<String>,
+    pub admin_credential_file: PathBuf,
+    pub api_tokens_file: PathBuf,
+    pub llm: LlmConfig,
+    pub pdf: PdfConfig,
+    pub mindat: MindatConfig,
+    pub inquiries: InquiryConfig,
+    pub email: EmailConfig,
+    pub classification: Classification,
+    pub translate_batch_interval: Duration,
+    /// Largest accepted photo upload, per image.
+    pub image_upload_max_mb: usize,
+    /// ImageMagick executable that turns HEIC and TIFF uploads into JPEG.
+    pub image_convert_bin: String,
+    /// Local command that removes photo backgrounds; without one the LLM
+    /// provider is asked.
+    pub background_removal_bin: Option<String>,
+    pub catalog_watch_debounce: Duration,
+    /// Typos a search term may have and still match; `0` turns fuzzy
+    /// matching off.
+    pub search_fuzzy_distance: usize,
+    pub readyz_check_llm: bool,
+    /// Serve the GraphQL playground on `GET /graphql`.
+    pub graphql_playground: bool,
+    pub shutdown_grace: Duration,
+    /// Days a deleted mineral stays in `data/trash`; `0` keeps it until
+    /// purged by hand.
+    pub trash_retention_days: u64,
+    /// `PUBLISH_WEBHOOK_URL`; minerals going live at their `publish_at`
+    /// are announced there as JSON.
+    pub publish_webhook_url: Option<String>,
+    /// Admin and visitor login, password change, and registration, per
+    /// client IP.
+    pub auth_rate_limit: RateLimit,
+    /// AI suggestions, publishing, and API writes (all call the LLM).
+    pub llm_rate_limit: RateLimit,
+    /// Inquiry forms on the mineral pages, per client IP.
+    pub inquiry_rate_limit: RateLimit,
+}
+
+pub struct TlsConfig {
+    /// PEM certificate chain, leaf first (certbot's `fullchain.pem`).
+    pub cert_file: PathBuf,
+    pub key_file: PathBuf,
+    /// Plain-HTTP port that redirects to `public_url` and answers ACME
+    /// challenges; no such listener when unset.
+    pub http_port: Option<u16>,
+    /// Directory an ACME client writes HTTP-01 challenge files into
+    /// (certbot's `--webroot-path`).
+    pub acme_webroot: Option<PathBuf>,
+}
+
+pub struct LlmConfig {
+    pub provider: LlmProviderKind,
+    pub model: String,
+    pub translation_model: String,
+    /// Read from the provider's key variable (`OPENAI_API_KEY`, ...).
+    pub api_key: Option<String>,
+    pub base_url: Option<String>,
+    pub timeout: Duration,
+    pub translation_timeout: Duration,
+    /// Languages translated at once when a mineral is published or edited.
+    pub translation_concurrency: usize,
+    /// Budget for all of a publish's translations; languages still pending
+    /// when it runs out fall back to English.
+    pub translation_deadline: Duration,
+    /// For the usage ledger's cost estimates.
+    pub prices: TokenPrices,
+    /// `LLM_MONTHLY_BUDGET_USD`; calls are refused once the month's
+    /// estimated cost reaches it.
+    pub monthly_budget_usd: Option<f64>,
+    pub retry: RetryPolicy,
+}
+
+pub struct MindatConfig {
+    /// `MINDAT_API_KEY`; the admin lookup is hidden when unset.
+    pub api_key: Option<String>,
+    pub base_url: String,
+}
+
+pub struct InquiryConfig {
+    /// `INQUIRY_WEBHOOK_URL`; each new inquiry is posted there as JSON.
+    pub webhook_url: Option<String>,
+}
+
+pub struct EmailConfig {
+    /// `SMTP_HOST`; mail is handed to `sendmail_bin` when unset.
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_security: SmtpSecurity,
+    /// `SMTP_USERNAME` and `SMTP_PASSWORD`, environment only.
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub from: Mailbox,
+    /// `ADMIN_EMAIL_TO`; told about new inquiries, failed PDF jobs, and an
+    /// exhausted LLM budget. Empty turns those notifications off.
+    pub admin_to: Vec<Mailbox>,
+    pub sendmail_bin: String,
+}
+
+pub struct PdfConfig {
+    pub backend: PdfBackendKind,
+    pub chromium_bin: String,
+    /// Ed25519 seed for `report.sig`; reports carry only a digest when unset.
+    pub signing_key_file: Option<PathBuf>,
+    /// Finished report runs kept per mineral; `0` keeps all.
+    pub retention: usize,
+    /// Admin report layouts, one folder each.
+    pub templates_dir: PathBuf,
+}
+
+/// Mirror of `minerals.toml`. Everything is optional; unknown keys are
+/// rejected so a typo does not silently leave a default in place.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    port: Option<u64>,
+    public_url: Option<String>,
+    trusted_proxies: Option<Vec<String>>,
+    data_root: Option<String>,
+    static_root: Option<String>,
+    locales_dir: Option<String>,
+    default_lang: Option<String>,
+    admin_credential_file: Option<String>,
+    api_tokens_file: Option<String>,
+    translate_batch_interval_ms: Option<u64>,
+    image_upload_max_mb: Option<u64>,
+    image_convert_bin: Option<String>,
+    background_removal_bin: Option<String>,
+    catalog_watch_debounce_ms: Option<u64>,
+    search_fuzzy_distance: Option<u64>,
+    readyz_check_llm: Option<bool>,
+    graphql_playground: Option<bool>,
+    shutdown_grace_secs: Option<u64>,
+    trash_retention_days: Option<u64>,
+    publish_webhook_url: Option<String>,
+    tls: FileTlsConfig,
+    llm: FileLlmConfig,
+    pdf: FilePdfConfig,
+    mindat: FileMindatConfig,
+    inquiries: FileInquiryConfig,
+    email: FileEmailConfig,
+    classification: FileClassificationConfig,
+    rate_limit: FileRateLimitConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileTlsConfig {
+    cert_file: Option<String>,
+    key_file: Option<String>,
+    http_port: Option<u64>,
+    acme_webroot: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileLlmConfig {
+    provider: Option<String>,
+    model: Option<String>,
+    translation_model: Option<String>,
+    base_url: Option<String>,
+    timeout_secs: Option<u64>,
+    translation_timeout_secs: Option<u64>,
+    translation_concurrency: Option<u64>,
+    translation_deadline_secs: Option<u64>,
+    price_prompt_per_mtok: Option<f64>,
+    price_completion_per_mtok: Option<f64>,
+    monthly_budget_usd: Option<f64>,
+    max_attempts: Option<u64>,
+    breaker_threshold: Option<u64>,
+    breaker_cooldown_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FilePdfConfig {
+    backend: Option<String>,
+    chromium_bin: Option<String>,
+    signing_key_file: Option<String>,
+    retention: Option<u64>,
+    templates_dir: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileMindatConfig {
+    base_url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileInquiryConfig {
+    webhook_url: Option<String>,
+    /// Older spellings of `email.admin_to` and `email.sendmail_bin`.
+    email_to: Option<String>,
+    sendmail_bin: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileEmailConfig {
+    smtp_host: Option<String>,
+    smtp_port: Option<u64>,
+    smtp_security: Option<String>,
+    from: Option<String>,
+    admin_to: Option<String>,
+    sendmail_bin: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileClassificationConfig {
+    hardness_cutoffs: Option<Vec<f64>>,
+    density_cutoffs: Option<Vec<f64>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileRateLimitConfig {
+    auth_per_minute: Option<u64>,
+    auth_burst: Option<u64>,
+    llm_per_minute: Option<u64>,
+    llm_burst: Option<u64>,
+    inquiry_per_minute: Option<u64>,
+    inquiry_burst: Option<u64>,
+}
+
+impl Config {
+    /// Reads the config file and the process environment. A missing
+    /// `minerals.toml` is fine; a missing file named by `MINERALS_CONFIG`
+    /// is not. Relative paths in the file are taken from the file's own
+    /// directory, so `/etc/minerals/minerals.toml` works from any working
+    /// directory.
+    pub fn load() -> Result<Self> {
+        let (path, explicit) = match env::var("MINERALS_CONFIG") {
+            Ok(path) => (PathBuf::from(path), true),
+            Err(_) => (PathBuf::from(DEFAULT_CONFIG_FILE), false),
+        };
+        let file = match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                info!("loading settings from {}", path.display());
+                parse_file(&text).with_context(|| format!("failed to parse {}", path.display()))?
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound && !explicit => FileConfig::default(),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", path.display()))
+            }
+        };
+        let file_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        resolve(file, &file_dir, |name| env::var(name).ok())
+    }
+}
+
+fn parse_file(text: &str) -> Result<FileConfig> {
+    Ok(toml::from_str(text)?)
+}
+
+/// Layers `env` over `file` (whose relative paths start at `file_dir`)
+/// and validates the result.
+fn resolve(
+    file: FileConfig,
+    file_dir: &Path,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<Config> {
+    let mut layers = Layers {
+        env,
+        file_dir: file_dir.to_path_buf(),
+        problems: Vec::new(),
+    };
+    let retry_defaults = RetryPolicy::default();
+
+    let port = layers.number("PORT", "port", file.port, 7979u16, 1);
+    let tls = resolve_tls(&mut layers, file.tls);
+    let public_url = layers
+        .text("PUBLIC_URL", file.public_url)
+        .map(|url| url.trim_end_matches('/').to_string())
+        .unwrap_or_else(|| {
+            let scheme = if tls.is_some() { "https" } else { "http" };
+            format!("{scheme}://localhost:{port}")
+        });
+    if !(public_url.starts_with("http://") || public_url.starts_with("https://")) {
+        layers.problem(
+            "PUBLIC_URL",
+            "public_url",
+            format!("'{public_url}' must start with http:// or https://"),
+        );
+    }
+    let trusted_proxies = layers.cidrs("TRUSTED_PROXIES", "trusted_proxies", file.trusted_proxies);
+    let data_root = layers.path("DATA_ROOT", "data_root", file.data_root, "data");
+    if data_root.exists() && !data_root.is_dir() {
+        layers.problem(
+            "DATA_ROOT",
+            "data_root",
+            format!("'{}' exists but is not a directory", data_root.display()),
+        );
+    }
+    let static_root = layers.path("STATIC_ROOT", "static_root", file.static_root, "static");
+    if !static_root.is_dir() {
+        layers.problem(
+            "STATIC_ROOT",
+            "static_root",
+            format!(
+                "'{}' is not a directory; point it at the repository's static/ folder",
+                static_root.display()
+            ),
+        );
+    }
+    let locales_dir = layers.path("LOCALES_DIR", "locales_dir", file.locales_dir, "locales");
+    let default_language = layers.choice(
+        "DEFAULT_LANG",
+        "default_lang",
+        file.default_lang,
+        Language::En,
+        Language::from_code,
+        Language::all().iter().map(|language| language.code()),
+    );
+
+    let provider = layers.choice(
+        "LLM_PROVIDER",
+        "llm.provider",
+        file.llm.provider,
+        LlmProviderKind::default(),
+        LlmProviderKind::from_code,
+        LlmProviderKind::all().iter().map(|kind| kind.as_str()),
+    );
+    // OPENAI_* model names predate LLM_PROVIDER and only apply to OpenAI.
+    let legacy_model = |name: &str| {
+        (provider == LlmProviderKind::OpenAi)
+            .then(|| layers.env(name))
+            .flatten()
+    };
+    let model = layers
+        .env("LLM_MODEL")
+        .or_else(|| legacy_model("OPENAI_MODEL"))
+        .or(file.llm.model)
+        .unwrap_or_else(|| provider.default_model().to_string());
+    let translation_model = layers
+        .env("LLM_TRANSLATION_MODEL")
+        .or_else(|| legacy_model("OPENAI_TRANSLATION_MODEL"))
+        .or(file.llm.translation_model)
+        .unwrap_or_else(|| model.clone());
+    let base_url = layers.text("LLM_BASE_URL", file.llm.base_url);
+    if let Some(url) = base_url.as_deref() {
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            layers.problem(
+                "LLM_BASE_URL",
+                "llm.base_url",
+                format!("'{url}' must start with http:// or https://"),
+            );
+        }
+    }
+    let default_prices = provider.default_prices();
+    let llm = LlmConfig {
+        provider,
+        api_key: provider.api_key_env().and_then(|name| layers.env(name)),
+        base_url,
+        timeout: Duration::from_secs(layers.number(
+            "LLM_TIMEOUT_SECS",
+            "llm.timeout_secs",
+            file.llm.timeout_secs,
+            90,
+            1,
+        )),
+        translation_timeout: Duration::from_secs(layers.number(
+            "LLM_TRANSLATION_TIMEOUT_SECS",
+            "llm.translation_timeout_secs",
+            file.llm.translation_timeout_secs,
+            30,
+            1,
+        )),
+        translation_concurrency: layers.number(
+            "LLM_TRANSLATION_CONCURRENCY",
+            "llm.translation_concurrency",
+            file.llm.translation_concurrency,
+            4,
+            1,
+        ),
+        translation_deadline: Duration::from_secs(layers.number(
+            "LLM_TRANSLATION_DEADLINE_SECS",
+            "llm.translation_deadline_secs",
+            file.llm.translation_deadline_secs,
+            90,
+            1,
+        )),
+        prices: TokenPrices {
+            prompt_per_mtok: layers
+                .decimal(
+                    "LLM_PRICE_PROMPT_PER_MTOK",
+                    "llm.price_prompt_per_mtok",
+                    file.llm.price_prompt_per_mtok,
+                )
+                .unwrap_or(default_prices.prompt_per_mtok),
+            completion_per_mtok: layers
+                .decimal(
+                    "LLM_PRICE_COMPLETION_PER_MTOK",
+                    "llm.price_completion_per_mtok",
+                    file.llm.price_completion_per_mtok,
+                )
+                .unwrap_or(default_prices.completion_per_mtok),
+        },
+        monthly_budget_usd: layers.decimal(
+            "LLM_MONTHLY_BUDGET_USD",
+            "llm.monthly_budget_usd",
+            file.llm.monthly_budget_usd,
+        ),
+        retry: RetryPolicy {
+            max_attempts: layers.number(
+                "LLM_MAX_ATTEMPTS",
+                "llm.max_attempts",
+                file.llm.max_attempts,
+                retry_defaults.max_attempts,
+                1,
+            ),
+            breaker_threshold: layers.number(
+                "LLM_BREAKER_THRESHOLD",
+                "llm.breaker_threshold",
+                file.llm.breaker_threshold,
+                retry_defaults.breaker_threshold,
+                1,
+            ),
+            breaker_cooldown: Duration::from_secs(layers.number(
+                "LLM_BREAKER_COOLDOWN_SECS",
+                "llm.breaker_cooldown_secs",
+                file.llm.breaker_cooldown_secs,
+                retry_defaults.breaker_cooldown.as_secs(),
+                0,
+            )),
+            ..retry_defaults
+        },
+        model,
+        translation_model,
+    };
+
+    let pdf = PdfConfig {
+        backend: layers.choice(
+            "PDF_BACKEND",
+            "pdf.backend",
+            file.pdf.backend,
+            PdfBackendKind::default(),
+            PdfBackendKind::from_code,
+            PdfBackendKind::all().iter().map(|kind| kind.as_str()),
+        ),
+        chromium_bin: layers
+            .text("CHROMIUM_BIN", file.pdf.chromium_bin)
+            .unwrap_or_else(|| "chromium".to_string()),
+        signing_key_file: layers
+            .optional_path("REPORT_SIGNING_KEY_FILE", file.pdf.signing_key_file),
+        retention: layers.number(
+            "REPORT_RETENTION",
+            "pdf.retention",
+            file.pdf.retention,
+            10,
+            0,
+        ),
+        templates_dir: layers.path(
+            "REPORT_TEMPLATES_DIR",
+            "pdf.templates_dir",
+            file.pdf.templates_dir,
+            "templates/reports",
+        ),
+    };
+
+    let mindat_base_url = layers
+        .text("MINDAT_BASE_URL", file.mindat.base_url)
+        .unwrap_or_else(|| MINDAT_BASE_URL.to_string());
+    if !(mindat_base_url.starts_with("http://") || mindat_base_url.starts_with("https://")) {
+        layers.problem(
+            "MINDAT_BASE_URL",
+            "mindat.base_url",
+            format!("'{mindat_base_url}' must start with http:// or https://"),
+        );
+    }
+    let mindat = MindatConfig {
+        api_key: layers.env("MINDAT_API_KEY"),
+        base_url: mindat_base_url,
+    };
+
+    let inquiry_webhook_url = layers.text("INQUIRY_WEBHOOK_URL", file.inquiries.webhook_url);
+    if let Some(url) = inquiry_webhook_url
+        .as_deref()
+        .filter(|url| !(url.starts_with("http://") || url.starts_with("https://")))
+    {
+        layers.problem(
+            "INQUIRY_WEBHOOK_URL",
+            "inquiries.webhook_url",
+            format!("'{url}' must start with http:// or https://"),
+        );
+    }
+    let inquiries = InquiryConfig {
+        webhook_url: inquiry_webhook_url,
+    };
+
+    let publish_webhook_url = layers.text("PUBLISH_WEBHOOK_URL", file.publish_webhook_url);
+    if let Some(url) = publish_webhook_url
+        .as_deref()
+        .filter(|url| !(url.starts_with("http://") || url.starts_with("https://")))
+    {
+        layers.problem(
+            "PUBLISH_WEBHOOK_URL",
+            "publish_webhook_url",
+            format!("'{url}' must start with http:// or https://"),
+        );
+    }
+
+    let smtp_security = layers.choice(
+        "SMTP_SECURITY",
+        "email.smtp_security",
+        file.email.smtp_security,
+        SmtpSecurity::default(),
+        SmtpSecurity::from_code,
+        SmtpSecurity::all().iter().map(|security| security.as_str()),
+    );
+    let email_from = layers
+        .text("EMAIL_FROM", file.email.from)
+        .unwrap_or_else(|| "Minerals <minerals@localhost>".to_string());
+    let from = email_from.parse::<Mailbox>().unwrap_or_else(|err| {
+        layers.problem(
+            "EMAIL_FROM",
+            "email.from",
+            format!("'{email_from}' is not an email address: {err}"),
+        );
+        Mailbox::new(None, "minerals@localhost".parse().expect("valid address"))
+    });
+    // INQUIRY_EMAIL_TO predates ADMIN_EMAIL_TO and only ever reached admins.
+    let admin_to = match layers
+        .text("ADMIN_EMAIL_TO", file.email.admin_to)
+        .or_else(|| layers.text("INQUIRY_EMAIL_TO", file.inquiries.email_to))
+    {
+        Some(raw) => parse_addresses(&raw).unwrap_or_else(|err| {
+            layers.problem("ADMIN_EMAIL_TO", "email.admin_to", format!("{err:#}"));
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    let email = EmailConfig {
+        smtp_host: layers.text("SMTP_HOST", file.email.smtp_host),
+        smtp_port: layers.number(
+            "SMTP_PORT",
+            "email.smtp_port",
+            file.email.smtp_port,
+            smtp_security.default_port(),
+            1,
+        ),
+        smtp_security,
+        smtp_username: layers.env("SMTP_USERNAME"),
+        smtp_password: layers.env("SMTP_PASSWORD"),
+        from,
+        admin_to,
+        sendmail_bin: layers
+            .text(
+                "SENDMAIL_BIN",
+                file.email.sendmail_bin.or(file.inquiries.sendmail_bin),
+            )
+            .unwrap_or_else(|| "sendmail".to_string()),
+    };
+
+    let classification_defaults = Classification::default();
+    let classification = Classification {
+        hardness: layers.cutoffs(
+            "HARDNESS_BAND_CUTOFFS",
+            "classification.hardness_cutoffs",
+            file.classification.hardness_cutoffs,
+            classification_defaults.hardness,
+        ),
+        density: layers.cutoffs(
+            "DENSITY_BAND_CUTOFFS",
+            "classification.density_cutoffs",
+            file.classification.density_cutoffs,
+            classification_defaults.density,
+        ),
+    };
+
+    let config = Config {
+        port,
+        public_url,
+        tls,
+        trusted_proxies,
+        default_language,
+        admin_password: layers.env("ADMIN_PASSWORD"),
+        admin_credential_file: layers.path(
+            "ADMIN_CREDENTIAL_FILE",
+            "admin_credential_file",
+            file.admin_credential_file,
+            ".admin_credential",
+        ),
+        api_tokens_file: layers.path(
+            "API_TOKENS_FILE",
+            "api_tokens_file",
+            file.api_tokens_file,
+            ".api_tokens.json",
+        ),
+        llm,
+        pdf,
+        mindat,
+        inquiries,
+        email,
+        classification,
+        translate_batch_interval: Duration::from_millis(layers.number(
+            "TRANSLATE_BATCH_INTERVAL_MS",
+            "translate_batch_interval_ms",
+            file.translate_batch_interval_ms,
+            1500,
+            0,
+        )),
+        image_upload_max_mb: layers.number(
+            "IMAGE_UPLOAD_MAX_MB",
+            "image_upload_max_mb",
+            file.image_upload_max_mb,
+            20,
+            1,
+        ),
+        image_convert_bin: layers
+            .text("IMAGE_CONVERT_BIN", file.image_convert_bin)
+            .unwrap_or_else(|| "convert".to_string()),
+        background_removal_bin: layers.text("BACKGROUND_REMOVAL_BIN", file.background_removal_bin),
+        catalog_watch_debounce: Duration::from_millis(layers.number(
+            "CATALOG_WATCH_DEBOUNCE_MS",
+            "catalog_watch_debounce_ms",
+            file.catalog_watch_debounce_ms,
+            500,
+            1,
+        )),
+        search_fuzzy_distance: layers.number(
+            "SEARCH_FUZZY_DISTANCE",
+            "search_fuzzy_distance",
+            file.search_fuzzy_distance,
+            1,
+            0,
+        ),
+        readyz_check_llm: layers.flag(
+            "READYZ_CHECK_LLM",
+            "readyz_check_llm",
+            file.readyz_check_llm,
+        ),
+        graphql_playground: layers.flag(
+            "GRAPHQL_PLAYGROUND",
+            "graphql_playground",
+            file.graphql_playground,
+        ),
+        shutdown_grace: Duration::from_secs(layers.number(
+            "SHUTDOWN_GRACE_SECS",
+            "shutdown_grace_secs",
+            file.shutdown_grace_secs,
+            30,
+            0,
+        )),
+        trash_retention_days: layers.number(
+            "TRASH_RETENTION_DAYS",
+            "trash_retention_days",
+            file.trash_retention_days,
+            30,
+            0,
+        ),
+        publish_webhook_url,
+        auth_rate_limit: RateLimit {
+            per_minute: layers.number(
+                "RATE_LIMIT_AUTH_PER_MINUTE",
+                "rate_limit.auth_per_minute",
+                file.rate_limit.auth_per_minute,
+                10,
+                0,
+            ),
+            burst: layers.number(
+                "RATE_LIMIT_AUTH_BURST",
+                "rate_limit.auth_burst",
+                file.rate_limit.auth_burst,
+                5,
+                1,
+            ),
+        },
+        llm_rate_limit: RateLimit {
+            per_minute: layers.number(
+                "RATE_LIMIT_LLM_PER_MINUTE",
+                "rate_limit.llm_per_minute",
+                file.rate_limit.llm_per_minute,
+                10,
+                0,
+            ),
+            burst: layers.number(
+                "RATE_LIMIT_LLM_BURST",
+                "rate_limit.llm_burst",
+                file.rate_limit.llm_burst,
+                5,
+                1,
+            ),
+        },
+        inquiry_rate_limit: RateLimit {
+            per_minute: layers.number(
+                "RATE_LIMIT_INQUIRY_PER_MINUTE",
+                "rate_limit.inquiry_per_minute",
+                file.rate_limit.inquiry_per_minute,
+                5,
+                0,
+            ),
+            burst: layers.number(
+                "RATE_LIMIT_INQUIRY_BURST",
+                "rate_limit.inquiry_burst",
+                file.rate_limit.inquiry_burst,
+                3,
+                1,
+            ),
+        },
+        data_root,
+        static_root,
+        locales_dir,
+    };
+
+    if !layers.problems.is_empty() {
+        bail!(
+            "invalid configuration:\n  - {}",
+            layers.problems.join("\n  - ")
+        );
+    }
+    Ok(config)
+}
+
+/// Certificate and key are given together or not at all; the ACME
+/// webroot is only reachable through the plain-HTTP listener.
+fn resolve_tls<E: Fn(&str) -> Option<String>>(
+    layers: &mut Layers<E>,
+    file: FileTlsConfig,
+) -> Option<TlsConfig> {
+    let cert_file = layers.optional_path("TLS_CERT_FILE", file.cert_file);
+    let key_file = layers.optional_path("TLS_KEY_FILE", file.key_file);
+    let http_port = (layers.env("TLS_HTTP_PORT").is_some() || file.http_port.is_some())
+        .then(|| layers.number("TLS_HTTP_PORT", "tls.http_port", file.http_port, 80u16, 1));
+    let acme_webroot = layers.optional_path("ACME_WEBROOT", file.acme_webroot);
+    for (name, key, path) in [
+        ("TLS_CERT_FILE", "tls.cert_file", &cert_file),
+        ("TLS_KEY_FILE", "tls.key_file", &key_file),
+    ] {
+        if let Some(path) = path.as_ref().filter(|path| !path.is_file()) {
+            layers.problem(name, key, format!("'{}' is not a file", path.display()));
+        }
+    }
+    if let Some(webroot) = acme_webroot.as_ref().filter(|path| !path.is_dir()) {
+        layers.problem(
+            "ACME_WEBROOT",
+            "tls.acme_webroot",
+            format!("'{}' is not a directory", webroot.display()),
+        );
+    }
+    match (cert_file, key_file) {
+        (Some(cert_file), Some(key_file)) => {
+            if acme_webroot.is_some() && http_port.is_none() {
+                layers.problem(
+                    "ACME_WEBROOT",
+                    "tls.acme_webroot",
+                    "needs TLS_HTTP_PORT for the challenge listener",
+                );
+            }
+            Some(TlsConfig {
+                cert_file,
+                key_file,
+                http_port,
+                acme_webroot,
+            })
+        }
+        (None, None) => {
+            if http_port.is_some() || acme_webroot.is_some() {
+                layers.problem(
+                    "TLS_CERT_FILE",
+                    "tls.cert_file",
+                    "TLS_HTTP_PORT and ACME_WEBROOT need a certificate and key",
+                );
+            }
+            None
+        }
+        (Some(_), None) => {
+            layers.problem(
+                "TLS_KEY_FILE",
+                "tls.key_file",
+                "required with TLS_CERT_FILE",
+            );
+            None
+        }
+        (None, Some(_)) => {
+            layers.problem(
+                "TLS_CERT_FILE",
+                "tls.cert_file",
+                "required with TLS_KEY_FILE",
+            );
+            None
+        }
+    }
+}
+
+/// Resolves one setting at a time from the environment, then the file,
+/// then the default, collecting problems instead of stopping at the first.
+struct Layers<E> {
+    env: E,
+    file_dir: PathBuf,
+    problems: Vec<String>,
+}
+
+impl<E: Fn(&str) -> Option<String>> Layers<E> {
+    /// Blank variables count as unset, as they do in `.env` files.
+    fn env(&self, name: &str) -> Option<String> {
+        (self.env)(name)
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+
+    fn problem(&mut self, name: &str, key: &str, detail: impl Display) {
+        self.problems.push(format!("{key} / {name}: {detail}"));
+    }
+
+    fn text(&self, name: &str, file: Option<String>) -> Option<String> {
+        self.env(name)
+            .or(file.map(|value| value.trim().to_string()))
+            .filter(|value| !value.is_empty())
+    }
+
+    /// Environment paths are relative to the working directory, file paths
+    /// to the config file.
+    fn path(&mut self, name: &str, key: &str, file: Option<String>, default: &str) -> PathBuf {
+        if let Some(value) = self.env(name) {
+            return PathBuf::from(value);
+        }
+        match file {
+            Some(value) if value.trim().is_empty() => {
+                self.problem(name, key, "must not be empty");
+                PathBuf::from(default)
+            }
+            Some(value) => self.file_dir.join(value.trim()),
+            None => PathBuf::from(default),
+        }
+    }
+
+    /// Like [`Self::path`] for settings that may be left unset.
+    fn optional_path(&self, name: &str, file: Option<String>) -> Option<PathBuf> {
+        match self.env(name) {
+            Some(value) => Some(PathBuf::from(value)),
+            None => self.text(name, file).map(|value| self.file_dir.join(value)),
+        }
+    }
+
+    fn number<T>(&mut self, name: &str, key: &str, file: Option<u64>, default: T, min: u64) -> T
+    where
+        T: TryFrom<u64>,
+    {
+        let value = match self.env(name) {
+            Some(raw) => match raw.parse::<u64>() {
+                Ok(value) => value,
+                Err(_) => {
+                    self.problem(name, key, format!("'{raw}' is not a whole number"));
+                    return default;
+                }
+            },
+            None => match file {
+                Some(value) => value,
+                None => return default,
+            },
+        };
+        if value < min {
+            self.problem(name, key, format!("{value} is below the minimum of {min}"));
+            return default;
+        }
+        T::try_from(value).unwrap_or_else(|_| {
+            self.problem(name, key, format!("{value} is too large"));
+            default
+        })
+    }
+
+    /// A non-negative decimal such as a price; `None` when unset or invalid.
+    fn decimal(&mut self, name: &str, key: &str, file: Option<f64>) -> Option<f64> {
+        let value = match self.env(name) {
+            Some(raw) => match raw.trim().parse::<f64>() {
+                Ok(value) => value,
+                Err(_) => {
+                    self.problem(name, key, format!("'{raw}' is not a number"));
+                    return None;
+                }
+            },
+            None => file?,
+        };
+        if !value.is_finite() || value < 0.0 {
+            self.problem(name, key, format!("{value} must be zero or more"));
+            return None;
+        }
+        Some(value)
+    }
+
+    fn flag(&mut self, name: &str, key: &str, file: Option<bool>) -> bool {
+        let Some(raw) = self.env(name) else {
+            return file.unwrap_or(false);
+        };
+        match raw.to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => true,
+            "0" | "false" | "no" | "off" => false,
+            _ => {
+                self.problem(name, key, format!("'{raw}' is not true or false"));
+                false
+            }
+        }
+    }
+
+    /// `N` ascending positive numbers; the environment lists them
+    /// comma-separated, e.g. `3,6,7.5`.
+    fn cutoffs<const N: usize>(
+        &mut self,
+        name: &str,
+        key: &str,
+        file: Option<Vec<f64>>,
+        default: [f32; N],
+    ) -> [f32; N] {
+        let values = match self.env(name) {
+            Some(raw) => {
+                match raw
+                    .split(',')
+                    .map(|value| value.trim().parse::<f64>())
+                    .collect::<Result<Vec<_>, _>>()
+                {
+                    Ok(values) => values,
+                    Err(_) => {
+                        self.problem(name, key, format!("'{raw}' is not a list of numbers"));
+                        return default;
+                    }
+                }
+            }
+            None => match file {
+                Some(values) => values,
+                None => return default,
+            },
+        };
+        let ascending = values.windows(2).all(|pair| pair[0] < pair[1]);
+        let positive = values.iter().all(|value| value.is_finite() && *value > 0.0);
+        match <[f64; N]>::try_from(values) {
+            Ok(values) if ascending && positive => values.map(|value| value as f32),
+            _ => {
+                self.problem(
+                    name,
+                    key,
+                    format!("expected {N} positive numbers in ascending order"),
+                );
+                default
+            }
+        }
+    }
+
+    /// Addresses or CIDR blocks; the environment lists them
+    /// comma-separated, e.g. `127.0.0.1, 10.0.0.0/8`.
+    fn cidrs(&mut self, name: &str, key: &str, file: Option<Vec<String>>) -> Vec<Cidr> {
+        let raw = match self.env(name) {
+            Some(raw) => raw.split(',').map(str::to_string).collect(),
+            None => file.unwrap_or_default(),
+        };
+        raw.iter()
+            .filter(|value| !value.trim().is_empty())
+            .filter_map(|value| {
+                value
+                    .parse()
+                    .map_err(|err| self.problem(name, key, err))
+                    .ok()
+            })
+            .collect()
+    }
+
+    fn choice<'a, T>(
+        &mut self,
+        name: &str,
+        key: &str,
+        file: Option<String>,
+        default: T,
+        parse: impl Fn(&str) -> Option<T>,
+        expected: impl Iterator<Item = &'a str>,
+    ) -> T {
+        let Some(raw) = self.text(name, file) else {
+            return default;
+        };
+        parse(&raw).unwrap_or_else(|| {
+            let expected = expected.collect::<Vec<_>>().join(", ");
+            self.problem(
+                name,
+                key,
+                format!("'{raw}' is not supported (expected one of: {expected})"),
+            );
+            default
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::Path};
+
+    use super::{parse_file, resolve};
+    use crate::{i18n::Language, llm::LlmProviderKind, pdf::PdfBackendKind};
+
+    #[test]
+    fn takes_file_paths_from_the_config_file_directory() {
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let file = parse_file(
+            r#"
+            data_root = "data"
+            static_root = "static"
+            api_tokens_file = "secrets/tokens.json"
+            "#,
+        )
+        .unwrap();
+        let config = resolve(file, manifest_dir, |_| None).unwrap();
+        assert_eq!(config.data_root, manifest_dir.join("data"));
+        assert_eq!(config.static_root, manifest_dir.join("static"));
+        assert_eq!(
+            config.api_tokens_file,
+            manifest_dir.join("secrets/tokens.json")
+        );
+
+        let env = HashMap::from([
+            ("DATA_ROOT", "relative/data"),
+            ("STATIC_ROOT", "/nonexistent/static"),
+        ]);
+        let file = parse_file(r#"static_root = "static""#).unwrap();
+        let message = resolve(file, manifest_dir, |name| {
+            env.get(name).map(|value| value.to_string())
+        })
+        .err()
+        .unwrap()
+        .to_string();
+        assert!(message.contains("STATIC_ROOT"), "{message}");
+        assert!(message.contains("/nonexistent/static"), "{message}");
+        assert!(!message.contains("DATA_ROOT"), "{message}");
+    }
+
+    #[test]
+    fn layers_env_over_file_and_reports_every_problem() {
+        let file = parse_file(
+            r#"
+            port = 8080
+            public_url = "https://minerals.example.org/"
+            trusted_proxies = ["127.0.0.1", "10.0.0.0/8"]
+            default_lang = "es"
+            api_tokens_file = "tokens.json"
+
+            [llm]
+            provider = "ollama"
+            model = "llama3.1"
+            translation_concurrency = 2
+
+            [pdf]
+            backend = "typst"
+
+            [inquiries]
+            email_to = "sales@example.org"
+
+            [email]
+            smtp_security = "tls"
+
+            [classification]
+            hardness_cutoffs = [2.5, 5.5, 7]
+            "#,
+        )
+        .unwrap();
+        let env = HashMap::from([
+            ("PORT", "9090"),
+            ("PDF_BACKEND", " Chromium "),
+            ("DENSITY_BAND_CUTOFFS", "2.5, 3"),
+            ("DATA_ROOT", "/srv/minerals/data"),
+            (
+                "STATIC_ROOT",
+                concat!(env!("CARGO_MANIFEST_DIR"), "/static"),
+            ),
+        ]);
+        let config = resolve(file, Path::new("/etc/minerals"), |name| {
+            env.get(name).map(|value| value.to_string())
+        })
+        .unwrap();
+        assert_eq!(config.data_root, Path::new("/srv/minerals/data"));
+        assert_eq!(
+            config.api_tokens_file,
+            Path::new("/etc/minerals/tokens.json")
+        );
+        assert_eq!(config.port, 9090);
+        assert_eq!(config.public_url, "https://minerals.example.org");
+        assert!(config.tls.is_none());
+        assert_eq!(config.trusted_proxies.len(), 2);
+        assert_eq!(config.default_language, Language::Es);
+        assert_eq!(config.llm.provider, LlmProviderKind::Ollama);
+        assert_eq!(config.llm.translation_model, "llama3.1");
+        assert_eq!(config.llm.translation_concurrency, 2);
+        assert_eq!(config.pdf.backend, PdfBackendKind::Chromium);
+        assert_eq!(config.classification.hardness, [2.5, 5.5, 7.0]);
+        assert_eq!(config.classification.density, [2.5, 3.0]);
+        assert_eq!(config.email.smtp_port, 465);
+        assert_eq!(config.email.admin_to.len(), 1);
+        assert_eq!(config.email.from.email.to_string(), "minerals@localhost");
+
+        assert!(parse_file("prot = 8080").is_err());
+
+        let env = HashMap::from([
+            ("PORT", "0"),
+            ("DEFAULT_LANG", "xx"),
+            ("LLM_MAX_ATTEMPTS", "many"),
+            ("LLM_BASE_URL", "localhost:11434"),
+            ("PUBLIC_URL", "minerals.example.org"),
+            ("HARDNESS_BAND_CUTOFFS", "6,3,7.5"),
+            ("ADMIN_EMAIL_TO", "ops@example.org, ops"),
+            ("TLS_CERT_FILE", "/nonexistent/fullchain.pem"),
+            ("TRUSTED_PROXIES", "127.0.0.1, proxy.internal"),
+        ]);
+        let message = resolve(Default::default(), Path::new(""), |name| {
+            env.get(name).map(|value| value.to_string())
+        })
+        .err()
+        .unwrap()
+        .to_string();
+        for setting in [
+            "PORT",
+            "DEFAULT_LANG",
+            "LLM_MAX_ATTEMPTS",
+            "LLM_BASE_URL",
+            "PUBLIC_URL",
+            "HARDNESS_BAND_CUTOFFS",
+            "ADMIN_EMAIL_TO",
+            "TLS_CERT_FILE",
+            "TLS_KEY_FILE",
+            "TRUSTED_PROXIES",
+        ] {
+            assert!(
+                message.contains(setting),
+                "{setting} missing from: {message}"
+            );
+        }
+    }
+}
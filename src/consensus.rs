@@ -0,0 +1,496 @@
+//! Merging the AI suggestions made for each photo of a specimen into one.
+//! Text fields take the answer most photos agree on, ignoring case and
+//! spacing; numbers take the median. How many photos agreed, and how sure
+//! the model said it was, is kept per field, so the admin form can point at
+//! the shaky values. The identifications that lost become [`Candidate`]s
+//! the operator can switch to.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{AiAlternative, AiMineralSuggestion};
+
+/// Hardness answers this close to the median count as agreeing.
+const HARDNESS_TOLERANCE: f32 = 0.5;
+/// Density answers this close (g/cm³) to the median count as agreeing.
+const DENSITY_TOLERANCE: f32 = 0.2;
+/// Model confidence below which a field is flagged for review.
+const LOW_CONFIDENCE: f32 = 0.5;
+/// Alternatives kept after the suggestion itself.
+const MAX_ALTERNATIVES: usize = 3;
+
+/// How many of the analysed photos agreed on one suggested field, and the
+/// model's mean confidence in it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FieldConfidence {
+    pub field: String,
+    pub agreeing: usize,
+    pub total: usize,
+    /// From 0 to 1; `None` in drafts made before the model reported it.
+    #[serde(default)]
+    pub score: Option<f32>,
+}
+
+impl FieldConfidence {
+    pub fn label(&self) -> &str {
+        match self.field.as_str() {
+            "common_name" => "Common name",
+            "mineral_family" => "Family",
+            "formula" => "Formula",
+            "hardness_mohs" => "Hardness",
+            "density_g_cm3" => "Density",
+            "crystal_system" => "Crystal system",
+            "color" => "Color",
+            "streak" => "Streak",
+            "luster" => "Luster",
+            other => other,
+        }
+    }
+
+    pub fn is_unanimous(&self) -> bool {
+        self.agreeing == self.total
+    }
+
+    /// Photos disagreed, or the model was unsure.
+    pub fn is_doubtful(&self) -> bool {
+        !self.is_unanimous() || self.score.is_some_and(|score| score < LOW_CONFIDENCE)
+    }
+
+    /// `Formula 2/3 · 85%`; the photo count only when several were compared.
+    pub fn summary(&self) -> String {
+        let mut summary = self.label().to_string();
+        if self.total > 1 {
+            summary.push_str(&format!(" {}/{}", self.agreeing, self.total));
+        }
+        if let Some(score) = self.score {
+            summary.push_str(&format!(" · {}", percent(score)));
+        }
+        summary
+    }
+}
+
+/// One identification the admin form can switch to: the suggestion itself,
+/// or an alternative ranked below it. Numbers are formatted as the form
+/// shows them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Candidate {
+    pub common_name: String,
+    pub description: String,
+    pub mineral_family: String,
+    pub formula: String,
+    pub hardness_mohs: String,
+    pub density_g_cm3: String,
+    pub crystal_system: String,
+    pub color: String,
+    pub streak: String,
+    pub luster: String,
+    pub confidence: Option<f32>,
+}
+
+impl Candidate {
+    /// `Quartz · 85%`.
+    pub fn label(&self) -> String {
+        match self.confidence {
+            Some(confidence) => format!("{} · {}", self.common_name, percent(confidence)),
+            None => self.common_name.clone(),
+        }
+    }
+}
+
+impl From<&AiAlternative> for Candidate {
+    fn from(alternative: &AiAlternative) -> Self {
+        Self {
+            common_name: alternative.common_name.clone(),
+            description: alternative.description.clone(),
+            mineral_family: alternative.mineral_family.clone(),
+            formula: alternative.formula.clone(),
+            hardness_mohs: format!("{:.2}", alternative.hardness_mohs),
+            density_g_cm3: format!("{:.2}", alternative.density_g_cm3),
+            crystal_system: alternative.crystal_system.clone(),
+            color: alternative.color.clone(),
+            streak: alternative.streak.clone(),
+            luster: alternative.luster.clone(),
+            confidence: Some(alternative.confidence.clamp(0.0, 1.0)),
+        }
+    }
+}
+
+/// The suggestion followed by its alternatives, in the order the admin form
+/// lists them.
+pub fn candidates(suggestion: &AiMineralSuggestion) -> Vec<Candidate> {
+    let chosen = Candidate {
+        confidence: suggestion
+            .confidence
+            .get("common_name")
+            .map(|score| score.clamp(0.0, 1.0)),
+        ..Candidate::from(&as_alternative(suggestion))
+    };
+    std::iter::once(chosen)
+        .chain(suggestion.alternatives.iter().map(Candidate::from))
+        .collect()
+}
+
+/// One suggestion from several, with the agreement behind each field.
+/// Description, notes, and composition come from a photo whose answer won
+/// the name and formula votes, so they describe the chosen mineral. `None`
+/// when there is nothing to merge.
+pub fn merge(
+    mut suggestions: Vec<AiMineralSuggestion>,
+) -> Option<(AiMineralSuggestion, Vec<FieldConfidence>)> {
+    let total = suggestions.len();
+    if total == 0 {
+        return None;
+    }
+    let scores = |field: &str| {
+        let reported = suggestions
+            .iter()
+            .filter_map(|s| s.confidence.get(field))
+            .map(|score| score.clamp(0.0, 1.0))
+            .collect::<Vec<_>>();
+        (!reported.is_empty()).then(|| reported.iter().sum::<f32>() / reported.len() as f32)
+    };
+    let mut confidence = Vec::new();
+    let mut text = |field: &str, pick: fn(&AiMineralSuggestion) -> &str| {
+        let (winner, agreeing) = vote(suggestions.iter().map(pick));
+        confidence.push(FieldConfidence {
+            field: field.to_string(),
+            agreeing,
+            total,
+            score: scores(field),
+        });
+        winner
+    };
+    let name = text("common_name", |s| &s.common_name);
+    let family = text("mineral_family", |s| &s.mineral_family);
+    let formula = text("formula", |s| &s.formula);
+    let crystal_system = text("crystal_system", |s| &s.crystal_system);
+    let color = text("color", |s| &s.color);
+    let streak = text("streak", |s| &s.streak);
+    let luster = text("luster", |s| &s.luster);
+
+    let mut number = |field: &str, values: Vec<f32>, tolerance: f32| {
+        let median = median(values.clone());
+        confidence.push(FieldConfidence {
+            field: field.to_string(),
+            agreeing: values
+                .iter()
+                .filter(|value| (*value - median).abs() <= tolerance)
+                .count(),
+            total,
+            score: scores(field),
+        });
+        median
+    };
+    let hardness = number(
+        "hardness_mohs",
+        suggestions.iter().map(|s| s.hardness_mohs).collect(),
+        HARDNESS_TOLERANCE,
+    );
+    let density = number(
+        "density_g_cm3",
+        suggestions.iter().map(|s| s.density_g_cm3).collect(),
+        DENSITY_TOLERANCE,
+    );
+    confidence.sort_by_key(|entry| field_order(&entry.field));
+
+    let take = |index: usize, suggestions: &[AiMineralSuggestion]| suggestions[index].clone();
+    let alternatives = rank_alternatives(&suggestions, &suggestions[name].common_name);
+    let named = take(name, &suggestions);
+    let composition = std::mem::take(&mut suggestions[formula].major_elements);
+    let merged = AiMineralSuggestion {
+        mineral_family: take(family, &suggestions).mineral_family,
+        formula: take(formula, &suggestions).formula,
+        hardness_mohs: hardness,
+        density_g_cm3: density,
+        crystal_system: take(crystal_system, &suggestions).crystal_system,
+        color: take(color, &suggestions).color,
+        streak: take(streak, &suggestions).streak,
+        luster: take(luster, &suggestions).luster,
+        major_elements: composition,
+        confidence: confidence
+            .iter()
+            .filter_map(|entry| Some((entry.field.clone(), entry.score?)))
+            .collect(),
+        alternatives,
+        ..named
+    };
+    Some((merged, confidence))
+}
+
+/// The alternatives of every photo, plus the identifications that lost the
+/// name vote, once per name and most confident first.
+fn rank_alternatives(suggestions: &[AiMineralSuggestion], chosen: &str) -> Vec<AiAlternative> {
+    let mut ranked: Vec<AiAlternative> = Vec::new();
+    let proposals = suggestions.iter().map(as_alternative).chain(
+        suggestions
+            .iter()
+            .flat_map(|s| s.alternatives.iter().cloned()),
+    );
+    for proposal in proposals {
+        let name = normalize(&proposal.common_name);
+        if name.is_empty() || name == normalize(chosen) {
+            continue;
+        }
+        match ranked
+            .iter_mut()
+            .find(|known| normalize(&known.common_name) == name)
+        {
+            Some(known) if known.confidence < proposal.confidence => *known = proposal,
+            Some(_) => {}
+            None => ranked.push(proposal),
+        }
+    }
+    ranked.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    ranked.truncate(MAX_ALTERNATIVES);
+    ranked
+}
+
+/// A suggestion's identification, rated by its confidence in the name.
+fn as_alternative(suggestion: &AiMineralSuggestion) -> AiAlternative {
+    AiAlternative {
+        common_name: suggestion.common_name.clone(),
+        description: suggestion.description.clone(),
+        mineral_family: suggestion.mineral_family.clone(),
+        formula: suggestion.formula.clone(),
+        hardness_mohs: suggestion.hardness_mohs,
+        density_g_cm3: suggestion.density_g_cm3,
+        crystal_system: suggestion.crystal_system.clone(),
+        color: suggestion.color.clone(),
+        streak: suggestion.streak.clone(),
+        luster: suggestion.luster.clone(),
+        confidence: suggestion
+            .confidence
+            .get("common_name")
+            .copied()
+            .unwrap_or_default(),
+    }
+}
+
+fn normalize(answer: &str) -> String {
+    answer
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn percent(score: f32) -> String {
+    format!("{:.0}%", score.clamp(0.0, 1.0) * 100.0)
+}
+
+/// Index of the first answer with the most votes, and its vote count.
+fn vote<'a>(answers: impl Iterator<Item = &'a str>) -> (usize, usize) {
+    let keys = answers.map(normalize).collect::<Vec<_>>();
+    let mut counts = HashMap::new();
+    for key in &keys {
+        *counts.entry(key.as_str()).or_insert(0) += 1;
+    }
+    keys.iter()
+        .enumerate()
+        .map(|(index, key)| (index, counts[key.as_str()]))
+        // `max_by_key` keeps the last maximum; reversing keeps the first.
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .unwrap_or((0, 0))
+}
+
+fn median(mut values: Vec<f32>) -> f32 {
+    values.sort_by(f32::total_cmp);
+    let middle = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    }
+}
+
+/// Admin form order.
+fn field_order(field: &str) -> usize {
+    [
+        "common_name",
+        "mineral_family",
+        "formula",
+        "hardness_mohs",
+        "density_g_cm3",
+        "crystal_system",
+        "color",
+        "streak",
+        "luster",
+    ]
+    .iter()
+    .position(|known| *known == field)
+    .unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{candidates, merge, Candidate};
+    use crate::{models::MineralFormData, AiAlternative, AiMajorElement, AiMineralSuggestion};
+
+    fn alternative(name: &str, confidence: f32) -> AiAlternative {
+        AiAlternative {
+            common_name: name.to_string(),
+            description: String::new(),
+            mineral_family: "Silicates".to_string(),
+            formula: "SiO2".to_string(),
+            hardness_mohs: 7.0,
+            density_g_cm3: 2.65,
+            crystal_system: "Trigonal".to_string(),
+            color: "Colorless".to_string(),
+            streak: "White".to_string(),
+            luster: "Vitreous".to_string(),
+            confidence,
+        }
+    }
+
+    fn suggestion(name: &str, formula: &str, hardness: f32) -> AiMineralSuggestion {
+        AiMineralSuggestion {
+            common_name: name.to_string(),
+            description: format!("{name} description"),
+            mineral_family: "Silicates".to_string(),
+            formula: formula.to_string(),
+            hardness_mohs: hardness,
+            density_g_cm3: 2.65,
+            crystal_system: "Trigonal".to_string(),
+            color: "Colorless".to_string(),
+            streak: "White".to_string(),
+            luster: "Vitreous".to_string(),
+            major_elements: vec![AiMajorElement {
+                element: formula.to_string(),
+                percent: 100.0,
+            }],
+            notes: String::new(),
+            confidence: [
+                ("common_name".to_string(), 0.6),
+                ("luster".to_string(), 0.9),
+            ]
+            .into(),
+            alternatives: vec![alternative("Chalcedony", 0.3)],
+        }
+    }
+
+    #[test]
+    fn takes_the_majority_answer_and_reports_agreement() {
+        let (merged, confidence) = merge(vec![
+            suggestion("Calcite", "CaCO3", 3.0),
+            suggestion("Quartz", "SiO2", 7.0),
+            suggestion(" quartz ", "SiO2", 6.5),
+        ])
+        .unwrap();
+
+        assert_eq!(merged.common_name, "Quartz");
+        assert_eq!(merged.description, "Quartz description");
+        assert_eq!(merged.formula, "SiO2");
+        assert_eq!(merged.major_elements[0].element, "SiO2");
+        assert_eq!(merged.hardness_mohs, 6.5);
+
+        let agreement = |field: &str| {
+            let entry = confidence
+                .iter()
+                .find(|entry| entry.field == field)
+                .unwrap();
+            (entry.agreeing, entry.total)
+        };
+        assert_eq!(agreement("common_name"), (2, 3));
+        assert_eq!(agreement("hardness_mohs"), (2, 3));
+        assert_eq!(agreement("luster"), (3, 3));
+        assert_eq!(confidence[0].field, "common_name");
+        assert_eq!(confidence[0].summary(), "Common name 2/3 · 60%");
+        assert!(confidence[0].is_doubtful());
+
+        // The losing identification becomes an alternative, once.
+        let names = candidates(&merged)
+            .into_iter()
+            .map(|candidate| candidate.label())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Quartz · 60%", "Calcite · 60%", "Chalcedony · 30%"]);
+
+        let (single, confidence) = merge(vec![suggestion("Quartz", "SiO2", 7.0)]).unwrap();
+        assert_eq!(single.common_name, "Quartz");
+        assert!(confidence.iter().all(|entry| entry.is_unanimous()));
+        let luster = confidence
+            .iter()
+            .find(|entry| entry.field == "luster")
+            .unwrap();
+        assert_eq!(luster.summary(), "Luster · 90%");
+        assert!(!luster.is_doubtful());
+        assert!(merge(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn ranks_the_model_alternatives_for_the_admin_form() {
+        let reply = |extra: serde_json::Value| {
+            let mut reply = serde_json::json!({
+                "common_name": "Quartz",
+                "description": "",
+                "mineral_family": "Silicates",
+                "formula": "SiO2",
+                "hardness_mohs": 7.0,
+                "density_g_cm3": 2.65,
+                "crystal_system": "Trigonal",
+                "color": "Colorless",
+                "streak": "White",
+                "luster": "Vitreous",
+                "major_elements": [],
+                "notes": ""
+            });
+            reply
+                .as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            serde_json::from_value::<AiMineralSuggestion>(reply).unwrap()
+        };
+
+        // Replies without ratings still parse, and offer nothing to switch to.
+        let (plain, confidence) = merge(vec![reply(serde_json::json!({}))]).unwrap();
+        assert!(confidence.iter().all(|entry| entry.score.is_none()));
+        assert!(!confidence.iter().any(|entry| entry.is_doubtful()));
+        let form = MineralFormData {
+            field_confidence: confidence,
+            candidates: candidates(&plain),
+            ..MineralFormData::default()
+        };
+        assert!(form.suggestion_confidence().is_empty());
+        assert!(form.switchable_candidates().is_empty());
+
+        let mut rated = reply(serde_json::json!({
+            "confidence": {"common_name": 1.4, "formula": 0.2}
+        }));
+        rated.alternatives = vec![
+            alternative("Calcite", 0.1),
+            alternative("Chalcedony", 0.4),
+            alternative("quartz", 0.9),
+            alternative("Opal", 0.2),
+            alternative("Agate", 0.3),
+        ];
+        let (rated, confidence) = merge(vec![rated]).unwrap();
+        let formula = confidence
+            .iter()
+            .find(|entry| entry.field == "formula")
+            .unwrap();
+        assert!(formula.is_doubtful());
+        let form = MineralFormData {
+            field_confidence: confidence,
+            candidates: candidates(&rated),
+            ..MineralFormData::default()
+        };
+        assert!(!form.suggestion_confidence().is_empty());
+        let labels = form
+            .switchable_candidates()
+            .iter()
+            .map(Candidate::label)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            [
+                "Quartz · 100%",
+                "Chalcedony · 40%",
+                "Agate · 30%",
+                "Opal · 20%"
+            ]
+        );
+        assert_eq!(form.candidates[1].hardness_mohs, "7.00");
+    }
+}
@@ -0,0 +1,30 @@
+//! Secrets and identifiers from the operating system's CSPRNG through
+//! `getrandom`, which works on every platform the server runs on, minimal
+//! containers without `/dev` included.
+
+use anyhow::{anyhow, Result};
+
+pub fn fill_random(buf: &mut [u8]) -> Result<()> {
+    getrandom::getrandom(buf).map_err(|err| anyhow!("failed to read random bytes: {err}"))
+}
+
+/// `byte_len` random bytes as lowercase hex, for session and CSRF tokens,
+/// draft ids, and folder ids.
+pub fn random_hex(byte_len: usize) -> Result<String> {
+    let mut buf = vec![0_u8; byte_len];
+    fill_random(&mut buf)?;
+    Ok(buf.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::random_hex;
+
+    #[test]
+    fn hex_has_two_digits_per_byte() {
+        let first = random_hex(24).unwrap();
+        assert_eq!(first.len(), 48);
+        assert!(first.chars().all(|ch| matches!(ch, '0'..='9' | 'a'..='f')));
+        assert_ne!(first, random_hex(24).unwrap());
+    }
+}
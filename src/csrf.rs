@@ -0,0 +1,164 @@
+//! CSRF tokens for the admin forms. Each admin session gets its own token
+//! at login, stored next to the session in `AppState` and embedded as a
+//! hidden `csrf_token` field; state-changing admin handlers reject requests
+//! without it. The login form has no session yet, so it uses a
+//! double-submit cookie instead. Scripts can send the token in the
+//! `X-CSRF-Token` header, which `/admin` responses also carry. Visitor
+//! accounts follow the same scheme: a token per visitor session, and a
+//! double-submit cookie on `/account` for login and registration.
+
+use axum::{
+    http::{HeaderMap, HeaderValue},
+    response::Response,
+    Form,
+};
+use serde::Deserialize;
+
+use crate::{admin_token_from_headers, append_set_cookie, cookie_value, AppError, AppState};
+
+pub const HEADER: &str = "x-csrf-token";
+const LOGIN_COOKIE: &str = "admin_login_csrf";
+const ACCOUNT_LOGIN_COOKIE: &str = "account_login_csrf";
+
+/// Body of admin forms that carry nothing but the token.
+#[derive(Debug, Default, Deserialize)]
+pub struct CsrfForm {
+    #[serde(default)]
+    pub csrf_token: String,
+}
+
+/// Token submitted with a form that scripted clients may leave out in
+/// favour of the header.
+pub fn form_token(form: Option<Form<CsrfForm>>) -> String {
+    form.map(|Form(form)| form.csrf_token).unwrap_or_default()
+}
+
+/// Token of the admin session in `headers`, if there is one.
+pub fn session_token(state: &AppState, headers: &HeaderMap) -> Option<String> {
+    let session = admin_token_from_headers(headers)?;
+    state.admin_sessions.lock().ok()?.get(&session).cloned()
+}
+
+/// Checks the token submitted with a state-changing admin request against
+/// the session's. The form field wins; the header is the fallback for
+/// requests without a form body.
+pub fn verify(state: &AppState, headers: &HeaderMap, submitted: &str) -> Result<(), AppError> {
+    let expected = session_token(state, headers).ok_or_else(|| {
+        AppError::Unauthorized("Admin session required. Log in at /admin.".to_string())
+    })?;
+    verify_visitor(&expected, headers, submitted)
+}
+
+/// Same check for a visitor form, against the visitor session's token.
+pub fn verify_visitor(
+    expected: &str,
+    headers: &HeaderMap,
+    submitted: &str,
+) -> Result<(), AppError> {
+    if tokens_match(expected, &submitted_or_header(headers, submitted)) {
+        Ok(())
+    } else {
+        Err(rejected())
+    }
+}
+
+/// The login form's token must match the cookie set with the form.
+pub fn verify_login(headers: &HeaderMap, submitted: &str) -> Result<(), AppError> {
+    verify_cookie(headers, LOGIN_COOKIE, submitted)
+}
+
+/// Visitor login and registration, like [`verify_login`].
+pub fn verify_account_login(headers: &HeaderMap, submitted: &str) -> Result<(), AppError> {
+    verify_cookie(headers, ACCOUNT_LOGIN_COOKIE, submitted)
+}
+
+/// Sets the login cookie for a freshly rendered login form carrying
+/// `token`.
+pub fn attach_login_cookie(response: &mut Response, token: &str) -> Result<(), AppError> {
+    append_set_cookie(
+        response,
+        &format!("{LOGIN_COOKIE}={token}; HttpOnly; Path=/admin; SameSite=Strict; Max-Age=3600"),
+    )?;
+    expose(response, token);
+    Ok(())
+}
+
+/// Visitor forms post to `/account/...`, so the cookie is scoped there.
+pub fn attach_account_login_cookie(response: &mut Response, token: &str) -> Result<(), AppError> {
+    append_set_cookie(
+        response,
+        &format!(
+            "{ACCOUNT_LOGIN_COOKIE}={token}; HttpOnly; Path=/account; SameSite=Strict; Max-Age=3600"
+        ),
+    )
+}
+
+/// Mirrors the token in a response header for scripted clients.
+pub fn expose(response: &mut Response, token: &str) {
+    if let Ok(value) = HeaderValue::from_str(token) {
+        response.headers_mut().insert(HEADER, value);
+    }
+}
+
+fn verify_cookie(headers: &HeaderMap, cookie: &str, submitted: &str) -> Result<(), AppError> {
+    match cookie_value(headers, cookie) {
+        Some(expected) if tokens_match(&expected, &submitted_or_header(headers, submitted)) => {
+            Ok(())
+        }
+        _ => Err(rejected()),
+    }
+}
+
+fn submitted_or_header(headers: &HeaderMap, submitted: &str) -> String {
+    if submitted.is_empty() {
+        header_token(headers).unwrap_or_default()
+    } else {
+        submitted.to_string()
+    }
+}
+
+fn header_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+fn rejected() -> AppError {
+    AppError::Forbidden("Missing or invalid CSRF token; reload the page and retry.".to_string())
+}
+
+/// Length and content are compared without an early exit.
+pub fn tokens_match(expected: &str, submitted: &str) -> bool {
+    let (expected, submitted) = (expected.as_bytes(), submitted.as_bytes());
+    !expected.is_empty()
+        && expected.len() == submitted.len()
+        && expected
+            .iter()
+            .zip(submitted)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header, HeaderMap, HeaderValue};
+
+    use super::{tokens_match, verify_login};
+
+    #[test]
+    fn login_token_must_match_its_cookie() {
+        assert!(tokens_match("abc123", "abc123"));
+        assert!(!tokens_match("abc123", "abc124"));
+        assert!(!tokens_match("", ""));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("lang=en; admin_login_csrf=f00d"),
+        );
+        assert!(verify_login(&headers, "f00d").is_ok());
+        assert!(verify_login(&headers, "beef").is_err());
+        assert!(verify_login(&HeaderMap::new(), "f00d").is_err());
+    }
+}
@@ -0,0 +1,306 @@
+//! Chain of custody for a mineral: who held or handled it, when, and on
+//! what paperwork. Each event is one `custody/<timestamp>.json` file inside
+//! the mineral folder. The log is append-only; anyone can read it and
+//! admins add events.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::HeaderMap,
+    response::{IntoResponse, Redirect, Response},
+    Form,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::info;
+
+use crate::{
+    csrf, get_mineral, has_admin_session,
+    i18n::{ui_text, Language},
+    mineral_folder_for_slug,
+    models::Mineral,
+    require_visible, resolve_language,
+    timestamp_id::{is_valid_timestamp_id, timestamp_id},
+    web::{CustodyTemplate, TemplateResponse},
+    AppError, AppState,
+};
+
+pub const CUSTODY_DIR: &str = "custody";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustodyEvent {
+    /// File stem under `custody/`, the time the event was recorded; not
+    /// stored in the file itself.
+    #[serde(skip)]
+    pub id: String,
+    /// Person or organization that took custody or acted on the mineral.
+    pub actor: String,
+    /// `YYYY-MM-DD` the event happened.
+    pub date: String,
+    /// Invoice, export permit, certificate, or similar.
+    pub document_reference: String,
+    pub note: String,
+    pub recorded_utc: String,
+}
+
+/// The admin form's fields as typed, so a rejected submission can be shown
+/// back unchanged.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CustodyForm {
+    pub csrf_token: String,
+    pub actor: String,
+    pub date: String,
+    pub document_reference: String,
+    pub note: String,
+}
+
+impl CustodyForm {
+    fn to_event(&self, recorded_at: DateTime<Utc>) -> Result<CustodyEvent, String> {
+        let actor = self.actor.trim();
+        if actor.is_empty() {
+            return Err("actor is required".to_string());
+        }
+        let date = self.date.trim();
+        if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+            return Err(format!("event date must be YYYY-MM-DD: '{date}'"));
+        }
+
+        Ok(CustodyEvent {
+            id: timestamp_id(&recorded_at),
+            actor: actor.to_string(),
+            date: date.to_string(),
+            document_reference: self.document_reference.trim().to_string(),
+            note: self.note.trim().to_string(),
+            recorded_utc: recorded_at.to_rfc3339(),
+        })
+    }
+}
+
+/// Custody log of a published mineral, for the mineral page and reports.
+pub async fn for_mineral(
+    state: &AppState,
+    mineral: &Mineral,
+) -> Result<Vec<CustodyEvent>, AppError> {
+    list(&state.data_root.join("minerals").join(&mineral.folder_name)).await
+}
+
+pub async fn custody_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+) -> Result<TemplateResponse<CustodyTemplate>, AppError> {
+    let language = resolve_language(&state, &headers);
+    let folder_path = mineral_folder(&state, language, &slug).await?;
+    Ok(TemplateResponse(
+        custody_template(&state, &headers, language, &slug, &folder_path).await?,
+    ))
+}
+
+pub async fn append_custody_event(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+    Form(form): Form<CustodyForm>,
+) -> Result<Response, AppError> {
+    csrf::verify(&state, &headers, &form.csrf_token)?;
+    let language = resolve_language(&state, &headers);
+    let folder_path = mineral_folder(&state, language, &slug).await?;
+    let event = match form.to_event(Utc::now()) {
+        Ok(event) => event,
+        Err(message) => {
+            let template =
+                custody_template(&state, &headers, language, &slug, &folder_path).await?;
+            return Ok(TemplateResponse(CustodyTemplate {
+                error_message: Some(message),
+                form,
+                ..template
+            })
+            .into_response());
+        }
+    };
+
+    append(&folder_path, &event).await?;
+    info!("recorded custody event {} of {slug}", event.id);
+    Ok(Redirect::to(&format!("/minerals/{slug}/custody")).into_response())
+}
+
+async fn custody_template(
+    state: &AppState,
+    headers: &HeaderMap,
+    language: Language,
+    slug: &str,
+    folder_path: &Path,
+) -> Result<CustodyTemplate, AppError> {
+    let mineral = get_mineral(state, language, slug).await?;
+    require_visible(state, headers, slug, &mineral)?;
+    Ok(CustodyTemplate {
+        lang_code: language.code().to_string(),
+        lang_dir: language.dir().to_string(),
+        txt: ui_text(language),
+        mineral,
+        events: list(folder_path).await?,
+        has_admin_session: has_admin_session(state, headers),
+        csrf_token: csrf::session_token(state, headers).unwrap_or_default(),
+        form: CustodyForm::default(),
+        error_message: None,
+    })
+}
+
+async fn mineral_folder(
+    state: &AppState,
+    language: Language,
+    slug: &str,
+) -> Result<PathBuf, AppError> {
+    mineral_folder_for_slug(state, language, slug)
+        .await
+        .map_err(AppError::NotFound)
+}
+
+/// Appends an event dated today on behalf of another workflow, such as an
+/// order being shipped.
+pub async fn record(
+    folder_path: &Path,
+    actor: &str,
+    document_reference: &str,
+    note: &str,
+) -> Result<(), AppError> {
+    let now = Utc::now();
+    let event = CustodyForm {
+        actor: actor.to_string(),
+        date: now.format("%Y-%m-%d").to_string(),
+        document_reference: document_reference.to_string(),
+        note: note.to_string(),
+        ..CustodyForm::default()
+    }
+    .to_event(now)
+    .map_err(AppError::BadRequest)?;
+    append(folder_path, &event).await
+}
+
+/// Oldest first by event date; events on the same day keep the order they
+/// were recorded in. Unreadable files are skipped rather than hiding the
+/// rest of the log.
+async fn list(folder_path: &Path) -> Result<Vec<CustodyEvent>, AppError> {
+    let custody_dir = folder_path.join(CUSTODY_DIR);
+    let mut entries = match fs::read_dir(&custody_dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(AppError::Internal(
+                anyhow::Error::new(err)
+                    .context(format!("failed to read {}", custody_dir.display())),
+            ))
+        }
+    };
+
+    let mut events = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .with_context(|| format!("failed to read {}", custody_dir.display()))?
+    {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(id) = file_name
+            .strip_suffix(".json")
+            .filter(|id| is_valid_timestamp_id(id))
+        else {
+            continue;
+        };
+        let Ok(raw) = fs::read_to_string(entry.path()).await else {
+            continue;
+        };
+        if let Ok(event) = serde_json::from_str::<CustodyEvent>(&raw) {
+            events.push(CustodyEvent {
+                id: id.to_string(),
+                ..event
+            });
+        }
+    }
+    sort_events(&mut events);
+    Ok(events)
+}
+
+fn sort_events(events: &mut [CustodyEvent]) {
+    events.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.id.cmp(&b.id)));
+}
+
+/// Writes a new event file; never replaces an existing one.
+async fn append(folder_path: &Path, event: &CustodyEvent) -> Result<(), AppError> {
+    let custody_dir = folder_path.join(CUSTODY_DIR);
+    fs::create_dir_all(&custody_dir)
+        .await
+        .with_context(|| format!("failed to create {}", custody_dir.display()))?;
+    let path = custody_dir.join(format!("{}.json", event.id));
+    let json = serde_json::to_string_pretty(event).context("failed to serialize custody event")?;
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .await
+        .with_context(|| format!("failed to create {}", path.display()))?;
+    file.write_all(json.as_bytes())
+        .await
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{sort_events, CustodyForm};
+
+    #[test]
+    fn validates_events_and_orders_them_by_date_then_recording() {
+        let form = CustodyForm {
+            actor: " Mina Esperança cooperative ".to_string(),
+            date: "2024-05-02".to_string(),
+            document_reference: "Export permit 88/2024".to_string(),
+            ..CustodyForm::default()
+        };
+        let recorded = Utc.with_ymd_and_hms(2026, 10, 16, 16, 5, 6).unwrap();
+        let first = form.to_event(recorded).unwrap();
+        assert_eq!(first.actor, "Mina Esperança cooperative");
+        assert_eq!(first.id, "20261016T160506000000Z");
+
+        let later_same_day = form
+            .to_event(recorded + chrono::Duration::seconds(1))
+            .unwrap();
+        let earlier_day = CustodyForm {
+            date: "2023-11-30".to_string(),
+            ..form.clone()
+        }
+        .to_event(recorded + chrono::Duration::seconds(2))
+        .unwrap();
+        let mut events = vec![later_same_day.clone(), earlier_day.clone(), first.clone()];
+        sort_events(&mut events);
+        let ids = events
+            .iter()
+            .map(|event| event.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![
+                earlier_day.id.as_str(),
+                first.id.as_str(),
+                later_same_day.id.as_str()
+            ]
+        );
+
+        let no_actor = CustodyForm {
+            actor: "  ".to_string(),
+            ..form.clone()
+        };
+        assert!(no_actor.to_event(recorded).is_err());
+        let bad_date = CustodyForm {
+            date: "May 2024".to_string(),
+            ..form
+        };
+        assert!(bad_date.to_event(recorded).is_err());
+    }
+}
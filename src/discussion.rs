@@ -0,0 +1,267 @@
+//! Admin discussion thread on a mineral: notes admins leave each other
+//! about a record, such as questions on an identification or what is still
+//! to be checked. Each note is one `data/notes/<folder>/<timestamp>.json`
+//! file with its author and a Markdown body. The thread is shown at the end
+//! of the mineral page, only with an admin session, and is append-only.
+//! While a mineral is in the trash its thread is kept under its trash id,
+//! and it goes when the mineral is purged.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
+};
+
+use anyhow::Context;
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::HeaderMap,
+    response::Redirect,
+    Form,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::info;
+
+use crate::{
+    csrf, folder_file_name, lang_path, mineral_folder_for_slug,
+    models::Mineral,
+    resolve_language,
+    timestamp_id::{is_valid_timestamp_id, timestamp_id},
+    AppError, AppState,
+};
+
+/// Under the data root, one folder per mineral folder name. Not a `notes/`
+/// folder inside each mineral folder: `/data/minerals` is served as static
+/// files to anyone who can see a public mineral, and these notes are for
+/// admins only.
+pub const NOTES_DIR: &str = "notes";
+
+const MAX_AUTHOR_CHARS: usize = 120;
+const MAX_BODY_CHARS: usize = 20_000;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdminNote {
+    /// File stem in the thread folder, the time the note was posted; not stored
+    /// in the file itself.
+    #[serde(skip)]
+    pub id: String,
+    pub author: String,
+    /// RFC 3339.
+    pub timestamp: String,
+    /// Markdown.
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AdminNoteForm {
+    pub csrf_token: String,
+    pub author: String,
+    pub body: String,
+}
+
+impl AdminNoteForm {
+    fn to_note(&self, posted_at: DateTime<Utc>) -> Result<AdminNote, String> {
+        let author = self.author.trim();
+        if author.is_empty() || author.chars().count() > MAX_AUTHOR_CHARS {
+            return Err(format!(
+                "author is required (at most {MAX_AUTHOR_CHARS} characters)"
+            ));
+        }
+        let body = self.body.trim();
+        if body.is_empty() || body.chars().count() > MAX_BODY_CHARS {
+            return Err(format!(
+                "note is required (at most {MAX_BODY_CHARS} characters)"
+            ));
+        }
+
+        Ok(AdminNote {
+            id: timestamp_id(&posted_at),
+            author: author.replace(['\r', '\n'], " "),
+            timestamp: posted_at.to_rfc3339(),
+            // Browsers submit textarea line breaks as CRLF.
+            body: body.replace("\r\n", "\n"),
+        })
+    }
+}
+
+/// The thread of a published mineral, oldest first, for the mineral page.
+pub async fn for_mineral(state: &AppState, mineral: &Mineral) -> Result<Vec<AdminNote>, AppError> {
+    list(&thread_dir(&state.data_root, &mineral.folder_name)).await
+}
+
+fn thread_dir(data_root: &Path, folder_name: &str) -> PathBuf {
+    data_root.join(NOTES_DIR).join(folder_name)
+}
+
+/// Re-keys a thread, for a mineral folder going into or out of the trash.
+/// A mineral without notes has nothing to move.
+pub async fn move_thread(data_root: &Path, from: &str, to: &str) -> anyhow::Result<()> {
+    let (from, to) = (thread_dir(data_root, from), thread_dir(data_root, to));
+    match fs::rename(&from, &to).await {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(anyhow::Error::new(err)
+            .context(format!(
+                "failed to move {} to {}",
+                from.display(),
+                to.display()
+            ))),
+        _ => Ok(()),
+    }
+}
+
+/// Deletes a thread, for a mineral purged from the trash.
+pub async fn remove_thread(data_root: &Path, key: &str) -> anyhow::Result<()> {
+    let dir = thread_dir(data_root, key);
+    match fs::remove_dir_all(&dir).await {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(anyhow::Error::new(err).context(format!("failed to delete {}", dir.display())))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// `POST /minerals/:slug/notes`, from the form under the thread.
+pub async fn post_note(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+    Form(form): Form<AdminNoteForm>,
+) -> Result<Redirect, AppError> {
+    csrf::verify(&state, &headers, &form.csrf_token)?;
+    let language = resolve_language(&state, &headers);
+    let folder_path = mineral_folder_for_slug(&state, language, &slug)
+        .await
+        .map_err(AppError::NotFound)?;
+    let note = form.to_note(Utc::now()).map_err(AppError::BadRequest)?;
+    append(
+        &thread_dir(&state.data_root, &folder_file_name(&folder_path)),
+        &note,
+    )
+    .await?;
+    info!("posted admin note {} on {slug}", note.id);
+    // The thread is not part of the catalog, so the mineral page's `ETag`
+    // would otherwise still match the page without the note.
+    state.catalog_generation.fetch_add(1, Ordering::Relaxed);
+    Ok(Redirect::to(&lang_path::prefixed(
+        &headers,
+        &format!("/minerals/{slug}#notes"),
+    )))
+}
+
+/// Oldest first. Unreadable files are skipped rather than hiding the rest
+/// of the thread.
+async fn list(notes_dir: &Path) -> Result<Vec<AdminNote>, AppError> {
+    let mut entries = match fs::read_dir(notes_dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(AppError::Internal(
+                anyhow::Error::new(err).context(format!("failed to read {}", notes_dir.display())),
+            ))
+        }
+    };
+
+    let mut notes = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .with_context(|| format!("failed to read {}", notes_dir.display()))?
+    {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(id) = file_name
+            .strip_suffix(".json")
+            .filter(|id| is_valid_timestamp_id(id))
+        else {
+            continue;
+        };
+        let Ok(raw) = fs::read_to_string(entry.path()).await else {
+            continue;
+        };
+        if let Ok(note) = serde_json::from_str::<AdminNote>(&raw) {
+            notes.push(AdminNote {
+                id: id.to_string(),
+                ..note
+            });
+        }
+    }
+    notes.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(notes)
+}
+
+/// Writes a new note file; never replaces an existing one.
+async fn append(notes_dir: &Path, note: &AdminNote) -> Result<(), AppError> {
+    fs::create_dir_all(notes_dir)
+        .await
+        .with_context(|| format!("failed to create {}", notes_dir.display()))?;
+    let path = notes_dir.join(format!("{}.json", note.id));
+    let json = serde_json::to_string_pretty(note).context("failed to serialize admin note")?;
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .await
+        .with_context(|| format!("failed to create {}", path.display()))?;
+    file.write_all(json.as_bytes())
+        .await
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use std::path::Path;
+
+    use super::{append, list, thread_dir, AdminNoteForm};
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn keeps_threads_outside_the_served_mineral_folders() {
+        let dir = thread_dir(Path::new("data"), "mineral.oxides.0x1a2b3");
+        assert_eq!(dir, Path::new("data/notes/mineral.oxides.0x1a2b3"));
+        assert!(!dir.starts_with("data/minerals"));
+    }
+
+    #[tokio::test]
+    async fn appends_notes_and_lists_them_oldest_first() {
+        let folder = TempDir::new("discussion");
+        let form = AdminNoteForm {
+            author: " Ana ".to_string(),
+            body: "Checked the streak:\r\n\r\n- **reddish brown**".to_string(),
+            ..AdminNoteForm::default()
+        };
+        let posted = Utc.with_ymd_and_hms(2026, 10, 16, 16, 5, 6).unwrap();
+        let first = form.to_note(posted).unwrap();
+        assert_eq!(first.id, "20261016T160506000000Z");
+        assert_eq!(first.author, "Ana");
+        assert_eq!(first.body, "Checked the streak:\n\n- **reddish brown**");
+
+        let reply = AdminNoteForm {
+            author: "Ben".to_string(),
+            body: "Agreed, hematite.".to_string(),
+            ..AdminNoteForm::default()
+        }
+        .to_note(posted + chrono::Duration::seconds(90))
+        .unwrap();
+        append(&folder, &reply).await.unwrap();
+        append(&folder, &first).await.unwrap();
+        assert!(append(&folder, &first).await.is_err());
+
+        let notes = list(&folder).await.unwrap();
+        let authors = notes
+            .iter()
+            .map(|note| note.author.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(authors, ["Ana", "Ben"]);
+        assert_eq!(notes[0].timestamp, "2026-10-16T16:05:06+00:00");
+
+        let blank = AdminNoteForm {
+            body: "  ".to_string(),
+            ..form
+        };
+        assert!(blank.to_note(posted).is_err());
+    }
+}
@@ -0,0 +1,474 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tracing::{info, warn};
+
+use crate::{auth::sha256_hex, models::MineralFormData, MineralImage};
+
+const DRAFT_META_FILE: &str = "draft.json";
+
+/// Admin drafts persisted under `data/drafts/<id>/`: `draft.json` with the
+/// form state plus the uploads as `image.<ext>`, `image-2.<ext>`, ... Each draft belongs to the
+/// admin session that created it; only a SHA-256 of the session token is
+/// written to disk.
+#[derive(Debug, Clone)]
+pub struct DraftStore {
+    root: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DraftMeta {
+    id: String,
+    owner: String,
+    created_utc: String,
+    updated_utc: String,
+    /// One extension per uploaded image, in upload order.
+    #[serde(default)]
+    image_exts: Vec<String>,
+    /// Single-image drafts written before multi-upload.
+    #[serde(default, skip_serializing)]
+    image_ext: Option<String>,
+    /// Last autosave from the publish form; the newest autosaved draft is
+    /// reopened on the admin page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    autosaved_utc: Option<String>,
+    form: MineralFormData,
+}
+
+#[derive(Debug, Clone)]
+pub struct DraftSummary {
+    pub id: String,
+    pub common_name: String,
+    pub mineral_family: String,
+    pub updated_utc: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Draft {
+    pub form: MineralFormData,
+    pub images: Vec<MineralImage>,
+}
+
+impl DraftStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub async fn create(
+        &self,
+        session_token: &str,
+        id: &str,
+        images: &[MineralImage],
+        form: &MineralFormData,
+    ) -> Result<()> {
+        let dir = self.draft_dir(id)?;
+        fs::create_dir_all(&dir)
+            .await
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+
+        for (index, image) in images.iter().enumerate() {
+            let image_path = dir.join(draft_image_file(index, &image.ext));
+            fs::write(&image_path, &image.bytes)
+                .await
+                .with_context(|| format!("failed to write {}", image_path.display()))?;
+        }
+
+        let now = Utc::now().to_rfc3339();
+        write_meta(
+            &dir,
+            &DraftMeta {
+                id: id.to_string(),
+                owner: owner_key(session_token),
+                created_utc: now.clone(),
+                updated_utc: now,
+                image_exts: images.iter().map(|image| image.ext.clone()).collect(),
+                image_ext: None,
+                autosaved_utc: None,
+                form: form.clone(),
+            },
+        )
+        .await
+    }
+
+    pub async fn load(&self, session_token: &str, id: &str) -> Result<Option<Draft>> {
+        let Some(meta) = self.owned_meta(session_token, id).await? else {
+            return Ok(None);
+        };
+
+        let dir = self.draft_dir(id)?;
+        let mut images = Vec::new();
+        for (index, ext) in meta.image_exts.iter().enumerate() {
+            let image_path = dir.join(draft_image_file(index, ext));
+            let bytes = fs::read(&image_path)
+                .await
+                .with_context(|| format!("failed to read {}", image_path.display()))?;
+            images.push(MineralImage {
+                bytes,
+                ext: ext.clone(),
+            });
+        }
+
+        Ok(Some(Draft {
+            form: MineralFormData {
+                draft_id: Some(meta.id),
+                ..meta.form
+            },
+            images,
+        }))
+    }
+
+    /// Stores the operator's latest edits so a resumed draft shows them.
+    pub async fn save_form(
+        &self,
+        session_token: &str,
+        id: &str,
+        form: &MineralFormData,
+    ) -> Result<()> {
+        let Some(meta) = self.owned_meta(session_token, id).await? else {
+            return Err(anyhow!("draft {id} not found"));
+        };
+
+        write_meta(
+            &self.draft_dir(id)?,
+            &DraftMeta {
+                updated_utc: Utc::now().to_rfc3339(),
+                form: form.clone(),
+                ..meta
+            },
+        )
+        .await
+    }
+
+    /// Stores the publish form as typed so far, keeping the AI suggestion
+    /// inputs the form does not carry and one caption, rotation, and crop
+    /// per stored photo.
+    /// `false` when the session has no such draft.
+    pub async fn autosave(
+        &self,
+        session_token: &str,
+        id: &str,
+        form: &MineralFormData,
+    ) -> Result<bool> {
+        let Some(meta) = self.owned_meta(session_token, id).await? else {
+            return Ok(false);
+        };
+
+        let image_count = meta.image_exts.len();
+        let per_photo = |values: &[String]| {
+            let mut values = values.to_vec();
+            values.resize(image_count, String::new());
+            values
+        };
+        let now = Utc::now().to_rfc3339();
+        write_meta(
+            &self.draft_dir(id)?,
+            &DraftMeta {
+                updated_utc: now.clone(),
+                autosaved_utc: Some(now),
+                form: MineralFormData {
+                    image_captions: per_photo(&form.image_captions),
+                    image_rotations: per_photo(&form.image_rotations),
+                    image_crops: per_photo(&form.image_crops),
+                    primary_image: form.primary_image.min(image_count.saturating_sub(1)),
+                    ..form.clone().with_suggestion_of(&meta.form)
+                },
+                ..meta
+            },
+        )
+        .await?;
+        Ok(true)
+    }
+
+    /// The session's most recently autosaved draft, to reopen.
+    pub async fn latest_autosave(&self, session_token: &str) -> Option<String> {
+        let owner = owner_key(session_token);
+        self.read_all_meta()
+            .await
+            .into_iter()
+            .filter(|meta| meta.owner == owner)
+            .filter_map(|meta| Some((meta.autosaved_utc?, meta.id)))
+            .max()
+            .map(|(_, id)| id)
+    }
+
+    pub async fn discard(&self, session_token: &str, id: &str) -> Result<bool> {
+        if self.owned_meta(session_token, id).await?.is_none() {
+            return Ok(false);
+        }
+
+        let dir = self.draft_dir(id)?;
+        fs::remove_dir_all(&dir)
+            .await
+            .with_context(|| format!("failed to remove {}", dir.display()))?;
+        Ok(true)
+    }
+
+    pub async fn discard_session(&self, session_token: &str) -> Result<usize> {
+        let owner = owner_key(session_token);
+        let mut removed = 0;
+        for meta in self.read_all_meta().await {
+            if meta.owner == owner {
+                fs::remove_dir_all(self.draft_dir(&meta.id)?)
+                    .await
+                    .with_context(|| format!("failed to remove draft {}", meta.id))?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Drops drafts untouched for longer than `max_age`; their owning
+    /// session has expired by then, so nobody can resume them.
+    pub async fn prune_stale(&self, max_age: Duration) -> Result<usize> {
+        let cutoff = Utc::now() - max_age;
+        let mut removed = 0;
+        for meta in self.read_all_meta().await {
+            let updated = match DateTime::parse_from_rfc3339(&meta.updated_utc) {
+                Ok(updated) => updated,
+                Err(err) => {
+                    // Kept rather than guessed stale: it may be live work.
+                    warn!(
+                        "draft {} has an unreadable updated_utc '{}': {err}",
+                        meta.id, meta.updated_utc
+                    );
+                    continue;
+                }
+            };
+            if updated < cutoff {
+                fs::remove_dir_all(self.draft_dir(&meta.id)?)
+                    .await
+                    .with_context(|| format!("failed to remove draft {}", meta.id))?;
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            info!("pruned {removed} stale admin drafts");
+        }
+        Ok(removed)
+    }
+
+    pub async fn list(&self, session_token: &str) -> Vec<DraftSummary> {
+        let owner = owner_key(session_token);
+        let mut drafts = self
+            .read_all_meta()
+            .await
+            .into_iter()
+            .filter(|meta| meta.owner == owner)
+            .map(|meta| DraftSummary {
+                id: meta.id,
+                common_name: meta.form.common_name,
+                mineral_family: meta.form.mineral_family,
+                updated_utc: meta.updated_utc,
+            })
+            .collect::<Vec<_>>();
+        drafts.sort_by(|a, b| b.updated_utc.cmp(&a.updated_utc));
+        drafts
+    }
+
+    async fn owned_meta(&self, session_token: &str, id: &str) -> Result<Option<DraftMeta>> {
+        if !is_valid_draft_id(id) {
+            return Ok(None);
+        }
+        let path = self.draft_dir(id)?.join(DRAFT_META_FILE);
+        let raw = match fs::read_to_string(&path).await {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(anyhow!("failed to read {}: {err}", path.display())),
+        };
+        let meta = serde_json::from_str::<DraftMeta>(&raw)
+            .map(DraftMeta::normalize)
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        Ok(Some(meta).filter(|meta| meta.owner == owner_key(session_token)))
+    }
+
+    async fn read_all_meta(&self) -> Vec<DraftMeta> {
+        let Ok(mut entries) = fs::read_dir(&self.root).await else {
+            return Vec::new();
+        };
+
+        let mut metas = Vec::new();
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path().join(DRAFT_META_FILE);
+            let Ok(raw) = fs::read_to_string(&path).await else {
+                continue;
+            };
+            match serde_json::from_str::<DraftMeta>(&raw) {
+                Ok(meta) if is_valid_draft_id(&meta.id) => metas.push(meta),
+                Ok(_) => {}
+                Err(err) => warn!("skipping unreadable draft {}: {err}", path.display()),
+            }
+        }
+        metas
+    }
+
+    fn draft_dir(&self, id: &str) -> Result<PathBuf> {
+        if !is_valid_draft_id(id) {
+            return Err(anyhow!("invalid draft id: {id}"));
+        }
+        Ok(self.root.join(id))
+    }
+}
+
+impl DraftMeta {
+    fn normalize(mut self) -> Self {
+        if let Some(ext) = self.image_ext.take() {
+            if self.image_exts.is_empty() {
+                self.image_exts.push(ext);
+            }
+        }
+        self
+    }
+}
+
+/// Same naming as mineral folders, so draft files map 1:1 on publish.
+fn draft_image_file(index: usize, ext: &str) -> String {
+    match index {
+        0 => format!("image.{ext}"),
+        _ => format!("image-{}.{ext}", index + 1),
+    }
+}
+
+async fn write_meta(dir: &Path, meta: &DraftMeta) -> Result<()> {
+    let path = dir.join(DRAFT_META_FILE);
+    let json = serde_json::to_string_pretty(meta).context("failed to serialize draft")?;
+    fs::write(&path, json)
+        .await
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn is_valid_draft_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 64 && id.chars().all(|ch| ch.is_ascii_hexdigit())
+}
+
+fn owner_key(session_token: &str) -> String {
+    sha256_hex(session_token.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DraftStore;
+    use crate::{models::MineralFormData, test_support::TempDir, MineralImage};
+
+    #[tokio::test]
+    async fn saves_loads_and_discards_drafts_per_session() {
+        let root = TempDir::new("drafts-owned");
+        let store = DraftStore::new(root.to_path_buf());
+        let images = [
+            MineralImage {
+                bytes: vec![1, 2, 3],
+                ext: "png".to_string(),
+            },
+            MineralImage {
+                bytes: vec![4, 5],
+                ext: "jpg".to_string(),
+            },
+        ];
+        let form = MineralFormData {
+            common_name: "Beryl".to_string(),
+            mineral_family: "silicates".to_string(),
+            ..MineralFormData::default()
+        };
+        store.create("token", "aa", &images, &form).await.unwrap();
+        store
+            .create("token", "bb", &[], &MineralFormData::default())
+            .await
+            .unwrap();
+        store
+            .create("other", "cc", &[], &MineralFormData::default())
+            .await
+            .unwrap();
+
+        let edited = MineralFormData {
+            formula: "Be3Al2Si6O18".to_string(),
+            ..form
+        };
+        store.save_form("token", "aa", &edited).await.unwrap();
+        let draft = store.load("token", "aa").await.unwrap().unwrap();
+        assert_eq!(draft.form.draft_id.as_deref(), Some("aa"));
+        assert_eq!(draft.form.common_name, "Beryl");
+        assert_eq!(draft.form.formula, "Be3Al2Si6O18");
+        assert_eq!(draft.images.len(), 2);
+        assert_eq!(draft.images[1].bytes, [4, 5]);
+        assert_eq!(draft.images[1].ext, "jpg");
+        assert!(store.load("other", "aa").await.unwrap().is_none());
+        assert_eq!(store.list("token").await.len(), 2);
+        assert_eq!(store.list("other").await[0].id, "cc");
+
+        assert!(!store.discard("other", "aa").await.unwrap());
+        assert!(store.discard("token", "aa").await.unwrap());
+        assert!(store.load("token", "aa").await.unwrap().is_none());
+        assert!(!root.join("aa").exists());
+        assert_eq!(store.discard_session("token").await.unwrap(), 1);
+        assert!(store.list("token").await.is_empty());
+        assert_eq!(store.list("other").await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn autosave_keeps_suggestion_context_and_reopens_newest_draft() {
+        let root = TempDir::new("drafts");
+        let store = DraftStore::new(root.to_path_buf());
+        let image = MineralImage {
+            bytes: vec![1, 2, 3],
+            ext: "png".to_string(),
+        };
+        let suggested = MineralFormData {
+            common_name: "Quartz".to_string(),
+            suggestion_context: "clear prism".to_string(),
+            ..MineralFormData::default()
+        };
+        store
+            .create("token", "aa", std::slice::from_ref(&image), &suggested)
+            .await
+            .unwrap();
+        store
+            .create("token", "bb", &[image], &suggested)
+            .await
+            .unwrap();
+        assert_eq!(store.latest_autosave("token").await, None);
+
+        let typed = MineralFormData {
+            common_name: "Smoky quartz".to_string(),
+            image_captions: vec!["front".to_string(), "stray".to_string()],
+            primary_image: 3,
+            ..MineralFormData::default()
+        };
+        assert!(store.autosave("token", "bb", &typed).await.unwrap());
+        assert!(!store.autosave("other", "bb", &typed).await.unwrap());
+        assert_eq!(store.latest_autosave("token").await.as_deref(), Some("bb"));
+        assert_eq!(store.latest_autosave("other").await, None);
+
+        let form = store.load("token", "bb").await.unwrap().unwrap().form;
+        assert_eq!(form.common_name, "Smoky quartz");
+        assert_eq!(form.suggestion_context, "clear prism");
+        assert_eq!(form.image_captions, vec!["front".to_string()]);
+        assert_eq!(form.primary_image, 0);
+    }
+
+    #[tokio::test]
+    async fn prunes_stale_drafts_but_keeps_unreadable_timestamps() {
+        let root = TempDir::new("drafts-prune");
+        let store = DraftStore::new(root.to_path_buf());
+        for id in ["aa", "bb"] {
+            store
+                .create("token", id, &[], &MineralFormData::default())
+                .await
+                .unwrap();
+        }
+        let meta_path = root.join("bb").join(super::DRAFT_META_FILE);
+        let mut meta: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&meta_path).unwrap()).unwrap();
+        meta["updated_utc"] = "yesterday-ish".into();
+        std::fs::write(&meta_path, meta.to_string()).unwrap();
+
+        let removed = store
+            .prune_stale(chrono::Duration::seconds(-1))
+            .await
+            .unwrap();
+        assert_eq!(removed, 1);
+        assert!(!root.join("aa").exists());
+        assert!(root.join("bb").exists());
+    }
+}
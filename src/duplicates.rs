@@ -0,0 +1,168 @@
+//! Likely duplicates of a mineral about to be published. A draft matches a
+//! catalog entry with the same name, the same formula, or the same three
+//! most abundant elements in the same order; the admin then has to publish
+//! it anyway on purpose.
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BTreeMap,
+};
+
+use crate::models::{Mineral, MineralDiskRecord};
+
+/// At most this many matches are shown.
+const MAX_CANDIDATES: usize = 5;
+/// Elements compared, most abundant first.
+const DOMINANT_ELEMENTS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateCandidate {
+    pub slug: String,
+    pub common_name: String,
+    pub formula: String,
+    /// Why it matched, e.g. `same formula`.
+    pub reasons: Vec<String>,
+}
+
+/// Catalog minerals `record` may duplicate, strongest match first.
+pub fn find(catalog: &[Mineral], record: &MineralDiskRecord) -> Vec<DuplicateCandidate> {
+    let name = name_key(&record.common_name);
+    let formula = formula_key(&record.formula);
+    let elements = dominant_elements(&record.major_elements_pct);
+
+    let mut candidates = catalog
+        .iter()
+        .filter_map(|mineral| {
+            let mut reasons = Vec::new();
+            if !name.is_empty() && name_key(&mineral.common_name) == name {
+                reasons.push("same name".to_string());
+            }
+            if !formula.is_empty() && formula_key(&mineral.formula) == formula {
+                reasons.push("same formula".to_string());
+            }
+            if elements.len() == DOMINANT_ELEMENTS
+                && dominant_elements(&mineral.major_elements_pct) == elements
+            {
+                reasons.push(format!("same dominant elements ({})", elements.join(", ")));
+            }
+            (!reasons.is_empty()).then(|| DuplicateCandidate {
+                slug: mineral.slug.clone(),
+                common_name: mineral.common_name.clone(),
+                formula: mineral.formula.clone(),
+                reasons,
+            })
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|candidate| Reverse(candidate.reasons.len()));
+    candidates.truncate(MAX_CANDIDATES);
+    candidates
+}
+
+/// Case, spacing, and punctuation do not tell minerals apart.
+fn name_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Element symbols are case-sensitive (`Co` is not `CO`), so only spacing
+/// and the middle-dot/period variants of hydrate notation are dropped.
+fn formula_key(formula: &str) -> String {
+    formula
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| if c == '·' || c == '•' { '.' } else { c })
+        .collect()
+}
+
+/// The most abundant elements, highest share first.
+fn dominant_elements(pct: &BTreeMap<String, f32>) -> Vec<String> {
+    let mut shares = pct
+        .iter()
+        .filter(|(_, percent)| **percent > 0.0)
+        .collect::<Vec<_>>();
+    shares.sort_by(|a, b| b.1.partial_cmp(a.1).unwrap_or(Ordering::Equal));
+    shares
+        .into_iter()
+        .take(DOMINANT_ELEMENTS)
+        .map(|(element, _)| element.trim().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find;
+    use crate::models::{sample_mineral, Mineral, MineralDiskRecord};
+
+    fn mineral(slug: &str, name: &str, formula: &str, elements: &[(&str, f32)]) -> Mineral {
+        Mineral {
+            slug: slug.to_string(),
+            folder_name: slug.to_string(),
+            common_name: name.to_string(),
+            mineral_family: "silicates".to_string(),
+            formula: formula.to_string(),
+            hardness_mohs: 7.0,
+            density_g_cm3: 2.7,
+            crystal_system: "hexagonal".to_string(),
+            color: "green".to_string(),
+            streak: "white".to_string(),
+            luster: "vitreous".to_string(),
+            major_elements_pct: elements
+                .iter()
+                .map(|(element, percent)| (element.to_string(), *percent))
+                .collect(),
+            ..sample_mineral()
+        }
+    }
+
+    fn draft(name: &str, formula: &str, elements: serde_json::Value) -> MineralDiskRecord {
+        serde_json::from_value(serde_json::json!({
+            "common_name": name,
+            "mineral_family": "silicates",
+            "formula": formula,
+            "hardness_mohs": 7.0,
+            "density_g_cm3": 2.7,
+            "crystal_system": "hexagonal",
+            "color": "green",
+            "streak": "white",
+            "luster": "vitreous",
+            "major_elements_pct": elements,
+            "notes": "",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn matches_on_name_formula_or_dominant_elements() {
+        let catalog = vec![
+            mineral("quartz", "Quartz", "SiO2", &[("O", 53.3), ("Si", 46.7)]),
+            mineral(
+                "beryl",
+                "Beryl",
+                "Be3Al2Si6O18",
+                &[("O", 53.6), ("Si", 31.4), ("Al", 10.0), ("Be", 5.0)],
+            ),
+            mineral("cobaltite", "Cobaltite", "CoAsS", &[]),
+        ];
+
+        let found = find(&catalog, &draft(" quartz ", "Si O2", serde_json::json!({})));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].slug, "quartz");
+        assert_eq!(found[0].reasons, ["same name", "same formula"]);
+
+        let emerald = draft(
+            "Emerald",
+            "Be3Al2(SiO3)6",
+            serde_json::json!({"O": 53.0, "Si": 31.0, "Al": 10.1, "Be": 5.1}),
+        );
+        let found = find(&catalog, &emerald);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].slug, "beryl");
+        assert_eq!(found[0].reasons, ["same dominant elements (O, Si, Al)"]);
+
+        // Two elements are too few to call a match, and symbols keep case.
+        let unrelated = draft("Other", "COAsS", serde_json::json!({"O": 60.0, "Si": 40.0}));
+        assert!(find(&catalog, &unrelated).is_empty());
+    }
+}
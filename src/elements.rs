@@ -0,0 +1,348 @@
+//! Periodic table reference, hydrogen through uranium: symbol, name,
+//! standard atomic weight, and category. Backs the formula arithmetic in
+//! [`crate::chemistry`] and the `/elements/<symbol>` pages, which list
+//! every published mineral containing an element.
+
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::HeaderMap,
+    Json,
+};
+use serde::Serialize;
+
+use crate::{
+    catalog_for_language, chemistry,
+    i18n::{ui_text, Language},
+    locale_format::LocaleFormat,
+    models::Mineral,
+    resolve_language, visible_catalog,
+    web::{ElementTemplate, TemplateResponse},
+    AppError, AppState,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    AlkaliMetal,
+    AlkalineEarthMetal,
+    TransitionMetal,
+    PostTransitionMetal,
+    Metalloid,
+    Nonmetal,
+    Halogen,
+    NobleGas,
+    Lanthanide,
+    Actinide,
+}
+
+impl Category {
+    pub fn label(self) -> &'static str {
+        match self {
+            Category::AlkaliMetal => "alkali metal",
+            Category::AlkalineEarthMetal => "alkaline earth metal",
+            Category::TransitionMetal => "transition metal",
+            Category::PostTransitionMetal => "post-transition metal",
+            Category::Metalloid => "metalloid",
+            Category::Nonmetal => "nonmetal",
+            Category::Halogen => "halogen",
+            Category::NobleGas => "noble gas",
+            Category::Lanthanide => "lanthanide",
+            Category::Actinide => "actinide",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Element {
+    pub atomic_number: u8,
+    pub symbol: &'static str,
+    pub name: &'static str,
+    /// Standard atomic weight (IUPAC, abridged); the most stable isotope's
+    /// mass number for elements without one.
+    pub atomic_mass: f64,
+    pub category: Category,
+}
+
+impl Element {
+    /// The mass as pages show it, through [`LocaleFormat::number`].
+    ///
+    /// [`LocaleFormat::number`]: crate::locale_format::LocaleFormat::number
+    pub fn shown_mass(&self) -> f32 {
+        self.atomic_mass as f32
+    }
+}
+
+const fn element(
+    atomic_number: u8,
+    symbol: &'static str,
+    name: &'static str,
+    atomic_mass: f64,
+    category: Category,
+) -> Element {
+    Element {
+        atomic_number,
+        symbol,
+        name,
+        atomic_mass,
+        category,
+    }
+}
+
+use Category::*;
+
+/// In atomic-number order, so `ELEMENTS[z - 1]` has atomic number `z`.
+pub const ELEMENTS: &[Element] = &[
+    element(1, "H", "Hydrogen", 1.008, Nonmetal),
+    element(2, "He", "Helium", 4.0026, NobleGas),
+    element(3, "Li", "Lithium", 6.94, AlkaliMetal),
+    element(4, "Be", "Beryllium", 9.0122, AlkalineEarthMetal),
+    element(5, "B", "Boron", 10.81, Metalloid),
+    element(6, "C", "Carbon", 12.011, Nonmetal),
+    element(7, "N", "Nitrogen", 14.007, Nonmetal),
+    element(8, "O", "Oxygen", 15.999, Nonmetal),
+    element(9, "F", "Fluorine", 18.998, Halogen),
+    element(10, "Ne", "Neon", 20.180, NobleGas),
+    element(11, "Na", "Sodium", 22.990, AlkaliMetal),
+    element(12, "Mg", "Magnesium", 24.305, AlkalineEarthMetal),
+    element(13, "Al", "Aluminium", 26.982, PostTransitionMetal),
+    element(14, "Si", "Silicon", 28.085, Metalloid),
+    element(15, "P", "Phosphorus", 30.974, Nonmetal),
+    element(16, "S", "Sulfur", 32.06, Nonmetal),
+    element(17, "Cl", "Chlorine", 35.45, Halogen),
+    element(18, "Ar", "Argon", 39.948, NobleGas),
+    element(19, "K", "Potassium", 39.098, AlkaliMetal),
+    element(20, "Ca", "Calcium", 40.078, AlkalineEarthMetal),
+    element(21, "Sc", "Scandium", 44.956, TransitionMetal),
+    element(22, "Ti", "Titanium", 47.867, TransitionMetal),
+    element(23, "V", "Vanadium", 50.942, TransitionMetal),
+    element(24, "Cr", "Chromium", 51.996, TransitionMetal),
+    element(25, "Mn", "Manganese", 54.938, TransitionMetal),
+    element(26, "Fe", "Iron", 55.845, TransitionMetal),
+    element(27, "Co", "Cobalt", 58.933, TransitionMetal),
+    element(28, "Ni", "Nickel", 58.693, TransitionMetal),
+    element(29, "Cu", "Copper", 63.546, TransitionMetal),
+    element(30, "Zn", "Zinc", 65.38, TransitionMetal),
+    element(31, "Ga", "Gallium", 69.723, PostTransitionMetal),
+    element(32, "Ge", "Germanium", 72.630, Metalloid),
+    element(33, "As", "Arsenic", 74.922, Metalloid),
+    element(34, "Se", "Selenium", 78.971, Nonmetal),
+    element(35, "Br", "Bromine", 79.904, Halogen),
+    element(36, "Kr", "Krypton", 83.798, NobleGas),
+    element(37, "Rb", "Rubidium", 85.468, AlkaliMetal),
+    element(38, "Sr", "Strontium", 87.62, AlkalineEarthMetal),
+    element(39, "Y", "Yttrium", 88.906, TransitionMetal),
+    element(40, "Zr", "Zirconium", 91.224, TransitionMetal),
+    element(41, "Nb", "Niobium", 92.906, TransitionMetal),
+    element(42, "Mo", "Molybdenum", 95.95, TransitionMetal),
+    element(43, "Tc", "Technetium", 98.0, TransitionMetal),
+    element(44, "Ru", "Ruthenium", 101.07, TransitionMetal),
+    element(45, "Rh", "Rhodium", 102.91, TransitionMetal),
+    element(46, "Pd", "Palladium", 106.42, TransitionMetal),
+    element(47, "Ag", "Silver", 107.87, TransitionMetal),
+    element(48, "Cd", "Cadmium", 112.41, TransitionMetal),
+    element(49, "In", "Indium", 114.82, PostTransitionMetal),
+    element(50, "Sn", "Tin", 118.71, PostTransitionMetal),
+    element(51, "Sb", "Antimony", 121.76, Metalloid),
+    element(52, "Te", "Tellurium", 127.60, Metalloid),
+    element(53, "I", "Iodine", 126.90, Halogen),
+    element(54, "Xe", "Xenon", 131.29, NobleGas),
+    element(55, "Cs", "Caesium", 132.91, AlkaliMetal),
+    element(56, "Ba", "Barium", 137.33, AlkalineEarthMetal),
+    element(57, "La", "Lanthanum", 138.91, Lanthanide),
+    element(58, "Ce", "Cerium", 140.12, Lanthanide),
+    element(59, "Pr", "Praseodymium", 140.91, Lanthanide),
+    element(60, "Nd", "Neodymium", 144.24, Lanthanide),
+    element(61, "Pm", "Promethium", 145.0, Lanthanide),
+    element(62, "Sm", "Samarium", 150.36, Lanthanide),
+    element(63, "Eu", "Europium", 151.96, Lanthanide),
+    element(64, "Gd", "Gadolinium", 157.25, Lanthanide),
+    element(65, "Tb", "Terbium", 158.93, Lanthanide),
+    element(66, "Dy", "Dysprosium", 162.50, Lanthanide),
+    element(67, "Ho", "Holmium", 164.93, Lanthanide),
+    element(68, "Er", "Erbium", 167.26, Lanthanide),
+    element(69, "Tm", "Thulium", 168.93, Lanthanide),
+    element(70, "Yb", "Ytterbium", 173.05, Lanthanide),
+    element(71, "Lu", "Lutetium", 174.97, Lanthanide),
+    element(72, "Hf", "Hafnium", 178.49, TransitionMetal),
+    element(73, "Ta", "Tantalum", 180.95, TransitionMetal),
+    element(74, "W", "Tungsten", 183.84, TransitionMetal),
+    element(75, "Re", "Rhenium", 186.21, TransitionMetal),
+    element(76, "Os", "Osmium", 190.23, TransitionMetal),
+    element(77, "Ir", "Iridium", 192.22, TransitionMetal),
+    element(78, "Pt", "Platinum", 195.08, TransitionMetal),
+    element(79, "Au", "Gold", 196.97, TransitionMetal),
+    element(80, "Hg", "Mercury", 200.59, TransitionMetal),
+    element(81, "Tl", "Thallium", 204.38, PostTransitionMetal),
+    element(82, "Pb", "Lead", 207.2, PostTransitionMetal),
+    element(83, "Bi", "Bismuth", 208.98, PostTransitionMetal),
+    element(84, "Po", "Polonium", 209.0, PostTransitionMetal),
+    element(85, "At", "Astatine", 210.0, Halogen),
+    element(86, "Rn", "Radon", 222.0, NobleGas),
+    element(87, "Fr", "Francium", 223.0, AlkaliMetal),
+    element(88, "Ra", "Radium", 226.0, AlkalineEarthMetal),
+    element(89, "Ac", "Actinium", 227.0, Actinide),
+    element(90, "Th", "Thorium", 232.04, Actinide),
+    element(91, "Pa", "Protactinium", 231.04, Actinide),
+    element(92, "U", "Uranium", 238.03, Actinide),
+];
+
+/// Exact symbol, as written in formulas (`Co` is cobalt, `CO` is not an
+/// element).
+pub fn by_symbol(symbol: &str) -> Option<&'static Element> {
+    ELEMENTS.iter().find(|element| element.symbol == symbol)
+}
+
+/// Symbol from a URL, where `fe` and `FE` mean `Fe`.
+pub fn lookup(raw: &str) -> Option<&'static Element> {
+    ELEMENTS
+        .iter()
+        .find(|element| element.symbol.eq_ignore_ascii_case(raw.trim()))
+}
+
+/// A mineral's share of the element: the entered wt% when there is one,
+/// otherwise what the formula implies.
+pub fn weight_pct(mineral: &Mineral, symbol: &str) -> Option<f32> {
+    mineral
+        .major_elements_pct
+        .get(symbol)
+        .copied()
+        .or_else(|| {
+            chemistry::weight_percentages(&mineral.formula)
+                .ok()?
+                .get(symbol)
+                .copied()
+        })
+        .filter(|pct| *pct > 0.0)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ElementMineral {
+    pub slug: String,
+    pub common_name: String,
+    pub formula: String,
+    pub mineral_family: String,
+    pub weight_pct: f32,
+}
+
+/// Catalog minerals containing `symbol`, richest first.
+fn minerals_containing(minerals: &[Mineral], symbol: &str) -> Vec<ElementMineral> {
+    let mut found = minerals
+        .iter()
+        .filter_map(|mineral| {
+            Some(ElementMineral {
+                weight_pct: weight_pct(mineral, symbol)?,
+                slug: mineral.slug.clone(),
+                common_name: mineral.common_name.clone(),
+                formula: mineral.formula.clone(),
+                mineral_family: mineral.mineral_family.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+    found.sort_by(|a, b| {
+        b.weight_pct
+            .total_cmp(&a.weight_pct)
+            .then_with(|| a.common_name.cmp(&b.common_name))
+    });
+    found
+}
+
+fn find(symbol: &str) -> Result<&'static Element, AppError> {
+    lookup(symbol).ok_or_else(|| AppError::NotFound(format!("element '{symbol}' not found")))
+}
+
+/// `/elements/:symbol`.
+pub async fn element_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(symbol): AxumPath<String>,
+) -> Result<TemplateResponse<ElementTemplate>, AppError> {
+    let language = resolve_language(&state, &headers);
+    let element = find(&symbol)?;
+    let catalog = visible_catalog(&state, &headers, language).await?;
+    Ok(TemplateResponse(ElementTemplate {
+        lang_code: language.code().to_string(),
+        lang_dir: language.dir().to_string(),
+        txt: ui_text(language),
+        locale: LocaleFormat::new(language),
+        element: *element,
+        minerals: minerals_containing(&catalog.ordered, element.symbol),
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ElementApiResponse {
+    #[serde(flatten)]
+    element: Element,
+    minerals: Vec<ElementMineral>,
+}
+
+/// `/api/elements/:symbol`, in English.
+pub async fn element_api(
+    State(state): State<AppState>,
+    AxumPath(symbol): AxumPath<String>,
+) -> Result<Json<ElementApiResponse>, AppError> {
+    let element = find(&symbol)?;
+    let catalog = catalog_for_language(&state, Language::En)
+        .await?
+        .public_view();
+    Ok(Json(ElementApiResponse {
+        element: *element,
+        minerals: minerals_containing(&catalog.ordered, element.symbol),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{by_symbol, lookup, minerals_containing, Category, ELEMENTS};
+    use crate::models::{sample_mineral, Mineral};
+
+    #[test]
+    fn table_is_in_atomic_number_order() {
+        assert_eq!(ELEMENTS.len(), 92);
+        for (index, element) in ELEMENTS.iter().enumerate() {
+            assert_eq!(usize::from(element.atomic_number), index + 1);
+        }
+        assert_eq!(lookup("fe").unwrap().name, "Iron");
+        assert_eq!(lookup("SI").unwrap().category, Category::Metalloid);
+        assert!(by_symbol("CO").is_none());
+        assert!(lookup("Xx").is_none());
+    }
+
+    fn mineral(slug: &str, formula: &str, elements: &[(&str, f32)]) -> Mineral {
+        Mineral {
+            slug: slug.to_string(),
+            folder_name: slug.to_string(),
+            common_name: slug.to_string(),
+            mineral_family: "oxides".to_string(),
+            formula: formula.to_string(),
+            hardness_mohs: 6.0,
+            density_g_cm3: 5.0,
+            crystal_system: "trigonal".to_string(),
+            color: "black".to_string(),
+            streak: "red".to_string(),
+            luster: "metallic".to_string(),
+            major_elements_pct: elements
+                .iter()
+                .map(|(element, percent)| (element.to_string(), *percent))
+                .collect(),
+            ..sample_mineral()
+        }
+    }
+
+    #[test]
+    fn lists_minerals_richest_first() {
+        let minerals = vec![
+            mineral("magnetite", "Fe3O4", &[]),
+            mineral("hematite", "Fe2O3", &[("Fe", 69.9), ("O", 30.1)]),
+            mineral("quartz", "SiO2", &[("Si", 46.7), ("O", 53.3)]),
+        ];
+        let iron = minerals_containing(&minerals, "Fe");
+        let slugs = iron
+            .iter()
+            .map(|item| item.slug.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(slugs, vec!["magnetite", "hematite"]);
+        assert_eq!(iron[0].weight_pct, 72.36);
+        assert!(minerals_containing(&minerals, "Cu").is_empty());
+    }
+}
@@ -0,0 +1,309 @@
+//! Outgoing mail: admin notifications (new inquiries, failed PDF jobs, an
+//! exhausted LLM budget) and reports sent as attachments. Mail goes through
+//! the SMTP server named by `SMTP_HOST`, or the local `sendmail` when it is
+//! unset.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use lettre::{
+    message::{header::ContentType, Attachment as MailAttachment, Mailbox, MultiPart, SinglePart},
+    transport::smtp::authentication::Credentials,
+    AsyncSendmailTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use tokio::fs;
+use tracing::{info, warn};
+
+use crate::{config::EmailConfig, pdf::GeneratedArtifacts, signing::SIGNATURE_FILE};
+
+/// A mail server that hangs must not pile up tasks.
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SmtpSecurity {
+    /// Plain connection upgraded with `STARTTLS`; port 587.
+    #[default]
+    StartTls,
+    /// TLS from the first byte; port 465.
+    Tls,
+    /// No encryption, for a relay on localhost; port 25.
+    None,
+}
+
+impl SmtpSecurity {
+    pub fn all() -> &'static [SmtpSecurity] {
+        &[
+            SmtpSecurity::StartTls,
+            SmtpSecurity::Tls,
+            SmtpSecurity::None,
+        ]
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SmtpSecurity::StartTls => "starttls",
+            SmtpSecurity::Tls => "tls",
+            SmtpSecurity::None => "none",
+        }
+    }
+
+    pub fn from_code(value: &str) -> Option<Self> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|security| security.as_str().eq_ignore_ascii_case(value.trim()))
+    }
+
+    pub fn default_port(self) -> u16 {
+        match self {
+            SmtpSecurity::StartTls => 587,
+            SmtpSecurity::Tls => 465,
+            SmtpSecurity::None => 25,
+        }
+    }
+}
+
+/// One file attached to a message.
+pub struct Attachment {
+    pub file_name: String,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+enum Transport {
+    Smtp(AsyncSmtpTransport<Tokio1Executor>),
+    Sendmail(AsyncSendmailTransport<Tokio1Executor>),
+}
+
+pub struct Mailer {
+    transport: Transport,
+    from: Mailbox,
+    /// `ADMIN_EMAIL_TO`; notifications are skipped when empty.
+    admin_to: Vec<Mailbox>,
+}
+
+impl Mailer {
+    pub fn new(config: &EmailConfig) -> Result<Self> {
+        let transport = match &config.smtp_host {
+            Some(host) => {
+                let builder = match config.smtp_security {
+                    SmtpSecurity::StartTls => {
+                        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+                    }
+                    SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host),
+                    SmtpSecurity::None => Ok(
+                        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+                    ),
+                }
+                .with_context(|| format!("invalid SMTP host '{host}'"))?
+                .port(config.smtp_port)
+                .timeout(Some(SEND_TIMEOUT));
+                let builder = match (&config.smtp_username, &config.smtp_password) {
+                    (Some(username), Some(password)) => {
+                        builder.credentials(Credentials::new(username.clone(), password.clone()))
+                    }
+                    _ => builder,
+                };
+                info!(
+                    "email: SMTP {host}:{} ({})",
+                    config.smtp_port,
+                    config.smtp_security.as_str()
+                );
+                Transport::Smtp(builder.build())
+            }
+            None => Transport::Sendmail(
+                AsyncSendmailTransport::<Tokio1Executor>::new_with_command(&config.sendmail_bin),
+            ),
+        };
+        Ok(Self {
+            transport,
+            from: config.from.clone(),
+            admin_to: config.admin_to.clone(),
+        })
+    }
+
+    /// Mails every admin; failures are logged, since whatever is being
+    /// announced has already happened.
+    pub async fn notify_admins(&self, subject: &str, body: &str, reply_to: Option<&str>) {
+        if self.admin_to.is_empty() {
+            return;
+        }
+        let result = async {
+            let reply_to = reply_to
+                .map(|address| address.parse::<Mailbox>())
+                .transpose()
+                .context("invalid Reply-To address")?;
+            let message = self.message(&self.admin_to, reply_to, subject, body, Vec::new())?;
+            self.deliver(message).await
+        }
+        .await;
+        if let Err(err) = result {
+            warn!("admin notification '{subject}' was not sent: {err:#}");
+        }
+    }
+
+    /// Mails `to` (one or more comma-separated addresses) with
+    /// `attachments`.
+    pub async fn send(
+        &self,
+        to: &str,
+        subject: &str,
+        body: &str,
+        attachments: Vec<Attachment>,
+    ) -> Result<()> {
+        let to = parse_addresses(to)?;
+        let message = self.message(&to, None, subject, body, attachments)?;
+        self.deliver(message).await
+    }
+
+    /// Mails a finished run's `report.pdf` and its `report.sig` to `to`.
+    pub async fn send_report(
+        &self,
+        to: &str,
+        title: &str,
+        artifacts: &GeneratedArtifacts,
+        link: &str,
+    ) -> Result<()> {
+        let mut attachments = Vec::new();
+        for (file_name, content_type) in [
+            ("report.pdf", "application/pdf"),
+            (SIGNATURE_FILE, "application/json"),
+        ] {
+            let path = artifacts.run_dir.join(file_name);
+            attachments.push(Attachment {
+                file_name: file_name.to_string(),
+                content_type,
+                body: fs::read(&path)
+                    .await
+                    .with_context(|| format!("failed to read {}", path.display()))?,
+            });
+        }
+        let body = format!(
+            "{title} is attached, with {SIGNATURE_FILE}, its integrity record.\n\n\
+             The report online: {link}\n"
+        );
+        self.send(to, title, &body, attachments).await
+    }
+
+    fn message(
+        &self,
+        to: &[Mailbox],
+        reply_to: Option<Mailbox>,
+        subject: &str,
+        body: &str,
+        attachments: Vec<Attachment>,
+    ) -> Result<Message> {
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(subject.replace(['\r', '\n'], " "));
+        for mailbox in to {
+            builder = builder.to(mailbox.clone());
+        }
+        if let Some(reply_to) = reply_to {
+            builder = builder.reply_to(reply_to);
+        }
+        let text = SinglePart::plain(body.to_string());
+        let message = if attachments.is_empty() {
+            builder.singlepart(text)
+        } else {
+            let mut parts = MultiPart::mixed().singlepart(text);
+            for attachment in attachments {
+                let content_type =
+                    ContentType::parse(attachment.content_type).with_context(|| {
+                        format!("invalid content type for {}", attachment.file_name)
+                    })?;
+                parts = parts.singlepart(
+                    MailAttachment::new(attachment.file_name).body(attachment.body, content_type),
+                );
+            }
+            builder.multipart(parts)
+        };
+        message.context("failed to build the message")
+    }
+
+    async fn deliver(&self, message: Message) -> Result<()> {
+        let sent = async {
+            match &self.transport {
+                Transport::Smtp(transport) => transport
+                    .send(message)
+                    .await
+                    .map(drop)
+                    .context("SMTP delivery failed"),
+                Transport::Sendmail(transport) => transport
+                    .send(message)
+                    .await
+                    .context("sendmail delivery failed"),
+            }
+        };
+        tokio::time::timeout(SEND_TIMEOUT, sent)
+            .await
+            .map_err(|_| anyhow!("mail delivery timed out"))?
+    }
+}
+
+/// Comma-separated addresses, each optionally with a display name:
+/// `Sales <sales@example.org>, ops@example.org`.
+pub fn parse_addresses(raw: &str) -> Result<Vec<Mailbox>> {
+    let mailboxes = raw
+        .split(',')
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .map(|address| {
+            address
+                .parse::<Mailbox>()
+                .map_err(|err| anyhow!("'{address}' is not an email address: {err}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if mailboxes.is_empty() {
+        return Err(anyhow!("no email address given"));
+    }
+    Ok(mailboxes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_addresses, Attachment, Mailer, SmtpSecurity};
+    use crate::config::EmailConfig;
+
+    #[test]
+    fn builds_messages_with_attachments_and_parses_address_lists() {
+        let admins = parse_addresses("Sales <sales@example.org>, ops@example.org,").unwrap();
+        assert_eq!(admins.len(), 2);
+        assert_eq!(admins[0].email.to_string(), "sales@example.org");
+        assert!(parse_addresses("sales@example.org, nobody").is_err());
+        assert!(parse_addresses(" , ").is_err());
+        assert_eq!(SmtpSecurity::from_code(" TLS "), Some(SmtpSecurity::Tls));
+
+        let mailer = Mailer::new(&EmailConfig {
+            smtp_host: None,
+            smtp_port: 587,
+            smtp_security: SmtpSecurity::StartTls,
+            smtp_username: None,
+            smtp_password: None,
+            from: "Minerals <minerals@example.org>".parse().unwrap(),
+            admin_to: admins.clone(),
+            sendmail_bin: "sendmail".to_string(),
+        })
+        .unwrap();
+        let message = mailer
+            .message(
+                &admins,
+                Some("ana@example.com".parse().unwrap()),
+                "Report\r\nBcc: victim@example.org",
+                "Attached.",
+                vec![Attachment {
+                    file_name: "report.pdf".to_string(),
+                    content_type: "application/pdf",
+                    body: b"%PDF-1.7".to_vec(),
+                }],
+            )
+            .unwrap();
+        let raw = String::from_utf8(message.formatted()).unwrap();
+        assert!(raw.contains("From: Minerals <minerals@example.org>"));
+        assert!(raw.contains("Reply-To: ana@example.com"));
+        assert!(raw.contains("Subject: Report  Bcc: victim@example.org"));
+        assert!(!raw.contains("\r\nBcc:"));
+        assert!(raw.contains("filename=\"report.pdf\""));
+        assert!(raw.contains("Content-Type: application/pdf"));
+    }
+}
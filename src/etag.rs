@@ -0,0 +1,169 @@
+//! Conditional GET support. Catalog pages get an `ETag` derived from the
+//! catalog generation, so a kiosk revalidating an unchanged page gets a
+//! `304` without the page being rendered; files under `/data/minerals` get
+//! one derived from their size and modification time.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::{Component, Path},
+    sync::atomic::Ordering,
+    time::UNIX_EPOCH,
+};
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{accounts, csrf, presets, resolve_language, resolve_units, AppState};
+
+/// Wraps `/minerals`, `/minerals/:slug`, and `/shop`. The tag covers the catalog
+/// generation, the active language and unit system, the visitor or admin
+/// session, the visitor's favorites, the saved report presets, and the full
+/// URI (sort, page, slug).
+pub async fn catalog_pages(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    // Pages polling a PDF job change while the catalog stays the same.
+    let polls_job = request
+        .uri()
+        .query()
+        .is_some_and(|query| query.split('&').any(|pair| pair.starts_with("job=")));
+    if !is_read(request.method()) || polls_job {
+        return next.run(request).await;
+    }
+
+    let mut hasher = DefaultHasher::new();
+    request.uri().hash(&mut hasher);
+    resolve_units(request.headers()).code().hash(&mut hasher);
+    // A signed-in visitor's mineral pages show their favorite star.
+    if let Some(session) = accounts::visitor_session(&state, request.headers()) {
+        session.csrf_token.hash(&mut hasher);
+        state
+            .accounts
+            .favorites(&session.account_id)
+            .hash(&mut hasher);
+    }
+    // Mineral pages list the saved presets, and give admins the form to
+    // save one.
+    presets::names(&state).await.hash(&mut hasher);
+    csrf::session_token(&state, request.headers()).hash(&mut hasher);
+    let tag = format!(
+        "W/\"c{}-{}-{:x}\"",
+        state.catalog_generation.load(Ordering::Relaxed),
+        resolve_language(&state, request.headers()).code(),
+        hasher.finish()
+    );
+
+    let mut response = respond(&tag, request, next).await;
+    // The language and units come from cookies, so caches must key on them
+    // too.
+    response
+        .headers_mut()
+        .insert(header::VARY, HeaderValue::from_static("cookie"));
+    response
+}
+
+/// Wraps the `/data/minerals` file service (paths arrive with that prefix
+/// already stripped).
+pub async fn data_files(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if !is_read(request.method()) {
+        return next.run(request).await;
+    }
+    let Some(relative) = safe_relative_path(request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let metadata = match tokio::fs::metadata(state.data_root.join("minerals").join(relative)).await
+    {
+        Ok(metadata) if metadata.is_file() => metadata,
+        _ => return next.run(request).await,
+    };
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .unwrap_or_default();
+    let tag = format!(
+        "W/\"{:x}-{:x}.{:x}\"",
+        metadata.len(),
+        modified.as_secs(),
+        modified.subsec_nanos()
+    );
+
+    respond(&tag, request, next).await
+}
+
+async fn respond(tag: &str, request: Request, next: Next) -> Response {
+    let Ok(value) = HeaderValue::from_str(tag) else {
+        return next.run(request).await;
+    };
+    if matches_if_none_match(request.headers(), tag) {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, value)]).into_response();
+    }
+
+    let mut response = next.run(request).await;
+    if response.status() == StatusCode::OK {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    response
+}
+
+fn is_read(method: &Method) -> bool {
+    method == Method::GET || method == Method::HEAD
+}
+
+/// Weak comparison, as RFC 9110 requires for `If-None-Match`.
+fn matches_if_none_match(headers: &HeaderMap, tag: &str) -> bool {
+    let opaque = |value: &str| value.trim().trim_start_matches("W/").to_string();
+    let tag = opaque(tag);
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|candidate| candidate.trim() == "*" || opaque(candidate) == tag)
+}
+
+/// Plain relative paths only; anything encoded or escaping the root is left
+/// for the file service to answer.
+fn safe_relative_path(path: &str) -> Option<&Path> {
+    let relative = Path::new(path.trim_start_matches('/'));
+    let plain = !path.contains('%')
+        && relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+    plain.then_some(relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use axum::http::{header, HeaderMap, HeaderValue};
+
+    use super::{matches_if_none_match, safe_relative_path};
+
+    #[test]
+    fn compares_tags_weakly_and_rejects_escaping_paths() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_static("\"other\", \"c3-en-ab\""),
+        );
+        assert!(matches_if_none_match(&headers, "W/\"c3-en-ab\""));
+        assert!(!matches_if_none_match(&headers, "W/\"c4-en-ab\""));
+        assert!(!matches_if_none_match(&HeaderMap::new(), "W/\"c3-en-ab\""));
+
+        assert_eq!(
+            safe_relative_path("/mineral.x.0x1/image.png"),
+            Some(Path::new("mineral.x.0x1/image.png"))
+        );
+        assert_eq!(safe_relative_path("/../secret"), None);
+        assert_eq!(safe_relative_path("/a%2e%2e/b"), None);
+    }
+}
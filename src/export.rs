@@ -0,0 +1,297 @@
+//! Spreadsheet exports of the whole catalog: one row per mineral, with the
+//! `major_elements_pct` map flattened into one column per element and the
+//! `extra_attributes` map into one column per attribute name.
+
+use std::collections::BTreeSet;
+
+use anyhow::{Context, Result};
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+};
+use rust_xlsxwriter::{Format, Workbook};
+use serde::Deserialize;
+
+use crate::{
+    i18n::{ui_text, Language, UiText},
+    models::Mineral,
+    resolve_language, visible_catalog, AppError, AppState,
+};
+
+const XLSX_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportParams {
+    lang: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Cell {
+    Text(String),
+    Number(f64),
+    Empty,
+}
+
+#[derive(Debug, Clone)]
+struct CatalogTable {
+    headers: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+pub async fn export_csv(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<ExportParams>,
+) -> Result<Response, AppError> {
+    let language = export_language(&state, &headers, &params)?;
+    let table = catalog_table(
+        &visible_catalog(&state, &headers, language).await?.ordered,
+        &ui_text(language),
+    );
+    let body = table.to_csv()?;
+    Ok(attachment(
+        body,
+        "text/csv; charset=utf-8",
+        &format!("minerals.{}.csv", language.code()),
+    ))
+}
+
+pub async fn export_xlsx(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<ExportParams>,
+) -> Result<Response, AppError> {
+    let language = export_language(&state, &headers, &params)?;
+    let table = catalog_table(
+        &visible_catalog(&state, &headers, language).await?.ordered,
+        &ui_text(language),
+    );
+    let body = table.to_xlsx()?;
+    Ok(attachment(
+        body,
+        XLSX_CONTENT_TYPE,
+        &format!("minerals.{}.xlsx", language.code()),
+    ))
+}
+
+/// `?lang=` wins over the language cookie so scripts can pick a language
+/// without managing cookies.
+fn export_language(
+    state: &AppState,
+    headers: &HeaderMap,
+    params: &ExportParams,
+) -> Result<Language, AppError> {
+    match params.lang.as_deref() {
+        Some(code) => Language::from_code(code)
+            .ok_or_else(|| AppError::BadRequest(format!("unsupported language code '{code}'"))),
+        None => Ok(resolve_language(state, headers)),
+    }
+}
+
+pub fn attachment(body: impl Into<Body>, content_type: &'static str, file_name: &str) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            ),
+        ],
+        body.into(),
+    )
+        .into_response()
+}
+
+fn catalog_table(minerals: &[Mineral], txt: &UiText) -> CatalogTable {
+    let elements = minerals
+        .iter()
+        .flat_map(|mineral| mineral.major_elements_pct.keys().cloned())
+        .collect::<BTreeSet<_>>();
+    let attributes = minerals
+        .iter()
+        .flat_map(|mineral| mineral.extra_attributes.keys().cloned())
+        .collect::<BTreeSet<_>>();
+
+    let mut headers = vec![
+        "slug".to_string(),
+        txt.label_name.to_string(),
+        txt.label_family.to_string(),
+        txt.label_formula.to_string(),
+        txt.label_hardness.to_string(),
+        txt.label_density.to_string(),
+        txt.label_crystal_system.to_string(),
+        txt.label_color.to_string(),
+        txt.label_streak.to_string(),
+        txt.label_luster.to_string(),
+        txt.label_description.to_string(),
+        txt.label_notes.to_string(),
+    ];
+    headers.extend(
+        elements
+            .iter()
+            .map(|element| format!("{element} ({})", txt.label_weight_pct)),
+    );
+    headers.extend(attributes.iter().cloned());
+
+    let rows = minerals
+        .iter()
+        .map(|mineral| {
+            let mut row = vec![
+                Cell::Text(mineral.slug.clone()),
+                Cell::Text(mineral.common_name.clone()),
+                Cell::Text(mineral.mineral_family.clone()),
+                Cell::Text(mineral.formula.clone()),
+                number(mineral.hardness_mohs),
+                number(mineral.density_g_cm3),
+                Cell::Text(mineral.crystal_system.clone()),
+                Cell::Text(mineral.color.clone()),
+                Cell::Text(mineral.streak.clone()),
+                Cell::Text(mineral.luster.clone()),
+                Cell::Text(mineral.description.clone()),
+                Cell::Text(mineral.notes.clone()),
+            ];
+            row.extend(elements.iter().map(|element| {
+                mineral
+                    .major_elements_pct
+                    .get(element)
+                    .map(|pct| number(*pct))
+                    .unwrap_or(Cell::Empty)
+            }));
+            row.extend(attributes.iter().map(|name| {
+                mineral
+                    .extra_attributes
+                    .get(name)
+                    .map(|value| Cell::Text(value.clone()))
+                    .unwrap_or(Cell::Empty)
+            }));
+            row
+        })
+        .collect();
+
+    CatalogTable { headers, rows }
+}
+
+/// Goes through the `f32` display form so spreadsheets show `2.65`
+/// rather than `2.6500000953674316`.
+fn number(value: f32) -> Cell {
+    value
+        .to_string()
+        .parse()
+        .map(Cell::Number)
+        .unwrap_or(Cell::Empty)
+}
+
+impl CatalogTable {
+    fn to_csv(&self) -> Result<Vec<u8>> {
+        // The BOM makes Excel read the file as UTF-8 instead of the
+        // system code page, which matters for the non-Latin languages.
+        let mut writer = csv::Writer::from_writer(b"\xEF\xBB\xBF".to_vec());
+        writer
+            .write_record(&self.headers)
+            .context("failed to write CSV header")?;
+        for row in &self.rows {
+            writer
+                .write_record(row.iter().map(|cell| match cell {
+                    Cell::Text(text) => text.clone(),
+                    Cell::Number(value) => value.to_string(),
+                    Cell::Empty => String::new(),
+                }))
+                .context("failed to write CSV row")?;
+        }
+        writer
+            .into_inner()
+            .map_err(|err| anyhow::anyhow!("failed to finish CSV export: {err}"))
+    }
+
+    fn to_xlsx(&self) -> Result<Vec<u8>> {
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        let bold = Format::new().set_bold();
+
+        for (col, title) in self.headers.iter().enumerate() {
+            sheet
+                .write_string_with_format(0, col as u16, title, &bold)
+                .context("failed to write XLSX header")?;
+        }
+        for (index, row) in self.rows.iter().enumerate() {
+            let line = index as u32 + 1;
+            for (col, cell) in row.iter().enumerate() {
+                match cell {
+                    Cell::Text(text) => sheet.write_string(line, col as u16, text),
+                    Cell::Number(value) => sheet.write_number(line, col as u16, *value),
+                    Cell::Empty => continue,
+                }
+                .context("failed to write XLSX cell")?;
+            }
+        }
+        sheet
+            .set_freeze_panes(1, 0)
+            .context("failed to freeze XLSX header row")?;
+
+        workbook
+            .save_to_buffer()
+            .context("failed to build XLSX workbook")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{catalog_table, Cell};
+    use crate::{
+        i18n::{ui_text, Language},
+        models::{sample_mineral, Mineral},
+    };
+
+    fn mineral(slug: &str, elements: &[(&str, f32)], lot: Option<&str>) -> Mineral {
+        Mineral {
+            slug: slug.to_string(),
+            folder_name: format!("mineral.test.{slug}"),
+            common_name: slug.to_string(),
+            mineral_family: "oxide".to_string(),
+            formula: "X".to_string(),
+            hardness_mohs: 5.5,
+            density_g_cm3: 2.65,
+            crystal_system: "cubic".to_string(),
+            color: "grey".to_string(),
+            streak: "black".to_string(),
+            luster: "metallic".to_string(),
+            major_elements_pct: elements
+                .iter()
+                .map(|(name, pct)| (name.to_string(), *pct))
+                .collect::<BTreeMap<_, _>>(),
+            extra_attributes: lot
+                .map(|lot| [("Lot".to_string(), lot.to_string())].into())
+                .unwrap_or_default(),
+            ..sample_mineral()
+        }
+    }
+
+    #[test]
+    fn flattens_elements_into_shared_columns() {
+        let minerals = [
+            mineral("hematite", &[("Fe", 69.9), ("O", 30.1)], Some("L-17")),
+            mineral("quartz", &[("O", 53.3), ("Si", 46.7)], None),
+        ];
+        let table = catalog_table(&minerals, &ui_text(Language::En));
+
+        assert_eq!(table.headers.len(), 16);
+        assert_eq!(table.headers[12], "Fe (Weight Percent)");
+        assert_eq!(table.rows[0][5], Cell::Number(2.65));
+        assert_eq!(table.rows[1][12], Cell::Empty);
+        assert_eq!(table.rows[1][14], Cell::Number(46.7));
+        assert_eq!(table.headers[15], "Lot");
+        assert_eq!(table.rows[0][15], Cell::Text("L-17".to_string()));
+        assert_eq!(table.rows[1][15], Cell::Empty);
+
+        let csv = String::from_utf8(table.to_csv().unwrap()).unwrap();
+        assert!(
+            csv.contains("quartz,quartz,oxide,X,5.5,2.65,cubic,grey,black,metallic,,,,53.3,46.7,")
+        );
+        assert!(table.to_xlsx().unwrap().starts_with(b"PK"));
+    }
+}
@@ -0,0 +1,288 @@
+//! Mineral families: `/families` lists the published catalog grouped by
+//! family and `/families/:family` one family, with a description admins
+//! edit on that page. Families are identified by the slug of their English
+//! name, so the same URL works in every language; descriptions are kept
+//! per language in `data/families.json`.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::{anyhow, Context, Result};
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::HeaderMap,
+    response::Redirect,
+    Form,
+};
+use serde::Deserialize;
+use tokio::fs;
+
+use crate::{
+    catalog::MineralCatalog,
+    catalog_for_language, csrf, has_admin_session,
+    i18n::{ui_text, Language},
+    lang_path,
+    models::Mineral,
+    permalinks::name_slug,
+    resolve_language, visible_catalog,
+    web::{FamiliesTemplate, FamilyTemplate, TemplateResponse},
+    AppError, AppState,
+};
+
+const MAX_DESCRIPTION_CHARS: usize = 4000;
+
+/// Family descriptions by language code, then family slug.
+#[derive(Debug, Clone)]
+pub struct FamilyNotes {
+    path: PathBuf,
+    notes: Arc<RwLock<BTreeMap<String, BTreeMap<String, String>>>>,
+}
+
+impl FamilyNotes {
+    pub async fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let notes = match fs::read_to_string(&path).await {
+            Ok(raw) => serde_json::from_str(&raw)
+                .with_context(|| format!("failed to parse {}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(anyhow!("failed to read {}: {err}", path.display())),
+        };
+        Ok(Self {
+            path,
+            notes: Arc::new(RwLock::new(notes)),
+        })
+    }
+
+    /// The description in `language`, or the English one while it has
+    /// none.
+    pub fn description(&self, language: Language, family: &str) -> String {
+        let Ok(notes) = self.notes.read() else {
+            return String::new();
+        };
+        [language, Language::En]
+            .iter()
+            .find_map(|language| notes.get(language.code())?.get(family))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Replaces the description in `language`; a blank one removes it.
+    pub async fn set(&self, language: Language, family: &str, description: &str) -> Result<()> {
+        let snapshot = {
+            let mut notes = self
+                .notes
+                .write()
+                .map_err(|_| anyhow!("family notes lock poisoned"))?;
+            let entries = notes.entry(language.code().to_string()).or_default();
+            match description.trim() {
+                "" => entries.remove(family),
+                description => entries.insert(family.to_string(), description.to_string()),
+            };
+            notes.retain(|_, entries| !entries.is_empty());
+            notes.clone()
+        };
+        let json =
+            serde_json::to_string_pretty(&snapshot).context("failed to serialize family notes")?;
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, json)
+            .await
+            .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &self.path)
+            .await
+            .with_context(|| format!("failed to replace {}", self.path.display()))
+    }
+}
+
+/// One family and its minerals in catalog order.
+#[derive(Debug, Clone)]
+pub struct FamilyGroup {
+    pub slug: String,
+    /// As the page language writes it.
+    pub name: String,
+    pub description: String,
+    pub minerals: Vec<Mineral>,
+}
+
+/// The `/families/<slug>` of a mineral's family, from its English record;
+/// `None` for minerals without a family.
+pub fn family_slug(english: &MineralCatalog, mineral_slug: &str) -> Option<String> {
+    english
+        .by_slug
+        .get(mineral_slug)
+        .and_then(|mineral| name_slug(&mineral.mineral_family))
+}
+
+/// Groups `catalog` by family, alphabetical by the localized name;
+/// minerals without a family are left out.
+fn group(catalog: &MineralCatalog, english: &MineralCatalog) -> Vec<FamilyGroup> {
+    let mut groups: Vec<FamilyGroup> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for mineral in &catalog.ordered {
+        let Some(slug) = family_slug(english, &mineral.slug) else {
+            continue;
+        };
+        let position = *index.entry(slug.clone()).or_insert_with(|| {
+            groups.push(FamilyGroup {
+                slug,
+                name: mineral.mineral_family.trim().to_string(),
+                description: String::new(),
+                minerals: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[position].minerals.push(mineral.clone());
+    }
+    // Folded so `Óxidos` sorts with the O's.
+    groups.sort_by_cached_key(|group| {
+        name_slug(&group.name).unwrap_or_else(|| group.name.to_lowercase())
+    });
+    groups
+}
+
+async fn families(
+    state: &AppState,
+    headers: &HeaderMap,
+    language: Language,
+) -> Result<Vec<FamilyGroup>, AppError> {
+    let catalog = visible_catalog(state, headers, language).await?;
+    let english = catalog_for_language(state, Language::En).await?;
+    Ok(group(&catalog, &english)
+        .into_iter()
+        .map(|group| FamilyGroup {
+            description: state.family_notes.description(language, &group.slug),
+            ..group
+        })
+        .collect())
+}
+
+async fn find(
+    state: &AppState,
+    headers: &HeaderMap,
+    language: Language,
+    family: &str,
+) -> Result<FamilyGroup, AppError> {
+    families(state, headers, language)
+        .await?
+        .into_iter()
+        .find(|group| group.slug == family)
+        .ok_or_else(|| AppError::NotFound(format!("family '{family}' not found")))
+}
+
+/// `/families`.
+pub async fn families_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<TemplateResponse<FamiliesTemplate>, AppError> {
+    let language = resolve_language(&state, &headers);
+    Ok(TemplateResponse(FamiliesTemplate {
+        lang_code: language.code().to_string(),
+        lang_dir: language.dir().to_string(),
+        txt: ui_text(language),
+        families: families(&state, &headers, language).await?,
+    }))
+}
+
+/// `/families/:family`; admins also get the description form.
+pub async fn family_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(family): AxumPath<String>,
+) -> Result<TemplateResponse<FamilyTemplate>, AppError> {
+    let language = resolve_language(&state, &headers);
+    let family = find(&state, &headers, language, &family).await?;
+    Ok(TemplateResponse(FamilyTemplate {
+        lang_code: language.code().to_string(),
+        lang_dir: language.dir().to_string(),
+        txt: ui_text(language),
+        family,
+        has_admin_session: has_admin_session(&state, &headers),
+        csrf_token: csrf::session_token(&state, &headers).unwrap_or_default(),
+    }))
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct DescriptionForm {
+    csrf_token: String,
+    description: String,
+}
+
+/// `POST /admin/families/:family`: the description in the page language.
+pub async fn save_description(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(family): AxumPath<String>,
+    Form(form): Form<DescriptionForm>,
+) -> Result<Redirect, AppError> {
+    csrf::verify(&state, &headers, &form.csrf_token)?;
+    let language = resolve_language(&state, &headers);
+    let family = find(&state, &headers, language, &family).await?;
+    if form.description.chars().count() > MAX_DESCRIPTION_CHARS {
+        return Err(AppError::BadRequest(format!(
+            "the description is limited to {MAX_DESCRIPTION_CHARS} characters"
+        )));
+    }
+    state
+        .family_notes
+        .set(language, &family.slug, &form.description)
+        .await?;
+    Ok(Redirect::to(&lang_path::prefixed(
+        &headers,
+        &format!("/families/{}", family.slug),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::group;
+    use crate::{
+        catalog::MineralCatalog,
+        models::{sample_mineral, Mineral},
+    };
+
+    fn mineral(slug: &str, family: &str) -> Mineral {
+        Mineral {
+            slug: slug.to_string(),
+            folder_name: slug.to_string(),
+            common_name: slug.to_string(),
+            mineral_family: family.to_string(),
+            hardness_mohs: 5.0,
+            density_g_cm3: 3.0,
+            ..sample_mineral()
+        }
+    }
+
+    #[test]
+    fn groups_by_english_family_with_localized_names() {
+        let english = MineralCatalog::new(vec![
+            mineral("quartz", "Silicates"),
+            mineral("hematite", "Oxides"),
+            mineral("beryl", "Silicates"),
+            mineral("unknown", ""),
+        ]);
+        let spanish = MineralCatalog::new(vec![
+            mineral("quartz", "Silicatos"),
+            mineral("hematite", "Óxidos"),
+            mineral("beryl", "Silicatos"),
+            mineral("unknown", ""),
+        ]);
+        let groups = group(&spanish, &english);
+        let summary: Vec<_> = groups
+            .iter()
+            .map(|group| {
+                (
+                    group.slug.as_str(),
+                    group.name.as_str(),
+                    group.minerals.len(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [("oxides", "Óxidos", 1), ("silicates", "Silicatos", 2)]
+        );
+    }
+}
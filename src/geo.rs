@@ -0,0 +1,198 @@
+//! Where a mineral was collected: a free-text locality and country plus
+//! optional coordinates, shown as degrees/minutes/seconds, an OpenStreetMap
+//! link, and a single map tile with a marker.
+
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+/// OpenStreetMap zoom level for the embedded tile; 6 shows a region of a
+/// few hundred kilometres.
+const MAP_ZOOM: u32 = 6;
+
+/// Stored flat in the metadata JSON (`locality`, `country`, `latitude`,
+/// `longitude`) and shared by every language.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Location {
+    pub locality: String,
+    pub country: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// One 256px OpenStreetMap tile and where the point falls on it, in
+/// percent from the top-left corner.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MapTile {
+    pub url: String,
+    pub marker_left_pct: String,
+    pub marker_top_pct: String,
+}
+
+impl Location {
+    /// Builds a location from form text; blank coordinates mean none.
+    pub fn parse(
+        locality: &str,
+        country: &str,
+        latitude: &str,
+        longitude: &str,
+    ) -> Result<Self, String> {
+        let coordinate = |raw: &str, key: &str| match raw.trim() {
+            "" => Ok(None),
+            value => value
+                .parse::<f64>()
+                .map(Some)
+                .map_err(|_| format!("'{key}' must be a decimal number of degrees")),
+        };
+        Self {
+            locality: locality.to_string(),
+            country: country.to_string(),
+            latitude: coordinate(latitude, "latitude")?,
+            longitude: coordinate(longitude, "longitude")?,
+        }
+        .validate()
+    }
+
+    /// Trims the text fields and checks that coordinates come in pairs and
+    /// lie on the globe.
+    pub fn validate(self) -> Result<Self, String> {
+        match (self.latitude, self.longitude) {
+            (Some(latitude), Some(longitude)) => {
+                if !(latitude.is_finite() && (-90.0..=90.0).contains(&latitude)) {
+                    return Err("'latitude' must be between -90 and 90".to_string());
+                }
+                if !(longitude.is_finite() && (-180.0..=180.0).contains(&longitude)) {
+                    return Err("'longitude' must be between -180 and 180".to_string());
+                }
+            }
+            (None, None) => {}
+            _ => return Err("'latitude' and 'longitude' must be given together".to_string()),
+        }
+        Ok(Self {
+            locality: self.locality.trim().to_string(),
+            country: self.country.trim().to_string(),
+            ..self
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.place().is_empty() && self.coordinates().is_none()
+    }
+
+    /// `Minas Gerais, Brazil`, skipping whichever part is blank.
+    pub fn place(&self) -> String {
+        [self.locality.as_str(), self.country.as_str()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn coordinates(&self) -> Option<Coordinates> {
+        Some(Coordinates {
+            latitude: self.latitude?,
+            longitude: self.longitude?,
+        })
+    }
+
+    pub fn latitude_text(&self) -> String {
+        self.latitude
+            .map(|value| value.to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn longitude_text(&self) -> String {
+        self.longitude
+            .map(|value| value.to_string())
+            .unwrap_or_default()
+    }
+}
+
+impl Coordinates {
+    /// `-19.91670, -43.93450`.
+    pub fn decimal(&self) -> String {
+        format!("{:.5}, {:.5}", self.latitude, self.longitude)
+    }
+
+    /// `19°55′00″ S, 43°56′04″ W`.
+    pub fn dms(&self) -> String {
+        format!(
+            "{} {}, {} {}",
+            dms_part(self.latitude),
+            if self.latitude < 0.0 { 'S' } else { 'N' },
+            dms_part(self.longitude),
+            if self.longitude < 0.0 { 'W' } else { 'E' },
+        )
+    }
+
+    pub fn osm_url(&self) -> String {
+        format!(
+            "https://www.openstreetmap.org/?mlat={lat:.5}&mlon={lon:.5}#map=10/{lat:.5}/{lon:.5}",
+            lat = self.latitude,
+            lon = self.longitude,
+        )
+    }
+
+    /// Web Mercator tile containing the point at `MAP_ZOOM`.
+    pub fn tile(&self) -> MapTile {
+        let tiles = f64::from(1u32 << MAP_ZOOM);
+        let x = (self.longitude + 180.0) / 360.0 * tiles;
+        // Mercator is undefined at the poles; clamp to its usual limit.
+        let latitude = self.latitude.clamp(-85.0511, 85.0511).to_radians();
+        let y = (1.0 - latitude.tan().asinh() / PI) / 2.0 * tiles;
+        let max_index = tiles - 1.0;
+        let (tile_x, tile_y) = (x.floor().min(max_index), y.floor().min(max_index));
+
+        MapTile {
+            url: format!(
+                "https://tile.openstreetmap.org/{MAP_ZOOM}/{}/{}.png",
+                tile_x as u32, tile_y as u32
+            ),
+            marker_left_pct: format!("{:.1}", (x - tile_x) * 100.0),
+            marker_top_pct: format!("{:.1}", (y - tile_y) * 100.0),
+        }
+    }
+}
+
+fn dms_part(degrees: f64) -> String {
+    let total_seconds = (degrees.abs() * 3600.0).round() as u64;
+    format!(
+        "{}°{:02}′{:02}″",
+        total_seconds / 3600,
+        total_seconds / 60 % 60,
+        total_seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Location;
+
+    #[test]
+    fn parses_validates_and_formats_coordinates() {
+        let location = Location::parse(" Ouro Preto ", "Brazil", "-20.3856", "-43.5035").unwrap();
+        assert_eq!(location.place(), "Ouro Preto, Brazil");
+        let coordinates = location.coordinates().unwrap();
+        assert_eq!(coordinates.dms(), "20°23′08″ S, 43°30′13″ W");
+        assert_eq!(coordinates.decimal(), "-20.38560, -43.50350");
+
+        let tile = coordinates.tile();
+        assert_eq!(tile.url, "https://tile.openstreetmap.org/6/24/35.png");
+        assert_eq!(
+            (tile.marker_left_pct.as_str(), tile.marker_top_pct.as_str()),
+            ("26.6", "70.3")
+        );
+
+        assert!(Location::parse("", "", "", "").unwrap().is_empty());
+        assert!(Location::parse("", "", "91", "0").is_err());
+        assert!(Location::parse("", "", "10", "").is_err());
+        assert!(Location::parse("", "", "north", "10").is_err());
+    }
+}
@@ -0,0 +1,501 @@
+//! Read-only GraphQL over the published catalog at `POST /graphql`, for
+//! partners who want to pick fields instead of taking whole records:
+//! minerals with their element breakdown, filtered by family, hardness,
+//! crystal system, tag, and text, and the rule-based report for a given
+//! audience. With `GRAPHQL_PLAYGROUND`, `GET /graphql` serves an in-browser
+//! query editor. Private and unlisted minerals are left out unless the
+//! request carries an admin session.
+
+use std::sync::Arc;
+
+use async_graphql::{
+    http::{playground_source, GraphQLPlaygroundConfig},
+    Context, EmptyMutation, EmptySubscription, Enum, InputObject, Object, Schema, SimpleObject,
+};
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::{Html, IntoResponse, Response},
+    Json,
+};
+use tracing::error;
+
+use crate::{
+    agent::{run_agentic_chain, ElementShare, MineralReport},
+    catalog::{CatalogQuery, MineralCatalog, SortKey, MAX_PAGE_SIZE},
+    catalog_for_language, default_report_request, get_mineral_with_related, has_admin_session,
+    i18n::Language,
+    load_attachments,
+    models::{Mineral, ReportRequest},
+    AppError, AppState,
+};
+
+pub type CatalogSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Each `report` counts 10 fields, since it reads the mineral's folder; a
+/// query asking for a few dozen reports through aliases is refused.
+const MAX_COMPLEXITY: usize = 400;
+
+pub fn schema() -> CatalogSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+/// Whether the request came with an admin session, which also sees
+/// private and unlisted minerals.
+struct AdminSession(bool);
+
+pub async fn execute(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    let schema = state.graphql.clone();
+    let admin = AdminSession(has_admin_session(&state, &headers));
+    Json(schema.execute(request.data(state).data(admin)).await)
+}
+
+pub async fn playground(State(state): State<AppState>) -> Result<Response, AppError> {
+    if !state.graphql_playground {
+        return Err(AppError::NotFound(
+            "the GraphQL playground is disabled; set GRAPHQL_PLAYGROUND=true".to_string(),
+        ));
+    }
+    Ok(Html(playground_source(GraphQLPlaygroundConfig::new("/graphql"))).into_response())
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Published minerals, sorted by `sort` or, with `search`, by relevance.
+    /// `lang` defaults to the site's default language.
+    #[allow(clippy::too_many_arguments)]
+    async fn minerals(
+        &self,
+        ctx: &Context<'_>,
+        lang: Option<String>,
+        filter: Option<MineralFilter>,
+        search: Option<String>,
+        #[graphql(default)] sort: MineralSort,
+        #[graphql(default)] offset: usize,
+        #[graphql(default = 50)] limit: usize,
+    ) -> async_graphql::Result<MineralPage> {
+        let state = ctx.data::<AppState>()?;
+        let language = language(state, lang.as_deref())?;
+        if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+            return Err(format!("limit must be between 1 and {MAX_PAGE_SIZE}").into());
+        }
+        let query = CatalogQuery::from(filter.unwrap_or_default());
+        let catalog = visible_catalog(ctx, state, language).await?;
+        let matching = match search.as_deref().map(str::trim) {
+            Some(text) if !text.is_empty() => catalog
+                .search(text, state.search_fuzzy_distance)
+                .into_iter()
+                .filter(|mineral| query.matches(mineral))
+                .collect(),
+            _ => catalog
+                .matching(&query, sort.into())
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>(),
+        };
+
+        Ok(MineralPage {
+            total: matching.len(),
+            items: matching
+                .into_iter()
+                .skip(offset)
+                .take(limit)
+                .map(|mineral| MineralObject { mineral, language })
+                .collect(),
+        })
+    }
+
+    /// One published mineral, or `null` when there is none with `slug`.
+    async fn mineral(
+        &self,
+        ctx: &Context<'_>,
+        slug: String,
+        lang: Option<String>,
+    ) -> async_graphql::Result<Option<MineralObject>> {
+        let state = ctx.data::<AppState>()?;
+        let language = language(state, lang.as_deref())?;
+        let catalog = visible_catalog(ctx, state, language).await?;
+        Ok(catalog
+            .resolve(&slug)
+            .cloned()
+            .map(|mineral| MineralObject { mineral, language }))
+    }
+}
+
+/// Unset fields match every mineral.
+#[derive(Debug, Default, InputObject)]
+struct MineralFilter {
+    /// Case-insensitive substring, so `silicate` matches `Silicates`.
+    family: Option<String>,
+    hardness_min: Option<f32>,
+    hardness_max: Option<f32>,
+    crystal_system: Option<String>,
+    tag: Option<String>,
+}
+
+impl From<MineralFilter> for CatalogQuery {
+    fn from(filter: MineralFilter) -> Self {
+        CatalogQuery {
+            family: filter.family,
+            hardness_min: filter.hardness_min,
+            hardness_max: filter.hardness_max,
+            crystal_system: filter.crystal_system,
+            tag: filter.tag,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Enum)]
+enum MineralSort {
+    #[default]
+    Name,
+    Hardness,
+    Density,
+    Family,
+}
+
+impl From<MineralSort> for SortKey {
+    fn from(sort: MineralSort) -> Self {
+        match sort {
+            MineralSort::Name => SortKey::Name,
+            MineralSort::Hardness => SortKey::Hardness,
+            MineralSort::Density => SortKey::Density,
+            MineralSort::Family => SortKey::Family,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+struct MineralPage {
+    /// Matches before `offset` and `limit`.
+    total: usize,
+    items: Vec<MineralObject>,
+}
+
+struct MineralObject {
+    mineral: Mineral,
+    language: Language,
+}
+
+#[Object(name = "Mineral")]
+impl MineralObject {
+    async fn slug(&self) -> &str {
+        &self.mineral.slug
+    }
+
+    async fn lang(&self) -> &str {
+        self.language.code()
+    }
+
+    async fn name(&self) -> &str {
+        &self.mineral.common_name
+    }
+
+    async fn family(&self) -> &str {
+        &self.mineral.mineral_family
+    }
+
+    async fn formula(&self) -> &str {
+        &self.mineral.formula
+    }
+
+    async fn description(&self) -> &str {
+        &self.mineral.description
+    }
+
+    async fn hardness_mohs(&self) -> f64 {
+        float(self.mineral.hardness_mohs)
+    }
+
+    async fn density_g_cm3(&self) -> f64 {
+        float(self.mineral.density_g_cm3)
+    }
+
+    async fn crystal_system(&self) -> &str {
+        &self.mineral.crystal_system
+    }
+
+    async fn color(&self) -> &str {
+        &self.mineral.color
+    }
+
+    async fn streak(&self) -> &str {
+        &self.mineral.streak
+    }
+
+    async fn luster(&self) -> &str {
+        &self.mineral.luster
+    }
+
+    async fn notes(&self) -> &str {
+        &self.mineral.notes
+    }
+
+    async fn tags(&self) -> &[String] {
+        &self.mineral.tags
+    }
+
+    async fn locality(&self) -> &str {
+        &self.mineral.location.locality
+    }
+
+    async fn country(&self) -> &str {
+        &self.mineral.location.country
+    }
+
+    async fn latitude(&self) -> Option<f64> {
+        self.mineral.location.latitude
+    }
+
+    async fn longitude(&self) -> Option<f64> {
+        self.mineral.location.longitude
+    }
+
+    /// Public page in this language.
+    async fn page_url(&self, ctx: &Context<'_>) -> async_graphql::Result<String> {
+        let state = ctx.data::<AppState>()?;
+        Ok(state
+            .pdf_generator
+            .mineral_url(&self.mineral.slug, self.language))
+    }
+
+    async fn image_url(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<String>> {
+        let state = ctx.data::<AppState>()?;
+        Ok(self
+            .mineral
+            .image_path
+            .as_deref()
+            .map(|path| state.pdf_generator.public_url(path)))
+    }
+
+    async fn thumbnail_url(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<String>> {
+        let state = ctx.data::<AppState>()?;
+        Ok(self
+            .mineral
+            .thumbnail_src()
+            .map(|path| state.pdf_generator.public_url(path)))
+    }
+
+    /// Major elements in wt%, largest first.
+    async fn elements(&self) -> Vec<ElementShareObject> {
+        let mut elements = self
+            .mineral
+            .major_elements_pct
+            .iter()
+            .map(|(name, percent)| ElementShareObject {
+                name: name.clone(),
+                percent: float(*percent),
+            })
+            .collect::<Vec<_>>();
+        elements.sort_by(|a, b| b.percent.total_cmp(&a.percent));
+        elements
+    }
+
+    /// The rule-based report the mineral page shows; arguments left out
+    /// take the page's defaults for the language.
+    #[graphql(complexity = "10 + child_complexity")]
+    async fn report(
+        &self,
+        ctx: &Context<'_>,
+        audience: Option<String>,
+        purpose: Option<String>,
+        site_context: Option<String>,
+    ) -> async_graphql::Result<ReportObject> {
+        let state = ctx.data::<AppState>()?;
+        let defaults = default_report_request(self.language);
+        let request = ReportRequest {
+            audience: audience.unwrap_or(defaults.audience),
+            purpose: purpose.unwrap_or(defaults.purpose),
+            site_context: site_context.unwrap_or(defaults.site_context),
+            ..defaults
+        };
+        let (mineral, related) = get_mineral_with_related(state, self.language, &self.mineral.slug)
+            .await
+            .map_err(graphql_error)?;
+        let attachments = load_attachments(state, &mineral)
+            .await
+            .map_err(graphql_error)?;
+        Ok(ReportObject(run_agentic_chain(
+            &mineral,
+            &request,
+            related,
+            attachments,
+            state.classification,
+            self.language,
+        )))
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "ElementShare")]
+struct ElementShareObject {
+    name: String,
+    /// wt%.
+    percent: f64,
+}
+
+impl From<&ElementShare> for ElementShareObject {
+    fn from(share: &ElementShare) -> Self {
+        Self {
+            name: share.name.clone(),
+            percent: float(share.percent),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "RelatedMineral")]
+struct RelatedMineralObject {
+    slug: String,
+    name: String,
+    family: String,
+    /// 0 to 1.
+    similarity: f64,
+}
+
+struct ReportObject(MineralReport);
+
+#[Object(name = "Report")]
+impl ReportObject {
+    async fn audience(&self) -> &str {
+        &self.0.audience
+    }
+
+    async fn purpose(&self) -> &str {
+        &self.0.purpose
+    }
+
+    async fn site_context(&self) -> &str {
+        &self.0.site_context
+    }
+
+    /// RFC 3339.
+    async fn generated_utc(&self) -> &str {
+        &self.0.generated_utc
+    }
+
+    async fn summary(&self) -> &str {
+        &self.0.summary
+    }
+
+    async fn recommendations(&self) -> &[String] {
+        &self.0.recommendations
+    }
+
+    async fn hardness_band(&self) -> &str {
+        &self.0.hardness_band
+    }
+
+    async fn density_band(&self) -> &str {
+        &self.0.density_band
+    }
+
+    /// Nickel–Strunz class, e.g. `9.FA Tectosilicates`.
+    async fn strunz_class(&self) -> &str {
+        &self.0.strunz_class
+    }
+
+    async fn dominant_element(&self) -> &str {
+        &self.0.dominant_element
+    }
+
+    async fn dominant_element_pct(&self) -> f64 {
+        float(self.0.dominant_element_pct)
+    }
+
+    async fn element_breakdown(&self) -> Vec<ElementShareObject> {
+        self.0.element_breakdown.iter().map(Into::into).collect()
+    }
+
+    /// Closest catalog matches, best first.
+    async fn related(&self) -> Vec<RelatedMineralObject> {
+        self.0
+            .related
+            .iter()
+            .map(|related| RelatedMineralObject {
+                slug: related.slug.clone(),
+                name: related.common_name.clone(),
+                family: related.mineral_family.clone(),
+                similarity: float(related.similarity),
+            })
+            .collect()
+    }
+}
+
+fn language(state: &AppState, code: Option<&str>) -> async_graphql::Result<Language> {
+    match code {
+        Some(code) => Language::from_code(code)
+            .ok_or_else(|| format!("unsupported language code '{code}'").into()),
+        None => Ok(state.default_language),
+    }
+}
+
+/// `48.8` rather than the `48.79999923706055` that `f64::from` gives.
+fn float(value: f32) -> f64 {
+    value.to_string().parse().unwrap_or(value.into())
+}
+
+/// The catalog in `language`, as the request's session may see it.
+async fn visible_catalog(
+    ctx: &Context<'_>,
+    state: &AppState,
+    language: Language,
+) -> async_graphql::Result<Arc<MineralCatalog>> {
+    let catalog = catalog_for_language(state, language)
+        .await
+        .map_err(graphql_error)?;
+    Ok(match ctx.data_opt::<AdminSession>() {
+        Some(AdminSession(true)) => catalog,
+        _ => catalog.public_view(),
+    })
+}
+
+/// Client errors keep their message; internal ones are logged and hidden,
+/// as `AppError` responses do.
+fn graphql_error(err: AppError) -> async_graphql::Error {
+    match err {
+        AppError::Internal(err) => {
+            error!("internal error: {err:#}");
+            async_graphql::Error::new("internal server error")
+        }
+        other => async_graphql::Error::new(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::schema;
+
+    #[tokio::test]
+    async fn validates_queries_against_the_schema() {
+        let schema = schema();
+        let sdl = schema.sdl();
+        assert!(sdl.contains("type Mineral {"));
+        assert!(sdl.contains("type ElementShare {"));
+        assert!(
+            sdl.contains("report(audience: String, purpose: String, siteContext: String): Report!")
+        );
+
+        let unknown = schema.execute("{ minerals { items { weight } } }").await;
+        assert!(unknown.errors[0]
+            .message
+            .contains("Unknown field \"weight\""));
+
+        let reports = (0..40)
+            .map(|index| format!("r{index}: report {{ summary }}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let costly = schema
+            .execute(format!("{{ mineral(slug: \"x\") {{ {reports} }} }}"))
+            .await;
+        assert!(costly.errors[0].message.contains("too complex"));
+    }
+}
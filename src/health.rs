@@ -0,0 +1,158 @@
+//! Liveness and readiness probes for orchestrators. `/healthz` only says
+//! the process is serving; `/readyz` checks the dependencies a publish or
+//! PDF run needs and answers `503` when any of them is missing.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+use tokio::fs;
+
+use crate::AppState;
+
+const LLM_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Serialize)]
+pub struct HealthResponse {
+    status: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    status: &'static str,
+    checks: Vec<ReadinessCheck>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessCheck {
+    name: &'static str,
+    ok: bool,
+    detail: String,
+}
+
+pub async fn healthz() -> Json<HealthResponse> {
+    Json(HealthResponse { status: "ok" })
+}
+
+pub async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<ReadinessResponse>) {
+    let mut checks = vec![
+        data_dir_check(&state.data_root).await,
+        pdf_tool_check(&state),
+    ];
+    if state.readyz_check_llm {
+        checks.push(llm_check(&state).await);
+    }
+
+    let ready = checks.iter().all(|check| check.ok);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(ReadinessResponse {
+            status: if ready { "ready" } else { "not_ready" },
+            checks,
+        }),
+    )
+}
+
+/// Writes and removes a probe file; permissions alone do not catch
+/// read-only mounts or a full disk.
+async fn data_dir_check(data_root: &Path) -> ReadinessCheck {
+    let probe = data_root.join(".readyz");
+    let result = match fs::write(&probe, b"ok").await {
+        Ok(()) => fs::remove_file(&probe).await,
+        Err(err) => Err(err),
+    };
+    ReadinessCheck {
+        name: "data_dir",
+        ok: result.is_ok(),
+        detail: match result {
+            Ok(()) => format!("{} is writable", data_root.display()),
+            Err(err) => format!("{} is not writable: {err}", data_root.display()),
+        },
+    }
+}
+
+fn pdf_tool_check(state: &AppState) -> ReadinessCheck {
+    let backend = state.pdf_generator.default_backend();
+    let program = state
+        .pdf_generator
+        .backend_program(backend)
+        .unwrap_or_default();
+    let found = find_program(program);
+    ReadinessCheck {
+        name: "pdf_backend",
+        ok: found.is_some(),
+        detail: match found {
+            Some(path) => format!("{} uses {}", backend.as_str(), path.display()),
+            None => format!("{} needs '{program}' on PATH", backend.as_str()),
+        },
+    }
+}
+
+/// Any HTTP answer counts as reachable: the probe has no credentials, so a
+/// `401` or `404` still proves the endpoint is up.
+async fn llm_check(state: &AppState) -> ReadinessCheck {
+    let Some(provider) = state.llm.as_deref() else {
+        return ReadinessCheck {
+            name: "llm",
+            ok: false,
+            detail: "no LLM provider is configured".to_string(),
+        };
+    };
+    let Some(endpoint) = provider.endpoint() else {
+        return ReadinessCheck {
+            name: "llm",
+            ok: true,
+            detail: format!("{} has no endpoint to probe", provider.kind().as_str()),
+        };
+    };
+
+    let result = reqwest::Client::new()
+        .get(endpoint)
+        .timeout(LLM_PROBE_TIMEOUT)
+        .send()
+        .await;
+    ReadinessCheck {
+        name: "llm",
+        ok: result.is_ok(),
+        detail: match result {
+            Ok(response) => format!("{endpoint} answered {}", response.status()),
+            Err(err) => format!("{endpoint} is unreachable: {err}"),
+        },
+    }
+}
+
+/// Resolves `program` the way the shell would: paths are checked directly,
+/// bare names are searched on `PATH`.
+fn find_program(program: &str) -> Option<PathBuf> {
+    if program.is_empty() {
+        return None;
+    }
+    if program.contains(std::path::MAIN_SEPARATOR) {
+        let path = PathBuf::from(program);
+        return path.is_file().then_some(path);
+    }
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_program;
+
+    #[test]
+    fn finds_programs_on_path_only_when_present() {
+        assert!(find_program("sh").is_some());
+        assert!(find_program("definitely-not-a-real-tool-7f3a").is_none());
+        assert!(find_program("").is_none());
+    }
+}
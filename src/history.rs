@@ -0,0 +1,429 @@
+//! Version history for mineral records. Before an edit, API update, or
+//! restore rewrites a mineral's metadata, the current files of every
+//! language are saved as `history/<timestamp>.json` inside the mineral
+//! folder. Anyone can list the versions; admins can compare one with the
+//! live record and restore it.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use axum::{
+    extract::{Path as AxumPath, Query, State},
+    http::HeaderMap,
+    response::Redirect,
+    Form,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::fs;
+use tracing::info;
+
+use crate::{
+    csrf::{self, CsrfForm},
+    folder_file_name, get_mineral, has_admin_session,
+    i18n::{ui_text, Language},
+    migrations, mineral_folder_for_slug,
+    models::MineralDiskRecord,
+    read_english_record, read_record_file, refresh_mineral, require_admin_token, require_visible,
+    resolve_language,
+    timestamp_id::{self, is_valid_timestamp_id},
+    web::{HistoryTemplate, TemplateResponse},
+    write_localized_records, AppError, AppState,
+};
+
+pub const HISTORY_DIR: &str = "history";
+
+/// One saved version: the metadata of every language as it was on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub saved_utc: String,
+    /// What replaced this version: `edit` or `restore`.
+    pub reason: String,
+    pub records: BTreeMap<String, MineralDiskRecord>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VersionSummary {
+    pub id: String,
+    pub saved_at: String,
+    pub reason: String,
+    pub common_name: String,
+}
+
+/// English field that differs between a saved version and the live record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub saved: String,
+    pub current: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub version: VersionSummary,
+    pub changes: Vec<FieldChange>,
+    /// Other languages whose wording differs from the saved version.
+    pub changed_languages: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct HistoryQuery {
+    #[serde(default)]
+    restored: Option<String>,
+}
+
+/// Saves the current metadata of `folder_path` before it is overwritten.
+pub async fn record(folder_path: &Path, reason: &str) -> Result<(), AppError> {
+    let records = read_current_records(folder_path).await;
+    if records.is_empty() {
+        return Ok(());
+    }
+    let now = Utc::now();
+    let snapshot = Snapshot {
+        saved_utc: now.to_rfc3339(),
+        reason: reason.to_string(),
+        records,
+    };
+
+    let history_dir = folder_path.join(HISTORY_DIR);
+    fs::create_dir_all(&history_dir)
+        .await
+        .with_context(|| format!("failed to create {}", history_dir.display()))?;
+    let path = history_dir.join(format!("{}.json", timestamp_id::timestamp_id(&now)));
+    let json = serde_json::to_string_pretty(&snapshot)
+        .context("failed to serialize mineral history snapshot")?;
+    fs::write(&path, json)
+        .await
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+pub async fn history_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<TemplateResponse<HistoryTemplate>, AppError> {
+    let language = resolve_language(&state, &headers);
+    let folder_path = mineral_folder(&state, language, &slug).await?;
+    let restored = query
+        .restored
+        .filter(|id| is_valid_timestamp_id(id))
+        .map(|id| display_time(&id));
+
+    Ok(TemplateResponse(HistoryTemplate {
+        restored,
+        ..history_template(&state, &headers, language, &slug, &folder_path).await?
+    }))
+}
+
+pub async fn history_version_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath((slug, version)): AxumPath<(String, String)>,
+) -> Result<TemplateResponse<HistoryTemplate>, AppError> {
+    require_admin_token(&state, &headers)?;
+    let language = resolve_language(&state, &headers);
+    let folder_path = mineral_folder(&state, language, &slug).await?;
+    let snapshot = read_snapshot(&folder_path, &version).await?;
+    let current = read_current_records(&folder_path).await;
+
+    let saved_english = snapshot.records.get(Language::En.code());
+    let current_english = current.get(Language::En.code());
+    let changes = match (saved_english, current_english) {
+        (Some(saved), Some(current)) => diff_records(saved, current),
+        _ => Vec::new(),
+    };
+    let changed_languages = snapshot
+        .records
+        .iter()
+        .filter(|(code, saved)| {
+            code.as_str() != Language::En.code()
+                && current
+                    .get(code.as_str())
+                    .is_some_and(|live| !diff_records(saved, live).is_empty())
+        })
+        .map(|(code, _)| code.clone())
+        .collect();
+
+    Ok(TemplateResponse(HistoryTemplate {
+        comparison: Some(Comparison {
+            version: summarize(&version, &snapshot),
+            changes,
+            changed_languages,
+        }),
+        ..history_template(&state, &headers, language, &slug, &folder_path).await?
+    }))
+}
+
+/// Puts a saved version back in place. The record being replaced is saved
+/// first, so a restore can itself be undone.
+pub async fn restore_version(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath((slug, version)): AxumPath<(String, String)>,
+    form: Option<Form<CsrfForm>>,
+) -> Result<Redirect, AppError> {
+    csrf::verify(&state, &headers, &csrf::form_token(form))?;
+    let language = resolve_language(&state, &headers);
+    let folder_path = mineral_folder(&state, language, &slug).await?;
+    let snapshot = read_snapshot(&folder_path, &version).await?;
+    let current = read_english_record(&folder_path).await?;
+
+    // Images replaced since the snapshot may be gone from disk.
+    let mut images = Vec::new();
+    if let Some(saved) = snapshot.records.get(Language::En.code()) {
+        for image in &saved.images {
+            if fs::try_exists(folder_path.join(&image.file))
+                .await
+                .unwrap_or(false)
+            {
+                images.push(image.clone());
+            }
+        }
+    }
+    if images.is_empty() {
+        images = current.images.clone();
+    }
+
+    let records = snapshot
+        .records
+        .into_iter()
+        .map(|(code, record)| {
+            let record = MineralDiskRecord {
+                images: images.clone(),
+                ..record
+            }
+            .normalize_images();
+            (code, record)
+        })
+        .collect::<HashMap<_, _>>();
+    let english = records
+        .get(Language::En.code())
+        .cloned()
+        .ok_or_else(|| AppError::BadRequest(format!("version {version} has no English record")))?;
+
+    record(&folder_path, "restore").await?;
+    write_localized_records(&folder_path, &records, english).await?;
+    refresh_mineral(&state, &folder_file_name(&folder_path)).await?;
+    info!("restored {slug} to version {version}");
+
+    Ok(Redirect::to(&format!(
+        "/minerals/{slug}/history?restored={version}"
+    )))
+}
+
+async fn history_template(
+    state: &AppState,
+    headers: &HeaderMap,
+    language: Language,
+    slug: &str,
+    folder_path: &Path,
+) -> Result<HistoryTemplate, AppError> {
+    let mineral = get_mineral(state, language, slug).await?;
+    require_visible(state, headers, slug, &mineral)?;
+    Ok(HistoryTemplate {
+        lang_code: language.code().to_string(),
+        lang_dir: language.dir().to_string(),
+        txt: ui_text(language),
+        mineral,
+        versions: list_versions(folder_path).await?,
+        has_admin_session: has_admin_session(state, headers),
+        comparison: None,
+        restored: None,
+        csrf_token: csrf::session_token(state, headers).unwrap_or_default(),
+    })
+}
+
+async fn mineral_folder(
+    state: &AppState,
+    language: Language,
+    slug: &str,
+) -> Result<PathBuf, AppError> {
+    mineral_folder_for_slug(state, language, slug)
+        .await
+        .map_err(AppError::NotFound)
+}
+
+/// Newest first. Unreadable snapshots are skipped rather than hiding the
+/// rest of the history.
+async fn list_versions(folder_path: &Path) -> Result<Vec<VersionSummary>, AppError> {
+    let history_dir = folder_path.join(HISTORY_DIR);
+    let mut entries = match fs::read_dir(&history_dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(AppError::Internal(
+                anyhow::Error::new(err)
+                    .context(format!("failed to read {}", history_dir.display())),
+            ))
+        }
+    };
+
+    let mut versions = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .with_context(|| format!("failed to read {}", history_dir.display()))?
+    {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(id) = file_name.strip_suffix(".json") else {
+            continue;
+        };
+        if !is_valid_timestamp_id(id) {
+            continue;
+        }
+        if let Ok(snapshot) = read_snapshot(folder_path, id).await {
+            versions.push(summarize(id, &snapshot));
+        }
+    }
+    versions.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(versions)
+}
+
+async fn read_snapshot(folder_path: &Path, version: &str) -> Result<Snapshot, AppError> {
+    if !is_valid_timestamp_id(version) {
+        return Err(AppError::NotFound(format!("version '{version}' not found")));
+    }
+    let path = folder_path
+        .join(HISTORY_DIR)
+        .join(format!("{version}.json"));
+    let raw = fs::read_to_string(&path)
+        .await
+        .map_err(|_| AppError::NotFound(format!("version '{version}' not found")))?;
+    let parse = || {
+        let mut snapshot = serde_json::from_str::<Value>(&raw)?;
+        // Saved records keep the layout they had; bring them up to date.
+        if let Some(records) = snapshot.get_mut("records").and_then(Value::as_object_mut) {
+            for record in records.values_mut() {
+                migrations::upgrade(record)?;
+            }
+        }
+        anyhow::Ok(serde_json::from_value::<Snapshot>(snapshot)?)
+    };
+    let snapshot = parse().with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(snapshot)
+}
+
+async fn read_current_records(folder_path: &Path) -> BTreeMap<String, MineralDiskRecord> {
+    let mut records = BTreeMap::new();
+    if let Ok(english) = read_english_record(folder_path).await {
+        records.insert(Language::En.code().to_string(), english);
+    }
+    for language in Language::all() {
+        if *language == Language::En {
+            continue;
+        }
+        let path = folder_path.join(format!("mineral.{}.json", language.code()));
+        if let Ok(record) = read_record_file(&path).await {
+            records.insert(language.code().to_string(), record);
+        }
+    }
+    records
+}
+
+fn summarize(id: &str, snapshot: &Snapshot) -> VersionSummary {
+    VersionSummary {
+        id: id.to_string(),
+        saved_at: DateTime::parse_from_rfc3339(&snapshot.saved_utc)
+            .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|_| snapshot.saved_utc.clone()),
+        reason: snapshot.reason.clone(),
+        common_name: snapshot
+            .records
+            .get(Language::En.code())
+            .map(|record| record.common_name.clone())
+            .unwrap_or_default(),
+    }
+}
+
+fn display_time(id: &str) -> String {
+    DateTime::parse_from_str(
+        &format!("{id}+0000"),
+        &format!("{}%z", timestamp_id::FORMAT),
+    )
+    .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+    .unwrap_or_else(|_| id.to_string())
+}
+
+/// Compares the serialized fields, so new record fields show up in diffs
+/// without touching this function.
+fn diff_records(saved: &MineralDiskRecord, current: &MineralDiskRecord) -> Vec<FieldChange> {
+    let (Ok(Value::Object(saved)), Ok(Value::Object(current))) =
+        (serde_json::to_value(saved), serde_json::to_value(current))
+    else {
+        return Vec::new();
+    };
+    let mut fields = saved.keys().chain(current.keys()).collect::<Vec<_>>();
+    fields.sort();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let before = saved.get(field).unwrap_or(&Value::Null);
+            let after = current.get(field).unwrap_or(&Value::Null);
+            (before != after).then(|| FieldChange {
+                field: field.clone(),
+                saved: display_value(before),
+                current: display_value(after),
+            })
+        })
+        .collect()
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{diff_records, display_time, FieldChange};
+    use crate::{models::MineralDiskRecord, timestamp_id::timestamp_id};
+
+    #[test]
+    fn diffs_changed_fields_and_displays_version_times() {
+        let saved = serde_json::from_str::<MineralDiskRecord>(
+            r#"{"common_name": "Quartz", "mineral_family": "Silicates", "formula": "SiO2",
+            "hardness_mohs": 7.0, "density_g_cm3": 2.65, "crystal_system": "trigonal",
+            "color": "colorless", "streak": "white", "luster": "vitreous", "notes": ""}"#,
+        )
+        .unwrap();
+        let current = MineralDiskRecord {
+            color: "smoky".to_string(),
+            hardness_mohs: 6.5,
+            ..saved.clone()
+        };
+
+        let changes = diff_records(&saved, &current);
+        assert_eq!(
+            changes,
+            vec![
+                FieldChange {
+                    field: "color".to_string(),
+                    saved: "colorless".to_string(),
+                    current: "smoky".to_string(),
+                },
+                FieldChange {
+                    field: "hardness_mohs".to_string(),
+                    saved: "7.0".to_string(),
+                    current: "6.5".to_string(),
+                },
+            ]
+        );
+        assert!(diff_records(&saved, &saved).is_empty());
+
+        let id = timestamp_id(&Utc.with_ymd_and_hms(2026, 10, 16, 16, 5, 6).unwrap());
+        assert_eq!(display_time(&id), "2026-10-16 16:05:06 UTC");
+    }
+}
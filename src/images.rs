@@ -0,0 +1,433 @@
+use std::{io::Cursor, path::Path, process::Stdio, time::Duration};
+
+use anyhow::{Context, Result};
+use image::{
+    codecs::jpeg::JpegEncoder, imageops::FilterType, metadata::Orientation, DynamicImage,
+    ImageDecoder, ImageFormat, ImageReader, Limits,
+};
+use tokio::{io::AsyncWriteExt, process::Command};
+use tracing::warn;
+
+/// Catalog grid variant of the first upload, written next to it.
+pub const THUMB_FILE: &str = "thumb.webp";
+/// Detail page variant of the first upload, written next to it.
+pub const MEDIUM_FILE: &str = "medium.webp";
+
+const THUMB_MAX_EDGE: u32 = 360;
+const MEDIUM_MAX_EDGE: u32 = 1200;
+/// libwebp quality (0-100); `image` itself only encodes lossless WebP,
+/// which is larger than the JPEG uploads it replaces.
+const WEBP_QUALITY: f32 = 80.0;
+const JPEG_QUALITY: u8 = 90;
+/// Uploads decoding to more pixels per edge than this are refused, so a
+/// small compressed file cannot expand into a huge bitmap.
+const UPLOAD_MAX_EDGE: u32 = 12_000;
+
+/// Accepted upload formats and the extension each is stored under.
+const UPLOAD_FORMATS: [(ImageFormat, &str); 4] = [
+    (ImageFormat::Png, "png"),
+    (ImageFormat::Jpeg, "jpg"),
+    (ImageFormat::WebP, "webp"),
+    (ImageFormat::Gif, "gif"),
+];
+
+/// HEIF brands (bytes 8..12 of the `ftyp` box) that phones write.
+const HEIF_BRANDS: [&[u8; 4]; 8] = [
+    b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis", b"mif1", b"msf1",
+];
+/// A stuck converter must not hold an upload request forever.
+const CONVERT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Upload formats the `image` crate cannot decode, by magic bytes:
+/// `heic` for HEIC/HEIF camera photos, `tiff` for TIFF scans. They go
+/// through [`convert_to_jpeg`] before [`sanitize_upload`].
+pub fn convertible_format(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"II*\0") || bytes.starts_with(b"MM\0*") {
+        return Some("tiff");
+    }
+    let brand = bytes.get(8..12)?;
+    (bytes.get(4..8) == Some(b"ftyp") && HEIF_BRANDS.iter().any(|known| known[..] == *brand))
+        .then_some("heic")
+}
+
+/// Transcodes a `heic` or `tiff` upload to JPEG with ImageMagick
+/// (`program` is `convert`, or `magick` on ImageMagick 7), through pipes
+/// so nothing touches the disk. The error is meant for the uploader.
+pub async fn convert_to_jpeg(program: &str, format: &str, bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let edge = UPLOAD_MAX_EDGE.to_string();
+    let mut child = Command::new(program)
+        .args(["-limit", "width", &edge, "-limit", "height", &edge])
+        .arg(format!("{format}:-"))
+        .args(["-auto-orient", "-strip", "-quality", &JPEG_QUALITY.to_string()])
+        .arg("jpeg:-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| {
+            warn!("image converter '{program}' unavailable: {err}");
+            format!("{format} photos cannot be converted on this server; upload a png, jpg, webp, or gif instead")
+        })?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = bytes.to_vec();
+    // Written alongside the read, or a large input fills the pipe while the
+    // converter waits for its output to be drained.
+    let writer = tokio::spawn(async move {
+        let _ = stdin.write_all(&input).await;
+    });
+    let output = tokio::time::timeout(CONVERT_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("converting the {format} image timed out"))?
+        .map_err(|err| format!("could not convert the {format} image: {err}"))?;
+    let _ = writer.await;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        warn!("{program} failed on a {format} upload: {}", stderr.trim());
+        return Err(format!("could not read the {format} image"));
+    }
+    Ok(output.stdout)
+}
+
+/// Checks an upload's magic bytes, decodes it, and re-encodes it in the
+/// same format, rotated upright per its EXIF orientation. The re-encoded
+/// file carries no EXIF (GPS included) or other metadata, and nothing
+/// appended after the image data survives. Returns the bytes to store and
+/// their extension; the error is meant for the uploader. CPU-bound; call
+/// it through `spawn_blocking`.
+pub fn sanitize_upload(bytes: &[u8]) -> Result<(Vec<u8>, &'static str), String> {
+    let sniffed = image::guess_format(bytes).ok();
+    let Some((format, ext)) = UPLOAD_FORMATS
+        .into_iter()
+        .find(|(format, _)| Some(*format) == sniffed)
+    else {
+        return Err("not a png, jpg, webp, gif, heic, or tiff image".to_string());
+    };
+
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(UPLOAD_MAX_EDGE);
+    limits.max_image_height = Some(UPLOAD_MAX_EDGE);
+    reader.limits(limits);
+    let unreadable = |err| format!("could not read the {ext} image: {err}");
+    let mut decoder = reader.into_decoder().map_err(unreadable)?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut image = DynamicImage::from_decoder(decoder).map_err(unreadable)?;
+    image.apply_orientation(orientation);
+
+    let encoded = encode(&image, format)
+        .map_err(|err| format!("could not re-encode the {ext} image: {err}"))?;
+    Ok((encoded, ext))
+}
+
+/// Rotation and crop picked in the publish form for one photo.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PhotoEdit {
+    /// Quarter turns clockwise, applied before the crop.
+    pub quarter_turns: u8,
+    /// The part of the rotated photo to keep, as left, top, width, and
+    /// height in percent of its size.
+    pub crop: Option<[f32; 4]>,
+}
+
+impl PhotoEdit {
+    /// Reads the form inputs: `rotation` in degrees clockwise (a multiple
+    /// of 90, blank for none) and `crop` as `left,top,width,height` in
+    /// percent (blank for none). The error is meant for the uploader.
+    pub fn parse(rotation: &str, crop: &str) -> Result<Self, String> {
+        let degrees = match rotation.trim() {
+            "" => 0,
+            value => value
+                .parse::<i32>()
+                .ok()
+                .filter(|degrees| degrees % 90 == 0)
+                .ok_or_else(|| format!("rotation must be a multiple of 90 degrees, got {value}"))?,
+        };
+        let crop = match crop.trim() {
+            "" => None,
+            value => Some(parse_crop(value)?),
+        };
+        Ok(Self {
+            quarter_turns: (degrees / 90).rem_euclid(4) as u8,
+            crop: crop.filter(|crop| *crop != [0.0, 0.0, 100.0, 100.0]),
+        })
+    }
+
+    pub fn is_noop(&self) -> bool {
+        self.quarter_turns == 0 && self.crop.is_none()
+    }
+}
+
+fn parse_crop(value: &str) -> Result<[f32; 4], String> {
+    let invalid = || format!("crop must be left,top,width,height in percent, got {value}");
+    let parts = value
+        .split(',')
+        .map(|part| part.trim().trim_end_matches('%').parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid())?;
+    let [left, top, width, height] = parts[..] else {
+        return Err(invalid());
+    };
+    let within = |offset: f32, size: f32| {
+        offset >= 0.0 && size > 0.0 && offset + size <= 100.0 + f32::EPSILON
+    };
+    if !within(left, width) || !within(top, height) {
+        return Err(format!("crop {value} falls outside the photo"));
+    }
+    Ok([left, top, width, height])
+}
+
+/// Name the unedited upload is kept under next to an edited `image_file`:
+/// `image.jpg` becomes `image_original.jpg`, `image-2.jpg`
+/// `image-2_original.jpg`. Originals get no variants.
+pub fn original_file(image_file: &str) -> String {
+    match image_file.rsplit_once('.') {
+        Some((stem, ext)) => format!("{stem}_original.{ext}"),
+        None => format!("{image_file}_original"),
+    }
+}
+
+/// Rotates then crops a sanitized upload, re-encoded in its own format.
+/// CPU-bound; call it through `spawn_blocking`.
+pub fn apply_edit(bytes: &[u8], edit: &PhotoEdit) -> Result<Vec<u8>> {
+    let format = image::guess_format(bytes).context("unknown image format")?;
+    let mut image =
+        image::load_from_memory_with_format(bytes, format).context("failed to decode image")?;
+    image = match edit.quarter_turns {
+        1 => image.rotate90(),
+        2 => image.rotate180(),
+        3 => image.rotate270(),
+        _ => image,
+    };
+    if let Some([left, top, width, height]) = edit.crop {
+        let (full_width, full_height) = (image.width(), image.height());
+        let pixels = |percent: f32, full: u32| ((percent / 100.0) * full as f32).round() as u32;
+        let x = pixels(left, full_width).min(full_width - 1);
+        let y = pixels(top, full_height).min(full_height - 1);
+        let crop_width = pixels(width, full_width).clamp(1, full_width - x);
+        let crop_height = pixels(height, full_height).clamp(1, full_height - y);
+        image = image.crop_imm(x, y, crop_width, crop_height);
+    }
+    encode(&image, format)
+}
+
+fn encode(image: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>> {
+    let mut encoded = Vec::new();
+    match format {
+        ImageFormat::WebP => return encode_webp(image),
+        ImageFormat::Jpeg => {
+            image.write_with_encoder(JpegEncoder::new_with_quality(&mut encoded, JPEG_QUALITY))?
+        }
+        _ => image.write_to(&mut Cursor::new(&mut encoded), format)?,
+    }
+    Ok(encoded)
+}
+
+/// Variant file names for an upload named `image[-N].<ext>`: `image.jpg`
+/// maps to `thumb.webp`/`medium.webp`, `image-2.jpg` to
+/// `thumb-2.webp`/`medium-2.webp`. Other names have no variants.
+pub fn variant_files(image_file: &str) -> Option<(String, String)> {
+    let (stem, _) = image_file.rsplit_once('.')?;
+    let suffix = stem.strip_prefix("image")?;
+    if suffix.is_empty() {
+        return Some((THUMB_FILE.to_string(), MEDIUM_FILE.to_string()));
+    }
+    let number = suffix.strip_prefix('-')?;
+    if number.is_empty() || !number.chars().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+    Some((
+        format!("thumb{suffix}.webp"),
+        format!("medium{suffix}.webp"),
+    ))
+}
+
+/// Decodes `image_bytes` and writes the downscaled WebP variants of
+/// `image_file` into `folder`. CPU-bound; call it through `spawn_blocking`.
+pub fn write_variants(folder: &Path, image_file: &str, image_bytes: &[u8]) -> Result<()> {
+    let (thumb_file, medium_file) =
+        variant_files(image_file).with_context(|| format!("no variant names for {image_file}"))?;
+    let original = image::load_from_memory(image_bytes).context("failed to decode image")?;
+    for (file_name, max_edge) in [(thumb_file, THUMB_MAX_EDGE), (medium_file, MEDIUM_MAX_EDGE)] {
+        let path = folder.join(file_name);
+        std::fs::write(&path, encode_webp(&downscale(&original, max_edge))?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Web-sized WebP of a photo whose background was removed, keeping its
+/// transparency. CPU-bound; call it through `spawn_blocking`.
+pub fn encode_clean(cutout: &[u8]) -> Result<Vec<u8>> {
+    let image = image::load_from_memory(cutout).context("failed to decode image")?;
+    encode_webp(&downscale(&image, MEDIUM_MAX_EDGE))
+}
+
+/// Removes variants left over from a previous upload of `image_file`.
+pub fn remove_variants(folder: &Path, image_file: &str) {
+    if let Some((thumb_file, medium_file)) = variant_files(image_file) {
+        for file_name in [thumb_file, medium_file] {
+            let _ = std::fs::remove_file(folder.join(file_name));
+        }
+    }
+}
+
+/// Generates missing variants for uploads that predate them. Returns the
+/// number of folders updated.
+pub fn backfill_variants(minerals_root: &Path) -> usize {
+    let Ok(entries) = std::fs::read_dir(minerals_root) else {
+        return 0;
+    };
+
+    let mut updated = 0;
+    for folder in entries.flatten().map(|entry| entry.path()) {
+        let Ok(files) = std::fs::read_dir(&folder) else {
+            continue;
+        };
+        let missing = files
+            .flatten()
+            .filter_map(|file| file.file_name().into_string().ok())
+            .filter(|name| {
+                variant_files(name).is_some_and(|(thumb, _)| !folder.join(thumb).exists())
+            })
+            .collect::<Vec<_>>();
+
+        let mut folder_updated = false;
+        for image_file in missing {
+            match std::fs::read(folder.join(&image_file))
+                .context("failed to read image")
+                .and_then(|bytes| write_variants(&folder, &image_file, &bytes))
+            {
+                Ok(()) => folder_updated = true,
+                Err(err) => warn!(
+                    "image variants skipped for {}: {err:#}",
+                    folder.join(&image_file).display()
+                ),
+            }
+        }
+        if folder_updated {
+            updated += 1;
+        }
+    }
+    updated
+}
+
+fn downscale(image: &DynamicImage, max_edge: u32) -> DynamicImage {
+    if image.width().max(image.height()) <= max_edge {
+        return image.clone();
+    }
+    image.resize(max_edge, max_edge, FilterType::Lanczos3)
+}
+
+fn encode_webp(image: &DynamicImage) -> Result<Vec<u8>> {
+    let (width, height) = (image.width(), image.height());
+    let encoded = if image.color().has_alpha() {
+        webp::Encoder::from_rgba(image.to_rgba8().as_raw(), width, height).encode(WEBP_QUALITY)
+    } else {
+        webp::Encoder::from_rgb(image.to_rgb8().as_raw(), width, height).encode(WEBP_QUALITY)
+    };
+    Ok(encoded.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, ImageFormat, RgbImage};
+
+    use super::{
+        apply_edit, convertible_format, original_file, sanitize_upload, variant_files,
+        write_variants, PhotoEdit, MEDIUM_FILE, THUMB_FILE, THUMB_MAX_EDGE,
+    };
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn writes_downscaled_webp_variants() {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(1600, 800))
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+
+        let dir = TempDir::new("images");
+        std::fs::create_dir_all(&dir).unwrap();
+        write_variants(&dir, "image.png", &png).unwrap();
+
+        let thumb = image::open(dir.join(THUMB_FILE)).unwrap();
+        assert_eq!(
+            (thumb.width(), thumb.height()),
+            (THUMB_MAX_EDGE, THUMB_MAX_EDGE / 2)
+        );
+        let medium = image::open(dir.join(MEDIUM_FILE)).unwrap();
+        assert_eq!(medium.width(), 1200);
+    }
+
+    #[test]
+    fn names_variants_per_gallery_image() {
+        assert_eq!(
+            variant_files("image-3.jpg"),
+            Some(("thumb-3.webp".to_string(), "medium-3.webp".to_string()))
+        );
+        assert_eq!(variant_files("thumb.webp"), None);
+        assert_eq!(variant_files("image-x.png"), None);
+    }
+
+    #[test]
+    fn sanitizes_uploads_by_their_content() {
+        let mut jpeg = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(40, 20))
+            .write_to(&mut Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .unwrap();
+        // An APP1 segment as cameras write it, holding the EXIF block.
+        let exif = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\0GPS-SECRET";
+        let mut tagged = jpeg[..2].to_vec();
+        tagged.extend([0xFF, 0xE1]);
+        tagged.extend(((exif.len() + 2) as u16).to_be_bytes());
+        tagged.extend(exif);
+        tagged.extend(&jpeg[2..]);
+        tagged.extend(b"#!/bin/sh\n");
+
+        let (clean, ext) = sanitize_upload(&tagged).unwrap();
+        assert_eq!(ext, "jpg");
+        let find = |needle: &[u8]| clean.windows(needle.len()).any(|w| w == needle);
+        assert!(!find(b"GPS-SECRET") && !find(b"#!/bin/sh"));
+        let decoded = image::load_from_memory(&clean).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (40, 20));
+
+        assert!(sanitize_upload(b"\x7fELF\x02\x01\x01 renamed to photo.jpg").is_err());
+        assert!(sanitize_upload(&jpeg[..jpeg.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn rotates_then_crops_photos() {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(400, 200))
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+
+        let edit = PhotoEdit::parse("-90", "10, 25, 50%, 50").unwrap();
+        assert_eq!(edit.quarter_turns, 3);
+        let edited = image::load_from_memory(&apply_edit(&png, &edit).unwrap()).unwrap();
+        // 200x400 once turned, then half of each side.
+        assert_eq!((edited.width(), edited.height()), (100, 200));
+
+        assert!(PhotoEdit::parse("", "0,0,100,100").unwrap().is_noop());
+        assert!(PhotoEdit::parse("45", "").is_err());
+        assert!(PhotoEdit::parse("", "60,0,50,10").is_err());
+        assert!(PhotoEdit::parse("", "0,0,50").is_err());
+        assert_eq!(original_file("image-2.jpg"), "image-2_original.jpg");
+        assert_eq!(variant_files(&original_file("image.png")), None);
+    }
+
+    #[test]
+    fn sniffs_formats_that_need_conversion() {
+        let heic = b"\0\0\0\x18ftypheic\0\0\0\0mif1heic";
+        assert_eq!(convertible_format(heic), Some("heic"));
+        assert_eq!(convertible_format(b"II*\0\x08\0\0\0"), Some("tiff"));
+        assert_eq!(convertible_format(b"MM\0*\0\0\0\x08"), Some("tiff"));
+        // MP4 video shares the box layout but not the brand.
+        assert_eq!(convertible_format(b"\0\0\0\x18ftypisom\0\0\0\0"), None);
+        assert_eq!(convertible_format(b"\xFF\xD8\xFF\xE0"), None);
+    }
+}
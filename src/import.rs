@@ -0,0 +1,295 @@
+//! Bulk import of legacy collections. Accepts a CSV file (header row with
+//! the admin form's field names) or a JSON array of records, validates every
+//! row like the publish form does, and reports the outcome row by row.
+
+use std::collections::BTreeMap;
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap},
+    Json,
+};
+use serde::Serialize;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    check_slugs_available, create_imported_folder, csrf, parse_mineral_fields, reload_catalog,
+    AppError, AppState, MineralFieldsRequest,
+};
+
+pub const IMPORT_BODY_MAX_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    imported: usize,
+    failed: usize,
+    rows: Vec<ImportRow>,
+}
+
+/// Outcome of one record; `row` is 1-based and does not count the CSV
+/// header line.
+#[derive(Debug, Serialize)]
+pub struct ImportRow {
+    row: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    folder_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+type RawRow = Result<BTreeMap<String, String>, String>;
+
+pub async fn import_minerals(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<ImportReport>, AppError> {
+    csrf::verify(&state, &headers, "")?;
+
+    let rows = if is_json_body(&headers, &body) {
+        json_rows(&body)?
+    } else {
+        csv_rows(&body)?
+    };
+    if rows.is_empty() {
+        return Err(AppError::BadRequest(
+            "import contains no records".to_string(),
+        ));
+    }
+
+    let mut report = ImportReport {
+        imported: 0,
+        failed: 0,
+        rows: Vec::with_capacity(rows.len()),
+    };
+    for (index, row) in rows.into_iter().enumerate() {
+        let outcome = match row {
+            Ok(row) => match parse_mineral_fields(&fields_from_row(&row)) {
+                Ok(record) => match check_slugs_available(&state, &record, None).await {
+                    Ok(()) => create_imported_folder(&state, record)
+                        .await
+                        .map_err(|err| err.to_string()),
+                    Err(err) => Err(err.to_string()),
+                },
+                Err(err) => Err(err.to_string()),
+            },
+            Err(err) => Err(err),
+        };
+        report.rows.push(match outcome {
+            Ok(folder_name) => {
+                report.imported += 1;
+                ImportRow {
+                    row: index + 1,
+                    folder_name: Some(folder_name),
+                    error: None,
+                }
+            }
+            Err(error) => {
+                report.failed += 1;
+                ImportRow {
+                    row: index + 1,
+                    folder_name: None,
+                    error: Some(error),
+                }
+            }
+        });
+    }
+
+    if report.imported > 0 {
+        reload_catalog(&state)?;
+    }
+    info!(
+        "bulk import finished: {} imported, {} failed",
+        report.imported, report.failed
+    );
+    Ok(Json(report))
+}
+
+/// Trusts an explicit JSON or CSV content type and otherwise sniffs for a
+/// leading `[`.
+fn is_json_body(headers: &HeaderMap, body: &[u8]) -> bool {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if content_type.contains("json") {
+        return true;
+    }
+    if content_type.contains("csv") {
+        return false;
+    }
+    body.iter()
+        .find(|byte| !byte.is_ascii_whitespace())
+        .is_some_and(|byte| *byte == b'[')
+}
+
+fn csv_rows(body: &[u8]) -> Result<Vec<RawRow>, AppError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body);
+    let header_row = reader
+        .headers()
+        .map_err(|err| AppError::BadRequest(format!("invalid CSV header: {err}")))?
+        .iter()
+        .map(|name| name.to_ascii_lowercase())
+        .collect::<Vec<_>>();
+
+    Ok(reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(|err| format!("invalid CSV row: {err}"))?;
+            Ok(header_row
+                .iter()
+                .cloned()
+                .zip(record.iter().map(str::to_string))
+                .collect())
+        })
+        .collect())
+}
+
+fn json_rows(body: &[u8]) -> Result<Vec<RawRow>, AppError> {
+    let values = serde_json::from_slice::<Vec<Value>>(body)
+        .map_err(|err| AppError::BadRequest(format!("expected a JSON array of records: {err}")))?;
+
+    Ok(values
+        .into_iter()
+        .map(|value| match value {
+            Value::Object(object) => Ok(object
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = key.to_ascii_lowercase();
+                    let cell = match key.as_str() {
+                        "references" => references_cell(value),
+                        _ => json_cell(value),
+                    };
+                    (key, cell)
+                })
+                .collect()),
+            _ => Err("record must be a JSON object".to_string()),
+        })
+        .collect())
+}
+
+/// Flattens a JSON value into the text the admin form would have sent.
+/// `major_elements_pct` objects become `Symbol=pct` lines and `tags`
+/// arrays a comma-separated list.
+fn json_cell(value: Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text,
+        Value::Array(items) => items
+            .into_iter()
+            .map(json_cell)
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Object(object) => object
+            .into_iter()
+            .map(|(key, value)| format!("{key}={}", json_cell(value)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        other => other.to_string(),
+    }
+}
+
+/// `references` as the admin form's `URL | title | accessed` lines; entries
+/// may be bare URLs or objects like the record's.
+fn references_cell(value: Value) -> String {
+    let Value::Array(items) = value else {
+        return json_cell(value);
+    };
+    items
+        .into_iter()
+        .map(|item| match item {
+            Value::Object(mut reference) => {
+                let mut part = |name: &str| json_cell(reference.remove(name).unwrap_or_default());
+                format!("{} | {} | {}", part("url"), part("title"), part("accessed"))
+            }
+            other => json_cell(other),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn fields_from_row(row: &BTreeMap<String, String>) -> MineralFieldsRequest {
+    let field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| row.get(*key))
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    MineralFieldsRequest {
+        common_name: field(&["common_name"]),
+        description: field(&["description"]),
+        mineral_family: field(&["mineral_family", "mineral_group"]),
+        formula: field(&["formula"]),
+        hardness_mohs: field(&["hardness_mohs"]),
+        density_g_cm3: field(&["density_g_cm3"]),
+        crystal_system: field(&["crystal_system"]),
+        color: field(&["color"]),
+        streak: field(&["streak"]),
+        luster: field(&["luster"]),
+        // A CSV cell holds one line, so `Si=46.7; O=53.3` is accepted too.
+        major_elements_pct_text: field(&["major_elements_pct_text", "major_elements_pct"])
+            .replace(';', "\n"),
+        notes: field(&["notes"]),
+        tags: field(&["tags"]),
+        references: field(&["references"]),
+        extra_attributes: field(&["extra_attributes"]),
+        permalink: field(&["permalink"]),
+        aliases: field(&["aliases"]).replace(';', "\n"),
+        locality: field(&["locality"]),
+        country: field(&["country"]),
+        latitude: field(&["latitude", "lat"]),
+        longitude: field(&["longitude", "lon", "lng"]),
+        for_sale: matches!(
+            field(&["for_sale"]).trim().to_ascii_lowercase().as_str(),
+            "true" | "yes" | "1"
+        ),
+        price: field(&["price"]),
+        currency: field(&["currency"]),
+        quantity_available: field(&["quantity_available", "quantity"]),
+        clean_background: false,
+        visibility: field(&["visibility"]),
+        publish_at: field(&["publish_at"]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderMap;
+
+    use super::{csv_rows, fields_from_row, is_json_body, json_rows};
+    use crate::parse_mineral_fields;
+
+    #[test]
+    fn reads_csv_and_json_rows_into_form_fields() {
+        let csv = "common_name,description,mineral_family,formula,hardness_mohs,density_g_cm3,crystal_system,color,streak,luster,major_elements_pct,notes\n\
+                   Quartz,Clear prism,Silicates,SiO2,7,2.65,trigonal,colorless,white,vitreous,Si=46.7; O=53.3,Common\n\
+                   Bad,,Oxides,X,hard,1,cubic,red,red,dull,,\n";
+        let rows = csv_rows(csv.as_bytes()).unwrap();
+        let quartz = parse_mineral_fields(&fields_from_row(rows[0].as_ref().unwrap())).unwrap();
+        assert_eq!(quartz.major_elements_pct.get("O"), Some(&53.3));
+        assert!(parse_mineral_fields(&fields_from_row(rows[1].as_ref().unwrap())).is_err());
+
+        let json =
+            br#"[{"common_name": "Hematite", "description": "Red", "mineral_family": "Oxides",
+            "formula": "Fe2O3", "hardness_mohs": 5.5, "density_g_cm3": 5.26,
+            "crystal_system": "trigonal", "color": "grey", "streak": "red", "luster": "metallic",
+            "major_elements_pct": {"Fe": 69.9, "O": 30.1}, "notes": "Ore",
+            "references": ["https://a.example/fe",
+                {"url": "https://www.mindat.org/min-1856.html", "title": "Hematite", "accessed": "2026-03-01"}]}, 3]"#;
+        assert!(is_json_body(&HeaderMap::new(), json));
+        let rows = json_rows(json).unwrap();
+        let hematite = parse_mineral_fields(&fields_from_row(rows[0].as_ref().unwrap())).unwrap();
+        assert_eq!(hematite.hardness_mohs, 5.5);
+        assert_eq!(hematite.major_elements_pct.get("Fe"), Some(&69.9));
+        assert_eq!(hematite.references.len(), 2);
+        assert_eq!(hematite.references[1].title, "Hematite");
+        assert_eq!(hematite.references[1].accessed, "2026-03-01");
+        assert!(rows[1].is_err());
+    }
+}
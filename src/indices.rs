@@ -0,0 +1,241 @@
+//! Geochemical indices derived from a mineral's composition and its
+//! specimens, for the "Derived Indices" section of reports. Each index is
+//! only produced when its inputs are present, so a plain oxide gets none of
+//! the silica figures and a mineral without weighed specimens no specific
+//! gravity.
+
+use crate::{elements, i18n::UiText, models::Mineral, specimens::Specimen};
+
+/// Value of each base metal relative to copper, from long-run average
+/// prices; indicative only, for ranking grades against each other.
+const CU_EQUIVALENT_FACTORS: &[(&str, f32)] = &[
+    ("Cu", 1.0),
+    ("Zn", 0.3),
+    ("Pb", 0.24),
+    ("Ni", 1.9),
+    ("Co", 3.5),
+    ("Mo", 4.0),
+    ("Sn", 3.0),
+];
+
+/// g/cm3 at 4 °C, the reference for specific gravity.
+const WATER_DENSITY: f32 = 0.99997;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexKind {
+    /// Sum of the base metals in [`CU_EQUIVALENT_FACTORS`], wt%.
+    BaseMetalGrade,
+    /// The same metals weighted by their value relative to copper, wt%.
+    CuEquivalent,
+    /// Si expressed as SiO2, wt%.
+    SilicaEquivalent,
+    /// SiO2 / (Al2O3 + Fe2O3), the silica modulus.
+    SilicaModulus,
+    /// H as H2O plus C as CO2, wt%: what ignition would drive off. A
+    /// placeholder until measured LOI values are recorded.
+    LoiEstimate,
+    /// Mean apparent specific gravity of the weighed specimens.
+    SpecimenSpecificGravity,
+    /// Specimen specific gravity minus the recorded density, g/cm3;
+    /// strongly negative values point to porosity or a light matrix.
+    DensityDelta,
+}
+
+impl IndexKind {
+    pub fn label(self, txt: &UiText) -> &'static str {
+        match self {
+            IndexKind::BaseMetalGrade => txt.index_base_metal_grade,
+            IndexKind::CuEquivalent => txt.index_cu_equivalent,
+            IndexKind::SilicaEquivalent => txt.index_silica_equivalent,
+            IndexKind::SilicaModulus => txt.index_silica_modulus,
+            IndexKind::LoiEstimate => txt.index_loi_estimate,
+            IndexKind::SpecimenSpecificGravity => txt.index_specimen_specific_gravity,
+            IndexKind::DensityDelta => txt.index_density_delta,
+        }
+    }
+
+    /// Shown after the value; empty for ratios.
+    pub fn unit(self) -> &'static str {
+        match self {
+            IndexKind::BaseMetalGrade
+            | IndexKind::CuEquivalent
+            | IndexKind::SilicaEquivalent
+            | IndexKind::LoiEstimate => "wt%",
+            IndexKind::DensityDelta => "g/cm3",
+            IndexKind::SilicaModulus | IndexKind::SpecimenSpecificGravity => "",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DerivedIndex {
+    pub kind: IndexKind,
+    pub value: f32,
+}
+
+/// Every index that applies, in [`IndexKind`] order.
+pub fn derive(mineral: &Mineral, specimens: &[Specimen]) -> Vec<DerivedIndex> {
+    let pct = |symbol: &str| elements::weight_pct(mineral, symbol).filter(|percent| *percent > 0.0);
+    let mut indices = Vec::new();
+    let mut push = |kind, value: Option<f32>| {
+        if let Some(value) = value.filter(|value| value.is_finite()) {
+            indices.push(DerivedIndex { kind, value });
+        }
+    };
+
+    let metals: Vec<(f32, f32)> = CU_EQUIVALENT_FACTORS
+        .iter()
+        .filter_map(|(symbol, factor)| Some((pct(symbol)?, *factor)))
+        .collect();
+    if !metals.is_empty() {
+        push(
+            IndexKind::BaseMetalGrade,
+            Some(metals.iter().map(|(percent, _)| percent).sum()),
+        );
+        push(
+            IndexKind::CuEquivalent,
+            Some(
+                metals
+                    .iter()
+                    .map(|(percent, factor)| percent * factor)
+                    .sum(),
+            ),
+        );
+    }
+
+    let silica = pct("Si").map(|si| si * oxide_factor("Si", 1, 2));
+    push(IndexKind::SilicaEquivalent, silica);
+    let sesquioxides = pct("Al").unwrap_or(0.0) * oxide_factor("Al", 2, 3)
+        + pct("Fe").unwrap_or(0.0) * oxide_factor("Fe", 2, 3);
+    push(
+        IndexKind::SilicaModulus,
+        silica
+            .filter(|_| sesquioxides > 0.0)
+            .map(|silica| silica / sesquioxides),
+    );
+
+    let volatiles = [("H", 2, 1), ("C", 1, 2)]
+        .iter()
+        .filter_map(|(symbol, atoms, oxygens)| {
+            Some(pct(symbol)? * oxide_factor(symbol, *atoms, *oxygens))
+        })
+        .reduce(|a, b| a + b);
+    push(IndexKind::LoiEstimate, volatiles);
+
+    let gravities: Vec<f32> = specimens.iter().filter_map(specific_gravity).collect();
+    if !gravities.is_empty() {
+        let gravity = gravities.iter().sum::<f32>() / gravities.len() as f32;
+        push(IndexKind::SpecimenSpecificGravity, Some(gravity));
+        push(
+            IndexKind::DensityDelta,
+            (mineral.density_g_cm3 > 0.0)
+                .then_some(gravity * WATER_DENSITY - mineral.density_g_cm3),
+        );
+    }
+    indices
+}
+
+/// Mass of the oxide `X{atoms}O{oxygens}` per unit mass of `X`.
+fn oxide_factor(symbol: &str, atoms: u8, oxygens: u8) -> f32 {
+    let mass = |symbol| elements::by_symbol(symbol).map_or(0.0, |element| element.atomic_mass);
+    let cation = mass(symbol) * f64::from(atoms);
+    ((cation + mass("O") * f64::from(oxygens)) / cation) as f32
+}
+
+/// Weight over the volume of the ellipsoid fitting the specimen's three
+/// dimensions, so irregular pieces come out close to their bulk density.
+fn specific_gravity(specimen: &Specimen) -> Option<f32> {
+    let weight = specimen.weight_g.filter(|weight| *weight > 0.0)?;
+    let [a, b, c] = dimensions_mm(&specimen.dimensions_mm)?;
+    let volume_cm3 = std::f32::consts::PI / 6.0 * a * b * c / 1000.0;
+    Some(weight / volume_cm3 / WATER_DENSITY)
+}
+
+/// `45 x 30 x 22`, `45×30×22 mm`, or `45*30*22`.
+fn dimensions_mm(raw: &str) -> Option<[f32; 3]> {
+    let raw = raw.trim().trim_end_matches("mm").to_ascii_lowercase();
+    let parts: Vec<f32> = raw
+        .split(['x', '×', '*'])
+        .map(|part| part.trim().replace(',', ".").parse::<f32>().ok())
+        .collect::<Option<_>>()?;
+    match parts[..] {
+        [a, b, c] if a > 0.0 && b > 0.0 && c > 0.0 => Some([a, b, c]),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{derive, dimensions_mm, IndexKind};
+    use crate::{
+        models::{sample_mineral, Mineral},
+        specimens::Specimen,
+    };
+
+    fn mineral(density: f32, elements: &[(&str, f32)]) -> Mineral {
+        Mineral {
+            slug: "sample".to_string(),
+            folder_name: "sample".to_string(),
+            common_name: "Sample".to_string(),
+            hardness_mohs: 5.0,
+            density_g_cm3: density,
+            major_elements_pct: elements
+                .iter()
+                .map(|(element, percent)| (element.to_string(), *percent))
+                .collect(),
+            ..sample_mineral()
+        }
+    }
+
+    fn value(indices: &[super::DerivedIndex], kind: IndexKind) -> Option<f32> {
+        indices
+            .iter()
+            .find(|index| index.kind == kind)
+            .map(|index| index.value)
+    }
+
+    #[test]
+    fn computes_silica_and_metal_indices_only_when_they_apply() {
+        // Kaolinite, Al2Si2O5(OH)4.
+        let kaolinite = derive(
+            &mineral(
+                2.6,
+                &[("Al", 20.9), ("Si", 21.76), ("O", 55.78), ("H", 1.56)],
+            ),
+            &[],
+        );
+        let silica = value(&kaolinite, IndexKind::SilicaEquivalent).unwrap();
+        assert!((silica - 46.55).abs() < 0.05, "{silica}");
+        let modulus = value(&kaolinite, IndexKind::SilicaModulus).unwrap();
+        assert!((modulus - 1.18).abs() < 0.01, "{modulus}");
+        let loi = value(&kaolinite, IndexKind::LoiEstimate).unwrap();
+        assert!((loi - 13.94).abs() < 0.05, "{loi}");
+        assert_eq!(value(&kaolinite, IndexKind::CuEquivalent), None);
+
+        // Sphalerite with some copper.
+        let ore = derive(
+            &mineral(4.0, &[("Zn", 60.0), ("Cu", 5.0), ("S", 35.0)]),
+            &[],
+        );
+        assert_eq!(value(&ore, IndexKind::BaseMetalGrade), Some(65.0));
+        let equivalent = value(&ore, IndexKind::CuEquivalent).unwrap();
+        assert!((equivalent - 23.0).abs() < 0.001, "{equivalent}");
+        assert_eq!(value(&ore, IndexKind::SilicaEquivalent), None);
+    }
+
+    #[test]
+    fn compares_specimen_gravity_with_recorded_density() {
+        let specimen = Specimen {
+            weight_g: Some(52.36),
+            dimensions_mm: "50 x 20 x 40 mm".to_string(),
+            ..Specimen::default()
+        };
+        let indices = derive(&mineral(2.65, &[]), &[specimen, Specimen::default()]);
+        let gravity = value(&indices, IndexKind::SpecimenSpecificGravity).unwrap();
+        assert!((gravity - 2.5).abs() < 0.01, "{gravity}");
+        let delta = value(&indices, IndexKind::DensityDelta).unwrap();
+        assert!((delta + 0.15).abs() < 0.01, "{delta}");
+        assert_eq!(dimensions_mm("45×30×22"), Some([45.0, 30.0, 22.0]));
+        assert_eq!(dimensions_mm("45 x 30"), None);
+    }
+}
@@ -0,0 +1,431 @@
+//! Buyer inquiries. Each mineral page carries a request-a-quote form that
+//! posts to `/minerals/:slug/inquiry`; every inquiry is one
+//! `data/inquiries/<timestamp>.json` file. New inquiries are announced to
+//! the admins through an optional webhook and by email to `ADMIN_EMAIL_TO`,
+//! and triaged on `/admin/inquiries`.
+
+use std::{path::Path, sync::Arc, time::Duration};
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::HeaderMap,
+    response::Redirect,
+    Form,
+};
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::{info, warn};
+
+use crate::{
+    client_ip::ClientIp,
+    csrf,
+    email::Mailer,
+    get_mineral,
+    i18n::ui_text,
+    lang_path, require_admin_token, require_visible, resolve_language,
+    timestamp_id::{is_valid_timestamp_id, timestamp_id},
+    web::{InquiriesTemplate, TemplateResponse},
+    AppError, AppState,
+};
+
+pub const INQUIRIES_DIR: &str = "inquiries";
+
+const MAX_NAME_CHARS: usize = 120;
+const MAX_EMAIL_CHARS: usize = 254;
+const MAX_MESSAGE_CHARS: usize = 4000;
+/// A webhook that hangs must not pile up tasks.
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Inquiry {
+    /// File stem under `data/inquiries/`, the time it was received; not
+    /// stored in the file itself.
+    #[serde(skip)]
+    pub id: String,
+    pub slug: String,
+    /// Name of the mineral in the language the visitor was reading.
+    pub mineral_name: String,
+    pub name: String,
+    pub email: String,
+    pub message: String,
+    /// Page language, so the reply can be written in it.
+    pub language: String,
+    pub received_utc: String,
+    pub handled_utc: Option<String>,
+    /// Sender's address, for telling spam waves apart; absent in older
+    /// inquiries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_ip: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct InquiryForm {
+    pub name: String,
+    pub email: String,
+    pub message: String,
+    /// Hidden from people; bots that fill in every field give themselves
+    /// away here.
+    pub website: String,
+}
+
+impl InquiryForm {
+    fn to_inquiry(
+        &self,
+        slug: &str,
+        mineral_name: &str,
+        language: &str,
+        received_at: DateTime<Utc>,
+    ) -> Result<Inquiry, String> {
+        let name = self.name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+            return Err(format!(
+                "name is required (at most {MAX_NAME_CHARS} characters)"
+            ));
+        }
+        let email = self.email.trim();
+        if !is_plausible_email(email) {
+            return Err(format!("'{email}' is not an email address"));
+        }
+        let message = self.message.trim();
+        if message.is_empty() || message.chars().count() > MAX_MESSAGE_CHARS {
+            return Err(format!(
+                "message is required (at most {MAX_MESSAGE_CHARS} characters)"
+            ));
+        }
+
+        Ok(Inquiry {
+            id: timestamp_id(&received_at),
+            slug: slug.to_string(),
+            mineral_name: mineral_name.to_string(),
+            // Names end up in mail headers; keep them on one line.
+            name: name.replace(['\r', '\n'], " "),
+            email: email.to_string(),
+            message: message.to_string(),
+            language: language.to_string(),
+            received_utc: received_at.to_rfc3339(),
+            handled_utc: None,
+            client_ip: None,
+        })
+    }
+}
+
+/// Where new inquiries are announced; both channels are optional.
+pub struct InquiryNotifier {
+    http: Client,
+    webhook_url: Option<String>,
+    mailer: Arc<Mailer>,
+}
+
+impl InquiryNotifier {
+    pub fn new(http: Client, webhook_url: Option<String>, mailer: Arc<Mailer>) -> Self {
+        Self {
+            http,
+            webhook_url,
+            mailer,
+        }
+    }
+
+    /// Sends every configured notification; failures are logged, since
+    /// the inquiry itself is already stored.
+    pub async fn notify(&self, inquiry: &Inquiry, mineral_url: &str) {
+        if let Some(url) = &self.webhook_url {
+            if let Err(err) = self.post_webhook(url, inquiry, mineral_url).await {
+                warn!("inquiry {} webhook failed: {err:#}", inquiry.id);
+            }
+        }
+        let (subject, body) = email_message(inquiry, mineral_url);
+        self.mailer
+            .notify_admins(&subject, &body, Some(&inquiry.email))
+            .await;
+    }
+
+    /// JSON with the inquiry, plus a `text` summary that Slack-style
+    /// incoming webhooks display as is.
+    async fn post_webhook(&self, url: &str, inquiry: &Inquiry, mineral_url: &str) -> Result<()> {
+        let body = json!({
+            "event": "inquiry.created",
+            "text": format!(
+                "New inquiry about {} from {} <{}>: {}",
+                inquiry.mineral_name, inquiry.name, inquiry.email, mineral_url
+            ),
+            "inquiry": {
+                "id": inquiry.id,
+                "slug": inquiry.slug,
+                "mineral_name": inquiry.mineral_name,
+                "mineral_url": mineral_url,
+                "name": inquiry.name,
+                "email": inquiry.email,
+                "message": inquiry.message,
+                "language": inquiry.language,
+                "received_utc": inquiry.received_utc,
+            },
+        });
+        self.http
+            .post(url)
+            .timeout(NOTIFY_TIMEOUT)
+            .json(&body)
+            .send()
+            .await
+            .context("request failed")?
+            .error_for_status()
+            .context("webhook refused the inquiry")?;
+        Ok(())
+    }
+}
+
+/// Subject and plain-text body for the admins; replies go to the visitor.
+fn email_message(inquiry: &Inquiry, mineral_url: &str) -> (String, String) {
+    (
+        format!("Inquiry about {}", inquiry.mineral_name),
+        format!(
+            "{} <{}> asked about {} ({}), page language {}:\n\n{}\n\n\
+             Triage it at /admin/inquiries.\n",
+            inquiry.name,
+            inquiry.email,
+            inquiry.mineral_name,
+            mineral_url,
+            inquiry.language,
+            inquiry.message
+        ),
+    )
+}
+
+pub async fn submit_inquiry(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ClientIp(client): ClientIp,
+    AxumPath(slug): AxumPath<String>,
+    Form(form): Form<InquiryForm>,
+) -> Result<Redirect, AppError> {
+    let language = resolve_language(&state, &headers);
+    let mineral = get_mineral(&state, language, &slug).await?;
+    require_visible(&state, &headers, &slug, &mineral)?;
+    let sent = Redirect::to(&lang_path::prefixed(
+        &headers,
+        &format!("/minerals/{slug}?inquiry=sent#inquiry"),
+    ));
+    if !form.website.is_empty() {
+        return Ok(sent);
+    }
+
+    let inquiry = Inquiry {
+        client_ip: Some(client.to_string()),
+        ..form
+            .to_inquiry(
+                &mineral.slug,
+                &mineral.common_name,
+                language.code(),
+                Utc::now(),
+            )
+            .map_err(AppError::BadRequest)?
+    };
+    write(&inquiries_dir(&state), &inquiry, true).await?;
+    info!("received inquiry {} about {slug}", inquiry.id);
+
+    let notifier = state.inquiry_notifier.clone();
+    let mineral_url = state.pdf_generator.mineral_url(&mineral.slug, language);
+    tokio::spawn(async move { notifier.notify(&inquiry, &mineral_url).await });
+    Ok(sent)
+}
+
+pub async fn inquiries_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<TemplateResponse<InquiriesTemplate>, AppError> {
+    require_admin_token(&state, &headers)?;
+    let language = resolve_language(&state, &headers);
+    let (handled, open) = list(&inquiries_dir(&state))
+        .await?
+        .into_iter()
+        .partition(|inquiry: &Inquiry| inquiry.handled_utc.is_some());
+    Ok(TemplateResponse(InquiriesTemplate {
+        lang_code: language.code().to_string(),
+        lang_dir: language.dir().to_string(),
+        txt: ui_text(language),
+        open,
+        handled,
+        csrf_token: csrf::session_token(&state, &headers).unwrap_or_default(),
+    }))
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct HandledForm {
+    csrf_token: String,
+    /// `false` reopens a handled inquiry.
+    handled: bool,
+}
+
+pub async fn mark_handled(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(id): AxumPath<String>,
+    Form(form): Form<HandledForm>,
+) -> Result<Redirect, AppError> {
+    csrf::verify(&state, &headers, &form.csrf_token)?;
+    set_handled(&state, &id, form.handled).await?;
+    Ok(Redirect::to("/admin/inquiries"))
+}
+
+pub async fn find(state: &AppState, id: &str) -> Result<Option<Inquiry>, AppError> {
+    read(&inquiries_dir(state), id).await
+}
+
+pub async fn set_handled(state: &AppState, id: &str, handled: bool) -> Result<(), AppError> {
+    let dir = inquiries_dir(state);
+    let mut inquiry = read(&dir, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("inquiry '{id}' not found")))?;
+    inquiry.handled_utc = handled.then(|| Utc::now().to_rfc3339());
+    write(&dir, &inquiry, false).await
+}
+
+fn inquiries_dir(state: &AppState) -> std::path::PathBuf {
+    state.data_root.join(INQUIRIES_DIR)
+}
+
+/// Newest first; unreadable files are skipped.
+async fn list(dir: &Path) -> Result<Vec<Inquiry>, AppError> {
+    let mut entries = match fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(AppError::Internal(
+                anyhow::Error::new(err).context(format!("failed to read {}", dir.display())),
+            ))
+        }
+    };
+
+    let mut inquiries = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .with_context(|| format!("failed to read {}", dir.display()))?
+    {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(id) = file_name.strip_suffix(".json") else {
+            continue;
+        };
+        if let Ok(Some(inquiry)) = read(dir, id).await {
+            inquiries.push(inquiry);
+        }
+    }
+    inquiries.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(inquiries)
+}
+
+async fn read(dir: &Path, id: &str) -> Result<Option<Inquiry>, AppError> {
+    if !is_valid_timestamp_id(id) {
+        return Ok(None);
+    }
+    let path = dir.join(format!("{id}.json"));
+    let raw = match fs::read_to_string(&path).await {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(AppError::Internal(
+                anyhow::Error::new(err).context(format!("failed to read {}", path.display())),
+            ))
+        }
+    };
+    let inquiry = serde_json::from_str::<Inquiry>(&raw)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(Some(Inquiry {
+        id: id.to_string(),
+        ..inquiry
+    }))
+}
+
+/// `create` refuses to replace an existing file; updates go through a
+/// temporary file so a crash cannot leave half an inquiry behind.
+async fn write(dir: &Path, inquiry: &Inquiry, create: bool) -> Result<(), AppError> {
+    fs::create_dir_all(dir)
+        .await
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+    let path = dir.join(format!("{}.json", inquiry.id));
+    let json = serde_json::to_string_pretty(inquiry).context("failed to serialize inquiry")?;
+    if create {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await
+            .with_context(|| format!("failed to create {}", path.display()))?;
+        file.write_all(json.as_bytes())
+            .await
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        return Ok(());
+    }
+
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, json)
+        .await
+        .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, &path)
+        .await
+        .with_context(|| format!("failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+/// One `@` with text on both sides and a dot in the domain; the real check
+/// is whether the reply arrives.
+fn is_plausible_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    email.len() <= MAX_EMAIL_CHARS
+        && !local.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !domain.contains('@')
+        && !email
+            .chars()
+            .any(|ch| ch.is_whitespace() || ch.is_control())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{email_message, InquiryForm};
+
+    #[test]
+    fn validates_inquiries_and_keeps_names_on_one_line() {
+        let form = InquiryForm {
+            name: " Ana\r\nBcc: victim@example.org ".to_string(),
+            email: "ana@example.com".to_string(),
+            message: "Is the 40 g specimen still available?".to_string(),
+            ..InquiryForm::default()
+        };
+        let received = Utc.with_ymd_and_hms(2026, 10, 16, 16, 5, 6).unwrap();
+        let inquiry = form
+            .to_inquiry("phenakite", "Phenakite", "es", received)
+            .unwrap();
+        assert_eq!(inquiry.id, "20261016T160506000000Z");
+        assert_eq!(inquiry.name, "Ana  Bcc: victim@example.org");
+
+        let (subject, body) = email_message(&inquiry, "https://x/es/minerals/p");
+        assert_eq!(subject, "Inquiry about Phenakite");
+        assert!(body.starts_with("Ana  Bcc: victim@example.org <ana@example.com> asked"));
+
+        for email in ["ana", "ana@example", "ana @example.com", "a@b@example.com"] {
+            let bad = InquiryForm {
+                email: email.to_string(),
+                ..form.clone()
+            };
+            assert!(bad.to_inquiry("p", "P", "en", received).is_err(), "{email}");
+        }
+        let empty = InquiryForm {
+            message: " ".to_string(),
+            ..form
+        };
+        assert!(empty.to_inquiry("p", "P", "en", received).is_err());
+    }
+}
@@ -0,0 +1,410 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::{
+    agent::{MineralReport, Narrative},
+    email::Mailer,
+    i18n::Language,
+    models::ReportRequest,
+    pdf::{PdfBackendKind, PdfGenerator},
+    report_templates::ReportLayout,
+};
+
+/// Finished jobs beyond this count are pruned oldest-first on submit.
+const MAX_RETAINED_JOBS: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Done | JobStatus::Failed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PdfJob {
+    pub id: String,
+    pub slug: String,
+    pub lang: String,
+    pub backend: &'static str,
+    pub status: JobStatus,
+    pub created_utc: String,
+    pub finished_utc: Option<String>,
+    pub summary: String,
+    /// `rules` or `llm`: what wrote the summary and recommendations.
+    pub narrative: &'static str,
+    /// Report preset the request was filled in from.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub preset: String,
+    pub run_id: Option<String>,
+    pub pdf_path: Option<String>,
+    pub html_path: Option<String>,
+    pub signature_path: Option<String>,
+    /// Where the finished report was mailed; a failed send leaves the job
+    /// done with the reason in `error`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emailed_to: Option<String>,
+    pub error: Option<String>,
+    #[serde(skip)]
+    pub request: ReportRequest,
+}
+
+struct QueuedJob {
+    id: String,
+    report: MineralReport,
+    layout: ReportLayout,
+    language: Language,
+    backend: PdfBackendKind,
+    email_to: Option<String>,
+}
+
+/// In-memory PDF job store backed by a single worker task, so at most one
+/// `latexmk` run is active at a time regardless of request volume.
+#[derive(Clone)]
+pub struct JobQueue {
+    jobs: Arc<Mutex<HashMap<String, PdfJob>>>,
+    sender: mpsc::UnboundedSender<QueuedJob>,
+}
+
+impl JobQueue {
+    pub fn start(generator: Arc<PdfGenerator>, mailer: Arc<Mailer>) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let jobs = Arc::new(Mutex::new(HashMap::new()));
+        tokio::spawn(run_worker(generator, mailer, Arc::clone(&jobs), receiver));
+        Self { jobs, sender }
+    }
+
+    pub fn submit(
+        &self,
+        id: String,
+        report: MineralReport,
+        layout: ReportLayout,
+        language: Language,
+        backend: PdfBackendKind,
+        email_to: Option<String>,
+    ) -> Result<PdfJob> {
+        let job = PdfJob {
+            id: id.clone(),
+            slug: report.mineral.slug.clone(),
+            lang: language.code().to_string(),
+            backend: backend.as_str(),
+            status: JobStatus::Queued,
+            created_utc: Utc::now().to_rfc3339(),
+            finished_utc: None,
+            summary: report.summary.clone(),
+            narrative: report.narrative.as_str(),
+            preset: report.preset.clone(),
+            run_id: None,
+            pdf_path: None,
+            html_path: None,
+            signature_path: None,
+            emailed_to: None,
+            error: None,
+            request: ReportRequest {
+                audience: report.audience.clone(),
+                purpose: report.purpose.clone(),
+                site_context: report.site_context.clone(),
+                template: layout.name().unwrap_or_default().to_string(),
+                llm_narrative: report.narrative != Narrative::Rules,
+                preset: report.preset.clone(),
+                report_language: language.code().to_string(),
+                units: report.units.code().to_string(),
+            },
+        };
+
+        {
+            let mut jobs = self
+                .jobs
+                .lock()
+                .map_err(|_| anyhow!("pdf job store lock poisoned"))?;
+            prune_finished_jobs(&mut jobs);
+            jobs.insert(id.clone(), job.clone());
+        }
+
+        self.sender
+            .send(QueuedJob {
+                id,
+                report,
+                layout,
+                language,
+                backend,
+                email_to,
+            })
+            .map_err(|_| anyhow!("pdf job worker is not running"))?;
+        Ok(job)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<PdfJob>> {
+        let jobs = self
+            .jobs
+            .lock()
+            .map_err(|_| anyhow!("pdf job store lock poisoned"))?;
+        Ok(jobs.get(id).cloned())
+    }
+}
+
+async fn run_worker(
+    generator: Arc<PdfGenerator>,
+    mailer: Arc<Mailer>,
+    jobs: Arc<Mutex<HashMap<String, PdfJob>>>,
+    mut receiver: mpsc::UnboundedReceiver<QueuedJob>,
+) {
+    while let Some(queued) = receiver.recv().await {
+        update_job(&jobs, &queued.id, |job| job.status = JobStatus::Running);
+
+        let result = generator
+            .generate_pdf(
+                &queued.report,
+                &queued.layout,
+                queued.language,
+                queued.backend,
+            )
+            .await;
+        let mineral_name = &queued.report.mineral.common_name;
+        let emailed = match (&result, &queued.email_to) {
+            (Ok(artifacts), Some(to)) => Some(
+                mailer
+                    .send_report(
+                        to,
+                        &format!("{mineral_name} report"),
+                        artifacts,
+                        &generator.public_url(&artifacts.pdf_path),
+                    )
+                    .await
+                    .map(|()| to.clone()),
+            ),
+            _ => None,
+        };
+        if let Err(err) = &result {
+            mailer
+                .notify_admins(
+                    &format!("PDF job failed: {mineral_name}"),
+                    &format!(
+                        "PDF job {} ({} backend, language {}) failed:\n\n{err:#}\n",
+                        queued.id,
+                        queued.backend.as_str(),
+                        queued.language.code()
+                    ),
+                    None,
+                )
+                .await;
+        }
+        update_job(&jobs, &queued.id, |job| {
+            job.finished_utc = Some(Utc::now().to_rfc3339());
+            match result {
+                Ok(artifacts) => {
+                    info!("pdf job {} finished for {}", job.id, job.slug);
+                    job.status = JobStatus::Done;
+                    job.run_id = Some(artifacts.run_id);
+                    job.pdf_path = Some(artifacts.pdf_path);
+                    job.html_path = Some(artifacts.html_path);
+                    job.signature_path = Some(artifacts.signature_path);
+                }
+                Err(err) => {
+                    warn!("pdf job {} failed for {}: {err:#}", job.id, job.slug);
+                    job.status = JobStatus::Failed;
+                    job.error = Some(err.to_string());
+                }
+            }
+            match emailed {
+                Some(Ok(to)) => job.emailed_to = Some(to),
+                Some(Err(err)) => {
+                    warn!("pdf job {} report was not mailed: {err:#}", job.id);
+                    job.error = Some(format!("report was not mailed: {err:#}"));
+                }
+                None => {}
+            }
+        });
+    }
+}
+
+fn update_job(jobs: &Mutex<HashMap<String, PdfJob>>, id: &str, apply: impl FnOnce(&mut PdfJob)) {
+    match jobs.lock() {
+        Ok(mut jobs) => {
+            if let Some(job) = jobs.get_mut(id) {
+                apply(job);
+            }
+        }
+        Err(_) => warn!("pdf job store lock poisoned; dropping update for job {id}"),
+    }
+}
+
+fn prune_finished_jobs(jobs: &mut HashMap<String, PdfJob>) {
+    if jobs.len() < MAX_RETAINED_JOBS {
+        return;
+    }
+
+    let mut finished = jobs
+        .values()
+        .filter(|job| job.status.is_finished())
+        .map(|job| (job.created_utc.clone(), job.id.clone()))
+        .collect::<Vec<_>>();
+    finished.sort();
+
+    let excess = jobs.len() + 1 - MAX_RETAINED_JOBS;
+    for (_, id) in finished.into_iter().take(excess) {
+        jobs.remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, os::unix::fs::PermissionsExt, sync::Arc, time::Duration};
+
+    use super::{prune_finished_jobs, JobQueue, JobStatus, PdfJob, MAX_RETAINED_JOBS};
+    use crate::{
+        agent::{run_agentic_chain, Classification, MineralAttachments},
+        config::EmailConfig,
+        email::{Mailer, SmtpSecurity},
+        i18n::Language,
+        models::{sample_mineral, ReportRequest},
+        pdf::{PdfBackendKind, PdfGenerator},
+        report_templates::ReportLayout,
+        signing::ReportSigner,
+        test_support::TempDir,
+    };
+
+    /// A queue whose Chromium backend is `chromium_bin`, writing under
+    /// `root`.
+    fn queue(root: &std::path::Path, chromium_bin: &str) -> JobQueue {
+        let generator = PdfGenerator::new(
+            root.join("minerals"),
+            "static",
+            PdfBackendKind::Chromium,
+            chromium_bin,
+            "http://localhost:7979",
+            10,
+            ReportSigner::default(),
+        );
+        let mailer = Mailer::new(&EmailConfig {
+            smtp_host: None,
+            smtp_port: 587,
+            smtp_security: SmtpSecurity::StartTls,
+            smtp_username: None,
+            smtp_password: None,
+            from: "Minerals <minerals@example.org>".parse().unwrap(),
+            admin_to: Vec::new(),
+            sendmail_bin: "sendmail".to_string(),
+        })
+        .unwrap();
+        JobQueue::start(Arc::new(generator), Arc::new(mailer))
+    }
+
+    async fn finished(queue: &JobQueue, id: &str) -> PdfJob {
+        for _ in 0..200 {
+            let job = queue.get(id).unwrap().unwrap();
+            if job.status.is_finished() {
+                return job;
+            }
+            tokio::time::sleep(Duration::from_millis(25)).await;
+        }
+        panic!("pdf job {id} did not finish");
+    }
+
+    #[tokio::test]
+    async fn runs_queued_jobs_to_done_or_failed() {
+        let root = TempDir::new("jobs");
+        let mineral = sample_mineral();
+        std::fs::create_dir_all(root.join("minerals").join(&mineral.folder_name)).unwrap();
+        let chromium = root.join("chromium");
+        std::fs::write(&chromium, "#!/bin/sh\nprintf '%%PDF-1.4' > report.pdf\n").unwrap();
+        std::fs::set_permissions(&chromium, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let report = || {
+            run_agentic_chain(
+                &mineral,
+                &ReportRequest::default(),
+                Vec::new(),
+                MineralAttachments::default(),
+                Classification::default(),
+                Language::En,
+            )
+        };
+        let submit = |queue: &JobQueue, id: &str| {
+            queue
+                .submit(
+                    id.to_string(),
+                    report(),
+                    ReportLayout::default(),
+                    Language::En,
+                    PdfBackendKind::Chromium,
+                    None,
+                )
+                .unwrap()
+        };
+
+        let working = queue(&root, chromium.to_str().unwrap());
+        assert_eq!(submit(&working, "ok").status, JobStatus::Queued);
+        let done = finished(&working, "ok").await;
+        assert_eq!(done.status, JobStatus::Done);
+        assert!(done.pdf_path.unwrap().ends_with("/report.pdf"));
+        assert!(done.error.is_none());
+
+        let broken = queue(&root, "/nonexistent/chromium");
+        submit(&broken, "broken");
+        let failed = finished(&broken, "broken").await;
+        assert_eq!(failed.status, JobStatus::Failed);
+        assert!(failed.pdf_path.is_none());
+        assert!(failed.error.unwrap().contains("/nonexistent/chromium"));
+        assert!(broken.get("ok").unwrap().is_none());
+    }
+
+    #[test]
+    fn prunes_the_oldest_finished_jobs_only() {
+        let job = |id: usize, status: JobStatus| PdfJob {
+            id: id.to_string(),
+            slug: String::new(),
+            lang: "en".to_string(),
+            backend: "latex",
+            status,
+            created_utc: format!("2026-01-01T00:00:{:02}Z", id % 60),
+            finished_utc: None,
+            summary: String::new(),
+            narrative: "rules",
+            preset: String::new(),
+            run_id: None,
+            pdf_path: None,
+            html_path: None,
+            signature_path: None,
+            emailed_to: None,
+            error: None,
+            request: ReportRequest::default(),
+        };
+        let mut jobs = (0..MAX_RETAINED_JOBS)
+            .map(|id| {
+                let status = match id {
+                    0 => JobStatus::Running,
+                    1 => JobStatus::Done,
+                    2 => JobStatus::Failed,
+                    _ => JobStatus::Queued,
+                };
+                (id.to_string(), job(id, status))
+            })
+            .collect::<HashMap<_, _>>();
+
+        prune_finished_jobs(&mut jobs);
+        assert_eq!(jobs.len(), MAX_RETAINED_JOBS - 1);
+        assert!(jobs.contains_key("0"));
+        assert!(!jobs.contains_key("1"));
+        assert!(jobs.contains_key("2"));
+
+        jobs.remove("3");
+        prune_finished_jobs(&mut jobs);
+        assert_eq!(jobs.len(), MAX_RETAINED_JOBS - 2);
+        assert!(jobs.contains_key("2"));
+    }
+}
@@ -0,0 +1,102 @@
+//! Language path prefixes. `/es/minerals/<slug>` serves the same page as
+//! `/minerals/<slug>` in Spanish, so localized pages can be shared and
+//! indexed. The prefix is stripped before routing and the language passed
+//! on in `PATH_LANGUAGE_HEADER`, which `resolve_language` prefers over the
+//! `lang` cookie.
+
+use axum::{
+    extract::Request,
+    http::{uri::PathAndQuery, HeaderMap, HeaderValue, Uri},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::i18n::Language;
+
+/// Set only by [`strip_prefix`]; a copy sent by the client is dropped.
+const PATH_LANGUAGE_HEADER: &str = "x-minerals-path-lang";
+
+/// Wraps the whole router, since a layer added with `Router::layer` runs
+/// after routing and could not change which route matches.
+pub async fn strip_prefix(mut request: Request, next: Next) -> Response {
+    request.headers_mut().remove(PATH_LANGUAGE_HEADER);
+    let split =
+        split_prefix(request.uri().path()).map(|(language, rest)| (language, rest.to_string()));
+    if let Some((language, rest)) = split {
+        let path_and_query = match request.uri().query() {
+            Some(query) => format!("{rest}?{query}"),
+            None => rest,
+        };
+        let mut parts = request.uri().clone().into_parts();
+        if let Ok(path_and_query) = PathAndQuery::try_from(path_and_query) {
+            parts.path_and_query = Some(path_and_query);
+            if let Ok(uri) = Uri::from_parts(parts) {
+                *request.uri_mut() = uri;
+                request.headers_mut().insert(
+                    PATH_LANGUAGE_HEADER,
+                    HeaderValue::from_static(language.code()),
+                );
+            }
+        }
+    }
+    next.run(request).await
+}
+
+/// Language named by the request path, if it had a prefix.
+pub fn path_language(headers: &HeaderMap) -> Option<Language> {
+    headers
+        .get(PATH_LANGUAGE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(Language::from_code)
+}
+
+/// `path` under the prefix the request came in with, so links handed back
+/// stay in the caller's language; unprefixed requests get `path` as is.
+pub fn prefixed(headers: &HeaderMap, path: &str) -> String {
+    match path_language(headers) {
+        Some(language) => format!("/{}{path}", language.code()),
+        None => path.to_string(),
+    }
+}
+
+/// `/es/minerals` splits into Spanish and `/minerals`, `/es` into Spanish
+/// and `/`. Only exact lowercase codes count, so `/esx` or `/ES` are left
+/// to the router.
+fn split_prefix(path: &str) -> Option<(Language, &str)> {
+    let rest = path.strip_prefix('/')?;
+    let (code, rest) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    let language = Language::all()
+        .iter()
+        .copied()
+        .find(|language| language.code() == code)?;
+    Some((language, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderMap;
+
+    use super::{prefixed, split_prefix};
+    use crate::i18n::Language;
+
+    #[test]
+    fn splits_known_language_prefixes_only() {
+        assert_eq!(
+            split_prefix("/es/minerals/mineral.silicates.0x1"),
+            Some((Language::Es, "/minerals/mineral.silicates.0x1"))
+        );
+        assert_eq!(split_prefix("/ko"), Some((Language::Ko, "/")));
+        assert_eq!(split_prefix("/sw/"), Some((Language::Sw, "/")));
+        assert_eq!(split_prefix("/minerals/es"), None);
+        assert_eq!(split_prefix("/esx/minerals"), None);
+        assert_eq!(split_prefix("/ES/minerals"), None);
+        assert_eq!(split_prefix("/"), None);
+        assert_eq!(
+            prefixed(&HeaderMap::new(), "/data/minerals/x/report.pdf"),
+            "/data/minerals/x/report.pdf"
+        );
+    }
+}
@@ -0,0 +1,924 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use metrics::counter;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use tracing::warn;
+
+use crate::llm_usage::{TokenPrices, UsageLedger};
+
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+const ANTHROPIC_MAX_TOKENS: u32 = 4096;
+const OLLAMA_BASE_URL: &str = "http://localhost:11434";
+/// OpenAI's image model; the chat models cannot return images.
+const OPENAI_IMAGE_MODEL: &str = "gpt-image-1";
+const BACKGROUND_REMOVAL_PROMPT: &str = "Remove the background around the mineral specimen. \
+Keep the specimen exactly as photographed, with its shape, colors, and texture unchanged, \
+on a fully transparent background.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LlmProviderKind {
+    #[default]
+    OpenAi,
+    Anthropic,
+    Ollama,
+}
+
+impl LlmProviderKind {
+    pub fn all() -> &'static [LlmProviderKind] {
+        &[
+            LlmProviderKind::OpenAi,
+            LlmProviderKind::Anthropic,
+            LlmProviderKind::Ollama,
+        ]
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LlmProviderKind::OpenAi => "openai",
+            LlmProviderKind::Anthropic => "anthropic",
+            LlmProviderKind::Ollama => "ollama",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        let normalized = code.trim().to_ascii_lowercase();
+        Self::all()
+            .iter()
+            .copied()
+            .find(|kind| kind.as_str() == normalized)
+    }
+
+    /// Model used when neither `LLM_MODEL` nor `OPENAI_MODEL` is set.
+    pub fn default_model(self) -> &'static str {
+        match self {
+            LlmProviderKind::OpenAi => "gpt-4o-mini",
+            LlmProviderKind::Anthropic => "claude-3-5-sonnet-latest",
+            LlmProviderKind::Ollama => "llama3.2-vision",
+        }
+    }
+
+    /// List price of [`Self::default_model`], for cost estimates when no
+    /// prices are configured. Local models cost nothing.
+    pub fn default_prices(self) -> TokenPrices {
+        let (prompt_per_mtok, completion_per_mtok) = match self {
+            LlmProviderKind::OpenAi => (0.15, 0.60),
+            LlmProviderKind::Anthropic => (3.0, 15.0),
+            LlmProviderKind::Ollama => (0.0, 0.0),
+        };
+        TokenPrices {
+            prompt_per_mtok,
+            completion_per_mtok,
+        }
+    }
+
+    /// Environment variable holding the provider's API key, if it needs one.
+    pub fn api_key_env(self) -> Option<&'static str> {
+        match self {
+            LlmProviderKind::OpenAi => Some("OPENAI_API_KEY"),
+            LlmProviderKind::Anthropic => Some("ANTHROPIC_API_KEY"),
+            LlmProviderKind::Ollama => None,
+        }
+    }
+}
+
+pub struct LlmImage<'a> {
+    pub mime: &'a str,
+    pub bytes: &'a [u8],
+}
+
+/// One structured-output completion: the provider must answer with a JSON
+/// document matching `schema`.
+pub struct LlmRequest<'a> {
+    pub model: &'a str,
+    pub system: &'a str,
+    pub prompt: String,
+    pub image: Option<LlmImage<'a>>,
+    pub schema_name: String,
+    pub schema: Value,
+    pub temperature: f32,
+    /// Upper bound for one attempt, enforced by [`ResilientProvider`].
+    pub timeout: Duration,
+}
+
+/// Tokens one completion used, as reported by the provider.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub prompt: u64,
+    pub completion: u64,
+}
+
+pub struct Completion {
+    pub content: String,
+    pub usage: Option<TokenUsage>,
+}
+
+/// Failures worth telling apart from generic transport or parse errors.
+#[derive(Debug, Error)]
+pub enum LlmCallError {
+    #[error("{provider} API returned {status}: {body}")]
+    Status {
+        provider: &'static str,
+        status: StatusCode,
+        body: String,
+    },
+    #[error("{provider} request timed out after {secs}s")]
+    Timeout { provider: &'static str, secs: u64 },
+    #[error("{provider} is unavailable after repeated failures; retrying in {secs}s")]
+    CircuitOpen { provider: &'static str, secs: u64 },
+    #[error(
+        "the monthly LLM budget of ${budget:.2} is used up (${spent:.2} spent this month); \
+raise LLM_MONTHLY_BUDGET_USD or wait for the next month"
+    )]
+    BudgetExceeded { spent: f64, budget: f64 },
+}
+
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    fn kind(&self) -> LlmProviderKind;
+
+    /// Base URL of the remote API, for reachability checks.
+    fn endpoint(&self) -> Option<&str> {
+        None
+    }
+
+    /// Returns the raw JSON text produced for `request` and the tokens it
+    /// used.
+    async fn complete(&self, request: &LlmRequest<'_>) -> Result<Completion>;
+
+    /// Returns the raw JSON text produced for `request`.
+    async fn complete_json(&self, request: &LlmRequest<'_>) -> Result<String> {
+        self.complete(request)
+            .await
+            .map(|completion| completion.content)
+    }
+
+    /// Returns `image` with the background around the specimen made
+    /// transparent, as PNG. Only providers with an image-editing API can.
+    async fn remove_background(&self, _image: &LlmImage<'_>) -> Result<Vec<u8>> {
+        Err(anyhow!(
+            "{} cannot remove photo backgrounds",
+            self.kind().as_str()
+        ))
+    }
+}
+
+/// Builds the provider for `kind`. Returns `None` when the provider needs an
+/// API key and none was given, so callers can fall back gracefully.
+pub fn build_provider(
+    kind: LlmProviderKind,
+    client: Client,
+    api_key: Option<String>,
+    base_url: Option<String>,
+) -> Option<Box<dyn LlmProvider>> {
+    let api_key = api_key.filter(|key| !key.trim().is_empty());
+    let base_url = |default: &str| {
+        base_url
+            .clone()
+            .filter(|url| !url.trim().is_empty())
+            .unwrap_or_else(|| default.to_string())
+            .trim_end_matches('/')
+            .to_string()
+    };
+
+    match kind {
+        LlmProviderKind::OpenAi => Some(Box::new(OpenAiProvider {
+            client,
+            api_key: api_key?,
+            base_url: base_url(OPENAI_BASE_URL),
+        })),
+        LlmProviderKind::Anthropic => Some(Box::new(AnthropicProvider {
+            client,
+            api_key: api_key?,
+            base_url: base_url(ANTHROPIC_BASE_URL),
+        })),
+        LlmProviderKind::Ollama => Some(Box::new(OllamaProvider {
+            client,
+            base_url: base_url(OLLAMA_BASE_URL),
+        })),
+    }
+}
+
+async fn read_json_response<T: for<'de> Deserialize<'de>>(
+    response: reqwest::Response,
+    provider: LlmProviderKind,
+) -> Result<T> {
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(LlmCallError::Status {
+            provider: provider.as_str(),
+            status,
+            body,
+        }
+        .into());
+    }
+    response
+        .json()
+        .await
+        .with_context(|| format!("failed to parse {} response", provider.as_str()))
+}
+
+fn record_tokens(provider: LlmProviderKind, prompt: u64, completion: u64) -> TokenUsage {
+    counter!("llm_tokens_total", "provider" => provider.as_str(), "kind" => "prompt")
+        .increment(prompt);
+    counter!("llm_tokens_total", "provider" => provider.as_str(), "kind" => "completion")
+        .increment(completion);
+    TokenUsage { prompt, completion }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Consecutive transient failures that open the circuit.
+    pub breaker_threshold: u32,
+    pub breaker_cooldown: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+            breaker_threshold: 5,
+            breaker_cooldown: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Wraps a provider with per-attempt timeouts, jittered exponential backoff
+/// on 429/5xx/transport errors, and a circuit breaker. Once the circuit is
+/// open, calls fail immediately so a publish falls back to English instead
+/// of waiting out every remaining language. With a [`UsageLedger`] it also
+/// records each call's tokens and refuses calls once the monthly budget is
+/// spent.
+pub struct ResilientProvider {
+    inner: Box<dyn LlmProvider>,
+    policy: RetryPolicy,
+    breaker: Mutex<BreakerState>,
+    ledger: Option<Arc<UsageLedger>>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl ResilientProvider {
+    pub fn new(inner: Box<dyn LlmProvider>, policy: RetryPolicy) -> Self {
+        Self {
+            inner,
+            policy: RetryPolicy {
+                max_attempts: policy.max_attempts.max(1),
+                ..policy
+            },
+            breaker: Mutex::new(BreakerState::default()),
+            ledger: None,
+        }
+    }
+
+    pub fn with_ledger(self, ledger: Arc<UsageLedger>) -> Self {
+        Self {
+            ledger: Some(ledger),
+            ..self
+        }
+    }
+
+    fn check_breaker(&self) -> Result<()> {
+        let Ok(state) = self.breaker.lock() else {
+            return Ok(());
+        };
+        match state.open_until {
+            Some(until) if until > Instant::now() => Err(LlmCallError::CircuitOpen {
+                provider: self.kind().as_str(),
+                secs: until.saturating_duration_since(Instant::now()).as_secs() + 1,
+            }
+            .into()),
+            // Past the cooldown one call goes through; another failure
+            // re-opens immediately because the count is still over threshold.
+            _ => Ok(()),
+        }
+    }
+
+    fn record(&self, success: bool) {
+        let Ok(mut state) = self.breaker.lock() else {
+            return;
+        };
+        if success {
+            *state = BreakerState::default();
+            return;
+        }
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= self.policy.breaker_threshold {
+            warn!(
+                "{} circuit open for {}s after {} consecutive failures",
+                self.kind().as_str(),
+                self.policy.breaker_cooldown.as_secs(),
+                state.consecutive_failures
+            );
+            state.open_until = Some(Instant::now() + self.policy.breaker_cooldown);
+        }
+    }
+
+    async fn attempt(&self, request: &LlmRequest<'_>) -> Result<Completion> {
+        match tokio::time::timeout(request.timeout, self.inner.complete(request)).await {
+            Ok(result) => result,
+            Err(_) => Err(LlmCallError::Timeout {
+                provider: self.kind().as_str(),
+                secs: request.timeout.as_secs(),
+            }
+            .into()),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ResilientProvider {
+    fn kind(&self) -> LlmProviderKind {
+        self.inner.kind()
+    }
+
+    fn endpoint(&self) -> Option<&str> {
+        self.inner.endpoint()
+    }
+
+    /// Not retried: one edit costs as much as many completions.
+    async fn remove_background(&self, image: &LlmImage<'_>) -> Result<Vec<u8>> {
+        if let Some(ledger) = &self.ledger {
+            ledger.check_budget()?;
+        }
+        self.check_breaker()?;
+        let result = self.inner.remove_background(image).await;
+        self.record(result.is_ok());
+        counter!(
+            "llm_image_edits_total",
+            "provider" => self.kind().as_str(),
+            "outcome" => if result.is_ok() { "ok" } else { "error" }
+        )
+        .increment(1);
+        result
+    }
+
+    async fn complete(&self, request: &LlmRequest<'_>) -> Result<Completion> {
+        if let Some(ledger) = &self.ledger {
+            ledger.check_budget()?;
+        }
+        let result = self.complete_with_retries(request).await;
+        counter!(
+            "llm_calls_total",
+            "provider" => self.kind().as_str(),
+            "outcome" => if result.is_ok() { "ok" } else { "error" }
+        )
+        .increment(1);
+        if let (Some(ledger), Ok(completion)) = (&self.ledger, &result) {
+            if let Err(err) = ledger.record(completion.usage.unwrap_or_default()).await {
+                warn!("failed to record LLM usage: {err:#}");
+            }
+        }
+        result
+    }
+}
+
+impl ResilientProvider {
+    async fn complete_with_retries(&self, request: &LlmRequest<'_>) -> Result<Completion> {
+        self.check_breaker()?;
+
+        let mut attempt = 1;
+        loop {
+            let err = match self.attempt(request).await {
+                Ok(content) => {
+                    self.record(true);
+                    return Ok(content);
+                }
+                Err(err) => err,
+            };
+
+            if !is_retryable(&err) {
+                return Err(err);
+            }
+            if attempt >= self.policy.max_attempts {
+                self.record(false);
+                return Err(err.context(format!("gave up after {attempt} attempts")));
+            }
+
+            let delay = backoff_delay(&self.policy, attempt);
+            warn!(
+                "{} attempt {attempt}/{} failed, retrying in {}ms: {err:#}",
+                self.kind().as_str(),
+                self.policy.max_attempts,
+                delay.as_millis()
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+fn is_retryable(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if let Some(call) = cause.downcast_ref::<LlmCallError>() {
+            return match call {
+                LlmCallError::Status { status, .. } => {
+                    *status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+                }
+                LlmCallError::Timeout { .. } => true,
+                LlmCallError::CircuitOpen { .. } | LlmCallError::BudgetExceeded { .. } => false,
+            };
+        }
+        cause
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(|err| err.is_timeout() || err.is_connect() || err.is_request())
+    })
+}
+
+/// "Equal jitter": half of the exponential step is fixed, the other half
+/// random, so concurrent retries spread out but never fire back-to-back.
+fn backoff_delay(policy: &RetryPolicy, attempt: u32) -> Duration {
+    let step = policy
+        .base_delay
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(policy.max_delay);
+    let half = step / 2;
+    let jitter_nanos = RandomState::new().build_hasher().finish() % (half.as_nanos() as u64 + 1);
+    half + Duration::from_nanos(jitter_nanos)
+}
+
+struct OpenAiProvider {
+    client: Client,
+    api_key: String,
+    base_url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatCompletionsRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage>,
+    response_format: ResponseFormat<'a>,
+    temperature: f32,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatMessage {
+    role: &'static str,
+    content: Vec<MessagePart>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type")]
+enum MessagePart {
+    #[serde(rename = "text")]
+    Text { text: String },
+    #[serde(rename = "image_url")]
+    ImageUrl { image_url: ImageUrlContent },
+}
+
+#[derive(Debug, Serialize)]
+struct ImageUrlContent {
+    url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ResponseFormat<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    json_schema: JsonSchemaSpec<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonSchemaSpec<'a> {
+    name: &'a str,
+    strict: bool,
+    schema: &'a Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionsResponse {
+    choices: Vec<ChatChoice>,
+    #[serde(default)]
+    usage: Option<OpenAiUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiUsage {
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatChoiceMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoiceMessage {
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImageEditResponse {
+    data: Vec<ImageEditData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImageEditData {
+    b64_json: String,
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiProvider {
+    fn kind(&self) -> LlmProviderKind {
+        LlmProviderKind::OpenAi
+    }
+
+    fn endpoint(&self) -> Option<&str> {
+        Some(&self.base_url)
+    }
+
+    async fn complete(&self, request: &LlmRequest<'_>) -> Result<Completion> {
+        let mut user_parts = vec![MessagePart::Text {
+            text: request.prompt.clone(),
+        }];
+        if let Some(image) = &request.image {
+            user_parts.push(MessagePart::ImageUrl {
+                image_url: ImageUrlContent {
+                    url: format!("data:{};base64,{}", image.mime, BASE64.encode(image.bytes)),
+                },
+            });
+        }
+
+        let body = ChatCompletionsRequest {
+            model: request.model,
+            messages: vec![
+                ChatMessage {
+                    role: "system",
+                    content: vec![MessagePart::Text {
+                        text: request.system.to_string(),
+                    }],
+                },
+                ChatMessage {
+                    role: "user",
+                    content: user_parts,
+                },
+            ],
+            response_format: ResponseFormat {
+                kind: "json_schema",
+                json_schema: JsonSchemaSpec {
+                    name: &request.schema_name,
+                    strict: true,
+                    schema: &request.schema,
+                },
+            },
+            temperature: request.temperature,
+        };
+
+        let response = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .send()
+            .await
+            .context("failed to call OpenAI API")?;
+        let parsed: ChatCompletionsResponse = read_json_response(response, self.kind()).await?;
+        let usage = parsed
+            .usage
+            .map(|usage| record_tokens(self.kind(), usage.prompt_tokens, usage.completion_tokens));
+
+        let content = parsed
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content)
+            .ok_or_else(|| anyhow!("OpenAI response had no choices"))?;
+        Ok(Completion { content, usage })
+    }
+
+    async fn remove_background(&self, image: &LlmImage<'_>) -> Result<Vec<u8>> {
+        let ext = image.mime.rsplit('/').next().unwrap_or("png");
+        let photo = reqwest::multipart::Part::bytes(image.bytes.to_vec())
+            .file_name(format!("specimen.{ext}"))
+            .mime_str(image.mime)
+            .context("invalid image type")?;
+        let form = reqwest::multipart::Form::new()
+            .text("model", OPENAI_IMAGE_MODEL)
+            .text("prompt", BACKGROUND_REMOVAL_PROMPT)
+            .text("background", "transparent")
+            .text("output_format", "png")
+            .part("image", photo);
+
+        let response = self
+            .client
+            .post(format!("{}/images/edits", self.base_url))
+            .bearer_auth(&self.api_key)
+            .multipart(form)
+            .send()
+            .await
+            .context("failed to call OpenAI API")?;
+        let parsed: ImageEditResponse = read_json_response(response, self.kind()).await?;
+        let encoded = parsed
+            .data
+            .into_iter()
+            .next()
+            .map(|data| data.b64_json)
+            .ok_or_else(|| anyhow!("OpenAI response had no image"))?;
+        BASE64
+            .decode(encoded)
+            .context("OpenAI returned an invalid image")
+    }
+}
+
+/// Anthropic has no JSON-schema response format; forcing a single tool call
+/// whose `input_schema` is the requested schema gives the same guarantee.
+struct AnthropicProvider {
+    client: Client,
+    api_key: String,
+    base_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicResponse {
+    content: Vec<AnthropicContentBlock>,
+    #[serde(default)]
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicUsage {
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicContentBlock {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    input: Option<Value>,
+}
+
+#[async_trait]
+impl LlmProvider for AnthropicProvider {
+    fn kind(&self) -> LlmProviderKind {
+        LlmProviderKind::Anthropic
+    }
+
+    fn endpoint(&self) -> Option<&str> {
+        Some(&self.base_url)
+    }
+
+    async fn complete(&self, request: &LlmRequest<'_>) -> Result<Completion> {
+        let mut content = Vec::new();
+        if let Some(image) = &request.image {
+            content.push(serde_json::json!({
+                "type": "image",
+                "source": {
+                    "type": "base64",
+                    "media_type": image.mime,
+                    "data": BASE64.encode(image.bytes),
+                },
+            }));
+        }
+        content.push(serde_json::json!({"type": "text", "text": request.prompt}));
+
+        let body = serde_json::json!({
+            "model": request.model,
+            "max_tokens": ANTHROPIC_MAX_TOKENS,
+            "temperature": request.temperature,
+            "system": request.system,
+            "messages": [{"role": "user", "content": content}],
+            "tools": [{
+                "name": request.schema_name,
+                "description": "Record the structured answer.",
+                "input_schema": request.schema,
+            }],
+            "tool_choice": {"type": "tool", "name": request.schema_name},
+        });
+
+        let response = self
+            .client
+            .post(format!("{}/v1/messages", self.base_url))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&body)
+            .send()
+            .await
+            .context("failed to call Anthropic API")?;
+        let parsed: AnthropicResponse = read_json_response(response, self.kind()).await?;
+        let usage = parsed
+            .usage
+            .map(|usage| record_tokens(self.kind(), usage.input_tokens, usage.output_tokens));
+
+        let content = parsed
+            .content
+            .into_iter()
+            .find(|block| block.kind == "tool_use")
+            .and_then(|block| block.input)
+            .map(|input| input.to_string())
+            .ok_or_else(|| anyhow!("Anthropic response had no tool_use block"))?;
+        Ok(Completion { content, usage })
+    }
+}
+
+/// Local models through Ollama's `/api/chat`; `format` accepts a JSON schema
+/// and images go alongside the user message as bare base64.
+struct OllamaProvider {
+    client: Client,
+    base_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    message: OllamaMessage,
+    #[serde(default)]
+    prompt_eval_count: u64,
+    #[serde(default)]
+    eval_count: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaMessage {
+    content: String,
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    fn kind(&self) -> LlmProviderKind {
+        LlmProviderKind::Ollama
+    }
+
+    fn endpoint(&self) -> Option<&str> {
+        Some(&self.base_url)
+    }
+
+    async fn complete(&self, request: &LlmRequest<'_>) -> Result<Completion> {
+        let mut user_message = serde_json::json!({"role": "user", "content": request.prompt});
+        if let Some(image) = &request.image {
+            user_message["images"] = serde_json::json!([BASE64.encode(image.bytes)]);
+        }
+
+        let body = serde_json::json!({
+            "model": request.model,
+            "stream": false,
+            "format": request.schema,
+            "options": {"temperature": request.temperature},
+            "messages": [
+                {"role": "system", "content": request.system},
+                user_message,
+            ],
+        });
+
+        let response = self
+            .client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&body)
+            .send()
+            .await
+            .with_context(|| format!("failed to call Ollama at {}", self.base_url))?;
+        let parsed: OllamaResponse = read_json_response(response, self.kind()).await?;
+        let usage = record_tokens(self.kind(), parsed.prompt_eval_count, parsed.eval_count);
+
+        Ok(Completion {
+            content: parsed.message.content,
+            usage: Some(usage),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicU32, Ordering},
+        sync::Arc,
+        time::Duration,
+    };
+
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use reqwest::StatusCode;
+
+    use super::{
+        build_provider, Completion, LlmCallError, LlmProvider, LlmProviderKind, LlmRequest,
+        ResilientProvider, RetryPolicy,
+    };
+
+    #[test]
+    fn hosted_providers_require_an_api_key() {
+        let client = reqwest::Client::new();
+        assert_eq!(
+            LlmProviderKind::from_code(" Anthropic "),
+            Some(LlmProviderKind::Anthropic)
+        );
+        assert_eq!(LlmProviderKind::from_code("bard"), None);
+
+        assert!(build_provider(LlmProviderKind::OpenAi, client.clone(), None, None).is_none());
+        assert!(build_provider(
+            LlmProviderKind::Anthropic,
+            client.clone(),
+            Some("  ".to_string()),
+            None
+        )
+        .is_none());
+        let ollama = build_provider(LlmProviderKind::Ollama, client, None, None).unwrap();
+        assert_eq!(ollama.kind(), LlmProviderKind::Ollama);
+    }
+
+    /// Fails with `status` for the first `failures` calls, then succeeds.
+    struct Flaky {
+        calls: Arc<AtomicU32>,
+        failures: u32,
+        status: StatusCode,
+    }
+
+    #[async_trait]
+    impl LlmProvider for Flaky {
+        fn kind(&self) -> LlmProviderKind {
+            LlmProviderKind::Ollama
+        }
+
+        async fn complete(&self, _request: &LlmRequest<'_>) -> Result<Completion> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(LlmCallError::Status {
+                    provider: "ollama",
+                    status: self.status,
+                    body: String::new(),
+                }
+                .into());
+            }
+            Ok(Completion {
+                content: "{}".to_string(),
+                usage: None,
+            })
+        }
+    }
+
+    fn resilient(failures: u32, status: StatusCode) -> (ResilientProvider, Arc<AtomicU32>) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let provider = ResilientProvider::new(
+            Box::new(Flaky {
+                calls: Arc::clone(&calls),
+                failures,
+                status,
+            }),
+            RetryPolicy {
+                max_attempts: 3,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(4),
+                breaker_threshold: 2,
+                breaker_cooldown: Duration::from_secs(60),
+            },
+        );
+        (provider, calls)
+    }
+
+    fn request() -> LlmRequest<'static> {
+        LlmRequest {
+            model: "test",
+            system: "",
+            prompt: String::new(),
+            image: None,
+            schema_name: "test".to_string(),
+            schema: serde_json::json!({}),
+            temperature: 0.0,
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors_and_opens_the_circuit() {
+        let (provider, calls) = resilient(2, StatusCode::TOO_MANY_REQUESTS);
+        assert!(provider.complete_json(&request()).await.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let (provider, calls) = resilient(u32::MAX, StatusCode::BAD_REQUEST);
+        assert!(provider.complete_json(&request()).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let (provider, calls) = resilient(u32::MAX, StatusCode::SERVICE_UNAVAILABLE);
+        for _ in 0..2 {
+            assert!(provider.complete_json(&request()).await.is_err());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+        let err = provider.complete_json(&request()).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<LlmCallError>(),
+            Some(LlmCallError::CircuitOpen { .. })
+        ));
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+    }
+}
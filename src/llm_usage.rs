@@ -0,0 +1,274 @@
+//! Running record of LLM token use in `data/llm_usage.json`: one entry per
+//! UTC day with the calls made, the tokens spent and their estimated cost
+//! at the configured prices. An optional monthly budget refuses further
+//! calls once the month's estimated cost reaches it; the call that reaches
+//! it mails the admins.
+
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{
+    email::Mailer,
+    llm::{LlmCallError, TokenUsage},
+};
+
+/// Days listed on the admin dashboard.
+const RECENT_DAYS: usize = 14;
+
+/// USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenPrices {
+    pub prompt_per_mtok: f64,
+    pub completion_per_mtok: f64,
+}
+
+impl TokenPrices {
+    pub fn cost(&self, usage: TokenUsage) -> f64 {
+        (usage.prompt as f64 * self.prompt_per_mtok
+            + usage.completion as f64 * self.completion_per_mtok)
+            / 1_000_000.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct DailyUsage {
+    pub calls: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl DailyUsage {
+    fn add(&mut self, other: &DailyUsage) {
+        self.calls += other.calls;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+}
+
+/// What the admin dashboard shows.
+#[derive(Debug, Clone)]
+pub struct UsageSummary {
+    /// `YYYY-MM`, UTC.
+    pub month: String,
+    pub month_total: DailyUsage,
+    pub monthly_budget_usd: Option<f64>,
+    pub prices: TokenPrices,
+    /// Newest first, `(YYYY-MM-DD, usage)`.
+    pub recent_days: Vec<(String, DailyUsage)>,
+}
+
+impl UsageSummary {
+    /// Share of the budget spent, capped at 100.
+    pub fn budget_percent(&self) -> Option<u32> {
+        self.monthly_budget_usd
+            .filter(|budget| *budget > 0.0)
+            .map(|budget| (self.month_total.cost_usd / budget * 100.0).min(100.0) as u32)
+    }
+
+    /// Same test as [`UsageLedger::check_budget`].
+    pub fn budget_spent(&self) -> bool {
+        self.monthly_budget_usd
+            .is_some_and(|budget| self.month_total.cost_usd >= budget)
+    }
+}
+
+pub struct UsageLedger {
+    path: PathBuf,
+    prices: TokenPrices,
+    monthly_budget_usd: Option<f64>,
+    days: Mutex<BTreeMap<String, DailyUsage>>,
+    /// Held across the file write so two calls finishing together cannot
+    /// leave the older snapshot on disk.
+    writer: tokio::sync::Mutex<()>,
+    /// Told once per month, when the budget is reached.
+    alerts: Option<Arc<Mailer>>,
+}
+
+impl UsageLedger {
+    pub async fn load(
+        path: impl Into<PathBuf>,
+        prices: TokenPrices,
+        monthly_budget_usd: Option<f64>,
+    ) -> Result<Self> {
+        let path = path.into();
+        let days = match fs::read_to_string(&path).await {
+            Ok(raw) => serde_json::from_str(&raw)
+                .with_context(|| format!("failed to parse {}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(anyhow!("failed to read {}: {err}", path.display())),
+        };
+        Ok(Self {
+            path,
+            prices,
+            monthly_budget_usd,
+            days: Mutex::new(days),
+            writer: tokio::sync::Mutex::new(()),
+            alerts: None,
+        })
+    }
+
+    pub fn with_alerts(mut self, mailer: Arc<Mailer>) -> Self {
+        self.alerts = Some(mailer);
+        self
+    }
+
+    /// Fails once this month's estimated cost has reached the budget.
+    pub fn check_budget(&self) -> Result<(), LlmCallError> {
+        let Some(budget) = self.monthly_budget_usd else {
+            return Ok(());
+        };
+        let spent = self.month_total(Utc::now().date_naive()).cost_usd;
+        if spent >= budget {
+            return Err(LlmCallError::BudgetExceeded { spent, budget });
+        }
+        Ok(())
+    }
+
+    pub async fn record(&self, usage: TokenUsage) -> Result<()> {
+        self.record_on(Utc::now().date_naive(), usage).await
+    }
+
+    async fn record_on(&self, day: NaiveDate, usage: TokenUsage) -> Result<()> {
+        let _writer = self.writer.lock().await;
+        let (json, spent_before, spent) = {
+            let mut days = self
+                .days
+                .lock()
+                .map_err(|_| anyhow!("usage ledger lock poisoned"))?;
+            let spent_before = month_total_in(&days, day).cost_usd;
+            days.entry(day.format("%Y-%m-%d").to_string())
+                .or_default()
+                .add(&DailyUsage {
+                    calls: 1,
+                    prompt_tokens: usage.prompt,
+                    completion_tokens: usage.completion,
+                    cost_usd: self.prices.cost(usage),
+                });
+            let json =
+                serde_json::to_string_pretty(&*days).context("failed to serialize LLM usage")?;
+            (json, spent_before, month_total_in(&days, day).cost_usd)
+        };
+        if let (Some(budget), Some(mailer)) = (self.monthly_budget_usd, &self.alerts) {
+            if spent_before < budget && spent >= budget {
+                let mailer = Arc::clone(mailer);
+                let month = day.format("%Y-%m");
+                let body = format!(
+                    "Estimated LLM spend for {month} reached ${spent:.2} of the ${budget:.2} \
+                     budget. Further LLM calls are refused until next month or until \
+                     LLM_MONTHLY_BUDGET_USD is raised: AI suggestions fail and translations \
+                     fall back to English.\n"
+                );
+                tokio::spawn(async move {
+                    mailer
+                        .notify_admins("LLM monthly budget reached", &body, None)
+                        .await;
+                });
+            }
+        }
+
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, json)
+            .await
+            .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &self.path)
+            .await
+            .with_context(|| format!("failed to replace {}", self.path.display()))
+    }
+
+    pub fn summary(&self) -> UsageSummary {
+        let today = Utc::now().date_naive();
+        let recent_days = self
+            .days
+            .lock()
+            .map(|days| {
+                days.iter()
+                    .rev()
+                    .take(RECENT_DAYS)
+                    .map(|(day, usage)| (day.clone(), *usage))
+                    .collect()
+            })
+            .unwrap_or_default();
+        UsageSummary {
+            month: today.format("%Y-%m").to_string(),
+            month_total: self.month_total(today),
+            monthly_budget_usd: self.monthly_budget_usd,
+            prices: self.prices,
+            recent_days,
+        }
+    }
+
+    fn month_total(&self, today: NaiveDate) -> DailyUsage {
+        self.days
+            .lock()
+            .map(|days| month_total_in(&days, today))
+            .unwrap_or_default()
+    }
+}
+
+fn month_total_in(days: &BTreeMap<String, DailyUsage>, today: NaiveDate) -> DailyUsage {
+    let month = today.format("%Y-%m-").to_string();
+    let mut total = DailyUsage::default();
+    days.iter()
+        .filter(|(day, _)| day.starts_with(&month))
+        .for_each(|(_, usage)| total.add(usage));
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{TokenPrices, UsageLedger};
+    use crate::{
+        llm::{LlmCallError, TokenUsage},
+        test_support::TempDir,
+    };
+
+    #[tokio::test]
+    async fn totals_the_month_and_enforces_the_budget() {
+        let dir = TempDir::new("llm-usage");
+        let path = dir.join("llm-usage.json");
+        let prices = TokenPrices {
+            prompt_per_mtok: 0.15,
+            completion_per_mtok: 0.60,
+        };
+        let ledger = UsageLedger::load(&path, prices, Some(0.5)).await.unwrap();
+        let usage = TokenUsage {
+            prompt: 1_000_000,
+            completion: 500_000,
+        };
+        let day = |d| NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        ledger
+            .record_on(NaiveDate::from_ymd_opt(2026, 2, 28).unwrap(), usage)
+            .await
+            .unwrap();
+        ledger.record_on(day(1), usage).await.unwrap();
+        ledger.record_on(day(2), usage).await.unwrap();
+
+        let march = ledger.month_total(day(15));
+        assert_eq!(march.calls, 2);
+        assert_eq!(march.prompt_tokens, 2_000_000);
+        assert!((march.cost_usd - 0.9).abs() < 1e-9);
+
+        let reloaded = UsageLedger::load(&path, prices, None).await.unwrap();
+        assert_eq!(reloaded.month_total(day(15)), march);
+        assert!(reloaded.check_budget().is_ok());
+
+        // The current month has no spend yet, so only a zero budget blocks.
+        let empty = UsageLedger::load(&path, prices, Some(0.0)).await.unwrap();
+        assert!(matches!(
+            empty.check_budget(),
+            Err(LlmCallError::BudgetExceeded { .. })
+        ));
+    }
+}
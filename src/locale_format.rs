@@ -0,0 +1,220 @@
+//! Numbers, prices, and dates as readers of each language write them: `7,50` in
+//! German, `1 234,5` in French, `2024年6月1日` in Japanese. Only what is
+//! shown is formatted this way; stored records, API fields, and values
+//! the templates compute with (bar widths) keep `.` and RFC 3339.
+
+use std::borrow::Borrow;
+
+use chrono::{DateTime, Utc};
+
+use crate::i18n::Language;
+
+/// Formatting for one language; `Copy` so templates can hold it as a field.
+/// Values are taken by `Borrow` because Askama passes loop fields by
+/// reference and top-level ones by value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocaleFormat {
+    language: Language,
+}
+
+impl LocaleFormat {
+    pub fn new(language: Language) -> Self {
+        Self { language }
+    }
+
+    /// `value` with exactly `places` decimals.
+    pub fn decimal(&self, value: impl Borrow<f32>, places: usize) -> String {
+        let value = *value.borrow();
+        self.localize(&format!("{value:.places$}"))
+    }
+
+    /// Like [`decimal`](Self::decimal), blank when there is no value.
+    pub fn optional_decimal(&self, value: impl Borrow<Option<f32>>, places: usize) -> String {
+        value
+            .borrow()
+            .map(|value| self.decimal(value, places))
+            .unwrap_or_default()
+    }
+
+    /// `value` with as many decimals as it needs, like `Display`.
+    pub fn number(&self, value: impl Borrow<f32>) -> String {
+        self.localize(&value.borrow().to_string())
+    }
+
+    /// Like [`money`](Self::money), blank when there is no price.
+    pub fn price(&self, amount: impl Borrow<Option<f64>>, currency: &str) -> String {
+        amount
+            .borrow()
+            .map(|amount| self.money(amount, currency))
+            .unwrap_or_default()
+    }
+
+    /// An amount such as `$1,234.50` or `1.234,50 €`: the currency's symbol
+    /// where it has a common one (its ISO code otherwise), on the side the
+    /// language puts it, and no decimals for currencies without minor
+    /// units.
+    pub fn money(&self, amount: impl Borrow<f64>, currency: &str) -> String {
+        let amount = *amount.borrow();
+        let places = match currency {
+            "JPY" | "KRW" | "VND" | "CLP" | "ISK" => 0,
+            _ => 2,
+        };
+        let number = self.localize(&format!("{amount:.places$}"));
+        let symbol = match currency {
+            "USD" => "$",
+            "EUR" => "€",
+            "GBP" => "£",
+            "JPY" => "¥",
+            "INR" => "₹",
+            "KRW" => "₩",
+            "BRL" => "R$",
+            "RUB" => "₽",
+            "TRY" => "₺",
+            other => other,
+        };
+        let after = matches!(
+            self.language,
+            Language::Es | Language::Fr | Language::De | Language::Cs | Language::Ru
+        );
+        match (after, symbol == currency) {
+            (true, _) => format!("{number}\u{A0}{symbol}"),
+            (false, true) => format!("{symbol}\u{A0}{number}"),
+            (false, false) => format!("{symbol}{number}"),
+        }
+    }
+
+    /// `2024-06-01 12:00:00 UTC` in the language's date order.
+    pub fn datetime(&self, time: DateTime<Utc>) -> String {
+        let pattern = match self.language {
+            Language::En => "%Y-%m-%d %H:%M:%S UTC",
+            Language::Zh | Language::Ja => "%Y年%-m月%-d日 %H:%M:%S UTC",
+            Language::Ko => "%Y. %-m. %-d. %H:%M:%S UTC",
+            Language::Cs => "%-d. %-m. %Y %H:%M:%S UTC",
+            Language::De | Language::Ru | Language::Tr => "%d.%m.%Y %H:%M:%S UTC",
+            Language::Es
+            | Language::Fr
+            | Language::Pt
+            | Language::Ar
+            | Language::Hi
+            | Language::Sw => "%d/%m/%Y %H:%M:%S UTC",
+        };
+        time.format(pattern).to_string()
+    }
+
+    /// An RFC 3339 timestamp such as `MineralReport::generated_utc`, or the
+    /// text unchanged if it does not parse.
+    pub fn timestamp(&self, rfc3339: &str) -> String {
+        DateTime::parse_from_rfc3339(rfc3339)
+            .map(|time| self.datetime(time.with_timezone(&Utc)))
+            .unwrap_or_else(|_| rfc3339.to_string())
+    }
+
+    /// Decimal mark and digit grouping.
+    fn separators(&self) -> (char, char) {
+        match self.language {
+            Language::Es | Language::De | Language::Pt | Language::Tr => (',', '.'),
+            Language::Fr => (',', '\u{202F}'),
+            Language::Cs | Language::Ru => (',', '\u{A0}'),
+            Language::En
+            | Language::Zh
+            | Language::Ar
+            | Language::Hi
+            | Language::Ja
+            | Language::Ko
+            | Language::Sw => ('.', ','),
+        }
+    }
+
+    /// Rewrites a number formatted by Rust (`-1234.5`).
+    fn localize(&self, plain: &str) -> String {
+        let (decimal_mark, group_mark) = self.separators();
+        let (sign, unsigned) = match plain.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", plain),
+        };
+        let (integer, fraction) = match unsigned.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (unsigned, None),
+        };
+        let mut text = sign.to_string();
+        text.push_str(&self.group(integer, group_mark));
+        if let Some(fraction) = fraction {
+            text.push(decimal_mark);
+            text.push_str(fraction);
+        }
+        text
+    }
+
+    /// Groups of three digits; Hindi groups by two above the thousands
+    /// (`1,23,456`), and Spanish leaves four-digit numbers ungrouped.
+    fn group(&self, digits: &str, mark: char) -> String {
+        let minimum = match self.language {
+            Language::Es => 5,
+            _ => 4,
+        };
+        if digits.len() < minimum || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            return digits.to_string();
+        }
+        let (head, last_three) = digits.split_at(digits.len() - 3);
+        let size = match self.language {
+            Language::Hi => 2,
+            _ => 3,
+        };
+        let mut groups = Vec::new();
+        let mut rest = head;
+        while rest.len() > size {
+            let (front, back) = rest.split_at(rest.len() - size);
+            groups.push(back);
+            rest = front;
+        }
+        groups.push(rest);
+        groups.reverse();
+        groups.push(last_three);
+        groups.join(&mark.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::LocaleFormat;
+    use crate::i18n::Language;
+
+    #[test]
+    fn formats_numbers_and_dates_per_language() {
+        let en = LocaleFormat::new(Language::En);
+        let de = LocaleFormat::new(Language::De);
+        assert_eq!(en.decimal(7.5, 2), "7.50");
+        assert_eq!(de.decimal(7.5, 2), "7,50");
+        assert_eq!(de.number(2.65), "2,65");
+        assert_eq!(en.decimal(-1234.5, 1), "-1,234.5");
+        assert_eq!(
+            LocaleFormat::new(Language::Fr).decimal(1234.5, 1),
+            "1\u{202F}234,5"
+        );
+        assert_eq!(LocaleFormat::new(Language::Es).decimal(1234.5, 1), "1234,5");
+        assert_eq!(
+            LocaleFormat::new(Language::Hi).decimal(1234567.0, 0),
+            "12,34,567"
+        );
+
+        assert_eq!(en.price(Some(1234.5), "USD"), "$1,234.50");
+        assert_eq!(de.price(Some(1234.5), "EUR"), "1.234,50\u{A0}€");
+        assert_eq!(
+            LocaleFormat::new(Language::Ja).price(Some(1500.0), "JPY"),
+            "¥1,500"
+        );
+        assert_eq!(en.price(Some(80.0), "CHF"), "CHF\u{A0}80.00");
+        assert_eq!(en.price(None, "USD"), "");
+
+        let time = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        assert_eq!(en.datetime(time), "2024-06-01 12:00:00 UTC");
+        assert_eq!(de.datetime(time), "01.06.2024 12:00:00 UTC");
+        assert_eq!(
+            LocaleFormat::new(Language::Ja).timestamp("2024-06-01T12:00:00+00:00"),
+            "2024年6月1日 12:00:00 UTC"
+        );
+        assert_eq!(de.timestamp("not a time"), "not a time");
+    }
+}
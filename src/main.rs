@@ -1,74 +1,261 @@
+mod accounts;
 mod agent;
+mod api;
+mod api_tokens;
+mod assets;
+mod auth;
+mod background;
+mod backup;
+mod batch;
+mod catalog;
+mod catalog_api;
+mod certificates;
+mod charts;
+mod chemistry;
+mod cli;
+mod client_ip;
+mod comparison;
+mod config;
+mod consensus;
+mod crypto;
+mod csrf;
+mod custody;
+mod discussion;
+mod drafts;
+mod duplicates;
+mod elements;
+mod email;
+mod etag;
+mod export;
+mod families;
+mod geo;
+mod graphql;
+mod health;
+mod history;
 mod i18n;
+mod images;
+mod import;
+mod indices;
+mod inquiries;
+mod jobs;
+mod lang_path;
+mod llm;
+mod llm_usage;
+mod locale_format;
+mod markdown;
+mod migrations;
+mod mindat;
 mod models;
+mod narrative;
+mod orders;
 mod pdf;
+mod permalinks;
+mod presets;
+mod rate_limit;
+mod report_runs;
+mod report_templates;
+mod request_log;
+mod safety;
+mod schedule;
+mod search;
+mod shop;
+mod signing;
+mod similarity;
+mod specimens;
+mod spectra;
+mod staging;
+mod standalone;
+mod strunz;
+mod telemetry;
+#[cfg(test)]
+mod test_support;
+mod timestamp_id;
+mod tls;
+mod translation_cache;
+mod translations;
+mod trash;
+mod units;
+mod visibility;
+mod watcher;
 mod web;
 
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
-    io::Read,
+    collections::{BTreeMap, HashMap},
+    future::Future,
     net::SocketAddr,
     path::{Path, PathBuf},
-    sync::{Arc, Mutex, RwLock},
+    pin::pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Duration,
 };
 
+use accounts::AccountStore;
 use anyhow::{anyhow, Context, Result};
+use api_tokens::ApiTokenStore;
+use auth::{
+    hash_password, load_admin_credential, store_admin_credential, verify_password,
+    MIN_ADMIN_PASSWORD_LEN,
+};
 use axum::{
-    extract::{DefaultBodyLimit, Multipart, Path as AxumPath, State},
+    extract::{DefaultBodyLimit, Multipart, Path as AxumPath, Query, State},
+    handler::Handler,
     http::{header, HeaderMap, HeaderValue, StatusCode},
-    response::{IntoResponse, Redirect, Response},
-    routing::{get, post},
-    Form, Json, Router,
+    middleware,
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{get, get_service, post},
+    Form, Json, Router, ServiceExt,
 };
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
-use i18n::{language_options, ui_text, Language};
+use catalog::{CatalogFilters, CatalogQuery, MineralCatalog, SortKey, DEFAULT_PAGE_SIZE};
+use clap::Parser;
+use cli::{Cli, Command};
+use client_ip::ClientIp;
+use config::Config;
+use consensus::FieldConfidence;
+use csrf::CsrfForm;
+use drafts::DraftStore;
+use email::Mailer;
+use families::FamilyNotes;
+use futures_util::{stream, StreamExt};
+use geo::Location;
+use graphql::CatalogSchema;
+use i18n::{language_options, ui_text, Language, UiText};
+use inquiries::InquiryNotifier;
+use jobs::{JobQueue, JobStatus, PdfJob};
+use llm::{build_provider, LlmImage, LlmProvider, LlmRequest, ResilientProvider};
+use llm_usage::UsageLedger;
+use locale_format::LocaleFormat;
+use metrics_exporter_prometheus::PrometheusHandle;
+use migrations::SCHEMA_VERSION;
+use mindat::MindatClient;
 use models::{
-    is_valid_mineral_folder_name, load_minerals, major_elements_to_text, parse_major_elements,
-    Mineral, MineralDiskRecord, MineralFormData, ReportRequest,
+    is_valid_mineral_folder_name, load_mineral, load_minerals, major_elements_to_text,
+    mineral_from_record, next_image_file, normalize_tag, parse_extra_attributes,
+    parse_major_elements, parse_references, parse_tags, select_metadata_path, GalleryImage,
+    ImageEntry, Mineral, MineralDiskRecord, MineralFormData, ReportRequest,
 };
-use pdf::GeneratedArtifacts;
+use rate_limit::RateLimiter;
+use report_templates::ReportTemplates;
 use reqwest::Client;
+use schedule::PublishNotifier;
 use serde::{Deserialize, Serialize};
+use shop::Listing;
+use signing::ReportSigner;
+use similarity::{RelatedMineral, RELATED_LIMIT};
 use thiserror::Error;
 use tokio::{fs, net::TcpListener};
-use tower_http::services::ServeDir;
+use tower::Layer;
+use tower_http::{
+    compression::{
+        predicate::{NotForContentType, Predicate},
+        CompressionLayer, DefaultPredicate,
+    },
+    services::ServeDir,
+};
 use tracing::{error, info, warn};
 use tracing_subscriber::EnvFilter;
+use translation_cache::TranslationCache;
+use translations::{find_missing_translations, BatchProgress, TranslationBatch};
+use units::UnitSystem;
+use visibility::Visibility;
 
 use crate::{
-    agent::run_agentic_chain,
-    pdf::PdfGenerator,
+    agent::{run_agentic_chain, Classification, MineralAttachments, MineralReport},
+    pdf::{PdfBackendKind, PdfGenerator},
+    report_runs::ReportRun,
+    search::SuggestKind,
     web::{
         AboutTemplate, AdminTemplate, HomeTemplate, IndexTemplate, InfoTemplate, MineralTemplate,
-        TemplateResponse,
+        ReportOutputTemplate, SelectOption, TemplateResponse, HTMX_SCRIPT,
     },
 };
 
 #[derive(Clone)]
 struct AppState {
-    catalogs_by_lang: Arc<RwLock<HashMap<String, MineralCatalog>>>,
-    admin_sessions: Arc<Mutex<HashSet<String>>>,
-    admin_drafts: Arc<Mutex<HashMap<String, AdminDraft>>>,
+    catalogs_by_lang: Arc<RwLock<HashMap<String, Arc<MineralCatalog>>>>,
+    /// Bumped on every catalog reload; feeds the catalog page `ETag`s.
+    catalog_generation: Arc<AtomicU64>,
+    /// Admin session token to the session's CSRF token.
+    admin_sessions: Arc<Mutex<HashMap<String, String>>>,
+    drafts: Arc<DraftStore>,
+    api_tokens: ApiTokenStore,
+    /// Visitor accounts, their favorites, and their sessions.
+    accounts: AccountStore,
+    inquiry_notifier: Arc<InquiryNotifier>,
+    /// Announces minerals going live at their `publish_at`.
+    publish_notifier: Arc<PublishNotifier>,
+    /// Admin-written family descriptions for `/families`.
+    family_notes: FamilyNotes,
+    /// Admin notifications and reports sent by email.
+    mailer: Arc<Mailer>,
     pdf_generator: Arc<PdfGenerator>,
+    pdf_jobs: JobQueue,
+    report_templates: ReportTemplates,
+    /// Hardness and density band cutoffs for reports.
+    classification: Classification,
     data_root: Arc<PathBuf>,
-    admin_password: Arc<String>,
-    openai_api_key: Arc<Option<String>>,
-    openai_model: Arc<String>,
-    openai_translation_model: Arc<String>,
+    static_root: Arc<PathBuf>,
+    /// `HTMX_SCRIPT` is installed under `static_root`.
+    htmx: bool,
+    admin_credential: Arc<RwLock<String>>,
+    admin_credential_path: Arc<PathBuf>,
+    llm: Option<Arc<dyn LlmProvider>>,
+    /// Tokens and estimated cost of every LLM call, per day.
+    llm_usage: Arc<UsageLedger>,
+    /// Reference lookups for the admin form; `None` without `MINDAT_API_KEY`.
+    mindat: Option<Arc<MindatClient>>,
+    llm_model: Arc<String>,
+    llm_translation_model: Arc<String>,
+    llm_timeout: Duration,
+    llm_translation_timeout: Duration,
+    /// Languages translated at once by `build_localized_metadata`.
+    llm_translation_concurrency: usize,
+    /// Overall budget for one `build_localized_metadata` call.
+    llm_translation_deadline: Duration,
+    /// Earlier provider replies to identical translation requests.
+    translation_cache: TranslationCache,
+    translation_batch: TranslationBatch,
+    translation_interval: Duration,
     default_language: Language,
-    http_client: Arc<Client>,
+    metrics: PrometheusHandle,
+    /// `READYZ_CHECK_LLM`: also require the LLM endpoint for readiness.
+    readyz_check_llm: bool,
+    graphql: CatalogSchema,
+    graphql_playground: bool,
+    /// `IMAGE_UPLOAD_MAX_MB`, per photo.
+    image_upload_max_mb: usize,
+    /// `IMAGE_CONVERT_BIN`, for HEIC and TIFF uploads.
+    image_convert_bin: String,
+    /// `BACKGROUND_REMOVAL_BIN`; see [`background`].
+    background_removal_bin: Option<String>,
+    /// `TRASH_RETENTION_DAYS`; `0` keeps deleted minerals forever.
+    trash_retention_days: u64,
+    /// `SEARCH_FUZZY_DISTANCE`; see [`search`].
+    search_fuzzy_distance: usize,
+    /// `PUBLIC_URL` is https, so session cookies are marked `Secure`.
+    secure_cookies: bool,
 }
 
-#[derive(Debug, Clone)]
-struct AdminDraft {
-    image_bytes: Vec<u8>,
-    image_ext: String,
-}
+impl AppState {
+    fn image_upload_max_bytes(&self) -> usize {
+        self.image_upload_max_mb * 1024 * 1024
+    }
+
+    /// `Set-Cookie` value for a login session; `max_age` 0 clears it.
+    fn session_cookie(&self, name: &str, value: &str, max_age: impl std::fmt::Display) -> String {
+        let secure = if self.secure_cookies { "; Secure" } else { "" };
+        format!("{name}={value}; HttpOnly; Path=/; SameSite=Lax; Max-Age={max_age}{secure}")
+    }
 
-#[derive(Debug, Clone, Default)]
-struct MineralCatalog {
-    by_slug: HashMap<String, Mineral>,
-    ordered: Vec<Mineral>,
+    fn image_too_large(&self) -> AppError {
+        AppError::BadRequest(format!(
+            "image upload too large; keep file under {} MB",
+            self.image_upload_max_mb
+        ))
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -76,19 +263,67 @@ struct LanguageSelectionRequest {
     lang: String,
 }
 
-impl MineralCatalog {
-    fn new(minerals: Vec<Mineral>) -> Self {
-        let by_slug = minerals
-            .iter()
-            .cloned()
-            .map(|mineral| (mineral.slug.clone(), mineral))
-            .collect::<HashMap<_, _>>();
+#[derive(Debug, Deserialize)]
+struct UnitsSelectionRequest {
+    units: String,
+    /// Page to go back to; only paths on this site are followed.
+    #[serde(default)]
+    return_to: String,
+}
 
-        Self {
-            by_slug,
-            ordered: minerals,
-        }
-    }
+#[derive(Debug, Deserialize)]
+struct PdfReportRequest {
+    #[serde(flatten)]
+    report: ReportRequest,
+    #[serde(default)]
+    backend: Option<String>,
+    /// Mail the finished report here (API token required).
+    #[serde(default)]
+    email_to: Option<String>,
+    /// From `?standalone=1`.
+    #[serde(skip)]
+    standalone: bool,
+}
+
+/// Query of the report generation API.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct GenerationParams {
+    /// Inline the stylesheet and images into `report.html`, so it can be
+    /// mailed and opened offline.
+    #[serde(deserialize_with = "models::checkbox")]
+    standalone: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct MineralPageParams {
+    job: Option<String>,
+    /// `sent` after an inquiry was submitted.
+    inquiry: Option<String>,
+    /// Report preset to fill the report builder with.
+    preset: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct IndexParams {
+    page: Option<usize>,
+    page_size: Option<usize>,
+    sort: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct SearchParams {
+    q: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct SuggestParams {
+    q: String,
+    limit: Option<usize>,
 }
 
 #[derive(Debug, Error)]
@@ -98,7 +333,12 @@ enum AppError {
     #[error("{0}")]
     Unauthorized(String),
     #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
     BadRequest(String),
+    /// Seconds until the client may try again.
+    #[error("too many requests; retry in {0}s")]
+    TooManyRequests(u64),
     #[error(transparent)]
     Internal(#[from] anyhow::Error),
 }
@@ -108,21 +348,34 @@ impl IntoResponse for AppError {
         match self {
             AppError::NotFound(message) => {
                 warn!("not found: {message}");
-                (StatusCode::NOT_FOUND, message).into_response()
+                (StatusCode::NOT_FOUND, with_request_id(message)).into_response()
             }
             AppError::Unauthorized(message) => {
                 warn!("unauthorized: {message}");
-                (StatusCode::UNAUTHORIZED, message).into_response()
+                (StatusCode::UNAUTHORIZED, with_request_id(message)).into_response()
+            }
+            AppError::Forbidden(message) => {
+                warn!("forbidden: {message}");
+                (StatusCode::FORBIDDEN, with_request_id(message)).into_response()
             }
             AppError::BadRequest(message) => {
                 warn!("bad request: {message}");
-                (StatusCode::BAD_REQUEST, message).into_response()
+                (StatusCode::BAD_REQUEST, with_request_id(message)).into_response()
+            }
+            AppError::TooManyRequests(retry_after) => {
+                warn!("rate limited: retry in {retry_after}s");
+                (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(header::RETRY_AFTER, retry_after.to_string())],
+                    with_request_id(format!("too many requests; retry in {retry_after}s")),
+                )
+                    .into_response()
             }
             AppError::Internal(error) => {
                 error!("internal error: {error:#}");
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    "internal server error".to_string(),
+                    with_request_id("internal server error".to_string()),
                 )
                     .into_response()
             }
@@ -130,21 +383,79 @@ impl IntoResponse for AppError {
     }
 }
 
+/// Error bodies name the request, so a reported failure can be found in
+/// the logs.
+fn with_request_id(message: String) -> String {
+    match request_log::current_id() {
+        Some(id) => format!("{message} (request id: {id})"),
+        None => message,
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct PdfApiResponse {
+    run_id: String,
     pdf_path: String,
     html_path: String,
+    signature_path: String,
     summary: String,
+    /// `rules` or `llm`: what wrote the summary and recommendations.
+    narrative: &'static str,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    preset: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    emailed_to: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct SearchApiResponse {
+    query: String,
+    lang: String,
+    total: usize,
+    results: Vec<Mineral>,
+}
+
+#[derive(Debug, Serialize)]
+struct SuggestApiResponse {
+    query: String,
+    lang: String,
+    suggestions: Vec<SuggestApiItem>,
+}
+
+#[derive(Debug, Serialize)]
+struct SuggestApiItem {
+    label: String,
+    kind: SuggestKind,
+    /// Mineral slug, or family slug for `/families/<slug>`.
+    slug: String,
+    url: String,
 }
 
 #[derive(Debug, Deserialize)]
 struct AdminLoginRequest {
     password: String,
+    #[serde(default)]
+    csrf_token: String,
 }
 
 #[derive(Debug, Deserialize)]
-struct PublishMineralRequest {
-    draft_id: String,
+struct ApiTokenCreateRequest {
+    label: String,
+    #[serde(default)]
+    csrf_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdminPasswordRequest {
+    current_password: String,
+    new_password: String,
+    confirm_password: String,
+    #[serde(default)]
+    csrf_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MineralFieldsRequest {
     common_name: String,
     description: String,
     mineral_family: String,
@@ -157,42 +468,111 @@ struct PublishMineralRequest {
     luster: String,
     major_elements_pct_text: String,
     notes: String,
+    #[serde(default)]
+    tags: String,
+    #[serde(default)]
+    references: String,
+    #[serde(default)]
+    extra_attributes: String,
+    #[serde(default)]
+    permalink: String,
+    #[serde(default)]
+    aliases: String,
+    #[serde(default)]
+    locality: String,
+    #[serde(default)]
+    country: String,
+    #[serde(default)]
+    latitude: String,
+    #[serde(default)]
+    longitude: String,
+    #[serde(default, deserialize_with = "models::checkbox")]
+    for_sale: bool,
+    #[serde(default)]
+    price: String,
+    #[serde(default)]
+    currency: String,
+    #[serde(default)]
+    quantity_available: String,
+    #[serde(default, deserialize_with = "models::checkbox")]
+    clean_background: bool,
+    #[serde(default)]
+    visibility: String,
+    #[serde(default)]
+    publish_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PublishMineralRequest {
+    draft_id: String,
+    #[serde(default)]
+    csrf_token: String,
+    #[serde(default)]
+    primary_image: usize,
+    /// Set by **Publish anyway** after likely duplicates were shown.
+    #[serde(default, deserialize_with = "models::checkbox")]
+    publish_anyway: bool,
+    #[serde(flatten)]
+    fields: MineralFieldsRequest,
+    /// `caption_<n>` inputs, one per gallery image.
+    #[serde(flatten)]
+    gallery: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EditMineralRequest {
+    #[serde(default)]
+    csrf_token: String,
+    #[serde(default)]
+    primary_image: usize,
+    #[serde(flatten)]
+    fields: MineralFieldsRequest,
+    #[serde(flatten)]
+    gallery: HashMap<String, String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct DeleteMineralRequest {
     slug: String,
+    #[serde(default)]
+    csrf_token: String,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 struct NewMineralDraft {
-    common_name: String,
-    description: String,
-    mineral_family: String,
-    formula: String,
-    hardness_mohs: f32,
-    density_g_cm3: f32,
-    crystal_system: String,
-    color: String,
-    streak: String,
-    luster: String,
-    notes: String,
-    major_elements_pct: BTreeMap<String, f32>,
-    image_bytes: Vec<u8>,
-    image_ext: String,
+    record: MineralDiskRecord,
+    images: Vec<MineralImage>,
+    captions: Vec<String>,
+    /// Rotation and crop per image, applied before it is stored.
+    edits: Vec<images::PhotoEdit>,
+    primary_image: usize,
+}
+
+#[derive(Debug, Clone)]
+struct MineralImage {
+    bytes: Vec<u8>,
+    ext: String,
 }
 
 #[derive(Debug)]
 struct SuggestInput {
     suggestion_context: String,
-    image_bytes: Vec<u8>,
-    image_ext: String,
+    /// Operator's streak and scratch test results, passed to the LLM.
+    streak_test: String,
+    hardness_test: String,
+    csrf_token: String,
+    /// The first [`SUGGEST_MAX_PHOTOS`] images are sent to the LLM.
+    images: Vec<MineralImage>,
 }
 
-const ADMIN_UPLOAD_MAX_MB: usize = 20;
-const ADMIN_UPLOAD_MAX_BYTES: usize = ADMIN_UPLOAD_MAX_MB * 1024 * 1024;
+const ADMIN_GALLERY_MAX_IMAGES: usize = 8;
+/// Photos analysed per AI suggestion, one LLM request each.
+const SUGGEST_MAX_PHOTOS: usize = 5;
+const SUGGEST_DEFAULT_LIMIT: usize = 8;
+const SUGGEST_MAX_LIMIT: usize = 20;
+const ADMIN_SESSION_MAX_AGE_SECS: i64 = 28800;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 struct AiMineralSuggestion {
     common_name: String,
     description: String,
@@ -206,6 +586,12 @@ struct AiMineralSuggestion {
     luster: String,
     major_elements: Vec<AiMajorElement>,
     notes: String,
+    /// The model's own confidence per field, from 0 to 1.
+    #[serde(default)]
+    confidence: BTreeMap<String, f32>,
+    /// Other identifications the model considered, most likely first.
+    #[serde(default)]
+    alternatives: Vec<AiAlternative>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -227,67 +613,25 @@ struct TranslationStats {
     fallback_lang_codes: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 struct AiMajorElement {
     element: String,
     percent: f32,
 }
 
-#[derive(Debug, Serialize)]
-struct ChatCompletionsRequest {
-    model: String,
-    messages: Vec<ChatMessage>,
-    response_format: ResponseFormat,
-    temperature: f32,
-}
-
-#[derive(Debug, Serialize)]
-struct ChatMessage {
-    role: String,
-    content: Vec<MessagePart>,
-}
-
-#[derive(Debug, Serialize)]
-#[serde(tag = "type")]
-enum MessagePart {
-    #[serde(rename = "text")]
-    Text { text: String },
-    #[serde(rename = "image_url")]
-    ImageUrl { image_url: ImageUrlContent },
-}
-
-#[derive(Debug, Serialize)]
-struct ImageUrlContent {
-    url: String,
-}
-
-#[derive(Debug, Serialize)]
-struct ResponseFormat {
-    #[serde(rename = "type")]
-    kind: String,
-    json_schema: JsonSchemaSpec,
-}
-
-#[derive(Debug, Serialize)]
-struct JsonSchemaSpec {
-    name: String,
-    strict: bool,
-    schema: serde_json::Value,
-}
-
-#[derive(Debug, Deserialize)]
-struct ChatCompletionsResponse {
-    choices: Vec<ChatChoice>,
-}
-
-#[derive(Debug, Deserialize)]
-struct ChatChoice {
-    message: ChatChoiceMessage,
-}
-
-#[derive(Debug, Deserialize)]
-struct ChatChoiceMessage {
-    content: String,
+#[derive(Debug, Clone, Deserialize)]
+struct AiAlternative {
+    common_name: String,
+    description: String,
+    mineral_family: String,
+    formula: String,
+    hardness_mohs: f32,
+    density_g_cm3: f32,
+    crystal_system: String,
+    color: String,
+    streak: String,
+    luster: String,
+    confidence: f32,
 }
 
 #[tokio::main]
@@ -297,101 +641,611 @@ async fn main() -> Result<()> {
 
     let env_filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new("minerals=info,tower_http=info"));
-    tracing_subscriber::fmt().with_env_filter(env_filter).init();
+    // Logs go to stderr so command output on stdout stays machine-readable.
+    let logger = tracing_subscriber::fmt()
+        .with_env_filter(env_filter)
+        .with_writer(std::io::stderr);
+    if std::env::var("LOG_FORMAT").is_ok_and(|format| format.trim() == "json") {
+        logger.json().init();
+    } else {
+        logger.init();
+    }
+
+    let cli = Cli::parse();
+    let config = Config::load()?;
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve(config).await,
+        command => cli::run(command, config).await,
+    }
+}
 
-    let data_root = PathBuf::from("data");
+/// Everything the routes share. The commands that need the catalog, the
+/// PDF pipeline, or the LLM start from the same state as the server.
+async fn build_state(config: &Config) -> Result<AppState> {
+    i18n::load(&config.locales_dir)?;
+    let data_root = config.data_root.clone();
     fs::create_dir_all(data_root.join("minerals"))
         .await
-        .context("failed to create data/minerals directory")?;
-
-    let admin_password = std::env::var("ADMIN_PASSWORD")
-        .context("ADMIN_PASSWORD is required. Set it in .env.local (or env) before starting.")?;
-    if admin_password.trim().is_empty() {
-        return Err(anyhow!("ADMIN_PASSWORD cannot be empty"));
-    }
-
-    let default_language = match std::env::var("DEFAULT_LANG") {
-        Ok(value) => Language::from_code(&value).unwrap_or_else(|| {
-            warn!(
-                "invalid DEFAULT_LANG='{}'; falling back to '{}'",
-                value,
-                Language::En.code()
-            );
-            Language::En
-        }),
-        Err(_) => Language::En,
-    };
-
-    let openai_model = std::env::var("OPENAI_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string());
-    let openai_translation_model =
-        std::env::var("OPENAI_TRANSLATION_MODEL").unwrap_or_else(|_| openai_model.clone());
-
+        .with_context(|| format!("failed to create {}", data_root.join("minerals").display()))?;
+
+    let drafts = DraftStore::new(data_root.join("drafts"));
+    let translation_cache = TranslationCache::new(data_root.join("cache").join("translations"));
+    drafts
+        .prune_stale(chrono::Duration::seconds(ADMIN_SESSION_MAX_AGE_SECS))
+        .await?;
+    staging::prune_stale(&data_root, &data_root.join("minerals")).await?;
+
+    let api_tokens = ApiTokenStore::load(&config.api_tokens_file).await?;
+    let accounts = AccountStore::load(data_root.join("accounts.json")).await?;
+    let family_notes = FamilyNotes::load(data_root.join("families.json")).await?;
+
+    let admin_credential_path = config.admin_credential_file.clone();
+    let admin_credential =
+        load_admin_credential(&admin_credential_path, config.admin_password.clone()).await?;
+
+    let pdf_generator = Arc::new(PdfGenerator::new(
+        data_root.join("minerals"),
+        config.static_root.clone(),
+        config.pdf.backend,
+        config.pdf.chromium_bin.clone(),
+        config.public_url.clone(),
+        config.pdf.retention,
+        ReportSigner::load(config.pdf.signing_key_file.as_deref()).await?,
+    ));
+    let http_client = Client::builder()
+        .build()
+        .context("failed to initialize HTTP client")?;
+    let mailer = Arc::new(Mailer::new(&config.email)?);
+    let llm_provider = config.llm.provider;
+    let llm_usage = Arc::new(
+        UsageLedger::load(
+            data_root.join("llm_usage.json"),
+            config.llm.prices,
+            config.llm.monthly_budget_usd,
+        )
+        .await?
+        .with_alerts(Arc::clone(&mailer)),
+    );
+    let llm = build_provider(
+        llm_provider,
+        http_client.clone(),
+        config.llm.api_key.clone(),
+        config.llm.base_url.clone(),
+    )
+    .map(|provider| {
+        Arc::new(
+            ResilientProvider::new(provider, config.llm.retry).with_ledger(Arc::clone(&llm_usage)),
+        ) as Arc<dyn LlmProvider>
+    });
+    match (&llm, llm_provider.api_key_env()) {
+        (Some(_), _) => info!(
+            "llm provider: {} ({})",
+            llm_provider.as_str(),
+            config.llm.model
+        ),
+        (None, Some(key)) => warn!(
+            "{key} is not set; AI suggestions are disabled and translations fall back to English"
+        ),
+        (None, None) => {}
+    }
+    let inquiry_notifier = Arc::new(InquiryNotifier::new(
+        http_client.clone(),
+        config.inquiries.webhook_url.clone(),
+        Arc::clone(&mailer),
+    ));
+    let publish_notifier = Arc::new(PublishNotifier::new(
+        http_client.clone(),
+        config.publish_webhook_url.clone(),
+    ));
+    let mindat = config.mindat.api_key.clone().map(|api_key| {
+        Arc::new(MindatClient::new(
+            http_client,
+            api_key,
+            config.mindat.base_url.clone(),
+        ))
+    });
     let state = AppState {
         catalogs_by_lang: Arc::new(RwLock::new(HashMap::new())),
-        admin_sessions: Arc::new(Mutex::new(HashSet::new())),
-        admin_drafts: Arc::new(Mutex::new(HashMap::new())),
-        pdf_generator: Arc::new(PdfGenerator::new(data_root.join("minerals"))),
+        // Seeded from the clock so tags from a previous run never match.
+        catalog_generation: Arc::new(AtomicU64::new(
+            chrono::Utc::now().timestamp_millis().unsigned_abs(),
+        )),
+        admin_sessions: Arc::new(Mutex::new(HashMap::new())),
+        drafts: Arc::new(drafts),
+        api_tokens,
+        accounts,
+        inquiry_notifier,
+        publish_notifier,
+        family_notes,
+        pdf_jobs: JobQueue::start(Arc::clone(&pdf_generator), Arc::clone(&mailer)),
+        mailer,
+        pdf_generator: Arc::clone(&pdf_generator),
+        report_templates: ReportTemplates::new(config.pdf.templates_dir.clone()),
+        classification: config.classification,
         data_root: Arc::new(data_root),
-        admin_password: Arc::new(admin_password),
-        openai_api_key: Arc::new(std::env::var("OPENAI_API_KEY").ok()),
-        openai_model: Arc::new(openai_model),
-        openai_translation_model: Arc::new(openai_translation_model),
-        default_language,
-        http_client: Arc::new(
-            Client::builder()
-                .build()
-                .context("failed to initialize HTTP client")?,
-        ),
+        static_root: Arc::new(config.static_root.clone()),
+        htmx: config.static_root.join(HTMX_SCRIPT).is_file(),
+        admin_credential: Arc::new(RwLock::new(admin_credential)),
+        admin_credential_path: Arc::new(admin_credential_path),
+        llm,
+        llm_usage,
+        mindat,
+        llm_model: Arc::new(config.llm.model.clone()),
+        llm_translation_model: Arc::new(config.llm.translation_model.clone()),
+        llm_timeout: config.llm.timeout,
+        llm_translation_timeout: config.llm.translation_timeout,
+        llm_translation_concurrency: config.llm.translation_concurrency,
+        llm_translation_deadline: config.llm.translation_deadline,
+        translation_cache,
+        translation_batch: TranslationBatch::default(),
+        translation_interval: config.translate_batch_interval,
+        default_language: config.default_language,
+        metrics: telemetry::install()?,
+        readyz_check_llm: config.readyz_check_llm,
+        graphql: graphql::schema(),
+        graphql_playground: config.graphql_playground,
+        image_upload_max_mb: config.image_upload_max_mb,
+        image_convert_bin: config.image_convert_bin.clone(),
+        background_removal_bin: config.background_removal_bin.clone(),
+        trash_retention_days: config.trash_retention_days,
+        search_fuzzy_distance: config.search_fuzzy_distance,
+        secure_cookies: config.public_url.starts_with("https://"),
     };
+    Ok(state)
+}
+
+/// Images and the PDF, archive, and spreadsheet downloads are compressed
+/// already.
+fn compressible() -> impl Predicate {
+    DefaultPredicate::new()
+        .and(NotForContentType::const_new("application/pdf"))
+        .and(NotForContentType::const_new("application/zip"))
+        .and(NotForContentType::const_new("application/zstd"))
+        .and(NotForContentType::const_new(
+            "application/vnd.openxmlformats",
+        ))
+}
+
+async fn serve(config: Config) -> Result<()> {
+    let state = build_state(&config).await?;
+    assets::init(&state.static_root);
+    let pdf_generator = Arc::clone(&state.pdf_generator);
+
+    let prewarm_state = state.clone();
+    tokio::spawn(async move { prewarm_catalogs(&prewarm_state).await });
+
+    let backfill_state = state.clone();
+    tokio::spawn(async move {
+        let minerals_root = backfill_state.data_root.join("minerals");
+        match tokio::task::spawn_blocking(move || images::backfill_variants(&minerals_root)).await {
+            Ok(0) => {}
+            Ok(updated) => {
+                info!("generated image variants for {updated} minerals");
+                if let Err(err) = reload_catalog(&backfill_state) {
+                    warn!("failed to reload catalog after image backfill: {err:#}");
+                }
+            }
+            Err(err) => warn!("image backfill task failed: {err}"),
+        }
+    });
+
+    tokio::spawn(trash::purge_periodically(
+        state.data_root.to_path_buf(),
+        state.trash_retention_days,
+    ));
+    tokio::spawn(schedule::publish_periodically(state.clone()));
+
+    if let Err(err) = watcher::spawn(state.clone(), config.catalog_watch_debounce) {
+        warn!("catalog hot reload disabled: {err:#}");
+    }
+    #[cfg(debug_assertions)]
+    if config.locales_dir.is_dir() {
+        if let Err(err) =
+            watcher::spawn_locales(config.locales_dir.clone(), config.catalog_watch_debounce)
+        {
+            warn!("locale hot reload disabled: {err:#}");
+        }
+    }
+
+    let auth_limit = middleware::from_fn_with_state(
+        RateLimiter::new("auth", config.auth_rate_limit),
+        rate_limit::enforce,
+    );
+    let llm_limit = middleware::from_fn_with_state(
+        RateLimiter::new("llm", config.llm_rate_limit),
+        rate_limit::enforce,
+    );
+    let inquiry_limit = middleware::from_fn_with_state(
+        RateLimiter::new("inquiry", config.inquiry_rate_limit),
+        rate_limit::enforce,
+    );
 
+    // Base64 inflates images by 4/3; leave headroom for the JSON fields.
+    let api_body_max_bytes = state.image_upload_max_bytes() / 3 * 4 + 64 * 1024;
     let app = Router::new()
         .route("/", get(home_page))
         .route("/language", post(set_language))
-        .route("/minerals", get(index))
+        .route("/units", post(set_units))
+        .route(
+            "/minerals",
+            get(index).layer(middleware::from_fn_with_state(
+                state.clone(),
+                etag::catalog_pages,
+            )),
+        )
+        .route(
+            "/minerals/tag/:tag",
+            get(tag_page).layer(middleware::from_fn_with_state(
+                state.clone(),
+                etag::catalog_pages,
+            )),
+        )
+        .route(
+            "/shop",
+            get(shop::shop_page).layer(middleware::from_fn_with_state(
+                state.clone(),
+                etag::catalog_pages,
+            )),
+        )
+        .route(
+            "/elements/:symbol",
+            get(elements::element_page).layer(middleware::from_fn_with_state(
+                state.clone(),
+                etag::catalog_pages,
+            )),
+        )
+        .route("/api/elements/:symbol", get(elements::element_api))
+        .route("/families", get(families::families_page))
+        .route("/families/:family", get(families::family_page))
+        .route("/minerals/search", get(search_page))
+        .route("/api/minerals/search", get(search_api))
+        .route("/api/minerals/suggest", get(suggest_api))
+        .route("/api/minerals/export.csv", get(export::export_csv))
+        .route("/api/minerals/export.xlsx", get(export::export_xlsx))
+        .route("/api/catalog", get(catalog_api::list_minerals))
+        .route(
+            "/api/minerals",
+            get(api::list_minerals)
+                .post(api::create_mineral.layer(llm_limit.clone()))
+                .layer(DefaultBodyLimit::max(api_body_max_bytes)),
+        )
+        .route(
+            "/api/minerals/:slug",
+            get(api::get_mineral)
+                .put(api::update_mineral.layer(llm_limit.clone()))
+                .delete(api::delete_mineral)
+                .layer(DefaultBodyLimit::max(api_body_max_bytes)),
+        )
+        .route("/graphql", get(graphql::playground).post(graphql::execute))
+        .route("/metrics", get(telemetry::metrics_page))
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz))
         .route("/about", get(about_page))
         .route("/pages/:slug", get(info_page))
-        .route("/minerals/:slug", get(mineral_page))
-        .route("/minerals/:slug/pdf", post(generate_pdf_form))
-        .route("/api/minerals/:slug/pdf", post(generate_pdf_api))
+        .route("/m/:name", get(permalinks::name_page))
+        .route("/account", get(accounts::account_page))
+        .route(
+            "/account/register",
+            post(accounts::register.layer(auth_limit.clone())),
+        )
+        .route(
+            "/account/login",
+            post(accounts::login.layer(auth_limit.clone())),
+        )
+        .route("/account/logout", post(accounts::logout))
+        .route("/account/favorites/:slug", post(accounts::toggle_favorite))
+        .route("/api/me/favorites", get(accounts::api_favorites))
+        .route(
+            "/minerals/:slug",
+            get(mineral_page).layer(middleware::from_fn_with_state(
+                state.clone(),
+                etag::catalog_pages,
+            )),
+        )
+        .route(
+            "/minerals/:slug/inquiry",
+            post(inquiries::submit_inquiry.layer(inquiry_limit)),
+        )
+        .route(
+            "/minerals/:slug/specimens",
+            get(specimens::specimens_page).post(specimens::create_specimen),
+        )
+        .route(
+            "/minerals/:slug/specimens/:id",
+            post(specimens::update_specimen),
+        )
+        .route(
+            "/minerals/:slug/specimens/:id/delete",
+            post(specimens::delete_specimen),
+        )
+        .route(
+            "/minerals/:slug/custody",
+            get(custody::custody_page).post(custody::append_custody_event),
+        )
+        .route("/minerals/:slug/notes", post(discussion::post_note))
+        .route(
+            "/minerals/:slug/spectra",
+            get(spectra::spectra_page).post(spectra::upload_spectrum.layer(DefaultBodyLimit::max(
+                spectra::SPECTRUM_UPLOAD_MAX_BYTES + 64 * 1024,
+            ))),
+        )
+        .route(
+            "/minerals/:slug/spectra/:id/delete",
+            post(spectra::delete_spectrum),
+        )
+        .route("/minerals/:slug/history", get(history::history_page))
+        .route(
+            "/minerals/:slug/history/:version",
+            get(history::history_version_page),
+        )
+        .route(
+            "/minerals/:slug/history/:version/restore",
+            post(history::restore_version),
+        )
+        .route(
+            "/minerals/:slug/pdf",
+            post(generate_pdf_form.layer(llm_limit.clone())),
+        )
+        .route("/minerals/:slug/report-output", get(report_output_fragment))
+        .route(
+            "/api/minerals/:slug/pdf",
+            post(generate_pdf_api.layer(llm_limit.clone())),
+        )
+        .route(
+            "/api/minerals/:slug/pdf/jobs",
+            post(submit_pdf_job_api.layer(llm_limit.clone())),
+        )
+        .route(
+            "/api/minerals/:slug/certificate",
+            post(certificates::issue_certificate),
+        )
+        .route("/api/jobs/:id", get(pdf_job_status_api))
+        .route("/api/jobs/:id/:artifact", get(pdf_job_artifact))
+        .route("/api/reports/batch", post(batch::batch_reports))
+        .route(
+            "/api/reports/compare",
+            post(comparison::compare_minerals.layer(llm_limit.clone())),
+        )
+        .route("/api/reports/:slug/verify", get(signing::verify_report))
+        .route(
+            "/api/certificates/:slug/verify",
+            get(signing::verify_certificate),
+        )
         .route("/admin", get(admin_page))
-        .route("/admin/login", post(admin_login))
+        .route("/admin/login", post(admin_login.layer(auth_limit.clone())))
         .route("/admin/logout", post(admin_logout))
+        .route(
+            "/admin/password",
+            post(admin_change_password.layer(auth_limit)),
+        )
+        .route("/admin/api-tokens", post(admin_create_api_token))
+        .route("/admin/backup", post(backup::download_backup))
+        .route(
+            "/admin/translate-missing",
+            get(admin_translation_progress).post(admin_translate_missing),
+        )
+        .route("/admin/translations", get(translations::translations_page))
+        .route("/admin/inquiries", get(inquiries::inquiries_page))
+        .route(
+            "/admin/inquiries/:id/order",
+            post(orders::create_from_inquiry),
+        )
+        .route("/admin/orders", get(orders::orders_page))
+        .route("/admin/families/:family", post(families::save_description))
+        .route("/admin/presets", post(presets::save_preset))
+        .route("/admin/presets/:name/delete", post(presets::delete_preset))
+        .route("/admin/trash", get(trash::trash_page))
+        .route("/admin/trash/:id/restore", post(trash::restore_entry))
+        .route("/admin/trash/:id/purge", post(trash::purge_entry))
+        .route("/admin/orders/:id/status", post(orders::change_status))
+        .route("/orders/:token", get(orders::order_status_page))
+        .route(
+            "/admin/inquiries/:id/handled",
+            post(inquiries::mark_handled),
+        )
+        .route(
+            "/admin/translations/:folder/:lang",
+            post(translations::retranslate_language.layer(llm_limit.clone())),
+        )
+        .route("/admin/api-tokens/:id/revoke", post(admin_revoke_api_token))
         .route(
             "/admin/minerals/suggest",
-            post(admin_suggest_mineral).layer(DefaultBodyLimit::max(ADMIN_UPLOAD_MAX_BYTES)),
+            post(admin_suggest_mineral.layer(llm_limit.clone())).layer(DefaultBodyLimit::max(
+                state.image_upload_max_bytes() * ADMIN_GALLERY_MAX_IMAGES,
+            )),
+        )
+        .route(
+            "/admin/minerals/publish",
+            post(admin_publish_mineral.layer(llm_limit.clone())),
+        )
+        .route(
+            "/admin/minerals/import",
+            post(import::import_minerals)
+                .layer(DefaultBodyLimit::max(import::IMPORT_BODY_MAX_BYTES)),
+        )
+        .route(
+            "/admin/chemistry/composition",
+            get(admin_formula_composition),
         )
-        .route("/admin/minerals/publish", post(admin_publish_mineral))
+        .route("/admin/mindat/lookup", get(mindat::lookup_reference))
+        .route("/admin/drafts/:id", get(admin_resume_draft))
+        .route("/admin/drafts/:id/discard", post(admin_discard_draft))
+        .route("/admin/drafts/:id/preview", post(admin_preview_draft))
+        .route("/admin/drafts/:id/autosave", post(admin_autosave_draft))
         .route("/admin/minerals/delete", post(admin_delete_mineral))
-        .nest_service("/static", ServeDir::new("static"))
-        .nest_service("/data", ServeDir::new("data"))
+        .route(
+            "/admin/minerals/:slug/edit",
+            get(admin_edit_mineral_page).post(admin_edit_mineral.layer(llm_limit)),
+        )
+        .route("/admin/minerals/:slug/for-sale", post(shop::set_for_sale))
+        .route(
+            "/admin/minerals/:slug/delete",
+            post(admin_delete_mineral_by_slug),
+        )
+        .nest_service(
+            "/static",
+            get_service(ServeDir::new(state.static_root.as_path()))
+                .layer(middleware::from_fn(assets::cache_headers)),
+        )
+        .nest_service(
+            "/data/minerals",
+            get_service(ServeDir::new(state.data_root.join("minerals")))
+                .layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    etag::data_files,
+                ))
+                .layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    visibility::data_files,
+                )),
+        )
+        .nest_service(
+            "/data/comparisons",
+            ServeDir::new(state.data_root.join(report_runs::COMPARISONS_DIR)),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            permalinks::redirect_aliases,
+        ))
+        .route_layer(middleware::from_fn(telemetry::track_requests))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            request_log::log_requests,
+        ))
+        .layer(middleware::from_fn_with_state(
+            Arc::new(config.trusted_proxies.clone()),
+            client_ip::resolve_client_ip,
+        ))
+        .layer(CompressionLayer::new().compress_when(compressible()))
         .with_state(state);
+    let app = middleware::from_fn(lang_path::strip_prefix).layer(app);
 
-    let port: u16 = std::env::var("PORT")
-        .ok()
-        .and_then(|value| value.parse().ok())
-        .unwrap_or(7979);
-
-    let address = SocketAddr::from(([0, 0, 0, 0], port));
+    let address = SocketAddr::from(([0, 0, 0, 0], config.port));
     let listener = TcpListener::bind(address)
         .await
         .with_context(|| format!("failed to bind to {address}"))?;
+    let rustls = match &config.tls {
+        Some(tls) => Some(tls::load(tls).await?),
+        None => None,
+    };
+    if let Some(http_port) = config.tls.as_ref().and_then(|tls| tls.http_port) {
+        let http_address = SocketAddr::from(([0, 0, 0, 0], http_port));
+        let http_listener = TcpListener::bind(http_address)
+            .await
+            .with_context(|| format!("failed to bind to {http_address}"))?;
+        let redirect = tls::redirect_app(
+            config.public_url.clone(),
+            config
+                .tls
+                .as_ref()
+                .and_then(|tls| tls.acme_webroot.as_deref()),
+        );
+        info!("redirecting http://{http_address} to {}", config.public_url);
+        tokio::spawn(async move {
+            if let Err(err) = axum::serve(http_listener, redirect).await {
+                warn!("HTTP redirect listener failed: {err}");
+            }
+        });
+    }
 
-    info!("minerals server listening on http://{address}");
-    axum::serve(listener, app)
-        .await
-        .context("server failed unexpectedly")?;
+    let shutdown_grace = config.shutdown_grace;
+    let (stop_sender, stop_receiver) = tokio::sync::oneshot::channel::<()>();
+    let drain = tokio::spawn(async move {
+        shutdown_signal().await;
+        info!(
+            "shutdown requested; waiting up to {}s for in-flight PDF runs",
+            shutdown_grace.as_secs()
+        );
+        let _ = stop_sender.send(());
+        pdf_generator.drain(shutdown_grace).await;
+    });
+
+    // The rate limiters key on the peer address.
+    match (rustls, &config.tls) {
+        (Some(rustls), Some(tls)) => {
+            tokio::spawn(tls::reload_periodically(
+                rustls.clone(),
+                tls.cert_file.clone(),
+                tls.key_file.clone(),
+            ));
+            let handle = axum_server::Handle::new();
+            let shutdown = handle.clone();
+            tokio::spawn(async move {
+                let _ = stop_receiver.await;
+                // Open connections get the same grace as PDF runs, then are cut.
+                shutdown.graceful_shutdown(Some(shutdown_grace));
+            });
+            // axum-server hands over hyper's body type; the router takes axum's.
+            let app = tower::ServiceExt::map_request(app, |request: axum::http::Request<_>| {
+                request.map(axum::body::Body::new)
+            });
+            info!("minerals server listening on https://{address}");
+            axum_server::from_tcp_rustls(listener.into_std()?, rustls)
+                .handle(handle)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                .await
+                .context("server failed unexpectedly")?;
+        }
+        _ => {
+            info!("minerals server listening on http://{address}");
+            axum::serve(
+                listener,
+                ServiceExt::<axum::extract::Request>::into_make_service_with_connect_info::<
+                    SocketAddr,
+                >(app),
+            )
+            .with_graceful_shutdown(async {
+                let _ = stop_receiver.await;
+            })
+            .await
+            .context("server failed unexpectedly")?;
+        }
+    }
+    // Queued background jobs are not HTTP requests; wait for them too.
+    if let Err(err) = drain.await {
+        warn!("PDF drain task failed: {err}");
+    }
+    info!("shutdown complete");
 
     Ok(())
 }
 
-async fn home_page(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-) -> TemplateResponse<HomeTemplate> {
-    let language = resolve_language(&state, &headers);
-
-    TemplateResponse(HomeTemplate {
-        lang_code: language.code().to_string(),
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            warn!("failed to listen for ctrl-c: {err}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                warn!("failed to listen for SIGTERM: {err}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+async fn home_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> TemplateResponse<HomeTemplate> {
+    let language = resolve_language(&state, &headers);
+
+    TemplateResponse(HomeTemplate {
+        lang_code: language.code().to_string(),
         lang_dir: language.dir().to_string(),
         txt: ui_text(language),
         language_options: language_options(),
@@ -415,18 +1269,218 @@ async fn set_language(
     Ok(response)
 }
 
+async fn set_units(Form(request): Form<UnitsSelectionRequest>) -> Result<Response, AppError> {
+    let selected = UnitSystem::from_code(&request.units).unwrap_or_default();
+    let back = Some(request.return_to.as_str())
+        .filter(|path| path.starts_with('/') && !path.starts_with("//") && !path.contains('\\'))
+        .unwrap_or("/");
+    let mut response = Redirect::to(back).into_response();
+    append_set_cookie(
+        &mut response,
+        &format!(
+            "{}={}; Path=/; SameSite=Lax; Max-Age=31536000",
+            units::COOKIE,
+            selected.code()
+        ),
+    )?;
+    Ok(response)
+}
+
 async fn index(
     State(state): State<AppState>,
     headers: HeaderMap,
+    Query(params): Query<IndexParams>,
+    Query(filters): Query<CatalogFilters>,
 ) -> Result<TemplateResponse<IndexTemplate>, AppError> {
+    catalog_listing(&state, &headers, &params, filters, None).await
+}
+
+/// `/minerals/tag/:tag`: the catalog listing narrowed to one tag.
+async fn tag_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(tag): AxumPath<String>,
+    Query(params): Query<IndexParams>,
+    Query(filters): Query<CatalogFilters>,
+) -> Result<TemplateResponse<IndexTemplate>, AppError> {
+    let tag = normalize_tag(&tag).ok_or_else(|| AppError::NotFound(format!("tag '{tag}'")))?;
+    catalog_listing(&state, &headers, &params, filters, Some(tag)).await
+}
+
+async fn catalog_listing(
+    state: &AppState,
+    headers: &HeaderMap,
+    params: &IndexParams,
+    filters: CatalogFilters,
+    tag: Option<String>,
+) -> Result<TemplateResponse<IndexTemplate>, AppError> {
+    let language = resolve_language(state, headers);
+    let sort = params
+        .sort
+        .as_deref()
+        .and_then(SortKey::from_param)
+        .unwrap_or_default();
+    let catalog = visible_catalog(state, headers, language).await?;
+    let page_number = params.page.unwrap_or(1);
+    let page_size = params.page_size.unwrap_or(DEFAULT_PAGE_SIZE);
+    let query = CatalogQuery {
+        tag: tag.clone(),
+        ..filters.to_query().map_err(AppError::BadRequest)?
+    };
+    let page = catalog.page(&query, sort, page_number, page_size);
+
+    let txt = ui_text(language);
+
+    Ok(TemplateResponse(IndexTemplate {
+        list_path: match tag.as_deref() {
+            Some(tag) => format!("/minerals/tag/{tag}"),
+            None => "/minerals".to_string(),
+        },
+        tags: catalog.tags.clone(),
+        active_tag: tag,
+        family_options: catalog.families.clone(),
+        crystal_system_options: catalog.crystal_systems.clone(),
+        filter_query: filters.query_string(),
+        filters,
+        lang_code: language.code().to_string(),
+        lang_dir: language.dir().to_string(),
+        locale: LocaleFormat::new(language),
+        sort_options: SortKey::all()
+            .iter()
+            .map(|key| SelectOption {
+                value: key.as_str(),
+                label: sort_label(&txt, *key),
+                selected: *key == sort,
+            })
+            .collect(),
+        txt,
+        minerals: page.minerals,
+        search_query: String::new(),
+        total_count: page.total,
+        page: page.page,
+        page_size: page.page_size,
+        total_pages: page.total_pages,
+        sort_key: sort.as_str(),
+    }))
+}
+
+fn sort_label(txt: &UiText, key: SortKey) -> &'static str {
+    match key {
+        SortKey::Name => txt.nav_current_mineral,
+        SortKey::Hardness => txt.label_hardness,
+        SortKey::Density => txt.label_density,
+        SortKey::Family => txt.label_family,
+    }
+}
+
+async fn search_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<SearchParams>,
+) -> Result<Response, AppError> {
+    let query = params.q.trim().to_string();
+    if query.is_empty() {
+        return Ok(Redirect::to("/minerals").into_response());
+    }
+
     let language = resolve_language(&state, &headers);
-    let minerals = catalog_for_language(&state, language)?.ordered;
+    let minerals = visible_catalog(&state, &headers, language)
+        .await?
+        .search(&query, state.search_fuzzy_distance);
 
     Ok(TemplateResponse(IndexTemplate {
         lang_code: language.code().to_string(),
         lang_dir: language.dir().to_string(),
         txt: ui_text(language),
+        locale: LocaleFormat::new(language),
+        total_count: minerals.len(),
+        page: 1,
+        page_size: minerals.len().max(1),
+        total_pages: 1,
         minerals,
+        search_query: query,
+        sort_key: SortKey::Name.as_str(),
+        sort_options: Vec::new(),
+        list_path: "/minerals".to_string(),
+        tags: Vec::new(),
+        active_tag: None,
+        filters: CatalogFilters::default(),
+        filter_query: String::new(),
+        family_options: Vec::new(),
+        crystal_system_options: Vec::new(),
+    })
+    .into_response())
+}
+
+async fn search_api(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<SearchApiResponse>, AppError> {
+    let query = params.q.trim().to_string();
+    if query.is_empty() {
+        return Err(AppError::BadRequest(
+            "query parameter 'q' is required".to_string(),
+        ));
+    }
+
+    let language = resolve_language(&state, &headers);
+    let results = visible_catalog(&state, &headers, language)
+        .await?
+        .search(&query, state.search_fuzzy_distance);
+
+    Ok(Json(SearchApiResponse {
+        query,
+        lang: language.code().to_string(),
+        total: results.len(),
+        results,
+    }))
+}
+
+/// Typeahead for the catalog search box. A blank query gets no
+/// suggestions rather than an error, since it is what an emptied box sends.
+async fn suggest_api(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<SuggestParams>,
+) -> Result<Json<SuggestApiResponse>, AppError> {
+    let query = params.q.trim().to_string();
+    let limit = params
+        .limit
+        .unwrap_or(SUGGEST_DEFAULT_LIMIT)
+        .clamp(1, SUGGEST_MAX_LIMIT);
+    let language = resolve_language(&state, &headers);
+    let catalog = visible_catalog(&state, &headers, language).await?;
+    let english = catalog_for_language(&state, Language::En).await?;
+
+    let suggestions = catalog
+        .suggest(&query, limit, state.search_fuzzy_distance)
+        .into_iter()
+        .filter_map(|suggestion| {
+            let (slug, path) = match suggestion.kind {
+                SuggestKind::Mineral => {
+                    let path = format!("/minerals/{}", suggestion.slug);
+                    (suggestion.slug, path)
+                }
+                SuggestKind::Family => {
+                    let slug = families::family_slug(&english, &suggestion.slug)?;
+                    let path = format!("/families/{slug}");
+                    (slug, path)
+                }
+            };
+            Some(SuggestApiItem {
+                label: suggestion.label,
+                kind: suggestion.kind,
+                slug,
+                url: lang_path::prefixed(&headers, &path),
+            })
+        })
+        .collect();
+
+    Ok(Json(SuggestApiResponse {
+        query,
+        lang: language.code().to_string(),
+        suggestions,
     }))
 }
 
@@ -463,91 +1517,478 @@ async fn mineral_page(
     State(state): State<AppState>,
     headers: HeaderMap,
     AxumPath(slug): AxumPath<String>,
+    Query(params): Query<MineralPageParams>,
 ) -> Result<TemplateResponse<MineralTemplate>, AppError> {
     let language = resolve_language(&state, &headers);
-    let mineral = get_mineral(&state, language, &slug)?;
-    let request = default_report_request(language);
-    let report = run_agentic_chain(&mineral, &request, language);
+    let page = mineral_report_state(&state, &headers, language, &slug, &params).await?;
+    let english = catalog_for_language(&state, Language::En).await?;
+    let family_slug = families::family_slug(&english, &page.mineral.slug);
+    let share_url = english.name_slug_of(&page.mineral.slug).map(|name| {
+        state
+            .pdf_generator
+            .public_url(&lang_path::prefixed(&headers, &format!("/m/{name}")))
+    });
+    let report_templates = state.report_templates.names().await;
+    let selected_backend = page
+        .job
+        .as_ref()
+        .and_then(|job| PdfBackendKind::from_code(job.backend))
+        .unwrap_or_else(|| state.pdf_generator.default_backend());
+    let output = report_output(&headers, language, &page);
+    let units = resolve_units(&headers);
+    let has_admin_session = has_admin_session(&state, &headers);
+    let admin_notes = if has_admin_session {
+        discussion::for_mineral(&state, &page.mineral).await?
+    } else {
+        Vec::new()
+    };
 
     Ok(TemplateResponse(MineralTemplate {
         lang_code: language.code().to_string(),
         lang_dir: language.dir().to_string(),
-        txt: ui_text(language),
-        mineral,
-        request,
-        report,
-        generated_pdf_path: None,
-        generated_html_path: None,
-        generation_error: None,
+        txt: units.relabel(&ui_text(language)),
+        locale: LocaleFormat::new(language),
+        units,
+        page_path: lang_path::prefixed(&headers, &format!("/minerals/{slug}")),
+        mineral: page.mineral,
+        report_units: UnitSystem::from_code(&page.request.units).unwrap_or(units),
+        request: page.request,
+        report: page.report,
+        pdf_backend_options: PdfBackendKind::all()
+            .iter()
+            .map(|kind| SelectOption {
+                value: kind.as_str(),
+                label: kind.label(),
+                selected: *kind == selected_backend,
+            })
+            .collect(),
+        report_templates,
+        presets: presets::names(&state).await,
+        has_admin_session,
+        admin_notes,
+        csrf_token: csrf::session_token(&state, &headers).unwrap_or_default(),
+        output,
+        htmx: state.htmx,
+        llm_narrative_available: state.llm.is_some() && has_admin_session,
+        favorite: accounts::favorite_button(&state, &headers, &slug),
+        share_url,
+        family_slug,
+        inquiry_sent: params.inquiry.as_deref() == Some("sent"),
     }))
 }
 
-async fn generate_pdf_form(
+/// `GET /minerals/:slug/report-output?job=<id>`: the report builder's
+/// output panel alone, which HTMX polls while a job runs.
+async fn report_output_fragment(
     State(state): State<AppState>,
     headers: HeaderMap,
     AxumPath(slug): AxumPath<String>,
-    Form(request): Form<ReportRequest>,
-) -> Result<TemplateResponse<MineralTemplate>, AppError> {
+    Query(params): Query<MineralPageParams>,
+) -> Result<TemplateResponse<ReportOutputTemplate>, AppError> {
     let language = resolve_language(&state, &headers);
-    let mineral = get_mineral(&state, language, &slug)?;
-    let report = run_agentic_chain(&mineral, &request, language);
+    let page = mineral_report_state(&state, &headers, language, &slug, &params).await?;
+    Ok(TemplateResponse(report_output(&headers, language, &page)))
+}
 
-    let (artifacts, generation_error): (Option<GeneratedArtifacts>, Option<String>) =
-        match state.pdf_generator.generate_pdf(&report, language).await {
-            Ok(paths) => (Some(paths), None),
-            Err(err) => (None, Some(err.to_string())),
-        };
+/// A mineral with its derived report, for the request of one of its PDF
+/// jobs, a saved preset, or the language's default request.
+struct MineralReportState {
+    mineral: Mineral,
+    job: Option<PdfJob>,
+    request: ReportRequest,
+    report: MineralReport,
+    report_runs: Vec<ReportRun>,
+}
 
-    Ok(TemplateResponse(MineralTemplate {
-        lang_code: language.code().to_string(),
-        lang_dir: language.dir().to_string(),
-        txt: ui_text(language),
+async fn mineral_report_state(
+    state: &AppState,
+    headers: &HeaderMap,
+    language: Language,
+    slug: &str,
+    params: &MineralPageParams,
+) -> Result<MineralReportState, AppError> {
+    let (mineral, related) = get_mineral_with_related(state, language, slug).await?;
+    require_visible(state, headers, slug, &mineral)?;
+    let attachments = load_attachments(state, &mineral).await?;
+    let report_runs =
+        report_runs::for_mineral(state, &mineral, LocaleFormat::new(language)).await?;
+    let job = match params.job.as_deref() {
+        Some(id) => state
+            .pdf_jobs
+            .get(id)?
+            .filter(|job| job.slug == mineral.slug),
+        None => None,
+    };
+    // A preset deleted since the link was made falls back to the default.
+    let preset = match (&job, params.preset.as_deref()) {
+        (None, Some(name)) => presets::load(state, name).await?,
+        _ => None,
+    };
+    let request = job
+        .as_ref()
+        .map(|job| job.request.clone())
+        .or(preset)
+        .unwrap_or_else(|| default_report_request(language));
+    let report = run_agentic_chain(
+        &mineral,
+        &request,
+        related,
+        attachments,
+        state.classification,
+        language,
+    );
+    Ok(MineralReportState {
         mineral,
+        job,
         request,
         report,
-        generated_pdf_path: artifacts.as_ref().map(|value| value.pdf_path.clone()),
-        generated_html_path: artifacts.as_ref().map(|value| value.html_path.clone()),
-        generation_error,
-    }))
+        report_runs,
+    })
+}
+
+fn report_output(
+    headers: &HeaderMap,
+    language: Language,
+    page: &MineralReportState,
+) -> ReportOutputTemplate {
+    let job = page.job.as_ref();
+    let pending = job.is_some_and(|job| !job.status.is_finished());
+    ReportOutputTemplate {
+        txt: ui_text(language),
+        pdf_job_pending: pending,
+        poll_url: job.filter(|_| pending).map(|job| {
+            lang_path::prefixed(
+                headers,
+                &format!(
+                    "/minerals/{}/report-output?job={}",
+                    page.mineral.slug, job.id
+                ),
+            )
+        }),
+        generated_pdf_path: job.and_then(|job| job.pdf_path.clone()),
+        generated_html_path: job.and_then(|job| job.html_path.clone()),
+        generation_error: job.and_then(|job| job.error.clone()),
+        report_runs: page.report_runs.clone(),
+        summary: page.report.summary.clone(),
+        recommendations: page.report.recommendations.clone(),
+    }
+}
+
+/// Full-page form posts are redirected to the mineral page; HTMX posts
+/// (`HX-Request`) get the output panel for the new job, and the job's page
+/// URL in `HX-Push-Url` so a reload shows the same state.
+async fn generate_pdf_form(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+    Form(request): Form<PdfReportRequest>,
+) -> Result<Response, AppError> {
+    let job = submit_pdf_job(&state, &headers, &slug, &request).await?;
+    let page_url = lang_path::prefixed(&headers, &format!("/minerals/{slug}?job={}", job.id));
+    if !headers.contains_key("hx-request") {
+        return Ok(Redirect::to(&page_url).into_response());
+    }
+
+    let language = resolve_language(&state, &headers);
+    let page = mineral_report_state(
+        &state,
+        &headers,
+        language,
+        &slug,
+        &MineralPageParams {
+            job: Some(job.id.clone()),
+            ..MineralPageParams::default()
+        },
+    )
+    .await?;
+    let mut response = TemplateResponse(report_output(&headers, language, &page)).into_response();
+    if let Ok(value) = HeaderValue::from_str(&page_url) {
+        response.headers_mut().insert("hx-push-url", value);
+    }
+    Ok(response)
+}
+
+async fn submit_pdf_job_api(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+    Query(params): Query<GenerationParams>,
+    Json(request): Json<PdfReportRequest>,
+) -> Result<(StatusCode, Json<PdfJob>), AppError> {
+    let request = PdfReportRequest {
+        standalone: params.standalone,
+        ..request
+    };
+    let job = submit_pdf_job(&state, &headers, &slug, &request).await?;
+    Ok((StatusCode::ACCEPTED, Json(job_with_links(&headers, job))))
+}
+
+async fn pdf_job_status_api(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(id): AxumPath<String>,
+) -> Result<Json<PdfJob>, AppError> {
+    Ok(Json(job_with_links(&headers, find_pdf_job(&state, &id)?)))
+}
+
+async fn pdf_job_artifact(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath((id, artifact)): AxumPath<(String, String)>,
+) -> Result<Redirect, AppError> {
+    let job = job_with_links(&headers, find_pdf_job(&state, &id)?);
+    if job.status != JobStatus::Done {
+        return Err(AppError::BadRequest(format!(
+            "PDF job '{id}' has no artifacts (status: {:?}).",
+            job.status
+        )));
+    }
+
+    let path = match artifact.as_str() {
+        "pdf" => job.pdf_path,
+        "html" => job.html_path,
+        "sig" => job.signature_path,
+        _ => {
+            return Err(AppError::NotFound(format!(
+                "Unknown job artifact '{artifact}'. Use 'pdf', 'html', or 'sig'."
+            )))
+        }
+    };
+    path.map(|path| Redirect::to(&path))
+        .ok_or_else(|| AppError::NotFound(format!("PDF job '{id}' has no {artifact} artifact.")))
+}
+
+async fn submit_pdf_job(
+    state: &AppState,
+    headers: &HeaderMap,
+    slug: &str,
+    request: &PdfReportRequest,
+) -> Result<PdfJob, AppError> {
+    let language = resolve_report_language(state, headers, &request.report)?;
+    let units = resolve_report_units(headers, &request.report.units)?;
+    let backend = resolve_pdf_backend(state, request.backend.as_deref())?;
+    let email_to = report_email_to(state, headers, request)?;
+    narrative::authorize(state, headers, &request.report)?;
+    let layout = state
+        .report_templates
+        .load(&request.report.template, backend)
+        .await
+        .map_err(AppError::BadRequest)?
+        .with_standalone(request.standalone);
+    presets::check(state, &request.report).await?;
+    let (mineral, related) = get_mineral_with_related(state, language, slug).await?;
+    require_visible(state, headers, slug, &mineral)?;
+    let attachments = load_attachments(state, &mineral).await?;
+    let mut report = run_agentic_chain(
+        &mineral,
+        &request.report,
+        related,
+        attachments,
+        state.classification,
+        language,
+    );
+    report.units = units;
+    narrative::apply(state, &request.report, &mut report, language).await;
+    let id = crypto::random_hex(12)?;
+    Ok(state
+        .pdf_jobs
+        .submit(id, report, layout, language, backend, email_to)?)
+}
+
+/// The request's `email_to`, checked. Mailing a report takes an API token,
+/// so the site cannot be used to send mail to strangers.
+fn report_email_to(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &PdfReportRequest,
+) -> Result<Option<String>, AppError> {
+    let Some(to) = request
+        .email_to
+        .as_deref()
+        .map(str::trim)
+        .filter(|to| !to.is_empty())
+    else {
+        return Ok(None);
+    };
+    api::require_api_token(state, headers)?;
+    email::parse_addresses(to).map_err(|err| AppError::BadRequest(format!("{err:#}")))?;
+    Ok(Some(to.to_string()))
+}
+
+fn resolve_pdf_backend(
+    state: &AppState,
+    requested: Option<&str>,
+) -> Result<PdfBackendKind, AppError> {
+    match requested.map(str::trim).filter(|value| !value.is_empty()) {
+        Some(value) => PdfBackendKind::from_code(value).ok_or_else(|| {
+            let known = PdfBackendKind::all()
+                .iter()
+                .map(|kind| kind.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            AppError::BadRequest(format!(
+                "Unknown PDF backend '{value}'. Use one of: {known}."
+            ))
+        }),
+        None => Ok(state.pdf_generator.default_backend()),
+    }
+}
+
+/// Artifact links under the language prefix the request came in with.
+fn job_with_links(headers: &HeaderMap, mut job: PdfJob) -> PdfJob {
+    for path in [
+        &mut job.pdf_path,
+        &mut job.html_path,
+        &mut job.signature_path,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        *path = lang_path::prefixed(headers, path);
+    }
+    job
+}
+
+fn find_pdf_job(state: &AppState, id: &str) -> Result<PdfJob, AppError> {
+    state
+        .pdf_jobs
+        .get(id)?
+        .ok_or_else(|| AppError::NotFound(format!("No PDF job with id '{id}'.")))
 }
 
 async fn generate_pdf_api(
     State(state): State<AppState>,
     headers: HeaderMap,
     AxumPath(slug): AxumPath<String>,
-    Json(request): Json<ReportRequest>,
+    Query(params): Query<GenerationParams>,
+    Json(request): Json<PdfReportRequest>,
 ) -> Result<Json<PdfApiResponse>, AppError> {
-    let language = resolve_language(&state, &headers);
-    let mineral = get_mineral(&state, language, &slug)?;
-    let report = run_agentic_chain(&mineral, &request, language);
+    let request = PdfReportRequest {
+        standalone: params.standalone,
+        ..request
+    };
+    let language = resolve_report_language(&state, &headers, &request.report)?;
+    let units = resolve_report_units(&headers, &request.report.units)?;
+    let email_to = report_email_to(&state, &headers, &request)?;
+    narrative::authorize(&state, &headers, &request.report)?;
+    require_visible(
+        &state,
+        &headers,
+        &slug,
+        &get_mineral(&state, language, &slug).await?,
+    )?;
+    let response = generate_report(&state, language, units, &slug, &request, email_to).await?;
+    Ok(Json(PdfApiResponse {
+        pdf_path: lang_path::prefixed(&headers, &response.pdf_path),
+        html_path: lang_path::prefixed(&headers, &response.html_path),
+        signature_path: lang_path::prefixed(&headers, &response.signature_path),
+        ..response
+    }))
+}
+
+/// Renders one mineral's report in the calling task and mails it to
+/// `email_to`, already checked; the artifact paths carry no language
+/// prefix. Shared by `POST /api/minerals/:slug/pdf` and
+/// `minerals generate-report`.
+async fn generate_report(
+    state: &AppState,
+    language: Language,
+    units: UnitSystem,
+    slug: &str,
+    request: &PdfReportRequest,
+    email_to: Option<String>,
+) -> Result<PdfApiResponse, AppError> {
+    let backend = resolve_pdf_backend(state, request.backend.as_deref())?;
+    let layout = state
+        .report_templates
+        .load(&request.report.template, backend)
+        .await
+        .map_err(AppError::BadRequest)?
+        .with_standalone(request.standalone);
+    presets::check(state, &request.report).await?;
+    let (mineral, related) = get_mineral_with_related(state, language, slug).await?;
+    let attachments = load_attachments(state, &mineral).await?;
+    let mut report = run_agentic_chain(
+        &mineral,
+        &request.report,
+        related,
+        attachments,
+        state.classification,
+        language,
+    );
+    report.units = units;
+    narrative::apply(state, &request.report, &mut report, language).await;
     let artifacts = state
         .pdf_generator
-        .generate_pdf(&report, language)
+        .generate_pdf(&report, &layout, language, backend)
         .await
         .with_context(|| format!("failed to generate pdf for slug '{slug}'"))?;
+    if let Some(to) = &email_to {
+        state
+            .mailer
+            .send_report(
+                to,
+                &format!("{} report", mineral.common_name),
+                &artifacts,
+                &state.pdf_generator.public_url(&artifacts.pdf_path),
+            )
+            .await
+            .with_context(|| format!("failed to mail the report for '{slug}' to {to}"))?;
+    }
 
-    Ok(Json(PdfApiResponse {
+    Ok(PdfApiResponse {
+        run_id: artifacts.run_id,
         pdf_path: artifacts.pdf_path,
         html_path: artifacts.html_path,
+        signature_path: artifacts.signature_path,
         summary: report.summary,
-    }))
+        narrative: report.narrative.as_str(),
+        preset: report.preset,
+        emailed_to: email_to,
+    })
 }
 
 async fn admin_page(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> TemplateResponse<AdminTemplate> {
+) -> Result<Response, AppError> {
     let language = resolve_language(&state, &headers);
-    TemplateResponse(AdminTemplate {
-        lang_code: language.code().to_string(),
-        lang_dir: language.dir().to_string(),
-        txt: ui_text(language),
-        has_admin_session: has_admin_session(&state, &headers),
-        error_message: None,
-        success_message: None,
-        draft_form: MineralFormData::default(),
-        has_suggestion: false,
-        admin_minerals: admin_minerals_for_ui(&state, language),
+    if has_admin_session(&state, &headers) {
+        let template = admin_session_template(&state, language, &headers).await;
+        let template = restore_autosaved_draft(&state, &headers, template).await?;
+        let csrf_token = template.csrf_token.clone();
+        let mut response = TemplateResponse(template).into_response();
+        csrf::expose(&mut response, &csrf_token);
+        Ok(response)
+    } else {
+        login_form_response(admin_template(&state, language, false).await)
+    }
+}
+
+/// Reopens the draft the operator was last editing, with the fields as they
+/// were autosaved.
+async fn restore_autosaved_draft(
+    state: &AppState,
+    headers: &HeaderMap,
+    template: AdminTemplate,
+) -> Result<AdminTemplate, AppError> {
+    let Ok(token) = require_admin_token(state, headers) else {
+        return Ok(template);
+    };
+    let Some(draft_id) = state.drafts.latest_autosave(&token).await else {
+        return Ok(template);
+    };
+    let Some(draft) = state.drafts.load(&token, &draft_id).await? else {
+        return Ok(template);
+    };
+    Ok(AdminTemplate {
+        success_message: Some("Restored your unsaved edits to a draft.".to_string()),
+        draft_form: MineralFormData {
+            preview_images: draft.images.iter().map(image_data_url).collect(),
+            ..draft.form
+        },
+        has_suggestion: true,
+        ..template
     })
 }
 
@@ -557,44 +1998,33 @@ async fn admin_login(
     Form(request): Form<AdminLoginRequest>,
 ) -> Result<Response, AppError> {
     let language = resolve_language(&state, &headers);
-    if request.password != *state.admin_password {
-        return Ok(TemplateResponse(AdminTemplate {
-            lang_code: language.code().to_string(),
-            lang_dir: language.dir().to_string(),
-            txt: ui_text(language),
-            has_admin_session: false,
+    csrf::verify_login(&headers, &request.csrf_token)?;
+    if !verify_admin_password(&state, request.password).await? {
+        return login_form_response(AdminTemplate {
             error_message: Some("Invalid admin password.".to_string()),
-            success_message: None,
-            draft_form: MineralFormData::default(),
-            has_suggestion: false,
-            admin_minerals: admin_minerals_for_ui(&state, language),
-        })
-        .into_response());
+            ..admin_template(&state, language, false).await
+        });
     }
 
-    let token = generate_secure_hex(24)?;
+    let token = crypto::random_hex(24)?;
+    let csrf_token = crypto::random_hex(24)?;
     {
         let mut sessions = state
             .admin_sessions
             .lock()
             .map_err(|_| anyhow!("admin session store lock poisoned"))?;
-        sessions.insert(token.clone());
+        sessions.insert(token.clone(), csrf_token.clone());
     }
 
     let mut response = TemplateResponse(AdminTemplate {
-        lang_code: language.code().to_string(),
-        lang_dir: language.dir().to_string(),
-        txt: ui_text(language),
-        has_admin_session: true,
-        error_message: None,
         success_message: Some("Admin session created.".to_string()),
-        draft_form: MineralFormData::default(),
-        has_suggestion: false,
-        admin_minerals: admin_minerals_for_ui(&state, language),
+        csrf_token: csrf_token.clone(),
+        ..admin_session_template(&state, language, &headers).await
     })
     .into_response();
+    csrf::expose(&mut response, &csrf_token);
 
-    let cookie = format!("admin_session={token}; HttpOnly; Path=/; SameSite=Lax; Max-Age=28800");
+    let cookie = state.session_cookie("admin_session", &token, ADMIN_SESSION_MAX_AGE_SECS);
     append_set_cookie(&mut response, &cookie)?;
     Ok(response)
 }
@@ -602,9 +2032,11 @@ async fn admin_login(
 async fn admin_logout(
     State(state): State<AppState>,
     headers: HeaderMap,
+    form: Option<Form<CsrfForm>>,
 ) -> Result<Response, AppError> {
     let language = resolve_language(&state, &headers);
-    if let Some(token) = admin_token_from_headers(&headers) {
+    if let Ok(token) = require_admin_token(&state, &headers) {
+        csrf::verify(&state, &headers, &csrf::form_token(form))?;
         {
             let mut sessions = state
                 .admin_sessions
@@ -612,33 +2044,275 @@ async fn admin_logout(
                 .map_err(|_| anyhow!("admin session store lock poisoned"))?;
             sessions.remove(&token);
         }
-        {
-            let mut drafts = state
-                .admin_drafts
-                .lock()
-                .map_err(|_| anyhow!("admin draft store lock poisoned"))?;
-            drafts.clear();
-        }
+        state.drafts.discard_session(&token).await?;
     }
 
-    let mut response = TemplateResponse(AdminTemplate {
-        lang_code: language.code().to_string(),
-        lang_dir: language.dir().to_string(),
-        txt: ui_text(language),
-        has_admin_session: false,
-        error_message: None,
+    let mut response = login_form_response(AdminTemplate {
         success_message: Some("Admin session closed.".to_string()),
-        draft_form: MineralFormData::default(),
-        has_suggestion: false,
-        admin_minerals: admin_minerals_for_ui(&state, language),
-    })
-    .into_response();
+        ..admin_template(&state, language, false).await
+    })?;
+
+    append_set_cookie(&mut response, &state.session_cookie("admin_session", "", 0))?;
+    Ok(response)
+}
+
+async fn admin_change_password(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(request): Form<AdminPasswordRequest>,
+) -> Result<TemplateResponse<AdminTemplate>, AppError> {
+    let language = resolve_language(&state, &headers);
+    csrf::verify(&state, &headers, &request.csrf_token)?;
+
+    let rejection = if request.new_password != request.confirm_password {
+        Some("New password and confirmation do not match.".to_string())
+    } else if request.new_password.chars().count() < MIN_ADMIN_PASSWORD_LEN {
+        Some(format!(
+            "New password must be at least {MIN_ADMIN_PASSWORD_LEN} characters."
+        ))
+    } else if !verify_admin_password(&state, request.current_password).await? {
+        Some("Current password is incorrect.".to_string())
+    } else {
+        None
+    };
+    if let Some(message) = rejection {
+        return Ok(TemplateResponse(AdminTemplate {
+            error_message: Some(message),
+            ..admin_session_template(&state, language, &headers).await
+        }));
+    }
+
+    let credential = tokio::task::spawn_blocking(move || hash_password(&request.new_password))
+        .await
+        .context("password hashing task failed")??;
+    store_admin_credential(&state.admin_credential_path, &credential).await?;
+    {
+        let mut stored = state
+            .admin_credential
+            .write()
+            .map_err(|_| anyhow!("admin credential lock poisoned"))?;
+        *stored = credential;
+    }
+
+    let current_token = admin_token_from_headers(&headers);
+    {
+        let mut sessions = state
+            .admin_sessions
+            .lock()
+            .map_err(|_| anyhow!("admin session store lock poisoned"))?;
+        sessions.retain(|token, _| Some(token) == current_token.as_ref());
+    }
+    info!("admin password rotated");
+
+    Ok(TemplateResponse(AdminTemplate {
+        success_message: Some(
+            "Admin password updated. Other admin sessions were signed out.".to_string(),
+        ),
+        ..admin_session_template(&state, language, &headers).await
+    }))
+}
+
+async fn admin_create_api_token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(request): Form<ApiTokenCreateRequest>,
+) -> Result<TemplateResponse<AdminTemplate>, AppError> {
+    let language = resolve_language(&state, &headers);
+    csrf::verify(&state, &headers, &request.csrf_token)?;
+
+    let label = match required_string(&request.label, "label") {
+        Ok(label) => label,
+        Err(err) => {
+            return Ok(TemplateResponse(AdminTemplate {
+                error_message: Some(err.to_string()),
+                ..admin_session_template(&state, language, &headers).await
+            }));
+        }
+    };
+    let id = crypto::random_hex(6)?;
+    let token = state
+        .api_tokens
+        .create(&id, &label, &crypto::random_hex(24)?)
+        .await?;
+    info!("api token '{label}' created");
+
+    Ok(TemplateResponse(AdminTemplate {
+        success_message: Some(format!(
+            "API token '{label}' created. Copy it now; it will not be shown again."
+        )),
+        new_api_token: Some(token),
+        ..admin_session_template(&state, language, &headers).await
+    }))
+}
+
+async fn admin_revoke_api_token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(id): AxumPath<String>,
+    form: Option<Form<CsrfForm>>,
+) -> Result<TemplateResponse<AdminTemplate>, AppError> {
+    let language = resolve_language(&state, &headers);
+    csrf::verify(&state, &headers, &csrf::form_token(form))?;
+
+    let template = if state.api_tokens.revoke(&id).await? {
+        info!("api token {id} revoked");
+        AdminTemplate {
+            success_message: Some("API token revoked.".to_string()),
+            ..admin_session_template(&state, language, &headers).await
+        }
+    } else {
+        AdminTemplate {
+            error_message: Some(format!("API token not found: {id}")),
+            ..admin_session_template(&state, language, &headers).await
+        }
+    };
+    Ok(TemplateResponse(template))
+}
+
+async fn admin_translate_missing(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    form: Option<Form<CsrfForm>>,
+) -> Result<TemplateResponse<AdminTemplate>, AppError> {
+    let language = resolve_language(&state, &headers);
+    csrf::verify(&state, &headers, &csrf::form_token(form))?;
+
+    if state.llm.is_none() {
+        return Ok(TemplateResponse(AdminTemplate {
+            error_message: Some(
+                "No LLM provider is configured; set LLM_PROVIDER and its API key first."
+                    .to_string(),
+            ),
+            ..admin_session_template(&state, language, &headers).await
+        }));
+    }
+
+    let pending = find_missing_translations(&state.data_root.join("minerals")).await?;
+    let file_count: usize = pending.iter().map(|item| item.languages.len()).sum();
+    let template = if pending.is_empty() {
+        AdminTemplate {
+            success_message: Some("Every mineral already has all translations.".to_string()),
+            ..admin_session_template(&state, language, &headers).await
+        }
+    } else if state.translation_batch.start(
+        state.clone(),
+        pending.clone(),
+        state.translation_interval,
+    )? {
+        info!(
+            "batch translation started: {file_count} files across {} minerals",
+            pending.len()
+        );
+        AdminTemplate {
+            success_message: Some(format!(
+                "Translating {file_count} missing files across {} minerals in the background.",
+                pending.len()
+            )),
+            ..admin_session_template(&state, language, &headers).await
+        }
+    } else {
+        AdminTemplate {
+            error_message: Some("A translation batch is already running.".to_string()),
+            ..admin_session_template(&state, language, &headers).await
+        }
+    };
+    Ok(TemplateResponse(template))
+}
+
+async fn admin_translation_progress(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Option<BatchProgress>>, AppError> {
+    require_admin_token(&state, &headers)?;
+    Ok(Json(state.translation_batch.snapshot()))
+}
+
+#[derive(Debug, Deserialize)]
+struct FormulaQuery {
+    formula: String,
+}
+
+#[derive(Debug, Serialize)]
+struct FormulaComposition {
+    major_elements_pct: BTreeMap<String, f32>,
+    /// Ready for the admin form's element textarea.
+    major_elements_pct_text: String,
+}
+
+/// Theoretical wt% for the admin form's "Compute from formula" button.
+async fn admin_formula_composition(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<FormulaQuery>,
+) -> Result<Json<FormulaComposition>, AppError> {
+    require_admin_token(&state, &headers)?;
+    let major_elements_pct =
+        chemistry::weight_percentages(&query.formula).map_err(AppError::BadRequest)?;
+    Ok(Json(FormulaComposition {
+        major_elements_pct_text: major_elements_to_text(&major_elements_pct),
+        major_elements_pct,
+    }))
+}
+
+async fn verify_admin_password(state: &AppState, password: String) -> Result<bool, AppError> {
+    let credential = state
+        .admin_credential
+        .read()
+        .map_err(|_| anyhow!("admin credential lock poisoned"))?
+        .clone();
+    let verified = tokio::task::spawn_blocking(move || verify_password(&password, &credential))
+        .await
+        .context("password verification task failed")?;
+    Ok(verified)
+}
+
+async fn admin_resume_draft(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(draft_id): AxumPath<String>,
+) -> Result<TemplateResponse<AdminTemplate>, AppError> {
+    let language = resolve_language(&state, &headers);
+    let token = require_admin_token(&state, &headers)?;
+
+    let Some(draft) = state.drafts.load(&token, &draft_id).await? else {
+        return Ok(TemplateResponse(AdminTemplate {
+            error_message: Some(format!("Draft not found: {draft_id}")),
+            ..admin_session_template(&state, language, &headers).await
+        }));
+    };
+
+    Ok(TemplateResponse(AdminTemplate {
+        success_message: Some("Draft resumed. Review and publish.".to_string()),
+        draft_form: MineralFormData {
+            preview_images: draft.images.iter().map(image_data_url).collect(),
+            ..draft.form
+        },
+        has_suggestion: true,
+        ..admin_session_template(&state, language, &headers).await
+    }))
+}
+
+async fn admin_discard_draft(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(draft_id): AxumPath<String>,
+    form: Option<Form<CsrfForm>>,
+) -> Result<TemplateResponse<AdminTemplate>, AppError> {
+    let language = resolve_language(&state, &headers);
+    let token = require_admin_token(&state, &headers)?;
+    csrf::verify(&state, &headers, &csrf::form_token(form))?;
 
-    append_set_cookie(
-        &mut response,
-        "admin_session=; HttpOnly; Path=/; SameSite=Lax; Max-Age=0",
-    )?;
-    Ok(response)
+    if !state.drafts.discard(&token, &draft_id).await? {
+        return Ok(TemplateResponse(AdminTemplate {
+            error_message: Some(format!("Draft not found: {draft_id}")),
+            ..admin_session_template(&state, language, &headers).await
+        }));
+    }
+
+    Ok(TemplateResponse(AdminTemplate {
+        success_message: Some("Draft discarded.".to_string()),
+        ..admin_session_template(&state, language, &headers).await
+    }))
 }
 
 async fn admin_suggest_mineral(
@@ -647,62 +2321,44 @@ async fn admin_suggest_mineral(
     mut multipart: Multipart,
 ) -> Result<TemplateResponse<AdminTemplate>, AppError> {
     let language = resolve_language(&state, &headers);
-    if !has_admin_session(&state, &headers) {
-        return Err(AppError::Unauthorized(
-            "Admin session required. Log in at /admin.".to_string(),
-        ));
-    }
+    let token = require_admin_token(&state, &headers)?;
 
-    let input = parse_suggest_multipart(&mut multipart).await?;
+    let input = parse_suggest_multipart(&state, &mut multipart).await?;
+    csrf::verify(&state, &headers, &input.csrf_token)?;
 
-    let suggestion = match request_openai_suggestion(&state, &input).await {
-        Ok(suggestion) => suggestion,
+    let (suggestion, field_confidence) = match request_ai_suggestion(&state, &input).await {
+        Ok(merged) => merged,
         Err(err) => {
             error!("admin ai suggestion failed: {err}");
             return Ok(TemplateResponse(AdminTemplate {
-                lang_code: language.code().to_string(),
-                lang_dir: language.dir().to_string(),
-                txt: ui_text(language),
-                has_admin_session: true,
                 error_message: Some(format!("AI suggestion failed: {err}")),
-                success_message: None,
                 draft_form: MineralFormData {
                     suggestion_context: input.suggestion_context,
+                    streak_test: input.streak_test,
+                    hardness_test: input.hardness_test,
                     ..MineralFormData::default()
                 },
-                has_suggestion: false,
-                admin_minerals: admin_minerals_for_ui(&state, language),
+                ..admin_session_template(&state, language, &headers).await
             }));
         }
     };
 
-    let preview_image_data_url = format!(
-        "data:{};base64,{}",
-        content_type_from_ext(&input.image_ext),
-        BASE64.encode(&input.image_bytes)
-    );
-
-    let draft_id = generate_secure_hex(12)?;
-    {
-        let mut drafts = state
-            .admin_drafts
-            .lock()
-            .map_err(|_| anyhow!("admin draft store lock poisoned"))?;
-        drafts.insert(
-            draft_id.clone(),
-            AdminDraft {
-                image_bytes: input.image_bytes,
-                image_ext: input.image_ext,
-            },
-        );
-    }
+    let preview_images = input.images.iter().map(image_data_url).collect();
+    let draft_id = crypto::random_hex(12)?;
+    let candidates = consensus::candidates(&suggestion);
 
     let form = MineralFormData {
-        draft_id: Some(draft_id),
+        draft_id: Some(draft_id.clone()),
         common_name: suggestion.common_name,
         description: suggestion.description,
         suggestion_context: input.suggestion_context,
-        preview_image_data_url,
+        streak_test: input.streak_test,
+        hardness_test: input.hardness_test,
+        field_confidence,
+        candidates,
+        preview_images,
+        image_captions: vec![String::new(); input.images.len()],
+        primary_image: 0,
         mineral_family: suggestion.mineral_family,
         formula: suggestion.formula,
         hardness_mohs: format!("{:.2}", suggestion.hardness_mohs),
@@ -715,91 +2371,202 @@ async fn admin_suggest_mineral(
             suggestion.major_elements,
         )),
         notes: suggestion.notes,
+        tags_text: String::new(),
+        references_text: String::new(),
+        locality: String::new(),
+        country: String::new(),
+        latitude: String::new(),
+        longitude: String::new(),
+        ..MineralFormData::default()
     };
+    state
+        .drafts
+        .create(&token, &draft_id, &input.images, &form)
+        .await?;
 
     Ok(TemplateResponse(AdminTemplate {
-        lang_code: language.code().to_string(),
-        lang_dir: language.dir().to_string(),
-        txt: ui_text(language),
-        has_admin_session: true,
-        error_message: None,
-        success_message: Some("AI suggestion generated. Review and publish.".to_string()),
+        success_message: Some(
+            "AI suggestion generated and saved as a draft. Review and publish.".to_string(),
+        ),
         draft_form: form,
         has_suggestion: true,
-        admin_minerals: admin_minerals_for_ui(&state, language),
+        ..admin_session_template(&state, language, &headers).await
     }))
 }
 
-async fn admin_publish_mineral(
+/// `POST /admin/drafts/:id/autosave`: the publish form's fields as typed so
+/// far, sent by the admin page while the operator edits. Photos stay as
+/// uploaded; only their captions, edits, and the primary choice are stored.
+async fn admin_autosave_draft(
     State(state): State<AppState>,
     headers: HeaderMap,
+    AxumPath(draft_id): AxumPath<String>,
     Form(request): Form<PublishMineralRequest>,
-) -> Result<TemplateResponse<AdminTemplate>, AppError> {
-    let language = resolve_language(&state, &headers);
-    if !has_admin_session(&state, &headers) {
-        return Err(AppError::Unauthorized(
-            "Admin session required. Log in at /admin.".to_string(),
-        ));
+) -> Result<StatusCode, AppError> {
+    let token = require_admin_token(&state, &headers)?;
+    csrf::verify(&state, &headers, &request.csrf_token)?;
+
+    let caption_count = request
+        .gallery
+        .keys()
+        .filter(|key| key.starts_with("caption_"))
+        .count();
+    let form = MineralFormData {
+        image_captions: gallery_captions(&request.gallery, caption_count),
+        image_rotations: gallery_inputs(&request.gallery, "rotate", caption_count),
+        image_crops: gallery_inputs(&request.gallery, "crop", caption_count),
+        primary_image: request.primary_image,
+        ..form_from_fields(&request.fields)
+    };
+    if !state.drafts.autosave(&token, &draft_id, &form).await? {
+        return Err(AppError::NotFound(format!("draft {draft_id} not found")));
     }
+    Ok(StatusCode::NO_CONTENT)
+}
 
-    let image_draft = {
-        let drafts = state
-            .admin_drafts
-            .lock()
-            .map_err(|_| anyhow!("admin draft store lock poisoned"))?;
-        drafts.get(&request.draft_id).cloned().ok_or_else(|| {
-            AppError::BadRequest("draft session not found; run AI suggestion again".to_string())
-        })?
-    };
+/// `POST /admin/drafts/:id/preview`: the report the draft would get once
+/// published, from the publish form's current fields and the draft's
+/// photos. The page is rendered in English, the language drafts are
+/// written in, and nothing is written to disk.
+async fn admin_preview_draft(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(draft_id): AxumPath<String>,
+    Form(request): Form<PublishMineralRequest>,
+) -> Result<Html<String>, AppError> {
+    let token = require_admin_token(&state, &headers)?;
+    csrf::verify(&state, &headers, &request.csrf_token)?;
+    let draft = state
+        .drafts
+        .load(&token, &draft_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("draft {draft_id} not found")))?;
+    let record = parse_mineral_fields(&request.fields)?;
+
+    let captions = gallery_captions(&request.gallery, draft.images.len());
+    let primary = request
+        .primary_image
+        .min(draft.images.len().saturating_sub(1));
+    let images = draft
+        .images
+        .iter()
+        .zip(captions)
+        .enumerate()
+        .map(|(index, (image, caption))| GalleryImage {
+            path: image_data_url(image),
+            thumbnail_path: None,
+            medium_path: None,
+            caption,
+            primary: index == primary,
+        })
+        .collect();
+    // The folder is named on publish; until then the draft id stands in.
+    let mineral = mineral_from_record(&format!("draft-{draft_id}"), record, images);
+
+    let language = Language::En;
+    let catalog = catalog_for_language(&state, language).await?;
+    let related = similarity::related(&mineral, catalog.listed(), RELATED_LIMIT);
+    let report = run_agentic_chain(
+        &mineral,
+        &default_report_request(language),
+        related,
+        MineralAttachments::default(),
+        state.classification,
+        language,
+    );
+    Ok(Html(
+        state.pdf_generator.preview_html(&report, language).await?,
+    ))
+}
 
+async fn admin_publish_mineral(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(request): Form<PublishMineralRequest>,
+) -> Result<TemplateResponse<AdminTemplate>, AppError> {
+    let language = resolve_language(&state, &headers);
+    let token = require_admin_token(&state, &headers)?;
+    csrf::verify(&state, &headers, &request.csrf_token)?;
+
+    let image_draft = state
+        .drafts
+        .load(&token, &request.draft_id)
+        .await?
+        .ok_or_else(|| {
+            AppError::BadRequest("draft not found; run AI suggestion again".to_string())
+        })?;
+
+    let captions = gallery_captions(&request.gallery, image_draft.images.len());
     let form = MineralFormData {
         draft_id: Some(request.draft_id.clone()),
-        common_name: request.common_name.clone(),
-        description: request.description.clone(),
-        suggestion_context: String::new(),
-        preview_image_data_url: format!(
-            "data:{};base64,{}",
-            content_type_from_ext(&image_draft.image_ext),
-            BASE64.encode(&image_draft.image_bytes)
-        ),
-        mineral_family: request.mineral_family.clone(),
-        formula: request.formula.clone(),
-        hardness_mohs: request.hardness_mohs.clone(),
-        density_g_cm3: request.density_g_cm3.clone(),
-        crystal_system: request.crystal_system.clone(),
-        color: request.color.clone(),
-        streak: request.streak.clone(),
-        luster: request.luster.clone(),
-        major_elements_pct_text: request.major_elements_pct_text.clone(),
-        notes: request.notes.clone(),
+        preview_images: image_draft.images.iter().map(image_data_url).collect(),
+        image_captions: captions.clone(),
+        image_rotations: gallery_inputs(&request.gallery, "rotate", image_draft.images.len()),
+        image_crops: gallery_inputs(&request.gallery, "crop", image_draft.images.len()),
+        primary_image: request.primary_image,
+        ..form_from_fields(&request.fields).with_suggestion_of(&image_draft.form)
     };
 
-    let parsed_draft = match parse_publish_request(&request, image_draft) {
+    let parsed = match parse_mineral_fields(&request.fields) {
+        Ok(record) => check_slugs_available(&state, &record, None)
+            .await
+            .map(|()| record),
+        Err(err) => Err(err),
+    };
+    let parsed = parsed.and_then(|record| Ok((record, photo_edits(&form)?)));
+    let (record, edits) = match parsed {
         Ok(value) => value,
         Err(err) => {
+            state
+                .drafts
+                .save_form(&token, &request.draft_id, &form)
+                .await?;
             return Ok(TemplateResponse(AdminTemplate {
-                lang_code: language.code().to_string(),
-                lang_dir: language.dir().to_string(),
-                txt: ui_text(language),
-                has_admin_session: true,
                 error_message: Some(err.to_string()),
-                success_message: None,
                 draft_form: form,
                 has_suggestion: true,
-                admin_minerals: admin_minerals_for_ui(&state, language),
+                ..admin_session_template(&state, language, &headers).await
+            }));
+        }
+    };
+    if !request.publish_anyway {
+        let duplicates = duplicates::find(
+            &catalog_for_language(&state, Language::En).await?.ordered,
+            &record,
+        );
+        if !duplicates.is_empty() {
+            state
+                .drafts
+                .save_form(&token, &request.draft_id, &form)
+                .await?;
+            return Ok(TemplateResponse(AdminTemplate {
+                error_message: Some(
+                    "This mineral may already be in the catalog. Check the matches below, \
+                     then use Publish anyway if it is a different record."
+                        .to_string(),
+                ),
+                draft_form: form,
+                has_suggestion: true,
+                duplicates,
+                ..admin_session_template(&state, language, &headers).await
             }));
         }
+    }
+    let parsed_draft = NewMineralDraft {
+        record,
+        images: image_draft.images,
+        captions,
+        edits,
+        primary_image: request.primary_image,
     };
 
+    let clean_background = parsed_draft.record.clean_background;
     let (folder_name, translation_stats) = create_mineral_folder(&state, parsed_draft).await?;
-    {
-        let mut drafts = state
-            .admin_drafts
-            .lock()
-            .map_err(|_| anyhow!("admin draft store lock poisoned"))?;
-        drafts.remove(&request.draft_id);
+    state.drafts.discard(&token, &request.draft_id).await?;
+    refresh_mineral(&state, &folder_name).await?;
+    if clean_background {
+        background::spawn_refresh(state.clone(), folder_name.clone());
     }
-    reload_catalog(&state)?;
 
     let mut success_message = format!(
         "Mineral published: {}. Localized files: {} translated.",
@@ -811,22 +2578,15 @@ async fn admin_publish_mineral(
     }
 
     Ok(TemplateResponse(AdminTemplate {
-        lang_code: language.code().to_string(),
-        lang_dir: language.dir().to_string(),
-        txt: ui_text(language),
-        has_admin_session: true,
-        error_message: None,
         success_message: Some(success_message),
-        draft_form: MineralFormData::default(),
-        has_suggestion: false,
-        admin_minerals: admin_minerals_for_ui(&state, language),
+        ..admin_session_template(&state, language, &headers).await
     }))
 }
 
-async fn admin_delete_mineral(
+async fn admin_edit_mineral_page(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Form(request): Form<DeleteMineralRequest>,
+    AxumPath(slug): AxumPath<String>,
 ) -> Result<TemplateResponse<AdminTemplate>, AppError> {
     let language = resolve_language(&state, &headers);
     if !has_admin_session(&state, &headers) {
@@ -835,124 +2595,349 @@ async fn admin_delete_mineral(
         ));
     }
 
-    let slug = required_string(&request.slug, "slug")?;
-    let mineral = match catalog_for_language(&state, language)?
-        .by_slug
-        .get(&slug)
-        .cloned()
-    {
-        Some(value) => value,
-        None => {
+    let folder_path = match mineral_folder_for_slug(&state, language, &slug).await {
+        Ok(path) => path,
+        Err(message) => {
             return Ok(TemplateResponse(AdminTemplate {
-                lang_code: language.code().to_string(),
-                lang_dir: language.dir().to_string(),
-                txt: ui_text(language),
-                has_admin_session: true,
-                error_message: Some(format!("mineral '{slug}' not found")),
-                success_message: None,
-                draft_form: MineralFormData::default(),
-                has_suggestion: false,
-                admin_minerals: admin_minerals_for_ui(&state, language),
+                error_message: Some(message),
+                ..admin_session_template(&state, language, &headers).await
             }));
         }
     };
-    let folder_name = mineral.folder_name;
-    if !is_valid_mineral_folder_name(&folder_name) {
-        return Ok(TemplateResponse(AdminTemplate {
-            lang_code: language.code().to_string(),
-            lang_dir: language.dir().to_string(),
-            txt: ui_text(language),
-            has_admin_session: true,
-            error_message: Some(format!("invalid mineral folder name: {folder_name}")),
-            success_message: None,
-            draft_form: MineralFormData::default(),
-            has_suggestion: false,
-            admin_minerals: admin_minerals_for_ui(&state, language),
-        }));
-    }
+    let english = read_english_record(&folder_path).await?;
+    let folder_name = folder_file_name(&folder_path);
+
+    Ok(TemplateResponse(AdminTemplate {
+        draft_form: MineralFormData {
+            preview_images: english
+                .images
+                .iter()
+                .map(|image| format!("/data/minerals/{folder_name}/{}", image.file))
+                .collect(),
+            ..MineralFormData::from_record(&english)
+        },
+        has_suggestion: true,
+        edit_slug: Some(slug),
+        ..admin_session_template(&state, language, &headers).await
+    }))
+}
 
-    let folder_path = state.data_root.join("minerals").join(&folder_name);
+async fn admin_edit_mineral(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+    Form(request): Form<EditMineralRequest>,
+) -> Result<TemplateResponse<AdminTemplate>, AppError> {
+    let language = resolve_language(&state, &headers);
+    csrf::verify(&state, &headers, &request.csrf_token)?;
 
-    let metadata = match fs::metadata(&folder_path).await {
-        Ok(metadata) => metadata,
-        Err(_) => {
+    let folder_path = match mineral_folder_for_slug(&state, language, &slug).await {
+        Ok(path) => path,
+        Err(message) => {
             return Ok(TemplateResponse(AdminTemplate {
-                lang_code: language.code().to_string(),
-                lang_dir: language.dir().to_string(),
-                txt: ui_text(language),
-                has_admin_session: true,
-                error_message: Some(format!("mineral folder not found: {folder_name}")),
-                success_message: None,
-                draft_form: MineralFormData::default(),
-                has_suggestion: false,
-                admin_minerals: admin_minerals_for_ui(&state, language),
+                error_message: Some(message),
+                ..admin_session_template(&state, language, &headers).await
             }));
         }
     };
-    if !metadata.is_dir() {
-        return Ok(TemplateResponse(AdminTemplate {
-            lang_code: language.code().to_string(),
-            lang_dir: language.dir().to_string(),
-            txt: ui_text(language),
-            has_admin_session: true,
-            error_message: Some(format!("mineral path is not a directory: {folder_name}")),
-            success_message: None,
-            draft_form: MineralFormData::default(),
-            has_suggestion: false,
-            admin_minerals: admin_minerals_for_ui(&state, language),
-        }));
+    let previous = read_english_record(&folder_path).await?;
+    let folder_name = folder_file_name(&folder_path);
+
+    let captions = gallery_captions(&request.gallery, previous.images.len());
+    let parsed = match parse_mineral_fields(&request.fields) {
+        Ok(mut record) => {
+            permalinks::carry_over(&previous, &mut record);
+            check_slugs_available(&state, &record, Some(&folder_name))
+                .await
+                .map(|()| record)
+        }
+        Err(err) => Err(err),
+    };
+    let mut updated = match parsed {
+        Ok(value) => value,
+        Err(err) => {
+            return Ok(TemplateResponse(AdminTemplate {
+                error_message: Some(err.to_string()),
+                draft_form: MineralFormData {
+                    preview_images: previous
+                        .images
+                        .iter()
+                        .map(|image| format!("/data/minerals/{folder_name}/{}", image.file))
+                        .collect(),
+                    image_captions: captions,
+                    primary_image: request.primary_image,
+                    ..form_from_fields(&request.fields)
+                },
+                has_suggestion: true,
+                edit_slug: Some(slug),
+                ..admin_session_template(&state, language, &headers).await
+            }));
+        }
+    };
+    updated.images = edited_images(&previous.images, captions, request.primary_image);
+    updated = updated.normalize_images();
+
+    let translation_stats =
+        update_mineral_folder(&state, &folder_path, &previous, &updated).await?;
+    refresh_mineral(&state, &folder_name).await?;
+    if background::needs_refresh(&previous, &updated) {
+        background::spawn_refresh(state.clone(), folder_name.clone());
+    }
+
+    let mut success_message = format!(
+        "Mineral updated: {}. Localized files: {} translated.",
+        slug, translation_stats.translated_count
+    );
+    if !translation_stats.fallback_lang_codes.is_empty() {
+        success_message.push_str(" Fallback used for: ");
+        success_message.push_str(&translation_stats.fallback_lang_codes.join(", "));
+    }
+
+    Ok(TemplateResponse(AdminTemplate {
+        success_message: Some(success_message),
+        ..admin_session_template(&state, language, &headers).await
+    }))
+}
+
+async fn admin_delete_mineral(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    client: ClientIp,
+    Form(request): Form<DeleteMineralRequest>,
+) -> Result<TemplateResponse<AdminTemplate>, AppError> {
+    csrf::verify(&state, &headers, &request.csrf_token)?;
+    let slug = required_string(&request.slug, "slug")?;
+    delete_mineral_response(&state, &headers, client, &slug).await
+}
+
+async fn admin_delete_mineral_by_slug(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    client: ClientIp,
+    AxumPath(slug): AxumPath<String>,
+    form: Option<Form<CsrfForm>>,
+) -> Result<TemplateResponse<AdminTemplate>, AppError> {
+    csrf::verify(&state, &headers, &csrf::form_token(form))?;
+    delete_mineral_response(&state, &headers, client, &slug).await
+}
+
+async fn delete_mineral_response(
+    state: &AppState,
+    headers: &HeaderMap,
+    ClientIp(client): ClientIp,
+    slug: &str,
+) -> Result<TemplateResponse<AdminTemplate>, AppError> {
+    let language = resolve_language(state, headers);
+    if !has_admin_session(state, headers) {
+        return Err(AppError::Unauthorized(
+            "Admin session required. Log in at /admin.".to_string(),
+        ));
     }
 
-    if let Err(err) = fs::remove_dir_all(&folder_path).await {
+    let folder_path = match mineral_folder_for_slug(state, language, slug).await {
+        Ok(path) => path,
+        Err(message) => {
+            return Ok(TemplateResponse(AdminTemplate {
+                error_message: Some(message),
+                ..admin_session_template(state, language, headers).await
+            }));
+        }
+    };
+
+    if let Err(err) = trash::move_to_trash(&state.data_root, &folder_path, "admin", client).await {
         return Ok(TemplateResponse(AdminTemplate {
-            lang_code: language.code().to_string(),
-            lang_dir: language.dir().to_string(),
-            txt: ui_text(language),
-            has_admin_session: true,
             error_message: Some(format!("failed to delete {}: {err}", folder_path.display())),
-            success_message: None,
-            draft_form: MineralFormData::default(),
-            has_suggestion: false,
-            admin_minerals: admin_minerals_for_ui(&state, language),
+            ..admin_session_template(state, language, headers).await
         }));
     }
 
-    reload_catalog(&state)?;
-    let success_message = format!("Mineral deleted: {slug}");
+    refresh_mineral(state, &folder_file_name(&folder_path)).await?;
+    let success_message = format!("Mineral moved to the trash: {slug}");
 
     Ok(TemplateResponse(AdminTemplate {
+        success_message: Some(success_message),
+        ..admin_session_template(state, language, headers).await
+    }))
+}
+
+/// Resolves a catalog slug to its folder on disk, returning a user-facing
+/// message when the record is unknown or the folder is missing.
+async fn mineral_folder_for_slug(
+    state: &AppState,
+    language: Language,
+    slug: &str,
+) -> Result<PathBuf, String> {
+    let catalog = catalog_for_language(state, language)
+        .await
+        .map_err(|err| err.to_string())?;
+    let Some(mineral) = catalog.resolve(slug) else {
+        return Err(format!("mineral '{slug}' not found"));
+    };
+
+    let folder_name = &mineral.folder_name;
+    if !is_valid_mineral_folder_name(folder_name) {
+        return Err(format!("invalid mineral folder name: {folder_name}"));
+    }
+
+    let folder_path = state.data_root.join("minerals").join(folder_name);
+    match fs::metadata(&folder_path).await {
+        Ok(metadata) if metadata.is_dir() => Ok(folder_path),
+        Ok(_) => Err(format!("mineral path is not a directory: {folder_name}")),
+        Err(_) => Err(format!("mineral folder not found: {folder_name}")),
+    }
+}
+
+/// Refuses a permalink or alias that another mineral already answers to.
+/// `folder_name` is the record's own folder when it is being edited.
+async fn check_slugs_available(
+    state: &AppState,
+    record: &MineralDiskRecord,
+    folder_name: Option<&str>,
+) -> Result<(), AppError> {
+    let catalog = catalog_for_language(state, Language::En).await?;
+    permalinks::check_available(&catalog, record, folder_name).map_err(AppError::BadRequest)
+}
+
+fn folder_file_name(folder_path: &Path) -> String {
+    folder_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+async fn admin_template(
+    state: &AppState,
+    language: Language,
+    has_admin_session: bool,
+) -> AdminTemplate {
+    AdminTemplate {
         lang_code: language.code().to_string(),
         lang_dir: language.dir().to_string(),
         txt: ui_text(language),
-        has_admin_session: true,
+        has_admin_session,
         error_message: None,
-        success_message: Some(success_message),
+        success_message: None,
         draft_form: MineralFormData::default(),
         has_suggestion: false,
-        admin_minerals: admin_minerals_for_ui(&state, language),
-    }))
+        edit_slug: None,
+        duplicates: Vec::new(),
+        admin_minerals: admin_minerals_for_ui(state, language).await,
+        saved_drafts: Vec::new(),
+        api_tokens: Vec::new(),
+        new_api_token: None,
+        translation_progress: None,
+        llm_usage: None,
+        csrf_token: String::new(),
+        mindat_lookup: state.mindat.is_some(),
+        image_upload_max_mb: state.image_upload_max_mb,
+    }
+}
+
+/// Renders the login form with a fresh login CSRF token and its cookie.
+fn login_form_response(template: AdminTemplate) -> Result<Response, AppError> {
+    let csrf_token = crypto::random_hex(24)?;
+    let mut response = TemplateResponse(AdminTemplate {
+        csrf_token: csrf_token.clone(),
+        ..template
+    })
+    .into_response();
+    csrf::attach_login_cookie(&mut response, &csrf_token)?;
+    Ok(response)
+}
+
+async fn admin_session_template(
+    state: &AppState,
+    language: Language,
+    headers: &HeaderMap,
+) -> AdminTemplate {
+    let saved_drafts = match admin_token_from_headers(headers) {
+        Some(token) => state.drafts.list(&token).await,
+        None => Vec::new(),
+    };
+    AdminTemplate {
+        saved_drafts,
+        api_tokens: state.api_tokens.list(),
+        translation_progress: state.translation_batch.snapshot(),
+        llm_usage: state.llm.as_ref().map(|_| state.llm_usage.summary()),
+        csrf_token: csrf::session_token(state, headers).unwrap_or_default(),
+        ..admin_template(state, language, true).await
+    }
+}
+
+fn form_from_fields(fields: &MineralFieldsRequest) -> MineralFormData {
+    MineralFormData {
+        common_name: fields.common_name.clone(),
+        description: fields.description.clone(),
+        mineral_family: fields.mineral_family.clone(),
+        formula: fields.formula.clone(),
+        hardness_mohs: fields.hardness_mohs.clone(),
+        density_g_cm3: fields.density_g_cm3.clone(),
+        crystal_system: fields.crystal_system.clone(),
+        color: fields.color.clone(),
+        streak: fields.streak.clone(),
+        luster: fields.luster.clone(),
+        major_elements_pct_text: fields.major_elements_pct_text.clone(),
+        notes: fields.notes.clone(),
+        tags_text: fields.tags.clone(),
+        references_text: fields.references.clone(),
+        extra_attributes_text: fields.extra_attributes.clone(),
+        permalink: fields.permalink.clone(),
+        aliases_text: fields.aliases.clone(),
+        locality: fields.locality.clone(),
+        country: fields.country.clone(),
+        latitude: fields.latitude.clone(),
+        longitude: fields.longitude.clone(),
+        for_sale: fields.for_sale,
+        price: fields.price.clone(),
+        currency: fields.currency.clone(),
+        quantity_available: fields.quantity_available.clone(),
+        clean_background: fields.clean_background,
+        visibility: Visibility::from_code(&fields.visibility).unwrap_or_default(),
+        publish_at: fields.publish_at.trim().to_string(),
+        ..MineralFormData::default()
+    }
 }
 
-fn parse_publish_request(
-    request: &PublishMineralRequest,
-    image: AdminDraft,
-) -> Result<NewMineralDraft, AppError> {
-    let common_name = required_string(&request.common_name, "common_name")?;
-    let description = required_string(&request.description, "description")?;
-    let mineral_family = required_string(&request.mineral_family, "mineral_family")?;
-    let formula = required_string(&request.formula, "formula")?;
-    let crystal_system = required_string(&request.crystal_system, "crystal_system")?;
-    let color = required_string(&request.color, "color")?;
-    let streak = required_string(&request.streak, "streak")?;
-    let luster = required_string(&request.luster, "luster")?;
-    let notes = required_string(&request.notes, "notes")?;
-
-    let hardness_mohs = parse_f32_from_str(&request.hardness_mohs, "hardness_mohs")?;
-    let density_g_cm3 = parse_f32_from_str(&request.density_g_cm3, "density_g_cm3")?;
+fn parse_mineral_fields(fields: &MineralFieldsRequest) -> Result<MineralDiskRecord, AppError> {
+    let common_name = required_string(&fields.common_name, "common_name")?;
+    let description = required_string(&fields.description, "description")?;
+    let mineral_family = required_string(&fields.mineral_family, "mineral_family")?;
+    let formula = required_string(&fields.formula, "formula")?;
+    let crystal_system = required_string(&fields.crystal_system, "crystal_system")?;
+    let color = required_string(&fields.color, "color")?;
+    let streak = required_string(&fields.streak, "streak")?;
+    let luster = required_string(&fields.luster, "luster")?;
+    let notes = required_string(&fields.notes, "notes")?;
+
+    let hardness_mohs = parse_f32_from_str(&fields.hardness_mohs, "hardness_mohs")?;
+    let density_g_cm3 = parse_f32_from_str(&fields.density_g_cm3, "density_g_cm3")?;
     let major_elements_pct =
-        parse_major_elements(&request.major_elements_pct_text).map_err(AppError::BadRequest)?;
+        parse_major_elements(&fields.major_elements_pct_text).map_err(AppError::BadRequest)?;
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let references = parse_references(&fields.references, &today).map_err(AppError::BadRequest)?;
+    let extra_attributes =
+        parse_extra_attributes(&fields.extra_attributes).map_err(AppError::BadRequest)?;
+    let permalink = permalinks::parse_permalink(&fields.permalink).map_err(AppError::BadRequest)?;
+    let aliases = permalinks::parse_aliases(&fields.aliases).map_err(AppError::BadRequest)?;
+    let location = Location::parse(
+        &fields.locality,
+        &fields.country,
+        &fields.latitude,
+        &fields.longitude,
+    )
+    .map_err(AppError::BadRequest)?;
+    let listing = Listing::parse(
+        fields.for_sale,
+        &fields.price,
+        &fields.currency,
+        &fields.quantity_available,
+    )
+    .map_err(AppError::BadRequest)?;
+    let visibility = Visibility::parse(&fields.visibility).map_err(AppError::BadRequest)?;
+    let publish_at =
+        schedule::parse_publish_at(&fields.publish_at).map_err(AppError::BadRequest)?;
 
-    Ok(NewMineralDraft {
+    Ok(MineralDiskRecord {
         common_name,
         description,
         mineral_family,
@@ -963,24 +2948,83 @@ fn parse_publish_request(
         color,
         streak,
         luster,
-        notes,
         major_elements_pct,
-        image_bytes: image.image_bytes,
-        image_ext: image.image_ext,
+        notes,
+        images: Vec::new(),
+        tags: parse_tags(&fields.tags),
+        references,
+        extra_attributes,
+        permalink,
+        aliases,
+        clean_background: fields.clean_background,
+        visibility,
+        publish_at,
+        location,
+        listing,
+        schema_version: SCHEMA_VERSION,
+        translated_from: None,
     })
 }
 
-async fn parse_suggest_multipart(multipart: &mut Multipart) -> Result<SuggestInput, AppError> {
+/// Reads the `caption_<n>` inputs the admin form renders per gallery image.
+/// The gallery as edited: the same files, with the form's captions and
+/// primary choice.
+fn edited_images(images: &[ImageEntry], captions: Vec<String>, primary: usize) -> Vec<ImageEntry> {
+    images
+        .iter()
+        .zip(captions)
+        .enumerate()
+        .map(|(index, (image, caption))| ImageEntry {
+            file: image.file.clone(),
+            caption,
+            primary: index == primary,
+        })
+        .collect()
+}
+
+fn gallery_captions(fields: &HashMap<String, String>, count: usize) -> Vec<String> {
+    gallery_inputs(fields, "caption", count)
+}
+
+/// Reads one `<name>_<n>` input per gallery image, trimmed.
+fn gallery_inputs(fields: &HashMap<String, String>, name: &str, count: usize) -> Vec<String> {
+    (0..count)
+        .map(|index| {
+            fields
+                .get(&format!("{name}_{index}"))
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Parses the rotation and crop typed for each photo of the form.
+fn photo_edits(form: &MineralFormData) -> Result<Vec<images::PhotoEdit>, AppError> {
+    form.image_rotations
+        .iter()
+        .zip(&form.image_crops)
+        .enumerate()
+        .map(|(index, (rotation, crop))| {
+            images::PhotoEdit::parse(rotation, crop)
+                .map_err(|err| AppError::BadRequest(format!("photo {}: {err}", index + 1)))
+        })
+        .collect()
+}
+
+async fn parse_suggest_multipart(
+    state: &AppState,
+    multipart: &mut Multipart,
+) -> Result<SuggestInput, AppError> {
     let mut suggestion_context = String::new();
-    let mut image_bytes: Option<Vec<u8>> = None;
-    let mut image_ext: Option<String> = None;
+    let mut streak_test = String::new();
+    let mut hardness_test = String::new();
+    let mut csrf_token = String::new();
+    let mut images = Vec::new();
 
     while let Some(field) = multipart.next_field().await.map_err(|err| {
         let message = err.to_string();
         if is_request_too_large_error(&message) {
-            AppError::BadRequest(format!(
-                "image upload too large; keep file under {ADMIN_UPLOAD_MAX_MB} MB"
-            ))
+            state.image_too_large()
         } else {
             AppError::BadRequest(format!("invalid multipart payload: {message}"))
         }
@@ -991,27 +3035,26 @@ async fn parse_suggest_multipart(multipart: &mut Multipart) -> Result<SuggestInp
         }
 
         if name == "image" {
-            let ext = detect_image_extension(&field)?;
             let bytes = field.bytes().await.map_err(|err| {
                 let message = err.to_string();
                 if is_request_too_large_error(&message) {
-                    AppError::BadRequest(format!(
-                        "image upload too large; keep file under {ADMIN_UPLOAD_MAX_MB} MB"
-                    ))
+                    state.image_too_large()
                 } else {
                     AppError::BadRequest(format!("failed to read image field: {message}"))
                 }
             })?;
             if bytes.is_empty() {
-                return Err(AppError::BadRequest("image upload is required".to_string()));
+                continue;
+            }
+            if bytes.len() > state.image_upload_max_bytes() {
+                return Err(state.image_too_large());
             }
-            if bytes.len() > ADMIN_UPLOAD_MAX_BYTES {
+            if images.len() == ADMIN_GALLERY_MAX_IMAGES {
                 return Err(AppError::BadRequest(format!(
-                    "image upload too large; keep file under {ADMIN_UPLOAD_MAX_MB} MB"
+                    "too many images; upload at most {ADMIN_GALLERY_MAX_IMAGES} per mineral"
                 )));
             }
-            image_ext = Some(ext);
-            image_bytes = Some(bytes.to_vec());
+            images.push(sanitize_image(state, bytes.to_vec()).await?);
             continue;
         }
 
@@ -1020,18 +3063,27 @@ async fn parse_suggest_multipart(multipart: &mut Multipart) -> Result<SuggestInp
             .await
             .map_err(|err| AppError::BadRequest(format!("failed to read field '{name}': {err}")))?;
 
-        match name.as_str() {
-            "suggestion_context" => suggestion_context = value.trim().to_string(),
-            _ => {}
+        if name == "suggestion_context" {
+            suggestion_context = value.trim().to_string();
+        } else if name == "streak_test" {
+            streak_test = value.trim().to_string();
+        } else if name == "hardness_test" {
+            hardness_test = value.trim().to_string();
+        } else if name == "csrf_token" {
+            csrf_token = value;
         }
     }
 
+    if images.is_empty() {
+        return Err(AppError::BadRequest("image upload is required".to_string()));
+    }
+
     Ok(SuggestInput {
         suggestion_context,
-        image_bytes: image_bytes
-            .ok_or_else(|| AppError::BadRequest("image upload is required".to_string()))?,
-        image_ext: image_ext
-            .ok_or_else(|| AppError::BadRequest("unable to determine image format".to_string()))?,
+        streak_test,
+        hardness_test,
+        csrf_token,
+        images,
     })
 }
 
@@ -1042,20 +3094,53 @@ fn is_request_too_large_error(message: &str) -> bool {
         || normalized.contains("length limit")
 }
 
-async fn request_openai_suggestion(
+/// Asks the LLM about each of the first [`SUGGEST_MAX_PHOTOS`] photos at
+/// once and merges the answers; see [`consensus`]. Photos whose request
+/// fails are left out as long as one succeeds.
+async fn request_ai_suggestion(
     state: &AppState,
     input: &SuggestInput,
-) -> Result<AiMineralSuggestion, AppError> {
-    let api_key = state.openai_api_key.as_ref().as_ref().ok_or_else(|| {
-        AppError::BadRequest("OPENAI_API_KEY is not configured. Add it to .env.local".to_string())
+) -> Result<(AiMineralSuggestion, Vec<FieldConfidence>), AppError> {
+    let provider = state.llm.as_deref().ok_or_else(|| {
+        AppError::BadRequest(
+            "No LLM provider is configured. Set LLM_PROVIDER and its API key in .env.local"
+                .to_string(),
+        )
     })?;
+    let photos = &input.images[..input.images.len().min(SUGGEST_MAX_PHOTOS)];
+    let results = futures_util::future::join_all(
+        photos
+            .iter()
+            .enumerate()
+            .map(|(index, image)| request_photo_suggestion(state, provider, input, index, image)),
+    )
+    .await;
+
+    let mut suggestions = Vec::new();
+    let mut first_error = None;
+    for (index, result) in results.into_iter().enumerate() {
+        match result {
+            Ok(suggestion) => suggestions.push(suggestion),
+            Err(err) => {
+                warn!("ai suggestion for photo {} failed: {err}", index + 1);
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+    match consensus::merge(suggestions) {
+        Some(merged) => Ok(merged),
+        None => Err(first_error
+            .unwrap_or_else(|| AppError::BadRequest("image upload is required".to_string()))),
+    }
+}
 
-    let image_data_url = format!(
-        "data:{};base64,{}",
-        content_type_from_ext(&input.image_ext),
-        BASE64.encode(&input.image_bytes)
-    );
-
+async fn request_photo_suggestion(
+    state: &AppState,
+    provider: &dyn LlmProvider,
+    input: &SuggestInput,
+    index: usize,
+    image: &MineralImage,
+) -> Result<AiMineralSuggestion, AppError> {
     let schema = serde_json::json!({
       "type": "object",
       "additionalProperties": false,
@@ -1082,7 +3167,66 @@ async fn request_openai_suggestion(
             "required": ["element", "percent"]
           }
         },
-        "notes": {"type": "string"}
+        "notes": {"type": "string"},
+        "confidence": {
+          "type": "object",
+          "additionalProperties": false,
+          "properties": {
+            "common_name": {"type": "number"},
+            "mineral_family": {"type": "number"},
+            "formula": {"type": "number"},
+            "hardness_mohs": {"type": "number"},
+            "density_g_cm3": {"type": "number"},
+            "crystal_system": {"type": "number"},
+            "color": {"type": "number"},
+            "streak": {"type": "number"},
+            "luster": {"type": "number"}
+          },
+          "required": [
+            "common_name",
+            "mineral_family",
+            "formula",
+            "hardness_mohs",
+            "density_g_cm3",
+            "crystal_system",
+            "color",
+            "streak",
+            "luster"
+          ]
+        },
+        "alternatives": {
+          "type": "array",
+          "items": {
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+              "common_name": {"type": "string"},
+              "description": {"type": "string"},
+              "mineral_family": {"type": "string"},
+              "formula": {"type": "string"},
+              "hardness_mohs": {"type": "number"},
+              "density_g_cm3": {"type": "number"},
+              "crystal_system": {"type": "string"},
+              "color": {"type": "string"},
+              "streak": {"type": "string"},
+              "luster": {"type": "string"},
+              "confidence": {"type": "number"}
+            },
+            "required": [
+              "common_name",
+              "description",
+              "mineral_family",
+              "formula",
+              "hardness_mohs",
+              "density_g_cm3",
+              "crystal_system",
+              "color",
+              "streak",
+              "luster",
+              "confidence"
+            ]
+          }
+        }
       },
       "required": [
         "mineral_family",
@@ -1096,79 +3240,59 @@ async fn request_openai_suggestion(
         "streak",
         "luster",
         "major_elements",
-        "notes"
+        "notes",
+        "confidence",
+        "alternatives"
       ]
     });
 
-    let system_prompt = "You assist mineral cataloging. Use the provided photo (and optional operator context) to infer likely mineral properties. Generate a plausible common_name and a concise description. If uncertain, provide conservative estimates and practical values. Output must follow JSON schema exactly.";
+    let system_prompt = "You assist mineral cataloging. Use the provided photo (and optional operator context) to infer likely mineral properties. Generate a plausible common_name and a concise description. If uncertain, provide conservative estimates and practical values. Rate your confidence in each field from 0 (a guess) to 1 (certain), and list the 2 to 3 next most likely identifications as alternatives, most likely first, each with its own confidence. Output must follow JSON schema exactly.";
 
-    let user_prompt = format!(
-        "User context (may be empty): {}\n\nGenerate a likely mineral profile from the image. The common_name and description must be generated too.",
-        input.suggestion_context
+    let mut user_prompt = format!(
+        "User context (may be empty): {}\n\nPhoto {} of {} of the same specimen.",
+        input.suggestion_context,
+        index + 1,
+        input.images.len().min(SUGGEST_MAX_PHOTOS)
+    );
+    if !input.streak_test.is_empty() {
+        user_prompt.push_str(&format!(
+            "\nStreak test by the operator: {}",
+            input.streak_test
+        ));
+    }
+    if !input.hardness_test.is_empty() {
+        user_prompt.push_str(&format!(
+            "\nHardness (scratch) test by the operator: {}",
+            input.hardness_test
+        ));
+    }
+    user_prompt.push_str(
+        "\n\nGenerate a likely mineral profile from the image. The common_name and description must be generated too.",
     );
 
-    let request = ChatCompletionsRequest {
-        model: (*state.openai_model).clone(),
-        messages: vec![
-            ChatMessage {
-                role: "system".to_string(),
-                content: vec![MessagePart::Text {
-                    text: system_prompt.to_string(),
-                }],
-            },
-            ChatMessage {
-                role: "user".to_string(),
-                content: vec![
-                    MessagePart::Text { text: user_prompt },
-                    MessagePart::ImageUrl {
-                        image_url: ImageUrlContent {
-                            url: image_data_url,
-                        },
-                    },
-                ],
-            },
-        ],
-        response_format: ResponseFormat {
-            kind: "json_schema".to_string(),
-            json_schema: JsonSchemaSpec {
-                name: "mineral_suggestion".to_string(),
-                strict: true,
-                schema,
-            },
-        },
+    let request = LlmRequest {
+        model: &state.llm_model,
+        system: system_prompt,
+        prompt: user_prompt,
+        image: Some(LlmImage {
+            mime: content_type_from_ext(&image.ext),
+            bytes: &image.bytes,
+        }),
+        schema_name: "mineral_suggestion".to_string(),
+        schema,
         temperature: 0.2,
+        timeout: state.llm_timeout,
     };
 
-    let response = state
-        .http_client
-        .post("https://api.openai.com/v1/chat/completions")
-        .bearer_auth(api_key)
-        .json(&request)
-        .send()
-        .await
-        .map_err(|err| AppError::BadRequest(format!("failed to call OpenAI API: {err}")))?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        error!("openai api error status={status} body={body}");
-        return Err(AppError::BadRequest(format!(
-            "OpenAI API returned {status}: {body}"
-        )));
-    }
-
-    let parsed: ChatCompletionsResponse = response
-        .json()
-        .await
-        .map_err(|err| AppError::BadRequest(format!("failed to parse OpenAI response: {err}")))?;
-
-    let content = parsed
-        .choices
-        .first()
-        .map(|choice| choice.message.content.as_str())
-        .ok_or_else(|| AppError::BadRequest("OpenAI response had no choices".to_string()))?;
+    let content = provider.complete_json(&request).await.map_err(|err| {
+        error!(
+            "llm suggestion error provider={}: {err:#}",
+            provider.kind().as_str()
+        );
+        AppError::BadRequest(format!("AI request failed: {err:#}"))
+    })?;
 
-    serde_json::from_str::<AiMineralSuggestion>(content)
+    serde_json::from_str::<AiMineralSuggestion>(&content)
         .map_err(|err| AppError::BadRequest(format!("invalid AI JSON payload: {err}")))
 }
 
@@ -1184,11 +3308,32 @@ fn ai_major_elements_to_map(input: Vec<AiMajorElement>) -> BTreeMap<String, f32>
     out
 }
 
-fn catalog_for_language(state: &AppState, language: Language) -> Result<MineralCatalog, AppError> {
+/// The cached catalog for `language`, loading it on a miss. Loading reads
+/// every mineral folder with blocking calls, so it runs on the blocking
+/// pool instead of a runtime worker. The catalog is shared, not copied, so
+/// per-request lookups stay cheap.
+async fn catalog_for_language(
+    state: &AppState,
+    language: Language,
+) -> Result<Arc<MineralCatalog>, AppError> {
+    cached_catalog(
+        &state.catalogs_by_lang,
+        &state.catalog_generation,
+        &state.data_root,
+        language,
+    )
+    .await
+}
+
+async fn cached_catalog(
+    catalogs: &RwLock<HashMap<String, Arc<MineralCatalog>>>,
+    catalog_generation: &AtomicU64,
+    data_root: &Arc<PathBuf>,
+    language: Language,
+) -> Result<Arc<MineralCatalog>, AppError> {
     let code = language.code().to_string();
 
-    if let Some(cached) = state
-        .catalogs_by_lang
+    if let Some(cached) = catalogs
         .read()
         .map_err(|_| anyhow!("catalog cache lock poisoned"))?
         .get(&code)
@@ -1197,21 +3342,60 @@ fn catalog_for_language(state: &AppState, language: Language) -> Result<MineralC
         return Ok(cached);
     }
 
-    let loaded = MineralCatalog::new(load_minerals(state.data_root.as_path(), language.code())?);
-    let mut guard = state
-        .catalogs_by_lang
+    let generation = catalog_generation.load(Ordering::Relaxed);
+    let data_root = data_root.clone();
+    let minerals = tokio::task::spawn_blocking(move || load_minerals(&data_root, language.code()))
+        .await
+        .context("catalog loader panicked")??;
+    let loaded = Arc::new(MineralCatalog::new(minerals));
+    let mut guard = catalogs
         .write()
         .map_err(|_| anyhow!("catalog cache lock poisoned"))?;
     if let Some(cached) = guard.get(&code).cloned() {
         return Ok(cached);
     }
-    guard.insert(code, loaded.clone());
+    // A change landed during the scan, which may have missed it.
+    if catalog_generation.load(Ordering::Relaxed) != generation {
+        return Ok(loaded);
+    }
+    metrics::gauge!("catalog_minerals", "lang" => code.clone()).set(loaded.ordered.len() as f64);
+    guard.insert(code, Arc::clone(&loaded));
     Ok(loaded)
 }
 
-fn admin_minerals_for_ui(state: &AppState, language: Language) -> Vec<Mineral> {
-    match catalog_for_language(state, language) {
-        Ok(catalog) => catalog.ordered,
+/// The catalog as the request may see it: all of it with an admin
+/// session, [`MineralCatalog::public_view`] otherwise.
+async fn visible_catalog(
+    state: &AppState,
+    headers: &HeaderMap,
+    language: Language,
+) -> Result<Arc<MineralCatalog>, AppError> {
+    let catalog = catalog_for_language(state, language).await?;
+    Ok(if has_admin_session(state, headers) {
+        catalog
+    } else {
+        catalog.public_view()
+    })
+}
+
+/// Answers for a private or not yet published mineral, requested as
+/// `slug`, as for an unknown one unless an admin session is present.
+fn require_visible(
+    state: &AppState,
+    headers: &HeaderMap,
+    slug: &str,
+    mineral: &Mineral,
+) -> Result<(), AppError> {
+    if mineral.is_reachable() || has_admin_session(state, headers) {
+        Ok(())
+    } else {
+        Err(AppError::NotFound(format!("mineral '{slug}' not found")))
+    }
+}
+
+async fn admin_minerals_for_ui(state: &AppState, language: Language) -> Vec<Mineral> {
+    match catalog_for_language(state, language).await {
+        Ok(catalog) => catalog.ordered.clone(),
         Err(err) => {
             error!("failed to load admin mineral list: {err:#}");
             Vec::new()
@@ -1219,20 +3403,113 @@ fn admin_minerals_for_ui(state: &AppState, language: Language) -> Vec<Mineral> {
     }
 }
 
-fn get_mineral(state: &AppState, language: Language, slug: &str) -> Result<Mineral, AppError> {
-    catalog_for_language(state, language)?
-        .by_slug
-        .get(slug)
+/// Former slugs resolve too; see [`permalinks`].
+async fn get_mineral(
+    state: &AppState,
+    language: Language,
+    slug: &str,
+) -> Result<Mineral, AppError> {
+    catalog_for_language(state, language)
+        .await?
+        .resolve(slug)
         .cloned()
         .ok_or_else(|| AppError::NotFound(format!("mineral '{slug}' not found")))
 }
 
+/// The mineral plus its closest matches among the public minerals of the
+/// same language's catalog.
+async fn get_mineral_with_related(
+    state: &AppState,
+    language: Language,
+    slug: &str,
+) -> Result<(Mineral, Vec<RelatedMineral>), AppError> {
+    let catalog = catalog_for_language(state, language).await?;
+    let mineral = catalog
+        .resolve(slug)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("mineral '{slug}' not found")))?;
+    let related = similarity::related(&mineral, catalog.listed(), RELATED_LIMIT);
+    Ok((mineral, related))
+}
+
+/// Specimens, custody events, and spectra filed in the mineral's folder.
+async fn load_attachments(
+    state: &AppState,
+    mineral: &Mineral,
+) -> Result<MineralAttachments, AppError> {
+    Ok(MineralAttachments {
+        specimens: specimens::for_mineral(state, mineral).await?,
+        custody: custody::for_mineral(state, mineral).await?,
+        spectra: spectra::for_mineral(state, mineral).await?,
+    })
+}
+
+/// Loads the catalog of every language so the first visitors do not wait
+/// for the folder scan.
+async fn prewarm_catalogs(state: &AppState) {
+    for language in Language::all() {
+        if let Err(err) = catalog_for_language(state, *language).await {
+            warn!("failed to load the {} catalog: {err}", language.code());
+        }
+    }
+}
+
+/// Re-reads one mineral folder into every cached catalog after it was
+/// published, edited, restored, or deleted, instead of dropping the caches
+/// and rescanning every folder. Languages not cached yet load in full on
+/// first use.
+async fn refresh_mineral(state: &AppState, folder_name: &str) -> Result<()> {
+    // Bumped first too, so a full load already under way is not cached
+    // without this change.
+    state.catalog_generation.fetch_add(1, Ordering::Relaxed);
+    let codes = state
+        .catalogs_by_lang
+        .read()
+        .map_err(|_| anyhow!("catalog lock poisoned"))?
+        .keys()
+        .cloned()
+        .collect::<Vec<_>>();
+    let data_root = state.data_root.clone();
+    let folder = folder_name.to_string();
+    let loaded = tokio::task::spawn_blocking(move || {
+        codes
+            .into_iter()
+            .map(|code| Ok((load_mineral(&data_root, &folder, &code)?, code)))
+            .collect::<Result<Vec<_>>>()
+    })
+    .await
+    .context("catalog loader panicked")??;
+
+    let mut guard = state
+        .catalogs_by_lang
+        .write()
+        .map_err(|_| anyhow!("catalog lock poisoned"))?;
+    for (mineral, code) in loaded {
+        let Some(catalog) = guard.get_mut(&code) else {
+            continue;
+        };
+        // Readers holding the old catalog keep it; the cache gets a copy
+        // only while one is out.
+        let catalog = Arc::make_mut(catalog);
+        match mineral {
+            Some(mineral) => catalog.upsert(mineral),
+            None => {
+                catalog.remove(folder_name);
+            }
+        }
+        metrics::gauge!("catalog_minerals", "lang" => code).set(catalog.ordered.len() as f64);
+    }
+    state.catalog_generation.fetch_add(1, Ordering::Relaxed);
+    Ok(())
+}
+
 fn reload_catalog(state: &AppState) -> Result<()> {
     let mut guard = state
         .catalogs_by_lang
         .write()
         .map_err(|_| anyhow!("catalog lock poisoned"))?;
     guard.clear();
+    state.catalog_generation.fetch_add(1, Ordering::Relaxed);
     Ok(())
 }
 
@@ -1245,20 +3522,77 @@ fn has_admin_session(state: &AppState, headers: &HeaderMap) -> bool {
         .admin_sessions
         .lock()
         .ok()
-        .map(|sessions| sessions.contains(&token))
+        .map(|sessions| sessions.contains_key(&token))
         .unwrap_or(false)
 }
 
+fn require_admin_token(state: &AppState, headers: &HeaderMap) -> Result<String, AppError> {
+    admin_token_from_headers(headers)
+        .filter(|_| has_admin_session(state, headers))
+        .ok_or_else(|| {
+            AppError::Unauthorized("Admin session required. Log in at /admin.".to_string())
+        })
+}
+
 fn admin_token_from_headers(headers: &HeaderMap) -> Option<String> {
     cookie_value(headers, "admin_session")
 }
 
+/// A `/<code>/...` path prefix wins over the `lang` cookie.
 fn resolve_language(state: &AppState, headers: &HeaderMap) -> Language {
-    cookie_value(headers, "lang")
-        .and_then(|raw| Language::from_code(&raw))
+    lang_path::path_language(headers)
+        .or_else(|| cookie_value(headers, "lang").and_then(|raw| Language::from_code(&raw)))
         .unwrap_or(state.default_language)
 }
 
+/// The visitor's `units` cookie; metric without one.
+fn resolve_units(headers: &HeaderMap) -> UnitSystem {
+    cookie_value(headers, units::COOKIE)
+        .and_then(|raw| UnitSystem::from_code(&raw))
+        .unwrap_or_default()
+}
+
+/// A request's `units` field; the visitor's cookie when it is blank.
+fn resolve_report_units(headers: &HeaderMap, requested: &str) -> Result<UnitSystem, AppError> {
+    match requested.trim() {
+        "" => Ok(resolve_units(headers)),
+        code => UnitSystem::from_code(code).ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "Unknown units '{code}'. Use one of: metric, imperial."
+            ))
+        }),
+    }
+}
+
+/// The request's `report_language`, so an operator can write a report for
+/// a client in another language; the page language when it is blank.
+fn resolve_report_language(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &ReportRequest,
+) -> Result<Language, AppError> {
+    report_language(request, || resolve_language(state, headers))
+}
+
+fn report_language(
+    request: &ReportRequest,
+    page_language: impl FnOnce() -> Language,
+) -> Result<Language, AppError> {
+    match request.report_language.trim() {
+        "" => Ok(page_language()),
+        code => Language::from_code(code).ok_or_else(|| {
+            let known = Language::all()
+                .iter()
+                .map(|language| language.code())
+                .collect::<Vec<_>>()
+                .join(", ");
+            AppError::BadRequest(format!(
+                "Unknown report language '{code}'. Use one of: {known}."
+            ))
+        }),
+    }
+}
+
 fn default_report_request(language: Language) -> ReportRequest {
     match language {
         Language::En => ReportRequest::default(),
@@ -1266,46 +3600,79 @@ fn default_report_request(language: Language) -> ReportRequest {
             audience: "geologo tecnico".to_string(),
             purpose: "briefing de exploracion".to_string(),
             site_context: "campana piloto de perforacion".to_string(),
+            ..ReportRequest::default()
         },
         Language::Cs => ReportRequest {
             audience: "technicky geolog".to_string(),
             purpose: "pruzkumny briefing".to_string(),
             site_context: "pilotni vrtna kampan".to_string(),
+            ..ReportRequest::default()
         },
         Language::Zh => ReportRequest {
             audience: "技术地质人员".to_string(),
             purpose: "勘查简报".to_string(),
             site_context: "试点钻探活动".to_string(),
+            ..ReportRequest::default()
         },
         Language::Ar => ReportRequest {
             audience: "جيولوجي تقني".to_string(),
             purpose: "احاطة استكشافية".to_string(),
             site_context: "حملة حفر تجريبية".to_string(),
+            ..ReportRequest::default()
         },
         Language::Fr => ReportRequest {
             audience: "geologue technique".to_string(),
             purpose: "briefing d'exploration".to_string(),
             site_context: "campagne pilote de forage".to_string(),
+            ..ReportRequest::default()
         },
         Language::De => ReportRequest {
             audience: "technischer geologe".to_string(),
             purpose: "explorations briefing".to_string(),
             site_context: "pilotbohrkampagne".to_string(),
+            ..ReportRequest::default()
         },
         Language::Pt => ReportRequest {
             audience: "geologo tecnico".to_string(),
             purpose: "briefing de exploracao".to_string(),
             site_context: "campanha piloto de perfuracao".to_string(),
+            ..ReportRequest::default()
         },
         Language::Hi => ReportRequest {
             audience: "takniki bhugarbha vaigyanik".to_string(),
             purpose: "anveshan briefing".to_string(),
             site_context: "pilot drilling abhiyan".to_string(),
+            ..ReportRequest::default()
         },
         Language::Ja => ReportRequest {
             audience: "技術地質担当者".to_string(),
             purpose: "探査ブリーフィング".to_string(),
             site_context: "パイロット掘削キャンペーン".to_string(),
+            ..ReportRequest::default()
+        },
+        Language::Ru => ReportRequest {
+            audience: "технический геолог".to_string(),
+            purpose: "разведочный брифинг".to_string(),
+            site_context: "пилотная буровая кампания".to_string(),
+            ..ReportRequest::default()
+        },
+        Language::Tr => ReportRequest {
+            audience: "teknik jeolog".to_string(),
+            purpose: "arama brifingi".to_string(),
+            site_context: "pilot sondaj kampanyasi".to_string(),
+            ..ReportRequest::default()
+        },
+        Language::Ko => ReportRequest {
+            audience: "기술 지질 담당자".to_string(),
+            purpose: "탐사 브리핑".to_string(),
+            site_context: "시범 시추 캠페인".to_string(),
+            ..ReportRequest::default()
+        },
+        Language::Sw => ReportRequest {
+            audience: "mwanajiolojia wa kiufundi".to_string(),
+            purpose: "muhtasari wa utafutaji".to_string(),
+            site_context: "kampeni ya majaribio ya uchimbaji".to_string(),
+            ..ReportRequest::default()
         },
     }
 }
@@ -1348,45 +3715,33 @@ fn parse_f32_from_str(value: &str, key: &str) -> Result<f32, AppError> {
         .map_err(|_| AppError::BadRequest(format!("'{key}' must be a number")))
 }
 
-fn detect_image_extension(field: &axum::extract::multipart::Field<'_>) -> Result<String, AppError> {
-    if let Some(file_name) = field.file_name() {
-        if let Some(ext) = file_name.rsplit('.').next() {
-            let normalized = ext.to_ascii_lowercase();
-            if ["heic", "heif"].contains(&normalized.as_str()) {
-                return Err(AppError::BadRequest(
-                    "HEIC/HEIF photos are not supported yet; upload png, jpg, webp, or gif"
-                        .to_string(),
-                ));
-            }
-            if ["png", "jpg", "jpeg", "webp", "gif"].contains(&normalized.as_str()) {
-                return Ok(if normalized == "jpeg" {
-                    "jpg".to_string()
-                } else {
-                    normalized
-                });
-            }
-        }
-    }
-
-    if let Some(content_type) = field.content_type() {
-        return match content_type {
-            "image/png" => Ok("png".to_string()),
-            "image/jpeg" => Ok("jpg".to_string()),
-            "image/jpg" => Ok("jpg".to_string()),
-            "image/webp" => Ok("webp".to_string()),
-            "image/gif" => Ok("gif".to_string()),
-            "image/heic" | "image/heif" => Err(AppError::BadRequest(
-                "HEIC/HEIF photos are not supported yet; upload png, jpg, webp, or gif".to_string(),
-            )),
-            _ => Err(AppError::BadRequest(
-                "unsupported image type; use png, jpg, webp, or gif".to_string(),
-            )),
-        };
-    }
+/// Keeps only real PNG, JPEG, WebP and GIF uploads, re-encoded without
+/// their metadata; the file name and declared content type are ignored.
+/// HEIC and TIFF uploads are converted to JPEG first.
+async fn sanitize_image(state: &AppState, bytes: Vec<u8>) -> Result<MineralImage, AppError> {
+    let rejected = |message| AppError::BadRequest(format!("rejected image upload: {message}"));
+    let bytes = match images::convertible_format(&bytes) {
+        Some(format) => images::convert_to_jpeg(&state.image_convert_bin, format, &bytes)
+            .await
+            .map_err(rejected)?,
+        None => bytes,
+    };
+    let (bytes, ext) = tokio::task::spawn_blocking(move || images::sanitize_upload(&bytes))
+        .await
+        .context("image check task failed")?
+        .map_err(rejected)?;
+    Ok(MineralImage {
+        bytes,
+        ext: ext.to_string(),
+    })
+}
 
-    Err(AppError::BadRequest(
-        "unsupported image type; use png, jpg, webp, or gif".to_string(),
-    ))
+fn image_data_url(image: &MineralImage) -> String {
+    format!(
+        "data:{};base64,{}",
+        content_type_from_ext(&image.ext),
+        BASE64.encode(&image.bytes)
+    )
 }
 
 fn content_type_from_ext(ext: &str) -> &'static str {
@@ -1402,45 +3757,187 @@ async fn create_mineral_folder(
     state: &AppState,
     draft: NewMineralDraft,
 ) -> Result<(String, TranslationStats), AppError> {
-    let family_slug = slugify_family(&draft.mineral_family);
-    let minerals_root = state.data_root.join("minerals");
+    let staged = allocate_mineral_folder(state, &draft.record.mineral_family).await?;
+    match fill_mineral_folder(state, &staged.path, draft).await {
+        Ok(translation_stats) => Ok((staged.publish().await?, translation_stats)),
+        Err(err) => {
+            staged.discard().await;
+            Err(err)
+        }
+    }
+}
 
-    let folder_name = create_unique_folder_name(&minerals_root, &family_slug)?;
-    if !is_valid_mineral_folder_name(&folder_name) {
-        return Err(AppError::Internal(anyhow!(
-            "generated invalid mineral folder name: {folder_name}"
-        )));
+async fn fill_mineral_folder(
+    state: &AppState,
+    folder_path: &Path,
+    draft: NewMineralDraft,
+) -> Result<TranslationStats, AppError> {
+    let mut images = Vec::new();
+    for (index, image) in draft.images.into_iter().enumerate() {
+        let edit = draft.edits.get(index).copied().unwrap_or_default();
+        let file = store_edited_image(folder_path, &images, image, edit).await?;
+        images.push(ImageEntry {
+            file,
+            caption: draft.captions.get(index).cloned().unwrap_or_default(),
+            primary: index == draft.primary_image,
+        });
     }
 
-    let folder_path = minerals_root.join(&folder_name);
-    fs::create_dir_all(&folder_path)
-        .await
-        .with_context(|| format!("failed to create {}", folder_path.display()))?;
+    let metadata = MineralDiskRecord {
+        images,
+        ..draft.record
+    }
+    .normalize_images();
+
+    let (localized_records, translation_stats) = build_localized_metadata(state, &metadata).await;
+    write_localized_records(folder_path, &localized_records, metadata).await?;
+
+    Ok(translation_stats)
+}
+
+/// Creates a folder for an imported record holding only the English
+/// metadata. Bulk imports skip the per-record LLM calls; **Translate
+/// Missing** fills in the other languages afterwards.
+async fn create_imported_folder(
+    state: &AppState,
+    record: MineralDiskRecord,
+) -> Result<String, AppError> {
+    let staged = allocate_mineral_folder(state, &record.mineral_family).await?;
+    let mut localized_records = HashMap::new();
+    localized_records.insert(Language::En.code().to_string(), record.clone());
+    if let Err(err) = write_localized_records(&staged.path, &localized_records, record).await {
+        staged.discard().await;
+        return Err(err);
+    }
+    Ok(staged.publish().await?)
+}
+
+/// Reserves a new folder name and a staging folder to write it in; see
+/// [`staging`].
+async fn allocate_mineral_folder(
+    state: &AppState,
+    mineral_family: &str,
+) -> Result<staging::StagedFolder, AppError> {
+    Ok(staging::allocate(
+        &state.data_root,
+        &state.data_root.join("minerals"),
+        &slugify_family(mineral_family),
+    )
+    .await?)
+}
 
-    let image_file = format!("image.{}", draft.image_ext);
+/// Writes `image` under the next free gallery file name and generates its
+/// variants. Returns the file name to record in the metadata.
+async fn store_image(
+    folder_path: &Path,
+    existing: &[ImageEntry],
+    image: MineralImage,
+) -> Result<String, AppError> {
+    let image_file = next_image_file(existing, &image.ext);
     let image_path = folder_path.join(&image_file);
-    fs::write(&image_path, draft.image_bytes)
+    fs::write(&image_path, &image.bytes)
         .await
         .with_context(|| format!("failed to write {}", image_path.display()))?;
+    write_image_variants(folder_path, &image_file, image.bytes).await;
+    Ok(image_file)
+}
 
-    let metadata = MineralDiskRecord {
-        common_name: draft.common_name,
-        description: draft.description,
-        mineral_family: draft.mineral_family,
-        formula: draft.formula,
-        hardness_mohs: draft.hardness_mohs,
-        density_g_cm3: draft.density_g_cm3,
-        crystal_system: draft.crystal_system,
-        color: draft.color,
-        streak: draft.streak,
-        luster: draft.luster,
-        major_elements_pct: draft.major_elements_pct,
-        notes: draft.notes,
-        image_file: Some(image_file),
+/// Stores `image` with `edit` applied, keeping the upload as it was under
+/// [`images::original_file`] next to it.
+async fn store_edited_image(
+    folder_path: &Path,
+    existing: &[ImageEntry],
+    image: MineralImage,
+    edit: images::PhotoEdit,
+) -> Result<String, AppError> {
+    if edit.is_noop() {
+        return store_image(folder_path, existing, image).await;
+    }
+    let original_path = folder_path.join(images::original_file(&next_image_file(
+        existing, &image.ext,
+    )));
+    fs::write(&original_path, &image.bytes)
+        .await
+        .with_context(|| format!("failed to write {}", original_path.display()))?;
+
+    let MineralImage { bytes, ext } = image;
+    let edited = tokio::task::spawn_blocking(move || images::apply_edit(&bytes, &edit))
+        .await
+        .context("image edit task failed")??;
+    store_image(folder_path, existing, MineralImage { bytes: edited, ext }).await
+}
+
+/// Writes the downscaled WebP variants of a freshly stored upload.
+/// Thumbnails are an optimization: if the upload cannot be decoded the
+/// catalog keeps serving the original file.
+async fn write_image_variants(folder_path: &Path, image_file: &str, image_bytes: Vec<u8>) {
+    let folder = folder_path.to_path_buf();
+    let file = image_file.to_string();
+    let result = tokio::task::spawn_blocking(move || {
+        images::remove_variants(&folder, &file);
+        images::write_variants(&folder, &file, &image_bytes)
+    })
+    .await;
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => warn!(
+            "image variants skipped for {}: {err:#}",
+            folder_path.display()
+        ),
+        Err(err) => warn!("image variant task failed: {err}"),
+    }
+}
+
+/// Rewrites every localized metadata file of an existing mineral folder,
+/// saving the current files to its history first. Only the translatable
+/// fields that changed in English are sent for translation; untouched
+/// fields keep their current localized wording.
+async fn update_mineral_folder(
+    state: &AppState,
+    folder_path: &Path,
+    previous: &MineralDiskRecord,
+    updated: &MineralDiskRecord,
+) -> Result<TranslationStats, AppError> {
+    history::record(folder_path, "edit").await?;
+    let needs_translation = translatable_fields_changed(previous, updated);
+    let (translated_records, translation_stats) = if needs_translation {
+        build_localized_metadata(state, updated).await
+    } else {
+        (HashMap::new(), TranslationStats::default())
     };
 
-    let (localized_records, translation_stats) = build_localized_metadata(state, &metadata).await;
-    for (lang_code, localized) in &localized_records {
+    let mut localized_records = HashMap::new();
+    localized_records.insert(Language::En.code().to_string(), updated.clone());
+    for language in Language::all() {
+        if *language == Language::En {
+            continue;
+        }
+
+        let code = language.code();
+        let metadata_path = folder_path.join(format!("mineral.{code}.json"));
+        let existing = match fs::try_exists(&metadata_path).await {
+            Ok(true) => read_record_file(&metadata_path).await.ok(),
+            _ => None,
+        }
+        .unwrap_or_else(|| previous.clone());
+
+        let translated = translated_records.get(code).unwrap_or(updated);
+        localized_records.insert(
+            code.to_string(),
+            merge_localized_record(&existing, translated, previous, updated),
+        );
+    }
+
+    write_localized_records(folder_path, &localized_records, updated.clone()).await?;
+    Ok(translation_stats)
+}
+
+async fn write_localized_records(
+    folder_path: &Path,
+    localized_records: &HashMap<String, MineralDiskRecord>,
+    english: MineralDiskRecord,
+) -> Result<(), AppError> {
+    for (lang_code, localized) in localized_records {
         let metadata_path = folder_path.join(format!("mineral.{lang_code}.json"));
         write_metadata_file(&metadata_path, localized).await?;
     }
@@ -1448,10 +3945,117 @@ async fn create_mineral_folder(
     let fallback_english = localized_records
         .get(Language::En.code())
         .cloned()
-        .unwrap_or(metadata);
-    write_metadata_file(&folder_path.join("mineral.json"), &fallback_english).await?;
+        .unwrap_or(english);
+    write_metadata_file(&folder_path.join("mineral.json"), &fallback_english).await
+}
+
+async fn read_english_record(folder_path: &Path) -> Result<MineralDiskRecord, AppError> {
+    let metadata_path =
+        select_metadata_path(folder_path, Language::En.code()).ok_or_else(|| {
+            AppError::NotFound(format!(
+                "no metadata file found in {}",
+                folder_path.display()
+            ))
+        })?;
+    Ok(read_record_file(&metadata_path).await?)
+}
+
+async fn read_record_file(path: &Path) -> Result<MineralDiskRecord> {
+    let raw = fs::read_to_string(path)
+        .await
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    migrations::parse_record(&raw).with_context(|| format!("failed to parse {}", path.display()))
+}
 
-    Ok((folder_name, translation_stats))
+fn translatable_fields_changed(previous: &MineralDiskRecord, updated: &MineralDiskRecord) -> bool {
+    previous.common_name != updated.common_name
+        || previous.description != updated.description
+        || previous.mineral_family != updated.mineral_family
+        || previous.formula != updated.formula
+        || previous.crystal_system != updated.crystal_system
+        || previous.color != updated.color
+        || previous.streak != updated.streak
+        || previous.luster != updated.luster
+        || previous.notes != updated.notes
+}
+
+fn merge_localized_record(
+    existing: &MineralDiskRecord,
+    translated: &MineralDiskRecord,
+    previous: &MineralDiskRecord,
+    updated: &MineralDiskRecord,
+) -> MineralDiskRecord {
+    let pick = |old_en: &str, new_en: &str, current: &str, fresh: &str| {
+        if old_en == new_en {
+            current.to_string()
+        } else {
+            fresh.to_string()
+        }
+    };
+
+    MineralDiskRecord {
+        common_name: pick(
+            &previous.common_name,
+            &updated.common_name,
+            &existing.common_name,
+            &translated.common_name,
+        ),
+        description: pick(
+            &previous.description,
+            &updated.description,
+            &existing.description,
+            &translated.description,
+        ),
+        mineral_family: pick(
+            &previous.mineral_family,
+            &updated.mineral_family,
+            &existing.mineral_family,
+            &translated.mineral_family,
+        ),
+        formula: pick(
+            &previous.formula,
+            &updated.formula,
+            &existing.formula,
+            &translated.formula,
+        ),
+        crystal_system: pick(
+            &previous.crystal_system,
+            &updated.crystal_system,
+            &existing.crystal_system,
+            &translated.crystal_system,
+        ),
+        color: pick(
+            &previous.color,
+            &updated.color,
+            &existing.color,
+            &translated.color,
+        ),
+        streak: pick(
+            &previous.streak,
+            &updated.streak,
+            &existing.streak,
+            &translated.streak,
+        ),
+        luster: pick(
+            &previous.luster,
+            &updated.luster,
+            &existing.luster,
+            &translated.luster,
+        ),
+        notes: pick(
+            &previous.notes,
+            &updated.notes,
+            &existing.notes,
+            &translated.notes,
+        ),
+        // A failed retranslation keeps the old fingerprint, so the coverage
+        // view shows the language as outdated rather than current.
+        translated_from: translated
+            .translated_from
+            .clone()
+            .or_else(|| existing.translated_from.clone()),
+        ..updated.clone()
+    }
 }
 
 async fn write_metadata_file(path: &Path, metadata: &MineralDiskRecord) -> Result<(), AppError> {
@@ -1473,9 +4077,9 @@ async fn build_localized_metadata(
     out.insert(Language::En.code().to_string(), english.clone());
 
     let mut stats = TranslationStats::default();
-    if state.openai_api_key.as_ref().is_none() {
+    if state.llm.is_none() {
         warn!(
-            "OPENAI_API_KEY is not configured; writing English fallback metadata for all non-English languages"
+            "no LLM provider is configured; writing English fallback metadata for all non-English languages"
         );
         for language in Language::all() {
             if *language == Language::En {
@@ -1487,13 +4091,28 @@ async fn build_localized_metadata(
         return (out, stats);
     }
 
+    let targets = Language::all()
+        .iter()
+        .copied()
+        .filter(|language| *language != Language::En);
+    let mut results = translate_concurrently(
+        targets,
+        state.llm_translation_concurrency,
+        state.llm_translation_deadline,
+        |language| request_ai_translation(state, english, language),
+    )
+    .await;
+
     for language in Language::all() {
         if *language == Language::En {
             continue;
         }
 
         let code = language.code().to_string();
-        match request_openai_translation(state, english, *language).await {
+        let result = results
+            .remove(language)
+            .unwrap_or_else(|| Err(anyhow!("translation deadline reached")));
+        match result {
             Ok(translated) => {
                 out.insert(code, translated);
                 stats.translated_count += 1;
@@ -1513,16 +4132,60 @@ async fn build_localized_metadata(
     (out, stats)
 }
 
-async fn request_openai_translation(
+/// Runs `translate` for each of `languages`, at most `concurrency` at a
+/// time. Requests still in flight at `deadline` are dropped, and their
+/// languages left out of the result, to fall back to English like any
+/// other failure.
+async fn translate_concurrently<T, F, Fut>(
+    languages: impl Iterator<Item = Language>,
+    concurrency: usize,
+    deadline: Duration,
+    translate: F,
+) -> HashMap<Language, Result<T>>
+where
+    F: Fn(Language) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let languages = languages.collect::<Vec<_>>();
+    let total = languages.len();
+    let translations = stream::iter(languages)
+        .map(|language| {
+            let translation = translate(language);
+            async move { (language, translation.await) }
+        })
+        .buffer_unordered(concurrency);
+    let mut translations = pin!(translations);
+
+    let pending = tokio::time::Instant::now() + deadline;
+    let mut results = HashMap::new();
+    loop {
+        match tokio::time::timeout_at(pending, translations.next()).await {
+            Ok(Some((language, result))) => {
+                results.insert(language, result);
+            }
+            Ok(None) => break,
+            Err(_) => {
+                warn!(
+                    "metadata translation deadline of {}s reached with {} languages pending",
+                    deadline.as_secs(),
+                    total - results.len()
+                );
+                break;
+            }
+        }
+    }
+    results
+}
+
+async fn request_ai_translation(
     state: &AppState,
     english: &MineralDiskRecord,
     target_language: Language,
 ) -> Result<MineralDiskRecord> {
-    let api_key = state
-        .openai_api_key
-        .as_ref()
-        .as_ref()
-        .ok_or_else(|| anyhow!("OPENAI_API_KEY is not configured"))?;
+    let provider = state
+        .llm
+        .as_deref()
+        .ok_or_else(|| anyhow!("no LLM provider is configured"))?;
 
     let schema = serde_json::json!({
       "type": "object",
@@ -1571,59 +4234,40 @@ Use concise professional wording. Preserve chemical formulas and symbols exactly
         source_json = source_payload
     );
 
-    let request = ChatCompletionsRequest {
-        model: (*state.openai_translation_model).clone(),
-        messages: vec![
-            ChatMessage {
-                role: "system".to_string(),
-                content: vec![MessagePart::Text {
-                    text: "You are a translation engine for mineral catalog metadata. Output JSON only and follow schema exactly.".to_string(),
-                }],
-            },
-            ChatMessage {
-                role: "user".to_string(),
-                content: vec![MessagePart::Text { text: user_prompt }],
-            },
-        ],
-        response_format: ResponseFormat {
-            kind: "json_schema".to_string(),
-            json_schema: JsonSchemaSpec {
-                name: format!("mineral_translation_{}", target_language.code()),
-                strict: true,
-                schema,
-            },
-        },
+    let request = LlmRequest {
+        model: &state.llm_translation_model,
+        system: "You are a translation engine for mineral catalog metadata. Output JSON only and follow schema exactly.",
+        prompt: user_prompt,
+        image: None,
+        schema_name: format!("mineral_translation_{}", target_language.code()),
+        schema,
         temperature: 0.1,
+        timeout: state.llm_translation_timeout,
     };
 
-    let response = state
-        .http_client
-        .post("https://api.openai.com/v1/chat/completions")
-        .bearer_auth(api_key)
-        .json(&request)
-        .send()
-        .await
-        .with_context(|| "failed to call OpenAI translation endpoint")?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        return Err(anyhow!("OpenAI translation error {status}: {body}"));
-    }
-
-    let parsed: ChatCompletionsResponse = response
-        .json()
-        .await
-        .with_context(|| "failed to parse OpenAI translation response")?;
-
-    let content = parsed
-        .choices
-        .first()
-        .map(|choice| choice.message.content.as_str())
-        .ok_or_else(|| anyhow!("OpenAI translation response had no choices"))?;
+    let cache_key = TranslationCache::key(
+        &source_payload.to_string(),
+        target_language,
+        &state.llm_translation_model,
+    );
+    let (content, cached) = match state.translation_cache.get(&cache_key).await {
+        Some(content) => (content, true),
+        None => {
+            let content = provider
+                .complete_json(&request)
+                .await
+                .context("translation request failed")?;
+            (content, false)
+        }
+    };
 
     let translated: AiMineralTranslation =
-        serde_json::from_str(content).with_context(|| "invalid OpenAI translation JSON payload")?;
+        serde_json::from_str(&content).with_context(|| "invalid translation JSON payload")?;
+    if !cached {
+        if let Err(err) = state.translation_cache.put(&cache_key, &content).await {
+            warn!("failed to cache translation: {err:#}");
+        }
+    }
 
     Ok(MineralDiskRecord {
         common_name: translated_or_source(translated.common_name, &english.common_name),
@@ -1638,7 +4282,19 @@ Use concise professional wording. Preserve chemical formulas and symbols exactly
         luster: translated_or_source(translated.luster, &english.luster),
         major_elements_pct: english.major_elements_pct.clone(),
         notes: translated_or_source(translated.notes, &english.notes),
-        image_file: english.image_file.clone(),
+        images: english.images.clone(),
+        tags: english.tags.clone(),
+        references: english.references.clone(),
+        extra_attributes: english.extra_attributes.clone(),
+        permalink: english.permalink.clone(),
+        aliases: english.aliases.clone(),
+        clean_background: english.clean_background,
+        visibility: english.visibility,
+        publish_at: english.publish_at.clone(),
+        location: english.location.clone(),
+        listing: english.listing.clone(),
+        schema_version: SCHEMA_VERSION,
+        translated_from: Some(english.translation_source()),
     })
 }
 
@@ -1700,20 +4356,6 @@ fn footer_page_content(slug: &str) -> (&'static str, &'static str) {
     }
 }
 
-fn create_unique_folder_name(minerals_root: &Path, family_slug: &str) -> Result<String, AppError> {
-    for _ in 0..16 {
-        let id = generate_secure_hex(4)?;
-        let candidate = format!("mineral.{family_slug}.0x{id}");
-        if !minerals_root.join(&candidate).exists() {
-            return Ok(candidate);
-        }
-    }
-
-    Err(AppError::Internal(anyhow!(
-        "failed to allocate unique mineral id"
-    )))
-}
-
 fn slugify_family(value: &str) -> String {
     let mut out = String::new();
     let mut prev_dash = false;
@@ -1739,12 +4381,343 @@ fn slugify_family(value: &str) -> String {
     }
 }
 
-fn generate_secure_hex(byte_len: usize) -> Result<String, AppError> {
-    let mut file = std::fs::File::open("/dev/urandom")
-        .map_err(|err| AppError::Internal(anyhow!("failed to open /dev/urandom: {err}")))?;
-    let mut buf = vec![0_u8; byte_len];
-    file.read_exact(&mut buf)
-        .map_err(|err| AppError::Internal(anyhow!("failed to read random bytes: {err}")))?;
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        net::Ipv4Addr,
+        sync::{
+            atomic::{AtomicU64, AtomicUsize, Ordering},
+            Arc, RwLock,
+        },
+        time::Duration,
+    };
+
+    use askama::Template;
+    use axum::http::HeaderMap;
+
+    use super::{
+        cached_catalog, edited_images, merge_localized_record, read_english_record,
+        read_record_file, report_language, report_output, translatable_fields_changed,
+        translate_concurrently, write_localized_records, AppError, MineralReportState,
+    };
+    use crate::{
+        agent::{run_agentic_chain, Classification, MineralAttachments},
+        catalog::MineralCatalog,
+        i18n::Language,
+        jobs::{JobStatus, PdfJob},
+        migrations::parse_record,
+        models::{
+            load_mineral, load_minerals, sample_mineral, ImageEntry, MineralDiskRecord,
+            ReportRequest,
+        },
+        permalinks,
+        test_support::TempDir,
+        trash,
+    };
+
+    const FOLDER: &str = "mineral.silicates.0x1a2b3c4d";
+
+    fn record(name: &str) -> MineralDiskRecord {
+        parse_record(
+            &serde_json::json!({
+                "common_name": name,
+                "mineral_family": "silicates",
+                "formula": "SiO2",
+                "hardness_mohs": 7.0,
+                "density_g_cm3": 2.65,
+                "crystal_system": "trigonal",
+                "color": "colorless",
+                "streak": "white",
+                "luster": "vitreous",
+                "notes": ""
+            })
+            .to_string(),
+        )
+        .unwrap()
+    }
+
+    fn temp_root(name: &str) -> TempDir {
+        let root = TempDir::new(name);
+        std::fs::create_dir_all(root.join("minerals").join(FOLDER)).unwrap();
+        root
+    }
+
+    #[test]
+    fn edit_retranslates_only_the_fields_that_changed() {
+        let previous = record("Quartz");
+        let mut updated = previous.clone();
+        assert!(!translatable_fields_changed(&previous, &updated));
+        updated.color = "pink".to_string();
+        updated.hardness_mohs = 7.5;
+        assert!(translatable_fields_changed(&previous, &updated));
+
+        let existing = MineralDiskRecord {
+            color: "incoloro".to_string(),
+            ..record("Cuarzo")
+        };
+        let translated = MineralDiskRecord {
+            color: "rosa".to_string(),
+            ..record("Cuarzo (auto)")
+        };
+        let merged = merge_localized_record(&existing, &translated, &previous, &updated);
+        assert_eq!(merged.common_name, "Cuarzo");
+        assert_eq!(merged.color, "rosa");
+        assert_eq!(merged.hardness_mohs, 7.5);
+    }
+
+    #[tokio::test]
+    async fn edit_rewrites_every_language_and_the_english_fallback() {
+        let root = temp_root("edit");
+        let folder = root.join("minerals").join(FOLDER);
+        let records = HashMap::from([
+            ("en".to_string(), record("Quartz")),
+            ("es".to_string(), record("Cuarzo")),
+        ]);
+        write_localized_records(&folder, &records, record("unused"))
+            .await
+            .unwrap();
+
+        let english = read_english_record(&folder).await.unwrap();
+        let fallback = read_record_file(&folder.join("mineral.json"))
+            .await
+            .unwrap();
+        let spanish = read_record_file(&folder.join("mineral.es.json"))
+            .await
+            .unwrap();
+        assert_eq!(english.common_name, "Quartz");
+        assert_eq!(fallback.common_name, "Quartz");
+        assert_eq!(spanish.common_name, "Cuarzo");
+    }
+
+    #[test]
+    fn edit_keeps_the_gallery_files_and_moves_the_primary_photo() {
+        let image = |file: &str, primary: bool| ImageEntry {
+            file: file.to_string(),
+            caption: String::new(),
+            primary,
+        };
+        let previous = [image("image.jpg", true), image("image-2.png", false)];
+
+        let edited = edited_images(&previous, vec!["Front".into(), "Twin".into()], 1);
+        assert_eq!(edited[0].file, "image.jpg");
+        assert_eq!(edited[1].caption, "Twin");
+        assert_eq!(
+            edited.iter().map(|image| image.primary).collect::<Vec<_>>(),
+            [false, true]
+        );
+        // A primary choice past the gallery falls back to the first photo.
+        let edited = MineralDiskRecord {
+            images: edited_images(&previous, vec![String::new(); 2], 5),
+            ..record("Quartz")
+        }
+        .normalize_images();
+        assert!(edited.images[0].primary);
+    }
+
+    #[test]
+    fn edit_refuses_a_permalink_another_mineral_uses() {
+        let catalog = MineralCatalog::new(vec![crate::models::Mineral {
+            slug: "quartz".to_string(),
+            folder_name: FOLDER.to_string(),
+            common_name: "Quartz".to_string(),
+            ..crate::models::sample_mineral()
+        }]);
+        let mut other = record("Rose Quartz");
+        other.permalink = Some("quartz".to_string());
+        assert!(permalinks::check_available(&catalog, &other, None).is_err());
+        assert!(
+            permalinks::check_available(&catalog, &other, Some("mineral.silicates.0x99")).is_err()
+        );
+        assert!(permalinks::check_available(&catalog, &other, Some(FOLDER)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn translates_languages_concurrently_until_the_deadline() {
+        let in_flight = AtomicUsize::new(0);
+        let most_in_flight = AtomicUsize::new(0);
+        let languages = [Language::Es, Language::Fr, Language::De, Language::Ja];
+        let results = translate_concurrently(
+            languages.into_iter(),
+            2,
+            Duration::from_millis(500),
+            |language| {
+                let (in_flight, most_in_flight) = (&in_flight, &most_in_flight);
+                async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    most_in_flight.fetch_max(now, Ordering::SeqCst);
+                    let millis = if language == Language::Ja { 60_000 } else { 20 };
+                    tokio::time::sleep(Duration::from_millis(millis)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    if language == Language::Fr {
+                        anyhow::bail!("provider error");
+                    }
+                    Ok(language.code())
+                }
+            },
+        )
+        .await;
+
+        assert_eq!(most_in_flight.load(Ordering::SeqCst), 2);
+        assert_eq!(results[&Language::Es].as_ref().unwrap(), &"es");
+        assert!(results[&Language::Fr].is_err());
+        assert_eq!(results[&Language::De].as_ref().unwrap(), &"de");
+        // Still translating at the deadline: left to the English fallback.
+        assert!(!results.contains_key(&Language::Ja));
+    }
+
+    #[tokio::test]
+    async fn deleted_minerals_leave_the_catalog_on_refresh() {
+        let root = temp_root("delete");
+        let folder = root.join("minerals").join(FOLDER);
+        std::fs::write(
+            folder.join("mineral.en.json"),
+            serde_json::to_string(&record("Quartz")).unwrap(),
+        )
+        .unwrap();
+        let mut catalog = MineralCatalog::new(load_minerals(&root, "en").unwrap());
+        assert_eq!(catalog.ordered.len(), 1);
+
+        trash::move_to_trash(&root, &folder, "admin", Ipv4Addr::LOCALHOST.into())
+            .await
+            .unwrap();
+        // What `refresh_mineral` does for each language.
+        let reloaded = load_mineral(&root, FOLDER, "en").unwrap();
+        assert!(reloaded.is_none());
+        assert!(catalog.remove(FOLDER).is_some());
+        assert!(catalog.resolve(FOLDER).is_none());
+        assert!(catalog.search("quartz", 0).is_empty());
+    }
+
+    #[test]
+    fn report_output_polls_only_while_the_job_runs() {
+        let mineral = sample_mineral();
+        let request = ReportRequest::default();
+        let report = run_agentic_chain(
+            &mineral,
+            &request,
+            Vec::new(),
+            MineralAttachments::default(),
+            Classification::default(),
+            Language::En,
+        );
+        let job = PdfJob {
+            id: "job-1".to_string(),
+            slug: mineral.slug.clone(),
+            lang: "en".to_string(),
+            backend: "chromium",
+            status: JobStatus::Running,
+            created_utc: "2024-06-01T12:00:00Z".to_string(),
+            finished_utc: None,
+            summary: report.summary.clone(),
+            narrative: "rules",
+            preset: String::new(),
+            run_id: None,
+            pdf_path: None,
+            html_path: None,
+            signature_path: None,
+            emailed_to: None,
+            error: None,
+            request: request.clone(),
+        };
+        let mut page = MineralReportState {
+            mineral,
+            job: Some(job),
+            request,
+            report,
+            report_runs: Vec::new(),
+        };
+
+        let running = report_output(&HeaderMap::new(), Language::En, &page);
+        assert!(running.pdf_job_pending);
+        assert_eq!(
+            running.poll_url.as_deref(),
+            Some("/minerals/mineral.test.sample/report-output?job=job-1")
+        );
+        let html = running.render().unwrap();
+        assert!(html.contains(r#"hx-get="/minerals/mineral.test.sample/report-output?job=job-1""#));
+        assert!(html.contains(&page.report.summary));
+
+        let job = page.job.as_mut().unwrap();
+        job.status = JobStatus::Done;
+        job.pdf_path = Some("/reports/job-1/report.pdf".to_string());
+        let done = report_output(&HeaderMap::new(), Language::En, &page);
+        assert!(!done.pdf_job_pending);
+        assert!(done.poll_url.is_none());
+        let html = done.render().unwrap();
+        assert!(!html.contains("hx-get"));
+        assert!(html.contains("/reports/job-1/report.pdf"));
+    }
 
-    Ok(buf.iter().map(|b| format!("{b:02x}")).collect::<String>())
+    #[test]
+    fn report_language_overrides_the_page_language() {
+        let request = |report_language: &str| {
+            serde_json::from_value::<ReportRequest>(serde_json::json!({
+                "audience": "client",
+                "purpose": "quote",
+                "site_context": "",
+                "report_language": report_language,
+            }))
+            .unwrap()
+        };
+        assert_eq!(
+            report_language(&request(""), || Language::Fr).unwrap(),
+            Language::Fr
+        );
+        assert_eq!(
+            report_language(&request(" es "), || Language::En).unwrap(),
+            Language::Es
+        );
+        assert!(matches!(
+            report_language(&request("xx"), || Language::En),
+            Err(AppError::BadRequest(message)) if message.contains("'xx'")
+        ));
+
+        let mineral = sample_mineral();
+        let summary = |language| {
+            run_agentic_chain(
+                &mineral,
+                &request("es"),
+                Vec::new(),
+                MineralAttachments::default(),
+                Classification::default(),
+                language,
+            )
+            .summary
+        };
+        assert_ne!(summary(Language::Es), summary(Language::En));
+    }
+
+    #[tokio::test]
+    async fn catalogs_load_off_the_runtime_once_per_language() {
+        let root = temp_root("catalog-cache");
+        std::fs::write(
+            root.join("minerals").join(FOLDER).join("mineral.en.json"),
+            serde_json::to_string(&record("Quartz")).unwrap(),
+        )
+        .unwrap();
+        let catalogs = RwLock::new(HashMap::new());
+        let generation = AtomicU64::new(0);
+        let data_root = Arc::new(root.to_path_buf());
+
+        let loaded = cached_catalog(&catalogs, &generation, &data_root, Language::En)
+            .await
+            .unwrap();
+        assert_eq!(loaded.ordered.len(), 1);
+        assert!(catalogs.read().unwrap().contains_key("en"));
+
+        // Served from the cache without another scan.
+        std::fs::remove_dir_all(root.join("minerals").join(FOLDER)).unwrap();
+        let cached = cached_catalog(&catalogs, &generation, &data_root, Language::En)
+            .await
+            .unwrap();
+        assert_eq!(cached.ordered.len(), 1);
+        assert!(Arc::ptr_eq(&cached, &loaded));
+
+        let spanish = cached_catalog(&catalogs, &generation, &data_root, Language::Es)
+            .await
+            .unwrap();
+        assert!(spanish.ordered.is_empty());
+        assert_eq!(catalogs.read().unwrap().len(), 2);
+    }
 }
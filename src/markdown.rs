@@ -0,0 +1,233 @@
+//! Markdown in free-text fields: a mineral's `description` and `notes` and
+//! the admin discussion. Pages and HTML reports get it as HTML with
+//! anything unsafe (scripts, event handlers, `javascript:` links) removed;
+//! LaTeX and Typst reports get the matching LaTeX constructs and Typst
+//! functions.
+
+use pulldown_cmark::{html, Event, Parser, Tag, TagEnd};
+
+use crate::pdf::{latex_escape, typst_escape};
+
+/// CommonMark as sanitized HTML, safe to insert into a page unescaped.
+pub fn to_html(markdown: &str) -> String {
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, Parser::new(markdown));
+    ammonia::clean(&unsafe_html)
+}
+
+/// CommonMark as LaTeX with every piece of text escaped. Headings become
+/// bold paragraphs and line breaks `\newline`, since the text may sit in a
+/// table cell where `\section` and `\\` do not belong. Images keep their
+/// alt text, links other than http(s) and mailto keep their text, and raw
+/// HTML is dropped.
+pub fn to_latex(markdown: &str) -> String {
+    let mut latex = String::new();
+    let mut in_code_block = false;
+    let mut in_link = false;
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(tag) => match tag {
+                Tag::Heading { .. } | Tag::Strong => latex.push_str("\\textbf{"),
+                Tag::Emphasis => latex.push_str("\\emph{"),
+                Tag::BlockQuote(_) => latex.push_str("\\begin{quote}\n"),
+                Tag::CodeBlock(_) => {
+                    in_code_block = true;
+                    latex.push_str("{\\ttfamily ");
+                }
+                Tag::List(Some(_)) => latex.push_str("\\begin{enumerate}\n"),
+                Tag::List(None) => latex.push_str("\\begin{itemize}\n"),
+                Tag::Item => latex.push_str("\\item "),
+                Tag::Link { dest_url, .. } => {
+                    in_link = ["http://", "https://", "mailto:"]
+                        .iter()
+                        .any(|scheme| dest_url.starts_with(scheme));
+                    if in_link {
+                        latex.push_str(&format!("\\href{{{}}}{{", href_escape(&dest_url)));
+                    }
+                }
+                _ => {}
+            },
+            Event::End(tag) => match tag {
+                TagEnd::Paragraph => latex.push_str("\\par\n"),
+                TagEnd::Heading(_) => latex.push_str("}\\par\n"),
+                TagEnd::Strong | TagEnd::Emphasis => latex.push('}'),
+                TagEnd::BlockQuote(_) => latex.push_str("\\end{quote}\n"),
+                TagEnd::CodeBlock => {
+                    in_code_block = false;
+                    latex.push_str("}\\par\n");
+                }
+                TagEnd::List(true) => latex.push_str("\\end{enumerate}\n"),
+                TagEnd::List(false) => latex.push_str("\\end{itemize}\n"),
+                TagEnd::Item => latex.push('\n'),
+                TagEnd::Link if in_link => {
+                    in_link = false;
+                    latex.push('}');
+                }
+                _ => {}
+            },
+            Event::Text(text) if in_code_block => {
+                let lines = text
+                    .trim_end_matches('\n')
+                    .lines()
+                    .map(latex_escape)
+                    .collect::<Vec<_>>();
+                latex.push_str(&lines.join("\\newline\n"));
+            }
+            Event::Text(text) => latex.push_str(&latex_escape(&text)),
+            Event::Code(code) => latex.push_str(&format!("\\texttt{{{}}}", latex_escape(&code))),
+            Event::SoftBreak => latex.push('\n'),
+            Event::HardBreak => latex.push_str("\\newline\n"),
+            Event::Rule => latex.push_str("\\par\\noindent\\rule{\\linewidth}{0.4pt}\\par\n"),
+            _ => {}
+        }
+    }
+
+    let mut latex = latex.trim_end();
+    while let Some(rest) = latex.strip_suffix("\\par") {
+        latex = rest.trim_end();
+    }
+    latex.to_string()
+}
+
+/// A URL for `\href`'s first argument. hyperref reads it verbatim but for
+/// `%`, `#`, and backslashes, so `_` and `~` stay as written; braces, which
+/// would end the argument, are percent-encoded.
+fn href_escape(url: &str) -> String {
+    url.replace('\\', "%5C")
+        .replace('{', "%7B")
+        .replace('}', "%7D")
+        .replace('%', "\\%")
+        .replace('#', "\\#")
+}
+
+/// CommonMark as Typst markup made only of function calls and string
+/// literals, so no character of the text can start Typst syntax. Block
+/// structure maps as in [`to_latex`]: headings become bold paragraphs,
+/// images keep their alt text, links other than http(s) and mailto keep
+/// their text, and raw HTML is dropped.
+pub fn to_typst(markdown: &str) -> String {
+    let mut typst = String::new();
+    let mut in_code_block = false;
+    let mut in_link = false;
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(tag) => match tag {
+                Tag::Heading { .. } | Tag::Strong => typst.push_str("#strong["),
+                Tag::Emphasis => typst.push_str("#emph["),
+                Tag::BlockQuote(_) => typst.push_str("#quote(block: true)["),
+                Tag::CodeBlock(_) => in_code_block = true,
+                Tag::List(Some(start)) => typst.push_str(&format!("#enum(start: {start}, ")),
+                Tag::List(None) => typst.push_str("#list("),
+                Tag::Item => typst.push('['),
+                Tag::Link { dest_url, .. } => {
+                    in_link = ["http://", "https://", "mailto:"]
+                        .iter()
+                        .any(|scheme| dest_url.starts_with(scheme));
+                    if in_link {
+                        typst.push_str(&format!("#link(\"{}\")[", typst_escape(&dest_url)));
+                    }
+                }
+                _ => {}
+            },
+            Event::End(tag) => match tag {
+                TagEnd::Paragraph => typst.push_str("#parbreak()\n"),
+                TagEnd::Heading(_) => typst.push_str("]#parbreak()\n"),
+                TagEnd::Strong | TagEnd::Emphasis => typst.push(']'),
+                TagEnd::BlockQuote(_) => typst.push_str("]\n"),
+                TagEnd::CodeBlock => in_code_block = false,
+                TagEnd::List(_) => typst.push_str(")\n"),
+                TagEnd::Item => typst.push_str("], "),
+                TagEnd::Link if in_link => {
+                    in_link = false;
+                    typst.push(']');
+                }
+                _ => {}
+            },
+            Event::Text(text) if in_code_block => typst.push_str(&format!(
+                "#raw(block: true, \"{}\")\n",
+                typst_escape(text.trim_end_matches('\n'))
+            )),
+            Event::Text(text) => typst.push_str(&format!("#\"{}\"", typst_escape(&text))),
+            Event::Code(code) => typst.push_str(&format!("#raw(\"{}\")", typst_escape(&code))),
+            Event::SoftBreak => typst.push_str("#\" \""),
+            Event::HardBreak => typst.push_str("#linebreak()"),
+            Event::Rule => typst.push_str("#line(length: 100%)\n"),
+            _ => {}
+        }
+    }
+
+    let mut typst = typst.trim_end();
+    while let Some(rest) = typst.strip_suffix("#parbreak()") {
+        typst = rest.trim_end();
+    }
+    typst.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_html, to_latex, to_typst};
+
+    #[test]
+    fn renders_sanitized_html() {
+        assert_eq!(
+            to_html("Forms **prismatic** crystals"),
+            "<p>Forms <strong>prismatic</strong> crystals</p>\n"
+        );
+        let html = to_html(
+            "<script>alert(1)</script>\n\n[x](javascript:alert(1)) <img src=a onerror=alert(1)>",
+        );
+        assert!(!html.contains("script"));
+        assert!(!html.contains("javascript"));
+        assert!(!html.contains("onerror"));
+        assert!(
+            to_html("[mindat](https://www.mindat.org/min-3337.html)").contains(
+                r#"<a href="https://www.mindat.org/min-3337.html" rel="noopener noreferrer">"#
+            )
+        );
+    }
+
+    #[test]
+    fn converts_markdown_to_latex() {
+        assert_eq!(to_latex("Plain 50% text"), "Plain 50\\% text");
+        assert_eq!(
+            to_latex("# Habit\n\n*Prismatic*, **striated**.\n\n- one\n- `SiO_2`"),
+            "\\textbf{Habit}\\par\n\\emph{Prismatic}, \\textbf{striated}.\\par\n\
+             \\begin{itemize}\n\\item one\n\\item \\texttt{SiO\\_2}\n\\end{itemize}"
+        );
+        assert_eq!(
+            to_latex("See [mindat](https://mindat.org/a_b) and [this](javascript:x)."),
+            "See \\href{https://mindat.org/a_b}{mindat} and this."
+        );
+        assert_eq!(
+            to_latex("[x](https://a.example/~me/50%25_off#top)"),
+            "\\href{https://a.example/~me/50\\%25_off\\#top}{x}"
+        );
+        assert_eq!(
+            to_latex("1. first\n2. second  \nline\n\n> quoted\n\n---"),
+            "\\begin{enumerate}\n\\item first\n\\item second\\newline\nline\n\\end{enumerate}\n\
+             \\begin{quote}\nquoted\\par\n\\end{quote}\n\\par\\noindent\\rule{\\linewidth}{0.4pt}"
+        );
+        assert_eq!(
+            to_latex("Code:\n\n```\na {b}\nc\n```"),
+            "Code:\\par\n{\\ttfamily a \\{b\\}\\newline\nc}"
+        );
+        assert_eq!(to_latex("<b>raw</b> html"), "raw html");
+    }
+
+    #[test]
+    fn converts_markdown_to_typst() {
+        assert_eq!(
+            to_typst("Plain \"50%\" // #text $x$ *a*"),
+            "#\"Plain \\\"50%\\\" // #text $x$ \"#emph[#\"a\"]"
+        );
+        assert_eq!(
+            to_typst("# Habit\n\n**Prismatic**\nand `SiO_2`.\n\n- one\n- [mindat](https://mindat.org/a)"),
+            "#strong[#\"Habit\"]#parbreak()\n#strong[#\"Prismatic\"]#\" \"#\"and \"#raw(\"SiO_2\")#\".\"\
+             #parbreak()\n#list([#\"one\"], [#link(\"https://mindat.org/a\")[#\"mindat\"]], )"
+        );
+        assert_eq!(
+            to_typst("3. third\n\n```\nfn x() {}\n```\n\n[x](javascript:y) <b>raw</b>"),
+            "#enum(start: 3, [#\"third\"], )\n#raw(block: true, \"fn x() {}\")\n#\"x\"#\" \"#\"raw\""
+        );
+    }
+}
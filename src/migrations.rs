@@ -0,0 +1,154 @@
+//! Layout versions of `mineral.<lang>.json`. Every record carries a
+//! `schema_version`; files written before it existed count as version 1.
+//! Records are upgraded step by step as JSON before they are deserialized,
+//! both when they are read and by `minerals migrate`, which writes the
+//! result back. A record from a newer build is refused rather than
+//! misread.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Value};
+
+use crate::models::MineralDiskRecord;
+
+/// The layout this build writes.
+pub const SCHEMA_VERSION: u32 = 3;
+/// Files without a `schema_version`.
+const UNVERSIONED: u32 = 1;
+
+/// `MIGRATIONS[i]` upgrades a version `i + 1` record to version `i + 2`.
+/// A format change bumps `SCHEMA_VERSION` and appends one step here.
+const MIGRATIONS: &[fn(&mut Map<String, Value>)] = &[gallery_and_family, structured_references];
+
+/// Upgrades `value` in place to `SCHEMA_VERSION`. Returns whether anything
+/// had to change.
+pub fn upgrade(value: &mut Value) -> Result<bool> {
+    let record = value
+        .as_object_mut()
+        .context("mineral metadata is not a JSON object")?;
+    let version = match record.get("schema_version") {
+        None => UNVERSIONED,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .with_context(|| format!("invalid schema_version {version}"))?,
+    };
+    if version > SCHEMA_VERSION {
+        bail!(
+            "schema_version {version} is newer than this build understands \
+             ({SCHEMA_VERSION}); upgrade minerals"
+        );
+    }
+    if version == SCHEMA_VERSION {
+        return Ok(false);
+    }
+    for migrate in &MIGRATIONS[version.saturating_sub(UNVERSIONED) as usize..] {
+        migrate(record);
+    }
+    record.insert("schema_version".to_string(), SCHEMA_VERSION.into());
+    Ok(true)
+}
+
+/// Parses a metadata file of any supported version.
+pub fn parse_record(raw: &str) -> Result<MineralDiskRecord> {
+    let mut value = serde_json::from_str::<Value>(raw)?;
+    upgrade(&mut value)?;
+    Ok(serde_json::from_value::<MineralDiskRecord>(value)?.normalize_images())
+}
+
+/// 1 → 2: `mineral_group` becomes `mineral_family`, and the single-photo
+/// `image_file` becomes the first, primary entry of `images`.
+fn gallery_and_family(record: &mut Map<String, Value>) {
+    if let Some(group) = record.remove("mineral_group") {
+        record.entry("mineral_family").or_insert(group);
+    }
+    let Some(Value::String(file)) = record.remove("image_file") else {
+        return;
+    };
+    let images = record
+        .entry("images")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    let Some(images) = images.as_array_mut() else {
+        return;
+    };
+    if images
+        .iter()
+        .any(|image| image.get("file").and_then(Value::as_str) == Some(file.as_str()))
+    {
+        return;
+    }
+    for image in images.iter_mut().filter_map(Value::as_object_mut) {
+        image.insert("primary".to_string(), false.into());
+    }
+    images.insert(
+        0,
+        serde_json::json!({ "file": file, "caption": "", "primary": true }),
+    );
+}
+
+/// 2 → 3: `references` holds objects with a title and accessed date
+/// instead of bare URLs.
+fn structured_references(record: &mut Map<String, Value>) {
+    let Some(Value::Array(references)) = record.get_mut("references") else {
+        return;
+    };
+    for reference in references.iter_mut() {
+        if let Value::String(url) = reference {
+            *reference = serde_json::json!({ "title": "", "url": url, "accessed": "" });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{parse_record, upgrade, SCHEMA_VERSION};
+
+    #[test]
+    fn upgrades_unversioned_records_and_refuses_newer_ones() {
+        let mut value = json!({
+            "common_name": "Quartz",
+            "mineral_group": "silicate",
+            "formula": "SiO2",
+            "hardness_mohs": 7.0,
+            "density_g_cm3": 2.65,
+            "crystal_system": "trigonal",
+            "color": "colorless",
+            "streak": "white",
+            "luster": "vitreous",
+            "notes": "",
+            "image_file": "image.jpg",
+            "images": [{ "file": "image-2.png", "caption": "Cluster", "primary": true }],
+            "references": ["https://www.mindat.org/min-3337.html"]
+        });
+        assert!(upgrade(&mut value).unwrap());
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        assert_eq!(value["mineral_family"], "silicate");
+        assert!(value.get("mineral_group").is_none());
+        assert!(value.get("image_file").is_none());
+        assert_eq!(value["images"][0]["file"], "image.jpg");
+        assert_eq!(value["images"][0]["primary"], true);
+        assert_eq!(value["images"][1]["primary"], false);
+        assert_eq!(
+            value["references"][0]["url"],
+            "https://www.mindat.org/min-3337.html"
+        );
+
+        let upgraded = value.clone();
+        assert!(!upgrade(&mut value).unwrap());
+        assert_eq!(value, upgraded);
+
+        let record = parse_record(&value.to_string()).unwrap();
+        assert_eq!(record.schema_version, SCHEMA_VERSION);
+        assert_eq!(record.images.len(), 2);
+        assert_eq!(record.references[0].title, "");
+        assert!(serde_json::to_string(&record)
+            .unwrap()
+            .contains(&format!("\"schema_version\":{SCHEMA_VERSION}")));
+
+        let mut newer = json!({ "schema_version": SCHEMA_VERSION + 1 });
+        let message = upgrade(&mut newer).unwrap_err().to_string();
+        assert!(message.contains("newer than this build"), "{message}");
+        assert!(upgrade(&mut json!({ "schema_version": "2" })).is_err());
+    }
+}
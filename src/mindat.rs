@@ -0,0 +1,210 @@
+//! Reference data from the mindat.org open API. The admin form asks for a
+//! mineral by name and gets formula, crystal system, hardness, and density
+//! to pre-fill, plus the mindat.org page they came from, which is kept in
+//! the record's `references`.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    Json,
+};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::{require_admin_token, AppError, AppState};
+
+pub const MINDAT_BASE_URL: &str = "https://api.mindat.org";
+const MINDAT_PAGE_URL: &str = "https://www.mindat.org";
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(15);
+const LOOKUP_FIELDS: &str =
+    "id,name,mindat_formula,ima_formula,csystem,hmin,hmax,dmeas,dmeas2,dcalc";
+
+pub struct MindatClient {
+    client: Client,
+    api_key: String,
+    base_url: String,
+}
+
+/// Values for the admin form; numbers are formatted as the form shows them
+/// and left out when mindat.org has none.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ReferenceData {
+    pub name: String,
+    pub formula: String,
+    pub crystal_system: String,
+    pub hardness_mohs: Option<String>,
+    pub density_g_cm3: Option<String>,
+    pub source_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LookupQuery {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeomaterialPage {
+    #[serde(default)]
+    results: Vec<Geomaterial>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Geomaterial {
+    id: u64,
+    name: String,
+    mindat_formula: Option<String>,
+    ima_formula: Option<String>,
+    csystem: Option<String>,
+    hmin: Option<f32>,
+    hmax: Option<f32>,
+    dmeas: Option<f32>,
+    dmeas2: Option<f32>,
+    dcalc: Option<f32>,
+}
+
+impl MindatClient {
+    pub fn new(client: Client, api_key: String, base_url: String) -> Self {
+        Self {
+            client,
+            api_key,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// The geomaterial whose name matches `name` (ignoring case), else the
+    /// first result; `None` when mindat.org knows no such mineral.
+    pub async fn lookup(&self, name: &str) -> Result<Option<ReferenceData>> {
+        let page = self
+            .client
+            .get(format!("{}/geomaterials/", self.base_url))
+            .header("Authorization", format!("Token {}", self.api_key))
+            .query(&[
+                ("name", name),
+                ("fields", LOOKUP_FIELDS),
+                ("format", "json"),
+            ])
+            .timeout(LOOKUP_TIMEOUT)
+            .send()
+            .await
+            .context("mindat.org request failed")?
+            .error_for_status()
+            .context("mindat.org rejected the lookup")?
+            .json::<GeomaterialPage>()
+            .await
+            .context("invalid mindat.org response")?;
+
+        let mut results = page.results;
+        let index = results
+            .iter()
+            .position(|material| material.name.eq_ignore_ascii_case(name))
+            .unwrap_or(0);
+        Ok((index < results.len()).then(|| reference_data(results.swap_remove(index))))
+    }
+}
+
+pub async fn lookup_reference(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<LookupQuery>,
+) -> Result<Json<ReferenceData>, AppError> {
+    require_admin_token(&state, &headers)?;
+    let client = state.mindat.as_ref().ok_or_else(|| {
+        AppError::BadRequest(
+            "MINDAT_API_KEY is not set; mindat.org lookups are disabled".to_string(),
+        )
+    })?;
+    let name = query.name.trim();
+    if name.is_empty() {
+        return Err(AppError::BadRequest(
+            "enter a mineral name first".to_string(),
+        ));
+    }
+    client
+        .lookup(name)
+        .await?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("mindat.org has no mineral named '{name}'")))
+}
+
+/// Ranges (`hmin`–`hmax`, `dmeas`–`dmeas2`) become their midpoint, since
+/// the form takes one value; density falls back to the calculated one.
+fn reference_data(material: Geomaterial) -> ReferenceData {
+    let known = |value: Option<f32>| value.filter(|value| *value > 0.0);
+    let midpoint = |low: Option<f32>, high: Option<f32>| match (known(low), known(high)) {
+        (Some(low), Some(high)) => Some((low + high) / 2.0),
+        (low, high) => low.or(high),
+    };
+    let hardness = midpoint(material.hmin, material.hmax);
+    let density = midpoint(material.dmeas, material.dmeas2).or(known(material.dcalc));
+    let formula = material
+        .mindat_formula
+        .filter(|formula| !formula.trim().is_empty())
+        .or(material.ima_formula)
+        .unwrap_or_default();
+
+    ReferenceData {
+        formula: plain_formula(&formula),
+        crystal_system: material.csystem.unwrap_or_default().trim().to_lowercase(),
+        hardness_mohs: hardness.map(|value| format!("{value:.2}")),
+        density_g_cm3: density.map(|value| format!("{value:.2}")),
+        source_url: format!("{MINDAT_PAGE_URL}/min-{}.html", material.id),
+        name: material.name,
+    }
+}
+
+/// mindat.org marks subscripts up as HTML (`SiO<sub>2</sub>`); the form
+/// wants the plain `SiO2` that `chemistry` parses.
+fn plain_formula(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for ch in html.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(ch),
+            _ => {}
+        }
+    }
+    text.replace("&middot;", "·")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reference_data, Geomaterial, GeomaterialPage};
+
+    #[test]
+    fn turns_a_geomaterial_into_form_values() {
+        let page: GeomaterialPage = serde_json::from_str(
+            r#"{"count": 1, "results": [{"id": 3337, "name": "Quartz",
+            "mindat_formula": "SiO<sub>2</sub>", "ima_formula": "SiO2",
+            "csystem": "Trigonal", "hmin": 7.0, "hmax": 7.0,
+            "dmeas": 2.65, "dmeas2": null, "dcalc": 2.66}]}"#,
+        )
+        .unwrap();
+        let quartz = reference_data(page.results.into_iter().next().unwrap());
+        assert_eq!(quartz.formula, "SiO2");
+        assert_eq!(quartz.crystal_system, "trigonal");
+        assert_eq!(quartz.hardness_mohs.as_deref(), Some("7.00"));
+        assert_eq!(quartz.density_g_cm3.as_deref(), Some("2.65"));
+        assert_eq!(quartz.source_url, "https://www.mindat.org/min-3337.html");
+
+        let bare = reference_data(Geomaterial {
+            id: 1,
+            hmin: Some(6.0),
+            hmax: Some(6.5),
+            dcalc: Some(5.3),
+            ..Geomaterial::default()
+        });
+        assert_eq!(bare.hardness_mohs.as_deref(), Some("6.25"));
+        assert_eq!(bare.density_g_cm3.as_deref(), Some("5.30"));
+        assert_eq!(bare.formula, "");
+    }
+}
@@ -1,7 +1,20 @@
 use std::{collections::BTreeMap, fs, path::Path};
 
 use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+    auth::sha256_hex,
+    background,
+    chemistry::{self, ElementDivergence},
+    consensus::{Candidate, FieldConfidence},
+    geo::Location,
+    images::variant_files,
+    migrations, permalinks, schedule,
+    shop::Listing,
+    visibility::Visibility,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Mineral {
@@ -20,14 +33,105 @@ pub struct Mineral {
     pub major_elements_pct: BTreeMap<String, f32>,
     pub notes: String,
     pub image_path: Option<String>,
+    /// Downscaled WebP variants; `None` for images that predate them.
+    pub thumbnail_path: Option<String>,
+    pub medium_path: Option<String>,
+    /// Every photo in gallery order, primary included.
+    pub images: Vec<GalleryImage>,
+    /// Primary photo with its background removed, when the mineral opted
+    /// in and it has been generated; see [`crate::background`].
+    pub clean_image_path: Option<String>,
+    pub tags: Vec<String>,
+    /// Former slugs; see [`crate::permalinks`].
+    pub aliases: Vec<String>,
+    pub references: Vec<Reference>,
+    /// Client-specific fields such as a lot number or warehouse bin.
+    pub extra_attributes: BTreeMap<String, String>,
+    pub location: Location,
+    pub listing: Listing,
+    pub visibility: Visibility,
+    /// RFC 3339 UTC time the mineral goes public; see [`crate::schedule`].
+    pub publish_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GalleryImage {
+    pub path: String,
+    pub thumbnail_path: Option<String>,
+    pub medium_path: Option<String>,
+    pub caption: String,
+    pub primary: bool,
+}
+
+impl GalleryImage {
+    pub fn thumbnail_src(&self) -> &str {
+        self.thumbnail_path.as_deref().unwrap_or(&self.path)
+    }
+}
+
+impl Mineral {
+    /// Smallest available image, for list views.
+    pub fn thumbnail_src(&self) -> Option<&str> {
+        self.thumbnail_path
+            .as_deref()
+            .or(self.image_path.as_deref())
+    }
+
+    /// Web-sized image for the detail page; the original stays linked.
+    pub fn display_src(&self) -> Option<&str> {
+        self.medium_path.as_deref().or(self.image_path.as_deref())
+    }
+
+    /// Image for catalog cards: the clean-background photo when there is
+    /// one, else the thumbnail.
+    pub fn card_src(&self) -> Option<&str> {
+        self.clean_image_path
+            .as_deref()
+            .or_else(|| self.thumbnail_src())
+    }
+
+    pub fn publish_time(&self) -> Option<DateTime<Utc>> {
+        self.publish_at.as_deref().and_then(schedule::publish_time)
+    }
+
+    /// Whether the publish time, if any, has passed. A time that does not
+    /// parse keeps the mineral hidden rather than publishing it early.
+    pub fn is_published(&self) -> bool {
+        self.publish_at.is_none() || self.publish_time().is_some_and(|at| at <= Utc::now())
+    }
+
+    /// Whether a visitor without an admin session may open the page.
+    pub fn is_reachable(&self) -> bool {
+        self.visibility.is_reachable() && self.is_published()
+    }
+
+    /// Whether the mineral shows up in public listings, search, and the API.
+    pub fn is_listed(&self) -> bool {
+        self.visibility.is_public() && self.is_published()
+    }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct ReportRequest {
     pub audience: String,
     pub purpose: String,
     pub site_context: String,
+    /// Folder under `REPORT_TEMPLATES_DIR`; blank for the built-in layout.
+    pub template: String,
+    /// Have the LLM provider write the summary and recommendations.
+    #[serde(deserialize_with = "checkbox")]
+    pub llm_narrative: bool,
+    /// Saved preset the fields were filled in from; blank for none.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub preset: String,
+    /// Language code to write the report in (`es`); blank for the page
+    /// language.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub report_language: String,
+    /// `metric` or `imperial`; blank for the visitor's `units` cookie.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub units: String,
 }
 
 impl Default for ReportRequest {
@@ -36,17 +140,55 @@ impl Default for ReportRequest {
             audience: "technical geologist".to_string(),
             purpose: "exploration briefing".to_string(),
             site_context: "pilot drill campaign".to_string(),
+            template: String::new(),
+            llm_narrative: false,
+            preset: String::new(),
+            report_language: String::new(),
+            units: String::new(),
         }
     }
 }
 
-#[derive(Debug, Clone, Default)]
+/// A JSON boolean, or a form checkbox (`true`/`on`). Flattened form
+/// fields reach serde as strings, so a plain `bool` would be rejected.
+pub(crate) fn checkbox<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Checkbox {
+        Bool(bool),
+        Text(String),
+    }
+    Ok(match Checkbox::deserialize(deserializer)? {
+        Checkbox::Bool(value) => value,
+        Checkbox::Text(text) => matches!(text.trim(), "true" | "on" | "1"),
+    })
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MineralFormData {
+    #[serde(skip)]
     pub draft_id: Option<String>,
     pub common_name: String,
     pub description: String,
     pub suggestion_context: String,
-    pub preview_image_data_url: String,
+    /// Streak and scratch test results given with the photos.
+    pub streak_test: String,
+    pub hardness_test: String,
+    /// Agreement between the photos behind the AI suggestion, and the
+    /// model's confidence, per field.
+    pub field_confidence: Vec<FieldConfidence>,
+    /// The suggested identification followed by its alternatives.
+    pub candidates: Vec<Candidate>,
+    /// Data URLs (drafts) or public paths (edits), in gallery order.
+    #[serde(skip)]
+    pub preview_images: Vec<String>,
+    pub image_captions: Vec<String>,
+    /// Degrees clockwise per photo, as typed; applied on publish.
+    pub image_rotations: Vec<String>,
+    /// `left,top,width,height` in percent per photo; applied on publish.
+    pub image_crops: Vec<String>,
+    pub primary_image: usize,
     pub mineral_family: String,
     pub formula: String,
     pub hardness_mohs: String,
@@ -57,14 +199,165 @@ pub struct MineralFormData {
     pub luster: String,
     pub major_elements_pct_text: String,
     pub notes: String,
+    /// Comma-separated, as typed in the admin form.
+    pub tags_text: String,
+    /// `URL | title | accessed`, one per line.
+    pub references_text: String,
+    /// `name = value`, one per line.
+    pub extra_attributes_text: String,
+    pub permalink: String,
+    /// Former slugs, one per line.
+    pub aliases_text: String,
+    pub locality: String,
+    pub country: String,
+    /// Decimal degrees; blank when no coordinates are recorded.
+    pub latitude: String,
+    pub longitude: String,
+    pub for_sale: bool,
+    /// Decimal; blank when the mineral has no price.
+    pub price: String,
+    pub currency: String,
+    /// Blank when stock is not tracked.
+    pub quantity_available: String,
+    pub clean_background: bool,
+    pub visibility: Visibility,
+    /// `datetime-local` value in UTC; blank to publish right away.
+    pub publish_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct FormImage {
+    pub index: usize,
+    pub src: String,
+    pub caption: String,
+    pub rotation: String,
+    pub crop: String,
+    pub primary: bool,
+}
+
+impl MineralFormData {
+    /// Keeps what the AI suggestion was made from, and how sure it was,
+    /// from `suggested`: the publish form does not post these back.
+    pub fn with_suggestion_of(self, suggested: &MineralFormData) -> Self {
+        Self {
+            suggestion_context: suggested.suggestion_context.clone(),
+            streak_test: suggested.streak_test.clone(),
+            hardness_test: suggested.hardness_test.clone(),
+            field_confidence: suggested.field_confidence.clone(),
+            candidates: suggested.candidates.clone(),
+            ..self
+        }
+    }
+
+    /// Field confidence worth showing: when several photos were compared
+    /// or the model rated its answers.
+    pub fn suggestion_confidence(&self) -> &[FieldConfidence] {
+        if self
+            .field_confidence
+            .iter()
+            .any(|entry| entry.total > 1 || entry.score.is_some())
+        {
+            &self.field_confidence
+        } else {
+            &[]
+        }
+    }
+
+    /// Candidates to switch between; empty when there is nothing to switch
+    /// to.
+    pub fn switchable_candidates(&self) -> &[Candidate] {
+        if self.candidates.len() > 1 {
+            &self.candidates
+        } else {
+            &[]
+        }
+    }
+
+    pub fn from_record(record: &MineralDiskRecord) -> Self {
+        Self {
+            image_captions: record
+                .images
+                .iter()
+                .map(|image| image.caption.clone())
+                .collect(),
+            primary_image: record
+                .images
+                .iter()
+                .position(|image| image.primary)
+                .unwrap_or(0),
+            common_name: record.common_name.clone(),
+            description: record.description.clone(),
+            mineral_family: record.mineral_family.clone(),
+            formula: record.formula.clone(),
+            hardness_mohs: format!("{:.2}", record.hardness_mohs),
+            density_g_cm3: format!("{:.2}", record.density_g_cm3),
+            crystal_system: record.crystal_system.clone(),
+            color: record.color.clone(),
+            streak: record.streak.clone(),
+            luster: record.luster.clone(),
+            major_elements_pct_text: major_elements_to_text(&record.major_elements_pct),
+            notes: record.notes.clone(),
+            tags_text: tags_to_text(&record.tags),
+            references_text: references_to_text(&record.references),
+            extra_attributes_text: extra_attributes_to_text(&record.extra_attributes),
+            permalink: record.permalink.clone().unwrap_or_default(),
+            aliases_text: record.aliases.join("\n"),
+            locality: record.location.locality.clone(),
+            country: record.location.country.clone(),
+            latitude: record.location.latitude_text(),
+            longitude: record.location.longitude_text(),
+            for_sale: record.listing.for_sale,
+            price: record.listing.price_text(),
+            currency: record.listing.currency.clone(),
+            quantity_available: record.listing.quantity_text(),
+            clean_background: record.clean_background,
+            visibility: record.visibility,
+            publish_at: record
+                .publish_at
+                .as_deref()
+                .map(schedule::publish_at_input)
+                .unwrap_or_default(),
+            ..Self::default()
+        }
+    }
+
+    pub fn gallery(&self) -> Vec<FormImage> {
+        self.preview_images
+            .iter()
+            .enumerate()
+            .map(|(index, src)| FormImage {
+                index,
+                src: src.clone(),
+                caption: self.image_captions.get(index).cloned().unwrap_or_default(),
+                rotation: self.image_rotations.get(index).cloned().unwrap_or_default(),
+                crop: self.image_crops.get(index).cloned().unwrap_or_default(),
+                primary: index == self.primary_image,
+            })
+            .collect()
+    }
+
+    /// Entered element percentages that disagree with the formula. Empty
+    /// when either side is blank or does not parse.
+    pub fn formula_divergences(&self) -> Vec<ElementDivergence> {
+        let entered = match parse_major_elements(&self.major_elements_pct_text) {
+            Ok(entered) if !entered.is_empty() => entered,
+            _ => return Vec::new(),
+        };
+        chemistry::weight_percentages(&self.formula)
+            .map(|expected| chemistry::divergences(&expected, &entered))
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MineralDiskRecord {
+    /// Layout version; see [`crate::migrations`]. Records are upgraded to
+    /// the current one before they are deserialized.
+    #[serde(default)]
+    pub schema_version: u32,
     pub common_name: String,
     #[serde(default)]
     pub description: String,
-    #[serde(alias = "mineral_group")]
     pub mineral_family: String,
     pub formula: String,
     pub hardness_mohs: f32,
@@ -77,9 +370,138 @@ pub struct MineralDiskRecord {
     pub major_elements_pct: BTreeMap<String, f32>,
     pub notes: String,
     #[serde(default)]
-    pub image_file: Option<String>,
+    pub images: Vec<ImageEntry>,
+    /// Free-form labels (project, locality, client) shared by every
+    /// language; see [`normalize_tag`].
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Sources for the recorded values, such as the mindat.org page a
+    /// lookup filled the form from; shared by every language.
+    #[serde(default)]
+    pub references: Vec<Reference>,
+    /// Client-specific fields (lot number, assay id, warehouse bin) by
+    /// name; shared by every language.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra_attributes: BTreeMap<String, String>,
+    /// Stable slug used instead of the folder name; see
+    /// [`crate::permalinks`]. Shared by every language.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permalink: Option<String>,
+    /// Slugs the mineral was published under before, which redirect to the
+    /// current one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Show the primary photo with its background removed on catalog cards
+    /// and certificates; shared by every language.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub clean_background: bool,
+    /// Who can see the mineral; see [`crate::visibility`]. Shared by every
+    /// language.
+    #[serde(default, skip_serializing_if = "Visibility::is_public")]
+    pub visibility: Visibility,
+    /// RFC 3339 UTC time before which the mineral stays out of public
+    /// views; see [`crate::schedule`]. Shared by every language.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publish_at: Option<String>,
+    /// Collection site; its fields sit at the top level of the JSON.
+    #[serde(default, flatten)]
+    pub location: Location,
+    /// Shop price and stock; its fields sit at the top level of the JSON.
+    #[serde(default, flatten)]
+    pub listing: Listing,
+    /// [`MineralDiskRecord::translation_source`] of the English record an
+    /// LLM translation was made from. English records and untranslated
+    /// fallback copies leave it unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translated_from: Option<String>,
 }
 
+/// A cited source: the page, its title, and the day it was read.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Reference {
+    #[serde(default)]
+    pub title: String,
+    pub url: String,
+    /// `YYYY-MM-DD`; blank when unknown.
+    #[serde(default)]
+    pub accessed: String,
+}
+
+impl Reference {
+    /// The title, or the URL for references without one.
+    pub fn display_title(&self) -> &str {
+        if self.title.is_empty() {
+            &self.url
+        } else {
+            &self.title
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImageEntry {
+    pub file: String,
+    #[serde(default)]
+    pub caption: String,
+    #[serde(default)]
+    pub primary: bool,
+}
+
+impl MineralDiskRecord {
+    /// Short fingerprint of the translatable fields. A translation whose
+    /// `translated_from` no longer matches its English record's fingerprint
+    /// is out of date.
+    pub fn translation_source(&self) -> String {
+        let text = [
+            &self.common_name,
+            &self.description,
+            &self.mineral_family,
+            &self.formula,
+            &self.crystal_system,
+            &self.color,
+            &self.streak,
+            &self.luster,
+            &self.notes,
+        ]
+        .map(String::as_str)
+        .join("\u{1f}");
+        sha256_hex(text.as_bytes())[..16].to_string()
+    }
+
+    /// Makes sure exactly one entry of `images` is primary.
+    pub fn normalize_images(mut self) -> Self {
+        let primary = self
+            .images
+            .iter()
+            .position(|image| image.primary)
+            .unwrap_or(0);
+        for (index, image) in self.images.iter_mut().enumerate() {
+            image.primary = index == primary;
+        }
+        self
+    }
+}
+
+/// Picks the next free `image[-N].<ext>` name so new uploads never
+/// overwrite another gallery entry or its variants.
+pub fn next_image_file(images: &[ImageEntry], ext: &str) -> String {
+    let taken = |stem: &str| {
+        images
+            .iter()
+            .any(|image| image.file.rsplit_once('.').map(|(s, _)| s) == Some(stem))
+    };
+    if !taken("image") {
+        return format!("image.{ext}");
+    }
+    (2..)
+        .map(|n| format!("image-{n}"))
+        .find(|stem| !taken(stem))
+        .map(|stem| format!("{stem}.{ext}"))
+        .unwrap_or_default()
+}
+
+const MAX_TAG_CHARS: usize = 48;
+
 pub fn load_minerals(data_root: &Path, lang_code: &str) -> Result<Vec<Mineral>> {
     let minerals_root = data_root.join("minerals");
     if !minerals_root.exists() {
@@ -98,46 +520,109 @@ pub fn load_minerals(data_root: &Path, lang_code: &str) -> Result<Vec<Mineral>>
         }
 
         let folder_name = entry.file_name().to_string_lossy().to_string();
-        if !is_valid_mineral_folder_name(&folder_name) {
-            continue;
+        if let Some(mineral) = load_mineral(data_root, &folder_name, lang_code)? {
+            minerals.push(mineral);
         }
-
-        let metadata_path = select_metadata_path(&path, lang_code);
-        let Some(metadata_path) = metadata_path else {
-            continue;
-        };
-
-        let raw = fs::read_to_string(&metadata_path)
-            .with_context(|| format!("failed to read {}", metadata_path.display()))?;
-        let record: MineralDiskRecord = serde_json::from_str(&raw)
-            .with_context(|| format!("failed to parse {}", metadata_path.display()))?;
-
-        minerals.push(Mineral {
-            slug: folder_name.clone(),
-            folder_name: folder_name.clone(),
-            common_name: record.common_name,
-            description: record.description,
-            mineral_family: record.mineral_family,
-            formula: record.formula,
-            hardness_mohs: record.hardness_mohs,
-            density_g_cm3: record.density_g_cm3,
-            crystal_system: record.crystal_system,
-            color: record.color,
-            streak: record.streak,
-            luster: record.luster,
-            major_elements_pct: record.major_elements_pct,
-            notes: record.notes,
-            image_path: record
-                .image_file
-                .map(|file| format!("/data/minerals/{}/{}", folder_name, file)),
-        });
     }
 
     minerals.sort_by(|a, b| a.common_name.cmp(&b.common_name));
     Ok(minerals)
 }
 
-fn select_metadata_path(folder: &Path, lang_code: &str) -> Option<std::path::PathBuf> {
+/// One mineral folder in `lang_code`; `None` when the folder is gone, has
+/// an invalid name, or holds no metadata yet.
+pub fn load_mineral(
+    data_root: &Path,
+    folder_name: &str,
+    lang_code: &str,
+) -> Result<Option<Mineral>> {
+    if !is_valid_mineral_folder_name(folder_name) {
+        return Ok(None);
+    }
+    let path = data_root.join("minerals").join(folder_name);
+    let Some(metadata_path) = select_metadata_path(&path, lang_code) else {
+        return Ok(None);
+    };
+
+    let record = read_disk_record(&metadata_path)?;
+    let images = record
+        .images
+        .iter()
+        .map(|image| gallery_image(&path, folder_name, image))
+        .collect::<Vec<_>>();
+    let clean_image_path = (record.clean_background && path.join(background::CLEAN_FILE).exists())
+        .then(|| format!("/data/minerals/{folder_name}/{}", background::CLEAN_FILE));
+    Ok(Some(Mineral {
+        clean_image_path,
+        ..mineral_from_record(folder_name, record, images)
+    }))
+}
+
+/// The catalog view of `record`, with its gallery already resolved to
+/// URLs.
+pub fn mineral_from_record(
+    folder_name: &str,
+    record: MineralDiskRecord,
+    images: Vec<GalleryImage>,
+) -> Mineral {
+    let primary = images.iter().find(|image| image.primary).cloned();
+    Mineral {
+        slug: permalinks::slug(folder_name, &record),
+        folder_name: folder_name.to_string(),
+        common_name: record.common_name,
+        description: record.description,
+        mineral_family: record.mineral_family,
+        formula: record.formula,
+        hardness_mohs: record.hardness_mohs,
+        density_g_cm3: record.density_g_cm3,
+        crystal_system: record.crystal_system,
+        color: record.color,
+        streak: record.streak,
+        luster: record.luster,
+        major_elements_pct: record.major_elements_pct,
+        notes: record.notes,
+        image_path: primary.as_ref().map(|image| image.path.clone()),
+        thumbnail_path: primary
+            .as_ref()
+            .and_then(|image| image.thumbnail_path.clone()),
+        medium_path: primary.and_then(|image| image.medium_path),
+        images,
+        clean_image_path: None,
+        tags: normalize_tags(record.tags.iter().map(String::as_str)),
+        aliases: record.aliases,
+        references: record.references,
+        extra_attributes: record.extra_attributes,
+        location: record.location,
+        listing: record.listing,
+        visibility: record.visibility,
+        publish_at: record.publish_at,
+    }
+}
+
+fn gallery_image(folder: &Path, folder_name: &str, image: &ImageEntry) -> GalleryImage {
+    let public_path = |file: &str| format!("/data/minerals/{folder_name}/{file}");
+    let variant = |file: &str| folder.join(file).exists().then(|| public_path(file));
+    let (thumbnail_path, medium_path) = match variant_files(&image.file) {
+        Some((thumb, medium)) => (variant(&thumb), variant(&medium)),
+        None => (None, None),
+    };
+
+    GalleryImage {
+        path: public_path(&image.file),
+        thumbnail_path,
+        medium_path,
+        caption: image.caption.clone(),
+        primary: image.primary,
+    }
+}
+
+pub fn read_disk_record(path: &Path) -> Result<MineralDiskRecord> {
+    let raw =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    migrations::parse_record(&raw).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+pub fn select_metadata_path(folder: &Path, lang_code: &str) -> Option<std::path::PathBuf> {
     let preferred = folder.join(format!("mineral.{lang_code}.json"));
     if preferred.exists() {
         return Some(preferred);
@@ -204,3 +689,297 @@ pub fn major_elements_to_text(values: &BTreeMap<String, f32>) -> String {
         .collect::<Vec<_>>()
         .join("\n")
 }
+
+/// Lowercases a tag and joins its words with `-` so it can sit in a URL
+/// path: `Minas Gerais` becomes `minas-gerais`. `None` when nothing is
+/// left.
+pub fn normalize_tag(raw: &str) -> Option<String> {
+    let mut tag = String::new();
+    for c in raw.trim().chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() || c == '_' {
+            tag.push(c);
+        } else if !tag.is_empty() && !tag.ends_with('-') {
+            tag.push('-');
+        }
+    }
+    let tag = tag
+        .trim_end_matches('-')
+        .chars()
+        .take(MAX_TAG_CHARS)
+        .collect::<String>();
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// Normalized tags without duplicates, in first-seen order.
+pub fn normalize_tags<'a>(raw: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut tags = Vec::new();
+    for tag in raw.into_iter().filter_map(normalize_tag) {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Splits the admin form's tag input on commas, semicolons, or newlines.
+pub fn parse_tags(raw: &str) -> Vec<String> {
+    normalize_tags(raw.split([',', ';', '\n']))
+}
+
+pub fn tags_to_text(tags: &[String]) -> String {
+    tags.join(", ")
+}
+
+/// Parses the admin form's reference input: one `URL | title | accessed`
+/// per line, title and date optional. A line without `|` may list several
+/// bare URLs, as the form used to. References without an accessed date get
+/// `today`; each URL is kept once.
+pub fn parse_references(raw: &str, today: &str) -> Result<Vec<Reference>, String> {
+    let mut references: Vec<Reference> = Vec::new();
+    for line in raw.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let parsed = if line.contains('|') {
+            let mut parts = line.split('|').map(str::trim);
+            vec![Reference {
+                url: parts.next().unwrap_or_default().to_string(),
+                title: parts.next().unwrap_or_default().to_string(),
+                accessed: parts.next().unwrap_or_default().to_string(),
+            }]
+        } else {
+            line.split_whitespace()
+                .map(|url| url.trim_end_matches([',', ';']))
+                .filter(|url| !url.is_empty())
+                .map(|url| Reference {
+                    url: url.to_string(),
+                    ..Reference::default()
+                })
+                .collect()
+        };
+        for mut reference in parsed {
+            let url = &reference.url;
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return Err(format!(
+                    "reference '{url}' must start with http:// or https://"
+                ));
+            }
+            if reference.accessed.is_empty() {
+                reference.accessed = today.to_string();
+            } else if NaiveDate::parse_from_str(&reference.accessed, "%Y-%m-%d").is_err() {
+                return Err(format!(
+                    "reference '{url}': accessed date '{}' must be YYYY-MM-DD",
+                    reference.accessed
+                ));
+            }
+            if !references.iter().any(|known| known.url == reference.url) {
+                references.push(reference);
+            }
+        }
+    }
+    Ok(references)
+}
+
+/// Parses the admin form's extra attributes, one `name = value` per line.
+/// Lines with a blank value are dropped, so clearing a value removes the
+/// attribute.
+pub fn parse_extra_attributes(raw: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut attributes = BTreeMap::new();
+    for line in raw.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("extra attribute '{line}' must be written as name = value"))?;
+        let (name, value) = (name.trim(), value.trim());
+        if name.is_empty() {
+            return Err(format!("extra attribute '{line}' has no name"));
+        }
+        if value.is_empty() {
+            continue;
+        }
+        if attributes
+            .insert(name.to_string(), value.to_string())
+            .is_some()
+        {
+            return Err(format!("extra attribute '{name}' is given twice"));
+        }
+    }
+    Ok(attributes)
+}
+
+/// Trims names and values given through the API, dropping blank values.
+pub fn normalize_extra_attributes(
+    attributes: BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>, String> {
+    parse_extra_attributes(
+        &attributes
+            .iter()
+            .filter(|(_, value)| !value.trim().is_empty())
+            .map(|(name, value)| {
+                if name.contains(['=', '\n']) {
+                    Err(format!(
+                        "extra attribute name '{name}' must not contain '=' or line breaks"
+                    ))
+                } else if value.contains('\n') {
+                    Err(format!("extra attribute '{name}' must fit on one line"))
+                } else {
+                    Ok(format!("{name} = {value}"))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .join("\n"),
+    )
+}
+
+pub fn extra_attributes_to_text(attributes: &BTreeMap<String, String>) -> String {
+    attributes
+        .iter()
+        .map(|(name, value)| format!("{name} = {value}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The admin form's text for `references`, one per line.
+pub fn references_to_text(references: &[Reference]) -> String {
+    references
+        .iter()
+        .map(|reference| {
+            format!(
+                "{} | {} | {}",
+                reference.url, reference.title, reference.accessed
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A blank public mineral for tests; override the fields a test cares
+/// about with `..sample_mineral()`.
+#[cfg(test)]
+pub fn sample_mineral() -> Mineral {
+    Mineral {
+        slug: "mineral.test.sample".to_string(),
+        folder_name: "mineral.test.sample".to_string(),
+        common_name: "Sample".to_string(),
+        description: String::new(),
+        mineral_family: String::new(),
+        formula: String::new(),
+        hardness_mohs: 0.0,
+        density_g_cm3: 0.0,
+        crystal_system: String::new(),
+        color: String::new(),
+        streak: String::new(),
+        luster: String::new(),
+        major_elements_pct: BTreeMap::new(),
+        notes: String::new(),
+        image_path: None,
+        thumbnail_path: None,
+        medium_path: None,
+        images: Vec::new(),
+        clean_image_path: None,
+        tags: Vec::new(),
+        aliases: Vec::new(),
+        references: Vec::new(),
+        extra_attributes: BTreeMap::new(),
+        location: Location::default(),
+        listing: Listing::default(),
+        visibility: Visibility::default(),
+        publish_at: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        extra_attributes_to_text, next_image_file, normalize_tag, parse_extra_attributes,
+        parse_references, parse_tags, references_to_text,
+    };
+    use crate::migrations::parse_record;
+
+    #[test]
+    fn folds_legacy_image_file_into_gallery() {
+        let record = parse_record(
+            &serde_json::json!({
+            "common_name": "Quartz",
+            "mineral_family": "silicate",
+            "formula": "SiO2",
+            "hardness_mohs": 7.0,
+            "density_g_cm3": 2.65,
+            "crystal_system": "trigonal",
+            "color": "colorless",
+            "streak": "white",
+            "luster": "vitreous",
+            "notes": "",
+            "image_file": "image.jpg",
+            "images": [{ "file": "image-2.png", "caption": "Cluster" }]
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(record.images.len(), 2);
+        assert_eq!(record.images[0].file, "image.jpg");
+        assert!(record.images[0].primary);
+        assert!(!record.images[1].primary);
+        assert_eq!(next_image_file(&record.images, "webp"), "image-3.webp");
+        assert!(!serde_json::to_string(&record)
+            .unwrap()
+            .contains("image_file"));
+    }
+
+    #[test]
+    fn normalizes_free_form_tags() {
+        assert_eq!(
+            normalize_tag("  Minas Gerais / 2024 "),
+            Some("minas-gerais-2024".to_string())
+        );
+        assert_eq!(
+            normalize_tag("Ölbach_Site"),
+            Some("ölbach_site".to_string())
+        );
+        assert_eq!(normalize_tag(" -- "), None);
+        assert_eq!(
+            parse_tags("Client A, client a; Drill #3\n\n"),
+            vec!["client-a".to_string(), "drill-3".to_string()]
+        );
+    }
+
+    #[test]
+    fn parses_extra_attributes_one_per_line() {
+        let attributes =
+            parse_extra_attributes("Lot number = L-2024/17\n\n warehouse bin=B3 \nassay id =\n")
+                .unwrap();
+
+        assert_eq!(attributes.len(), 2);
+        assert_eq!(attributes["Lot number"], "L-2024/17");
+        assert_eq!(attributes["warehouse bin"], "B3");
+        assert_eq!(
+            parse_extra_attributes(&extra_attributes_to_text(&attributes)).unwrap(),
+            attributes
+        );
+
+        assert!(parse_extra_attributes("lot L-17").is_err());
+        assert!(parse_extra_attributes("= L-17").is_err());
+        assert!(parse_extra_attributes("lot = 1\nlot = 2").is_err());
+    }
+
+    #[test]
+    fn parses_structured_and_bare_references() {
+        let references = parse_references(
+            "https://www.mindat.org/min-3337.html | Quartz - mindat.org | 2026-03-01\n\
+             https://a.example/x, https://www.mindat.org/min-3337.html\n",
+            "2026-10-17",
+        )
+        .unwrap();
+
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0].title, "Quartz - mindat.org");
+        assert_eq!(references[0].accessed, "2026-03-01");
+        assert_eq!(references[1].url, "https://a.example/x");
+        assert_eq!(references[1].display_title(), "https://a.example/x");
+        assert_eq!(references[1].accessed, "2026-10-17");
+        assert_eq!(
+            parse_references(&references_to_text(&references), "2027-01-01").unwrap(),
+            references
+        );
+
+        assert!(parse_references("ftp://a.example | A", "2026-10-17").is_err());
+        assert!(parse_references("https://a.example | A | March", "2026-10-17").is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Opt-in LLM narrative for mineral reports. When a request sets
+//! `llm_narrative` and an LLM provider is configured, the model rewrites
+//! the summary and recommendations from the metrics the agent chain
+//! derived. Any failure keeps the chain's own text, so a report never
+//! depends on the provider; `MineralReport::narrative` records which one
+//! was used. Since every such report spends LLM credit and carries the
+//! request's free text into the prompt, asking for one takes an admin
+//! session or an API token.
+
+use anyhow::{anyhow, Context, Result};
+use axum::http::HeaderMap;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::{
+    agent::{MineralReport, Narrative},
+    api, has_admin_session,
+    i18n::Language,
+    llm::{LlmProvider, LlmRequest},
+    models::ReportRequest,
+    AppError, AppState,
+};
+
+/// More than this reads as a checklist rather than a briefing.
+const MAX_RECOMMENDATIONS: usize = 6;
+
+const SYSTEM_PROMPT: &str = "You write the narrative of mineral analysis reports. Base every statement on the metrics provided; never invent measurements, localities, or sources. Output JSON only and follow the schema exactly.";
+
+#[derive(Debug, Deserialize)]
+struct LlmNarrative {
+    summary: String,
+    recommendations: Vec<String>,
+}
+
+/// Refuses `llm_narrative` from visitors; checked by the web and API
+/// routes before anything is rendered.
+pub fn authorize(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &ReportRequest,
+) -> Result<(), AppError> {
+    if !request.llm_narrative || has_admin_session(state, headers) {
+        return Ok(());
+    }
+    api::require_api_token(state, headers).map_err(|_| {
+        AppError::Unauthorized(
+            "'llm_narrative' requires an admin session or an API token.".to_string(),
+        )
+    })
+}
+
+/// Replaces the summary and recommendations of `report` with the model's
+/// when `request` asks for it.
+pub async fn apply(
+    state: &AppState,
+    request: &ReportRequest,
+    report: &mut MineralReport,
+    language: Language,
+) {
+    if !request.llm_narrative {
+        return;
+    }
+    let Some(provider) = state.llm.as_deref() else {
+        warn!(
+            "llm narrative requested for {} but no LLM provider is configured; keeping the rule-based text",
+            report.mineral.slug
+        );
+        return;
+    };
+    match request_narrative(provider, state, report, language).await {
+        Ok(narrative) => {
+            info!(
+                "llm narrative for {} written by {}",
+                report.mineral.slug, state.llm_model
+            );
+            report.summary = narrative.summary;
+            report.recommendations = narrative.recommendations;
+            report.narrative = Narrative::Llm {
+                model: state.llm_model.to_string(),
+            };
+        }
+        Err(err) => warn!(
+            "llm narrative fallback for {}: {err:#}",
+            report.mineral.slug
+        ),
+    }
+}
+
+async fn request_narrative(
+    provider: &dyn LlmProvider,
+    state: &AppState,
+    report: &MineralReport,
+    language: Language,
+) -> Result<LlmNarrative> {
+    let schema = json!({
+      "type": "object",
+      "additionalProperties": false,
+      "properties": {
+        "summary": {"type": "string"},
+        "recommendations": {"type": "array", "items": {"type": "string"}}
+      },
+      "required": ["summary", "recommendations"]
+    });
+
+    let prompt = format!(
+        "Write the summary (2 to 4 sentences) and 3 to 5 short, actionable recommendations of a \
+mineral report for a {audience} preparing a {purpose} ({site_context}). Write in {language_name} \
+({language_code}). Keep chemical formulas and element symbols unchanged.\n\nMetrics JSON:\n{metrics}",
+        audience = report.audience,
+        purpose = report.purpose,
+        site_context = report.site_context,
+        language_name = language.english_name(),
+        language_code = language.code(),
+        metrics = metrics_payload(report)
+    );
+
+    let request = LlmRequest {
+        model: &state.llm_model,
+        system: SYSTEM_PROMPT,
+        prompt,
+        image: None,
+        schema_name: "mineral_report_narrative".to_string(),
+        schema,
+        temperature: 0.3,
+        timeout: state.llm_timeout,
+    };
+    let content = provider
+        .complete_json(&request)
+        .await
+        .context("narrative request failed")?;
+    parse_narrative(&content)
+}
+
+/// What the chain knows about the mineral, including its own wording as a
+/// baseline the model may improve on.
+fn metrics_payload(report: &MineralReport) -> Value {
+    let mineral = &report.mineral;
+    json!({
+        "mineral": mineral.common_name,
+        "family": mineral.mineral_family,
+        "formula": mineral.formula,
+        "strunz_class": report.strunz_class,
+        "crystal_system": mineral.crystal_system,
+        "hardness_mohs": mineral.hardness_mohs,
+        "hardness_band": report.hardness_band,
+        "density_g_cm3": mineral.density_g_cm3,
+        "density_band": report.density_band,
+        "hardness_band_cutoffs_mohs": report.classification.hardness,
+        "density_band_cutoffs_g_cm3": report.classification.density,
+        "dominant_element": report.dominant_element,
+        "dominant_element_wt_pct": report.dominant_element_pct,
+        "element_breakdown_wt_pct": report
+            .element_breakdown
+            .iter()
+            .map(|share| json!({"element": share.name, "wt_pct": share.percent}))
+            .collect::<Vec<_>>(),
+        "related_minerals": report
+            .related
+            .iter()
+            .map(|related| related.common_name.as_str())
+            .collect::<Vec<_>>(),
+        "specimens_on_file": report.specimens.len(),
+        "rule_based_summary": report.summary,
+        "rule_based_recommendations": report.recommendations,
+    })
+}
+
+/// Trimmed narrative without blank recommendations; an empty summary or
+/// no recommendations at all is an error, so the caller falls back.
+fn parse_narrative(content: &str) -> Result<LlmNarrative> {
+    let parsed: LlmNarrative =
+        serde_json::from_str(content).context("invalid narrative JSON payload")?;
+    let summary = parsed.summary.trim().to_string();
+    let recommendations = parsed
+        .recommendations
+        .iter()
+        .map(|recommendation| recommendation.trim())
+        .filter(|recommendation| !recommendation.is_empty())
+        .take(MAX_RECOMMENDATIONS)
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    if summary.is_empty() || recommendations.is_empty() {
+        return Err(anyhow!(
+            "narrative has an empty summary or no recommendations"
+        ));
+    }
+    Ok(LlmNarrative {
+        summary,
+        recommendations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_narrative, MAX_RECOMMENDATIONS};
+
+    #[test]
+    fn keeps_a_usable_narrative_and_rejects_an_empty_one() {
+        let recommendations = (1..=8)
+            .map(|n| format!("\" Step {n} \""))
+            .collect::<Vec<_>>()
+            .join(",");
+        let narrative = parse_narrative(&format!(
+            r#"{{"summary": "  Phenakite is very hard. ", "recommendations": ["  ", {recommendations}]}}"#
+        ))
+        .unwrap();
+        assert_eq!(narrative.summary, "Phenakite is very hard.");
+        assert_eq!(narrative.recommendations.len(), MAX_RECOMMENDATIONS);
+        assert_eq!(narrative.recommendations[0], "Step 1");
+
+        assert!(parse_narrative(r#"{"summary": " ", "recommendations": ["Log it."]}"#).is_err());
+        assert!(parse_narrative(r#"{"summary": "Fine.", "recommendations": [""]}"#).is_err());
+        assert!(parse_narrative("not json").is_err());
+    }
+}
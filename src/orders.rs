@@ -0,0 +1,594 @@
+//! Orders that grow out of inquiries. An admin turns an inquiry into a
+//! quoted order on `/admin/inquiries`, then moves it forward one step at a
+//! time on `/admin/orders`: quoted, paid, shipped, delivered. Each order is
+//! one `data/orders/<timestamp>.json` file. The customer follows it on
+//! `/orders/<token>`, keyed by a random token rather than the guessable id.
+//! Shipping and delivery are also recorded in the mineral's chain of
+//! custody.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::HeaderMap,
+    response::{IntoResponse, Redirect, Response},
+    Form,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::info;
+
+use crate::{
+    crypto, csrf, custody, get_mineral,
+    i18n::{ui_text, Language, UiText},
+    inquiries::{self, Inquiry},
+    locale_format::LocaleFormat,
+    mineral_folder_for_slug, require_admin_token, resolve_language,
+    shop::Listing,
+    timestamp_id::{is_valid_timestamp_id, timestamp_id},
+    web::{OrderStatusTemplate, OrdersTemplate, TemplateResponse},
+    AppError, AppState,
+};
+
+pub const ORDERS_DIR: &str = "orders";
+
+/// Random bytes in a customer's status token.
+const TOKEN_BYTES: usize = 24;
+const MAX_SHIPMENT_FIELD_CHARS: usize = 120;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    #[default]
+    Quoted,
+    Paid,
+    Shipped,
+    Delivered,
+}
+
+impl OrderStatus {
+    pub const ALL: [OrderStatus; 4] = [Self::Quoted, Self::Paid, Self::Shipped, Self::Delivered];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Quoted => "quoted",
+            Self::Paid => "paid",
+            Self::Shipped => "shipped",
+            Self::Delivered => "delivered",
+        }
+    }
+
+    pub fn from_param(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str() == value)
+    }
+
+    /// The only status an order may move to from this one.
+    pub fn next(self) -> Option<Self> {
+        match self {
+            Self::Quoted => Some(Self::Paid),
+            Self::Paid => Some(Self::Shipped),
+            Self::Shipped => Some(Self::Delivered),
+            Self::Delivered => None,
+        }
+    }
+
+    pub fn label(self, txt: &UiText) -> &'static str {
+        match self {
+            Self::Quoted => txt.order_status_quoted,
+            Self::Paid => txt.order_status_paid,
+            Self::Shipped => txt.order_status_shipped,
+            Self::Delivered => txt.order_status_delivered,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatusChange {
+    pub status: OrderStatus,
+    pub changed_utc: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Shipment {
+    pub carrier: String,
+    pub tracking_number: String,
+    /// Export permits, invoices, or certificates travelling with the
+    /// parcel; also recorded on the custody event.
+    pub custody_references: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Order {
+    /// File stem under `data/orders/`, the time the order was created; not
+    /// stored in the file itself.
+    #[serde(skip)]
+    pub id: String,
+    /// Key of the customer's status page.
+    pub token: String,
+    pub inquiry_id: String,
+    pub slug: String,
+    pub mineral_name: String,
+    pub customer_name: String,
+    pub email: String,
+    /// The inquiry's page language; the status page link uses it.
+    pub language: String,
+    pub quantity: u32,
+    /// Per item.
+    pub unit_price: f64,
+    pub currency: String,
+    pub status: OrderStatus,
+    /// Oldest first, starting with `quoted`.
+    pub history: Vec<StatusChange>,
+    pub shipment: Option<Shipment>,
+    pub created_utc: String,
+}
+
+impl Order {
+    pub fn total(&self) -> f64 {
+        self.unit_price * f64::from(self.quantity)
+    }
+
+    /// `/es/orders/<token>` in the customer's language.
+    pub fn status_path(&self) -> String {
+        match Language::from_code(&self.language).filter(|language| *language != Language::En) {
+            Some(language) => format!("/{}/orders/{}", language.code(), self.token),
+            None => format!("/orders/{}", self.token),
+        }
+    }
+
+    fn changed_utc(&self, status: OrderStatus) -> Option<&str> {
+        self.history
+            .iter()
+            .find(|change| change.status == status)
+            .map(|change| change.changed_utc.as_str())
+    }
+}
+
+/// One step of the customer's progress bar.
+#[derive(Debug, Clone)]
+pub struct OrderStep {
+    pub label: String,
+    pub reached: bool,
+    /// Localized; blank for steps not reached yet.
+    pub changed: String,
+}
+
+/// The admin's quote as typed; blank price fields fall back to the
+/// mineral's shop listing.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct QuoteForm {
+    pub csrf_token: String,
+    pub unit_price: String,
+    pub currency: String,
+    pub quantity: String,
+}
+
+impl QuoteForm {
+    fn to_order(
+        &self,
+        inquiry: &Inquiry,
+        listing: &Listing,
+        token: String,
+        created_at: DateTime<Utc>,
+    ) -> Result<Order, String> {
+        let currency = match self.currency.trim() {
+            "" => listing.currency.as_str(),
+            currency => currency,
+        };
+        let quote = Listing::parse(false, &self.unit_price, currency, "")?;
+        let (unit_price, currency) = match quote.price {
+            Some(price) => (price, quote.currency),
+            None => (
+                listing
+                    .price
+                    .ok_or("a unit price is required; the mineral has no shop price")?,
+                listing.currency.clone(),
+            ),
+        };
+        let quantity = match self.quantity.trim() {
+            "" => 1,
+            value => value
+                .parse::<u32>()
+                .ok()
+                .filter(|quantity| *quantity > 0)
+                .ok_or("quantity must be a whole number of at least 1")?,
+        };
+
+        Ok(Order {
+            id: timestamp_id(&created_at),
+            token,
+            inquiry_id: inquiry.id.clone(),
+            slug: inquiry.slug.clone(),
+            mineral_name: inquiry.mineral_name.clone(),
+            customer_name: inquiry.name.clone(),
+            email: inquiry.email.clone(),
+            language: inquiry.language.clone(),
+            quantity,
+            unit_price,
+            currency,
+            status: OrderStatus::Quoted,
+            history: vec![StatusChange {
+                status: OrderStatus::Quoted,
+                changed_utc: created_at.to_rfc3339(),
+            }],
+            shipment: None,
+            created_utc: created_at.to_rfc3339(),
+        })
+    }
+}
+
+/// Moves an order to `status`; shipping also takes the parcel details.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StatusForm {
+    pub csrf_token: String,
+    pub status: String,
+    pub carrier: String,
+    pub tracking_number: String,
+    /// One per line or comma-separated.
+    pub custody_references: String,
+}
+
+impl StatusForm {
+    fn apply(&self, order: &mut Order, changed_at: DateTime<Utc>) -> Result<(), String> {
+        let status = OrderStatus::from_param(self.status.trim())
+            .ok_or_else(|| format!("unknown order status '{}'", self.status.trim()))?;
+        if order.status.next() != Some(status) {
+            return Err(format!(
+                "order {} is {} and cannot become {}",
+                order.id,
+                order.status.as_str(),
+                status.as_str()
+            ));
+        }
+        if status == OrderStatus::Shipped {
+            let carrier = self.carrier.trim();
+            let tracking_number = self.tracking_number.trim();
+            if carrier.is_empty() || tracking_number.is_empty() {
+                return Err("shipping needs a carrier and a tracking number".to_string());
+            }
+            let custody_references = self
+                .custody_references
+                .split(['\n', ','])
+                .map(str::trim)
+                .filter(|reference| !reference.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>();
+            if [carrier, tracking_number]
+                .into_iter()
+                .chain(custody_references.iter().map(String::as_str))
+                .any(|field| field.chars().count() > MAX_SHIPMENT_FIELD_CHARS)
+            {
+                return Err(format!(
+                    "shipment fields are limited to {MAX_SHIPMENT_FIELD_CHARS} characters"
+                ));
+            }
+            order.shipment = Some(Shipment {
+                carrier: carrier.to_string(),
+                tracking_number: tracking_number.to_string(),
+                custody_references,
+            });
+        }
+        order.status = status;
+        order.history.push(StatusChange {
+            status,
+            changed_utc: changed_at.to_rfc3339(),
+        });
+        Ok(())
+    }
+}
+
+/// Turns an inquiry into a quoted order and marks the inquiry handled.
+pub async fn create_from_inquiry(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(inquiry_id): AxumPath<String>,
+    Form(form): Form<QuoteForm>,
+) -> Result<Response, AppError> {
+    csrf::verify(&state, &headers, &form.csrf_token)?;
+    let inquiry = inquiries::find(&state, &inquiry_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("inquiry '{inquiry_id}' not found")))?;
+    let listing = get_mineral(&state, Language::En, &inquiry.slug)
+        .await
+        .map(|mineral| mineral.listing)
+        .unwrap_or_default();
+    let order = match form.to_order(
+        &inquiry,
+        &listing,
+        crypto::random_hex(TOKEN_BYTES)?,
+        Utc::now(),
+    ) {
+        Ok(order) => order,
+        Err(message) => return orders_response(&state, &headers, Some(message)).await,
+    };
+
+    write(&orders_dir(&state), &order, true).await?;
+    inquiries::set_handled(&state, &inquiry_id, true).await?;
+    info!("created order {} from inquiry {inquiry_id}", order.id);
+    Ok(Redirect::to(&format!("/admin/orders#{}", order.id)).into_response())
+}
+
+pub async fn orders_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    require_admin_token(&state, &headers)?;
+    orders_response(&state, &headers, None).await
+}
+
+pub async fn change_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(id): AxumPath<String>,
+    Form(form): Form<StatusForm>,
+) -> Result<Response, AppError> {
+    csrf::verify(&state, &headers, &form.csrf_token)?;
+    let dir = orders_dir(&state);
+    let mut order = read(&dir, &id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("order '{id}' not found")))?;
+    if let Err(message) = form.apply(&mut order, Utc::now()) {
+        return orders_response(&state, &headers, Some(message)).await;
+    }
+
+    record_custody(&state, &order).await?;
+    write(&dir, &order, false).await?;
+    info!("order {id} is now {}", order.status.as_str());
+    Ok(Redirect::to(&format!("/admin/orders#{id}")).into_response())
+}
+
+/// The customer's view: progress, shipment, and totals, in the page
+/// language.
+pub async fn order_status_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(token): AxumPath<String>,
+) -> Result<TemplateResponse<OrderStatusTemplate>, AppError> {
+    let not_found = || AppError::NotFound("order not found".to_string());
+    if token.len() != TOKEN_BYTES * 2 || !token.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(not_found());
+    }
+    let order = list(&orders_dir(&state))
+        .await?
+        .into_iter()
+        .find(|order| csrf::tokens_match(&order.token, &token))
+        .ok_or_else(not_found)?;
+
+    let language = resolve_language(&state, &headers);
+    let txt = ui_text(language);
+    let locale = LocaleFormat::new(language);
+    let steps = OrderStatus::ALL
+        .into_iter()
+        .map(|status| {
+            let changed = order.changed_utc(status);
+            OrderStep {
+                label: status.label(&txt).to_string(),
+                reached: changed.is_some(),
+                changed: changed
+                    .map(|time| locale.timestamp(time))
+                    .unwrap_or_default(),
+            }
+        })
+        .collect();
+    Ok(TemplateResponse(OrderStatusTemplate {
+        lang_code: language.code().to_string(),
+        lang_dir: language.dir().to_string(),
+        status_label: order.status.label(&txt).to_string(),
+        txt,
+        locale,
+        order,
+        steps,
+    }))
+}
+
+async fn orders_response(
+    state: &AppState,
+    headers: &HeaderMap,
+    error_message: Option<String>,
+) -> Result<Response, AppError> {
+    let language = resolve_language(state, headers);
+    Ok(TemplateResponse(OrdersTemplate {
+        lang_code: language.code().to_string(),
+        lang_dir: language.dir().to_string(),
+        txt: ui_text(language),
+        orders: list(&orders_dir(state)).await?,
+        csrf_token: csrf::session_token(state, headers).unwrap_or_default(),
+        error_message,
+    })
+    .into_response())
+}
+
+/// Shipping hands the mineral to the carrier and delivery to the customer;
+/// both go into the mineral's custody log.
+async fn record_custody(state: &AppState, order: &Order) -> Result<(), AppError> {
+    let (actor, document_reference, note) = match (order.status, &order.shipment) {
+        (OrderStatus::Shipped, Some(shipment)) => (
+            shipment.carrier.clone(),
+            [shipment.tracking_number.clone()]
+                .into_iter()
+                .chain(shipment.custody_references.iter().cloned())
+                .collect::<Vec<_>>()
+                .join("; "),
+            format!("Shipped to {} (order {})", order.customer_name, order.id),
+        ),
+        (OrderStatus::Delivered, shipment) => (
+            order.customer_name.clone(),
+            shipment
+                .as_ref()
+                .map(|shipment| shipment.tracking_number.clone())
+                .unwrap_or_default(),
+            format!("Delivered (order {})", order.id),
+        ),
+        _ => return Ok(()),
+    };
+    // A mineral deleted since the quote has no log left to extend.
+    let Ok(folder_path) = mineral_folder_for_slug(state, Language::En, &order.slug).await else {
+        return Ok(());
+    };
+    custody::record(&folder_path, &actor, &document_reference, &note).await
+}
+
+fn orders_dir(state: &AppState) -> PathBuf {
+    state.data_root.join(ORDERS_DIR)
+}
+
+/// Newest first; unreadable files are skipped.
+async fn list(dir: &Path) -> Result<Vec<Order>, AppError> {
+    let mut entries = match fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(AppError::Internal(
+                anyhow::Error::new(err).context(format!("failed to read {}", dir.display())),
+            ))
+        }
+    };
+
+    let mut orders = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .with_context(|| format!("failed to read {}", dir.display()))?
+    {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(id) = file_name.strip_suffix(".json") else {
+            continue;
+        };
+        if let Ok(Some(order)) = read(dir, id).await {
+            orders.push(order);
+        }
+    }
+    orders.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(orders)
+}
+
+async fn read(dir: &Path, id: &str) -> Result<Option<Order>, AppError> {
+    if !is_valid_timestamp_id(id) {
+        return Ok(None);
+    }
+    let path = dir.join(format!("{id}.json"));
+    let raw = match fs::read_to_string(&path).await {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(AppError::Internal(
+                anyhow::Error::new(err).context(format!("failed to read {}", path.display())),
+            ))
+        }
+    };
+    let order = serde_json::from_str::<Order>(&raw)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(Some(Order {
+        id: id.to_string(),
+        ..order
+    }))
+}
+
+/// `create` refuses to replace an existing file; updates go through a
+/// temporary file so a crash cannot leave half an order behind.
+async fn write(dir: &Path, order: &Order, create: bool) -> Result<(), AppError> {
+    fs::create_dir_all(dir)
+        .await
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+    let path = dir.join(format!("{}.json", order.id));
+    let json = serde_json::to_string_pretty(order).context("failed to serialize order")?;
+    if create {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await
+            .with_context(|| format!("failed to create {}", path.display()))?;
+        file.write_all(json.as_bytes())
+            .await
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        return Ok(());
+    }
+
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, json)
+        .await
+        .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, &path)
+        .await
+        .with_context(|| format!("failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{OrderStatus, QuoteForm, StatusForm};
+    use crate::{inquiries::Inquiry, shop::Listing};
+
+    #[test]
+    fn quotes_fall_back_to_the_listing_and_statuses_only_move_forward() {
+        let inquiry = Inquiry {
+            id: "20261016T160506000000Z".to_string(),
+            slug: "mineral.silicates.0x5b6b8000".to_string(),
+            name: "Ana".to_string(),
+            language: "es".to_string(),
+            ..Inquiry::default()
+        };
+        let listing = Listing::parse(true, "120", "EUR", "3").unwrap();
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 16, 5, 6).unwrap();
+        let token = "ab".repeat(24);
+
+        let mut order = QuoteForm {
+            quantity: "2".to_string(),
+            ..QuoteForm::default()
+        }
+        .to_order(&inquiry, &listing, token.clone(), now)
+        .unwrap();
+        assert_eq!((order.unit_price, order.currency.as_str()), (120.0, "EUR"));
+        assert_eq!(order.total(), 240.0);
+        assert_eq!(order.status_path(), format!("/es/orders/{token}"));
+
+        let quoted = QuoteForm {
+            unit_price: "99.5".to_string(),
+            currency: "usd".to_string(),
+            ..QuoteForm::default()
+        }
+        .to_order(&inquiry, &listing, token.clone(), now)
+        .unwrap();
+        assert_eq!((quoted.unit_price, quoted.quantity), (99.5, 1));
+        assert_eq!(quoted.currency, "USD");
+        assert!(QuoteForm::default()
+            .to_order(&inquiry, &Listing::default(), token, now)
+            .is_err());
+
+        let step = |status: &str| StatusForm {
+            status: status.to_string(),
+            ..StatusForm::default()
+        };
+        assert!(step("shipped").apply(&mut order, now).is_err());
+        step("paid").apply(&mut order, now).unwrap();
+        assert!(step("shipped").apply(&mut order, now).is_err());
+        StatusForm {
+            carrier: "DHL".to_string(),
+            tracking_number: "JD0146".to_string(),
+            custody_references: "Export permit 88/2024\nInvoice 17".to_string(),
+            ..step("shipped")
+        }
+        .apply(&mut order, now)
+        .unwrap();
+        assert_eq!(
+            order.shipment.as_ref().unwrap().custody_references,
+            vec!["Export permit 88/2024", "Invoice 17"]
+        );
+        step("delivered").apply(&mut order, now).unwrap();
+        assert_eq!(order.status, OrderStatus::Delivered);
+        assert_eq!(order.history.len(), 4);
+        assert!(step("delivered").apply(&mut order, now).is_err());
+    }
+}
@@ -1,108 +1,815 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::{anyhow, Context, Result};
 use askama::Template;
-use tokio::{fs, process::Command};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::Utc;
+use image::{ImageFormat, Luma};
+use metrics::{counter, histogram};
+use qrcode::QrCode;
+use serde::Serialize;
+use tokio::{fs, process::Command, sync::watch};
+use tracing::{info, warn};
 
-use crate::agent::{ElementShare, MineralReport};
+use crate::agent::{
+    density_band_names, element_share, hardness_band_names, Classification, ComparisonReport,
+    ElementShare, MineralReport, Narrative,
+};
+use crate::charts;
+use crate::geo::MapTile;
 use crate::i18n::{ui_text, Language, UiText};
+use crate::locale_format::LocaleFormat;
+use crate::markdown;
+use crate::models::Mineral;
+use crate::report_runs::{self, CERTIFICATES_DIR, COMPARISONS_DIR, REPORTS_DIR};
+use crate::report_templates::ReportLayout;
+use crate::signing::{write_signature, ReportSigner, SIGNATURE_FILE};
+use crate::specimens::Specimen;
+use crate::spectra::Spectrum;
+use crate::standalone;
+use crate::units::UnitSystem;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PdfBackendKind {
+    #[default]
+    Latex,
+    Typst,
+    Chromium,
+    Weasyprint,
+}
+
+impl PdfBackendKind {
+    pub fn all() -> &'static [PdfBackendKind] {
+        &[
+            PdfBackendKind::Latex,
+            PdfBackendKind::Typst,
+            PdfBackendKind::Chromium,
+            PdfBackendKind::Weasyprint,
+        ]
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PdfBackendKind::Latex => "latex",
+            PdfBackendKind::Typst => "typst",
+            PdfBackendKind::Chromium => "chromium",
+            PdfBackendKind::Weasyprint => "weasyprint",
+        }
+    }
+
+    /// Template the backend turns into `report.pdf`.
+    pub fn source_file(self) -> &'static str {
+        match self {
+            PdfBackendKind::Latex => "report.tex",
+            PdfBackendKind::Typst => "report.typ",
+            PdfBackendKind::Chromium | PdfBackendKind::Weasyprint => "report.html",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PdfBackendKind::Latex => "LaTeX (XeLaTeX)",
+            PdfBackendKind::Typst => "Typst",
+            PdfBackendKind::Chromium => "Chromium (headless)",
+            PdfBackendKind::Weasyprint => "WeasyPrint",
+        }
+    }
+
+    pub fn from_code(value: &str) -> Option<Self> {
+        let value = value.trim().to_ascii_lowercase();
+        Self::all()
+            .iter()
+            .copied()
+            .find(|kind| kind.as_str() == value)
+    }
+}
+
+/// Compiles a rendered report into `report.pdf` inside `run_dir`.
+/// `source` is the contents of the backend's `source_file()`; `report.html`
+/// and the QR code image, if the report has one, have already been written
+/// there when `render_pdf` is called.
+#[async_trait]
+pub trait PdfBackend: Send + Sync {
+    /// Executable the backend shells out to.
+    fn program(&self) -> &str;
+
+    async fn render_pdf(&self, run_dir: &Path, source: &str) -> Result<()>;
+}
+
+/// Sources of one report, rendered before its run folder is created.
+struct RenderedReport {
+    html: String,
+    /// What the backend compiles; the HTML itself for the HTML engines.
+    source: String,
+    /// Files the source includes (`QR_FILE`, `CHART_FILE`, spectrum
+    /// plots), written next to it.
+    files: Vec<(String, Vec<u8>)>,
+}
+
+/// Bar colors of the comparison chart, one per compared mineral.
+const SERIES_COLORS: [&str; 5] = ["1F77B4", "D62728", "2CA02C", "FF7F0E", "9467BD"];
+
+/// QR code of the mineral page, next to the LaTeX and Typst sources that
+/// include it. The HTML report embeds its own copy.
+const QR_FILE: &str = "report_qr.png";
+
+/// Where the mineral folder's photos are from a run folder
+/// (`reports/<run>/`), for the LaTeX and Typst sources.
+const MINERAL_DIR_FROM_RUN: &str = "../../";
+
+/// How long aborted runs get to kill their tool and clean up.
+const ABORT_CLEANUP_WAIT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Open,
+    Draining,
+    Aborted,
+}
 
 #[derive(Clone)]
 pub struct PdfGenerator {
     minerals_root: PathBuf,
+    data_root: PathBuf,
+    /// For inlining `/static/` files into standalone reports.
+    static_root: PathBuf,
+    default_backend: PdfBackendKind,
+    backends: HashMap<PdfBackendKind, Arc<dyn PdfBackend>>,
+    public_url: String,
+    /// Finished runs kept per mineral; `0` keeps all.
+    retention: usize,
+    signer: ReportSigner,
+    phase: Arc<watch::Sender<Phase>>,
+    active: Arc<watch::Sender<usize>>,
+}
+
+/// Counts one run as in flight until dropped.
+struct ActiveRun(Arc<watch::Sender<usize>>);
+
+impl ActiveRun {
+    fn start(active: &Arc<watch::Sender<usize>>) -> Self {
+        active.send_modify(|count| *count += 1);
+        Self(Arc::clone(active))
+    }
+}
+
+impl Drop for ActiveRun {
+    fn drop(&mut self) {
+        self.0.send_modify(|count| *count -= 1);
+    }
+}
+
+/// Canonical page of the reported mineral, printed on the report as a QR
+/// code so a paper copy leads back to the live record.
+#[derive(Debug, Clone)]
+pub struct ReportLink {
+    pub url: String,
+    /// PNG bytes of the QR code.
+    pub qr_png: Vec<u8>,
+}
+
+impl ReportLink {
+    pub fn new(url: String) -> Result<Self> {
+        let code = QrCode::new(url.as_bytes())
+            .with_context(|| format!("failed to encode '{url}' as a QR code"))?;
+        let image = code.render::<Luma<u8>>().module_dimensions(8, 8).build();
+        let mut qr_png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut qr_png), ImageFormat::Png)
+            .context("failed to encode QR code image")?;
+        Ok(Self { url, qr_png })
+    }
+
+    fn data_uri(&self) -> String {
+        format!("data:image/png;base64,{}", BASE64.encode(&self.qr_png))
+    }
+}
+
+/// What a certificate of authenticity vouches for: one specimen of a
+/// mineral, signed off by `issued_by`.
+#[derive(Debug, Clone)]
+pub struct Certificate {
+    pub mineral: Mineral,
+    pub specimen: Specimen,
+    /// Name printed under the signature line; may be empty.
+    pub issued_by: String,
+    /// RFC 3339.
+    pub issued_utc: String,
+    /// Units the density and the specimen's size are shown in.
+    pub units: UnitSystem,
 }
 
 #[derive(Debug, Clone)]
 pub struct GeneratedArtifacts {
+    /// Run folder under `reports/` (or `certificates/`, `comparisons/`).
+    pub run_id: String,
     pub pdf_path: String,
     pub html_path: String,
+    pub signature_path: String,
+    /// The run folder on disk, for attaching its files to an email.
+    pub run_dir: PathBuf,
 }
 
 impl PdfGenerator {
-    pub fn new(minerals_root: impl Into<PathBuf>) -> Self {
+    pub fn new(
+        minerals_root: impl Into<PathBuf>,
+        static_root: impl Into<PathBuf>,
+        default_backend: PdfBackendKind,
+        chromium_bin: impl Into<String>,
+        public_url: impl Into<String>,
+        retention: usize,
+        signer: ReportSigner,
+    ) -> Self {
+        let minerals_root = minerals_root.into();
+        let static_root = static_root.into();
+        let data_root = minerals_root
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| minerals_root.clone());
+
+        let mut backends: HashMap<PdfBackendKind, Arc<dyn PdfBackend>> = HashMap::new();
+        backends.insert(PdfBackendKind::Latex, Arc::new(LatexBackend));
+        backends.insert(PdfBackendKind::Typst, Arc::new(TypstBackend));
+        backends.insert(
+            PdfBackendKind::Chromium,
+            Arc::new(HtmlToPdfBackend {
+                kind: PdfBackendKind::Chromium,
+                program: chromium_bin.into(),
+                static_root: static_root.clone(),
+                data_root: data_root.clone(),
+            }),
+        );
+        backends.insert(
+            PdfBackendKind::Weasyprint,
+            Arc::new(HtmlToPdfBackend {
+                kind: PdfBackendKind::Weasyprint,
+                program: "weasyprint".to_string(),
+                static_root: static_root.clone(),
+                data_root: data_root.clone(),
+            }),
+        );
+
         Self {
-            minerals_root: minerals_root.into(),
+            minerals_root,
+            data_root,
+            static_root,
+            default_backend,
+            backends,
+            public_url: public_url.into(),
+            retention,
+            signer,
+            phase: Arc::new(watch::Sender::new(Phase::Open)),
+            active: Arc::new(watch::Sender::new(0)),
         }
     }
 
+    pub fn default_backend(&self) -> PdfBackendKind {
+        self.default_backend
+    }
+
+    pub fn signer(&self) -> &ReportSigner {
+        &self.signer
+    }
+
+    /// Public page of the mineral in the report's language, as encoded in
+    /// report QR codes.
+    pub fn mineral_url(&self, slug: &str, language: Language) -> String {
+        self.public_url(&format!("/{}/minerals/{slug}", language.code()))
+    }
+
+    /// Verification endpoint of one certificate run, as encoded in the
+    /// certificate's QR code.
+    pub fn certificate_verify_url(&self, slug: &str, run_id: &str) -> String {
+        self.public_url(&format!("/api/certificates/{slug}/verify?run={run_id}"))
+    }
+
+    /// `path` (from its leading `/`) on the public site.
+    pub fn public_url(&self, path: &str) -> String {
+        format!("{}{path}", self.public_url)
+    }
+
+    pub fn backend_program(&self, backend: PdfBackendKind) -> Option<&str> {
+        self.backends
+            .get(&backend)
+            .map(|renderer| renderer.program())
+    }
+
     pub async fn generate_pdf(
         &self,
         report: &MineralReport,
+        layout: &ReportLayout,
+        language: Language,
+        backend: PdfBackendKind,
+    ) -> Result<GeneratedArtifacts> {
+        let folder_name = &report.mineral.folder_name;
+        self.generate(
+            &self.minerals_root.join(folder_name).join(REPORTS_DIR),
+            &format!("/data/minerals/{folder_name}/{REPORTS_DIR}"),
+            backend,
+            self.retention,
+            layout.is_standalone(),
+            |_| {
+                let link = ReportLink::new(self.mineral_url(&report.mineral.slug, language))?;
+                render_mineral(report, &link, layout, language, backend)
+            },
+        )
+        .await
+    }
+
+    /// `report.html` for `report` in the built-in layout, with its files
+    /// inlined, rendered in memory: no run folder, PDF, or signature. For
+    /// previewing a draft before it is published.
+    pub async fn preview_html(&self, report: &MineralReport, language: Language) -> Result<String> {
+        let link = ReportLink::new(self.mineral_url(&report.mineral.slug, language))?;
+        let html = render_source(
+            &ReportLayout::default(),
+            "report.html",
+            &ReportHtmlTemplate::from_report(report, &link, language),
+        )?;
+        Ok(self.standalone_html(&html).await)
+    }
+
+    /// Renders a comparison report into `data/comparisons/<run>/`.
+    pub async fn generate_comparison(
+        &self,
+        report: &ComparisonReport,
         language: Language,
+        backend: PdfBackendKind,
     ) -> Result<GeneratedArtifacts> {
-        let run_dir = self.minerals_root.join(&report.mineral.folder_name);
-        fs::create_dir_all(&run_dir)
+        let urls = report
+            .minerals
+            .iter()
+            .map(|mineral| self.mineral_url(&mineral.mineral.slug, language))
+            .collect::<Vec<_>>();
+        self.generate(
+            &self.data_root.join(COMPARISONS_DIR),
+            &format!("/data/{COMPARISONS_DIR}"),
+            backend,
+            self.retention,
+            false,
+            |_| render_comparison(report, &urls, language, backend),
+        )
+        .await
+    }
+
+    /// Renders a specimen certificate into the mineral's `certificates/`
+    /// folder. Certificates are never pruned, so the verification link
+    /// printed on each one keeps working.
+    pub async fn generate_certificate(
+        &self,
+        certificate: &Certificate,
+        language: Language,
+        backend: PdfBackendKind,
+    ) -> Result<GeneratedArtifacts> {
+        let mineral = &certificate.mineral;
+        let folder_name = &mineral.folder_name;
+        self.generate(
+            &self.minerals_root.join(folder_name).join(CERTIFICATES_DIR),
+            &format!("/data/minerals/{folder_name}/{CERTIFICATES_DIR}"),
+            backend,
+            0,
+            false,
+            |run_id| {
+                let link = ReportLink::new(self.certificate_verify_url(&mineral.slug, run_id))?;
+                render_certificate(certificate, &link, language, backend)
+            },
+        )
+        .await
+    }
+
+    /// Renders with `render`, which gets the run id, into a new run folder
+    /// under `runs_dir` whose files are served below `public_dir`. `keep`
+    /// is the retention applied to `runs_dir` afterwards; `standalone`
+    /// inlines the files `report.html` links to.
+    async fn generate(
+        &self,
+        runs_dir: &Path,
+        public_dir: &str,
+        backend: PdfBackendKind,
+        keep: usize,
+        standalone: bool,
+        render: impl FnOnce(&str) -> Result<RenderedReport>,
+    ) -> Result<GeneratedArtifacts> {
+        if *self.phase.borrow() != Phase::Open {
+            return Err(anyhow!(
+                "server is shutting down; PDF generation is unavailable"
+            ));
+        }
+        let _run = ActiveRun::start(&self.active);
+        let mut phase = self.phase.subscribe();
+
+        let started = Instant::now();
+        let result = async {
+            let (run_id, run_dir) = report_runs::create_run_dir(runs_dir, Utc::now()).await?;
+            let rendered = match render(&run_id) {
+                Ok(rendered) if standalone => Ok(RenderedReport {
+                    html: self.standalone_html(&rendered.html).await,
+                    ..rendered
+                }),
+                rendered => rendered,
+            };
+            let result = match rendered {
+                Ok(rendered) => tokio::select! {
+                    result = self.render_artifacts(&rendered, backend, public_dir, &run_id, &run_dir) => result,
+                    _ = wait_until(&mut phase, |phase| *phase == Phase::Aborted) => {
+                        Err(anyhow!("PDF generation was aborted by server shutdown"))
+                    }
+                },
+                Err(err) => Err(err),
+            };
+            self.finish_run(runs_dir, &run_dir, keep, result.is_ok()).await;
+            result
+        }
+        .await;
+        histogram!("pdf_generation_duration_seconds", "backend" => backend.as_str())
+            .record(started.elapsed().as_secs_f64());
+        if result.is_err() {
+            counter!("pdf_generation_failures_total", "backend" => backend.as_str()).increment(1);
+        }
+        result
+    }
+
+    /// `html` with its stylesheet, images, and script inlined.
+    async fn standalone_html(&self, html: &str) -> String {
+        standalone::inline(
+            html,
+            &standalone::Roots {
+                static_root: &self.static_root,
+                data_root: &self.data_root,
+                public_url: &self.public_url,
+            },
+        )
+        .await
+    }
+
+    /// Applies the retention policy after a successful run; a failed or
+    /// aborted run's folder is removed, since the tool output is already in
+    /// the error.
+    async fn finish_run(&self, runs_dir: &Path, run_dir: &Path, keep: usize, succeeded: bool) {
+        if !succeeded {
+            if let Err(err) = fs::remove_dir_all(run_dir).await {
+                warn!("failed to remove {}: {err}", run_dir.display());
+            }
+            return;
+        }
+        match report_runs::prune(runs_dir, keep).await {
+            Ok(0) => {}
+            Ok(removed) => info!("pruned {removed} old reports in {}", runs_dir.display()),
+            Err(err) => warn!("failed to prune reports in {}: {err:#}", runs_dir.display()),
+        }
+    }
+
+    /// Refuses new runs and waits up to `grace` for the active ones. Runs
+    /// still going at the deadline are aborted: their tool is killed and
+    /// their run folder removed.
+    pub async fn drain(&self, grace: Duration) {
+        self.phase.send_replace(Phase::Draining);
+        let mut active = self.active.subscribe();
+        if tokio::time::timeout(grace, wait_until(&mut active, |count| *count == 0))
             .await
-            .with_context(|| format!("failed to create output directory {}", run_dir.display()))?;
+            .is_ok()
+        {
+            return;
+        }
+
+        warn!(
+            "aborting {} PDF runs still active at the shutdown deadline",
+            *active.borrow()
+        );
+        self.phase.send_replace(Phase::Aborted);
+        let _ = tokio::time::timeout(
+            ABORT_CLEANUP_WAIT,
+            wait_until(&mut active, |count| *count == 0),
+        )
+        .await;
+    }
+
+    async fn render_artifacts(
+        &self,
+        rendered: &RenderedReport,
+        backend: PdfBackendKind,
+        public_dir: &str,
+        run_id: &str,
+        run_dir: &Path,
+    ) -> Result<GeneratedArtifacts> {
+        let renderer = self
+            .backends
+            .get(&backend)
+            .ok_or_else(|| anyhow!("pdf backend '{}' is not registered", backend.as_str()))?;
+
+        for (name, contents) in &rendered.files {
+            let file = run_dir.join(name);
+            fs::write(&file, contents)
+                .await
+                .with_context(|| format!("failed to write {}", file.display()))?;
+        }
 
-        let html = ReportHtmlTemplate::from_report(report, language).render()?;
         let html_file = run_dir.join("report.html");
-        fs::write(&html_file, html)
+        fs::write(&html_file, &rendered.html)
             .await
             .with_context(|| format!("failed to write {}", html_file.display()))?;
 
-        let tex = ReportTexTemplate::from_report(report, language).render()?;
+        let pdf_file = run_dir.join("report.pdf");
+        renderer.render_pdf(run_dir, &rendered.source).await?;
+
+        if !pdf_file.exists() {
+            return Err(anyhow!(
+                "{} completed but {} was not generated",
+                backend.as_str(),
+                pdf_file.display()
+            ));
+        }
+        write_signature(&self.signer, run_dir).await?;
+
+        let run_path = format!("{public_dir}/{run_id}");
+        Ok(GeneratedArtifacts {
+            run_id: run_id.to_string(),
+            pdf_path: format!("{run_path}/report.pdf"),
+            html_path: format!("{run_path}/report.html"),
+            signature_path: format!("{run_path}/{SIGNATURE_FILE}"),
+            run_dir: run_dir.to_path_buf(),
+        })
+    }
+}
+
+struct LatexBackend;
+
+#[async_trait]
+impl PdfBackend for LatexBackend {
+    fn program(&self) -> &str {
+        "latexmk"
+    }
+
+    async fn render_pdf(&self, run_dir: &Path, source: &str) -> Result<()> {
         let tex_file = run_dir.join("report.tex");
-        fs::write(&tex_file, tex)
+        fs::write(&tex_file, source)
             .await
             .with_context(|| format!("failed to write {}", tex_file.display()))?;
 
-        let output = Command::new("latexmk")
+        let mut command = Command::new(self.program());
+        command
             .arg("-xelatex")
             .arg("-interaction=nonstopmode")
             .arg("-halt-on-error")
-            .arg("report.tex")
-            .current_dir(&run_dir)
-            .output()
+            .arg("report.tex");
+        run_tool(
+            command,
+            run_dir,
+            "latexmk",
+            "install latexmk + XeLaTeX + required fonts",
+        )
+        .await
+    }
+}
+
+struct TypstBackend;
+
+#[async_trait]
+impl PdfBackend for TypstBackend {
+    fn program(&self) -> &str {
+        "typst"
+    }
+
+    async fn render_pdf(&self, run_dir: &Path, source: &str) -> Result<()> {
+        let typ_file = run_dir.join("report.typ");
+        fs::write(&typ_file, source)
             .await
-            .with_context(|| {
-                "failed to execute 'latexmk'; install latexmk + XeLaTeX + required fonts"
-            })?;
+            .with_context(|| format!("failed to write {}", typ_file.display()))?;
 
-        if !output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow!(
-                "latexmk failed in {}\nstdout:\n{}\nstderr:\n{}",
-                run_dir.display(),
-                stdout.trim(),
-                stderr.trim()
-            ));
-        }
+        let mut command = Command::new(self.program());
+        command
+            .arg("compile")
+            .arg("--root")
+            .arg(MINERAL_DIR_FROM_RUN)
+            .arg("report.typ")
+            .arg("report.pdf");
+        run_tool(command, run_dir, "typst", "install the typst CLI").await
+    }
+}
 
-        let pdf_file = run_dir.join("report.pdf");
-        if !pdf_file.exists() {
-            return Err(anyhow!(
-                "latexmk completed but {} was not generated",
-                pdf_file.display()
-            ));
+/// Prints the HTML report through an external HTML engine. Root-relative
+/// `/static/` and `/data/` URLs only resolve through the web server, so the
+/// engine gets a copy with those rewritten to `file://` URLs.
+struct HtmlToPdfBackend {
+    kind: PdfBackendKind,
+    program: String,
+    static_root: PathBuf,
+    data_root: PathBuf,
+}
+
+#[async_trait]
+impl PdfBackend for HtmlToPdfBackend {
+    fn program(&self) -> &str {
+        &self.program
+    }
+
+    async fn render_pdf(&self, run_dir: &Path, source: &str) -> Result<()> {
+        let static_root = absolute_path(&self.static_root)?;
+        let data_root = absolute_path(&self.data_root)?;
+        let html = source
+            .replace(
+                "=\"/static/",
+                &format!("=\"file://{}/", static_root.display()),
+            )
+            .replace("=\"/data/", &format!("=\"file://{}/", data_root.display()));
+
+        let print_file = absolute_path(&run_dir.join("report.print.html"))?;
+        fs::write(&print_file, html)
+            .await
+            .with_context(|| format!("failed to write {}", print_file.display()))?;
+
+        let mut command = Command::new(&self.program);
+        match self.kind {
+            PdfBackendKind::Weasyprint => {
+                command.arg(&print_file).arg("report.pdf");
+            }
+            _ => {
+                command
+                    .arg("--headless")
+                    .arg("--disable-gpu")
+                    .arg("--no-sandbox")
+                    .arg("--no-pdf-header-footer")
+                    .arg("--print-to-pdf=report.pdf")
+                    .arg(format!("file://{}", print_file.display()));
+            }
         }
 
-        Ok(GeneratedArtifacts {
-            pdf_path: format!("/data/minerals/{}/report.pdf", report.mineral.folder_name),
-            html_path: format!("/data/minerals/{}/report.html", report.mineral.folder_name),
-        })
+        let result = run_tool(
+            command,
+            run_dir,
+            &self.program,
+            "install it or pick another PDF backend",
+        )
+        .await;
+        let _ = fs::remove_file(&print_file).await;
+        result
     }
 }
 
-#[derive(Debug, Clone)]
+async fn run_tool(
+    mut command: Command,
+    run_dir: &Path,
+    program: &str,
+    install_hint: &str,
+) -> Result<()> {
+    // An aborted run drops this future; the tool must not outlive it.
+    let output = command
+        .current_dir(run_dir)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .with_context(|| format!("failed to execute '{program}'; {install_hint}"))?;
+
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!(
+            "{program} failed in {}\nstdout:\n{}\nstderr:\n{}",
+            run_dir.display(),
+            stdout.trim(),
+            stderr.trim()
+        ));
+    }
+
+    Ok(())
+}
+
+fn absolute_path(path: &Path) -> Result<PathBuf> {
+    if path.is_absolute() {
+        return Ok(path.to_path_buf());
+    }
+    Ok(std::env::current_dir()
+        .context("failed to resolve working directory")?
+        .join(path))
+}
+
+#[derive(Debug, Clone, Serialize)]
 struct LatexElementShare {
     name: String,
     percent: String,
+    /// Share of the full bar width, `0.467` for 46.7 wt%.
+    fraction: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 struct HtmlElementShare {
     name: String,
     percent: String,
+    /// Share of the full bar width, `0.467` for 46.7 wt%.
+    fraction: String,
 }
 
-#[derive(Template)]
+/// Related-mineral line; text fields are escaped for the target format
+/// the same way as the rest of the template.
+#[derive(Debug, Clone, Serialize)]
+struct ReportRelated {
+    name: String,
+    family: String,
+    hardness_mohs: String,
+    match_pct: u32,
+}
+
+/// Specimen table row; text fields are escaped like the rest of the
+/// template.
+#[derive(Debug, Clone, Serialize)]
+struct ReportSpecimen {
+    label: String,
+    weight_g: String,
+    dimensions_mm: String,
+    locality: String,
+    acquired_on: String,
+    storage_location: String,
+}
+
+/// Chain-of-custody row; text fields are escaped like the rest of the
+/// template.
+#[derive(Debug, Clone, Serialize)]
+struct ReportCustodyEvent {
+    date: String,
+    actor: String,
+    document_reference: String,
+    note: String,
+}
+
+/// A client-specific field shown after the standard properties, escaped
+/// like the rest of the template.
+#[derive(Debug, Clone, Serialize)]
+struct ReportAttribute {
+    name: String,
+    value: String,
+}
+
+/// Bibliography entry; text fields are escaped like the rest of the
+/// template. `title` is blank for references without one.
+#[derive(Debug, Clone, Serialize)]
+struct ReportReference {
+    title: String,
+    url: String,
+    accessed: String,
+}
+
+/// One row of the derived indices table, escaped like the rest of the
+/// template.
+#[derive(Debug, Clone, Serialize)]
+struct ReportIndex {
+    label: String,
+    value: String,
+    /// Empty for ratios.
+    unit: String,
+}
+
+/// Spectrum plot with its caption, escaped like the rest of the template.
+/// `chart` is a `data:` URI for HTML, a file next to the source for Typst,
+/// and a TikZ picture for LaTeX.
+#[derive(Debug, Clone, Serialize)]
+struct ReportSpectrum {
+    caption: String,
+    chart: String,
+}
+
+/// Collection site; text fields are escaped like the rest of the
+/// template. Absent when the record has no location at all.
+#[derive(Debug, Clone, Serialize)]
+struct ReportProvenance {
+    locality: String,
+    country: String,
+    coordinates: Option<ReportCoordinates>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ReportCoordinates {
+    dms: String,
+    decimal: String,
+    map_url: String,
+    tile: MapTile,
+}
+
+/// Secondary gallery photo. `src` is a path relative to the run
+/// directory for LaTeX/Typst and a public URL for HTML.
+#[derive(Debug, Clone, Serialize)]
+struct ReportImage {
+    src: String,
+    caption: String,
+}
+
+#[derive(Template, Serialize)]
 #[template(path = "report.tex", escape = "none")]
 struct ReportTexTemplate {
     lang_code: String,
     txt: UiText,
     generated_utc: String,
+    /// What wrote the summary and recommendations, e.g. `Rule-based`.
+    narrative: String,
+    /// Canonical page of the mineral and the QR image that encodes it.
+    mineral_url: String,
+    qr_src: String,
     mineral_name: String,
     mineral_family: String,
     description: String,
@@ -111,6 +818,12 @@ struct ReportTexTemplate {
     hardness_band: String,
     density_g_cm3: String,
     density_band: String,
+    /// Band names with the configured cutoffs between them.
+    hardness_scale: String,
+    density_scale: String,
+    strunz_class: String,
+    /// The record's client-specific fields, by name.
+    extra_attributes: Vec<ReportAttribute>,
     crystal_system: String,
     color: String,
     streak: String,
@@ -120,20 +833,95 @@ struct ReportTexTemplate {
     audience: String,
     purpose: String,
     site_context: String,
+    /// Report preset name; blank for none.
+    preset: String,
     summary: String,
     notes: String,
     image_file: Option<String>,
+    gallery: Vec<ReportImage>,
     recommendations: Vec<String>,
+    related: Vec<ReportRelated>,
+    specimens: Vec<ReportSpecimen>,
+    provenance: Option<ReportProvenance>,
+    custody: Vec<ReportCustodyEvent>,
+    /// Bibliography entries, in the record's order.
+    references: Vec<ReportReference>,
+    spectra: Vec<ReportSpectrum>,
     element_breakdown: Vec<LatexElementShare>,
+    derived_indices: Vec<ReportIndex>,
+    /// Localized handling warnings, most severe first.
+    hazards: Vec<String>,
+    /// Bar color of the composition chart drawn beside the breakdown.
+    chart_color: String,
 }
 
-#[derive(Template)]
+#[derive(Template, Serialize)]
+#[template(path = "report.typ", escape = "none")]
+struct ReportTypstTemplate {
+    lang_code: String,
+    lang_dir: String,
+    txt: UiText,
+    generated_utc: String,
+    /// What wrote the summary and recommendations, e.g. `Rule-based`.
+    narrative: String,
+    /// Canonical page of the mineral and the QR image that encodes it.
+    mineral_url: String,
+    qr_src: String,
+    mineral_name: String,
+    mineral_family: String,
+    description: String,
+    formula: String,
+    hardness_mohs: String,
+    hardness_band: String,
+    density_g_cm3: String,
+    density_band: String,
+    /// Band names with the configured cutoffs between them.
+    hardness_scale: String,
+    density_scale: String,
+    strunz_class: String,
+    /// The record's client-specific fields, by name.
+    extra_attributes: Vec<ReportAttribute>,
+    crystal_system: String,
+    color: String,
+    streak: String,
+    luster: String,
+    dominant_element: String,
+    dominant_element_pct: String,
+    audience: String,
+    purpose: String,
+    site_context: String,
+    /// Report preset name; blank for none.
+    preset: String,
+    summary: String,
+    notes: String,
+    image_file: Option<String>,
+    gallery: Vec<ReportImage>,
+    recommendations: Vec<String>,
+    related: Vec<ReportRelated>,
+    specimens: Vec<ReportSpecimen>,
+    provenance: Option<ReportProvenance>,
+    custody: Vec<ReportCustodyEvent>,
+    spectra: Vec<ReportSpectrum>,
+    element_breakdown: Vec<HtmlElementShare>,
+    derived_indices: Vec<ReportIndex>,
+    /// Localized handling warnings, most severe first.
+    hazards: Vec<String>,
+    /// `CHART_FILE`, unless the breakdown is empty.
+    chart_src: Option<String>,
+}
+
+#[derive(Template, Serialize)]
 #[template(path = "report.html")]
 struct ReportHtmlTemplate {
     lang_code: String,
     lang_dir: String,
     txt: UiText,
     generated_utc: String,
+    /// What wrote the summary and recommendations, e.g. `Rule-based`.
+    narrative: String,
+    /// Canonical page of the mineral and the QR image that encodes it.
+    mineral_url: String,
+    qr_src: String,
     mineral_name: String,
     mineral_family: String,
     description: String,
@@ -142,6 +930,12 @@ struct ReportHtmlTemplate {
     hardness_band: String,
     density_g_cm3: String,
     density_band: String,
+    /// Band names with the configured cutoffs between them.
+    hardness_scale: String,
+    density_scale: String,
+    strunz_class: String,
+    /// The record's client-specific fields, by name.
+    extra_attributes: Vec<ReportAttribute>,
     crystal_system: String,
     color: String,
     streak: String,
@@ -151,110 +945,1046 @@ struct ReportHtmlTemplate {
     audience: String,
     purpose: String,
     site_context: String,
+    /// Report preset name; blank for none.
+    preset: String,
     summary: String,
     notes: String,
     image_path: Option<String>,
+    gallery: Vec<ReportImage>,
     recommendations: Vec<String>,
+    related: Vec<ReportRelated>,
+    specimens: Vec<ReportSpecimen>,
+    provenance: Option<ReportProvenance>,
+    custody: Vec<ReportCustodyEvent>,
+    spectra: Vec<ReportSpectrum>,
     element_breakdown: Vec<HtmlElementShare>,
+    derived_indices: Vec<ReportIndex>,
+    /// Localized handling warnings, most severe first.
+    hazards: Vec<String>,
+    /// Composition chart as a `data:` URI, unless the breakdown is empty.
+    chart_src: Option<String>,
 }
 
 impl ReportTexTemplate {
-    fn from_report(report: &MineralReport, language: Language) -> Self {
-        let txt = ui_text(language);
+    fn from_report(report: &MineralReport, link: &ReportLink, language: Language) -> Self {
+        let txt = report.units.relabel(&ui_text(language));
+        let locale = LocaleFormat::new(language);
         Self {
             lang_code: language.code().to_string(),
             txt,
-            generated_utc: latex_escape(&report.generated_utc),
+            generated_utc: latex_escape(&locale.timestamp(&report.generated_utc)),
+            narrative: latex_escape(&narrative_label(report, &txt)),
+            mineral_url: latex_escape(&link.url),
+            qr_src: QR_FILE.to_string(),
             mineral_name: latex_escape(&report.mineral.common_name),
             mineral_family: latex_escape(&report.mineral.mineral_family),
-            description: latex_escape(&report.mineral.description),
+            description: markdown::to_latex(&report.mineral.description),
             formula: latex_escape(&report.mineral.formula),
-            hardness_mohs: format!("{:.2}", report.mineral.hardness_mohs),
+            hardness_mohs: locale.decimal(report.mineral.hardness_mohs, 2),
             hardness_band: latex_escape(&report.hardness_band),
-            density_g_cm3: format!("{:.2}", report.mineral.density_g_cm3),
+            density_g_cm3: locale.decimal(report.units.density(report.mineral.density_g_cm3), 2),
             density_band: latex_escape(&report.density_band),
+            hardness_scale: hardness_scale(
+                &report.classification,
+                language,
+                latex_escape,
+                LATEX_SIGNS,
+            ),
+            density_scale: density_scale(
+                &report.classification,
+                report.units,
+                language,
+                latex_escape,
+                LATEX_SIGNS,
+            ),
+            strunz_class: latex_escape(&report.strunz_class),
+            extra_attributes: extra_attributes(report, latex_escape),
             crystal_system: latex_escape(&report.mineral.crystal_system),
             color: latex_escape(&report.mineral.color),
             streak: latex_escape(&report.mineral.streak),
             luster: latex_escape(&report.mineral.luster),
             dominant_element: latex_escape(&report.dominant_element),
-            dominant_element_pct: format!("{:.1}", report.dominant_element_pct),
+            dominant_element_pct: locale.decimal(report.dominant_element_pct, 1),
             audience: latex_escape(&report.audience),
             purpose: latex_escape(&report.purpose),
             site_context: latex_escape(&report.site_context),
+            preset: latex_escape(&report.preset),
             summary: latex_escape(&report.summary),
-            notes: latex_escape(&report.mineral.notes),
+            notes: markdown::to_latex(&report.mineral.notes),
             image_file: image_file_name(&report.mineral.image_path),
+            gallery: gallery_images(report, |path| image_file_name(&Some(path.to_string())))
+                .into_iter()
+                .map(|image| ReportImage {
+                    caption: latex_escape(&image.caption),
+                    ..image
+                })
+                .collect(),
             recommendations: report
                 .recommendations
                 .iter()
                 .map(|rec| latex_escape(rec))
                 .collect(),
+            related: related_lines(report, locale, latex_escape),
+            specimens: specimen_rows(report, locale, latex_escape),
+            provenance: provenance(report, latex_escape),
+            custody: custody_rows(report, latex_escape),
+            references: report
+                .mineral
+                .references
+                .iter()
+                .map(|reference| ReportReference {
+                    title: latex_escape(&reference.title),
+                    url: latex_escape(&reference.url),
+                    accessed: latex_escape(&reference.accessed),
+                })
+                .collect(),
+            spectra: spectrum_plots(report, &txt, latex_escape, |_, spectrum| {
+                charts::spectrum_tikz(
+                    &spectrum.points,
+                    &latex_escape(spectrum.kind.axis_label(&txt)),
+                    &latex_escape(txt.label_intensity),
+                    locale,
+                )
+            }),
             element_breakdown: report
                 .element_breakdown
                 .iter()
-                .map(to_latex_share)
+                .map(|elem| to_latex_share(elem, locale))
                 .collect(),
+            derived_indices: index_rows(report, &txt, locale, latex_escape),
+            hazards: hazard_warnings(report, &txt, latex_escape),
+            chart_color: charts::BAR_COLOR.to_string(),
+        }
+    }
+}
+
+impl ReportTypstTemplate {
+    fn from_report(report: &MineralReport, link: &ReportLink, language: Language) -> Self {
+        let html = ReportHtmlTemplate::from_report(report, link, language);
+        let locale = LocaleFormat::new(language);
+        Self {
+            lang_code: html.lang_code,
+            lang_dir: html.lang_dir,
+            txt: html.txt,
+            generated_utc: html.generated_utc,
+            narrative: html.narrative,
+            mineral_url: html.mineral_url,
+            qr_src: QR_FILE.to_string(),
+            mineral_name: html.mineral_name,
+            mineral_family: html.mineral_family,
+            description: markdown::to_typst(&report.mineral.description),
+            formula: html.formula,
+            hardness_mohs: html.hardness_mohs,
+            hardness_band: html.hardness_band,
+            density_g_cm3: html.density_g_cm3,
+            density_band: html.density_band,
+            hardness_scale: html.hardness_scale,
+            density_scale: html.density_scale,
+            strunz_class: html.strunz_class,
+            extra_attributes: html.extra_attributes,
+            crystal_system: html.crystal_system,
+            color: html.color,
+            streak: html.streak,
+            luster: html.luster,
+            dominant_element: html.dominant_element,
+            dominant_element_pct: html.dominant_element_pct,
+            audience: html.audience,
+            purpose: html.purpose,
+            site_context: html.site_context,
+            preset: html.preset,
+            summary: html.summary,
+            notes: markdown::to_typst(&report.mineral.notes),
+            image_file: image_file_name(&report.mineral.image_path),
+            gallery: gallery_images(report, |path| image_file_name(&Some(path.to_string()))),
+            recommendations: html.recommendations,
+            related: html.related,
+            specimens: html.specimens,
+            provenance: html.provenance,
+            custody: html.custody,
+            spectra: spectrum_plots(report, &html.txt, str::to_string, |index, spectrum| {
+                (!spectrum.chart_src(&html.txt, &locale).is_empty())
+                    .then(|| charts::spectrum_file(index))
+            }),
+            element_breakdown: html.element_breakdown,
+            derived_indices: html.derived_indices,
+            hazards: html.hazards,
+            chart_src: html.chart_src.map(|_| charts::CHART_FILE.to_string()),
         }
     }
 }
 
 impl ReportHtmlTemplate {
-    fn from_report(report: &MineralReport, language: Language) -> Self {
-        let txt = ui_text(language);
+    fn from_report(report: &MineralReport, link: &ReportLink, language: Language) -> Self {
+        let txt = report.units.relabel(&ui_text(language));
+        let locale = LocaleFormat::new(language);
         Self {
             lang_code: language.code().to_string(),
             lang_dir: language.dir().to_string(),
             txt,
-            generated_utc: report.generated_utc.clone(),
+            generated_utc: locale.timestamp(&report.generated_utc),
+            narrative: narrative_label(report, &txt),
+            mineral_url: link.url.clone(),
+            qr_src: link.data_uri(),
             mineral_name: report.mineral.common_name.clone(),
             mineral_family: report.mineral.mineral_family.clone(),
             description: report.mineral.description.clone(),
             formula: report.mineral.formula.clone(),
-            hardness_mohs: format!("{:.2}", report.mineral.hardness_mohs),
+            hardness_mohs: locale.decimal(report.mineral.hardness_mohs, 2),
             hardness_band: report.hardness_band.clone(),
-            density_g_cm3: format!("{:.2}", report.mineral.density_g_cm3),
+            density_g_cm3: locale.decimal(report.units.density(report.mineral.density_g_cm3), 2),
             density_band: report.density_band.clone(),
+            hardness_scale: hardness_scale(
+                &report.classification,
+                language,
+                str::to_string,
+                TEXT_SIGNS,
+            ),
+            density_scale: density_scale(
+                &report.classification,
+                report.units,
+                language,
+                str::to_string,
+                TEXT_SIGNS,
+            ),
+            strunz_class: report.strunz_class.clone(),
+            extra_attributes: extra_attributes(report, str::to_string),
             crystal_system: report.mineral.crystal_system.clone(),
             color: report.mineral.color.clone(),
             streak: report.mineral.streak.clone(),
             luster: report.mineral.luster.clone(),
             dominant_element: report.dominant_element.clone(),
-            dominant_element_pct: format!("{:.1}", report.dominant_element_pct),
+            dominant_element_pct: locale.decimal(report.dominant_element_pct, 1),
             audience: report.audience.clone(),
             purpose: report.purpose.clone(),
             site_context: report.site_context.clone(),
+            preset: report.preset.clone(),
             summary: report.summary.clone(),
             notes: report.mineral.notes.clone(),
             image_path: report.mineral.image_path.clone(),
+            gallery: gallery_images(report, |path| Some(path.to_string())),
             recommendations: report.recommendations.clone(),
-            element_breakdown: report.element_breakdown.iter().map(to_html_share).collect(),
+            related: related_lines(report, locale, str::to_string),
+            specimens: specimen_rows(report, locale, str::to_string),
+            provenance: provenance(report, str::to_string),
+            custody: custody_rows(report, str::to_string),
+            spectra: spectrum_plots(report, &txt, str::to_string, |_, spectrum| {
+                Some(spectrum.chart_src(&txt, &locale)).filter(|src| !src.is_empty())
+            }),
+            element_breakdown: report
+                .element_breakdown
+                .iter()
+                .map(|elem| to_html_share(elem, locale))
+                .collect(),
+            derived_indices: index_rows(report, &txt, locale, str::to_string),
+            hazards: hazard_warnings(report, &txt, str::to_string),
+            chart_src: charts::element_bars_svg(&report.element_breakdown, locale)
+                .map(|svg| charts::svg_data_uri(&svg)),
         }
     }
 }
 
+/// One compared mineral; text fields are escaped like the rest of the
+/// template.
+#[derive(Debug, Clone)]
+struct ComparedMineral {
+    name: String,
+    mineral_url: String,
+    /// Its bar color in the element chart, hex without `#`.
+    color: &'static str,
+}
+
+/// Row of the side-by-side property table, one value per mineral.
+#[derive(Debug, Clone)]
+struct ComparisonRow {
+    label: String,
+    values: Vec<String>,
+}
+
+/// Element in the overlaid chart, one bar per mineral.
+#[derive(Debug, Clone)]
+struct ComparisonElement {
+    name: String,
+    bars: Vec<ChartBar>,
+}
+
+#[derive(Debug, Clone)]
+struct ChartBar {
+    percent: String,
+    /// Share of the full 100 wt% scale, `0.488`.
+    fraction: String,
+    color: &'static str,
+}
+
+#[derive(Template)]
+#[template(path = "comparison.tex", escape = "none")]
+struct ComparisonTexTemplate {
+    lang_code: String,
+    txt: UiText,
+    generated_utc: String,
+    audience: String,
+    purpose: String,
+    site_context: String,
+    summary: String,
+    recommendations: Vec<String>,
+    minerals: Vec<ComparedMineral>,
+    properties: Vec<ComparisonRow>,
+    hardness_scale: String,
+    density_scale: String,
+    elements: Vec<ComparisonElement>,
+    /// Width of each mineral column, as a fraction of `\textwidth`.
+    column_width: String,
+}
+
+#[derive(Template)]
+#[template(path = "comparison.typ", escape = "none")]
+struct ComparisonTypstTemplate {
+    lang_code: String,
+    lang_dir: String,
+    txt: UiText,
+    generated_utc: String,
+    audience: String,
+    purpose: String,
+    site_context: String,
+    summary: String,
+    recommendations: Vec<String>,
+    minerals: Vec<ComparedMineral>,
+    properties: Vec<ComparisonRow>,
+    hardness_scale: String,
+    density_scale: String,
+    elements: Vec<ComparisonElement>,
+}
+
+#[derive(Template)]
+#[template(path = "comparison.html")]
+struct ComparisonHtmlTemplate {
+    lang_code: String,
+    lang_dir: String,
+    txt: UiText,
+    generated_utc: String,
+    audience: String,
+    purpose: String,
+    site_context: String,
+    summary: String,
+    recommendations: Vec<String>,
+    minerals: Vec<ComparedMineral>,
+    properties: Vec<ComparisonRow>,
+    hardness_scale: String,
+    density_scale: String,
+    elements: Vec<ComparisonElement>,
+}
+
+impl ComparisonTexTemplate {
+    fn from_report(report: &ComparisonReport, urls: &[String], language: Language) -> Self {
+        let txt = report.units.relabel(&ui_text(language));
+        let locale = LocaleFormat::new(language);
+        Self {
+            lang_code: language.code().to_string(),
+            generated_utc: latex_escape(&locale.timestamp(&report.generated_utc)),
+            audience: latex_escape(&report.audience),
+            purpose: latex_escape(&report.purpose),
+            site_context: latex_escape(&report.site_context),
+            summary: latex_escape(&report.summary),
+            recommendations: report
+                .recommendations
+                .iter()
+                .map(|rec| latex_escape(rec))
+                .collect(),
+            minerals: compared_minerals(report, urls, latex_escape),
+            properties: comparison_rows(report, &txt, locale, latex_escape),
+            hardness_scale: hardness_scale(
+                &report.classification,
+                language,
+                latex_escape,
+                LATEX_SIGNS,
+            ),
+            density_scale: density_scale(
+                &report.classification,
+                report.units,
+                language,
+                latex_escape,
+                LATEX_SIGNS,
+            ),
+            elements: comparison_elements(report, locale, latex_escape),
+            column_width: format!("{:.3}", 0.7 / report.minerals.len().max(1) as f32),
+            txt,
+        }
+    }
+}
+
+impl ComparisonTypstTemplate {
+    fn from_report(report: &ComparisonReport, urls: &[String], language: Language) -> Self {
+        let html = ComparisonHtmlTemplate::from_report(report, urls, language);
+        Self {
+            lang_code: html.lang_code,
+            lang_dir: html.lang_dir,
+            txt: html.txt,
+            generated_utc: html.generated_utc,
+            audience: html.audience,
+            purpose: html.purpose,
+            site_context: html.site_context,
+            summary: html.summary,
+            recommendations: html.recommendations,
+            minerals: html.minerals,
+            properties: html.properties,
+            hardness_scale: html.hardness_scale,
+            density_scale: html.density_scale,
+            elements: html.elements,
+        }
+    }
+}
+
+impl ComparisonHtmlTemplate {
+    fn from_report(report: &ComparisonReport, urls: &[String], language: Language) -> Self {
+        let txt = report.units.relabel(&ui_text(language));
+        let locale = LocaleFormat::new(language);
+        Self {
+            lang_code: language.code().to_string(),
+            lang_dir: language.dir().to_string(),
+            generated_utc: locale.timestamp(&report.generated_utc),
+            audience: report.audience.clone(),
+            purpose: report.purpose.clone(),
+            site_context: report.site_context.clone(),
+            summary: report.summary.clone(),
+            recommendations: report.recommendations.clone(),
+            minerals: compared_minerals(report, urls, str::to_string),
+            properties: comparison_rows(report, &txt, locale, str::to_string),
+            hardness_scale: hardness_scale(
+                &report.classification,
+                language,
+                str::to_string,
+                TEXT_SIGNS,
+            ),
+            density_scale: density_scale(
+                &report.classification,
+                report.units,
+                language,
+                str::to_string,
+                TEXT_SIGNS,
+            ),
+            elements: comparison_elements(report, locale, str::to_string),
+            txt,
+        }
+    }
+}
+
+/// Labelled line of a certificate's property list; text fields are escaped
+/// like the rest of the template.
+#[derive(Debug, Clone)]
+struct CertificateFact {
+    label: String,
+    value: String,
+}
+
+#[derive(Template)]
+#[template(path = "certificate.tex", escape = "none")]
+struct CertificateTexTemplate {
+    lang_code: String,
+    txt: UiText,
+    issued_utc: String,
+    issued_by: String,
+    /// Verification endpoint of this certificate and the QR image that
+    /// encodes it.
+    verify_url: String,
+    qr_src: String,
+    mineral_name: String,
+    specimen_id: String,
+    specimen_label: String,
+    facts: Vec<CertificateFact>,
+    image_file: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "certificate.typ", escape = "none")]
+struct CertificateTypstTemplate {
+    lang_code: String,
+    lang_dir: String,
+    txt: UiText,
+    issued_utc: String,
+    issued_by: String,
+    /// Verification endpoint of this certificate and the QR image that
+    /// encodes it.
+    verify_url: String,
+    qr_src: String,
+    mineral_name: String,
+    specimen_id: String,
+    specimen_label: String,
+    facts: Vec<CertificateFact>,
+    image_file: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "certificate.html")]
+struct CertificateHtmlTemplate {
+    lang_code: String,
+    lang_dir: String,
+    txt: UiText,
+    issued_utc: String,
+    issued_by: String,
+    /// Verification endpoint of this certificate and the QR image that
+    /// encodes it.
+    verify_url: String,
+    qr_src: String,
+    mineral_name: String,
+    specimen_id: String,
+    specimen_label: String,
+    facts: Vec<CertificateFact>,
+    image_path: Option<String>,
+}
+
+impl CertificateTexTemplate {
+    fn from_certificate(certificate: &Certificate, link: &ReportLink, language: Language) -> Self {
+        let txt = certificate.units.relabel(&ui_text(language));
+        let locale = LocaleFormat::new(language);
+        Self {
+            lang_code: language.code().to_string(),
+            issued_utc: latex_escape(&locale.timestamp(&certificate.issued_utc)),
+            issued_by: latex_escape(&certificate.issued_by),
+            verify_url: latex_escape(&link.url),
+            qr_src: QR_FILE.to_string(),
+            mineral_name: latex_escape(&certificate.mineral.common_name),
+            specimen_id: latex_escape(&certificate.specimen.id),
+            specimen_label: latex_escape(&certificate.specimen.label),
+            facts: certificate_facts(certificate, &txt, locale, latex_escape),
+            image_file: image_file_name(&certificate.mineral.image_path),
+            txt,
+        }
+    }
+}
+
+impl CertificateTypstTemplate {
+    fn from_certificate(certificate: &Certificate, link: &ReportLink, language: Language) -> Self {
+        let html = CertificateHtmlTemplate::from_certificate(certificate, link, language);
+        Self {
+            lang_code: html.lang_code,
+            lang_dir: html.lang_dir,
+            txt: html.txt,
+            issued_utc: html.issued_utc,
+            issued_by: html.issued_by,
+            verify_url: html.verify_url,
+            qr_src: QR_FILE.to_string(),
+            mineral_name: html.mineral_name,
+            specimen_id: html.specimen_id,
+            specimen_label: html.specimen_label,
+            facts: html.facts,
+            image_file: image_file_name(&certificate_photo(&certificate.mineral)),
+        }
+    }
+}
+
+impl CertificateHtmlTemplate {
+    fn from_certificate(certificate: &Certificate, link: &ReportLink, language: Language) -> Self {
+        let txt = certificate.units.relabel(&ui_text(language));
+        let locale = LocaleFormat::new(language);
+        Self {
+            lang_code: language.code().to_string(),
+            lang_dir: language.dir().to_string(),
+            issued_utc: locale.timestamp(&certificate.issued_utc),
+            issued_by: certificate.issued_by.clone(),
+            verify_url: link.url.clone(),
+            qr_src: link.data_uri(),
+            mineral_name: certificate.mineral.common_name.clone(),
+            specimen_id: certificate.specimen.id.clone(),
+            specimen_label: certificate.specimen.label.clone(),
+            facts: certificate_facts(certificate, &txt, locale, str::to_string),
+            image_path: certificate_photo(&certificate.mineral),
+            txt,
+        }
+    }
+}
+
+/// `report.html` and the backend's source for a mineral report.
+fn render_mineral(
+    report: &MineralReport,
+    link: &ReportLink,
+    layout: &ReportLayout,
+    language: Language,
+    backend: PdfBackendKind,
+) -> Result<RenderedReport> {
+    let html = render_source(
+        layout,
+        "report.html",
+        &ReportHtmlTemplate::from_report(report, link, language),
+    )?;
+    let source = match backend {
+        PdfBackendKind::Latex => render_source(
+            layout,
+            "report.tex",
+            &ReportTexTemplate::from_report(report, link, language),
+        )?,
+        PdfBackendKind::Typst => render_source(
+            layout,
+            "report.typ",
+            &ReportTypstTemplate::from_report(report, link, language),
+        )?,
+        PdfBackendKind::Chromium | PdfBackendKind::Weasyprint => html.clone(),
+    };
+    let locale = LocaleFormat::new(language);
+    let txt = ui_text(language);
+    let mut files = vec![(QR_FILE.to_string(), link.qr_png.clone())];
+    if let Some(svg) = charts::element_bars_svg(&report.element_breakdown, locale) {
+        files.push((charts::CHART_FILE.to_string(), svg.into_bytes()));
+    }
+    for (index, spectrum) in report.spectra.iter().enumerate() {
+        if let Some(svg) = charts::spectrum_svg(
+            &spectrum.points,
+            spectrum.kind.axis_label(&txt),
+            txt.label_intensity,
+            locale,
+        ) {
+            files.push((charts::spectrum_file(index), svg.into_bytes()));
+        }
+    }
+    Ok(RenderedReport {
+        html,
+        source,
+        files,
+    })
+}
+
+/// `report.html` and the backend's source for a comparison report, from
+/// the built-in templates; custom layouts only cover single minerals.
+/// `urls` are the compared minerals' pages, in report order.
+fn render_comparison(
+    report: &ComparisonReport,
+    urls: &[String],
+    language: Language,
+    backend: PdfBackendKind,
+) -> Result<RenderedReport> {
+    let html = ComparisonHtmlTemplate::from_report(report, urls, language).render()?;
+    let source = match backend {
+        PdfBackendKind::Latex => {
+            ComparisonTexTemplate::from_report(report, urls, language).render()?
+        }
+        PdfBackendKind::Typst => {
+            ComparisonTypstTemplate::from_report(report, urls, language).render()?
+        }
+        PdfBackendKind::Chromium | PdfBackendKind::Weasyprint => html.clone(),
+    };
+    Ok(RenderedReport {
+        html,
+        source,
+        files: Vec::new(),
+    })
+}
+
+/// `report.html` and the backend's source for a specimen certificate, from
+/// the built-in `certificate.*` templates.
+fn render_certificate(
+    certificate: &Certificate,
+    link: &ReportLink,
+    language: Language,
+    backend: PdfBackendKind,
+) -> Result<RenderedReport> {
+    let html = CertificateHtmlTemplate::from_certificate(certificate, link, language).render()?;
+    let source = match backend {
+        PdfBackendKind::Latex => {
+            CertificateTexTemplate::from_certificate(certificate, link, language).render()?
+        }
+        PdfBackendKind::Typst => {
+            CertificateTypstTemplate::from_certificate(certificate, link, language).render()?
+        }
+        PdfBackendKind::Chromium | PdfBackendKind::Weasyprint => html.clone(),
+    };
+    Ok(RenderedReport {
+        html,
+        source,
+        files: vec![(QR_FILE.to_string(), link.qr_png.clone())],
+    })
+}
+
+/// Key properties of the mineral and the specimen's own measurements;
+/// blank ones are left off the certificate. The specimen's locality wins
+/// over the mineral's type locality.
+fn certificate_facts(
+    certificate: &Certificate,
+    txt: &UiText,
+    locale: LocaleFormat,
+    text: impl Fn(&str) -> String,
+) -> Vec<CertificateFact> {
+    let mineral = &certificate.mineral;
+    let specimen = &certificate.specimen;
+    let locality = if specimen.locality.trim().is_empty() {
+        &mineral.location.locality
+    } else {
+        &specimen.locality
+    };
+    [
+        (txt.label_family, mineral.mineral_family.clone()),
+        (txt.label_formula, mineral.formula.clone()),
+        (txt.label_crystal_system, mineral.crystal_system.clone()),
+        (txt.label_hardness, locale.decimal(mineral.hardness_mohs, 2)),
+        (
+            txt.label_density,
+            locale.decimal(certificate.units.density(mineral.density_g_cm3), 2),
+        ),
+        (txt.label_color, mineral.color.clone()),
+        (txt.label_locality, locality.clone()),
+        (txt.label_country, mineral.location.country.clone()),
+        (
+            txt.label_weight_g,
+            certificate.units.weight_text(specimen.weight_g, &locale),
+        ),
+        (
+            txt.label_dimensions_mm,
+            certificate
+                .units
+                .dimensions(&specimen.dimensions_mm, &locale),
+        ),
+        (txt.label_acquired_on, specimen.acquired_on.clone()),
+    ]
+    .into_iter()
+    .filter(|(_, value)| !value.trim().is_empty())
+    .map(|(label, value)| CertificateFact {
+        label: text(label),
+        value: text(&value),
+    })
+    .collect()
+}
+
+fn compared_minerals(
+    report: &ComparisonReport,
+    urls: &[String],
+    text: impl Fn(&str) -> String,
+) -> Vec<ComparedMineral> {
+    report
+        .minerals
+        .iter()
+        .zip(urls)
+        .enumerate()
+        .map(|(index, (mineral, url))| ComparedMineral {
+            name: text(&mineral.mineral.common_name),
+            mineral_url: text(url),
+            color: SERIES_COLORS[index % SERIES_COLORS.len()],
+        })
+        .collect()
+}
+
+fn comparison_rows(
+    report: &ComparisonReport,
+    txt: &UiText,
+    locale: LocaleFormat,
+    text: impl Fn(&str) -> String,
+) -> Vec<ComparisonRow> {
+    let row = |label: &str, value: &dyn Fn(&MineralReport) -> String| ComparisonRow {
+        label: text(label),
+        values: report
+            .minerals
+            .iter()
+            .map(|mineral| text(&value(mineral)))
+            .collect(),
+    };
+    vec![
+        row(txt.label_family, &|r| r.mineral.mineral_family.clone()),
+        row(txt.label_formula, &|r| r.mineral.formula.clone()),
+        row(txt.label_hardness, &|r| {
+            locale.decimal(r.mineral.hardness_mohs, 2)
+        }),
+        row(txt.label_hardness_band, &|r| r.hardness_band.clone()),
+        row(txt.label_density, &|r| {
+            locale.decimal(report.units.density(r.mineral.density_g_cm3), 2)
+        }),
+        row(txt.label_density_band, &|r| r.density_band.clone()),
+        row(txt.label_crystal_system, &|r| {
+            r.mineral.crystal_system.clone()
+        }),
+        row(txt.label_color, &|r| r.mineral.color.clone()),
+        row(txt.label_streak, &|r| r.mineral.streak.clone()),
+        row(txt.label_luster, &|r| r.mineral.luster.clone()),
+        row(txt.label_dominant_element, &|r| {
+            format!(
+                "{} ({} wt%)",
+                r.dominant_element,
+                locale.decimal(r.dominant_element_pct, 1)
+            )
+        }),
+        row(txt.label_strunz_class, &|r| r.strunz_class.clone()),
+    ]
+}
+
+fn comparison_elements(
+    report: &ComparisonReport,
+    locale: LocaleFormat,
+    text: impl Fn(&str) -> String,
+) -> Vec<ComparisonElement> {
+    report
+        .elements
+        .iter()
+        .map(|element| ComparisonElement {
+            name: text(element),
+            bars: report
+                .minerals
+                .iter()
+                .enumerate()
+                .map(|(index, mineral)| {
+                    let percent = element_share(mineral, element);
+                    ChartBar {
+                        percent: locale.decimal(percent, 2),
+                        fraction: format!("{:.3}", (percent / 100.0).clamp(0.0, 1.0)),
+                        color: SERIES_COLORS[index % SERIES_COLORS.len()],
+                    }
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Every photo except the primary one, which the templates already show
+/// at the top of the report.
+fn gallery_images(
+    report: &MineralReport,
+    src: impl Fn(&str) -> Option<String>,
+) -> Vec<ReportImage> {
+    report
+        .mineral
+        .images
+        .iter()
+        .filter(|image| !image.primary)
+        .filter_map(|image| {
+            Some(ReportImage {
+                src: src(&image.path)?,
+                caption: image.caption.clone(),
+            })
+        })
+        .collect()
+}
+
+fn related_lines(
+    report: &MineralReport,
+    locale: LocaleFormat,
+    text: impl Fn(&str) -> String,
+) -> Vec<ReportRelated> {
+    report
+        .related
+        .iter()
+        .map(|related| ReportRelated {
+            name: text(&related.common_name),
+            family: text(&related.mineral_family),
+            hardness_mohs: locale.decimal(related.hardness_mohs, 1),
+            match_pct: related.similarity_pct(),
+        })
+        .collect()
+}
+
+fn specimen_rows(
+    report: &MineralReport,
+    locale: LocaleFormat,
+    text: impl Fn(&str) -> String,
+) -> Vec<ReportSpecimen> {
+    report
+        .specimens
+        .iter()
+        .map(|specimen| ReportSpecimen {
+            label: text(&specimen.label),
+            weight_g: report.units.weight_text(specimen.weight_g, &locale),
+            dimensions_mm: text(&report.units.dimensions(&specimen.dimensions_mm, &locale)),
+            locality: text(&specimen.locality),
+            acquired_on: text(&specimen.acquired_on),
+            storage_location: text(&specimen.storage_location),
+        })
+        .collect()
+}
+
+fn extra_attributes(report: &MineralReport, text: impl Fn(&str) -> String) -> Vec<ReportAttribute> {
+    report
+        .mineral
+        .extra_attributes
+        .iter()
+        .map(|(name, value)| ReportAttribute {
+            name: text(name),
+            value: text(value),
+        })
+        .collect()
+}
+
+fn custody_rows(report: &MineralReport, text: impl Fn(&str) -> String) -> Vec<ReportCustodyEvent> {
+    report
+        .custody
+        .iter()
+        .map(|event| ReportCustodyEvent {
+            date: text(&event.date),
+            actor: text(&event.actor),
+            document_reference: text(&event.document_reference),
+            note: text(&event.note),
+        })
+        .collect()
+}
+
+fn index_rows(
+    report: &MineralReport,
+    txt: &UiText,
+    locale: LocaleFormat,
+    text: impl Fn(&str) -> String,
+) -> Vec<ReportIndex> {
+    report
+        .derived_indices
+        .iter()
+        .map(|index| ReportIndex {
+            label: text(index.kind.label(txt)),
+            value: locale.decimal(index.value, 2),
+            unit: text(index.kind.unit()),
+        })
+        .collect()
+}
+
+fn hazard_warnings(
+    report: &MineralReport,
+    txt: &UiText,
+    text: impl Fn(&str) -> String,
+) -> Vec<String> {
+    report
+        .hazards
+        .iter()
+        .map(|hazard| text(hazard.warning(txt)))
+        .collect()
+}
+
+/// Spectra that have a plot; `chart` gets each one's position among all of
+/// the report's spectra, which names its Typst file.
+fn spectrum_plots(
+    report: &MineralReport,
+    txt: &UiText,
+    text: impl Fn(&str) -> String,
+    chart: impl Fn(usize, &Spectrum) -> Option<String>,
+) -> Vec<ReportSpectrum> {
+    report
+        .spectra
+        .iter()
+        .enumerate()
+        .filter_map(|(index, spectrum)| {
+            Some(ReportSpectrum {
+                chart: chart(index, spectrum)?,
+                caption: text(&format!(
+                    "{} — {}",
+                    spectrum.kind.label(txt),
+                    spectrum.label
+                )),
+            })
+        })
+        .collect()
+}
+
+fn provenance(report: &MineralReport, text: impl Fn(&str) -> String) -> Option<ReportProvenance> {
+    let location = &report.mineral.location;
+    if location.is_empty() {
+        return None;
+    }
+    Some(ReportProvenance {
+        locality: text(&location.locality),
+        country: text(&location.country),
+        coordinates: location.coordinates().map(|point| ReportCoordinates {
+            dms: point.dms(),
+            decimal: point.decimal(),
+            map_url: point.osm_url(),
+            tile: point.tile(),
+        }),
+    })
+}
+
+/// `watch::Receiver::wait_for` without holding its (non-`Send`) borrow
+/// across an await.
+async fn wait_until<T>(receiver: &mut watch::Receiver<T>, condition: impl FnMut(&T) -> bool) {
+    let _ = receiver.wait_for(condition).await;
+}
+
+/// The clean-background photo when the mineral has one. The LaTeX
+/// certificate keeps the upload: pdflatex cannot include WebP.
+fn certificate_photo(mineral: &Mineral) -> Option<String> {
+    mineral
+        .clean_image_path
+        .clone()
+        .or_else(|| mineral.image_path.clone())
+}
+
+/// Path of a mineral photo relative to the run folder.
 fn image_file_name(path: &Option<String>) -> Option<String> {
     path.as_ref()
         .and_then(|value| value.rsplit('/').next())
-        .map(str::to_string)
         .filter(|value| !value.is_empty())
+        .map(|name| format!("{MINERAL_DIR_FROM_RUN}{name}"))
 }
 
-fn to_latex_share(elem: &ElementShare) -> LatexElementShare {
+fn to_latex_share(elem: &ElementShare, locale: LocaleFormat) -> LatexElementShare {
     LatexElementShare {
         name: latex_escape(&elem.name),
-        percent: format!("{:.2}", elem.percent),
+        percent: locale.decimal(elem.percent, 2),
+        fraction: bar_fraction(elem.percent),
     }
 }
 
-fn to_html_share(elem: &ElementShare) -> HtmlElementShare {
+fn to_html_share(elem: &ElementShare, locale: LocaleFormat) -> HtmlElementShare {
     HtmlElementShare {
         name: elem.name.clone(),
-        percent: format!("{:.2}", elem.percent),
+        percent: locale.decimal(elem.percent, 2),
+        fraction: bar_fraction(elem.percent),
+    }
+}
+
+/// "Below" and "from" signs between bands and cutoffs.
+const TEXT_SIGNS: (&str, &str) = ("<", "≤");
+const LATEX_SIGNS: (&str, &str) = ("$<$", "$\\leq$");
+
+fn hardness_scale(
+    classification: &Classification,
+    language: Language,
+    text: impl Fn(&str) -> String,
+    signs: (&str, &str),
+) -> String {
+    band_scale(
+        &hardness_band_names(language),
+        &classification.hardness,
+        LocaleFormat::new(language),
+        text,
+        signs,
+    )
+}
+
+fn density_scale(
+    classification: &Classification,
+    units: UnitSystem,
+    language: Language,
+    text: impl Fn(&str) -> String,
+    signs: (&str, &str),
+) -> String {
+    band_scale(
+        &density_band_names(language),
+        &classification.density.map(|cutoff| units.density(cutoff)),
+        LocaleFormat::new(language),
+        text,
+        signs,
+    )
+}
+
+/// `soft < 3 ≤ medium < 6 ≤ hard < 7.5 ≤ very hard`.
+fn band_scale(
+    names: &[&str],
+    cutoffs: &[f32],
+    locale: LocaleFormat,
+    text: impl Fn(&str) -> String,
+    (below, from): (&str, &str),
+) -> String {
+    let mut scale = names.first().map(|name| text(name)).unwrap_or_default();
+    for (name, cutoff) in names.iter().skip(1).zip(cutoffs) {
+        scale.push_str(&format!(
+            " {below} {} {from} {}",
+            locale.number(*cutoff),
+            text(name)
+        ));
     }
+    scale
 }
 
-fn latex_escape(input: &str) -> String {
+fn narrative_label(report: &MineralReport, txt: &UiText) -> String {
+    match &report.narrative {
+        Narrative::Rules => txt.report_narrative_rules.to_string(),
+        Narrative::Llm { model } => format!("{} ({model})", txt.report_narrative_llm),
+    }
+}
+
+fn bar_fraction(percent: f32) -> String {
+    format!("{:.3}", percent.clamp(0.0, 100.0) / 100.0)
+}
+
+pub fn latex_escape(input: &str) -> String {
     input
         .replace('\\', "\\textbackslash{}")
         .replace('&', "\\&")
@@ -268,9 +1998,77 @@ fn latex_escape(input: &str) -> String {
         .replace('^', "\\textasciicircum{}")
 }
 
+/// `layout`'s source for `file` rendered with MiniJinja, or the built-in
+/// Askama template. Both see the same variables; MiniJinja escapes HTML in
+/// `.html` files by itself, where the `markdown_html` filter turns a field into
+/// sanitized HTML, and gets the `typst` filter for `.typ` ones.
+fn render_source<T: Template + Serialize>(
+    layout: &ReportLayout,
+    file: &str,
+    template: &T,
+) -> Result<String> {
+    let Some(source) = layout.source(file) else {
+        return Ok(template.render()?);
+    };
+    let mut env = minijinja::Environment::new();
+    env.add_filter("typst", |value: minijinja::Value| {
+        typst_escape(&value.to_string())
+    });
+    env.add_filter("markdown_html", |value: minijinja::Value| {
+        minijinja::Value::from_safe_string(markdown::to_html(&value.to_string()))
+    });
+    env.render_named_str(file, source, template)
+        .with_context(|| {
+            format!(
+                "failed to render {file} of report template '{}'",
+                layout.name().unwrap_or_default()
+            )
+        })
+}
+
+/// Escapes a value for use inside a Typst string literal (`"..."`).
+pub fn typst_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+}
+
+mod filters {
+    pub fn typst<T: std::fmt::Display>(value: T) -> askama::Result<String> {
+        Ok(super::typst_escape(&value.to_string()))
+    }
+
+    pub fn markdown_html<T: std::fmt::Display>(value: T) -> askama::Result<String> {
+        Ok(crate::markdown::to_html(&value.to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::latex_escape;
+    use std::time::Duration;
+
+    use askama::Template;
+    use serde::Serialize;
+
+    use super::{
+        filters, latex_escape, render_certificate, render_mineral, render_source, wait_until,
+        ActiveRun, Certificate, PdfBackendKind, PdfGenerator, Phase, ReportLink,
+    };
+    use crate::{
+        agent::{run_agentic_chain, Classification, MineralAttachments},
+        geo::Location,
+        i18n::Language,
+        migrations::parse_record,
+        models::{
+            mineral_from_record, sample_mineral, GalleryImage, Mineral, Reference, ReportRequest,
+        },
+        report_templates::{ReportLayout, ReportTemplates},
+        signing::ReportSigner,
+        specimens::Specimen,
+        test_support::TempDir,
+    };
 
     #[test]
     fn escapes_special_characters() {
@@ -278,4 +2076,314 @@ mod tests {
         let escaped = latex_escape(raw);
         assert_eq!(escaped, r"50\% Fe\_2O\_3 \& quartz");
     }
+
+    #[test]
+    fn escapes_typst_string_literals_and_parses_backends() {
+        let escaped = filters::typst("say \"hi\" \\ bye\nnow").unwrap();
+        assert_eq!(escaped, r#"say \"hi\" \\ bye\nnow"#);
+        assert_eq!(
+            PdfBackendKind::from_code(" Typst "),
+            Some(PdfBackendKind::Typst)
+        );
+        assert_eq!(PdfBackendKind::from_code("word"), None);
+    }
+
+    #[test]
+    fn encodes_the_mineral_url_as_a_qr_png() {
+        let link = ReportLink::new(
+            "https://minerals.example.org/minerals/mineral.silicates.0x5b6b8000".to_string(),
+        )
+        .unwrap();
+        let image = image::load_from_memory(&link.qr_png).unwrap().to_luma8();
+        assert_eq!(image.width(), image.height());
+        // Quiet zone is light, the finder pattern's corner is dark.
+        assert_eq!(image.get_pixel(0, 0).0, [255]);
+        assert_eq!(image.get_pixel(4 * 8, 4 * 8).0, [0]);
+        assert!(link.data_uri().starts_with("data:image/png;base64,iVBOR"));
+    }
+
+    #[test]
+    fn renders_a_certificate_with_the_specimen_and_its_verify_link() {
+        let certificate = Certificate {
+            mineral: Mineral {
+                slug: "mineral.silicates.0x5b6b8000".to_string(),
+                folder_name: "mineral.silicates.0x5b6b8000".to_string(),
+                common_name: "Phenakite".to_string(),
+                mineral_family: "silicates".to_string(),
+                formula: "Be_2SiO_4".to_string(),
+                hardness_mohs: 7.75,
+                density_g_cm3: 2.96,
+                crystal_system: "trigonal".to_string(),
+                streak: "white".to_string(),
+                luster: "vitreous".to_string(),
+                image_path: Some("/data/minerals/mineral.silicates.0x5b6b8000/1.png".to_string()),
+                location: Location {
+                    locality: "Type locality".to_string(),
+                    country: "Russia".to_string(),
+                    ..Location::default()
+                },
+                ..sample_mineral()
+            },
+            specimen: Specimen {
+                id: "a1b2c3d4".to_string(),
+                label: "PH-0042".to_string(),
+                weight_g: Some(12.5),
+                locality: "Ural Mountains".to_string(),
+                ..Specimen::default()
+            },
+            issued_by: "Curator & Co".to_string(),
+            issued_utc: "2024-06-01T12:00:00+00:00".to_string(),
+            units: Default::default(),
+        };
+        let link = ReportLink::new(
+            "https://minerals.example.org/api/certificates/mineral.silicates.0x5b6b8000/verify?run=2024-06-01T12-00-00"
+                .to_string(),
+        )
+        .unwrap();
+
+        let rendered =
+            render_certificate(&certificate, &link, Language::En, PdfBackendKind::Chromium)
+                .unwrap();
+        assert_eq!(rendered.source, rendered.html);
+        assert!(rendered.html.contains("Certificate of Authenticity"));
+        assert!(rendered.html.contains("<code>a1b2c3d4</code>"));
+        assert!(rendered.html.contains("<td>12.5</td>"));
+        // The specimen's own locality replaces the mineral's; blank
+        // properties are left off.
+        assert!(rendered.html.contains("Ural Mountains"));
+        assert!(!rendered.html.contains("Type locality"));
+        assert!(!rendered.html.contains("Color"));
+        assert!(rendered.html.contains("Curator &amp; Co"));
+        assert!(rendered.html.contains("verify?run=2024-06-01T12-00-00"));
+        assert_eq!(
+            rendered.files,
+            vec![(super::QR_FILE.to_string(), link.qr_png.clone())]
+        );
+
+        let latex =
+            render_certificate(&certificate, &link, Language::En, PdfBackendKind::Latex).unwrap();
+        assert!(latex.source.contains(r"Be\_2SiO\_4"));
+        assert!(latex.source.contains(r"Curator \& Co"));
+        assert!(latex.source.contains("../../1.png"));
+    }
+
+    #[test]
+    fn renders_references_extra_attributes_and_markdown_in_latex_and_typst() {
+        let mineral = Mineral {
+            slug: "quartz".to_string(),
+            folder_name: "mineral.silicates.0x1a2b3c4d".to_string(),
+            common_name: "Quartz".to_string(),
+            description: "Forms **prismatic** crystals".to_string(),
+            mineral_family: "silicates".to_string(),
+            formula: "SiO2".to_string(),
+            hardness_mohs: 7.0,
+            density_g_cm3: 2.65,
+            crystal_system: "trigonal".to_string(),
+            color: "colorless".to_string(),
+            streak: "white".to_string(),
+            luster: "vitreous".to_string(),
+            notes: "- twins <b>common</b>".to_string(),
+            references: vec![
+                Reference {
+                    title: "Quartz & varieties".to_string(),
+                    url: "https://www.mindat.org/min-3337.html".to_string(),
+                    accessed: "2026-03-01".to_string(),
+                },
+                Reference {
+                    url: "https://a.example/50%_quartz".to_string(),
+                    ..Reference::default()
+                },
+            ],
+            extra_attributes: [("Lot number".to_string(), "L_17".to_string())].into(),
+            ..sample_mineral()
+        };
+        let report = run_agentic_chain(
+            &mineral,
+            &ReportRequest::default(),
+            Vec::new(),
+            MineralAttachments::default(),
+            Classification::default(),
+            Language::En,
+        );
+        let link =
+            ReportLink::new("https://minerals.example.org/minerals/quartz".to_string()).unwrap();
+
+        let latex = render_mineral(
+            &report,
+            &link,
+            &ReportLayout::default(),
+            Language::En,
+            PdfBackendKind::Latex,
+        )
+        .unwrap();
+        assert!(latex
+            .source
+            .contains(r"\renewcommand{\refname}{ References }"));
+        assert!(latex.source.contains(
+            r"\bibitem{ref1} \textit{ Quartz \& varieties }. \href{https://www.mindat.org/min-3337.html}"
+        ));
+        assert!(latex.source.contains("(accessed 2026-03-01)"));
+        assert!(latex
+            .source
+            .contains(r"\bibitem{ref2} \href{https://a.example/50\%\_quartz}"));
+        assert!(latex.source.contains(r"\textbf{ Lot number } & L\_17 \\"));
+        assert!(latex
+            .html
+            .contains("<tr><th>Lot number</th><td>L_17</td></tr>"));
+
+        assert!(latex
+            .source
+            .contains(r"\textbf{ Description } & Forms \textbf{prismatic} crystals \\"));
+        assert!(latex
+            .source
+            .contains("\\begin{itemize}\n\\item twins common\n\\end{itemize}"));
+        assert!(latex
+            .html
+            .contains("<td><p>Forms <strong>prismatic</strong> crystals</p>\n</td>"));
+        assert!(latex
+            .html
+            .contains("<ul>\n<li>twins <b>common</b></li>\n</ul>"));
+
+        let typst = render_mineral(
+            &report,
+            &link,
+            &ReportLayout::default(),
+            Language::En,
+            PdfBackendKind::Typst,
+        )
+        .unwrap();
+        assert!(typst
+            .source
+            .contains(r##"("Description", [#"Forms "#strong[#"prismatic"]#" crystals"]),"##));
+        assert!(typst.source.contains(r##"#list([#"twins "#"common"], )"##));
+    }
+
+    #[derive(Template, Serialize)]
+    #[template(source = "<h1>{{ mineral_name }}</h1>", ext = "html")]
+    struct SampleReport {
+        mineral_name: String,
+        notes: Vec<String>,
+    }
+
+    #[tokio::test]
+    async fn renders_custom_layouts_with_the_builtin_variables() {
+        let root = TempDir::new("report-templates");
+        std::fs::create_dir_all(root.join("one-page")).unwrap();
+        std::fs::write(
+            root.join("one-page/report.html"),
+            "<h2>{{ mineral_name }}</h2>{% for note in notes %}[{{ note }}]{% endfor %}",
+        )
+        .unwrap();
+        let templates = ReportTemplates::new(root.to_path_buf());
+        assert_eq!(templates.names().await, vec!["one-page".to_string()]);
+
+        let report = SampleReport {
+            mineral_name: "Quartz & <Amethyst>".to_string(),
+            notes: vec!["a".to_string(), "b".to_string()],
+        };
+        let builtin = render_source(&ReportLayout::default(), "report.html", &report).unwrap();
+        assert_eq!(builtin, "<h1>Quartz &amp; &lt;Amethyst&gt;</h1>");
+
+        let layout = templates
+            .load("one-page", PdfBackendKind::Chromium)
+            .await
+            .unwrap();
+        let custom = render_source(&layout, "report.html", &report).unwrap();
+        assert_eq!(custom, "<h2>Quartz &amp; &lt;Amethyst&gt;</h2>[a][b]");
+
+        assert!(templates
+            .load("one-page", PdfBackendKind::Latex)
+            .await
+            .unwrap_err()
+            .contains("report.tex"));
+        assert!(templates
+            .load("../one-page", PdfBackendKind::Chromium)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn drain_aborts_runs_past_the_deadline() {
+        let generator = PdfGenerator::new(
+            "data/minerals",
+            "static",
+            PdfBackendKind::Latex,
+            "chromium",
+            "http://localhost:7979",
+            10,
+            ReportSigner::default(),
+        );
+        let run = ActiveRun::start(&generator.active);
+        let mut phase = generator.phase.subscribe();
+        let stuck = tokio::spawn(async move {
+            wait_until(&mut phase, |phase| *phase == Phase::Aborted).await;
+            drop(run);
+        });
+
+        generator.drain(Duration::from_millis(20)).await;
+        stuck.await.unwrap();
+        assert_eq!(*generator.phase.borrow(), Phase::Aborted);
+        assert_eq!(*generator.active.borrow(), 0);
+    }
+
+    #[tokio::test]
+    async fn previews_a_draft_report_without_writing_a_run() {
+        const PRIMARY: &str = "data:image/png;base64,iVBORw0KGgo=";
+        const SIDE: &str = "data:image/jpeg;base64,/9j/4AAQ";
+        let root = TempDir::new("preview");
+        std::fs::create_dir_all(root.join("minerals")).unwrap();
+        let generator = PdfGenerator::new(
+            root.join("minerals"),
+            "static",
+            PdfBackendKind::Latex,
+            "chromium",
+            "http://localhost:7979",
+            10,
+            ReportSigner::default(),
+        );
+        let record = parse_record(
+            &serde_json::json!({
+                "common_name": "Draft Quartz",
+                "mineral_family": "silicates",
+                "formula": "SiO2",
+                "hardness_mohs": 7.0,
+                "density_g_cm3": 2.65,
+                "crystal_system": "trigonal",
+                "color": "colorless",
+                "streak": "white",
+                "luster": "vitreous",
+                "notes": ""
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let photo = |path: &str, primary| GalleryImage {
+            path: path.to_string(),
+            thumbnail_path: None,
+            medium_path: None,
+            caption: String::new(),
+            primary,
+        };
+        // As the draft preview builds it: photos inlined as data URLs.
+        let mineral = mineral_from_record(
+            "draft-abc",
+            record,
+            vec![photo(PRIMARY, true), photo(SIDE, false)],
+        );
+        let report = run_agentic_chain(
+            &mineral,
+            &ReportRequest::default(),
+            Vec::new(),
+            MineralAttachments::default(),
+            Classification::default(),
+            Language::En,
+        );
+
+        let html = generator.preview_html(&report, Language::En).await.unwrap();
+        let written = std::fs::read_dir(root.join("minerals")).unwrap().count();
+        assert!(html.contains("Draft Quartz"));
+        assert!(html.contains(PRIMARY));
+        assert!(html.contains(SIDE));
+        assert_eq!(written, 0);
+    }
 }
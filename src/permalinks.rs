@@ -0,0 +1,352 @@
+//! Stable public URLs. A mineral's slug is its folder name
+//! (`mineral.silicates.0x1a2b`) unless the record sets a `permalink` such
+//! as `quartz`. `aliases` lists slugs the mineral was reachable under
+//! before: a permalink that has since changed, or the folder name of a
+//! record that was deleted and published again. The catalog maps every
+//! alias, and the folder name of a mineral with a permalink, to the current
+//! slug; a page requested under one answers `301 Moved Permanently`.
+//!
+//! Separately, `/m/<name>` serves the mineral page under a slug made from
+//! the English common name (`/m/rose-quartz`), for links shared with
+//! buyers. Minerals whose names give the same slug are numbered in
+//! folder-name order: `rose-quartz`, `rose-quartz-2`.
+
+use axum::{
+    extract::{Path as AxumPath, Query, Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    catalog::MineralCatalog,
+    catalog_for_language,
+    i18n::Language,
+    lang_path, mineral_page,
+    models::{is_valid_mineral_folder_name, MineralDiskRecord},
+    web::{MineralTemplate, TemplateResponse},
+    AppError, AppState, MineralPageParams,
+};
+
+const MAX_PERMALINK_CHARS: usize = 64;
+
+/// The public slug of the mineral in `folder_name`.
+pub fn slug(folder_name: &str, record: &MineralDiskRecord) -> String {
+    record
+        .permalink
+        .clone()
+        .unwrap_or_else(|| folder_name.to_string())
+}
+
+/// `Rose Quartz` becomes `rose-quartz` and `Göthite` `gothite`. Names
+/// with no Latin letters or digits have none.
+pub fn name_slug(common_name: &str) -> Option<String> {
+    let mut slug = String::new();
+    for c in common_name.chars().flat_map(char::to_lowercase) {
+        match fold_accent(c) {
+            Some(folded) => slug.push_str(folded),
+            None if c.is_ascii_alphanumeric() => slug.push(c),
+            None if !slug.is_empty() && !slug.ends_with('-') => slug.push('-'),
+            None => {}
+        }
+    }
+    let slug = slug
+        .chars()
+        .take(MAX_PERMALINK_CHARS)
+        .collect::<String>()
+        .trim_end_matches('-')
+        .to_string();
+    (!slug.is_empty()).then_some(slug)
+}
+
+/// `GET /m/:name`: the page of the mineral [`name_slug`] gave `name`, in
+/// the request's language.
+pub async fn name_page(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    AxumPath(name): AxumPath<String>,
+    params: Query<MineralPageParams>,
+) -> Result<TemplateResponse<MineralTemplate>, AppError> {
+    let slug = catalog_for_language(&state, Language::En)
+        .await?
+        .by_name_slug
+        .get(&name)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("mineral '{name}' not found")))?;
+    mineral_page(State(state), headers, AxumPath(slug), params).await
+}
+
+/// Lowercase letters, digits, and inner hyphens, so `Quartz` becomes
+/// `quartz`. Permalinks never contain a dot and cannot be mistaken for a
+/// folder name. Blank means none.
+pub fn parse_permalink(raw: &str) -> Result<Option<String>, String> {
+    let permalink = raw.trim().to_ascii_lowercase();
+    if permalink.is_empty() {
+        return Ok(None);
+    }
+    if !is_valid_permalink(&permalink) {
+        return Err(format!(
+            "permalink '{permalink}' may only use a-z, 0-9, and inner hyphens \
+             (at most {MAX_PERMALINK_CHARS} characters)"
+        ));
+    }
+    Ok(Some(permalink))
+}
+
+/// Splits the admin form's former-slug input on whitespace or commas,
+/// keeping each slug once. Each must be a permalink or a folder name.
+pub fn parse_aliases(raw: &str) -> Result<Vec<String>, String> {
+    let mut aliases = Vec::new();
+    for alias in raw
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|alias| !alias.is_empty())
+    {
+        if !is_valid_permalink(alias) && !is_valid_mineral_folder_name(alias) {
+            return Err(format!(
+                "alias '{alias}' is neither a permalink nor a mineral folder name"
+            ));
+        }
+        if !aliases.iter().any(|known| known == alias) {
+            aliases.push(alias.to_string());
+        }
+    }
+    Ok(aliases)
+}
+
+/// Keeps a permalink that `updated` drops or replaces as an alias, so links
+/// to it go on working.
+pub fn carry_over(previous: &MineralDiskRecord, updated: &mut MineralDiskRecord) {
+    if let Some(old) = &previous.permalink {
+        if updated.permalink.as_ref() != Some(old) && !updated.aliases.contains(old) {
+            updated.aliases.push(old.clone());
+        }
+    }
+    if let Some(current) = &updated.permalink {
+        updated.aliases.retain(|alias| alias != current);
+    }
+}
+
+/// Refuses a permalink or alias that already leads to another mineral.
+/// `folder_name` is the record's own folder when it is being edited.
+pub fn check_available(
+    catalog: &MineralCatalog,
+    record: &MineralDiskRecord,
+    folder_name: Option<&str>,
+) -> Result<(), String> {
+    for slug in record.permalink.iter().chain(&record.aliases) {
+        if let Some(other) = catalog
+            .resolve(slug)
+            .filter(|other| Some(other.folder_name.as_str()) != folder_name)
+        {
+            return Err(format!(
+                "'{slug}' already leads to {} ({})",
+                other.common_name, other.slug
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Redirects `GET /minerals/<alias>/...` to the current slug, keeping the
+/// language prefix, the rest of the path, and the query. Other methods
+/// resolve aliases in the handlers instead, since a 301 would turn a POST
+/// into a GET.
+pub async fn redirect_aliases(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    // Static files, data files, and everything else pass straight through.
+    if requested_slug(&request).is_none() {
+        return next.run(request).await;
+    }
+    // Permalinks and aliases are shared by every language.
+    let location = match catalog_for_language(&state, Language::En).await {
+        Ok(catalog) => moved_location(&catalog, &request),
+        Err(_) => None,
+    };
+    match location {
+        Some(location) => (
+            StatusCode::MOVED_PERMANENTLY,
+            [(header::LOCATION, location)],
+        )
+            .into_response(),
+        None => next.run(request).await,
+    }
+}
+
+/// The slug of a `GET`/`HEAD` under `/minerals/`, and the rest of the path.
+fn requested_slug(request: &Request) -> Option<(&str, Option<&str>)> {
+    if !matches!(*request.method(), Method::GET | Method::HEAD) {
+        return None;
+    }
+    let rest = request.uri().path().strip_prefix("/minerals/")?;
+    Some(match rest.split_once('/') {
+        Some((slug, tail)) => (slug, Some(tail)),
+        None => (rest, None),
+    })
+}
+
+fn moved_location(catalog: &MineralCatalog, request: &Request) -> Option<String> {
+    let (slug, tail) = requested_slug(request)?;
+    if catalog.by_slug.contains_key(slug) {
+        return None;
+    }
+    let current = catalog.aliases.get(slug)?;
+
+    let mut path = format!("/minerals/{current}");
+    if let Some(tail) = tail {
+        path.push('/');
+        path.push_str(tail);
+    }
+    if let Some(query) = request.uri().query() {
+        path.push('?');
+        path.push_str(query);
+    }
+    Some(lang_path::prefixed(request.headers(), &path))
+}
+
+fn fold_accent(c: char) -> Option<&'static str> {
+    Some(match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' => "a",
+        'æ' => "ae",
+        'ç' | 'č' | 'ć' => "c",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ě' => "e",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' => "i",
+        'ñ' | 'ń' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' => "o",
+        'œ' => "oe",
+        'ř' => "r",
+        'š' | 'ś' => "s",
+        'ß' => "ss",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' => "u",
+        'ý' | 'ÿ' => "y",
+        'ž' | 'ź' | 'ż' => "z",
+        _ => return None,
+    })
+}
+
+fn is_valid_permalink(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_PERMALINK_CHARS
+        && !value.starts_with('-')
+        && !value.ends_with('-')
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, extract::Request, http::Method};
+
+    use super::{
+        carry_over, moved_location, name_slug, parse_aliases, parse_permalink, requested_slug,
+    };
+    use crate::{
+        catalog::MineralCatalog,
+        migrations::parse_record,
+        models::{sample_mineral, Mineral},
+    };
+
+    #[test]
+    fn parses_permalinks_and_keeps_replaced_ones_as_aliases() {
+        assert_eq!(parse_permalink("  Quartz "), Ok(Some("quartz".to_string())));
+        assert_eq!(parse_permalink(""), Ok(None));
+        assert!(parse_permalink("rose quartz").is_err());
+        assert!(parse_permalink("-quartz").is_err());
+        assert!(parse_permalink("mineral.silicates.0x1").is_err());
+        assert_eq!(
+            parse_aliases("old-quartz, mineral.silicates.0x1a2b\nold-quartz").unwrap(),
+            ["old-quartz", "mineral.silicates.0x1a2b"]
+        );
+        assert!(parse_aliases("../etc").is_err());
+        assert_eq!(
+            name_slug(" Rose Quartz (var.) "),
+            Some("rose-quartz-var".into())
+        );
+        assert_eq!(name_slug("Göthite"), Some("gothite".into()));
+        assert_eq!(name_slug("石英"), None);
+
+        let previous = parse_record(
+            &serde_json::json!({
+                "common_name": "Quartz",
+                "mineral_family": "silicates",
+                "formula": "SiO2",
+                "hardness_mohs": 7.0,
+                "density_g_cm3": 2.65,
+                "crystal_system": "trigonal",
+                "color": "colorless",
+                "streak": "white",
+                "luster": "vitreous",
+                "notes": "",
+                "permalink": "quartz",
+                "aliases": ["rock-crystal"]
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut renamed = previous.clone();
+        renamed.permalink = Some("rock-crystal".to_string());
+        carry_over(&previous, &mut renamed);
+        assert_eq!(renamed.aliases, ["quartz"]);
+
+        let mut cleared = previous.clone();
+        cleared.permalink = None;
+        carry_over(&previous, &mut cleared);
+        assert_eq!(cleared.aliases, ["rock-crystal", "quartz"]);
+
+        let mut unchanged = previous.clone();
+        carry_over(&previous, &mut unchanged);
+        assert_eq!(unchanged.aliases, ["rock-crystal"]);
+    }
+
+    #[test]
+    fn redirects_only_aliased_mineral_pages() {
+        let request = |method: Method, uri: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap()
+        };
+        let catalog = MineralCatalog::new(vec![Mineral {
+            slug: "quartz".to_string(),
+            folder_name: "mineral.silicates.0x1a2b".to_string(),
+            aliases: vec!["rock-crystal".to_string()],
+            ..sample_mineral()
+        }]);
+
+        let get = request(Method::GET, "/minerals/rock-crystal/report-output?job=1");
+        assert_eq!(
+            requested_slug(&get),
+            Some(("rock-crystal", Some("report-output")))
+        );
+        assert_eq!(
+            moved_location(&catalog, &get).as_deref(),
+            Some("/minerals/quartz/report-output?job=1")
+        );
+        assert_eq!(
+            moved_location(
+                &catalog,
+                &request(Method::HEAD, "/minerals/mineral.silicates.0x1a2b")
+            )
+            .as_deref(),
+            Some("/minerals/quartz")
+        );
+        assert!(moved_location(&catalog, &request(Method::GET, "/minerals/quartz")).is_none());
+
+        // Passed through before the catalog is consulted.
+        for skipped in [
+            request(Method::POST, "/minerals/rock-crystal/pdf"),
+            request(
+                Method::GET,
+                "/data/minerals/mineral.silicates.0x1a2b/image.png",
+            ),
+            request(Method::GET, "/static/app.css"),
+        ] {
+            assert!(requested_slug(&skipped).is_none(), "{}", skipped.uri());
+        }
+    }
+}
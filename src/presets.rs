@@ -0,0 +1,255 @@
+//! Named report request presets ("investor briefing", "museum label",
+//! ...). Admins save the report builder's fields under a name from the
+//! mineral page; every preset is one `data/presets/<name>.json` file, and
+//! visitors pick one from the builder's dropdown to fill in the form. The
+//! name travels with the request into the report, its job, and the API
+//! response.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::{HeaderMap, HeaderValue},
+    response::{IntoResponse, Redirect, Response},
+    Form,
+};
+use serde::Deserialize;
+use tokio::fs;
+use tracing::info;
+
+use crate::{csrf, lang_path, models::ReportRequest, permalinks::name_slug, AppError, AppState};
+
+pub const PRESETS_DIR: &str = "presets";
+
+const MAX_NAME_CHARS: usize = 64;
+
+fn presets_dir(state: &AppState) -> PathBuf {
+    state.data_root.join(PRESETS_DIR)
+}
+
+/// Saved preset names, sorted; a missing folder just means none.
+pub async fn names(state: &AppState) -> Vec<String> {
+    names_in(&presets_dir(state)).await
+}
+
+async fn names_in(dir: &Path) -> Vec<String> {
+    let Ok(mut entries) = fs::read_dir(dir).await else {
+        return Vec::new();
+    };
+    let mut names = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if let Some(name) = file_name
+            .strip_suffix(".json")
+            .filter(|name| is_valid_name(name))
+        {
+            names.push(name.to_string());
+        }
+    }
+    names.sort();
+    names
+}
+
+/// The request saved as `name`, carrying that name; `None` when there is
+/// no such preset.
+pub async fn load(state: &AppState, name: &str) -> Result<Option<ReportRequest>, AppError> {
+    load_from(&presets_dir(state), name).await
+}
+
+async fn load_from(dir: &Path, name: &str) -> Result<Option<ReportRequest>, AppError> {
+    if !is_valid_name(name) {
+        return Ok(None);
+    }
+    let path = dir.join(format!("{name}.json"));
+    let raw = match fs::read_to_string(&path).await {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(AppError::Internal(
+                anyhow::Error::new(err).context(format!("failed to read {}", path.display())),
+            ))
+        }
+    };
+    let request = serde_json::from_str::<ReportRequest>(&raw)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(Some(ReportRequest {
+        preset: name.to_string(),
+        ..request
+    }))
+}
+
+/// Refuses a request naming a preset that does not exist, so reports only
+/// ever reference saved presets. A blank name is no preset.
+pub async fn check(state: &AppState, request: &ReportRequest) -> Result<(), AppError> {
+    let name = request.preset.trim();
+    if name.is_empty() || load(state, name).await?.is_some() {
+        return Ok(());
+    }
+    Err(AppError::BadRequest(format!(
+        "unknown report preset '{name}'"
+    )))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SavePresetForm {
+    #[serde(default)]
+    csrf_token: String,
+    /// Folded into a slug: `Investor briefing` is saved as
+    /// `investor-briefing`.
+    #[serde(default)]
+    preset_name: String,
+    /// Mineral page to return to.
+    #[serde(default)]
+    slug: String,
+    #[serde(flatten)]
+    report: ReportRequest,
+}
+
+/// `POST /admin/presets`: saves the builder's fields, replacing a preset
+/// of the same name, and returns to the mineral page with it selected.
+pub async fn save_preset(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(form): Form<SavePresetForm>,
+) -> Result<Response, AppError> {
+    csrf::verify(&state, &headers, &form.csrf_token)?;
+    let name = name_slug(&form.preset_name)
+        .filter(|name| is_valid_name(name))
+        .ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "a preset name needs Latin letters or digits (at most {MAX_NAME_CHARS} characters)"
+            ))
+        })?;
+    let request = ReportRequest {
+        preset: String::new(),
+        ..form.report
+    };
+    write(&presets_dir(&state), &name, &request).await?;
+    info!("saved report preset {name}");
+    Ok(back_to_mineral(
+        &headers,
+        &format!("/minerals/{}?preset={name}", form.slug),
+    ))
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct DeletePresetForm {
+    csrf_token: String,
+    slug: String,
+}
+
+/// `POST /admin/presets/:name/delete`.
+pub async fn delete_preset(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(name): AxumPath<String>,
+    Form(form): Form<DeletePresetForm>,
+) -> Result<Response, AppError> {
+    csrf::verify(&state, &headers, &form.csrf_token)?;
+    if load(&state, &name).await?.is_none() {
+        return Err(AppError::NotFound(format!("preset '{name}' not found")));
+    }
+    let path = presets_dir(&state).join(format!("{name}.json"));
+    fs::remove_file(&path)
+        .await
+        .with_context(|| format!("failed to remove {}", path.display()))?;
+    info!("deleted report preset {name}");
+    Ok(back_to_mineral(
+        &headers,
+        &format!("/minerals/{}", form.slug),
+    ))
+}
+
+/// A redirect for plain form posts. The buttons sit in the HTMX report
+/// builder, so HTMX posts get `HX-Redirect` for a full page load instead
+/// of a page swapped into the output panel.
+fn back_to_mineral(headers: &HeaderMap, path: &str) -> Response {
+    let target = lang_path::prefixed(headers, path);
+    if !headers.contains_key("hx-request") {
+        return Redirect::to(&target).into_response();
+    }
+    let mut response = ().into_response();
+    if let Ok(value) = HeaderValue::from_str(&target) {
+        response.headers_mut().insert("hx-redirect", value);
+    }
+    response
+}
+
+async fn write(dir: &Path, name: &str, request: &ReportRequest) -> Result<(), AppError> {
+    fs::create_dir_all(dir)
+        .await
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+    let path = dir.join(format!("{name}.json"));
+    let json = serde_json::to_string_pretty(request).context("failed to serialize preset")?;
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, json)
+        .await
+        .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, &path)
+        .await
+        .with_context(|| format!("failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_CHARS
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_valid_name, load_from, names_in, write};
+    use crate::{
+        agent::{run_agentic_chain, Classification, MineralAttachments},
+        i18n::Language,
+        models::{sample_mineral, ReportRequest},
+        test_support::TempDir,
+    };
+
+    #[tokio::test]
+    async fn saved_presets_fill_in_and_name_the_report() {
+        let dir = TempDir::new("presets");
+        assert!(names_in(&dir).await.is_empty());
+        let briefing = ReportRequest {
+            audience: "investors".to_string(),
+            purpose: "due diligence".to_string(),
+            ..ReportRequest::default()
+        };
+        write(&dir, "investor-briefing", &briefing).await.unwrap();
+        write(&dir, "museum-label", &ReportRequest::default())
+            .await
+            .unwrap();
+        std::fs::write(dir.join("Not Valid.json"), "{}").unwrap();
+
+        assert_eq!(names_in(&dir).await, ["investor-briefing", "museum-label"]);
+        let request = load_from(&dir, "investor-briefing").await.unwrap().unwrap();
+        assert!(load_from(&dir, "missing").await.unwrap().is_none());
+        assert!(load_from(&dir, "../presets/museum-label")
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(request.preset, "investor-briefing");
+        assert_eq!(request.audience, "investors");
+
+        let report = run_agentic_chain(
+            &sample_mineral(),
+            &request,
+            Vec::new(),
+            MineralAttachments::default(),
+            Classification::default(),
+            Language::En,
+        );
+        assert_eq!(report.preset, "investor-briefing");
+        assert_eq!(report.audience, "investors");
+        assert_eq!(report.purpose, "due diligence");
+
+        assert!(is_valid_name("museum-label-2"));
+        assert!(!is_valid_name("Museum"));
+        assert!(!is_valid_name(&"a".repeat(65)));
+    }
+}
@@ -0,0 +1,143 @@
+//! Per-IP token buckets for the routes that are expensive to hammer: admin
+//! authentication (password guessing) and the LLM-backed admin and API
+//! writes (provider credit). Each route group has its own limiter.
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics::counter;
+
+use crate::{client_ip::ClientIp, AppError};
+
+/// Idle buckets are dropped once the table grows past this many clients.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// `per_minute` tokens are refilled evenly over a minute, up to `burst`.
+/// A `per_minute` of zero turns the limit off.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub per_minute: u32,
+    pub burst: u32,
+}
+
+pub struct RateLimiter {
+    group: &'static str,
+    limit: RateLimit,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(group: &'static str, limit: RateLimit) -> Arc<Self> {
+        Arc::new(Self {
+            group,
+            limit,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Takes one token for `client`, or returns how long until one is
+    /// available.
+    fn acquire(&self, client: IpAddr, now: Instant) -> Result<(), Duration> {
+        if self.limit.per_minute == 0 {
+            return Ok(());
+        }
+        let capacity = f64::from(self.limit.burst.max(1));
+        let per_second = f64::from(self.limit.per_minute) / 60.0;
+        let Ok(mut buckets) = self.buckets.lock() else {
+            return Ok(());
+        };
+
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            let full_after = Duration::from_secs_f64(capacity / per_second);
+            buckets.retain(|_, bucket| now.duration_since(bucket.updated) < full_after);
+        }
+
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+        }
+    }
+}
+
+/// Route middleware; answers `429` with `Retry-After` once the client's
+/// bucket is empty.
+pub async fn enforce(
+    State(limiter): State<Arc<RateLimiter>>,
+    ClientIp(client): ClientIp,
+    request: Request,
+    next: Next,
+) -> Response {
+    match limiter.acquire(client, Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => {
+            counter!("rate_limited_total", "group" => limiter.group).increment(1);
+            AppError::TooManyRequests(wait.as_secs_f64().ceil() as u64).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        time::{Duration, Instant},
+    };
+
+    use super::{RateLimit, RateLimiter};
+
+    #[test]
+    fn refills_per_client_and_reports_the_wait() {
+        let limiter = RateLimiter::new(
+            "auth",
+            RateLimit {
+                per_minute: 6,
+                burst: 2,
+            },
+        );
+        let alice = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let bob = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let start = Instant::now();
+
+        assert!(limiter.acquire(alice, start).is_ok());
+        assert!(limiter.acquire(alice, start).is_ok());
+        assert_eq!(limiter.acquire(alice, start), Err(Duration::from_secs(10)));
+        assert!(limiter.acquire(bob, start).is_ok());
+        assert!(limiter
+            .acquire(alice, start + Duration::from_secs(10))
+            .is_ok());
+
+        let unlimited = RateLimiter::new(
+            "llm",
+            RateLimit {
+                per_minute: 0,
+                burst: 0,
+            },
+        );
+        assert!((0..100).all(|_| unlimited.acquire(alice, start).is_ok()));
+    }
+}
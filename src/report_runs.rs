@@ -0,0 +1,217 @@
+//! Past report runs. Every PDF generation renders into its own
+//! `<timestamp>/` folder, under `reports/` inside the mineral folder or
+//! under `data/comparisons/` for comparison reports, so a new report never
+//! overwrites an earlier one. `REPORT_RETENTION` caps how many finished
+//! runs are kept. Specimen certificates are archived the same way under
+//! `certificates/`, but never pruned: their QR codes must keep resolving.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use tokio::fs;
+
+use crate::{locale_format::LocaleFormat, models::Mineral, AppError, AppState};
+
+pub const REPORTS_DIR: &str = "reports";
+
+/// Certificate of authenticity runs, next to `REPORTS_DIR`.
+pub const CERTIFICATES_DIR: &str = "certificates";
+
+/// Runs of comparison reports, under the data root.
+pub const COMPARISONS_DIR: &str = "comparisons";
+
+/// Run folder names: the UTC start time, `2024-06-01T12-00-00`.
+const RUN_ID_FORMAT: &str = "%Y-%m-%dT%H-%M-%S";
+
+/// A finished run, for the mineral page.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportRun {
+    pub id: String,
+    /// `2024-06-01 12:00:00 UTC`, in the page language's date order.
+    pub generated_utc: String,
+    pub pdf_path: String,
+    pub html_path: String,
+}
+
+/// Creates the folder for a new run in `runs_dir`. A second run started
+/// within the same second gets a `-2` (`-3`, ...) suffix.
+pub async fn create_run_dir(runs_dir: &Path, started: DateTime<Utc>) -> Result<(String, PathBuf)> {
+    fs::create_dir_all(runs_dir)
+        .await
+        .with_context(|| format!("failed to create {}", runs_dir.display()))?;
+
+    let stem = started.format(RUN_ID_FORMAT).to_string();
+    let mut attempt = 1;
+    loop {
+        let id = match attempt {
+            1 => stem.clone(),
+            n => format!("{stem}-{n}"),
+        };
+        let run_dir = runs_dir.join(&id);
+        match fs::create_dir(&run_dir).await {
+            Ok(()) => return Ok((id, run_dir)),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => attempt += 1,
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to create {}", run_dir.display()))
+            }
+        }
+    }
+}
+
+/// Finished runs of a published mineral, newest first.
+pub async fn for_mineral(
+    state: &AppState,
+    mineral: &Mineral,
+    locale: LocaleFormat,
+) -> Result<Vec<ReportRun>, AppError> {
+    let runs_dir = state
+        .data_root
+        .join("minerals")
+        .join(&mineral.folder_name)
+        .join(REPORTS_DIR);
+    Ok(finished_runs(&runs_dir)
+        .await?
+        .into_iter()
+        .map(|id| ReportRun {
+            generated_utc: display_time(&id, locale),
+            pdf_path: format!(
+                "/data/minerals/{}/{REPORTS_DIR}/{id}/report.pdf",
+                mineral.folder_name
+            ),
+            html_path: format!(
+                "/data/minerals/{}/{REPORTS_DIR}/{id}/report.html",
+                mineral.folder_name
+            ),
+            id,
+        })
+        .collect())
+}
+
+/// Folder of the newest finished run in `runs_dir`, or of run `id` if given.
+pub async fn run_dir(runs_dir: &Path, id: Option<&str>) -> Result<Option<PathBuf>> {
+    let runs = finished_runs(runs_dir).await?;
+    let id = match id {
+        Some(id) => runs.into_iter().find(|run| run == id),
+        None => runs.into_iter().next(),
+    };
+    Ok(id.map(|id| runs_dir.join(id)))
+}
+
+/// Deletes finished runs beyond the newest `keep`; `0` keeps everything.
+/// Runs still rendering have no `report.pdf` yet and are never touched.
+pub async fn prune(runs_dir: &Path, keep: usize) -> Result<usize> {
+    if keep == 0 {
+        return Ok(0);
+    }
+    let expired = finished_runs(runs_dir)
+        .await?
+        .into_iter()
+        .skip(keep)
+        .collect::<Vec<_>>();
+    for id in &expired {
+        let run_dir = runs_dir.join(id);
+        fs::remove_dir_all(&run_dir)
+            .await
+            .with_context(|| format!("failed to remove {}", run_dir.display()))?;
+    }
+    Ok(expired.len())
+}
+
+/// Ids of runs that produced a `report.pdf`, newest first.
+async fn finished_runs(runs_dir: &Path) -> Result<Vec<String>> {
+    let mut entries = match fs::read_dir(runs_dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read {}", runs_dir.display()))
+        }
+    };
+
+    let mut ids = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .with_context(|| format!("failed to read {}", runs_dir.display()))?
+    {
+        let id = entry.file_name().to_string_lossy().to_string();
+        if is_valid_run_id(&id) && entry.path().join("report.pdf").is_file() {
+            ids.push(id);
+        }
+    }
+    sort_newest_first(&mut ids);
+    Ok(ids)
+}
+
+/// Same-second suffixes sort after the unsuffixed run they follow.
+fn sort_newest_first(ids: &mut [String]) {
+    ids.sort_by_key(|id| {
+        let (stem, suffix) = id.split_at(id.len().min(19));
+        let attempt = suffix
+            .strip_prefix('-')
+            .and_then(|n| n.parse::<u32>().ok())
+            .unwrap_or(1);
+        std::cmp::Reverse((stem.to_string(), attempt))
+    });
+}
+
+fn display_time(id: &str, locale: LocaleFormat) -> String {
+    id.get(..19)
+        .and_then(|stem| NaiveDateTime::parse_from_str(stem, RUN_ID_FORMAT).ok())
+        .map(|time| locale.datetime(time.and_utc()))
+        .unwrap_or_else(|| id.to_string())
+}
+
+fn is_valid_run_id(id: &str) -> bool {
+    id.len() >= 19
+        && id.len() <= 32
+        && id
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '-' || c == 'T')
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{create_run_dir, display_time, prune, run_dir};
+    use crate::{i18n::Language, locale_format::LocaleFormat, test_support::TempDir};
+
+    #[tokio::test]
+    async fn keeps_the_newest_finished_runs() {
+        let runs = TempDir::new("report-runs");
+        let started = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let mut ids = Vec::new();
+        for offset in [0, 0, 5, 9] {
+            let (id, dir) = create_run_dir(&runs, started + chrono::Duration::seconds(offset))
+                .await
+                .unwrap();
+            if offset != 9 {
+                std::fs::write(dir.join("report.pdf"), b"%PDF").unwrap();
+            }
+            ids.push(id);
+        }
+        assert_eq!(
+            ids,
+            [
+                "2024-06-01T12-00-00",
+                "2024-06-01T12-00-00-2",
+                "2024-06-01T12-00-05",
+                "2024-06-01T12-00-09"
+            ]
+        );
+        assert_eq!(
+            display_time(&ids[1], LocaleFormat::new(Language::En)),
+            "2024-06-01 12:00:00 UTC"
+        );
+
+        // The unfinished run is neither the latest report nor pruned.
+        let latest = run_dir(&runs, None).await.unwrap().unwrap();
+        assert!(latest.ends_with("2024-06-01T12-00-05"));
+        assert_eq!(prune(&runs, 2).await.unwrap(), 1);
+        assert!(!runs.join("2024-06-01T12-00-00").exists());
+        assert!(runs.join("2024-06-01T12-00-00-2").exists());
+        assert!(runs.join("2024-06-01T12-00-09").exists());
+    }
+}
@@ -0,0 +1,137 @@
+//! Admin-provided report layouts (client-branded, one-page summary, full
+//! dossier, ...). Each folder under `REPORT_TEMPLATES_DIR`
+//! (`templates/reports/<name>/`) holds any of `report.html`, `report.tex`,
+//! and `report.typ` in Jinja syntax, rendered with MiniJinja against the
+//! same variables as the built-in templates. Files a layout leaves out fall
+//! back to the built-in ones, except the source the chosen PDF backend
+//! renders from, which the layout must provide.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use tokio::fs;
+
+use crate::pdf::PdfBackendKind;
+
+const LAYOUT_FILES: &[&str] = &["report.html", "report.tex", "report.typ"];
+
+#[derive(Debug, Clone)]
+pub struct ReportTemplates {
+    root: PathBuf,
+}
+
+/// Layout of one report, with its template sources read at submission so
+/// a queued job is not affected by later edits.
+#[derive(Debug, Clone, Default)]
+pub struct ReportLayout {
+    /// Folder name; `None` for the built-in layout.
+    name: Option<String>,
+    sources: HashMap<&'static str, String>,
+    /// `report.html` gets its stylesheet and images inlined (`?standalone=1`).
+    standalone: bool,
+}
+
+impl ReportTemplates {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Layout folders, sorted. Read on every call, so a new folder shows up
+    /// without a restart; a missing root just means none.
+    pub async fn names(&self) -> Vec<String> {
+        let Ok(mut entries) = fs::read_dir(&self.root).await else {
+            return Vec::new();
+        };
+        let mut names = Vec::new();
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if is_valid_name(&name) && is_dir(&entry.path()).await {
+                names.push(name);
+            }
+        }
+        names.sort();
+        names
+    }
+
+    /// Reads layout `name` for `backend`; a blank name is the built-in
+    /// layout. Errors are messages for the client.
+    pub async fn load(&self, name: &str, backend: PdfBackendKind) -> Result<ReportLayout, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Ok(ReportLayout::default());
+        }
+        let dir = self.root.join(name);
+        if !is_valid_name(name) || !is_dir(&dir).await {
+            return Err(format!("unknown report template '{name}'"));
+        }
+
+        let sources = read_sources(&dir).await?;
+        let required = backend.source_file();
+        if !sources.contains_key(required) {
+            return Err(format!(
+                "report template '{name}' has no {required} for the {} backend",
+                backend.as_str()
+            ));
+        }
+        Ok(ReportLayout {
+            name: Some(name.to_string()),
+            sources,
+            standalone: false,
+        })
+    }
+}
+
+impl ReportLayout {
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn with_standalone(self, standalone: bool) -> Self {
+        Self { standalone, ..self }
+    }
+
+    pub fn is_standalone(&self) -> bool {
+        self.standalone
+    }
+
+    /// Custom source for `file` (`report.tex`, ...), if the layout has one.
+    pub fn source(&self, file: &str) -> Option<&str> {
+        self.sources.get(file).map(String::as_str)
+    }
+}
+
+async fn read_sources(dir: &Path) -> Result<HashMap<&'static str, String>, String> {
+    let mut sources = HashMap::new();
+    for file in LAYOUT_FILES {
+        match fs::read_to_string(dir.join(file)).await {
+            Ok(source) => {
+                sources.insert(*file, source);
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(format!(
+                    "failed to read {}: {err}",
+                    dir.join(file).display()
+                ))
+            }
+        }
+    }
+    Ok(sources)
+}
+
+/// Follows symlinks, like `Path::is_dir`, without blocking the runtime.
+async fn is_dir(path: &Path) -> bool {
+    fs::metadata(path)
+        .await
+        .is_ok_and(|metadata| metadata.is_dir())
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
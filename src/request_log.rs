@@ -0,0 +1,103 @@
+//! One log line per request, with a request id that follows the request
+//! through every log line it causes and back out in `X-Request-Id`, so a
+//! user reporting a failure can quote the id and it can be found in the
+//! logs.
+
+use std::time::Instant;
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{info, info_span, warn, Instrument};
+
+use crate::{client_ip::ClientIp, crypto, has_admin_session, resolve_language, AppState};
+
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// The id of the request being handled, for error responses.
+pub fn current_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Keeps an id set by a proxy in front of the server, so both logs share
+/// it; anything that does not look like an id is replaced.
+fn request_id(request: &Request) -> String {
+    request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_id(id))
+        .map(str::to_string)
+        .or_else(|| crypto::random_hex(8).ok())
+        .unwrap_or_default()
+}
+
+fn is_valid_id(id: &str) -> bool {
+    (1..=64).contains(&id.len())
+        && id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'))
+}
+
+pub async fn log_requests(
+    State(state): State<AppState>,
+    ClientIp(client): ClientIp,
+    request: Request,
+    next: Next,
+) -> Response {
+    let id = request_id(&request);
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let language = resolve_language(&state, request.headers());
+    let admin = has_admin_session(&state, request.headers());
+    let span = info_span!(
+        "request",
+        id = %id,
+        method = %method,
+        path = %path,
+        client = %client
+    );
+    let started = Instant::now();
+
+    let mut response = REQUEST_ID
+        .scope(id.clone(), next.run(request))
+        .instrument(span.clone())
+        .await;
+
+    let status = response.status().as_u16();
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let lang = language.code();
+    if response.status().is_server_error() {
+        warn!(parent: &span, status, latency_ms, lang, admin, "request failed");
+    } else {
+        info!(parent: &span, status, latency_ms, lang, admin, "request handled");
+    }
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER.clone(), value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_valid_id;
+
+    #[test]
+    fn accepts_only_plain_ids_from_upstream() {
+        assert!(is_valid_id("3f2a9c1e0b7d4a56"));
+        assert!(is_valid_id("req-42_a.b"));
+        assert!(!is_valid_id(""));
+        assert!(!is_valid_id("id with spaces"));
+        assert!(!is_valid_id("id\nforged: log line"));
+        assert!(!is_valid_id(&"a".repeat(65)));
+    }
+}
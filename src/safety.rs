@@ -0,0 +1,129 @@
+//! Handling hazards inferred from a mineral's record: toxic or radioactive
+//! elements in its composition, and asbestiform habits named in its text.
+//! The flags are shown above everything else on the mineral page and as a
+//! boxed section in reports. They are inferred, so a mineral without flags
+//! is not thereby safe to grind, ingest, or store in a closed room.
+
+use crate::{elements, i18n::UiText, models::Mineral};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hazard {
+    Radioactive,
+    Asbestiform,
+    Arsenic,
+    Mercury,
+    Lead,
+    Cadmium,
+    Thallium,
+    Beryllium,
+}
+
+/// Elements that raise a flag as soon as the composition has any of them.
+const HAZARDOUS_ELEMENTS: &[(Hazard, &[&str])] = &[
+    (Hazard::Radioactive, &["U", "Th", "Ra", "Rn", "Po"]),
+    (Hazard::Arsenic, &["As"]),
+    (Hazard::Mercury, &["Hg"]),
+    (Hazard::Lead, &["Pb"]),
+    (Hazard::Cadmium, &["Cd"]),
+    (Hazard::Thallium, &["Tl"]),
+    (Hazard::Beryllium, &["Be"]),
+];
+
+/// Names and habit words, lower case, that mark fibrous asbestos-type
+/// material in the name, family, description, notes, or tags.
+const ASBESTIFORM_TERMS: &[&str] = &[
+    "asbest",
+    "chrysotile",
+    "crocidolite",
+    "amosite",
+    "amianth",
+    "byssolite",
+    "mountain leather",
+];
+
+impl Hazard {
+    /// The warning in the page or report language.
+    pub fn warning(self, txt: &UiText) -> &'static str {
+        match self {
+            Hazard::Radioactive => txt.hazard_radioactive,
+            Hazard::Asbestiform => txt.hazard_asbestiform,
+            Hazard::Arsenic => txt.hazard_arsenic,
+            Hazard::Mercury => txt.hazard_mercury,
+            Hazard::Lead => txt.hazard_lead,
+            Hazard::Cadmium => txt.hazard_cadmium,
+            Hazard::Thallium => txt.hazard_thallium,
+            Hazard::Beryllium => txt.hazard_beryllium,
+        }
+    }
+}
+
+/// Every hazard that applies, most severe first.
+pub fn hazards(mineral: &Mineral) -> Vec<Hazard> {
+    let mut found: Vec<Hazard> = HAZARDOUS_ELEMENTS
+        .iter()
+        .filter(|(_, symbols)| {
+            symbols.iter().any(|symbol| {
+                elements::weight_pct(mineral, symbol).is_some_and(|percent| percent > 0.0)
+            })
+        })
+        .map(|(hazard, _)| *hazard)
+        .collect();
+    if is_asbestiform(mineral) {
+        found.push(Hazard::Asbestiform);
+    }
+    found.sort_by_key(|hazard| *hazard as u8);
+    found
+}
+
+fn is_asbestiform(mineral: &Mineral) -> bool {
+    let text = [
+        mineral.common_name.as_str(),
+        &mineral.mineral_family,
+        &mineral.description,
+        &mineral.notes,
+        &mineral.tags.join(" "),
+    ]
+    .join(" ")
+    .to_lowercase();
+    ASBESTIFORM_TERMS.iter().any(|term| text.contains(term))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hazards, Hazard};
+    use crate::models::{sample_mineral, Mineral};
+
+    fn mineral(name: &str, formula: &str, notes: &str) -> Mineral {
+        Mineral {
+            slug: name.to_lowercase(),
+            folder_name: name.to_lowercase(),
+            common_name: name.to_string(),
+            formula: formula.to_string(),
+            hardness_mohs: 3.0,
+            density_g_cm3: 3.0,
+            notes: notes.to_string(),
+            ..sample_mineral()
+        }
+    }
+
+    #[test]
+    fn flags_toxic_radioactive_and_fibrous_minerals() {
+        assert_eq!(
+            hazards(&mineral("Mimetite", "Pb5(AsO4)3Cl", "")),
+            [Hazard::Arsenic, Hazard::Lead]
+        );
+        assert_eq!(
+            hazards(&mineral("Autunite", "Ca(UO2)2(PO4)2", "")),
+            [Hazard::Radioactive]
+        );
+        assert_eq!(
+            hazards(&mineral(
+                "Tremolite",
+                "Ca2Mg5Si8O22(OH)2",
+                "Fibrous, Asbestiform habit"
+            )),
+            [Hazard::Asbestiform]
+        );
+        assert!(hazards(&mineral("Quartz", "SiO2", "")).is_empty());
+    }
+}
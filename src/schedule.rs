@@ -0,0 +1,275 @@
+//! Scheduled publishing. A record's `publish_at` (UTC, shared by every
+//! language) keeps the mineral out of public views until that time, as if
+//! it were private; admins see it all along. A background task checks for
+//! minerals whose time has come, rebuilds the cached catalogs so they show
+//! up in listings and search, and announces each one to
+//! `PUBLISH_WEBHOOK_URL`. Minerals whose time came while the server was
+//! down are live after startup, without an announcement.
+
+use std::{collections::HashSet, future::Future, time::Duration};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use reqwest::Client;
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::{catalog_for_language, i18n::Language, models::Mineral, refresh_mineral, AppState};
+
+/// How often due minerals are looked for; listings show a mineral at most
+/// this long after its time, while its page opens right away.
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// A webhook that hangs must not hold up the next check.
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Reads a publish time as RFC 3339 (`2026-11-01T09:00:00Z`, any offset)
+/// or as the admin form's `2026-11-01T09:00`, taken as UTC, and returns it
+/// as records store it: RFC 3339 in UTC. Blank means none.
+pub fn parse_publish_at(raw: &str) -> Result<Option<String>, String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    DateTime::parse_from_rfc3339(raw)
+        .map(|at| at.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M")
+                .or_else(|_| NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M"))
+                .map(|at| at.and_utc())
+        })
+        .map(|at| Some(at.to_rfc3339_opts(SecondsFormat::Secs, true)))
+        .map_err(|_| {
+            format!("publish_at '{raw}' must be a UTC time such as 2026-11-01T09:00 or RFC 3339")
+        })
+}
+
+/// A stored publish time; `None` when it does not parse.
+pub fn publish_time(stored: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(stored)
+        .ok()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+/// The value of the admin form's `datetime-local` input for a stored
+/// publish time.
+pub fn publish_at_input(stored: &str) -> String {
+    publish_time(stored)
+        .map(|at| at.format("%Y-%m-%dT%H:%M").to_string())
+        .unwrap_or_else(|| stored.to_string())
+}
+
+/// Minerals scheduled for after `since` and up to `now`.
+fn due(
+    minerals: &[Mineral],
+    since: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> impl Iterator<Item = &Mineral> {
+    minerals.iter().filter(move |mineral| {
+        mineral
+            .publish_time()
+            .is_some_and(|at| since < at && at <= now)
+    })
+}
+
+/// Publishes due minerals every `CHECK_INTERVAL`. A failed check is
+/// retried from the same point on the next tick, skipping the minerals it
+/// already published so they are not announced twice.
+pub async fn publish_periodically(state: AppState) {
+    let mut since = Utc::now();
+    let mut published = HashSet::new();
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        let now = Utc::now();
+        match publish_due(&state, since, now, &mut published).await {
+            Ok(()) => {
+                since = now;
+                published.clear();
+            }
+            Err(err) => warn!("scheduled publishing failed: {err:#}"),
+        }
+    }
+}
+
+async fn publish_due(
+    state: &AppState,
+    since: DateTime<Utc>,
+    now: DateTime<Utc>,
+    published: &mut HashSet<String>,
+) -> Result<()> {
+    let catalog = catalog_for_language(state, Language::En)
+        .await
+        .map_err(|err| anyhow::anyhow!("{err}"))?;
+    publish_each(
+        &catalog.ordered,
+        since,
+        now,
+        published,
+        |mineral| async move {
+            // Rebuilds the public views, which were made while it was hidden.
+            refresh_mineral(state, &mineral.folder_name).await?;
+            info!("published {} on schedule", mineral.slug);
+            if mineral.visibility.is_reachable() {
+                let url = state.pdf_generator.mineral_url(&mineral.slug, Language::En);
+                state.publish_notifier.notify(&mineral, &url).await;
+            }
+            Ok(())
+        },
+    )
+    .await
+}
+
+/// Runs `publish` on each mineral due between `since` and `now` whose
+/// folder is not in `published` yet, and records the folder as soon as it
+/// succeeds. Stops at the first failure.
+async fn publish_each<F, Fut>(
+    minerals: &[Mineral],
+    since: DateTime<Utc>,
+    now: DateTime<Utc>,
+    published: &mut HashSet<String>,
+    mut publish: F,
+) -> Result<()>
+where
+    F: FnMut(Mineral) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    for mineral in due(minerals, since, now) {
+        if published.contains(&mineral.folder_name) {
+            continue;
+        }
+        publish(mineral.clone()).await?;
+        published.insert(mineral.folder_name.clone());
+    }
+    Ok(())
+}
+
+/// Where scheduled minerals are announced once live; optional.
+pub struct PublishNotifier {
+    http: Client,
+    webhook_url: Option<String>,
+}
+
+impl PublishNotifier {
+    pub fn new(http: Client, webhook_url: Option<String>) -> Self {
+        Self { http, webhook_url }
+    }
+
+    /// Posts the announcement; failures are logged, since the mineral is
+    /// live either way.
+    pub async fn notify(&self, mineral: &Mineral, mineral_url: &str) {
+        let Some(url) = &self.webhook_url else {
+            return;
+        };
+        if let Err(err) = self.post_webhook(url, mineral, mineral_url).await {
+            warn!("publish webhook for {} failed: {err:#}", mineral.slug);
+        }
+    }
+
+    /// JSON with the mineral, plus a `text` summary that Slack-style
+    /// incoming webhooks display as is.
+    async fn post_webhook(&self, url: &str, mineral: &Mineral, mineral_url: &str) -> Result<()> {
+        let body = json!({
+            "event": "mineral.published",
+            "text": format!("{} is now published: {mineral_url}", mineral.common_name),
+            "mineral": {
+                "slug": mineral.slug,
+                "name": mineral.common_name,
+                "family": mineral.mineral_family,
+                "url": mineral_url,
+                "visibility": mineral.visibility,
+                "publish_at": mineral.publish_at,
+            },
+        });
+        self.http
+            .post(url)
+            .timeout(NOTIFY_TIMEOUT)
+            .json(&body)
+            .send()
+            .await
+            .context("request failed")?
+            .error_for_status()
+            .context("webhook refused the announcement")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+
+    use anyhow::bail;
+    use chrono::{TimeZone, Utc};
+
+    use super::{due, parse_publish_at, publish_at_input, publish_each};
+    use crate::{
+        models::{sample_mineral, Mineral},
+        visibility::Visibility,
+    };
+
+    #[test]
+    fn parses_publish_times_and_finds_due_minerals() {
+        let at = Utc.with_ymd_and_hms(2026, 11, 1, 9, 0, 0).unwrap();
+        let stored = Some("2026-11-01T09:00:00Z".to_string());
+        assert_eq!(parse_publish_at("2026-11-01T09:00"), Ok(stored.clone()));
+        assert_eq!(parse_publish_at("2026-11-01T10:00:00+01:00"), Ok(stored));
+        assert_eq!(parse_publish_at(" "), Ok(None));
+        assert!(parse_publish_at("next week").is_err());
+        assert_eq!(publish_at_input("2026-11-01T09:00:00Z"), "2026-11-01T09:00");
+
+        let scheduled = |slug: &str, hour: u32| Mineral {
+            slug: slug.to_string(),
+            folder_name: slug.to_string(),
+            common_name: slug.to_string(),
+            hardness_mohs: 1.0,
+            density_g_cm3: 1.0,
+            visibility: Visibility::Public,
+            publish_at: Some(format!("2026-11-01T{hour:02}:00:00Z")),
+            ..sample_mineral()
+        };
+        let minerals = [scheduled("early", 8), scheduled("on-time", 9)];
+        let since = Utc.with_ymd_and_hms(2026, 11, 1, 8, 0, 0).unwrap();
+        let slugs = due(&minerals, since, at)
+            .map(|mineral| mineral.slug.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(slugs, ["on-time"]);
+    }
+
+    #[tokio::test]
+    async fn retries_only_what_a_failed_check_left_unpublished() {
+        let scheduled = |slug: &str| Mineral {
+            slug: slug.to_string(),
+            folder_name: slug.to_string(),
+            publish_at: Some("2026-11-01T09:00:00Z".to_string()),
+            ..sample_mineral()
+        };
+        let minerals = [scheduled("first"), scheduled("second"), scheduled("third")];
+        let since = Utc.with_ymd_and_hms(2026, 11, 1, 8, 0, 0).unwrap();
+        let now = Utc.with_ymd_and_hms(2026, 11, 1, 9, 0, 30).unwrap();
+        let mut published = HashSet::new();
+        let mut announced = Vec::new();
+
+        let result = publish_each(&minerals, since, now, &mut published, |mineral| {
+            let failing = mineral.slug == "second";
+            announced.push(mineral.slug.clone());
+            async move {
+                if failing {
+                    bail!("refresh failed");
+                }
+                Ok(())
+            }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(announced, ["first", "second"]);
+
+        announced.clear();
+        publish_each(&minerals, since, now, &mut published, |mineral| {
+            announced.push(mineral.slug.clone());
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(announced, ["second", "third"]);
+    }
+}
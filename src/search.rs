@@ -0,0 +1,465 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap},
+};
+
+use serde::Serialize;
+
+use crate::models::Mineral;
+
+/// Field weights used to rank matches; a hit in the name outranks a hit in
+/// the free-text notes.
+const WEIGHT_NAME: u32 = 8;
+const WEIGHT_FAMILY: u32 = 4;
+const WEIGHT_FORMULA: u32 = 4;
+const WEIGHT_COLOR: u32 = 2;
+const WEIGHT_TAGS: u32 = 2;
+const WEIGHT_NOTES: u32 = 1;
+
+/// Shortest term matched with typos; shorter ones differ from too many
+/// words by a single letter.
+const FUZZY_MIN_CHARS: usize = 4;
+
+/// Inverted index over the searchable text fields of a catalog.
+///
+/// Postings reference minerals by slug, so one mineral can be added or
+/// removed without touching the others' entries.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    postings: BTreeMap<String, HashMap<String, u32>>,
+    /// Common name per slug, to break ties in catalog order.
+    names: HashMap<String, String>,
+}
+
+impl SearchIndex {
+    #[cfg(test)]
+    pub fn build(minerals: &[Mineral]) -> Self {
+        let mut index = Self::default();
+        for mineral in minerals {
+            index.insert(mineral);
+        }
+        index
+    }
+
+    pub fn insert(&mut self, mineral: &Mineral) {
+        for (text, weight) in fields(mineral) {
+            for token in tokenize(&text) {
+                let entry = self
+                    .postings
+                    .entry(token)
+                    .or_default()
+                    .entry(mineral.slug.clone())
+                    .or_default();
+                *entry = (*entry).max(weight);
+            }
+        }
+        self.names
+            .insert(mineral.slug.clone(), mineral.common_name.clone());
+    }
+
+    /// Drops `mineral`, as it was inserted, from the postings of its own
+    /// words only.
+    pub fn remove(&mut self, mineral: &Mineral) {
+        for (text, _) in fields(mineral) {
+            for token in tokenize(&text) {
+                if let Some(docs) = self.postings.get_mut(&token) {
+                    docs.remove(&mineral.slug);
+                    if docs.is_empty() {
+                        self.postings.remove(&token);
+                    }
+                }
+            }
+        }
+        self.names.remove(&mineral.slug);
+    }
+
+    /// Returns the slugs of minerals matching every query term, best first.
+    /// A term matches the words it begins and, when it has at least
+    /// [`FUZZY_MIN_CHARS`] letters, those it begins but for up to
+    /// `max_distance` typos (`quarts`, `malachyte`). Results with fewer
+    /// typos rank first, then by score; ties sort by name.
+    pub fn search(&self, query: &str, max_distance: usize) -> Vec<String> {
+        let terms = tokenize(query);
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        // Per slug: (typos, score).
+        let mut scores: Option<HashMap<&str, (usize, u32)>> = None;
+        for term in &terms {
+            let mut term_scores: HashMap<&str, (usize, u32)> = HashMap::new();
+            for (_, docs) in self
+                .postings
+                .range(term.clone()..)
+                .take_while(|(token, _)| token.starts_with(term.as_str()))
+            {
+                add_matches(&mut term_scores, docs, 0);
+            }
+            let chars = term.chars().collect::<Vec<_>>();
+            if max_distance > 0 && chars.len() >= FUZZY_MIN_CHARS {
+                for (token, docs) in &self.postings {
+                    if token.starts_with(term.as_str())
+                        || token.chars().count() + max_distance < chars.len()
+                    {
+                        continue;
+                    }
+                    let typos = prefix_distance(&chars, &token.chars().collect::<Vec<_>>());
+                    if typos <= max_distance {
+                        add_matches(&mut term_scores, docs, typos);
+                    }
+                }
+            }
+
+            scores = Some(match scores {
+                None => term_scores,
+                Some(mut acc) => {
+                    acc.retain(|slug, _| term_scores.contains_key(slug));
+                    for (slug, (typos, score)) in acc.iter_mut() {
+                        let (term_typos, term_score) = term_scores[slug];
+                        *typos += term_typos;
+                        *score += term_score;
+                    }
+                    acc
+                }
+            });
+        }
+
+        let name = |slug: &str| self.names.get(slug).map(String::as_str).unwrap_or_default();
+        let mut ranked: Vec<(&str, (usize, u32))> =
+            scores.unwrap_or_default().into_iter().collect();
+        ranked.sort_by(
+            |(slug_a, (typos_a, score_a)), (slug_b, (typos_b, score_b))| {
+                typos_a
+                    .cmp(typos_b)
+                    .then(score_b.cmp(score_a))
+                    .then_with(|| name(slug_a).cmp(name(slug_b)))
+                    .then(slug_a.cmp(slug_b))
+            },
+        );
+        ranked
+            .into_iter()
+            .map(|(slug, _)| slug.to_string())
+            .collect()
+    }
+}
+
+/// Counts `docs` as matching one term with `typos` typos, keeping each
+/// mineral's best match.
+fn add_matches<'a>(
+    scores: &mut HashMap<&'a str, (usize, u32)>,
+    docs: &'a HashMap<String, u32>,
+    typos: usize,
+) {
+    for (slug, weight) in docs {
+        let entry = scores.entry(slug.as_str()).or_insert((typos, 0));
+        if typos < entry.0 {
+            *entry = (typos, *weight);
+        } else if typos == entry.0 {
+            entry.1 = entry.1.max(*weight);
+        }
+    }
+}
+
+/// The searchable text of `mineral` with its weight.
+fn fields(mineral: &Mineral) -> [(Cow<'_, str>, u32); 6] {
+    [
+        (Cow::from(&mineral.common_name), WEIGHT_NAME),
+        (Cow::from(&mineral.mineral_family), WEIGHT_FAMILY),
+        (Cow::from(&mineral.formula), WEIGHT_FORMULA),
+        (Cow::from(&mineral.color), WEIGHT_COLOR),
+        (Cow::from(mineral.tags.join(" ")), WEIGHT_TAGS),
+        (Cow::from(&mineral.notes), WEIGHT_NOTES),
+    ]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestKind {
+    Mineral,
+    Family,
+}
+
+#[derive(Debug, Clone)]
+struct SuggestEntry {
+    kind: SuggestKind,
+    label: String,
+    words: Vec<Vec<char>>,
+}
+
+/// Mineral names and family names for search-as-you-type; much smaller
+/// than [`SearchIndex`], since only labels are matched.
+#[derive(Debug, Clone, Default)]
+pub struct SuggestIndex {
+    /// Per slug.
+    minerals: HashMap<String, SuggestEntry>,
+    /// Per lowercased family: its label as first spelled, and the names
+    /// and slugs of its minerals in catalog order.
+    families: HashMap<String, (SuggestEntry, BTreeSet<(String, String)>)>,
+}
+
+/// One match of [`SuggestIndex::suggest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub kind: SuggestKind,
+    pub label: String,
+    /// The mineral, or for a family its first mineral in catalog order.
+    pub slug: String,
+}
+
+impl SuggestIndex {
+    #[cfg(test)]
+    pub fn build(minerals: &[Mineral]) -> Self {
+        let mut index = Self::default();
+        for mineral in minerals {
+            index.insert(mineral);
+        }
+        index
+    }
+
+    pub fn insert(&mut self, mineral: &Mineral) {
+        let entry = SuggestEntry::new(SuggestKind::Mineral, &mineral.common_name);
+        if !entry.words.is_empty() {
+            self.minerals.insert(mineral.slug.clone(), entry);
+        }
+        let family = mineral.mineral_family.trim();
+        if !family.is_empty() {
+            self.families
+                .entry(family.to_lowercase())
+                .or_insert_with(|| {
+                    (
+                        SuggestEntry::new(SuggestKind::Family, family),
+                        BTreeSet::new(),
+                    )
+                })
+                .1
+                .insert((mineral.common_name.clone(), mineral.slug.clone()));
+        }
+    }
+
+    /// Drops `mineral`, as it was inserted, and its family once no other
+    /// mineral has it.
+    pub fn remove(&mut self, mineral: &Mineral) {
+        self.minerals.remove(&mineral.slug);
+        let family = mineral.mineral_family.trim().to_lowercase();
+        if let Some((_, members)) = self.families.get_mut(&family) {
+            members.remove(&(mineral.common_name.clone(), mineral.slug.clone()));
+            if members.is_empty() {
+                self.families.remove(&family);
+            }
+        }
+    }
+
+    /// Up to `limit` labels for `query`: those starting with it first,
+    /// then those with a word starting with it, then those with a word
+    /// starting with it but for up to `max_distance` typos. Minerals come
+    /// before families within each group, then alphabetical.
+    pub fn suggest(&self, query: &str, limit: usize, max_distance: usize) -> Vec<Suggestion> {
+        let query = query.trim().to_lowercase();
+        let query_words = tokenize(&query);
+        let Some(last) = query_words.last() else {
+            return Vec::new();
+        };
+        let last = last.chars().collect::<Vec<_>>();
+        let fuzzy = max_distance > 0 && last.len() >= FUZZY_MIN_CHARS;
+
+        let entries = self
+            .minerals
+            .iter()
+            .map(|(slug, entry)| (entry, slug))
+            .chain(
+                self.families
+                    .values()
+                    .filter_map(|(entry, members)| members.first().map(|(_, slug)| (entry, slug))),
+            );
+        let mut ranked = entries
+            .filter_map(|(entry, slug)| {
+                let rank = if entry.label.to_lowercase().starts_with(&query) {
+                    0
+                } else if entry.words.iter().any(|word| word.starts_with(&last)) {
+                    1
+                } else if fuzzy
+                    && entry
+                        .words
+                        .iter()
+                        .any(|word| prefix_distance(&last, word) <= max_distance)
+                {
+                    2
+                } else {
+                    return None;
+                };
+                Some((rank, entry, slug))
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_by(|(rank_a, a, slug_a), (rank_b, b, slug_b)| {
+            rank_a
+                .cmp(rank_b)
+                .then(a.kind.cmp(&b.kind))
+                .then_with(|| a.label.to_lowercase().cmp(&b.label.to_lowercase()))
+                .then(slug_a.cmp(slug_b))
+        });
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(_, entry, slug)| Suggestion {
+                kind: entry.kind,
+                label: entry.label.clone(),
+                slug: slug.clone(),
+            })
+            .collect()
+    }
+}
+
+impl SuggestEntry {
+    fn new(kind: SuggestKind, label: &str) -> Self {
+        Self {
+            kind,
+            label: label.trim().to_string(),
+            words: tokenize(label)
+                .iter()
+                .map(|word| word.chars().collect())
+                .collect(),
+        }
+    }
+}
+
+/// Edits needed to turn `query` into the closest prefix of `word`, so
+/// `quatz` is one edit from `quartz` however long the word goes on.
+fn prefix_distance(query: &[char], word: &[char]) -> usize {
+    let mut row = (0..=word.len()).collect::<Vec<_>>();
+    for (i, query_char) in query.iter().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, word_char) in word.iter().enumerate() {
+            let substitution = previous + usize::from(query_char != word_char);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+    row.into_iter().min().unwrap_or_default()
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{SearchIndex, SuggestIndex, SuggestKind};
+    use crate::models::{sample_mineral, Mineral};
+
+    fn mineral(name: &str, family: &str, formula: &str, notes: &str) -> Mineral {
+        Mineral {
+            slug: name.to_lowercase().replace(' ', "-"),
+            folder_name: name.to_lowercase().replace(' ', "-"),
+            common_name: name.to_string(),
+            mineral_family: family.to_string(),
+            formula: formula.to_string(),
+            hardness_mohs: 5.0,
+            density_g_cm3: 3.0,
+            crystal_system: "cubic".to_string(),
+            color: "white".to_string(),
+            streak: "white".to_string(),
+            luster: "vitreous".to_string(),
+            notes: notes.to_string(),
+            ..sample_mineral()
+        }
+    }
+
+    #[test]
+    fn ranks_name_hits_above_notes_and_requires_all_terms() {
+        let minerals = vec![
+            mineral(
+                "Calcite",
+                "carbonates",
+                "CaCO3",
+                "often found near quartz veins",
+            ),
+            mineral("Quartz", "silicates", "SiO2", "common"),
+            mineral("Rose Quartz", "silicates", "SiO2", "pink variety"),
+        ];
+        let index = SearchIndex::build(&minerals);
+
+        assert_eq!(
+            index.search("quar", 1),
+            ["quartz", "rose-quartz", "calcite"]
+        );
+        assert_eq!(index.search("quartz pink", 1), ["rose-quartz"]);
+        assert!(index.search("   ", 1).is_empty());
+    }
+
+    #[test]
+    fn tolerates_typos_up_to_the_threshold_and_ranks_exact_hits_first() {
+        let minerals = vec![
+            mineral("Malachite", "carbonates", "Cu2CO3(OH)2", ""),
+            mineral("Quartz", "silicates", "SiO2", ""),
+            mineral("Quarts Lode", "silicates", "SiO2", "mine name"),
+        ];
+        let index = SearchIndex::build(&minerals);
+
+        assert_eq!(index.search("malachyte", 1), ["malachite"]);
+        assert_eq!(index.search("quarts", 1), ["quarts-lode", "quartz"]);
+        assert_eq!(index.search("quarts", 0), ["quarts-lode"]);
+        assert!(index.search("malashyte", 1).is_empty());
+        assert_eq!(index.search("malashyte", 2), ["malachite"]);
+        assert!(index.search("quat", 0).is_empty());
+        assert!(index.search("qaz", 2).is_empty());
+    }
+
+    #[test]
+    fn suggests_prefixes_before_typos() {
+        let minerals = vec![
+            mineral("Quartz", "silicates", "SiO2", ""),
+            mineral("Rose Quartz", "silicates", "SiO2", ""),
+            mineral("Malachite", "carbonates", "Cu2CO3(OH)2", ""),
+        ];
+        let index = SuggestIndex::build(&minerals);
+        let labels = |query: &str| {
+            index
+                .suggest(query, 10, 1)
+                .into_iter()
+                .map(|suggestion| suggestion.label)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(labels("qua"), ["Quartz", "Rose Quartz"]);
+        assert_eq!(labels("quatz"), ["Quartz", "Rose Quartz"]);
+        assert_eq!(labels("malak"), ["Malachite"]);
+        assert_eq!(labels("ca"), ["carbonates"]);
+        assert_eq!(index.suggest("sil", 10, 1)[0].kind, SuggestKind::Family);
+        assert_eq!(index.suggest("qu", 1, 1).len(), 1);
+        assert!(labels("qz").is_empty());
+        assert!(labels(" ").is_empty());
+    }
+
+    #[test]
+    fn removes_one_mineral_from_both_indexes() {
+        let quartz = mineral("Quartz", "silicates", "SiO2", "");
+        let malachite = mineral("Malachite", "carbonates", "Cu2CO3(OH)2", "");
+        let minerals = vec![
+            quartz.clone(),
+            mineral("Rose Quartz", "silicates", "SiO2", ""),
+            malachite.clone(),
+        ];
+        let mut search = SearchIndex::build(&minerals);
+        let mut suggest = SuggestIndex::build(&minerals);
+
+        search.remove(&quartz);
+        suggest.remove(&quartz);
+        suggest.remove(&malachite);
+        assert_eq!(search.search("quar", 0), ["rose-quartz"]);
+        assert_eq!(search.search("sio2", 0), ["rose-quartz"]);
+        let suggestions = suggest.suggest("s", 10, 0);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].label, "silicates");
+        assert_eq!(suggestions[0].slug, "rose-quartz");
+        assert!(suggest.suggest("mala", 10, 0).is_empty());
+        assert!(suggest.suggest("carb", 10, 0).is_empty());
+
+        search.insert(&quartz);
+        assert_eq!(search.search("quar", 0), ["quartz", "rose-quartz"]);
+    }
+}
@@ -0,0 +1,221 @@
+//! Storefront: an optional price, currency, and stock count on each
+//! mineral, and the `/shop` listing of the ones that can be bought.
+
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::HeaderMap,
+    response::Redirect,
+    Form,
+};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{
+    csrf, folder_file_name, history,
+    i18n::{ui_text, Language},
+    locale_format::LocaleFormat,
+    mineral_folder_for_slug, read_record_file, refresh_mineral, resolve_language, visible_catalog,
+    web::{ShopTemplate, TemplateResponse},
+    write_metadata_file, AppError, AppState,
+};
+
+/// Stored flat in the metadata JSON (`for_sale`, `price`, `currency`,
+/// `quantity_available`) and shared by every language.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Listing {
+    pub for_sale: bool,
+    pub price: Option<f64>,
+    /// ISO 4217 code such as `USD`; required with a price.
+    pub currency: String,
+    /// `None` when stock is not tracked; `0` is sold out.
+    pub quantity_available: Option<u32>,
+}
+
+impl Listing {
+    /// Builds a listing from form text; a blank price or quantity means
+    /// none.
+    pub fn parse(
+        for_sale: bool,
+        price: &str,
+        currency: &str,
+        quantity_available: &str,
+    ) -> Result<Self, String> {
+        let price = match price.trim() {
+            "" => None,
+            value => Some(
+                value
+                    .parse::<f64>()
+                    .map_err(|_| "'price' must be a decimal number".to_string())?,
+            ),
+        };
+        let quantity_available = match quantity_available.trim() {
+            "" => None,
+            value => Some(
+                value
+                    .parse::<u32>()
+                    .map_err(|_| "'quantity_available' must be a whole number".to_string())?,
+            ),
+        };
+        Self {
+            for_sale,
+            price,
+            currency: currency.to_string(),
+            quantity_available,
+        }
+        .validate()
+    }
+
+    /// Upper-cases the currency and checks that a price is non-negative,
+    /// comes with a currency, and is set when the mineral is for sale.
+    pub fn validate(self) -> Result<Self, String> {
+        let currency = self.currency.trim().to_ascii_uppercase();
+        let is_code = currency.len() == 3 && currency.bytes().all(|byte| byte.is_ascii_uppercase());
+        if !currency.is_empty() && !is_code {
+            return Err("'currency' must be a three-letter ISO 4217 code such as USD".to_string());
+        }
+        match self.price {
+            Some(price) if !price.is_finite() || price < 0.0 => {
+                return Err("'price' must be a non-negative number".to_string());
+            }
+            Some(_) if currency.is_empty() => {
+                return Err("'currency' is required with a price".to_string());
+            }
+            None if self.for_sale => {
+                return Err("a mineral listed for sale needs a 'price'".to_string());
+            }
+            _ => {}
+        }
+        Ok(Self { currency, ..self })
+    }
+
+    /// For sale, priced, and not sold out.
+    pub fn is_available(&self) -> bool {
+        self.for_sale && self.price.is_some() && self.quantity_available != Some(0)
+    }
+
+    pub fn is_sold_out(&self) -> bool {
+        self.for_sale && self.quantity_available == Some(0)
+    }
+
+    pub fn price_text(&self) -> String {
+        self.price
+            .map(|value| value.to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn quantity_text(&self) -> String {
+        self.quantity_available
+            .map(|value| value.to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// `/shop`: the catalog narrowed to minerals that can be bought, in
+/// catalog order.
+pub async fn shop_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<TemplateResponse<ShopTemplate>, AppError> {
+    let language = resolve_language(&state, &headers);
+    let minerals = visible_catalog(&state, &headers, language)
+        .await?
+        .ordered
+        .iter()
+        .filter(|mineral| mineral.listing.is_available())
+        .cloned()
+        .collect();
+    Ok(TemplateResponse(ShopTemplate {
+        lang_code: language.code().to_string(),
+        lang_dir: language.dir().to_string(),
+        txt: ui_text(language),
+        locale: LocaleFormat::new(language),
+        minerals,
+    }))
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ForSaleForm {
+    csrf_token: String,
+    /// `false` takes the mineral out of the shop.
+    for_sale: bool,
+}
+
+/// Lists or unlists a mineral in every language file without touching
+/// anything else, so no retranslation is needed.
+pub async fn set_for_sale(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+    Form(form): Form<ForSaleForm>,
+) -> Result<Redirect, AppError> {
+    csrf::verify(&state, &headers, &form.csrf_token)?;
+    let folder_path = mineral_folder_for_slug(&state, Language::En, &slug)
+        .await
+        .map_err(AppError::NotFound)?;
+
+    let mut paths = Vec::new();
+    let mut entries = fs::read_dir(&folder_path)
+        .await
+        .map_err(|err| AppError::Internal(err.into()))?;
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|err| AppError::Internal(err.into()))?
+    {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with("mineral.") && name.ends_with(".json") {
+            paths.push(entry.path());
+        }
+    }
+
+    let mut records = Vec::new();
+    for path in paths {
+        let mut record = read_record_file(&path).await?;
+        record.listing = Listing {
+            for_sale: form.for_sale,
+            ..record.listing
+        }
+        .validate()
+        .map_err(|err| AppError::BadRequest(format!("{slug}: {err}; set it on the edit form")))?;
+        records.push((path, record));
+    }
+
+    history::record(&folder_path, "edit").await?;
+    for (path, record) in &records {
+        write_metadata_file(path, record).await?;
+    }
+    refresh_mineral(&state, &folder_file_name(&folder_path)).await?;
+    Ok(Redirect::to("/admin#manage"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Listing;
+
+    #[test]
+    fn validates_prices_and_availability() {
+        let listing = Listing::parse(true, " 120.5 ", "usd", "3").unwrap();
+        assert_eq!(listing.price, Some(120.5));
+        assert_eq!(listing.currency, "USD");
+        assert!(listing.is_available());
+
+        let sold_out = Listing::parse(true, "120", "EUR", "0").unwrap();
+        assert!(!sold_out.is_available());
+        assert!(sold_out.is_sold_out());
+
+        // Stock is untracked when the quantity is blank.
+        assert!(Listing::parse(true, "9", "JPY", "").unwrap().is_available());
+        assert!(!Listing::parse(false, "9", "JPY", "")
+            .unwrap()
+            .is_available());
+        assert_eq!(Listing::parse(false, "", "", ""), Ok(Listing::default()));
+
+        assert!(Listing::parse(true, "", "USD", "").is_err());
+        assert!(Listing::parse(false, "10", "", "").is_err());
+        assert!(Listing::parse(false, "-1", "USD", "").is_err());
+        assert!(Listing::parse(false, "10", "dollars", "").is_err());
+        assert!(Listing::parse(false, "10", "USD", "2.5").is_err());
+    }
+}
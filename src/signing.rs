@@ -0,0 +1,362 @@
+//! Integrity records for generated reports. Every `report.pdf` gets a
+//! `report.sig` next to it holding the PDF's SHA-256 digest and, when a
+//! signing key is configured, an Ed25519 signature over the raw 32-byte
+//! digest. `GET /api/reports/:slug/verify` checks the stored PDF of the
+//! latest run (or of `?run=`), or the digest of a recipient's copy,
+//! against that record; `GET /api/certificates/:slug/verify` does the same
+//! for specimen certificates.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use axum::{
+    extract::{Path as AxumPath, Query, State},
+    http::HeaderMap,
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::Utc;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tracing::info;
+
+use crate::{
+    auth::sha256_hex, crypto, get_mineral, mineral_folder_for_slug, report_runs, require_visible,
+    resolve_language, AppError, AppState,
+};
+
+pub const SIGNATURE_FILE: &str = "report.sig";
+
+/// Contents of `report.sig`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportSignature {
+    pub file: String,
+    /// Lowercase hex.
+    pub sha256: String,
+    pub algorithm: Option<String>,
+    /// Base64; absent when no signing key is configured.
+    pub signature: Option<String>,
+    /// Base64 Ed25519 public key that produced `signature`.
+    pub public_key: Option<String>,
+    pub signed_utc: String,
+}
+
+/// Signs report digests with the key from `REPORT_SIGNING_KEY_FILE`, or
+/// only records digests when none is configured.
+#[derive(Clone, Default)]
+pub struct ReportSigner {
+    key: Option<SigningKey>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct VerifyQuery {
+    /// Digest of the recipient's copy; the stored `report.pdf` is hashed
+    /// when absent.
+    #[serde(default)]
+    sha256: Option<String>,
+    /// Report run to check; the newest when absent.
+    #[serde(default)]
+    run: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VerifyResponse {
+    slug: String,
+    run: String,
+    file: String,
+    /// Digest recorded when the report was generated.
+    sha256: String,
+    signed_utc: String,
+    /// `query` when the caller sent a digest, `stored` when the server
+    /// hashed its own copy.
+    checked: &'static str,
+    digest_matches: bool,
+    /// Whether the signature is valid under this server's current key;
+    /// `null` when the report is unsigned or no key is configured.
+    signature_valid: Option<bool>,
+    public_key: Option<String>,
+    /// Digest matches and the signature, if any, is valid.
+    verified: bool,
+}
+
+impl ReportSigner {
+    /// Reads a base64 32-byte Ed25519 seed from `path`, creating the file
+    /// with a fresh key on first start.
+    pub async fn load(path: Option<&Path>) -> Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self::default());
+        };
+        let seed = match fs::read_to_string(path).await {
+            Ok(raw) => decode_seed(&raw)
+                .with_context(|| format!("invalid signing key in {}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                let seed = random_seed()?;
+                store_seed(path, &seed).await?;
+                info!("created report signing key {}", path.display());
+                seed
+            }
+            Err(err) => return Err(anyhow!("failed to read {}: {err}", path.display())),
+        };
+        let signer = Self {
+            key: Some(SigningKey::from_bytes(&seed)),
+        };
+        info!(
+            "signing reports with Ed25519 key {}",
+            signer.public_key().unwrap_or_default()
+        );
+        Ok(signer)
+    }
+
+    pub fn public_key(&self) -> Option<String> {
+        self.key
+            .as_ref()
+            .map(|key| BASE64.encode(key.verifying_key().as_bytes()))
+    }
+
+    pub fn sign(&self, file: &str, contents: &[u8]) -> ReportSignature {
+        let digest = Sha256::digest(contents);
+        ReportSignature {
+            file: file.to_string(),
+            sha256: sha256_hex(contents),
+            algorithm: self.key.as_ref().map(|_| "ed25519".to_string()),
+            signature: self
+                .key
+                .as_ref()
+                .map(|key| BASE64.encode(key.sign(&digest).to_bytes())),
+            public_key: self.public_key(),
+            signed_utc: Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Signature check against this server's key, not the one in the file,
+    /// so a rewritten `report.sig` cannot vouch for itself.
+    pub fn signature_valid(&self, record: &ReportSignature) -> Option<bool> {
+        let key = self.key.as_ref()?.verifying_key();
+        let signature = record.signature.as_deref()?;
+        Some(verify_signature(&key, &record.sha256, signature))
+    }
+}
+
+/// Hashes and signs `run_dir/report.pdf` into `run_dir/report.sig`.
+pub async fn write_signature(signer: &ReportSigner, run_dir: &Path) -> Result<()> {
+    let pdf_file = run_dir.join("report.pdf");
+    let pdf = fs::read(&pdf_file)
+        .await
+        .with_context(|| format!("failed to read {}", pdf_file.display()))?;
+    let record = signer.sign("report.pdf", &pdf);
+    let sig_file = run_dir.join(SIGNATURE_FILE);
+    let json = serde_json::to_string_pretty(&record).context("failed to serialize signature")?;
+    fs::write(&sig_file, json)
+        .await
+        .with_context(|| format!("failed to write {}", sig_file.display()))
+}
+
+pub async fn verify_report(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+    Query(query): Query<VerifyQuery>,
+) -> Result<Json<VerifyResponse>, AppError> {
+    verify_run(
+        &state,
+        &headers,
+        slug,
+        report_runs::REPORTS_DIR,
+        "report",
+        query,
+    )
+    .await
+}
+
+/// Target of the QR code printed on each certificate.
+pub async fn verify_certificate(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+    Query(query): Query<VerifyQuery>,
+) -> Result<Json<VerifyResponse>, AppError> {
+    verify_run(
+        &state,
+        &headers,
+        slug,
+        report_runs::CERTIFICATES_DIR,
+        "certificate",
+        query,
+    )
+    .await
+}
+
+/// Checks a run in the mineral's `runs_dir_name` folder; `kind` names
+/// the document in errors. Private and scheduled minerals answer 404 like
+/// their pages, so the endpoint does not reveal that they exist.
+async fn verify_run(
+    state: &AppState,
+    headers: &HeaderMap,
+    slug: String,
+    runs_dir_name: &str,
+    kind: &str,
+    query: VerifyQuery,
+) -> Result<Json<VerifyResponse>, AppError> {
+    let language = resolve_language(state, headers);
+    let mineral = get_mineral(state, language, &slug).await?;
+    require_visible(state, headers, &slug, &mineral)?;
+    let folder_path = mineral_folder_for_slug(state, language, &slug)
+        .await
+        .map_err(AppError::NotFound)?;
+    let runs_dir = folder_path.join(runs_dir_name);
+    let run_dir = report_runs::run_dir(&runs_dir, query.run.as_deref())
+        .await?
+        .ok_or_else(|| match &query.run {
+            Some(run) => AppError::NotFound(format!("no {kind} run '{run}' for '{slug}'")),
+            None => AppError::NotFound(format!("no signed {kind} has been generated")),
+        })?;
+    let record = read_signature(&run_dir, kind).await?;
+
+    let (checked, digest) = match query.sha256 {
+        Some(digest) => {
+            let digest = digest.trim().to_ascii_lowercase();
+            if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(AppError::BadRequest(
+                    "'sha256' must be 64 hex characters".to_string(),
+                ));
+            }
+            ("query", digest)
+        }
+        None => {
+            let pdf_file = run_dir.join(&record.file);
+            let pdf = fs::read(&pdf_file).await.map_err(|_| {
+                AppError::NotFound(format!("{kind} for '{slug}' has no {}", record.file))
+            })?;
+            ("stored", sha256_hex(&pdf))
+        }
+    };
+
+    let digest_matches = digest == record.sha256;
+    let signature_valid = state.pdf_generator.signer().signature_valid(&record);
+    Ok(Json(VerifyResponse {
+        slug,
+        run: run_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        file: record.file,
+        sha256: record.sha256,
+        signed_utc: record.signed_utc,
+        checked,
+        digest_matches,
+        signature_valid,
+        public_key: record.public_key,
+        verified: digest_matches && signature_valid != Some(false),
+    }))
+}
+
+async fn read_signature(run_dir: &Path, kind: &str) -> Result<ReportSignature, AppError> {
+    let path = run_dir.join(SIGNATURE_FILE);
+    let raw = fs::read_to_string(&path)
+        .await
+        .map_err(|_| AppError::NotFound(format!("no signed {kind} has been generated")))?;
+    Ok(
+        serde_json::from_str(&raw)
+            .with_context(|| format!("failed to parse {}", path.display()))?,
+    )
+}
+
+fn verify_signature(key: &VerifyingKey, sha256_hex: &str, signature: &str) -> bool {
+    let Some(digest) = unhex(sha256_hex) else {
+        return false;
+    };
+    let Some(signature) = BASE64
+        .decode(signature)
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+    else {
+        return false;
+    };
+    key.verify(&digest, &signature).is_ok()
+}
+
+fn decode_seed(raw: &str) -> Result<[u8; 32]> {
+    BASE64
+        .decode(raw.trim())
+        .context("expected base64")?
+        .try_into()
+        .map_err(|_| anyhow!("expected a 32-byte Ed25519 seed"))
+}
+
+fn random_seed() -> Result<[u8; 32]> {
+    let mut seed = [0u8; 32];
+    crypto::fill_random(&mut seed)?;
+    Ok(seed)
+}
+
+async fn store_seed(path: &Path, seed: &[u8; 32]) -> Result<()> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let tmp_path = PathBuf::from(format!("{}.tmp", path.display()));
+    fs::write(&tmp_path, format!("{}\n", BASE64.encode(seed)))
+        .await
+        .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o600))
+            .await
+            .with_context(|| format!("failed to restrict {}", tmp_path.display()))?;
+    }
+    fs::rename(&tmp_path, path)
+        .await
+        .with_context(|| format!("failed to replace {}", path.display()))
+}
+
+/// Raw bytes of a hex SHA-256 digest.
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if text.len() != 64 || !text.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(text.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::SigningKey;
+
+    use super::{ReportSignature, ReportSigner};
+
+    #[test]
+    fn signs_digests_and_rejects_tampering() {
+        let signer = ReportSigner {
+            key: Some(SigningKey::from_bytes(&[7u8; 32])),
+        };
+        let record = signer.sign("report.pdf", b"%PDF-1.7 mineral report");
+        assert_eq!(record.sha256.len(), 64);
+        assert_eq!(record.algorithm.as_deref(), Some("ed25519"));
+        assert_eq!(signer.signature_valid(&record), Some(true));
+
+        let tampered = ReportSignature {
+            sha256: signer.sign("report.pdf", b"%PDF-1.7 edited").sha256,
+            ..record.clone()
+        };
+        assert_eq!(signer.signature_valid(&tampered), Some(false));
+
+        let other_key = ReportSigner {
+            key: Some(SigningKey::from_bytes(&[9u8; 32])),
+        };
+        assert_eq!(other_key.signature_valid(&record), Some(false));
+
+        let unsigned = ReportSigner::default().sign("report.pdf", b"%PDF-1.7 mineral report");
+        assert_eq!(unsigned.sha256, record.sha256);
+        assert_eq!(unsigned.signature, None);
+        assert_eq!(signer.signature_valid(&unsigned), None);
+    }
+}
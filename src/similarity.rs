@@ -0,0 +1,185 @@
+//! Related-mineral recommendations. Minerals are scored against each other
+//! on a shared dominant element, an overlapping family name, and how close
+//! their hardness and density are; the best matches are listed on the
+//! mineral page and in generated reports.
+
+use std::{cmp::Ordering, collections::BTreeSet};
+
+use crate::models::Mineral;
+
+/// How many related minerals the page and reports show.
+pub const RELATED_LIMIT: usize = 4;
+
+const WEIGHT_DOMINANT_ELEMENT: f32 = 3.0;
+const WEIGHT_FAMILY: f32 = 2.0;
+const WEIGHT_HARDNESS: f32 = 1.5;
+const WEIGHT_DENSITY: f32 = 1.0;
+const MAX_SCORE: f32 = WEIGHT_DOMINANT_ELEMENT + WEIGHT_FAMILY + WEIGHT_HARDNESS + WEIGHT_DENSITY;
+
+/// Hardness (Mohs) and density (g/cm3) differences at which the closeness
+/// terms reach zero.
+const HARDNESS_SPAN: f32 = 2.0;
+const DENSITY_SPAN: f32 = 1.0;
+
+/// Matches scoring below this share of `MAX_SCORE` are not worth showing.
+const MIN_SIMILARITY: f32 = 0.3;
+
+#[derive(Debug, Clone)]
+pub struct RelatedMineral {
+    pub slug: String,
+    pub common_name: String,
+    pub mineral_family: String,
+    pub formula: String,
+    pub hardness_mohs: f32,
+    pub density_g_cm3: f32,
+    pub thumbnail_path: Option<String>,
+    /// 0 to 1.
+    pub similarity: f32,
+}
+
+impl RelatedMineral {
+    pub fn similarity_pct(&self) -> u32 {
+        (self.similarity * 100.0).round() as u32
+    }
+}
+
+/// The `limit` minerals in `candidates` most similar to `mineral`, best
+/// first. Ties go to the alphabetically first name.
+pub fn related(mineral: &Mineral, candidates: &[Mineral], limit: usize) -> Vec<RelatedMineral> {
+    let mut scored = candidates
+        .iter()
+        .filter(|candidate| candidate.slug != mineral.slug)
+        .map(|candidate| (candidate, similarity(mineral, candidate)))
+        .filter(|(_, similarity)| *similarity >= MIN_SIMILARITY)
+        .collect::<Vec<_>>();
+    scored.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.0.common_name.cmp(&b.0.common_name))
+    });
+
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(candidate, similarity)| RelatedMineral {
+            slug: candidate.slug.clone(),
+            common_name: candidate.common_name.clone(),
+            mineral_family: candidate.mineral_family.clone(),
+            formula: candidate.formula.clone(),
+            hardness_mohs: candidate.hardness_mohs,
+            density_g_cm3: candidate.density_g_cm3,
+            thumbnail_path: candidate.thumbnail_src().map(str::to_string),
+            similarity,
+        })
+        .collect()
+}
+
+fn similarity(a: &Mineral, b: &Mineral) -> f32 {
+    let mut score = 0.0;
+    if dominant_element(a).is_some_and(|element| dominant_element(b) == Some(element)) {
+        score += WEIGHT_DOMINANT_ELEMENT;
+    }
+    if !family_words(&a.mineral_family).is_disjoint(&family_words(&b.mineral_family)) {
+        score += WEIGHT_FAMILY;
+    }
+    score += WEIGHT_HARDNESS * closeness(a.hardness_mohs, b.hardness_mohs, HARDNESS_SPAN);
+    score += WEIGHT_DENSITY * closeness(a.density_g_cm3, b.density_g_cm3, DENSITY_SPAN);
+    score / MAX_SCORE
+}
+
+fn dominant_element(mineral: &Mineral) -> Option<&str> {
+    mineral
+        .major_elements_pct
+        .iter()
+        .max_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(Ordering::Equal))
+        .map(|(element, _)| element.as_str())
+}
+
+/// Lowercased words with a plural `s` dropped, so `Silicates` and
+/// `silicate (tectosilicate)` overlap.
+fn family_words(family: &str) -> BTreeSet<String> {
+    family
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 2)
+        .map(|word| {
+            let word = word.to_lowercase();
+            word.strip_suffix('s').map(str::to_string).unwrap_or(word)
+        })
+        .collect()
+}
+
+/// 1 for equal values, falling linearly to 0 at `span` apart.
+fn closeness(a: f32, b: f32, span: f32) -> f32 {
+    if !a.is_finite() || !b.is_finite() {
+        return 0.0;
+    }
+    (1.0 - (a - b).abs() / span).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{related, RELATED_LIMIT};
+    use crate::models::{sample_mineral, Mineral};
+
+    fn mineral(
+        name: &str,
+        family: &str,
+        hardness: f32,
+        density: f32,
+        elements: &[(&str, f32)],
+    ) -> Mineral {
+        Mineral {
+            slug: format!("mineral.test.0x{}", name.to_lowercase()),
+            folder_name: format!("mineral.test.0x{}", name.to_lowercase()),
+            common_name: name.to_string(),
+            mineral_family: family.to_string(),
+            hardness_mohs: hardness,
+            density_g_cm3: density,
+            major_elements_pct: elements
+                .iter()
+                .map(|(element, pct)| (element.to_string(), *pct))
+                .collect::<BTreeMap<_, _>>(),
+            ..sample_mineral()
+        }
+    }
+
+    #[test]
+    fn ranks_shared_chemistry_and_family_above_loose_matches() {
+        let quartz = mineral(
+            "Quartz",
+            "Silicates",
+            7.0,
+            2.65,
+            &[("O", 53.3), ("Si", 46.7)],
+        );
+        let catalog = vec![
+            quartz.clone(),
+            mineral(
+                "Orthoclase",
+                "silicate (feldspar)",
+                6.0,
+                2.56,
+                &[("O", 46.0), ("Si", 30.3)],
+            ),
+            mineral(
+                "Calcite",
+                "Carbonates",
+                3.0,
+                2.71,
+                &[("O", 48.0), ("Ca", 40.0)],
+            ),
+            mineral("Galena", "Sulfides", 2.5, 7.6, &[("Pb", 86.6), ("S", 13.4)]),
+        ];
+
+        let related = related(&quartz, &catalog, RELATED_LIMIT);
+        let names = related
+            .iter()
+            .map(|item| item.common_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Orthoclase", "Calcite"]);
+        assert!(related[0].similarity > related[1].similarity);
+        assert!(related[0].similarity_pct() <= 100);
+    }
+}
@@ -0,0 +1,382 @@
+//! Physical specimens of a mineral. Each specimen is one
+//! `specimens/<id>.json` file inside the mineral folder, holding its label,
+//! weight, dimensions, locality, acquisition date, and storage location.
+//! Anyone can see the list; admins add, edit, and delete entries.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use axum::{
+    extract::{Path as AxumPath, Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Redirect, Response},
+    Form,
+};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tracing::info;
+
+use crate::{
+    crypto,
+    csrf::{self, CsrfForm},
+    get_mineral, has_admin_session,
+    i18n::{ui_text, Language},
+    lang_path,
+    locale_format::LocaleFormat,
+    mineral_folder_for_slug,
+    models::Mineral,
+    require_visible, resolve_language, resolve_units,
+    web::{SpecimensTemplate, TemplateResponse},
+    AppError, AppState,
+};
+
+pub const SPECIMENS_DIR: &str = "specimens";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Specimen {
+    /// File stem under `specimens/`; not stored in the file itself.
+    #[serde(skip)]
+    pub id: String,
+    /// Catalog number or other label written on the specimen.
+    pub label: String,
+    pub weight_g: Option<f32>,
+    /// Free text, e.g. `45 x 30 x 22`.
+    pub dimensions_mm: String,
+    pub locality: String,
+    /// `YYYY-MM-DD`, or empty when unknown.
+    pub acquired_on: String,
+    pub storage_location: String,
+    pub notes: String,
+    pub updated_utc: String,
+}
+
+impl Specimen {
+    pub fn weight_text(&self) -> String {
+        self.weight_g
+            .map(|weight| format!("{weight:.1}"))
+            .unwrap_or_default()
+    }
+}
+
+/// The admin form's fields as typed, so a rejected submission can be shown
+/// back unchanged.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SpecimenForm {
+    pub csrf_token: String,
+    pub label: String,
+    pub weight_g: String,
+    pub dimensions_mm: String,
+    pub locality: String,
+    pub acquired_on: String,
+    pub storage_location: String,
+    pub notes: String,
+}
+
+impl SpecimenForm {
+    fn from_specimen(specimen: &Specimen) -> Self {
+        Self {
+            csrf_token: String::new(),
+            label: specimen.label.clone(),
+            weight_g: specimen.weight_text(),
+            dimensions_mm: specimen.dimensions_mm.clone(),
+            locality: specimen.locality.clone(),
+            acquired_on: specimen.acquired_on.clone(),
+            storage_location: specimen.storage_location.clone(),
+            notes: specimen.notes.clone(),
+        }
+    }
+
+    fn to_specimen(&self, id: String) -> Result<Specimen, String> {
+        let label = self.label.trim();
+        if label.is_empty() {
+            return Err("label is required".to_string());
+        }
+        let weight_g = match self.weight_g.trim() {
+            "" => None,
+            raw => Some(
+                raw.parse::<f32>()
+                    .ok()
+                    .filter(|weight| weight.is_finite() && *weight > 0.0)
+                    .ok_or_else(|| format!("weight must be a positive number of grams: '{raw}'"))?,
+            ),
+        };
+        let acquired_on = self.acquired_on.trim();
+        if !acquired_on.is_empty() && NaiveDate::parse_from_str(acquired_on, "%Y-%m-%d").is_err() {
+            return Err(format!(
+                "acquisition date must be YYYY-MM-DD: '{acquired_on}'"
+            ));
+        }
+
+        Ok(Specimen {
+            id,
+            label: label.to_string(),
+            weight_g,
+            dimensions_mm: self.dimensions_mm.trim().to_string(),
+            locality: self.locality.trim().to_string(),
+            acquired_on: acquired_on.to_string(),
+            storage_location: self.storage_location.trim().to_string(),
+            notes: self.notes.trim().to_string(),
+            updated_utc: Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SpecimensQuery {
+    #[serde(default)]
+    edit: Option<String>,
+}
+
+/// Specimens of a published mineral, for the mineral page and reports.
+pub async fn for_mineral(state: &AppState, mineral: &Mineral) -> Result<Vec<Specimen>, AppError> {
+    list(&state.data_root.join("minerals").join(&mineral.folder_name)).await
+}
+
+/// One specimen of a published mineral, for its certificate.
+pub async fn find(state: &AppState, mineral: &Mineral, id: &str) -> Result<Specimen, AppError> {
+    read(
+        &state.data_root.join("minerals").join(&mineral.folder_name),
+        id,
+    )
+    .await
+}
+
+pub async fn specimens_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+    Query(query): Query<SpecimensQuery>,
+) -> Result<TemplateResponse<SpecimensTemplate>, AppError> {
+    let language = resolve_language(&state, &headers);
+    let folder_path = mineral_folder(&state, language, &slug).await?;
+    let mut template = specimens_template(&state, &headers, language, &slug, &folder_path).await?;
+
+    if let Some(id) = query.edit.filter(|_| template.has_admin_session) {
+        let specimen = read(&folder_path, &id).await?;
+        template.form = SpecimenForm::from_specimen(&specimen);
+        template.editing = Some(specimen.id);
+    }
+    Ok(TemplateResponse(template))
+}
+
+pub async fn create_specimen(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+    Form(form): Form<SpecimenForm>,
+) -> Result<Response, AppError> {
+    csrf::verify(&state, &headers, &form.csrf_token)?;
+    let id = crypto::random_hex(8)?;
+    save(&state, &headers, &slug, id, form, None).await
+}
+
+pub async fn update_specimen(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath((slug, id)): AxumPath<(String, String)>,
+    Form(form): Form<SpecimenForm>,
+) -> Result<Response, AppError> {
+    csrf::verify(&state, &headers, &form.csrf_token)?;
+    let language = resolve_language(&state, &headers);
+    let folder_path = mineral_folder(&state, language, &slug).await?;
+    read(&folder_path, &id).await?;
+    save(&state, &headers, &slug, id.clone(), form, Some(id)).await
+}
+
+pub async fn delete_specimen(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath((slug, id)): AxumPath<(String, String)>,
+    form: Option<Form<CsrfForm>>,
+) -> Result<Redirect, AppError> {
+    csrf::verify(&state, &headers, &csrf::form_token(form))?;
+    let language = resolve_language(&state, &headers);
+    let folder_path = mineral_folder(&state, language, &slug).await?;
+    read(&folder_path, &id).await?;
+    let path = specimen_path(&folder_path, &id);
+    fs::remove_file(&path)
+        .await
+        .with_context(|| format!("failed to remove {}", path.display()))?;
+    info!("deleted specimen {id} of {slug}");
+    Ok(Redirect::to(&format!("/minerals/{slug}/specimens")))
+}
+
+/// Validates and writes a specimen, or shows the form again with the error.
+async fn save(
+    state: &AppState,
+    headers: &HeaderMap,
+    slug: &str,
+    id: String,
+    form: SpecimenForm,
+    editing: Option<String>,
+) -> Result<Response, AppError> {
+    let language = resolve_language(state, headers);
+    let folder_path = mineral_folder(state, language, slug).await?;
+    let specimen = match form.to_specimen(id) {
+        Ok(specimen) => specimen,
+        Err(message) => {
+            let template = specimens_template(state, headers, language, slug, &folder_path).await?;
+            return Ok(TemplateResponse(SpecimensTemplate {
+                error_message: Some(message),
+                form,
+                editing,
+                ..template
+            })
+            .into_response());
+        }
+    };
+
+    write(&folder_path, &specimen).await?;
+    info!("saved specimen {} of {slug}", specimen.id);
+    Ok(Redirect::to(&format!("/minerals/{slug}/specimens")).into_response())
+}
+
+async fn specimens_template(
+    state: &AppState,
+    headers: &HeaderMap,
+    language: Language,
+    slug: &str,
+    folder_path: &Path,
+) -> Result<SpecimensTemplate, AppError> {
+    let mineral = get_mineral(state, language, slug).await?;
+    require_visible(state, headers, slug, &mineral)?;
+    Ok(SpecimensTemplate {
+        lang_code: language.code().to_string(),
+        lang_dir: language.dir().to_string(),
+        txt: ui_text(language),
+        locale: LocaleFormat::new(language),
+        units: resolve_units(headers),
+        page_path: lang_path::prefixed(headers, &format!("/minerals/{slug}/specimens")),
+        mineral,
+        specimens: list(folder_path).await?,
+        has_admin_session: has_admin_session(state, headers),
+        csrf_token: csrf::session_token(state, headers).unwrap_or_default(),
+        form: SpecimenForm::default(),
+        editing: None,
+        error_message: None,
+    })
+}
+
+async fn mineral_folder(
+    state: &AppState,
+    language: Language,
+    slug: &str,
+) -> Result<PathBuf, AppError> {
+    mineral_folder_for_slug(state, language, slug)
+        .await
+        .map_err(AppError::NotFound)
+}
+
+/// Sorted by label. Unreadable files are skipped rather than hiding the
+/// rest of the collection.
+async fn list(folder_path: &Path) -> Result<Vec<Specimen>, AppError> {
+    let specimens_dir = folder_path.join(SPECIMENS_DIR);
+    let mut entries = match fs::read_dir(&specimens_dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(AppError::Internal(
+                anyhow::Error::new(err)
+                    .context(format!("failed to read {}", specimens_dir.display())),
+            ))
+        }
+    };
+
+    let mut specimens = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .with_context(|| format!("failed to read {}", specimens_dir.display()))?
+    {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(id) = file_name.strip_suffix(".json") else {
+            continue;
+        };
+        if let Ok(specimen) = read(folder_path, id).await {
+            specimens.push(specimen);
+        }
+    }
+    specimens.sort_by(|a, b| {
+        a.label
+            .to_lowercase()
+            .cmp(&b.label.to_lowercase())
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    Ok(specimens)
+}
+
+async fn read(folder_path: &Path, id: &str) -> Result<Specimen, AppError> {
+    if !is_valid_specimen_id(id) {
+        return Err(AppError::NotFound(format!("specimen '{id}' not found")));
+    }
+    let path = specimen_path(folder_path, id);
+    let raw = fs::read_to_string(&path)
+        .await
+        .map_err(|_| AppError::NotFound(format!("specimen '{id}' not found")))?;
+    let specimen = serde_json::from_str::<Specimen>(&raw)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(Specimen {
+        id: id.to_string(),
+        ..specimen
+    })
+}
+
+async fn write(folder_path: &Path, specimen: &Specimen) -> Result<(), AppError> {
+    let specimens_dir = folder_path.join(SPECIMENS_DIR);
+    fs::create_dir_all(&specimens_dir)
+        .await
+        .with_context(|| format!("failed to create {}", specimens_dir.display()))?;
+    let path = specimen_path(folder_path, &specimen.id);
+    let json = serde_json::to_string_pretty(specimen).context("failed to serialize specimen")?;
+    fs::write(&path, json)
+        .await
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+fn specimen_path(folder_path: &Path, id: &str) -> PathBuf {
+    folder_path.join(SPECIMENS_DIR).join(format!("{id}.json"))
+}
+
+fn is_valid_specimen_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 32 && id.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_valid_specimen_id, SpecimenForm};
+
+    #[test]
+    fn validates_form_input_into_a_specimen() {
+        let form = SpecimenForm {
+            label: "  MN-0042 ".to_string(),
+            weight_g: "125.5".to_string(),
+            acquired_on: "2024-03-09".to_string(),
+            storage_location: "Cabinet B, drawer 3".to_string(),
+            ..SpecimenForm::default()
+        };
+        let specimen = form.to_specimen("a1b2".to_string()).unwrap();
+        assert_eq!(specimen.label, "MN-0042");
+        assert_eq!(specimen.weight_g, Some(125.5));
+        assert_eq!(specimen.weight_text(), "125.5");
+
+        let no_label = SpecimenForm::default();
+        assert!(no_label.to_specimen("a1b2".to_string()).is_err());
+        let bad_weight = SpecimenForm {
+            weight_g: "-3".to_string(),
+            ..form.clone()
+        };
+        assert!(bad_weight.to_specimen("a1b2".to_string()).is_err());
+        let bad_date = SpecimenForm {
+            acquired_on: "09/03/2024".to_string(),
+            ..form
+        };
+        assert!(bad_date.to_specimen("a1b2".to_string()).is_err());
+
+        assert!(is_valid_specimen_id("3f9a0c1d2e4b5a6c"));
+        assert!(!is_valid_specimen_id("../mineral.en"));
+    }
+}
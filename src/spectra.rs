@@ -0,0 +1,513 @@
+//! Raman and X-ray diffraction spectra of a mineral. An upload, either a
+//! two-column CSV or a RRUFF text file, is kept as `spectra/<id>.<ext>`
+//! inside the mineral folder, next to `spectra/<id>.json` holding the
+//! parsed curve downsampled for plotting. Anyone can see the plots; admins
+//! upload and delete spectra.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use axum::{
+    extract::{Multipart, Path as AxumPath, State},
+    http::HeaderMap,
+    response::{IntoResponse, Redirect, Response},
+    Form,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tracing::info;
+
+use crate::{
+    charts, crypto,
+    csrf::{self, CsrfForm},
+    get_mineral, has_admin_session,
+    i18n::{ui_text, Language, UiText},
+    is_request_too_large_error,
+    locale_format::LocaleFormat,
+    mineral_folder_for_slug,
+    models::Mineral,
+    require_visible, resolve_language,
+    web::{SpectraTemplate, TemplateResponse},
+    AppError, AppState,
+};
+
+pub const SPECTRA_DIR: &str = "spectra";
+pub const SPECTRUM_UPLOAD_MAX_MB: usize = 5;
+pub const SPECTRUM_UPLOAD_MAX_BYTES: usize = SPECTRUM_UPLOAD_MAX_MB * 1024 * 1024;
+/// Points kept per spectrum; RRUFF scans often have several thousand.
+const MAX_POINTS: usize = 600;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpectrumKind {
+    #[default]
+    Raman,
+    Xrd,
+}
+
+impl SpectrumKind {
+    pub fn all() -> &'static [SpectrumKind] {
+        &[SpectrumKind::Raman, SpectrumKind::Xrd]
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SpectrumKind::Raman => "raman",
+            SpectrumKind::Xrd => "xrd",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        let normalized = code.trim().to_ascii_lowercase();
+        Self::all()
+            .iter()
+            .copied()
+            .find(|kind| kind.as_str() == normalized)
+    }
+
+    pub fn label(self, txt: &UiText) -> &'static str {
+        match self {
+            SpectrumKind::Raman => txt.spectrum_raman,
+            SpectrumKind::Xrd => txt.spectrum_xrd,
+        }
+    }
+
+    /// Quantity on the x axis.
+    pub fn axis_label(self, txt: &UiText) -> &'static str {
+        match self {
+            SpectrumKind::Raman => txt.label_raman_shift,
+            SpectrumKind::Xrd => txt.label_two_theta,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Spectrum {
+    /// File stem under `spectra/`; not stored in the file itself.
+    #[serde(skip)]
+    pub id: String,
+    pub kind: SpectrumKind,
+    /// RRUFF sample name and ID, or the uploaded file's name.
+    pub label: String,
+    /// The upload as received, under `spectra/`.
+    pub source_file: String,
+    /// `(x, y)` pairs in ascending `x`, at most `MAX_POINTS` of them.
+    pub points: Vec<[f32; 2]>,
+    pub uploaded_utc: String,
+}
+
+impl Spectrum {
+    /// Plot for pages and `report.html`, as a `data:` URI.
+    pub fn chart_src(&self, txt: &UiText, locale: &LocaleFormat) -> String {
+        charts::spectrum_svg(
+            &self.points,
+            self.kind.axis_label(txt),
+            txt.label_intensity,
+            *locale,
+        )
+        .map(|svg| charts::svg_data_uri(&svg))
+        .unwrap_or_default()
+    }
+}
+
+/// Spectra of a published mineral, for the mineral page and reports.
+pub async fn for_mineral(state: &AppState, mineral: &Mineral) -> Result<Vec<Spectrum>, AppError> {
+    list(&state.data_root.join("minerals").join(&mineral.folder_name)).await
+}
+
+pub async fn spectra_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+) -> Result<TemplateResponse<SpectraTemplate>, AppError> {
+    let language = resolve_language(&state, &headers);
+    let folder_path = mineral_folder(&state, language, &slug).await?;
+    Ok(TemplateResponse(
+        spectra_template(&state, &headers, language, &slug, &folder_path).await?,
+    ))
+}
+
+/// Multipart form with `csrf_token`, `kind`, and the `file` itself.
+pub async fn upload_spectrum(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(slug): AxumPath<String>,
+    mut multipart: Multipart,
+) -> Result<Response, AppError> {
+    let upload = read_upload(&mut multipart).await?;
+    csrf::verify(&state, &headers, &upload.csrf_token)?;
+    let language = resolve_language(&state, &headers);
+    let folder_path = mineral_folder(&state, language, &slug).await?;
+
+    let id = crypto::random_hex(8)?;
+    let spectrum = match upload.to_spectrum(id) {
+        Ok(spectrum) => spectrum,
+        Err(message) => {
+            let template =
+                spectra_template(&state, &headers, language, &slug, &folder_path).await?;
+            return Ok(TemplateResponse(SpectraTemplate {
+                error_message: Some(message),
+                ..template
+            })
+            .into_response());
+        }
+    };
+
+    write(&folder_path, &spectrum, &upload.bytes).await?;
+    info!(
+        "attached {} spectrum {} to {slug} ({} points)",
+        spectrum.kind.as_str(),
+        spectrum.id,
+        spectrum.points.len()
+    );
+    Ok(Redirect::to(&format!("/minerals/{slug}/spectra")).into_response())
+}
+
+pub async fn delete_spectrum(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath((slug, id)): AxumPath<(String, String)>,
+    form: Option<Form<CsrfForm>>,
+) -> Result<Redirect, AppError> {
+    csrf::verify(&state, &headers, &csrf::form_token(form))?;
+    let language = resolve_language(&state, &headers);
+    let folder_path = mineral_folder(&state, language, &slug).await?;
+    let spectrum = read(&folder_path, &id).await?;
+    let spectra_dir = folder_path.join(SPECTRA_DIR);
+    for path in [
+        spectra_dir.join(&spectrum.source_file),
+        spectrum_path(&folder_path, &id),
+    ] {
+        match fs::remove_file(&path).await {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(AppError::Internal(
+                    anyhow::Error::new(err).context(format!("failed to remove {}", path.display())),
+                ))
+            }
+        }
+    }
+    info!("deleted spectrum {id} of {slug}");
+    Ok(Redirect::to(&format!("/minerals/{slug}/spectra")))
+}
+
+#[derive(Debug, Default)]
+struct SpectrumUpload {
+    csrf_token: String,
+    kind: String,
+    file_name: String,
+    bytes: Vec<u8>,
+}
+
+impl SpectrumUpload {
+    fn to_spectrum(&self, id: String) -> Result<Spectrum, String> {
+        let kind = SpectrumKind::from_code(&self.kind)
+            .ok_or_else(|| format!("unknown spectrum type '{}'", self.kind))?;
+        if self.bytes.is_empty() {
+            return Err("choose a spectrum file to upload".to_string());
+        }
+        let text = String::from_utf8_lossy(&self.bytes);
+        let (points, header) = parse_points(&text)?;
+
+        let stem = Path::new(&self.file_name)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let label = [header.get("NAMES"), header.get("RRUFFID")]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let ext = match Path::new(&self.file_name)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("csv") => "csv",
+            _ => "txt",
+        };
+
+        Ok(Spectrum {
+            source_file: format!("{id}.{ext}"),
+            id,
+            kind,
+            label: if label.is_empty() { stem } else { label },
+            points: downsample(points, MAX_POINTS),
+            uploaded_utc: Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+async fn read_upload(multipart: &mut Multipart) -> Result<SpectrumUpload, AppError> {
+    let too_large = || {
+        AppError::BadRequest(format!(
+            "spectrum upload too large; keep file under {SPECTRUM_UPLOAD_MAX_MB} MB"
+        ))
+    };
+    let mut upload = SpectrumUpload::default();
+    while let Some(field) = multipart.next_field().await.map_err(|err| {
+        if is_request_too_large_error(&err.to_string()) {
+            too_large()
+        } else {
+            AppError::BadRequest(format!("invalid multipart payload: {err}"))
+        }
+    })? {
+        let name = field.name().unwrap_or_default().to_string();
+        if name == "file" {
+            upload.file_name = field.file_name().unwrap_or_default().to_string();
+            upload.bytes = field
+                .bytes()
+                .await
+                .map_err(|err| {
+                    if is_request_too_large_error(&err.to_string()) {
+                        too_large()
+                    } else {
+                        AppError::BadRequest(format!("failed to read file field: {err}"))
+                    }
+                })?
+                .to_vec();
+            if upload.bytes.len() > SPECTRUM_UPLOAD_MAX_BYTES {
+                return Err(too_large());
+            }
+            continue;
+        }
+        let value = field
+            .text()
+            .await
+            .map_err(|err| AppError::BadRequest(format!("failed to read field '{name}': {err}")))?;
+        match name.as_str() {
+            "csrf_token" => upload.csrf_token = value,
+            "kind" => upload.kind = value,
+            _ => {}
+        }
+    }
+    Ok(upload)
+}
+
+/// `##KEY=value` lines at the top of a RRUFF file, e.g. `NAMES`, `RRUFFID`.
+type RruffHeader = BTreeMap<String, String>;
+
+/// `(x, y)` pairs sorted by `x`, and the header of a RRUFF file. Columns may be separated by commas, semicolons, tabs, or
+/// spaces; lines that do not start with two numbers (column headings,
+/// comments) are skipped, and RRUFF's `##END=` ends the data.
+fn parse_points(text: &str) -> Result<(Vec<[f32; 2]>, RruffHeader), String> {
+    let mut points = Vec::new();
+    let mut header = BTreeMap::new();
+    for line in text.lines().map(str::trim) {
+        if let Some(entry) = line.strip_prefix("##") {
+            let (key, value) = entry.split_once('=').unwrap_or((entry, ""));
+            if key.trim() == "END" {
+                break;
+            }
+            if !value.trim().is_empty() {
+                header.insert(key.trim().to_string(), value.trim().to_string());
+            }
+            continue;
+        }
+        let mut columns = line
+            .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+            .filter(|column| !column.is_empty())
+            .map(str::parse::<f32>);
+        if let (Some(Ok(x)), Some(Ok(y))) = (columns.next(), columns.next()) {
+            if x.is_finite() && y.is_finite() {
+                points.push([x, y]);
+            }
+        }
+    }
+    if points.len() < 2 {
+        return Err(
+            "no spectrum data found; expected two numeric columns (x, intensity)".to_string(),
+        );
+    }
+    points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    Ok((points, header))
+}
+
+/// At most `max_points` points: each run of consecutive points is reduced
+/// to its lowest and highest intensity, so narrow peaks survive.
+fn downsample(points: Vec<[f32; 2]>, max_points: usize) -> Vec<[f32; 2]> {
+    if points.len() <= max_points {
+        return points;
+    }
+    let run = points.len().div_ceil(max_points / 2);
+    let mut kept = Vec::with_capacity(max_points);
+    for chunk in points.chunks(run) {
+        let lowest = chunk.iter().min_by(|a, b| a[1].total_cmp(&b[1]));
+        let highest = chunk.iter().max_by(|a, b| a[1].total_cmp(&b[1]));
+        if let (Some(lowest), Some(highest)) = (lowest, highest) {
+            if lowest[0] <= highest[0] {
+                kept.push(*lowest);
+                if highest != lowest {
+                    kept.push(*highest);
+                }
+            } else {
+                kept.extend([*highest, *lowest]);
+            }
+        }
+    }
+    kept
+}
+
+async fn spectra_template(
+    state: &AppState,
+    headers: &HeaderMap,
+    language: Language,
+    slug: &str,
+    folder_path: &Path,
+) -> Result<SpectraTemplate, AppError> {
+    let mineral = get_mineral(state, language, slug).await?;
+    require_visible(state, headers, slug, &mineral)?;
+    Ok(SpectraTemplate {
+        lang_code: language.code().to_string(),
+        lang_dir: language.dir().to_string(),
+        txt: ui_text(language),
+        locale: LocaleFormat::new(language),
+        mineral,
+        spectra: list(folder_path).await?,
+        kinds: SpectrumKind::all().to_vec(),
+        has_admin_session: has_admin_session(state, headers),
+        csrf_token: csrf::session_token(state, headers).unwrap_or_default(),
+        error_message: None,
+    })
+}
+
+async fn mineral_folder(
+    state: &AppState,
+    language: Language,
+    slug: &str,
+) -> Result<PathBuf, AppError> {
+    mineral_folder_for_slug(state, language, slug)
+        .await
+        .map_err(AppError::NotFound)
+}
+
+/// Oldest upload first. Unreadable files are skipped rather than hiding
+/// the other spectra.
+async fn list(folder_path: &Path) -> Result<Vec<Spectrum>, AppError> {
+    let spectra_dir = folder_path.join(SPECTRA_DIR);
+    let mut entries = match fs::read_dir(&spectra_dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(AppError::Internal(
+                anyhow::Error::new(err)
+                    .context(format!("failed to read {}", spectra_dir.display())),
+            ))
+        }
+    };
+
+    let mut spectra = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .with_context(|| format!("failed to read {}", spectra_dir.display()))?
+    {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(id) = file_name.strip_suffix(".json") else {
+            continue;
+        };
+        if let Ok(spectrum) = read(folder_path, id).await {
+            spectra.push(spectrum);
+        }
+    }
+    spectra.sort_by(|a, b| {
+        a.uploaded_utc
+            .cmp(&b.uploaded_utc)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    Ok(spectra)
+}
+
+async fn read(folder_path: &Path, id: &str) -> Result<Spectrum, AppError> {
+    if !is_valid_spectrum_id(id) {
+        return Err(AppError::NotFound(format!("spectrum '{id}' not found")));
+    }
+    let path = spectrum_path(folder_path, id);
+    let raw = fs::read_to_string(&path)
+        .await
+        .map_err(|_| AppError::NotFound(format!("spectrum '{id}' not found")))?;
+    let spectrum = serde_json::from_str::<Spectrum>(&raw)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(Spectrum {
+        id: id.to_string(),
+        ..spectrum
+    })
+}
+
+async fn write(folder_path: &Path, spectrum: &Spectrum, upload: &[u8]) -> Result<(), AppError> {
+    let spectra_dir = folder_path.join(SPECTRA_DIR);
+    fs::create_dir_all(&spectra_dir)
+        .await
+        .with_context(|| format!("failed to create {}", spectra_dir.display()))?;
+    let source_path = spectra_dir.join(&spectrum.source_file);
+    fs::write(&source_path, upload)
+        .await
+        .with_context(|| format!("failed to write {}", source_path.display()))?;
+    let path = spectrum_path(folder_path, &spectrum.id);
+    let json = serde_json::to_string(spectrum).context("failed to serialize spectrum")?;
+    fs::write(&path, json)
+        .await
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+fn spectrum_path(folder_path: &Path, id: &str) -> PathBuf {
+    folder_path.join(SPECTRA_DIR).join(format!("{id}.json"))
+}
+
+fn is_valid_spectrum_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 32 && id.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{downsample, parse_points, SpectrumKind, SpectrumUpload};
+
+    #[test]
+    fn parses_rruff_and_csv_uploads() {
+        let rruff = "##NAMES=Quartz\n##RRUFFID=R040031\n##LOCALITY=Hot Springs\n\
+                     464.2, 1000.0\n128.0, 220.5\n206.1, 180\n##END=\n999, 1\n";
+        let (points, header) = parse_points(rruff).unwrap();
+        assert_eq!(
+            points,
+            vec![[128.0, 220.5], [206.1, 180.0], [464.2, 1000.0]]
+        );
+        assert_eq!(header.get("RRUFFID").map(String::as_str), Some("R040031"));
+
+        let upload = SpectrumUpload {
+            kind: "XRD".to_string(),
+            file_name: "hematite scan.CSV".to_string(),
+            bytes: b"two_theta;intensity\n24.1;30\n33.2;100\n35.6;70\n".to_vec(),
+            ..SpectrumUpload::default()
+        };
+        let spectrum = upload.to_spectrum("a1b2".to_string()).unwrap();
+        assert_eq!(spectrum.kind, SpectrumKind::Xrd);
+        assert_eq!(spectrum.label, "hematite scan");
+        assert_eq!(spectrum.source_file, "a1b2.csv");
+        assert_eq!(spectrum.points.len(), 3);
+
+        assert!(parse_points("wavenumber,intensity\n").is_err());
+        let bad_kind = SpectrumUpload {
+            kind: "ir".to_string(),
+            ..upload
+        };
+        assert!(bad_kind.to_spectrum("a1b2".to_string()).is_err());
+
+        // A single tall point among 2000 survives downsampling to 100.
+        let mut scan = (0..2000)
+            .map(|index| [index as f32, 1.0])
+            .collect::<Vec<_>>();
+        scan[1234][1] = 50.0;
+        let kept = downsample(scan, 100);
+        assert!(kept.len() <= 100);
+        assert!(kept.contains(&[1234.0, 50.0]));
+        assert!(kept.windows(2).all(|pair| pair[0][0] <= pair[1][0]));
+    }
+}
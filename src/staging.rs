@@ -0,0 +1,154 @@
+//! Publishing a new mineral folder without races or half-written states.
+//! The folder name is reserved with an exclusive `create_dir` under
+//! `data/minerals/`, so two simultaneous publishes cannot pick the same
+//! one. The files are written to `data/staging/<name>/` and moved over the
+//! empty reservation with a single rename, so the catalog (which skips
+//! folders without metadata) never sees a mineral with only some of its
+//! files. Leftovers of a crash are removed at startup.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use tokio::fs;
+use tracing::{info, warn};
+
+use crate::{crypto, models::is_valid_mineral_folder_name};
+
+pub const STAGING_DIR: &str = "staging";
+
+/// Random ids tried before giving up; with 32-bit ids a collision is
+/// already unlikely on the first.
+const ATTEMPTS: usize = 16;
+
+/// A reserved mineral folder whose files are being written.
+#[derive(Debug)]
+pub struct StagedFolder {
+    pub name: String,
+    /// Where the files go until [`StagedFolder::publish`].
+    pub path: PathBuf,
+    target: PathBuf,
+}
+
+/// Reserves `mineral.<family_slug>.0x<id>` under `minerals_root` and
+/// creates its staging folder under `data_root`.
+pub async fn allocate(
+    data_root: &Path,
+    minerals_root: &Path,
+    family_slug: &str,
+) -> Result<StagedFolder> {
+    fs::create_dir_all(minerals_root)
+        .await
+        .with_context(|| format!("failed to create {}", minerals_root.display()))?;
+    for _ in 0..ATTEMPTS {
+        let name = format!("mineral.{family_slug}.0x{}", crypto::random_hex(4)?);
+        if !is_valid_mineral_folder_name(&name) {
+            return Err(anyhow!("generated invalid mineral folder name: {name}"));
+        }
+        let target = minerals_root.join(&name);
+        match fs::create_dir(&target).await {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to create {}", target.display()))
+            }
+        }
+        let path = data_root.join(STAGING_DIR).join(&name);
+        if let Err(err) = fs::create_dir_all(&path).await {
+            let _ = fs::remove_dir(&target).await;
+            return Err(err).with_context(|| format!("failed to create {}", path.display()));
+        }
+        return Ok(StagedFolder { name, path, target });
+    }
+    Err(anyhow!("failed to allocate unique mineral id"))
+}
+
+impl StagedFolder {
+    /// Moves the staged files into place in one rename, replacing the
+    /// empty reservation.
+    pub async fn publish(self) -> Result<String> {
+        fs::rename(&self.path, &self.target)
+            .await
+            .with_context(|| {
+                format!(
+                    "failed to move {} to {}",
+                    self.path.display(),
+                    self.target.display()
+                )
+            })?;
+        Ok(self.name)
+    }
+
+    /// Removes the staged files and releases the name, after a failed
+    /// publish.
+    pub async fn discard(self) {
+        if let Err(err) = fs::remove_dir_all(&self.path).await {
+            warn!("failed to remove {}: {err}", self.path.display());
+        }
+        if let Err(err) = fs::remove_dir(&self.target).await {
+            warn!("failed to remove {}: {err}", self.target.display());
+        }
+    }
+}
+
+/// Removes staging folders left by a crash, and their reservations while
+/// these are still empty.
+pub async fn prune_stale(data_root: &Path, minerals_root: &Path) -> Result<usize> {
+    let staging_root = data_root.join(STAGING_DIR);
+    let mut entries = match fs::read_dir(&staging_root).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read {}", staging_root.display()))
+        }
+    };
+    let mut removed = 0;
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .with_context(|| format!("failed to read {}", staging_root.display()))?
+    {
+        let name = entry.file_name().to_string_lossy().to_string();
+        fs::remove_dir_all(entry.path())
+            .await
+            .with_context(|| format!("failed to remove {}", entry.path().display()))?;
+        if is_valid_mineral_folder_name(&name) {
+            // Fails, as it should, once the folder holds a mineral.
+            let _ = fs::remove_dir(minerals_root.join(&name)).await;
+        }
+        removed += 1;
+    }
+    if removed > 0 {
+        info!("removed {removed} unfinished mineral folders");
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{allocate, prune_stale};
+    use crate::test_support::TempDir;
+
+    #[tokio::test]
+    async fn reserves_distinct_names_and_publishes_in_one_move() {
+        let root = TempDir::new("staging");
+        let minerals = root.join("minerals");
+
+        let (first, second) = tokio::join!(
+            allocate(&root, &minerals, "silicates"),
+            allocate(&root, &minerals, "silicates")
+        );
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert_ne!(first.name, second.name);
+        assert!(minerals.join(&first.name).is_dir());
+
+        std::fs::write(first.path.join("mineral.en.json"), "{}").unwrap();
+        let name = first.publish().await.unwrap();
+        assert!(minerals.join(&name).join("mineral.en.json").is_file());
+
+        let abandoned = second.name.clone();
+        drop(second);
+        assert_eq!(prune_stale(&root, &minerals).await.unwrap(), 1);
+        assert!(!minerals.join(&abandoned).exists());
+        assert!(minerals.join(&name).is_dir());
+    }
+}
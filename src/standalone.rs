@@ -0,0 +1,184 @@
+//! Self-contained `report.html` for `?standalone=1`. The rendered report
+//! links its stylesheet, script, logo, and photos under `/static/` and
+//! `/data/minerals/`, which only resolve through this server. The
+//! standalone copy gets the stylesheet as a `<style>` block, every other
+//! such file as a `data:` URI, and its remaining root-relative links made
+//! absolute on `PUBLIC_URL`, so it can be mailed and opened offline. Map
+//! tiles stay remote.
+
+use std::path::{Component, Path, PathBuf};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use tokio::fs;
+
+use crate::report_runs::COMPARISONS_DIR;
+
+const STYLESHEET_TAG: &str = "<link rel=\"stylesheet\" href=\"";
+
+/// Where `/static/` and `/data/` URLs point on disk. Only the parts of the
+/// data root the server exposes, `minerals/` and `comparisons/`, are read.
+pub struct Roots<'a> {
+    pub static_root: &'a Path,
+    pub data_root: &'a Path,
+    /// Prefix for the links left, e.g. `https://minerals.example.org`.
+    pub public_url: &'a str,
+}
+
+/// `html` with its local files inlined. Files that cannot be read keep
+/// their URL, made absolute like every other link.
+pub async fn inline(html: &str, roots: &Roots<'_>) -> String {
+    let html = inline_stylesheets(html, roots).await;
+    inline_attributes(&html, roots).await
+}
+
+/// `<link rel="stylesheet" href="/static/...">` becomes `<style>`.
+async fn inline_stylesheets(html: &str, roots: &Roots<'_>) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(STYLESHEET_TAG) {
+        let after = &rest[start + STYLESHEET_TAG.len()..];
+        let (Some(quote), Some(close)) = (after.find('"'), after.find('>')) else {
+            break;
+        };
+        let css = match local_file(&after[..quote], roots).await {
+            Some(path) => fs::read_to_string(path).await.ok(),
+            None => None,
+        };
+        out.push_str(&rest[..start]);
+        match css {
+            Some(css) => {
+                out.push_str("<style>\n");
+                out.push_str(&css);
+                out.push_str("\n</style>");
+            }
+            None => out.push_str(&rest[start..start + STYLESHEET_TAG.len() + close + 1]),
+        }
+        rest = &after[close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Root-relative attribute values (`src`, `href`, `data-logo-*`) become
+/// `data:` URIs when they name a local file, absolute URLs otherwise.
+async fn inline_attributes(html: &str, roots: &Roots<'_>) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find("=\"/") {
+        let value_start = start + 2;
+        out.push_str(&rest[..value_start]);
+        let after = &rest[value_start..];
+        let end = after.find('"').unwrap_or(after.len());
+        let url = &after[..end];
+        let bytes = match local_file(url, roots).await {
+            Some(path) => fs::read(&path).await.ok().map(|bytes| (path, bytes)),
+            None => None,
+        };
+        match bytes {
+            Some((path, bytes)) => out.push_str(&format!(
+                "data:{};base64,{}",
+                content_type(&path),
+                BASE64.encode(bytes)
+            )),
+            None if url.starts_with("//") => out.push_str(url),
+            None => {
+                out.push_str(roots.public_url);
+                out.push_str(url);
+            }
+        }
+        rest = &after[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The file behind a `/static/`, `/data/minerals/`, or `/data/comparisons/`
+/// URL, query stripped; `None` for other URLs, such as the rest of the data
+/// root, and for paths that would leave their root, symlinks included.
+async fn local_file(url: &str, roots: &Roots<'_>) -> Option<PathBuf> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let (root, relative) = if let Some(relative) = path.strip_prefix("/static/") {
+        (roots.static_root.to_path_buf(), relative)
+    } else if let Some(relative) = path.strip_prefix("/data/minerals/") {
+        (roots.data_root.join("minerals"), relative)
+    } else if let Some(relative) = path.strip_prefix(&format!("/data/{COMPARISONS_DIR}/")) {
+        (roots.data_root.join(COMPARISONS_DIR), relative)
+    } else {
+        return None;
+    };
+    let relative = Path::new(relative);
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+    let root = fs::canonicalize(&root).await.ok()?;
+    let file = fs::canonicalize(root.join(relative)).await.ok()?;
+    file.starts_with(&root).then_some(file)
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "css" => "text/css",
+        "js" => "text/javascript",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{inline, Roots};
+    use crate::test_support::TempDir;
+
+    #[tokio::test]
+    async fn inlines_local_files_and_absolutizes_links() {
+        let root = TempDir::new("standalone");
+        let static_root = root.join("static");
+        let data_root = root.join("data");
+        std::fs::create_dir_all(&static_root).unwrap();
+        std::fs::create_dir_all(data_root.join("minerals/quartz")).unwrap();
+        std::fs::write(static_root.join("app.css"), "body { color: red; }").unwrap();
+        std::fs::write(data_root.join("minerals/quartz/image.png"), [1, 2, 3]).unwrap();
+        std::fs::write(data_root.join("accounts.json"), "[]").unwrap();
+
+        let html = inline(
+            concat!(
+                r#"<link rel="stylesheet" href="/static/app.css?v=abc" />"#,
+                r#"<img src="/data/minerals/quartz/image.png" />"#,
+                r#"<img src="/data/minerals/../../secret.png" />"#,
+                r#"<a href="/data/accounts.json">x</a>"#,
+                r#"<a href="/minerals">All</a><a href="//cdn.example.org/x">cdn</a>"#,
+            ),
+            &Roots {
+                static_root: &static_root,
+                data_root: &data_root,
+                public_url: "https://minerals.example.org",
+            },
+        )
+        .await;
+
+        assert_eq!(
+            html,
+            concat!(
+                "<style>\nbody { color: red; }\n</style>",
+                r#"<img src="data:image/png;base64,AQID" />"#,
+                r#"<img src="https://minerals.example.org/data/minerals/../../secret.png" />"#,
+                r#"<a href="https://minerals.example.org/data/accounts.json">x</a>"#,
+                r#"<a href="https://minerals.example.org/minerals">All</a>"#,
+                r#"<a href="//cdn.example.org/x">cdn</a>"#,
+            )
+        );
+    }
+}
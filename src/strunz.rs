@@ -0,0 +1,230 @@
+//! Nickel–Strunz class inference. Well-known formulas map straight to their
+//! division (`9.FA`); anything else falls back to the class implied by the
+//! family name or the formula's anion groups (`5`, carbonates).
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrunzClass {
+    /// `9.FA` for a division, `9` when only the class is known.
+    pub code: &'static str,
+    pub name: &'static str,
+}
+
+impl StrunzClass {
+    pub fn label(&self) -> String {
+        format!("{} {}", self.code, self.name)
+    }
+}
+
+/// Formula (as normalized by `normalize_formula`) to division.
+const KNOWN_FORMULAS: &[(&str, &str, &str)] = &[
+    ("Au", "1.AA", "Metals and intermetallic alloys"),
+    ("Ag", "1.AA", "Metals and intermetallic alloys"),
+    ("Cu", "1.AA", "Metals and intermetallic alloys"),
+    ("C", "1.CB", "Metalloids and nonmetals"),
+    ("S", "1.CC", "Metalloids and nonmetals"),
+    ("ZnS", "2.CB", "Sulfides, M:S = 1:1"),
+    ("CuFeS2", "2.CB", "Sulfides, M:S = 1:1"),
+    ("PbS", "2.CD", "Sulfides, M:S = 1:1"),
+    ("HgS", "2.CD", "Sulfides, M:S = 1:1"),
+    ("MoS2", "2.EA", "Sulfides, M:S = 1:2"),
+    ("FeS2", "2.EB", "Sulfides, M:S = 1:2"),
+    ("NaCl", "3.AA", "Simple halides, without H2O"),
+    ("KCl", "3.AA", "Simple halides, without H2O"),
+    ("CaF2", "3.AB", "Simple halides, without H2O"),
+    ("Fe3O4", "4.BB", "Oxides, M:O = 3:4 (spinel group)"),
+    ("Al2O3", "4.CB", "Oxides, M:O = 2:3"),
+    ("Fe2O3", "4.CB", "Oxides, M:O = 2:3"),
+    ("SiO2", "4.DA", "Oxides, M:O = 1:2 (quartz group)"),
+    ("TiO2", "4.DB", "Oxides, M:O = 1:2"),
+    ("SnO2", "4.DB", "Oxides, M:O = 1:2"),
+    ("CaCO3", "5.AB", "Carbonates without additional anions"),
+    ("FeCO3", "5.AB", "Carbonates without additional anions"),
+    ("CaMg(CO3)2", "5.AB", "Carbonates without additional anions"),
+    ("Cu2CO3(OH)2", "5.BA", "Carbonates with additional anions"),
+    (
+        "Cu3(CO3)2(OH)2",
+        "5.BA",
+        "Carbonates with additional anions",
+    ),
+    (
+        "BaSO4",
+        "7.AD",
+        "Sulfates without additional anions, without H2O",
+    ),
+    (
+        "CaSO4",
+        "7.AD",
+        "Sulfates without additional anions, without H2O",
+    ),
+    (
+        "CaSO4·2H2O",
+        "7.CD",
+        "Sulfates without additional anions, with H2O",
+    ),
+    (
+        "Ca5(PO4)3F",
+        "8.BN",
+        "Phosphates with additional anions, without H2O",
+    ),
+    (
+        "Ca5(PO4)3(OH)",
+        "8.BN",
+        "Phosphates with additional anions, without H2O",
+    ),
+    ("Be2SiO4", "9.AA", "Nesosilicates"),
+    ("Mg2SiO4", "9.AC", "Nesosilicates"),
+    ("ZrSiO4", "9.AD", "Nesosilicates"),
+    ("Be3Al2Si6O18", "9.CJ", "Cyclosilicates"),
+    ("CaMgSi2O6", "9.DA", "Inosilicates"),
+    ("Mg3Si4O10(OH)2", "9.EC", "Phyllosilicates"),
+    ("Al2Si2O5(OH)4", "9.ED", "Phyllosilicates"),
+    ("KAlSi3O8", "9.FA", "Tectosilicates"),
+    ("NaAlSi3O8", "9.FA", "Tectosilicates"),
+];
+
+/// Silicate subclasses named in a family (`Silicates (tectosilicate)`).
+const SILICATE_SUBCLASSES: &[(&str, &str, &str)] = &[
+    ("neso", "9.A", "Nesosilicates"),
+    ("soro", "9.B", "Sorosilicates"),
+    ("cyclo", "9.C", "Cyclosilicates"),
+    ("ino", "9.D", "Inosilicates"),
+    ("phyllo", "9.E", "Phyllosilicates"),
+    ("tecto", "9.F", "Tectosilicates"),
+];
+
+/// Family keywords to class, checked in order.
+const FAMILY_CLASSES: &[(&str, &str, &str)] = &[
+    ("silicate", "9", "Silicates"),
+    ("carbonate", "5", "Carbonates and nitrates"),
+    ("nitrate", "5", "Carbonates and nitrates"),
+    ("sulfate", "7", "Sulfates"),
+    ("sulphate", "7", "Sulfates"),
+    ("phosphate", "8", "Phosphates, arsenates, vanadates"),
+    ("arsenate", "8", "Phosphates, arsenates, vanadates"),
+    ("vanadate", "8", "Phosphates, arsenates, vanadates"),
+    ("borate", "6", "Borates"),
+    ("halide", "3", "Halides"),
+    ("sulfosalt", "2", "Sulfides and sulfosalts"),
+    ("sulfide", "2", "Sulfides and sulfosalts"),
+    ("sulphide", "2", "Sulfides and sulfosalts"),
+    ("oxide", "4", "Oxides and hydroxides"),
+    ("hydroxide", "4", "Oxides and hydroxides"),
+    ("native", "1", "Elements"),
+    ("element", "1", "Elements"),
+    ("organic", "10", "Organic compounds"),
+];
+
+/// Anion groups in a formula to class, checked in order.
+const ANION_CLASSES: &[(&str, &str, &str)] = &[
+    ("SiO", "9", "Silicates"),
+    ("Si2O", "9", "Silicates"),
+    ("CO3", "5", "Carbonates and nitrates"),
+    ("NO3", "5", "Carbonates and nitrates"),
+    ("SO4", "7", "Sulfates"),
+    ("PO4", "8", "Phosphates, arsenates, vanadates"),
+    ("AsO4", "8", "Phosphates, arsenates, vanadates"),
+    ("VO4", "8", "Phosphates, arsenates, vanadates"),
+    ("BO3", "6", "Borates"),
+    ("B4O7", "6", "Borates"),
+];
+
+pub fn classify(formula: &str, family: &str) -> Option<StrunzClass> {
+    let formula = normalize_formula(formula);
+    if let Some((_, code, name)) = KNOWN_FORMULAS.iter().find(|(known, ..)| *known == formula) {
+        return Some(StrunzClass { code, name });
+    }
+
+    let family = family.to_lowercase();
+    if family.contains("silicate") {
+        if let Some((_, code, name)) = SILICATE_SUBCLASSES
+            .iter()
+            .find(|(prefix, ..)| family.contains(&format!("{prefix}silicate")))
+        {
+            return Some(StrunzClass { code, name });
+        }
+    }
+
+    FAMILY_CLASSES
+        .iter()
+        .find(|(keyword, ..)| family.contains(keyword))
+        .or_else(|| {
+            ANION_CLASSES
+                .iter()
+                .find(|(group, ..)| formula.contains(group))
+        })
+        .map(|(_, code, name)| StrunzClass { code, name })
+        .or_else(|| class_from_elements(&formula))
+}
+
+/// Last resort for formulas without a recognizable anion group: a lone
+/// element, a halide, a sulfide, or a simple oxide.
+fn class_from_elements(formula: &str) -> Option<StrunzClass> {
+    let elements = element_symbols(formula);
+    let has = |symbol: &str| elements.iter().any(|element| element == symbol);
+    let (code, name) = match elements.as_slice() {
+        [] => return None,
+        [_] => ("1", "Elements"),
+        _ if has("O") => ("4", "Oxides and hydroxides"),
+        _ if ["F", "Cl", "Br", "I"].iter().any(|halogen| has(halogen)) => ("3", "Halides"),
+        _ if ["S", "Se", "Te", "As", "Sb"].iter().any(|anion| has(anion)) => {
+            ("2", "Sulfides and sulfosalts")
+        }
+        _ => return None,
+    };
+    Some(StrunzClass { code, name })
+}
+
+fn element_symbols(formula: &str) -> Vec<String> {
+    let mut symbols = Vec::<String>::new();
+    let mut chars = formula.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !c.is_ascii_uppercase() {
+            continue;
+        }
+        let mut symbol = c.to_string();
+        if let Some(next) = chars.peek().copied().filter(char::is_ascii_lowercase) {
+            symbol.push(next);
+            chars.next();
+        }
+        if !symbols.contains(&symbol) {
+            symbols.push(symbol);
+        }
+    }
+    symbols
+}
+
+/// Drops whitespace, turns subscript digits into ASCII, and writes hydrate
+/// dots as `·`.
+fn normalize_formula(formula: &str) -> String {
+    formula
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '₀'..='₉' => char::from(b'0' + (c as u32 - '₀' as u32) as u8),
+            '.' | '•' | '∙' | '⋅' => '·',
+            other => other,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::classify;
+
+    #[test]
+    fn classifies_known_formulas_then_falls_back_to_family_and_anions() {
+        let orthoclase = classify("KAlSi₃O₈", "Silicates").unwrap();
+        assert_eq!(orthoclase.label(), "9.FA Tectosilicates");
+        assert_eq!(classify("CaSO4 . 2H2O", "").unwrap().code, "7.CD");
+        assert_eq!(
+            classify("(Mg,Fe)7Si8O22(OH)2", "silicate (inosilicate)")
+                .unwrap()
+                .code,
+            "9.D"
+        );
+        assert_eq!(classify("ZnCO3", "").unwrap().code, "5");
+        assert_eq!(classify("Cu3AsS4", "Sulfosalts").unwrap().code, "2");
+        assert_eq!(classify("Pt", "").unwrap().code, "1");
+        assert_eq!(classify("", ""), None);
+    }
+}
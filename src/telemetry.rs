@@ -0,0 +1,130 @@
+//! Prometheus metrics: the global recorder, the `/metrics` handler, and
+//! the per-route request middleware. Other modules record through the
+//! `metrics` macros directly.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics::{counter, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+use crate::AppState;
+
+/// Shared by HTTP and PDF timings; PDF runs take seconds, requests
+/// milliseconds.
+const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0,
+];
+
+/// Installs the global recorder and starts the upkeep task that keeps
+/// histogram buckets from growing without bound.
+pub fn install() -> Result<PrometheusHandle> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), DURATION_BUCKETS)
+        .context("invalid metrics buckets")?
+        .install_recorder()
+        .context("failed to install metrics recorder")?;
+
+    let upkeep = handle.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(5));
+        loop {
+            interval.tick().await;
+            upkeep.run_upkeep();
+        }
+    });
+    Ok(handle)
+}
+
+pub async fn metrics_page(State(state): State<AppState>) -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+        .into_response()
+}
+
+/// Labels by route pattern (`/minerals/:slug`) rather than the raw path so
+/// every mineral does not become its own time series. Nested file services
+/// carry no `MatchedPath` and are labelled by their mount point instead.
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(path) => path.as_str().to_string(),
+        None => {
+            let mount = request
+                .uri()
+                .path()
+                .trim_start_matches('/')
+                .split('/')
+                .next()
+                .unwrap_or_default();
+            format!("/{mount}/*")
+        }
+    };
+    let method = request.method().as_str().to_string();
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    counter!(
+        "http_requests_total",
+        "method" => method.clone(),
+        "route" => route.clone(),
+        "status" => status
+    )
+    .increment(1);
+    histogram!(
+        "http_request_duration_seconds",
+        "method" => method,
+        "route" => route
+    )
+    .record(started.elapsed().as_secs_f64());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, extract::Request, middleware, routing::get, Router};
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use tower::ServiceExt;
+
+    use super::track_requests;
+
+    #[tokio::test]
+    async fn counts_requests_by_route_pattern() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        // The test runtime is single-threaded, so the middleware records
+        // here rather than into the global recorder.
+        let _guard = metrics::set_default_local_recorder(&recorder);
+        let app = Router::new()
+            .route("/minerals/:slug", get(|| async { "ok" }))
+            .layer(middleware::from_fn(track_requests));
+
+        for uri in [
+            "/minerals/quartz",
+            "/minerals/beryl",
+            "/data/minerals/x/image.png",
+        ] {
+            let request = Request::get(uri).body(Body::empty()).unwrap();
+            app.clone().oneshot(request).await.unwrap();
+        }
+
+        let rendered = handle.render();
+        assert!(rendered.contains(
+            r#"http_requests_total{method="GET",route="/minerals/:slug",status="200"} 2"#
+        ));
+        assert!(rendered
+            .contains(r#"http_requests_total{method="GET",route="/data/*",status="404"} 1"#));
+        assert!(rendered.contains(
+            r#"http_request_duration_seconds_count{method="GET",route="/minerals/:slug"} 2"#
+        ));
+    }
+}
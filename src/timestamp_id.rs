@@ -0,0 +1,36 @@
+//! Ids for the one-file-per-entry stores (history versions, custody
+//! events, admin notes, inquiries, orders): the UTC time the entry was
+//! written, which sorts chronologically and is safe as a file name.
+
+use chrono::{DateTime, Utc};
+
+/// `chrono` format of an id: `20261016T160506123456Z`.
+pub const FORMAT: &str = "%Y%m%dT%H%M%S%6fZ";
+
+pub fn timestamp_id(at: &DateTime<Utc>) -> String {
+    at.format(FORMAT).to_string()
+}
+
+/// Checks an id taken from a URL or a file name before it is joined onto
+/// a path.
+pub fn is_valid_timestamp_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 32 && id.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{is_valid_timestamp_id, timestamp_id};
+
+    #[test]
+    fn ids_sort_by_time_and_reject_paths() {
+        let earlier = timestamp_id(&Utc.with_ymd_and_hms(2026, 10, 16, 16, 5, 6).unwrap());
+        let later = timestamp_id(&Utc.with_ymd_and_hms(2026, 10, 17, 9, 0, 0).unwrap());
+        assert_eq!(earlier, "20261016T160506000000Z");
+        assert!(earlier < later);
+        assert!(is_valid_timestamp_id(&earlier));
+        assert!(!is_valid_timestamp_id(""));
+        assert!(!is_valid_timestamp_id("../mineral.en"));
+    }
+}
@@ -0,0 +1,118 @@
+//! Serving HTTPS directly, for deployments without a reverse proxy.
+//! Certificates come from PEM files. The server does not obtain or renew
+//! them itself: an external ACME client such as certbot does, in
+//! `--webroot` mode through the plain-HTTP listener, and renewed files are
+//! picked up without a restart.
+
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::{Request, State},
+    response::Redirect,
+    routing::get_service,
+    Router,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use tower_http::services::ServeDir;
+use tracing::{info, warn};
+
+use crate::config::TlsConfig;
+
+/// How often the certificate files are checked for a renewal.
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+pub async fn load(config: &TlsConfig) -> Result<RustlsConfig> {
+    // Only `ring` is compiled in; installing it twice is harmless.
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    RustlsConfig::from_pem_file(&config.cert_file, &config.key_file)
+        .await
+        .with_context(|| {
+            format!(
+                "failed to load TLS certificate {} and key {}",
+                config.cert_file.display(),
+                config.key_file.display()
+            )
+        })
+}
+
+/// Reloads the certificate whenever either file changes on disk, e.g. after
+/// a certbot renewal. A file caught half-written keeps the old certificate
+/// until the next check.
+pub async fn reload_periodically(rustls: RustlsConfig, cert_file: PathBuf, key_file: PathBuf) {
+    let mut loaded = modified(&cert_file, &key_file).await;
+    let mut interval = tokio::time::interval(RELOAD_CHECK_INTERVAL);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let current = modified(&cert_file, &key_file).await;
+        if current == loaded {
+            continue;
+        }
+        match rustls.reload_from_pem_file(&cert_file, &key_file).await {
+            Ok(()) => {
+                info!("reloaded TLS certificate {}", cert_file.display());
+                loaded = current;
+            }
+            Err(err) => warn!(
+                "failed to reload TLS certificate {}: {err}",
+                cert_file.display()
+            ),
+        }
+    }
+}
+
+async fn modified(cert_file: &Path, key_file: &Path) -> Option<(SystemTime, SystemTime)> {
+    let cert = tokio::fs::metadata(cert_file).await.ok()?.modified().ok()?;
+    let key = tokio::fs::metadata(key_file).await.ok()?.modified().ok()?;
+    Some((cert, key))
+}
+
+/// The plain-HTTP listener: ACME HTTP-01 challenges from `acme_webroot`
+/// under `/.well-known/acme-challenge/`, and a permanent redirect to
+/// `public_url` for everything else.
+pub fn redirect_app(public_url: String, acme_webroot: Option<&Path>) -> Router {
+    let router = Router::new()
+        .fallback(redirect_to_https)
+        .with_state(public_url);
+    match acme_webroot {
+        Some(webroot) => router.nest_service(
+            "/.well-known/acme-challenge",
+            get_service(ServeDir::new(webroot.join(".well-known/acme-challenge"))),
+        ),
+        None => router,
+    }
+}
+
+async fn redirect_to_https(State(public_url): State<String>, request: Request) -> Redirect {
+    Redirect::permanent(&https_location(&public_url, request.uri()))
+}
+
+fn https_location(public_url: &str, uri: &axum::http::Uri) -> String {
+    let path = uri
+        .path_and_query()
+        .map(|path| path.as_str())
+        .unwrap_or("/");
+    format!("{public_url}{path}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::https_location;
+
+    #[test]
+    fn redirect_keeps_path_and_query() {
+        let uri = "/es/minerals?page=2".parse().unwrap();
+        assert_eq!(
+            https_location("https://minerals.example.org", &uri),
+            "https://minerals.example.org/es/minerals?page=2"
+        );
+        assert_eq!(
+            https_location("https://minerals.example.org", &"/".parse().unwrap()),
+            "https://minerals.example.org/"
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! Finished LLM translations kept under `data/cache/translations/`, one
+//! file per source text, target language and model. Republishing a mineral
+//! or retranslating text that has not changed reads the file instead of
+//! calling the provider again.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use tokio::fs;
+use tracing::warn;
+
+use crate::{auth::sha256_hex, i18n::Language};
+
+#[derive(Debug, Clone)]
+pub struct TranslationCache {
+    root: PathBuf,
+}
+
+impl TranslationCache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// SHA-256 of everything that decides the translation's wording.
+    pub fn key(source_text: &str, language: Language, model: &str) -> String {
+        sha256_hex(format!("{model}\u{1f}{}\u{1f}{source_text}", language.code()).as_bytes())
+    }
+
+    /// The provider's JSON reply for `key`, if one was stored. An unreadable
+    /// entry counts as a miss.
+    pub async fn get(&self, key: &str) -> Option<String> {
+        let path = self.entry_path(key);
+        match fs::read_to_string(&path).await {
+            Ok(content) => Some(content),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => {
+                warn!("failed to read {}: {err}", path.display());
+                None
+            }
+        }
+    }
+
+    pub async fn put(&self, key: &str, content: &str) -> Result<()> {
+        fs::create_dir_all(&self.root)
+            .await
+            .with_context(|| format!("failed to create {}", self.root.display()))?;
+        let path = self.entry_path(key);
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, content)
+            .await
+            .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path)
+            .await
+            .with_context(|| format!("failed to replace {}", path.display()))
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.root.join(format!("{key}.json"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TranslationCache;
+    use crate::{i18n::Language, test_support::TempDir};
+
+    #[tokio::test]
+    async fn stores_replies_per_language_and_model() {
+        let dir = TempDir::new("translation-cache");
+        let cache = TranslationCache::new(dir.to_path_buf());
+        let key = TranslationCache::key("Quartz", Language::Es, "gpt-4o-mini");
+        assert_ne!(
+            key,
+            TranslationCache::key("Quartz", Language::Fr, "gpt-4o-mini")
+        );
+        assert_ne!(
+            key,
+            TranslationCache::key("Quartz", Language::Es, "llama3.1")
+        );
+
+        assert_eq!(cache.get(&key).await, None);
+        cache
+            .put(&key, r#"{"common_name":"Cuarzo"}"#)
+            .await
+            .unwrap();
+        assert_eq!(
+            cache.get(&key).await.as_deref(),
+            Some(r#"{"common_name":"Cuarzo"}"#)
+        );
+    }
+}
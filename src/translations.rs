@@ -0,0 +1,536 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Result};
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::HeaderMap,
+    response::{IntoResponse, Redirect, Response},
+    Form,
+};
+use chrono::Utc;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::{
+    csrf::{self, CsrfForm},
+    history,
+    i18n::{ui_text, Language},
+    models::{is_valid_mineral_folder_name, MineralDiskRecord},
+    read_english_record, read_record_file, refresh_mineral, reload_catalog, request_ai_translation,
+    require_admin_token, resolve_language, translatable_fields_changed,
+    web::{TemplateResponse, TranslationsTemplate},
+    write_metadata_file, AppError, AppState,
+};
+
+/// Only the most recent failures are kept for the admin panel.
+const MAX_REPORTED_ERRORS: usize = 20;
+
+/// One mineral folder and the languages whose metadata is missing or still
+/// an untranslated copy of the English record.
+#[derive(Debug, Clone)]
+pub struct PendingTranslation {
+    pub folder_name: String,
+    pub languages: Vec<Language>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchProgress {
+    pub running: bool,
+    pub total: usize,
+    pub translated: usize,
+    pub failed: usize,
+    pub current: Option<String>,
+    pub started_utc: String,
+    pub finished_utc: Option<String>,
+    pub errors: Vec<String>,
+}
+
+impl BatchProgress {
+    pub fn processed(&self) -> usize {
+        self.translated + self.failed
+    }
+}
+
+/// Tracks the single background batch that back-fills translations. Only
+/// one batch runs at a time; the last one's progress stays visible until
+/// the next starts.
+#[derive(Clone, Default)]
+pub struct TranslationBatch {
+    progress: Arc<Mutex<Option<BatchProgress>>>,
+}
+
+impl TranslationBatch {
+    pub fn snapshot(&self) -> Option<BatchProgress> {
+        self.progress
+            .lock()
+            .ok()
+            .and_then(|progress| progress.clone())
+    }
+
+    /// Starts translating `pending` in the background, spacing LLM calls at
+    /// least `interval` apart. Returns `false` if a batch is already running.
+    pub fn start(
+        &self,
+        state: AppState,
+        pending: Vec<PendingTranslation>,
+        interval: Duration,
+    ) -> Result<bool> {
+        if !self.begin(&pending)? {
+            return Ok(false);
+        }
+
+        let batch = self.clone();
+        tokio::spawn(async move {
+            batch.run(&state, pending, interval).await;
+        });
+        Ok(true)
+    }
+
+    /// Like `start`, but translates in the calling task and returns the
+    /// finished batch's progress.
+    pub async fn run_to_end(
+        &self,
+        state: &AppState,
+        pending: Vec<PendingTranslation>,
+        interval: Duration,
+    ) -> Result<BatchProgress> {
+        if !self.begin(&pending)? {
+            bail!("a translation batch is already running");
+        }
+        self.run(state, pending, interval).await;
+        self.snapshot()
+            .ok_or_else(|| anyhow!("translation batch progress was lost"))
+    }
+
+    /// Resets the progress for a new batch over `pending`; `false` if one is
+    /// still running.
+    fn begin(&self, pending: &[PendingTranslation]) -> Result<bool> {
+        let mut progress = self
+            .progress
+            .lock()
+            .map_err(|_| anyhow!("translation batch lock poisoned"))?;
+        if progress.as_ref().is_some_and(|progress| progress.running) {
+            return Ok(false);
+        }
+        *progress = Some(BatchProgress {
+            running: true,
+            total: pending.iter().map(|item| item.languages.len()).sum(),
+            translated: 0,
+            failed: 0,
+            current: None,
+            started_utc: Utc::now().to_rfc3339(),
+            finished_utc: None,
+            errors: Vec::new(),
+        });
+        Ok(true)
+    }
+
+    async fn run(&self, state: &AppState, pending: Vec<PendingTranslation>, interval: Duration) {
+        let minerals_root = state.data_root.join("minerals");
+        for item in pending {
+            let folder_path = minerals_root.join(&item.folder_name);
+            let english = match read_english_record(&folder_path).await {
+                Ok(english) => english,
+                Err(err) => {
+                    self.update(|progress| {
+                        progress.failed += item.languages.len();
+                        push_error(progress, format!("{}: {err}", item.folder_name));
+                    });
+                    continue;
+                }
+            };
+
+            for language in item.languages {
+                self.update(|progress| {
+                    progress.current = Some(format!("{} ({})", item.folder_name, language.code()));
+                });
+
+                let result = match request_ai_translation(state, &english, language).await {
+                    Ok(translated) => {
+                        let path = folder_path.join(format!("mineral.{}.json", language.code()));
+                        write_metadata_file(&path, &translated)
+                            .await
+                            .map_err(|err| anyhow!("{err}"))
+                    }
+                    Err(err) => Err(err),
+                };
+                self.update(|progress| match result {
+                    Ok(()) => progress.translated += 1,
+                    Err(err) => {
+                        warn!(
+                            "batch translation failed folder={} lang={}: {err:#}",
+                            item.folder_name,
+                            language.code()
+                        );
+                        progress.failed += 1;
+                        push_error(
+                            progress,
+                            format!("{} ({}): {err:#}", item.folder_name, language.code()),
+                        );
+                    }
+                });
+                tokio::time::sleep(interval).await;
+            }
+
+            if let Err(err) = reload_catalog(state) {
+                warn!("failed to reload catalog after batch translation: {err:#}");
+            }
+        }
+
+        self.update(|progress| {
+            progress.running = false;
+            progress.current = None;
+            progress.finished_utc = Some(Utc::now().to_rfc3339());
+            info!(
+                "batch translation finished: {} translated, {} failed",
+                progress.translated, progress.failed
+            );
+        });
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut BatchProgress)) {
+        match self.progress.lock() {
+            Ok(mut progress) => {
+                if let Some(progress) = progress.as_mut() {
+                    apply(progress);
+                }
+            }
+            Err(_) => warn!("translation batch lock poisoned; dropping progress update"),
+        }
+    }
+}
+
+fn push_error(progress: &mut BatchProgress, message: String) {
+    if progress.errors.len() == MAX_REPORTED_ERRORS {
+        progress.errors.remove(0);
+    }
+    progress.errors.push(message);
+}
+
+/// How a mineral's metadata file in one language relates to its English
+/// record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverageStatus {
+    /// An LLM translation of the current English text, or a file that
+    /// differs from English but predates the `translated_from` marker.
+    Translated,
+    /// Translated from English text that has since been edited.
+    Outdated,
+    /// A copy of the English record, written when translation failed.
+    Fallback,
+    Missing,
+}
+
+impl CoverageStatus {
+    pub fn of(english: &MineralDiskRecord, localized: Option<&MineralDiskRecord>) -> Self {
+        let Some(localized) = localized else {
+            return Self::Missing;
+        };
+        match &localized.translated_from {
+            Some(source) if *source == english.translation_source() => Self::Translated,
+            Some(_) => Self::Outdated,
+            None if is_english_fallback(english, localized) => Self::Fallback,
+            None => Self::Translated,
+        }
+    }
+
+    /// CSS class and label on the coverage page.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Translated => "translated",
+            Self::Outdated => "outdated",
+            Self::Fallback => "fallback",
+            Self::Missing => "missing",
+        }
+    }
+
+    pub fn needs_translation(self) -> bool {
+        matches!(self, Self::Fallback | Self::Missing)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CoverageCell {
+    pub language: Language,
+    pub status: CoverageStatus,
+}
+
+/// One row of the coverage page: every non-English language of a mineral.
+#[derive(Debug, Clone)]
+pub struct MineralCoverage {
+    pub folder_name: String,
+    pub common_name: String,
+    pub cells: Vec<CoverageCell>,
+}
+
+impl MineralCoverage {
+    pub fn is_complete(&self) -> bool {
+        self.cells
+            .iter()
+            .all(|cell| cell.status == CoverageStatus::Translated)
+    }
+}
+
+/// Classifies every language file of every mineral folder, in folder
+/// order. Folders without a readable English record are skipped.
+pub async fn coverage(minerals_root: &Path) -> Result<Vec<MineralCoverage>> {
+    let mut folder_names = Vec::new();
+    let mut entries = match tokio::fs::read_dir(minerals_root).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(anyhow!("failed to read {}: {err}", minerals_root.display())),
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.path().is_dir() && is_valid_mineral_folder_name(&name) {
+            folder_names.push(name);
+        }
+    }
+    folder_names.sort();
+
+    let mut rows = Vec::new();
+    for folder_name in folder_names {
+        let folder_path = minerals_root.join(&folder_name);
+        let Ok(english) = read_english_record(&folder_path).await else {
+            continue;
+        };
+
+        let mut cells = Vec::new();
+        for language in Language::all() {
+            if *language == Language::En {
+                continue;
+            }
+            let path = folder_path.join(format!("mineral.{}.json", language.code()));
+            let localized = read_record_file(&path).await.ok();
+            cells.push(CoverageCell {
+                language: *language,
+                status: CoverageStatus::of(&english, localized.as_ref()),
+            });
+        }
+
+        rows.push(MineralCoverage {
+            folder_name,
+            common_name: english.common_name,
+            cells,
+        });
+    }
+
+    Ok(rows)
+}
+
+/// Lists every mineral folder with at least one language file that is
+/// missing or identical to English in all translatable fields (the
+/// fallback written when translation failed at publish time).
+pub async fn find_missing_translations(minerals_root: &Path) -> Result<Vec<PendingTranslation>> {
+    Ok(coverage(minerals_root)
+        .await?
+        .into_iter()
+        .filter_map(|row| {
+            let languages = row
+                .cells
+                .iter()
+                .filter(|cell| cell.status.needs_translation())
+                .map(|cell| cell.language)
+                .collect::<Vec<_>>();
+            (!languages.is_empty()).then_some(PendingTranslation {
+                folder_name: row.folder_name,
+                languages,
+            })
+        })
+        .collect())
+}
+
+/// Translates one language of one mineral from its current English record
+/// and overwrites that language's file, saving the folder to its history
+/// first.
+pub async fn retranslate(state: &AppState, folder_name: &str, language: Language) -> Result<()> {
+    let folder_path = state.data_root.join("minerals").join(folder_name);
+    let english = read_english_record(&folder_path)
+        .await
+        .map_err(|err| anyhow!("{err}"))?;
+    let translated = request_ai_translation(state, &english, language).await?;
+    history::record(&folder_path, "edit")
+        .await
+        .map_err(|err| anyhow!("{err}"))?;
+    let path = folder_path.join(format!("mineral.{}.json", language.code()));
+    write_metadata_file(&path, &translated)
+        .await
+        .map_err(|err| anyhow!("{err}"))?;
+    refresh_mineral(state, folder_name).await
+}
+
+/// Admin page listing every mineral's languages as translated, outdated,
+/// English fallback, or missing.
+pub async fn translations_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<TemplateResponse<TranslationsTemplate>, AppError> {
+    require_admin_token(&state, &headers)?;
+    let language = resolve_language(&state, &headers);
+    Ok(TemplateResponse(
+        translations_template(&state, &headers, language).await?,
+    ))
+}
+
+/// Re-translates one language file of one mineral from its current
+/// English record, whatever its coverage status.
+pub async fn retranslate_language(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath((folder_name, code)): AxumPath<(String, String)>,
+    form: Option<Form<CsrfForm>>,
+) -> Result<Response, AppError> {
+    csrf::verify(&state, &headers, &csrf::form_token(form))?;
+    let target = Language::from_code(&code)
+        .filter(|target| *target != Language::En)
+        .ok_or_else(|| AppError::NotFound(format!("no translation language '{code}'")))?;
+    let folder_path = state.data_root.join("minerals").join(&folder_name);
+    if !is_valid_mineral_folder_name(&folder_name) || !folder_path.is_dir() {
+        return Err(AppError::NotFound(format!(
+            "mineral folder '{folder_name}' not found"
+        )));
+    }
+
+    let language = resolve_language(&state, &headers);
+    if let Err(err) = retranslate(&state, &folder_name, target).await {
+        warn!("retranslation failed folder={folder_name} lang={code}: {err:#}");
+        return Ok(TemplateResponse(TranslationsTemplate {
+            error_message: Some(format!("{folder_name} ({code}): {err:#}")),
+            ..translations_template(&state, &headers, language).await?
+        })
+        .into_response());
+    }
+    info!("retranslated {folder_name} into {code}");
+    Ok(Redirect::to(&format!("/admin/translations#{folder_name}")).into_response())
+}
+
+async fn translations_template(
+    state: &AppState,
+    headers: &HeaderMap,
+    language: Language,
+) -> Result<TranslationsTemplate, AppError> {
+    let minerals = coverage(&state.data_root.join("minerals")).await?;
+    Ok(TranslationsTemplate {
+        lang_code: language.code().to_string(),
+        lang_dir: language.dir().to_string(),
+        txt: ui_text(language),
+        complete_count: minerals.iter().filter(|row| row.is_complete()).count(),
+        minerals,
+        languages: Language::all()
+            .iter()
+            .copied()
+            .filter(|language| *language != Language::En)
+            .collect(),
+        llm_available: state.llm.is_some(),
+        csrf_token: csrf::session_token(state, headers).unwrap_or_default(),
+        error_message: None,
+    })
+}
+
+fn is_english_fallback(english: &MineralDiskRecord, localized: &MineralDiskRecord) -> bool {
+    !translatable_fields_changed(english, localized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        find_missing_translations, push_error, CoverageStatus, PendingTranslation,
+        TranslationBatch, MAX_REPORTED_ERRORS,
+    };
+    use crate::{i18n::Language, models::MineralDiskRecord, test_support::TempDir};
+
+    #[tokio::test]
+    async fn finds_missing_and_fallback_languages_to_back_fill() {
+        let root = TempDir::new("backfill");
+        let english = serde_json::json!({
+            "common_name": "Quartz", "mineral_family": "Silicates", "formula": "SiO2",
+            "hardness_mohs": 7.0, "density_g_cm3": 2.65, "crystal_system": "trigonal",
+            "color": "colorless", "streak": "white", "luster": "vitreous", "notes": ""
+        });
+        let complete = root.join("mineral.silicates.0x1111");
+        let partial = root.join("mineral.silicates.0x2222");
+        for folder in [&complete, &partial] {
+            std::fs::create_dir_all(folder).unwrap();
+            std::fs::write(folder.join("mineral.en.json"), english.to_string()).unwrap();
+        }
+        let mut spanish = english.clone();
+        spanish["common_name"] = "Cuarzo".into();
+        for language in Language::all().iter().filter(|lang| **lang != Language::En) {
+            let path = complete.join(format!("mineral.{}.json", language.code()));
+            std::fs::write(path, spanish.to_string()).unwrap();
+        }
+        std::fs::write(partial.join("mineral.es.json"), spanish.to_string()).unwrap();
+        std::fs::write(partial.join("mineral.fr.json"), english.to_string()).unwrap();
+
+        let pending = find_missing_translations(&root).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].folder_name, "mineral.silicates.0x2222");
+        assert!(pending[0].languages.contains(&Language::Fr));
+        assert!(!pending[0].languages.contains(&Language::Es));
+        assert_eq!(pending[0].languages.len(), Language::all().len() - 2);
+    }
+
+    #[test]
+    fn runs_one_batch_at_a_time_and_keeps_the_latest_errors() {
+        let batch = TranslationBatch::default();
+        let pending = [PendingTranslation {
+            folder_name: "mineral.silicates.0x1111".to_string(),
+            languages: vec![Language::Es, Language::Fr],
+        }];
+        assert!(batch.begin(&pending).unwrap());
+        assert!(!batch.begin(&pending).unwrap());
+        let progress = batch.snapshot().unwrap();
+        assert!(progress.running);
+        assert_eq!(progress.total, 2);
+
+        batch.update(|progress| {
+            for n in 0..MAX_REPORTED_ERRORS + 3 {
+                push_error(progress, format!("error {n}"));
+            }
+            progress.running = false;
+        });
+        let progress = batch.snapshot().unwrap();
+        assert_eq!(progress.errors.len(), MAX_REPORTED_ERRORS);
+        assert_eq!(progress.errors[0], "error 3");
+        assert!(batch.begin(&[]).unwrap());
+        assert!(batch.snapshot().unwrap().errors.is_empty());
+    }
+
+    #[test]
+    fn classifies_translations_against_the_english_record() {
+        let english = serde_json::from_str::<MineralDiskRecord>(
+            r#"{"common_name": "Quartz", "mineral_family": "Silicates", "formula": "SiO2",
+            "hardness_mohs": 7.0, "density_g_cm3": 2.65, "crystal_system": "trigonal",
+            "color": "colorless", "streak": "white", "luster": "vitreous", "notes": ""}"#,
+        )
+        .unwrap();
+        let translated = MineralDiskRecord {
+            common_name: "Cuarzo".to_string(),
+            color: "incoloro".to_string(),
+            translated_from: Some(english.translation_source()),
+            ..english.clone()
+        };
+        let edited = MineralDiskRecord {
+            notes: "Piezoelectric.".to_string(),
+            ..english.clone()
+        };
+        let legacy = MineralDiskRecord {
+            translated_from: None,
+            ..translated.clone()
+        };
+
+        let status = |localized| CoverageStatus::of(&english, localized);
+        assert_eq!(status(Some(&translated)), CoverageStatus::Translated);
+        assert_eq!(status(Some(&legacy)), CoverageStatus::Translated);
+        assert_eq!(status(Some(&english)), CoverageStatus::Fallback);
+        assert_eq!(status(None), CoverageStatus::Missing);
+        assert_eq!(
+            CoverageStatus::of(&edited, Some(&translated)),
+            CoverageStatus::Outdated
+        );
+    }
+}
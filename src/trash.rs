@@ -0,0 +1,424 @@
+//! Deleted minerals. Deleting a mineral, from the admin panel or the REST
+//! API, moves its folder to `data/trash/<timestamp>-<folder>/` with a
+//! `tombstone.json` saying when and how it went. `/admin/trash` lists
+//! them; each can be restored while its folder name is free, or purged.
+//! Folders older than `TRASH_RETENTION_DAYS` are purged automatically.
+//! The mineral's admin notes follow it in and out of the trash, and are
+//! deleted with it.
+
+use std::{
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Context;
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::HeaderMap,
+    response::Redirect,
+    Form,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tracing::{info, warn};
+
+use crate::{
+    csrf::{self, CsrfForm},
+    discussion,
+    i18n::ui_text,
+    models::is_valid_mineral_folder_name,
+    read_english_record, refresh_mineral, require_admin_token, resolve_language,
+    web::{TemplateResponse, TrashTemplate},
+    AppError, AppState,
+};
+
+pub const TRASH_DIR: &str = "trash";
+pub const TOMBSTONE_FILE: &str = "tombstone.json";
+/// How often the server looks for folders past the retention window.
+pub const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub folder_name: String,
+    /// English name at the time of deletion.
+    pub common_name: String,
+    pub deleted_utc: String,
+    /// `admin` or `api`.
+    pub deleted_via: String,
+    /// Client address of the deleting request; absent in older tombstones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_from: Option<String>,
+}
+
+/// One folder in the trash.
+#[derive(Debug, Clone)]
+pub struct TrashEntry {
+    /// `<timestamp>-<folder>`, the name under `data/trash/`.
+    pub id: String,
+    pub tombstone: Tombstone,
+    /// When the automatic purge will remove it; `None` when kept forever.
+    pub purge_after: Option<String>,
+    /// Another mineral now uses the folder name, so it cannot be restored.
+    pub name_taken: bool,
+}
+
+/// Moves a mineral folder to the trash and writes its tombstone. The
+/// caller reloads the catalog.
+pub async fn move_to_trash(
+    data_root: &Path,
+    folder_path: &Path,
+    deleted_via: &str,
+    client: IpAddr,
+) -> Result<String, AppError> {
+    let folder_name = folder_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .filter(|name| is_valid_mineral_folder_name(name))
+        .ok_or_else(|| AppError::BadRequest("not a mineral folder".to_string()))?;
+    let common_name = read_english_record(folder_path)
+        .await
+        .map(|record| record.common_name)
+        .unwrap_or_default();
+
+    let deleted_at = Utc::now();
+    let trash_dir = data_root.join(TRASH_DIR);
+    fs::create_dir_all(&trash_dir)
+        .await
+        .with_context(|| format!("failed to create {}", trash_dir.display()))?;
+    let id = format!("{}-{folder_name}", deleted_at.format("%Y%m%dT%H%M%SZ"));
+    let entry_dir = trash_dir.join(&id);
+    fs::rename(folder_path, &entry_dir).await.with_context(|| {
+        format!(
+            "failed to move {} to {}",
+            folder_path.display(),
+            entry_dir.display()
+        )
+    })?;
+    // Keyed by the trash id, so a new mineral given the same folder name
+    // starts with an empty thread.
+    if let Err(err) = discussion::move_thread(data_root, &folder_name, &id).await {
+        warn!("{err:#}");
+    }
+
+    let tombstone = Tombstone {
+        folder_name,
+        common_name,
+        deleted_utc: deleted_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        deleted_via: deleted_via.to_string(),
+        deleted_from: Some(client.to_string()),
+    };
+    let json = serde_json::to_string_pretty(&tombstone).context("failed to serialize tombstone")?;
+    let tombstone_path = entry_dir.join(TOMBSTONE_FILE);
+    fs::write(&tombstone_path, json)
+        .await
+        .with_context(|| format!("failed to write {}", tombstone_path.display()))?;
+    info!("moved {} to the trash as {id}", tombstone.folder_name);
+    Ok(id)
+}
+
+/// Newest first. Folders without a readable tombstone are skipped.
+pub async fn list(data_root: &Path, retention_days: u64) -> Result<Vec<TrashEntry>, AppError> {
+    let trash_dir = data_root.join(TRASH_DIR);
+    let mut dir = match fs::read_dir(&trash_dir).await {
+        Ok(dir) => dir,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(AppError::Internal(
+                anyhow::Error::new(err).context(format!("failed to read {}", trash_dir.display())),
+            ))
+        }
+    };
+
+    let mut entries = Vec::new();
+    while let Some(entry) = dir
+        .next_entry()
+        .await
+        .with_context(|| format!("failed to read {}", trash_dir.display()))?
+    {
+        let id = entry.file_name().to_string_lossy().into_owned();
+        let Some(tombstone) = read_tombstone(&trash_dir, &id).await else {
+            continue;
+        };
+        let purge_after = purge_time(&tombstone, retention_days)
+            .map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true));
+        let name_taken = fs::try_exists(data_root.join("minerals").join(&tombstone.folder_name))
+            .await
+            .unwrap_or(false);
+        entries.push(TrashEntry {
+            id,
+            tombstone,
+            purge_after,
+            name_taken,
+        });
+    }
+    entries.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(entries)
+}
+
+/// Moves a trashed folder back into `data/minerals`. Refused while another
+/// mineral uses its folder name. The caller reloads the catalog.
+pub async fn restore(data_root: &Path, id: &str) -> Result<String, AppError> {
+    let trash_dir = data_root.join(TRASH_DIR);
+    let tombstone = read_tombstone(&trash_dir, id)
+        .await
+        .ok_or_else(|| AppError::NotFound(format!("trash entry '{id}' not found")))?;
+    let target = data_root.join("minerals").join(&tombstone.folder_name);
+    let taken = fs::try_exists(&target)
+        .await
+        .with_context(|| format!("failed to check {}", target.display()))?;
+    if taken {
+        return Err(AppError::BadRequest(format!(
+            "{} is in use by another mineral; delete or rename it first",
+            tombstone.folder_name
+        )));
+    }
+    // The tombstone goes only once the folder is back, so a failed move
+    // leaves the entry listed and restorable.
+    let entry_dir = trash_dir.join(id);
+    fs::rename(&entry_dir, &target).await.with_context(|| {
+        format!(
+            "failed to move {} to {}",
+            entry_dir.display(),
+            target.display()
+        )
+    })?;
+    let tombstone_path = target.join(TOMBSTONE_FILE);
+    if let Err(err) = fs::remove_file(&tombstone_path).await {
+        warn!("failed to remove {}: {err}", tombstone_path.display());
+    }
+    if let Err(err) = discussion::move_thread(data_root, id, &tombstone.folder_name).await {
+        warn!("{err:#}");
+    }
+    info!("restored {} from the trash", tombstone.folder_name);
+    Ok(tombstone.folder_name)
+}
+
+/// Deletes a trashed folder for good.
+pub async fn purge(data_root: &Path, id: &str) -> Result<(), AppError> {
+    let trash_dir = data_root.join(TRASH_DIR);
+    if read_tombstone(&trash_dir, id).await.is_none() {
+        return Err(AppError::NotFound(format!("trash entry '{id}' not found")));
+    }
+    let entry_dir = trash_dir.join(id);
+    fs::remove_dir_all(&entry_dir)
+        .await
+        .with_context(|| format!("failed to delete {}", entry_dir.display()))?;
+    if let Err(err) = discussion::remove_thread(data_root, id).await {
+        warn!("{err:#}");
+    }
+    info!("purged {id} from the trash");
+    Ok(())
+}
+
+/// Purges every folder past the retention window; `0` keeps them all.
+/// Returns how many were removed.
+pub async fn purge_expired(data_root: &Path, retention_days: u64) -> Result<usize, AppError> {
+    let now = Utc::now();
+    let mut purged = 0;
+    for entry in list(data_root, retention_days).await? {
+        let expired = purge_time(&entry.tombstone, retention_days).is_some_and(|time| time <= now);
+        if expired {
+            purge(data_root, &entry.id).await?;
+            purged += 1;
+        }
+    }
+    Ok(purged)
+}
+
+/// Runs `purge_expired` now and every `PURGE_INTERVAL`.
+pub async fn purge_periodically(data_root: PathBuf, retention_days: u64) {
+    if retention_days == 0 {
+        return;
+    }
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        match purge_expired(&data_root, retention_days).await {
+            Ok(0) => {}
+            Ok(purged) => info!("purged {purged} minerals past the trash retention window"),
+            Err(err) => warn!("trash purge failed: {err}"),
+        }
+    }
+}
+
+pub async fn trash_page(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<TemplateResponse<TrashTemplate>, AppError> {
+    require_admin_token(&state, &headers)?;
+    let language = resolve_language(&state, &headers);
+    Ok(TemplateResponse(TrashTemplate {
+        lang_code: language.code().to_string(),
+        lang_dir: language.dir().to_string(),
+        txt: ui_text(language),
+        entries: list(&state.data_root, state.trash_retention_days).await?,
+        retention_days: state.trash_retention_days,
+        csrf_token: csrf::session_token(&state, &headers).unwrap_or_default(),
+    }))
+}
+
+pub async fn restore_entry(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(id): AxumPath<String>,
+    Form(form): Form<CsrfForm>,
+) -> Result<Redirect, AppError> {
+    csrf::verify(&state, &headers, &form.csrf_token)?;
+    let folder_name = restore(&state.data_root, &id).await?;
+    refresh_mineral(&state, &folder_name).await?;
+    Ok(Redirect::to(&format!("/minerals/{folder_name}")))
+}
+
+pub async fn purge_entry(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(id): AxumPath<String>,
+    Form(form): Form<CsrfForm>,
+) -> Result<Redirect, AppError> {
+    csrf::verify(&state, &headers, &form.csrf_token)?;
+    purge(&state.data_root, &id).await?;
+    Ok(Redirect::to("/admin/trash"))
+}
+
+/// The tombstone of `id`, if `id` names a trashed folder.
+async fn read_tombstone(trash_dir: &Path, id: &str) -> Option<Tombstone> {
+    let folder_name = trashed_folder_name(id)?;
+    let raw = fs::read_to_string(trash_dir.join(id).join(TOMBSTONE_FILE))
+        .await
+        .ok()?;
+    serde_json::from_str::<Tombstone>(&raw)
+        .ok()
+        .filter(|tombstone| tombstone.folder_name == folder_name)
+}
+
+/// The folder name in `id`, which must read `<YYYYMMDD>T<HHMMSS>Z-<folder>`
+/// with nothing that could step out of the trash directory.
+fn trashed_folder_name(id: &str) -> Option<&str> {
+    let (stamp, folder_name) = id.split_once('-')?;
+    let (date, time) = stamp.strip_suffix('Z')?.split_once('T')?;
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    let valid = date.len() == 8
+        && digits(date)
+        && digits(time)
+        && !folder_name.contains(['/', '\\'])
+        && is_valid_mineral_folder_name(folder_name);
+    valid.then_some(folder_name)
+}
+
+fn purge_time(tombstone: &Tombstone, retention_days: u64) -> Option<DateTime<Utc>> {
+    if retention_days == 0 {
+        return None;
+    }
+    let deleted_at = DateTime::parse_from_rfc3339(&tombstone.deleted_utc).ok()?;
+    Some(deleted_at.with_timezone(&Utc) + chrono::Duration::days(retention_days as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, net::Ipv4Addr};
+
+    use super::{
+        list, purge, purge_expired, restore, trashed_folder_name, TOMBSTONE_FILE, TRASH_DIR,
+    };
+    use crate::test_support::TempDir;
+
+    #[tokio::test]
+    async fn trashes_restores_and_purges_expired_folders() {
+        let root = TempDir::new("trash");
+        let folder = root.join("minerals/mineral.oxide.0x12345");
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(folder.join("mineral.en.json"), "{}").unwrap();
+        let thread = root.join("notes/mineral.oxide.0x12345");
+        fs::create_dir_all(&thread).unwrap();
+        fs::write(thread.join("20240601T120000000000Z.json"), "{}").unwrap();
+
+        let id = super::move_to_trash(&root, &folder, "admin", Ipv4Addr::LOCALHOST.into())
+            .await
+            .unwrap();
+        assert!(!folder.exists());
+        assert!(id.ends_with("-mineral.oxide.0x12345"));
+        assert!(!thread.exists());
+        assert!(root.join("notes").join(&id).exists());
+        let entries = list(&root, 30).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].tombstone.deleted_via, "admin");
+        assert_eq!(
+            entries[0].tombstone.deleted_from.as_deref(),
+            Some("127.0.0.1")
+        );
+        assert!(entries[0].purge_after.is_some());
+        assert!(!entries[0].name_taken);
+        assert!(list(&root, 0).await.unwrap()[0].purge_after.is_none());
+
+        // A new mineral took the name in the meantime.
+        fs::create_dir_all(&folder).unwrap();
+        assert!(list(&root, 30).await.unwrap()[0].name_taken);
+        assert!(restore(&root, &id).await.is_err());
+        fs::remove_dir_all(&folder).unwrap();
+
+        // A move that fails keeps the entry and its tombstone.
+        fs::remove_dir_all(root.join("minerals")).unwrap();
+        assert!(restore(&root, &id).await.is_err());
+        assert_eq!(list(&root, 30).await.unwrap().len(), 1);
+        fs::create_dir_all(root.join("minerals")).unwrap();
+
+        assert_eq!(restore(&root, &id).await.unwrap(), "mineral.oxide.0x12345");
+        assert!(folder.join("mineral.en.json").exists());
+        assert!(!folder.join(TOMBSTONE_FILE).exists());
+        assert!(thread.join("20240601T120000000000Z.json").exists());
+        assert!(restore(&root, "../minerals").await.is_err());
+
+        let id = super::move_to_trash(&root, &folder, "api", Ipv4Addr::LOCALHOST.into())
+            .await
+            .unwrap();
+        let tombstone = root.join(TRASH_DIR).join(&id).join(TOMBSTONE_FILE);
+        let aged = fs::read_to_string(&tombstone).unwrap().replacen(
+            &chrono::Utc::now().format("%Y").to_string(),
+            "2001",
+            1,
+        );
+        fs::write(&tombstone, aged).unwrap();
+        assert_eq!(purge_expired(&root, 0).await.unwrap(), 0);
+        assert_eq!(purge_expired(&root, 30).await.unwrap(), 1);
+        assert!(list(&root, 30).await.unwrap().is_empty());
+        assert!(!thread.exists());
+        assert!(!root.join("notes").join(&id).exists());
+    }
+
+    #[tokio::test]
+    async fn refuses_ids_that_step_out_of_the_trash() {
+        assert_eq!(
+            trashed_folder_name("20240601T120000Z-mineral.oxide.0x12345"),
+            Some("mineral.oxide.0x12345")
+        );
+        for id in [
+            "../../x-mineral.oxide.0x12345",
+            "20240601T120000Z-mineral.a/../b.0x12345",
+            "20240601T120000Z-mineral.a\\b.0x12345",
+            "2024T120000Z-mineral.oxide.0x12345",
+            "20240601T120000-mineral.oxide.0x12345",
+            "20240601TZ-mineral.oxide.0x12345",
+            "20240601T120000Z-../mineral.oxide.0x12345",
+        ] {
+            assert_eq!(trashed_folder_name(id), None, "{id}");
+        }
+
+        // A tombstone planted outside the trash is never read or purged.
+        let root = TempDir::new("trash-ids");
+        let outside = root.join("x-mineral.oxide.0x12345");
+        fs::create_dir_all(&outside).unwrap();
+        fs::create_dir_all(root.join(TRASH_DIR)).unwrap();
+        fs::write(
+            outside.join(TOMBSTONE_FILE),
+            r#"{"folder_name":"mineral.oxide.0x12345","common_name":"Hematite","deleted_utc":"2024-06-01T12:00:00Z","deleted_via":"admin"}"#,
+        )
+        .unwrap();
+        let id = "../x-mineral.oxide.0x12345";
+        assert!(restore(&root, id).await.is_err());
+        assert!(purge(&root, id).await.is_err());
+        let kept = outside.exists();
+        assert!(kept);
+    }
+}
@@ -0,0 +1,207 @@
+//! Metric or imperial figures on pages and in reports. Records always hold
+//! metric values (density in g/cm3, specimen weights in grams, dimensions in
+//! millimetres); only what is shown is converted. A visitor picks the system
+//! with the `units` cookie, a report request with its `units` field.
+
+use std::borrow::Borrow;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{i18n::UiText, locale_format::LocaleFormat};
+
+/// Cookie that holds the visitor's choice, by [`UnitSystem::code`].
+pub const COOKIE: &str = "units";
+
+const LB_FT3_PER_G_CM3: f32 = 62.427_96;
+const GRAMS_PER_OUNCE: f32 = 28.349_523;
+const MM_PER_INCH: f32 = 25.4;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitSystem {
+    #[default]
+    Metric,
+    /// Density in lb/ft3, weights in ounces, lengths in inches.
+    Imperial,
+}
+
+impl UnitSystem {
+    pub fn all() -> &'static [UnitSystem] {
+        &[UnitSystem::Metric, UnitSystem::Imperial]
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            UnitSystem::Metric => "metric",
+            UnitSystem::Imperial => "imperial",
+        }
+    }
+
+    pub fn from_code(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "metric" => Some(UnitSystem::Metric),
+            "imperial" => Some(UnitSystem::Imperial),
+            _ => None,
+        }
+    }
+
+    pub fn label(self, txt: &UiText) -> &'static str {
+        match self {
+            UnitSystem::Metric => txt.units_metric,
+            UnitSystem::Imperial => txt.units_imperial,
+        }
+    }
+
+    /// `txt` with the density, weight, and dimension labels naming this
+    /// system's units, so templates keep using the same keys.
+    pub fn relabel(self, txt: &UiText) -> UiText {
+        match self {
+            UnitSystem::Metric => *txt,
+            UnitSystem::Imperial => UiText {
+                label_density: txt.label_density_imperial,
+                label_density_scale: txt.label_density_scale_imperial,
+                label_weight_g: txt.label_weight_imperial,
+                label_dimensions_mm: txt.label_dimensions_imperial,
+                ..*txt
+            },
+        }
+    }
+
+    /// Unit written after a density, e.g. in the related minerals list.
+    pub fn density_symbol(self) -> &'static str {
+        match self {
+            UnitSystem::Metric => "g/cm³",
+            UnitSystem::Imperial => "lb/ft³",
+        }
+    }
+
+    /// `g_cm3` in this system, rounded to two decimals in lb/ft3.
+    pub fn density(self, g_cm3: impl Borrow<f32>) -> f32 {
+        let g_cm3 = *g_cm3.borrow();
+        match self {
+            UnitSystem::Metric => g_cm3,
+            UnitSystem::Imperial => round2(g_cm3 * LB_FT3_PER_G_CM3),
+        }
+    }
+
+    /// A weight in grams in this system, rounded to two decimals in ounces.
+    pub fn weight(self, grams: impl Borrow<Option<f32>>) -> Option<f32> {
+        grams.borrow().map(|grams| match self {
+            UnitSystem::Metric => grams,
+            UnitSystem::Imperial => round2(grams / GRAMS_PER_OUNCE),
+        })
+    }
+
+    /// A weight in grams as shown: one decimal in grams, two in ounces;
+    /// blank when there is none.
+    pub fn weight_text(self, grams: impl Borrow<Option<f32>>, locale: &LocaleFormat) -> String {
+        let places = match self {
+            UnitSystem::Metric => 1,
+            UnitSystem::Imperial => 2,
+        };
+        locale.optional_decimal(self.weight(grams), places)
+    }
+
+    /// A length in millimetres in this system, rounded to two decimals in
+    /// inches.
+    pub fn length(self, mm: f32) -> f32 {
+        match self {
+            UnitSystem::Metric => mm,
+            UnitSystem::Imperial => round2(mm / MM_PER_INCH),
+        }
+    }
+
+    /// Free-text dimensions such as `45 x 30 x 22` with every number
+    /// converted (`1.77 x 1.18 x 0.87`) and a trailing `mm` renamed. Metric
+    /// text is returned as written.
+    pub fn dimensions(self, text: impl AsRef<str>, locale: &LocaleFormat) -> String {
+        let text = text.as_ref();
+        if self == UnitSystem::Metric {
+            return text.to_string();
+        }
+        let mut converted = String::new();
+        let mut number = String::new();
+        let flush = |number: &mut String, converted: &mut String| {
+            if number.is_empty() {
+                return;
+            }
+            // A trailing mark is punctuation, not a decimal separator.
+            let separator = number.ends_with(['.', ',']).then(|| number.pop()).flatten();
+            match number.replace(',', ".").parse::<f32>() {
+                Ok(mm) => converted.push_str(&locale.number(self.length(mm))),
+                Err(_) => converted.push_str(number),
+            }
+            converted.extend(separator);
+            number.clear();
+        };
+        for ch in text.chars() {
+            if ch.is_ascii_digit() || (!number.is_empty() && matches!(ch, '.' | ',')) {
+                number.push(ch);
+            } else {
+                flush(&mut number, &mut converted);
+                converted.push(ch);
+            }
+        }
+        flush(&mut number, &mut converted);
+        match converted.strip_suffix("mm") {
+            Some(rest) => format!("{rest}in"),
+            None => converted,
+        }
+    }
+}
+
+fn round2(value: f32) -> f32 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UnitSystem;
+    use crate::{
+        i18n::{ui_text, Language},
+        locale_format::LocaleFormat,
+    };
+
+    #[test]
+    fn converts_density_weight_and_dimensions_to_imperial() {
+        let imperial = UnitSystem::Imperial;
+        assert_eq!(imperial.density(2.65), 165.43);
+        assert_eq!(imperial.weight(Some(100.0)), Some(3.53));
+        assert_eq!(imperial.weight(None), None);
+
+        let en = &LocaleFormat::new(Language::En);
+        assert_eq!(
+            imperial.dimensions("45 x 30 x 22", en),
+            "1.77 x 1.18 x 0.87"
+        );
+        assert_eq!(imperial.dimensions("25.4 x 12,7 mm", en), "1 x 0.5 in");
+        assert_eq!(imperial.dimensions("about 50.", en), "about 1.97.");
+        assert_eq!(
+            imperial.dimensions("45 x 30", &LocaleFormat::new(Language::De)),
+            "1,77 x 1,18"
+        );
+
+        let metric = UnitSystem::Metric;
+        assert_eq!(metric.density(2.65), 2.65);
+        assert_eq!(metric.dimensions("45 x 30 x 22 mm", en), "45 x 30 x 22 mm");
+    }
+
+    #[test]
+    fn relabels_unit_labels_and_reads_codes() {
+        let txt = UnitSystem::Imperial.relabel(&ui_text(Language::En));
+        assert_eq!(txt.label_density, "Density (lb/ft3)");
+        assert_eq!(txt.label_weight_g, "Weight (oz)");
+        assert_eq!(
+            UnitSystem::Metric
+                .relabel(&ui_text(Language::En))
+                .label_density,
+            "Density (g/cm3)"
+        );
+
+        assert_eq!(
+            UnitSystem::from_code(" Imperial"),
+            Some(UnitSystem::Imperial)
+        );
+        assert_eq!(UnitSystem::from_code("si"), None);
+    }
+}
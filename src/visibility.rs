@@ -0,0 +1,204 @@
+//! Who can see a mineral. Public minerals are listed everywhere; unlisted
+//! ones open from a direct link (slug, former slug, or `/m/<name>`) but are
+//! left out of listings, search, and the public API; private ones are
+//! hidden from everyone without an admin session. The setting is shared by
+//! every language of a record.
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api, catalog::MineralCatalog, catalog_for_language, has_admin_session, i18n::UiText,
+    models::Mineral, AppError, AppState,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    #[default]
+    Public,
+    Private,
+    Unlisted,
+}
+
+impl Visibility {
+    pub fn all() -> &'static [Visibility] {
+        &[
+            Visibility::Public,
+            Visibility::Unlisted,
+            Visibility::Private,
+        ]
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Visibility::Public => "public",
+            Visibility::Private => "private",
+            Visibility::Unlisted => "unlisted",
+        }
+    }
+
+    /// Blank means public, as in records that predate the field.
+    pub fn from_code(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "public" => Some(Visibility::Public),
+            "private" => Some(Visibility::Private),
+            "unlisted" => Some(Visibility::Unlisted),
+            _ => None,
+        }
+    }
+
+    /// Parses the admin form's or the API's value.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        Self::from_code(value).ok_or_else(|| {
+            format!(
+                "Unknown visibility '{}'. Use one of: public, unlisted, private.",
+                value.trim()
+            )
+        })
+    }
+
+    pub fn label(self, txt: &UiText) -> &'static str {
+        match self {
+            Visibility::Public => txt.visibility_public,
+            Visibility::Private => txt.visibility_private,
+            Visibility::Unlisted => txt.visibility_unlisted,
+        }
+    }
+
+    /// Taking a reference for `skip_serializing_if`.
+    pub fn is_public(&self) -> bool {
+        *self == Visibility::Public
+    }
+
+    /// Whether a visitor without an admin session may open the mineral's
+    /// page.
+    pub fn is_reachable(self) -> bool {
+        self != Visibility::Private
+    }
+}
+
+/// Wraps the `/data/minerals` file service (paths arrive with that prefix
+/// already stripped), so the metadata, photos, reports, and history of a
+/// private mineral, or of one scheduled for later, are answered with 404
+/// like its page. Paths outside a
+/// known mineral folder are refused too. Admin sessions and API tokens get
+/// everything.
+pub async fn data_files(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let headers = request.headers();
+    if has_admin_session(&state, headers) || api::require_api_token(&state, headers).is_ok() {
+        return next.run(request).await;
+    }
+    let catalog = match catalog_for_language(&state, state.default_language).await {
+        Ok(catalog) => catalog,
+        Err(err) => return err.into_response(),
+    };
+    if serves_data_file(&catalog, request.uri().path()) {
+        next.run(request).await
+    } else {
+        AppError::NotFound("file not found".to_string()).into_response()
+    }
+}
+
+/// Whether `path`, relative to `data/minerals`, is in the folder of a
+/// mineral visitors may open. The setting is shared by every language, so
+/// any language's catalog will do.
+fn serves_data_file(catalog: &MineralCatalog, path: &str) -> bool {
+    let folder_name = path.trim_start_matches('/').split('/').next();
+    folder_name
+        .and_then(|folder_name| catalog.by_folder(folder_name))
+        .is_some_and(Mineral::is_reachable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{serves_data_file, Visibility};
+    use crate::{
+        catalog::MineralCatalog,
+        models::{sample_mineral, Mineral, MineralDiskRecord},
+    };
+
+    #[test]
+    fn serves_files_of_reachable_minerals_only() {
+        let mineral = |folder_name: &str, visibility: Visibility| Mineral {
+            slug: folder_name.to_string(),
+            folder_name: folder_name.to_string(),
+            visibility,
+            ..sample_mineral()
+        };
+        let catalog = MineralCatalog::new(vec![
+            mineral("mineral.oxides.0xaaaaa", Visibility::Public),
+            mineral("mineral.oxides.0xbbbbb", Visibility::Unlisted),
+            Mineral {
+                slug: "secret".to_string(),
+                ..mineral("mineral.oxides.0xccccc", Visibility::Private)
+            },
+            Mineral {
+                publish_at: Some("2999-01-01T00:00:00Z".to_string()),
+                ..mineral("mineral.oxides.0xeeeee", Visibility::Public)
+            },
+            Mineral {
+                slug: "hematite".to_string(),
+                ..mineral("mineral.oxides.0xfffff", Visibility::Public)
+            },
+        ]);
+
+        assert!(serves_data_file(
+            &catalog,
+            "/mineral.oxides.0xaaaaa/image.png"
+        ));
+        assert!(serves_data_file(
+            &catalog,
+            "/mineral.oxides.0xbbbbb/mineral.en.json"
+        ));
+        assert!(serves_data_file(
+            &catalog,
+            "/mineral.oxides.0xfffff/thumb.webp"
+        ));
+        for path in [
+            "/mineral.oxides.0xccccc/mineral.en.json",
+            "/mineral.oxides.0xccccc/reports/2026-01-01T00-00-00/report.pdf",
+            "/mineral%2Eoxides.0xccccc/image.png",
+            "/secret/image.png",
+            "/hematite/image.png",
+            "/mineral.oxides.0xddddd/image.png",
+            "/mineral.oxides.0xeeeee/image.png",
+            "/",
+        ] {
+            assert!(!serves_data_file(&catalog, path), "{path}");
+        }
+    }
+
+    #[test]
+    fn reads_codes_and_defaults_records_to_public() {
+        assert_eq!(Visibility::from_code(""), Some(Visibility::Public));
+        assert_eq!(
+            Visibility::from_code(" Unlisted "),
+            Some(Visibility::Unlisted)
+        );
+        assert!(Visibility::parse("hidden").is_err());
+        assert!(!Visibility::Private.is_reachable());
+
+        let record: MineralDiskRecord = serde_json::from_str(
+            r#"{"common_name":"Quartz","mineral_family":"Silicates","formula":"SiO2",
+                "hardness_mohs":7,"density_g_cm3":2.65,"crystal_system":"Trigonal",
+                "color":"","streak":"","luster":"","notes":""}"#,
+        )
+        .unwrap();
+        assert_eq!(record.visibility, Visibility::Public);
+        let saved = serde_json::to_string(&record).unwrap();
+        assert!(!saved.contains("visibility"));
+
+        let private = MineralDiskRecord {
+            visibility: Visibility::Private,
+            ..record
+        };
+        assert!(serde_json::to_string(&private)
+            .unwrap()
+            .contains(r#""visibility":"private""#));
+    }
+}
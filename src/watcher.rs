@@ -0,0 +1,136 @@
+//! Hot reload for catalogs edited by hand: watches `data/minerals` and
+//! re-reads the changed mineral folders into the cached per-language
+//! catalogs once a burst of changes settles.
+//! Debug builds also reload the UI strings when a locale file changes.
+
+use std::{collections::BTreeSet, path::Path, sync::mpsc, time::Duration};
+
+use anyhow::{Context, Result};
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
+use tracing::{info, warn};
+
+use crate::{refresh_mineral, AppState};
+
+/// Starts the watcher on a dedicated thread. Events closer together than
+/// `debounce` are coalesced into one reload.
+pub fn spawn(state: AppState, debounce: Duration) -> Result<()> {
+    let minerals_root = state.data_root.join("minerals");
+    std::fs::create_dir_all(&minerals_root)
+        .with_context(|| format!("failed to create {}", minerals_root.display()))?;
+    let (sender, receiver) = mpsc::channel();
+    let mut debouncer =
+        new_debouncer(debounce, sender).context("failed to start catalog watcher")?;
+    debouncer
+        .watcher()
+        .watch(&minerals_root, RecursiveMode::Recursive)
+        .with_context(|| format!("failed to watch {}", minerals_root.display()))?;
+    info!("watching {} for catalog changes", minerals_root.display());
+    let runtime = tokio::runtime::Handle::current();
+
+    std::thread::spawn(move || {
+        // The debouncer stops watching when dropped, so it lives as long
+        // as this loop.
+        let _debouncer = debouncer;
+        for result in receiver {
+            match result {
+                Ok(events) => {
+                    let changed = events
+                        .iter()
+                        .filter_map(|event| changed_folder(&minerals_root, &event.path))
+                        .collect::<BTreeSet<_>>();
+                    if changed.is_empty() {
+                        continue;
+                    }
+                    info!(
+                        "catalog changed on disk ({} folders); reloading them",
+                        changed.len()
+                    );
+                    for folder_name in changed {
+                        if let Err(err) = runtime.block_on(refresh_mineral(&state, &folder_name)) {
+                            warn!("failed to reload {folder_name} after disk change: {err:#}");
+                        }
+                    }
+                }
+                Err(err) => warn!("catalog watcher error: {err}"),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Reloads the locale files in `dir` after each burst of edits, so a
+/// translator sees a change on the next page load. A file that does not
+/// parse keeps the previous strings.
+#[cfg(debug_assertions)]
+pub fn spawn_locales(dir: std::path::PathBuf, debounce: Duration) -> Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let mut debouncer =
+        new_debouncer(debounce, sender).context("failed to start locale watcher")?;
+    debouncer
+        .watcher()
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("failed to watch {}", dir.display()))?;
+    info!("watching {} for locale changes", dir.display());
+
+    std::thread::spawn(move || {
+        let _debouncer = debouncer;
+        for result in receiver {
+            match result {
+                Ok(_) => {
+                    if let Err(err) = crate::i18n::load(&dir) {
+                        warn!("failed to reload locales: {err:#}");
+                    }
+                }
+                Err(err) => warn!("locale watcher error: {err}"),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// The mineral folder whose catalog entry `path` can alter: a folder being
+/// added or removed, or a metadata JSON file directly inside one. Reports,
+/// image variants, and subfolders such as history snapshots and specimens
+/// are ignored.
+fn changed_folder(minerals_root: &Path, path: &Path) -> Option<String> {
+    let folder = if path.parent() == Some(minerals_root) {
+        path
+    } else if path.parent().and_then(Path::parent) == Some(minerals_root)
+        && path.extension().is_some_and(|ext| ext == "json")
+    {
+        path.parent()?
+    } else {
+        return None;
+    };
+    Some(folder.file_name()?.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::changed_folder;
+
+    #[test]
+    fn ignores_reports_and_image_variants() {
+        let root = Path::new("/data/minerals");
+        let name = "mineral.silicates.0x5b6b8000";
+        let folder = root.join(name);
+
+        assert_eq!(changed_folder(root, &folder).as_deref(), Some(name));
+        assert_eq!(
+            changed_folder(root, &folder.join("mineral.es.json")).as_deref(),
+            Some(name)
+        );
+        assert_eq!(changed_folder(root, &folder.join("report.pdf")), None);
+        assert_eq!(changed_folder(root, &folder.join("thumb.webp")), None);
+        assert_eq!(
+            changed_folder(root, &folder.join("history/20261016T160506000000Z.json")),
+            None
+        );
+        assert_eq!(
+            changed_folder(root, &folder.join("specimens/3f9a0c1d2e4b5a6c.json")),
+            None
+        );
+    }
+}
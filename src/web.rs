@@ -5,9 +5,29 @@ use axum::{
 };
 
 use crate::{
+    accounts::FavoriteButton,
     agent::MineralReport,
-    i18n::{LanguageOption, UiText},
+    api_tokens::ApiTokenRecord,
+    catalog::{CatalogFilters, TagCount},
+    custody::{CustodyEvent, CustodyForm},
+    discussion::AdminNote,
+    drafts::DraftSummary,
+    duplicates::DuplicateCandidate,
+    elements::{Element, ElementMineral},
+    families::FamilyGroup,
+    history::{Comparison, VersionSummary},
+    i18n::{Language, LanguageOption, UiText},
+    inquiries::Inquiry,
+    llm_usage::UsageSummary,
+    locale_format::LocaleFormat,
     models::{Mineral, MineralFormData, ReportRequest},
+    orders::{Order, OrderStep},
+    report_runs::ReportRun,
+    specimens::{Specimen, SpecimenForm},
+    spectra::{Spectrum, SpectrumKind},
+    translations::{BatchProgress, MineralCoverage},
+    trash::TrashEntry,
+    units::UnitSystem,
 };
 
 pub struct TemplateResponse<T>(pub T);
@@ -28,6 +48,13 @@ where
     }
 }
 
+mod filters {
+    /// Markdown fields as sanitized HTML: `{{ mineral.notes|markdown_html|safe }}`.
+    pub fn markdown_html<T: std::fmt::Display>(value: T) -> askama::Result<String> {
+        Ok(crate::markdown::to_html(&value.to_string()))
+    }
+}
+
 #[derive(Template)]
 #[template(path = "home.html")]
 pub struct HomeTemplate {
@@ -38,27 +65,146 @@ pub struct HomeTemplate {
     pub current_lang_code: &'static str,
 }
 
+#[derive(Debug, Clone)]
+pub struct SelectOption {
+    pub value: &'static str,
+    pub label: &'static str,
+    pub selected: bool,
+}
+
 #[derive(Template)]
 #[template(path = "index.html")]
 pub struct IndexTemplate {
     pub lang_code: String,
     pub lang_dir: String,
     pub txt: UiText,
+    pub locale: LocaleFormat,
+    pub minerals: Vec<Mineral>,
+    pub search_query: String,
+    pub total_count: usize,
+    pub page: usize,
+    pub page_size: usize,
+    pub total_pages: usize,
+    pub sort_key: &'static str,
+    pub sort_options: Vec<SelectOption>,
+    /// `/minerals` or the tag listing; pager and sort links point here.
+    pub list_path: String,
+    pub tags: Vec<TagCount>,
+    pub active_tag: Option<String>,
+    pub filters: CatalogFilters,
+    /// `filters` as `&key=value` pairs for the pager links.
+    pub filter_query: String,
+    pub family_options: Vec<String>,
+    pub crystal_system_options: Vec<String>,
+}
+
+#[derive(Template)]
+#[template(path = "shop.html")]
+pub struct ShopTemplate {
+    pub lang_code: String,
+    pub lang_dir: String,
+    pub txt: UiText,
+    pub locale: LocaleFormat,
+    /// Only minerals whose listing is available, in catalog order.
     pub minerals: Vec<Mineral>,
 }
 
+#[derive(Template)]
+#[template(path = "element.html")]
+pub struct ElementTemplate {
+    pub lang_code: String,
+    pub lang_dir: String,
+    pub txt: UiText,
+    pub locale: LocaleFormat,
+    pub element: Element,
+    /// Richest first.
+    pub minerals: Vec<ElementMineral>,
+}
+
+#[derive(Template)]
+#[template(path = "families.html")]
+pub struct FamiliesTemplate {
+    pub lang_code: String,
+    pub lang_dir: String,
+    pub txt: UiText,
+    /// Alphabetical by localized name.
+    pub families: Vec<FamilyGroup>,
+}
+
+#[derive(Template)]
+#[template(path = "family.html")]
+pub struct FamilyTemplate {
+    pub lang_code: String,
+    pub lang_dir: String,
+    pub txt: UiText,
+    pub family: FamilyGroup,
+    pub has_admin_session: bool,
+    pub csrf_token: String,
+}
+
 #[derive(Template)]
 #[template(path = "mineral.html")]
 pub struct MineralTemplate {
     pub lang_code: String,
     pub lang_dir: String,
+    /// Unit labels already name `units`.
     pub txt: UiText,
+    pub locale: LocaleFormat,
+    /// The visitor's unit system, from the `units` cookie.
+    pub units: UnitSystem,
+    /// This page under the request's language prefix, where the units
+    /// toggle returns to.
+    pub page_path: String,
     pub mineral: Mineral,
     pub request: ReportRequest,
+    /// Preselected in the report builder: the request's, else `units`.
+    pub report_units: UnitSystem,
     pub report: MineralReport,
+    pub pdf_backend_options: Vec<SelectOption>,
+    /// Admin layouts besides the built-in one.
+    pub report_templates: Vec<String>,
+    /// Saved report presets, sorted.
+    pub presets: Vec<String>,
+    /// Admins get the save-as-preset field in the report builder.
+    pub has_admin_session: bool,
+    /// The admin discussion thread; empty without an admin session.
+    pub admin_notes: Vec<AdminNote>,
+    pub csrf_token: String,
+    pub output: ReportOutputTemplate,
+    /// `static/htmx.min.js` is installed, so the report builder updates
+    /// `output` in place instead of reloading the page.
+    pub htmx: bool,
+    /// An LLM provider is configured to write the report narrative.
+    pub llm_narrative_available: bool,
+    /// `None` without a visitor session.
+    pub favorite: Option<FavoriteButton>,
+    /// Absolute `/m/<name>` link; `None` when the name gives no slug.
+    pub share_url: Option<String>,
+    /// `/families/<slug>` for the breadcrumb; `None` without a family.
+    pub family_slug: Option<String>,
+    /// The visitor just sent an inquiry from this page.
+    pub inquiry_sent: bool,
+}
+
+/// Served from `static/` when installed; see [`MineralTemplate::htmx`].
+pub const HTMX_SCRIPT: &str = "htmx.min.js";
+
+/// The report builder's output panel, rendered inside the mineral page and
+/// on its own for HTMX requests.
+#[derive(Template)]
+#[template(path = "report_output.html")]
+pub struct ReportOutputTemplate {
+    pub txt: UiText,
+    pub pdf_job_pending: bool,
+    /// Fragment URL polled while the job runs.
+    pub poll_url: Option<String>,
     pub generated_pdf_path: Option<String>,
     pub generated_html_path: Option<String>,
     pub generation_error: Option<String>,
+    /// Earlier reports, newest first.
+    pub report_runs: Vec<ReportRun>,
+    pub summary: String,
+    pub recommendations: Vec<String>,
 }
 
 #[derive(Template)]
@@ -72,7 +218,165 @@ pub struct AdminTemplate {
     pub success_message: Option<String>,
     pub draft_form: MineralFormData,
     pub has_suggestion: bool,
+    pub edit_slug: Option<String>,
+    /// Catalog minerals the draft being published may duplicate.
+    pub duplicates: Vec<DuplicateCandidate>,
     pub admin_minerals: Vec<Mineral>,
+    pub saved_drafts: Vec<DraftSummary>,
+    pub api_tokens: Vec<ApiTokenRecord>,
+    pub new_api_token: Option<String>,
+    pub translation_progress: Option<BatchProgress>,
+    /// This month's LLM spend; `None` without a provider.
+    pub llm_usage: Option<UsageSummary>,
+    pub csrf_token: String,
+    /// Show the mindat.org "fetch reference data" action.
+    pub mindat_lookup: bool,
+    pub image_upload_max_mb: usize,
+}
+
+#[derive(Template)]
+#[template(path = "history.html")]
+pub struct HistoryTemplate {
+    pub lang_code: String,
+    pub lang_dir: String,
+    pub txt: UiText,
+    pub mineral: Mineral,
+    pub versions: Vec<VersionSummary>,
+    pub has_admin_session: bool,
+    pub comparison: Option<Comparison>,
+    /// Display time of a version that was just restored.
+    pub restored: Option<String>,
+    pub csrf_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "translations.html")]
+pub struct TranslationsTemplate {
+    pub lang_code: String,
+    pub lang_dir: String,
+    pub txt: UiText,
+    pub minerals: Vec<MineralCoverage>,
+    /// Column order: every language but English.
+    pub languages: Vec<Language>,
+    /// Minerals whose every language is a current translation.
+    pub complete_count: usize,
+    /// Retranslation needs an LLM provider.
+    pub llm_available: bool,
+    pub csrf_token: String,
+    pub error_message: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "specimens.html")]
+pub struct SpecimensTemplate {
+    pub lang_code: String,
+    pub lang_dir: String,
+    pub txt: UiText,
+    pub locale: LocaleFormat,
+    /// Units of the list; the form always takes grams and millimetres.
+    pub units: UnitSystem,
+    /// Where the units toggle returns to.
+    pub page_path: String,
+    pub mineral: Mineral,
+    pub specimens: Vec<Specimen>,
+    pub has_admin_session: bool,
+    pub csrf_token: String,
+    pub form: SpecimenForm,
+    /// Id of the specimen the form updates; `None` adds a new one.
+    pub editing: Option<String>,
+    pub error_message: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "spectra.html")]
+pub struct SpectraTemplate {
+    pub lang_code: String,
+    pub lang_dir: String,
+    pub txt: UiText,
+    pub locale: LocaleFormat,
+    pub mineral: Mineral,
+    pub spectra: Vec<Spectrum>,
+    /// Choices for the upload form.
+    pub kinds: Vec<SpectrumKind>,
+    pub has_admin_session: bool,
+    pub csrf_token: String,
+    pub error_message: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "custody.html")]
+pub struct CustodyTemplate {
+    pub lang_code: String,
+    pub lang_dir: String,
+    pub txt: UiText,
+    pub mineral: Mineral,
+    pub events: Vec<CustodyEvent>,
+    pub has_admin_session: bool,
+    pub csrf_token: String,
+    pub form: CustodyForm,
+    pub error_message: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "account.html")]
+pub struct AccountTemplate {
+    pub lang_code: String,
+    pub lang_dir: String,
+    pub txt: UiText,
+    /// Signed-in visitor; `None` shows the login and registration forms.
+    pub username: Option<String>,
+    pub favorites: Vec<Mineral>,
+    pub csrf_token: String,
+    pub error_message: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "inquiries.html")]
+pub struct InquiriesTemplate {
+    pub lang_code: String,
+    pub lang_dir: String,
+    pub txt: UiText,
+    /// Newest first.
+    pub open: Vec<Inquiry>,
+    pub handled: Vec<Inquiry>,
+    pub csrf_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "trash.html")]
+pub struct TrashTemplate {
+    pub lang_code: String,
+    pub lang_dir: String,
+    pub txt: UiText,
+    /// Newest first.
+    pub entries: Vec<TrashEntry>,
+    /// `0` when nothing is purged automatically.
+    pub retention_days: u64,
+    pub csrf_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "orders.html")]
+pub struct OrdersTemplate {
+    pub lang_code: String,
+    pub lang_dir: String,
+    pub txt: UiText,
+    /// Newest first.
+    pub orders: Vec<Order>,
+    pub csrf_token: String,
+    pub error_message: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "order_status.html")]
+pub struct OrderStatusTemplate {
+    pub lang_code: String,
+    pub lang_dir: String,
+    pub txt: UiText,
+    pub locale: LocaleFormat,
+    pub order: Order,
+    pub status_label: String,
+    pub steps: Vec<OrderStep>,
 }
 
 #[derive(Template)]
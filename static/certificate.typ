@@ -0,0 +1,53 @@
+#set document(title: "{{ txt.certificate_title|typst }}")
+#set page(paper: "a4", margin: 2cm)
+#set text(
+  font: ("Noto Serif", "Noto Serif CJK {% if lang_code == "ja" %}JP{% else if lang_code == "ko" %}KR{% else %}SC{% endif %}", "Noto Naskh Arabic", "Noto Serif Devanagari"),
+  size: 11pt,
+  lang: "{{ lang_code }}",
+  dir: {% if lang_dir == "rtl" %}rtl{% else %}ltr{% endif %},
+)
+
+#let facts(..rows) = table(
+  columns: (35%, 1fr),
+  stroke: none,
+  inset: (x: 0pt, y: 3pt),
+  ..rows.pos().map(((label, value)) => (strong(label), value)).flatten(),
+)
+
+#align(center)[
+  #text(size: 22pt, weight: "bold", "{{ txt.certificate_title|typst }}") \
+  #v(0.4em)
+  #text(size: 15pt, "{{ mineral_name|typst }}") \
+  #v(0.6em)
+  #emph("{{ txt.certificate_statement|typst }}")
+]
+
+#v(1em)
+{% match image_file %}
+{% when Some with (file) %}
+#align(center, image("{{ file|typst }}", width: 40%, height: 6cm, fit: "contain"))
+#v(0.6em)
+{% when None %}
+{% endmatch %}
+
+#align(center, box(width: 85%, facts(
+  ("{{ txt.certificate_specimen_id|typst }}", raw("{{ specimen_id|typst }}")),
+  {% if !specimen_label.is_empty() %}("{{ txt.label_specimen|typst }}", "{{ specimen_label|typst }}"),
+  {% endif %}{% for fact in facts %}("{{ fact.label|typst }}", "{{ fact.value|typst }}"),
+  {% endfor %}
+)))
+
+#v(1fr)
+#grid(
+  columns: (1fr, auto),
+  align: (left + bottom, center + bottom),
+  [
+    #line(length: 80%, stroke: 0.4pt) \
+    #"{{ txt.certificate_signature|typst }}"{% if !issued_by.is_empty() %}: #"{{ issued_by|typst }}"{% endif %} \
+    #"{{ txt.certificate_issued|typst }}": #"{{ issued_utc|typst }}"
+  ],
+  [
+    #image("{{ qr_src|typst }}", width: 2.6cm) \
+    #text(size: 7pt)[#"{{ txt.certificate_verify|typst }}": #link("{{ verify_url|typst }}", raw("{{ verify_url|typst }}"))]
+  ],
+)
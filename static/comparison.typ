@@ -0,0 +1,65 @@
+#set document(title: "{{ txt.comparison_title|typst }}")
+#set page(margin: 1in)
+#set text(
+  font: ("Noto Serif", "Noto Serif CJK {% if lang_code == "ja" %}JP{% else if lang_code == "ko" %}KR{% else %}SC{% endif %}", "Noto Naskh Arabic", "Noto Serif Devanagari"),
+  size: 11pt,
+  lang: "{{ lang_code }}",
+  dir: {% if lang_dir == "rtl" %}rtl{% else %}ltr{% endif %},
+)
+#show heading: set block(above: 1.2em, below: 0.6em)
+
+#let facts(..rows) = table(
+  columns: (28%, 1fr),
+  stroke: none,
+  inset: (x: 0pt, y: 3pt),
+  ..rows.pos().map(((label, value)) => (strong(label), value)).flatten(),
+)
+
+#let bar(percent, fraction, color) = stack(
+  dir: ltr,
+  spacing: 4pt,
+  box(width: 3.2em, align(right, text(size: 8pt, percent))),
+  box(width: fraction * 80%, height: 5pt, fill: rgb(color)),
+)
+
+#align(center)[
+  #text(size: 20pt, weight: "bold", "{{ txt.comparison_title|typst }}") \
+  #v(0.4em)
+  {% for mineral in minerals %}#box(width: 8pt, height: 8pt, fill: rgb("#{{ mineral.color }}")) #link("{{ mineral.mineral_url|typst }}", "{{ mineral.name|typst }}"){% if !loop.last %} #h(1em) {% endif %}{% endfor %} \
+  #v(0.2em)
+  #"{{ txt.label_generated_utc|typst }}": #"{{ generated_utc|typst }}"
+]
+
+= #"{{ txt.context_heading|typst }}"
+#facts(
+  ("{{ txt.label_audience|typst }}", "{{ audience|typst }}"),
+  ("{{ txt.label_purpose|typst }}", "{{ purpose|typst }}"),
+  ("{{ txt.label_site_context|typst }}", "{{ site_context|typst }}"),
+)
+
+= #"{{ txt.comparison_properties_heading|typst }}"
+#table(
+  columns: (auto, {% for mineral in minerals %}1fr, {% endfor %}),
+  table.header([], {% for mineral in minerals %}strong("{{ mineral.name|typst }}"), {% endfor %}),
+  {% for row in properties %}strong("{{ row.label|typst }}"), {% for value in row.values %}"{{ value|typst }}", {% endfor %}
+  {% endfor %}
+)
+#text(size: 9pt)[#"{{ txt.label_hardness_scale|typst }}": #"{{ hardness_scale|typst }}" \
+#"{{ txt.label_density_scale|typst }}": #"{{ density_scale|typst }}"]
+
+#strong("{{ txt.summary_heading|typst }}")
+#quote(block: true, "{{ summary|typst }}")
+
+= #"{{ txt.major_elements_heading|typst }} ({{ txt.label_weight_pct|typst }})"
+#table(
+  columns: (auto, 1fr),
+  stroke: none,
+  {% for element in elements %}strong("{{ element.name|typst }}"), stack(spacing: 2pt, {% for bar in element.bars %}bar("{{ bar.percent }}", {{ bar.fraction }}, "#{{ bar.color }}"), {% endfor %}),
+  {% endfor %}
+)
+
+= #"{{ txt.recommendations_heading|typst }}"
+#enum(
+  {% for recommendation in recommendations %}"{{ recommendation|typst }}",
+  {% endfor %}
+)
@@ -0,0 +1,165 @@
+#set document(title: "{{ mineral_name|typst }}")
+#set page(margin: 1in)
+#set text(
+  font: ("Noto Serif", "Noto Serif CJK {% if lang_code == "ja" %}JP{% else if lang_code == "ko" %}KR{% else %}SC{% endif %}", "Noto Naskh Arabic", "Noto Serif Devanagari"),
+  size: 11pt,
+  lang: "{{ lang_code }}",
+  dir: {% if lang_dir == "rtl" %}rtl{% else %}ltr{% endif %},
+)
+#show heading: set block(above: 1.2em, below: 0.6em)
+
+#let facts(..rows) = table(
+  columns: (28%, 1fr),
+  stroke: none,
+  inset: (x: 0pt, y: 3pt),
+  ..rows.pos().map(((label, value)) => (strong(label), value)).flatten(),
+)
+
+#align(center)[
+  #text(size: 20pt, weight: "bold", "{{ txt.report_title_suffix|typst }}") \
+  #v(0.4em)
+  #text(size: 15pt, "{{ mineral_name|typst }}") \
+  #v(0.2em)
+  #"{{ txt.label_generated_utc|typst }}": #"{{ generated_utc|typst }}" \
+  #text(size: 9pt)[#"{{ txt.label_report_narrative|typst }}": #"{{ narrative|typst }}"]
+]
+
+#align(center)[
+  #image("{{ qr_src|typst }}", width: 2.6cm) \
+  #text(size: 9pt)[#"{{ txt.report_live_record|typst }}": #link("{{ mineral_url|typst }}", raw("{{ mineral_url|typst }}"))]
+]
+
+{% match image_file %}
+{% when Some with (file) %}
+#align(center, image("{{ file|typst }}", width: 36%))
+{% when None %}
+{% endmatch %}
+
+{% if !hazards.is_empty() %}
+#block(width: 100%, stroke: 1pt + rgb("#9b1c1c"), fill: rgb("#fdf2f2"), inset: 8pt)[
+  #strong("{{ txt.safety_heading|typst }}")
+  #list(
+    {% for warning in hazards %}"{{ warning|typst }}",
+    {% endfor %}
+  )
+]
+
+{% endif %}
+= #"{{ txt.context_heading|typst }}"
+#facts(
+  ("{{ txt.label_audience|typst }}", "{{ audience|typst }}"),
+  ("{{ txt.label_purpose|typst }}", "{{ purpose|typst }}"),
+  ("{{ txt.label_site_context|typst }}", "{{ site_context|typst }}"),
+  {% if !preset.is_empty() %}
+  ("{{ txt.label_report_preset|typst }}", "{{ preset|typst }}"),
+  {% endif %}
+)
+
+= #"{{ txt.snapshot_heading|typst }}"
+#facts(
+  ("{{ txt.label_family|typst }}", "{{ mineral_family|typst }}"),
+  ("{{ txt.label_description|typst }}", [{{ description }}]),
+  ("{{ txt.label_formula|typst }}", "{{ formula|typst }}"),
+  ("{{ txt.label_hardness|typst }}", "{{ hardness_mohs }}"),
+  ("{{ txt.label_hardness_band|typst }}", "{{ hardness_band|typst }}"),
+  ("{{ txt.label_hardness_scale|typst }}", "{{ hardness_scale|typst }}"),
+  ("{{ txt.label_density|typst }}", "{{ density_g_cm3 }}"),
+  ("{{ txt.label_density_band|typst }}", "{{ density_band|typst }}"),
+  ("{{ txt.label_density_scale|typst }}", "{{ density_scale|typst }}"),
+  ("{{ txt.label_crystal_system|typst }}", "{{ crystal_system|typst }}"),
+  ("{{ txt.label_color|typst }}", "{{ color|typst }}"),
+  ("{{ txt.label_streak|typst }}", "{{ streak|typst }}"),
+  ("{{ txt.label_luster|typst }}", "{{ luster|typst }}"),
+  ("{{ txt.label_dominant_element|typst }}", "{{ dominant_element|typst }} ({{ dominant_element_pct }} wt%)"),
+  ("{{ txt.label_strunz_class|typst }}", "{{ strunz_class|typst }}"),
+  {% for attribute in extra_attributes %}("{{ attribute.name|typst }}", "{{ attribute.value|typst }}"),
+  {% endfor %}
+)
+
+#strong("{{ txt.summary_heading|typst }}")
+#quote(block: true, "{{ summary|typst }}")
+
+= #"{{ txt.major_elements_heading|typst }}"
+#table(
+  columns: (1fr, 1fr),
+  table.header(strong("{{ txt.major_elements_heading|typst }}"), strong("{{ txt.label_weight_pct|typst }}")),
+  {% for element in element_breakdown %}"{{ element.name|typst }}", "{{ element.percent }}",
+  {% endfor %}
+)
+{% match chart_src %}
+{% when Some with (src) %}
+#image("{{ src|typst }}", width: 100%)
+{% when None %}
+{% endmatch %}
+{% if !derived_indices.is_empty() %}
+
+= #"{{ txt.derived_indices_heading|typst }}"
+#table(
+  columns: (1fr, 1fr),
+  {% for index in derived_indices %}strong("{{ index.label|typst }}"), "{{ index.value }} {{ index.unit|typst }}",
+  {% endfor %}
+)
+{% endif %}
+
+= #"{{ txt.recommendations_heading|typst }}"
+#enum(
+  {% for recommendation in recommendations %}"{{ recommendation|typst }}",
+  {% endfor %}
+)
+{% if !related.is_empty() %}
+
+= #"{{ txt.related_heading|typst }}"
+#list(
+  {% for item in related %}"{{ item.name|typst }} ({{ item.family|typst }}, {{ txt.label_hardness|typst }} {{ item.hardness_mohs }}) · {{ item.match_pct }}% {{ txt.related_match|typst }}",
+  {% endfor %}
+)
+{% endif %}
+
+{% match provenance %}
+{% when Some with (site) %}
+= #"{{ txt.provenance_heading|typst }}"
+#table(
+  columns: (auto, 1fr),
+  {% if !site.locality.is_empty() %}strong("{{ txt.label_locality|typst }}"), "{{ site.locality|typst }}",
+  {% endif %}{% if !site.country.is_empty() %}strong("{{ txt.label_country|typst }}"), "{{ site.country|typst }}",
+  {% endif %}{% match site.coordinates %}{% when Some with (point) %}strong("{{ txt.label_coordinates|typst }}"), [#"{{ point.dms|typst }} ({{ point.decimal }})" #link("{{ point.map_url|typst }}")[#"{{ txt.map_open|typst }}"]],
+  {% when None %}{% endmatch %}
+)
+
+{% when None %}
+{% endmatch %}
+{% if !custody.is_empty() %}
+= #"{{ txt.custody_heading|typst }}"
+#table(
+  columns: (auto, 1fr, 1fr, 1fr),
+  table.header(strong("{{ txt.label_event_date|typst }}"), strong("{{ txt.label_custody_actor|typst }}"), strong("{{ txt.label_document_reference|typst }}"), strong("{{ txt.label_notes|typst }}")),
+  {% for event in custody %}"{{ event.date|typst }}", "{{ event.actor|typst }}", "{{ event.document_reference|typst }}", "{{ event.note|typst }}",
+  {% endfor %}
+)
+
+{% endif %}
+{% if !specimens.is_empty() %}
+= #"{{ txt.specimens_heading|typst }}"
+#table(
+  columns: (auto, auto, auto, 1fr, auto, 1fr),
+  table.header(strong("{{ txt.label_specimen|typst }}"), strong("{{ txt.label_weight_g|typst }}"), strong("{{ txt.label_dimensions_mm|typst }}"), strong("{{ txt.label_locality|typst }}"), strong("{{ txt.label_acquired_on|typst }}"), strong("{{ txt.label_storage_location|typst }}")),
+  {% for specimen in specimens %}"{{ specimen.label|typst }}", "{{ specimen.weight_g }}", "{{ specimen.dimensions_mm|typst }}", "{{ specimen.locality|typst }}", "{{ specimen.acquired_on|typst }}", "{{ specimen.storage_location|typst }}",
+  {% endfor %}
+)
+
+{% endif %}
+{% if !spectra.is_empty() %}
+= #"{{ txt.spectra_heading|typst }}"
+{% for spectrum in spectra %}#figure(image("{{ spectrum.chart|typst }}", width: 100%), caption: [#"{{ spectrum.caption|typst }}"])
+{% endfor %}
+
+{% endif %}
+= #"{{ txt.notes_heading|typst }}"
+{{ notes }}
+{% if !gallery.is_empty() %}
+
+= #"{{ txt.gallery_heading|typst }}"
+{% for image in gallery %}
+#figure(image("{{ image.src|typst }}", width: 42%), caption: "{{ image.caption|typst }}")
+{% endfor %}
+{% endif %}